@@ -0,0 +1,135 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use bevy::prelude::{Component, Entity, Transform, Vec3};
+use phust::handle::{BodyHandle, ColliderHandle, JointHandle};
+use phust::joint::JointKind;
+use phust::material::PhysicsMaterial;
+use phust::rigid_body::BodyType;
+use phust::shape::Shape;
+
+/// Rigid body simulated for an entity, placed where its `Transform` is when it's spawned.
+///
+/// # Remarks
+/// Dynamic bodies write their pose back to the `Transform` of their entity after every step,
+/// while kinematic bodies follow it, so it's moved by hand. Static bodies stay where they
+/// were spawned.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct PhysicsBody {
+    /// How the body is moved by the world.
+    pub body_type: BodyType,
+
+    /// Mass of the body, or `None` to take it from the densities of its colliders.
+    pub mass: Option<f32>,
+
+    /// Velocity the body is spawned with.
+    pub velocity: Vec3,
+
+    /// Angular velocity the body is spawned with.
+    pub rotation: Vec3,
+}
+
+impl Default for PhysicsBody {
+    fn default() -> Self {
+        Self::new(BodyType::Dynamic)
+    }
+}
+
+impl PhysicsBody {
+    /// Creates a new body of the given type, at rest and with the mass of its colliders.
+    pub fn new(body_type: BodyType) -> Self {
+        Self {
+            body_type,
+            mass: None,
+            velocity: Vec3::ZERO,
+            rotation: Vec3::ZERO,
+        }
+    }
+}
+
+/// Collider attached to the body of its entity, or to the body of its parent, placed by its
+/// `Transform` relative to the parent.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct PhysicsCollider {
+    /// Shape of the collider.
+    pub shape: Shape<f32>,
+
+    /// Material of the collider.
+    pub material: PhysicsMaterial<f32>,
+
+    /// Whether the collider only reports what overlaps it, without generating contacts.
+    pub sensor: bool,
+}
+
+impl PhysicsCollider {
+    /// Creates a new solid collider with the given shape and the default material.
+    pub fn new(shape: Shape<f32>) -> Self {
+        Self {
+            shape,
+            material: PhysicsMaterial::default(),
+            sensor: false,
+        }
+    }
+}
+
+/// Joint between the bodies of two entities, or between the body of an entity and the scenery.
+#[derive(Component, Copy, Clone, PartialEq, Debug)]
+pub struct PhysicsJoint {
+    /// Entities of the bodies joined, the second being `None` for the scenery.
+    pub bodies: (Entity, Option<Entity>),
+
+    /// Kind of the joint, with its anchors in the spaces of the bodies.
+    pub kind: JointKind<f32>,
+
+    /// Force beyond which the joint breaks, if any.
+    pub break_force: Option<f32>,
+
+    /// Torque beyond which the joint breaks, if any.
+    pub break_torque: Option<f32>,
+}
+
+impl PhysicsJoint {
+    /// Creates a new unbreakable joint between the bodies of the given entities.
+    pub fn new(bodies: (Entity, Option<Entity>), kind: JointKind<f32>) -> Self {
+        Self {
+            bodies,
+            kind,
+            break_force: None,
+            break_torque: None,
+        }
+    }
+}
+
+/// Handle of the body of an entity in the physics world, inserted once it's added.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BodyId(pub BodyHandle);
+
+/// Handle of the collider of an entity in the physics world, inserted once it's added.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ColliderId(pub ColliderHandle);
+
+/// Handle of the joint of an entity in the physics world, inserted once it's added.
+#[derive(Component, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct JointId(pub JointHandle);
+
+/// Returns the position and orientation of a transform, as vectors of the engine.
+pub(crate) fn pose(transform: &Transform) -> (math::Vector3<f32>, math::Quaternion<f32>) {
+    let (translation, rotation) = (transform.translation, transform.rotation);
+    (
+        vector(translation),
+        math::Quaternion::new(rotation.w, rotation.x, rotation.y, rotation.z),
+    )
+}
+
+/// Converts a Bevy vector to a vector of the engine.
+pub(crate) fn vector(vector: Vec3) -> math::Vector3<f32> {
+    math::Vector3::new(vector.x, vector.y, vector.z)
+}
+
+/// Converts a vector of the engine to a Bevy vector.
+pub(crate) fn vec3(vector: &math::Vector3<f32>) -> Vec3 {
+    Vec3::new(vector.x, vector.y, vector.z)
+}
@@ -0,0 +1,51 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use bevy::prelude::{Entity, Message, Vec3};
+use phust::event::{ContactEventKind, SensorEventKind};
+
+/// Message written when the colliders of two entities start, keep or stop touching, or the
+/// collider of an entity touches a scenery plane.
+#[derive(Message, Copy, Clone, PartialEq, Debug)]
+pub struct ContactMessage {
+    /// Stage of the contact.
+    pub kind: ContactEventKind,
+
+    /// Entities of the colliders, with `None` for the scenery planes.
+    pub entities: (Entity, Option<Entity>),
+
+    /// Deepest point of the contact in world space.
+    pub point: Vec3,
+
+    /// Unit normal of the contact, pointing from the second collider towards the first.
+    pub normal: Vec3,
+
+    /// Sum of the normal impulses applied to the contact during the step.
+    pub normal_impulse: f32,
+}
+
+/// Message written when the collider of an entity enters or exits a sensor.
+#[derive(Message, Copy, Clone, PartialEq, Debug)]
+pub struct SensorMessage {
+    /// Stage of the overlap.
+    pub kind: SensorEventKind,
+
+    /// Entities of the colliders, at least one of them being a sensor.
+    pub entities: (Entity, Entity),
+}
+
+/// Message written when the joint of an entity breaks, exceeding its break thresholds.
+#[derive(Message, Copy, Clone, PartialEq, Debug)]
+pub struct JointBreakMessage {
+    /// Entity of the joint, now disabled.
+    pub entity: Entity,
+
+    /// Force applied by the joint in the step it broke.
+    pub force: Vec3,
+
+    /// Torque applied by the joint in the step it broke.
+    pub torque: Vec3,
+}
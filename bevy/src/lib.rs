@@ -0,0 +1,16 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+extern crate bevy;
+extern crate math;
+extern crate phust;
+
+pub mod components;
+pub mod event;
+pub mod plugin;
+
+#[cfg(test)]
+mod plugin_test;
@@ -0,0 +1,391 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::components::*;
+use crate::event::*;
+use bevy::prelude::{
+    App, ChildOf, Commands, Entity, FixedUpdate, IntoScheduleConfigs, MessageWriter, NonSendMut,
+    Plugin, Quat, Query, RemovedComponents, Res, SystemSet, Time, Transform, Vec3, Without,
+};
+use math::{Matrix3, Matrix4};
+use phust::collider::Collider;
+use phust::gravity::{GravityField, GravitySource};
+use phust::handle::{BodyHandle, ColliderHandle, JointHandle};
+use phust::joint::Joint;
+use phust::rigid_body::{BodyType, RigidBody};
+use phust::world::{World, WorldConfig};
+use std::collections::HashMap;
+
+/// Plugin simulating the bodies, colliders and joints of entities in a phust world, stepped
+/// in `FixedUpdate`.
+///
+/// # Remarks
+/// The systems of the plugin run in `PhysicsSystems`, so systems moving kinematic bodies or
+/// reading the poses of the dynamic ones can be ordered around them. Contacts, sensor
+/// overlaps and broken joints are written as `ContactMessage`, `SensorMessage` and
+/// `JointBreakMessage`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PhustPlugin {
+    /// Configuration of the world.
+    pub config: WorldConfig<f32>,
+
+    /// Uniform gravity of the world.
+    pub gravity: Vec3,
+}
+
+impl Default for PhustPlugin {
+    fn default() -> Self {
+        Self {
+            config: WorldConfig::default(),
+            gravity: Vec3::new(0.0, -9.81, 0.0),
+        }
+    }
+}
+
+impl Plugin for PhustPlugin {
+    fn build(&self, app: &mut App) {
+        let mut world = World::new(self.config);
+        world.gravity = GravityField::new(GravitySource::Uniform(vector(self.gravity)));
+        app.insert_non_send_resource(PhysicsWorld::new(world))
+            .add_message::<ContactMessage>()
+            .add_message::<SensorMessage>()
+            .add_message::<JointBreakMessage>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    remove_despawned,
+                    add_bodies,
+                    add_colliders,
+                    add_joints,
+                    follow_kinematic,
+                    step,
+                    write_transforms,
+                    write_messages,
+                )
+                    .chain()
+                    .in_set(PhysicsSystems),
+            );
+    }
+}
+
+/// Systems of `PhustPlugin`, run in `FixedUpdate`.
+#[derive(SystemSet, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PhysicsSystems;
+
+/// World simulated by `PhustPlugin`, along with the entities its objects were added for.
+///
+/// # Remarks
+/// The world holds force generators and callbacks that aren't `Send`, so it's a non-send
+/// resource, reached through `NonSend` and `NonSendMut`.
+pub struct PhysicsWorld {
+    /// Simulated world, where forces and impulses can be applied to the bodies of handles.
+    pub world: World<f32>,
+
+    bodies: HashMap<Entity, BodyHandle>,
+    colliders: HashMap<Entity, ColliderHandle>,
+    collider_entities: HashMap<ColliderHandle, Entity>,
+    joints: HashMap<Entity, JointHandle>,
+    joint_entities: HashMap<JointHandle, Entity>,
+}
+
+impl PhysicsWorld {
+    /// Creates a new physics world around the given world, without any entities.
+    pub fn new(world: World<f32>) -> Self {
+        Self {
+            world,
+            bodies: HashMap::new(),
+            colliders: HashMap::new(),
+            collider_entities: HashMap::new(),
+            joints: HashMap::new(),
+            joint_entities: HashMap::new(),
+        }
+    }
+
+    /// Returns the handle of the body of an entity, if it was added.
+    pub fn body_handle(&self, entity: Entity) -> Option<BodyHandle> {
+        self.bodies.get(&entity).copied()
+    }
+
+    /// Returns the handle of the collider of an entity, if it was added.
+    pub fn collider_handle(&self, entity: Entity) -> Option<ColliderHandle> {
+        self.colliders.get(&entity).copied()
+    }
+
+    /// Returns the handle of the joint of an entity, if it was added.
+    pub fn joint_handle(&self, entity: Entity) -> Option<JointHandle> {
+        self.joints.get(&entity).copied()
+    }
+
+    /// Returns the rigid body of an entity, if it was added.
+    pub fn body_mut(&mut self, entity: Entity) -> Option<&mut RigidBody<f32>> {
+        let handle = self.body_handle(entity)?;
+        self.world.body_mut(handle)
+    }
+
+    /// Returns the entity of the collider with the given index.
+    fn collider_entity(&mut self, index: usize) -> Option<Entity> {
+        let handle = self.world.collider_handle(index);
+        self.collider_entities.get(&handle).copied()
+    }
+
+    /// Forgets the colliders and joints removed from the world along with their bodies.
+    fn forget_removed(&mut self) {
+        let world = &self.world;
+        self.colliders
+            .retain(|_, handle| world.collider(*handle).is_some());
+        self.collider_entities
+            .retain(|handle, _| world.collider(*handle).is_some());
+        self.joints
+            .retain(|_, handle| world.joint(*handle).is_some());
+        self.joint_entities
+            .retain(|handle, _| world.joint(*handle).is_some());
+    }
+}
+
+/// Removes the bodies, colliders and joints of the entities despawned, or that had their
+/// components removed, from the world.
+fn remove_despawned(
+    mut commands: Commands,
+    mut physics: NonSendMut<PhysicsWorld>,
+    mut bodies: RemovedComponents<PhysicsBody>,
+    mut colliders: RemovedComponents<PhysicsCollider>,
+    mut joints: RemovedComponents<PhysicsJoint>,
+) {
+    for entity in joints.read() {
+        if let Some(handle) = physics.joints.remove(&entity) {
+            physics.joint_entities.remove(&handle);
+            physics.world.remove_joint(handle);
+            if let Ok(mut entity) = commands.get_entity(entity) {
+                entity.try_remove::<JointId>();
+            }
+        }
+    }
+    for entity in colliders.read() {
+        if let Some(handle) = physics.colliders.remove(&entity) {
+            physics.collider_entities.remove(&handle);
+            physics.world.remove_collider(handle);
+            if let Ok(mut entity) = commands.get_entity(entity) {
+                entity.try_remove::<ColliderId>();
+            }
+        }
+    }
+    let mut removed = false;
+    for entity in bodies.read() {
+        if let Some(handle) = physics.bodies.remove(&entity) {
+            physics.world.remove_body(handle);
+            if let Ok(mut entity) = commands.get_entity(entity) {
+                entity.try_remove::<BodyId>();
+            }
+            removed = true;
+        }
+    }
+    if removed {
+        physics.forget_removed();
+    }
+}
+
+/// Adds the bodies of the entities spawned since the last step to the world.
+fn add_bodies(
+    mut commands: Commands,
+    mut physics: NonSendMut<PhysicsWorld>,
+    added: Query<(Entity, &PhysicsBody, &Transform), Without<BodyId>>,
+) {
+    for (entity, body, transform) in added.iter() {
+        let (position, orientation) = pose(transform);
+        let mut added = RigidBody::new(position, body.mass.unwrap_or(1.0), &Matrix3::identity());
+        added.orientation = orientation;
+        added.velocity = vector(body.velocity);
+        added.rotation = vector(body.rotation);
+        added.set_body_type(body.body_type);
+        added.calculate_derived_data();
+        let handle = physics.world.add_body(added);
+        physics.bodies.insert(entity, handle);
+        commands.entity(entity).insert(BodyId(handle));
+    }
+}
+
+/// Attaches the colliders of the entities spawned since the last step to their bodies, giving
+/// the dynamic ones the mass of their colliders.
+fn add_colliders(
+    mut commands: Commands,
+    mut physics: NonSendMut<PhysicsWorld>,
+    added: Query<(Entity, &PhysicsCollider, &Transform, Option<&ChildOf>), Without<ColliderId>>,
+    bodies: Query<&PhysicsBody>,
+) {
+    let mut attached = Vec::new();
+    for (entity, collider, transform, parent) in added.iter() {
+        // Colliders of entities without bodies are placed relative to the body of their parent.
+        let (owner, offset) = match parent {
+            Some(parent) if !physics.bodies.contains_key(&entity) => (parent.parent(), *transform),
+            _ => (entity, Transform::IDENTITY),
+        };
+        let body = match physics
+            .body_handle(owner)
+            .and_then(|handle| physics.world.body_index(handle))
+        {
+            Some(body) => body,
+            None => continue,
+        };
+        let (position, orientation) = pose(&offset);
+        let offset = Matrix4::from_orientation_and_position(&orientation, &position);
+        let mut added = Collider::with_offset(body, collider.shape.clone(), offset);
+        added.material = collider.material;
+        added.sensor = collider.sensor;
+        let handle = physics.world.add_collider(added);
+        physics.colliders.insert(entity, handle);
+        physics.collider_entities.insert(handle, entity);
+        commands.entity(entity).insert(ColliderId(handle));
+        if !attached.contains(&(owner, body)) {
+            attached.push((owner, body));
+        }
+    }
+
+    for (owner, body) in attached {
+        let mass = match bodies.get(owner) {
+            Ok(owner) if owner.body_type == BodyType::Dynamic => owner.mass,
+            _ => continue,
+        };
+        let properties = physics.world.mass_properties(body);
+        match mass {
+            Some(mass) if properties.mass > 0.0 => physics
+                .world
+                .set_mass_properties(body, &properties.with_mass(mass)),
+            Some(_) => {}
+            None => {
+                physics.world.update_mass_properties(body);
+            }
+        }
+    }
+}
+
+/// Adds the joints of the entities spawned since the last step to the world, once the bodies
+/// they join are added.
+fn add_joints(
+    mut commands: Commands,
+    mut physics: NonSendMut<PhysicsWorld>,
+    added: Query<(Entity, &PhysicsJoint), Without<JointId>>,
+) {
+    for (entity, joint) in added.iter() {
+        let body = |entity: Entity| {
+            physics
+                .body_handle(entity)
+                .and_then(|handle| physics.world.body_index(handle))
+        };
+        let bodies = match (body(joint.bodies.0), joint.bodies.1.map(body)) {
+            (Some(one), None) => (one, None),
+            (Some(one), Some(Some(two))) => (one, Some(two)),
+            _ => continue,
+        };
+        let mut added = Joint::new(bodies, joint.kind);
+        added.break_force = joint.break_force;
+        added.break_torque = joint.break_torque;
+        let handle = physics.world.add_joint(added);
+        physics.joints.insert(entity, handle);
+        physics.joint_entities.insert(handle, entity);
+        commands.entity(entity).insert(JointId(handle));
+    }
+}
+
+/// Moves the kinematic bodies towards the transforms of their entities over the next step.
+fn follow_kinematic(
+    time: Res<Time>,
+    mut physics: NonSendMut<PhysicsWorld>,
+    bodies: Query<(&BodyId, &PhysicsBody, &Transform)>,
+) {
+    let duration = time.delta_secs();
+    if duration <= 0.0 {
+        return;
+    }
+    for (handle, body, transform) in bodies.iter() {
+        if body.body_type != BodyType::Kinematic {
+            continue;
+        }
+        let (position, orientation) = pose(transform);
+        if let Some(body) = physics.world.body_mut(handle.0) {
+            body.move_kinematic(&position, &orientation, duration);
+        }
+    }
+}
+
+/// Steps the world by the fixed timestep.
+fn step(time: Res<Time>, mut physics: NonSendMut<PhysicsWorld>) {
+    let duration = time.delta_secs();
+    if duration > 0.0 {
+        physics.world.start_frame();
+        physics.world.run_physics(duration);
+    }
+}
+
+/// Writes the poses of the dynamic and kinematic bodies to the transforms of their entities.
+fn write_transforms(
+    physics: NonSendMut<PhysicsWorld>,
+    mut bodies: Query<(&BodyId, &PhysicsBody, &mut Transform)>,
+) {
+    for (handle, body, mut transform) in bodies.iter_mut() {
+        if body.body_type != BodyType::Dynamic {
+            continue;
+        }
+        if let Some(body) = physics.world.body(handle.0) {
+            let orientation = &body.orientation;
+            transform.translation = vec3(&body.position);
+            transform.rotation =
+                Quat::from_xyzw(orientation.i, orientation.j, orientation.k, orientation.r);
+        }
+    }
+}
+
+/// Writes the contacts, sensor overlaps and broken joints of the last step as messages.
+fn write_messages(
+    mut physics: NonSendMut<PhysicsWorld>,
+    mut contacts: MessageWriter<ContactMessage>,
+    mut sensors: MessageWriter<SensorMessage>,
+    mut breaks: MessageWriter<JointBreakMessage>,
+) {
+    let events: Vec<_> = physics.world.drain_contact_events().collect();
+    for event in events {
+        let one = physics.collider_entity(event.colliders.0);
+        let two = event
+            .colliders
+            .1
+            .map(|index| physics.collider_entity(index));
+        let entities = match (one, two) {
+            (Some(one), None) => (one, None),
+            (Some(one), Some(Some(two))) => (one, Some(two)),
+            _ => continue,
+        };
+        contacts.write(ContactMessage {
+            kind: event.kind,
+            entities,
+            point: vec3(&event.point),
+            normal: vec3(&event.normal),
+            normal_impulse: event.normal_impulse,
+        });
+    }
+
+    let events: Vec<_> = physics.world.drain_sensor_events().collect();
+    for event in events {
+        let one = physics.collider_entity(event.colliders.0);
+        let two = physics.collider_entity(event.colliders.1);
+        if let (Some(one), Some(two)) = (one, two) {
+            sensors.write(SensorMessage {
+                kind: event.kind,
+                entities: (one, two),
+            });
+        }
+    }
+
+    let broken = physics.world.joint_breaks.clone();
+    for event in broken {
+        let handle = physics.world.joint_handle(event.joint);
+        if let Some(entity) = physics.joint_entities.get(&handle) {
+            breaks.write(JointBreakMessage {
+                entity: *entity,
+                force: vec3(&event.force),
+                torque: vec3(&event.torque),
+            });
+        }
+    }
+}
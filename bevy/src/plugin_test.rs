@@ -0,0 +1,208 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::components::*;
+use crate::event::*;
+use crate::plugin::*;
+use bevy::ecs::message::Messages;
+use bevy::prelude::{App, ChildOf, Entity, Transform, Vec3};
+use bevy::time::{TimePlugin, TimeUpdateStrategy};
+use math::Vector3;
+use phust::ball_joint::BallJoint;
+use phust::event::{ContactEventKind, SensorEventKind};
+use phust::joint::JointKind;
+use phust::rigid_body::BodyType;
+use phust::shape::Shape;
+use std::time::Duration;
+
+/// Returns an app stepping the physics once per update, with the default fixed timestep.
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins((TimePlugin, PhustPlugin::default()))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_micros(
+            15625,
+        )));
+    app
+}
+
+/// Runs the app for the given number of updates, returning the messages written.
+fn run<M: bevy::prelude::Message>(app: &mut App, updates: usize) -> Vec<M> {
+    let mut written = Vec::new();
+    for _ in 0..updates {
+        app.update();
+        written.extend(app.world_mut().resource_mut::<Messages<M>>().drain());
+    }
+    written
+}
+
+fn ground(app: &mut App) -> Entity {
+    app.world_mut()
+        .spawn((
+            PhysicsBody::new(BodyType::Static),
+            PhysicsCollider::new(Shape::cuboid(Vector3::new(10.0, 0.5, 10.0))),
+            Transform::from_xyz(0.0, -0.5, 0.0),
+        ))
+        .id()
+}
+
+fn translation(app: &App, entity: Entity) -> Vec3 {
+    app.world().get::<Transform>(entity).unwrap().translation
+}
+
+#[test]
+fn falling() {
+    let mut app = app();
+    let ground = ground(&mut app);
+    let ball = app
+        .world_mut()
+        .spawn((PhysicsBody::default(), Transform::from_xyz(0.0, 3.0, 0.0)))
+        .id();
+    let sphere = app
+        .world_mut()
+        .spawn((
+            PhysicsCollider::new(Shape::sphere(0.5)),
+            Transform::IDENTITY,
+            ChildOf(ball),
+        ))
+        .id();
+
+    // The ball falls onto the ground and comes to rest on it, its transform following it.
+    let contacts: Vec<ContactMessage> = run(&mut app, 180);
+    assert!((translation(&app, ball).y - 0.5).abs() < 0.05);
+    assert!(translation(&app, ball).x.abs() < 1e-3);
+    assert_eq!(Vec3::new(0.0, -0.5, 0.0), translation(&app, ground));
+    let started: Vec<_> = contacts
+        .iter()
+        .filter(|contact| contact.kind == ContactEventKind::Started)
+        .collect();
+    assert_eq!(1, started.len());
+    let entities = started[0].entities;
+    assert!(entities == (ground, Some(sphere)) || entities == (sphere, Some(ground)));
+
+    // The entities are given the handles of their objects, and the collider the mass of its
+    // shape.
+    let physics = app.world().non_send_resource::<PhysicsWorld>();
+    let handle = physics.body_handle(ball).unwrap();
+    assert_eq!(Some(&BodyId(handle)), app.world().get::<BodyId>(ball));
+    assert_eq!(
+        physics.collider_handle(sphere).map(ColliderId),
+        app.world().get::<ColliderId>(sphere).copied()
+    );
+    let mass = physics.world.body(handle).unwrap().mass();
+    assert!((mass - 4.0 / 3.0 * std::f32::consts::PI * 0.125).abs() < 1e-3);
+}
+
+#[test]
+fn joints() {
+    let mut app = app();
+    let anchor = app
+        .world_mut()
+        .spawn((
+            PhysicsBody::new(BodyType::Static),
+            Transform::from_xyz(0.0, 5.0, 0.0),
+        ))
+        .id();
+    let bob = PhysicsBody {
+        mass: Some(1.0),
+        velocity: Vec3::new(2.0, 0.0, 0.0),
+        ..PhysicsBody::default()
+    };
+    let bob = app
+        .world_mut()
+        .spawn((
+            bob,
+            PhysicsCollider::new(Shape::sphere(0.25)),
+            Transform::from_xyz(0.0, 3.0, 0.0),
+        ))
+        .id();
+    let ball = BallJoint::new(Vector3::origin(), Vector3::new(0.0, 2.0, 0.0));
+    let joint = app
+        .world_mut()
+        .spawn(PhysicsJoint::new(
+            (anchor, Some(bob)),
+            JointKind::Ball(ball),
+        ))
+        .id();
+
+    // The bob swings on the joint, keeping its distance to the anchor.
+    run::<ContactMessage>(&mut app, 60);
+    let offset = translation(&app, bob) - Vec3::new(0.0, 5.0, 0.0);
+    assert!((offset.length() - 2.0).abs() < 0.05);
+    assert!(translation(&app, bob).x.abs() > 0.1);
+    assert!(app.world().get::<JointId>(joint).is_some());
+
+    // Despawning the bob removes it from the world, along with its collider and joint.
+    app.world_mut().despawn(bob);
+    run::<ContactMessage>(&mut app, 1);
+    let physics = app.world().non_send_resource::<PhysicsWorld>();
+    assert_eq!(1, physics.world.body_count());
+    assert!(physics.world.colliders().is_empty());
+    assert!(physics.world.joints().is_empty());
+    assert_eq!(None, physics.body_handle(bob));
+    assert_eq!(None, physics.joint_handle(joint));
+
+    // Joints breaking past their thresholds report it.
+    let heavy = app
+        .world_mut()
+        .spawn((
+            PhysicsBody {
+                mass: Some(10.0),
+                ..PhysicsBody::default()
+            },
+            Transform::from_xyz(0.0, 3.0, 0.0),
+        ))
+        .id();
+    let mut weak = PhysicsJoint::new((anchor, Some(heavy)), JointKind::Ball(ball));
+    weak.break_force = Some(1.0);
+    let weak = app.world_mut().spawn(weak).id();
+    let broken: Vec<JointBreakMessage> = run(&mut app, 10);
+    assert_eq!(1, broken.len());
+    assert_eq!(weak, broken[0].entity);
+    assert!(broken[0].force.length() > 1.0);
+}
+
+#[test]
+fn kinematic() {
+    let mut app = app();
+    ground(&mut app);
+    let ball = app
+        .world_mut()
+        .spawn((
+            PhysicsBody::default(),
+            PhysicsCollider::new(Shape::sphere(0.5)),
+            Transform::from_xyz(0.0, 0.5, 0.0),
+        ))
+        .id();
+    let mut sensor = PhysicsCollider::new(Shape::sphere(0.5));
+    sensor.sensor = true;
+    let platform = app
+        .world_mut()
+        .spawn((
+            PhysicsBody::new(BodyType::Kinematic),
+            sensor,
+            Transform::from_xyz(0.0, 3.0, 0.0),
+        ))
+        .id();
+    run::<SensorMessage>(&mut app, 5);
+
+    // Kinematic bodies follow the transforms of their entities, as they're moved by hand.
+    let mut entered = Vec::new();
+    for step in 1..=30 {
+        app.world_mut()
+            .get_mut::<Transform>(platform)
+            .unwrap()
+            .translation = Vec3::new(0.0, 3.0 - step as f32 * 0.1, 0.0);
+        entered.extend(run::<SensorMessage>(&mut app, 1));
+    }
+    let physics = app.world().non_send_resource::<PhysicsWorld>();
+    let handle = physics.body_handle(platform).unwrap();
+    assert!(physics.world.body(handle).unwrap().position.y.abs() < 1e-3);
+    assert_eq!(Vec3::ZERO, translation(&app, platform));
+    assert_eq!(1, entered.len());
+    assert_eq!(SensorEventKind::Entered, entered[0].kind);
+    let entities = entered[0].entities;
+    assert!(entities == (ball, platform) || entities == (platform, ball));
+}
@@ -0,0 +1,82 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use core::particle::Particle;
+use core::particle_batch::{Lanes, ParticleBatch};
+use math::Vector3;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Number of particles integrated every step.
+const PARTICLES: usize = 100_000;
+
+/// Number of steps timed for every path.
+const STEPS: u32 = 200;
+
+/// Returns the average time a step took, running a warm up step first.
+fn time<S: FnMut()>(mut step: S) -> Duration {
+    step();
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        step();
+    }
+    start.elapsed() / STEPS
+}
+
+/// Reports the time a step took, and the time it took for every particle.
+fn report(name: &str, step: Duration) {
+    println!(
+        "{:<24} {:>10.3} ms/step {:>8.2} ns/particle",
+        name,
+        step.as_secs_f64() * 1e3,
+        step.as_secs_f64() * 1e9 / PARTICLES as f64
+    );
+}
+
+/// Creates the particles integrated by the benchmark.
+fn spray<F: num_traits::Float>() -> Vec<Particle<F>> {
+    (0..PARTICLES)
+        .map(|index| {
+            let offset = F::from(index % 1000).unwrap();
+            let mut particle = Particle::new(Vector3::new(offset, offset, offset), F::one());
+            particle.velocity = Vector3::new(F::one(), F::zero(), -F::one());
+            particle.acceleration = Vector3::new(F::zero(), F::from(-9.8).unwrap(), F::zero());
+            particle.damping = F::from(0.99).unwrap();
+            particle
+        })
+        .collect()
+}
+
+/// Times integrating the particles as a batch of the given lane count.
+fn batch<F: Lanes<N>, const N: usize>(name: &str) {
+    let duration = F::from(1.0 / 60.0).unwrap();
+    let mut batch: ParticleBatch<F, N> = spray::<F>().iter().collect();
+    let step = time(|| black_box(&mut batch).integrate(duration));
+    report(name, step);
+}
+
+/// Times integrating the particles one by one.
+fn particles<F: num_traits::Float>(name: &str) {
+    let duration = F::from(1.0 / 60.0).unwrap();
+    let mut particles = spray::<F>();
+    let step = time(|| {
+        for particle in black_box(&mut particles).iter_mut() {
+            particle.integrate(duration);
+        }
+    });
+    report(name, step);
+}
+
+/// Compares integrating particles one by one against integrating them as batches of 4 and
+/// 8 lanes. Run with `cargo bench -p core --bench particles`.
+fn main() {
+    particles::<f32>("f32 particles");
+    batch::<f32, 4>("f32 batch, 4 lanes");
+    batch::<f32, 8>("f32 batch, 8 lanes");
+    particles::<f64>("f64 particles");
+    batch::<f64, 4>("f64 batch, 4 lanes");
+    batch::<f64, 8>("f64 batch, 8 lanes");
+}
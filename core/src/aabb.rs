@@ -0,0 +1,218 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ray::Ray;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Axis-aligned bounding box, represented by its minimum and maximum corners.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Aabb<F: num_traits::Float = f64> {
+    /// Corner of the box with the smallest coordinates.
+    pub min: Vector3<F>,
+
+    /// Corner of the box with the largest coordinates.
+    pub max: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Aabb<F> {
+    /// Creates a new bounding box with the given corners.
+    pub fn new(min: Vector3<F>, max: Vector3<F>) -> Self {
+        Self { min, max }
+    }
+
+    /// Creates a new bounding box from its center and half-extents.
+    pub fn from_center(center: &Vector3<F>, half_extents: &Vector3<F>) -> Self {
+        Self {
+            min: center.vector_sub(half_extents),
+            max: center.vector_add(half_extents),
+        }
+    }
+
+    /// Creates the smallest bounding box containing all the given points.
+    /// Returns `None` when there are no points.
+    pub fn from_points<'a, I: IntoIterator<Item = &'a Vector3<F>>>(points: I) -> Option<Self>
+    where
+        F: 'a,
+    {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        let mut aabb = Self::new(first, first);
+        for point in points {
+            aabb.inplace_merge_point(point);
+        }
+        Some(aabb)
+    }
+
+    /// Returns the center of the bounding box.
+    pub fn center(&self) -> Vector3<F> {
+        self.min.vector_add(&self.max).scalar_mul(math::real(0.5))
+    }
+
+    /// Returns the half-extents of the bounding box.
+    pub fn half_extents(&self) -> Vector3<F> {
+        self.max.vector_sub(&self.min).scalar_mul(math::real(0.5))
+    }
+
+    /// Returns the surface area of the bounding box.
+    pub fn surface_area(&self) -> F {
+        let size = self.max.vector_sub(&self.min);
+        (size.x * size.y + size.y * size.z + size.z * size.x) * math::real(2.0)
+    }
+
+    /// Returns the volume of the bounding box.
+    pub fn volume(&self) -> F {
+        let size = self.max.vector_sub(&self.min);
+        size.x * size.y * size.z
+    }
+
+    /// Returns true if both bounding boxes overlap. Touching boxes are considered overlapping.
+    pub fn intersects(&self, other: &Aabb<F>) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    /// Returns true if the given bounding box is fully contained in this one.
+    pub fn contains(&self, other: &Aabb<F>) -> bool {
+        self.min.x <= other.min.x
+            && self.min.y <= other.min.y
+            && self.min.z <= other.min.z
+            && self.max.x >= other.max.x
+            && self.max.y >= other.max.y
+            && self.max.z >= other.max.z
+    }
+
+    /// Returns true if the given point is inside the bounding box.
+    pub fn contains_point(&self, point: &Vector3<F>) -> bool {
+        point.x >= self.min.x
+            && point.y >= self.min.y
+            && point.z >= self.min.z
+            && point.x <= self.max.x
+            && point.y <= self.max.y
+            && point.z <= self.max.z
+    }
+
+    /// Returns the smallest bounding box containing both bounding boxes.
+    pub fn merge(&self, other: &Aabb<F>) -> Self {
+        let mut copy = *self;
+        copy.inplace_merge(other);
+        copy
+    }
+
+    /// Grows the bounding box to contain the given one.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the bounding box.
+    pub fn inplace_merge(&mut self, other: &Aabb<F>) -> &mut Self {
+        self.inplace_merge_point(&other.min);
+        self.inplace_merge_point(&other.max)
+    }
+
+    /// Grows the bounding box to contain the given point.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the bounding box.
+    pub fn inplace_merge_point(&mut self, point: &Vector3<F>) -> &mut Self {
+        self.min = Vector3::new(
+            self.min.x.min(point.x),
+            self.min.y.min(point.y),
+            self.min.z.min(point.z),
+        );
+        self.max = Vector3::new(
+            self.max.x.max(point.x),
+            self.max.y.max(point.y),
+            self.max.z.max(point.z),
+        );
+        self
+    }
+
+    /// Returns a copy of the bounding box grown by `margin` in every direction.
+    pub fn loosened(&self, margin: F) -> Self {
+        let margin = Vector3::new(margin, margin, margin);
+        Self {
+            min: self.min.vector_sub(&margin),
+            max: self.max.vector_add(&margin),
+        }
+    }
+
+    /// Returns the point of the bounding box closest to the given point.
+    pub fn closest_point(&self, point: &Vector3<F>) -> Vector3<F> {
+        Vector3::new(
+            point.x.max(self.min.x).min(self.max.x),
+            point.y.max(self.min.y).min(self.max.y),
+            point.z.max(self.min.z).min(self.max.z),
+        )
+    }
+
+    /// Returns the squared distance between the bounding box and the given point.
+    pub fn squared_distance_to_point(&self, point: &Vector3<F>) -> F {
+        self.closest_point(point)
+            .vector_sub(point)
+            .squared_magnitude()
+    }
+
+    /// Computes the time of impact of the ray against the bounding box, using the slab method.
+    /// Returns `None` if the ray doesn't hit the box before `max_toi`.
+    /// Rays starting inside the box hit at time `0`.
+    pub fn cast_ray(&self, ray: &Ray<F>, max_toi: F) -> Option<F> {
+        let mut t_min: F = num_traits::zero();
+        let mut t_max = max_toi;
+
+        for axis in 0..3 {
+            let origin = component(&ray.origin, axis);
+            let direction = component(&ray.direction, axis);
+            let (min, max) = (self.min_along(axis), self.max_along(axis));
+
+            if direction.abs() < F::epsilon() {
+                // The ray is parallel to the slab, so it's either always inside or never.
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let inverse = F::one() / direction;
+            let mut t1 = (min - origin) * inverse;
+            let mut t2 = (max - origin) * inverse;
+            if t1 > t2 {
+                std::mem::swap(&mut t1, &mut t2);
+            }
+
+            t_min = t_min.max(t1);
+            t_max = t_max.min(t2);
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+
+    /// Returns the value of the minimum corner along the given axis.
+    pub fn min_along(&self, axis: usize) -> F {
+        component(&self.min, axis)
+    }
+
+    /// Returns the value of the maximum corner along the given axis.
+    pub fn max_along(&self, axis: usize) -> F {
+        component(&self.max, axis)
+    }
+}
+
+/// Returns the component of the vector along the given axis (`0` for x, `1` for y, `2` for z).
+pub(crate) fn component<F: num_traits::Float>(vector: &Vector3<F>, axis: usize) -> F {
+    match axis {
+        0 => vector.x,
+        1 => vector.y,
+        _ => vector.z,
+    }
+}
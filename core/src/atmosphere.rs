@@ -0,0 +1,181 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::wind::AIR_DENSITY;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Altitude (in meters) over which the density of the air of the Earth falls by a factor of `e`.
+pub const EARTH_SCALE_HEIGHT: f64 = 8500.0;
+
+/// Density of the air (in kilograms per cubic meter) at altitudes (in meters) of the
+/// U.S. Standard Atmosphere, 1976.
+pub const STANDARD_ATMOSPHERE: [(f64, f64); 11] = [
+    (0.0, 1.225),
+    (11000.0, 0.36392),
+    (20000.0, 0.088035),
+    (32000.0, 0.013225),
+    (47000.0, 0.0014275),
+    (51000.0, 0.0008616),
+    (71000.0, 0.000064211),
+    (86000.0, 0.000006958),
+    (100000.0, 5.604e-7),
+    (120000.0, 2.222e-8),
+    (150000.0, 2.076e-9),
+];
+
+/// How the density of the air changes with the altitude.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum DensityProfile<F: num_traits::Float = f64> {
+    /// Same density at every altitude.
+    Uniform(F),
+
+    /// Density falling exponentially with the altitude, `ρ = ρ0·e^(-h/H)`, from the given
+    /// density at zero altitude and scale height `H`.
+    Exponential {
+        /// Density at zero altitude.
+        density: F,
+
+        /// Altitude over which the density falls by a factor of `e`.
+        scale_height: F,
+    },
+
+    /// Densities sampled at increasing altitudes, as `(altitude, density)` pairs.
+    ///
+    /// # Remarks
+    /// The density is interpolated exponentially between the samples, and keeps falling at the
+    /// rate of the last two samples above them. Below the first sample, the density of the
+    /// first one is used.
+    Table(Vec<(F, F)>),
+}
+
+impl<F: num_traits::Float> DensityProfile<F> {
+    /// Returns the density of the air at the given altitude.
+    pub fn density(&self, altitude: F) -> F {
+        match self {
+            DensityProfile::Uniform(density) => *density,
+            DensityProfile::Exponential {
+                density,
+                scale_height,
+            } => *density * (-altitude / *scale_height).exp(),
+            DensityProfile::Table(samples) => {
+                let first = match samples.first() {
+                    Some(first) => first,
+                    None => return F::zero(),
+                };
+                if samples.len() < 2 || altitude <= first.0 {
+                    return first.1;
+                }
+                let segment = samples
+                    .windows(2)
+                    .find(|pair| altitude <= pair[1].0)
+                    .unwrap_or(&samples[samples.len() - 2..]);
+                let ((low, low_density), (high, high_density)) = (segment[0], segment[1]);
+                if low_density <= F::zero() || high_density <= F::zero() {
+                    let t = ((altitude - low) / (high - low)).min(F::one());
+                    return (low_density + (high_density - low_density) * t).max(F::zero());
+                }
+                let t = (altitude - low) / (high - low);
+                low_density * (high_density / low_density).powf(t)
+            }
+        }
+    }
+}
+
+/// Surface the altitude is measured from.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Datum<F: num_traits::Float = f64> {
+    /// Plane through the given point, with the given up direction of unit length.
+    Flat {
+        /// Point at zero altitude.
+        origin: Vector3<F>,
+
+        /// Direction the altitude grows towards.
+        up: Vector3<F>,
+    },
+
+    /// Sphere with the given center and radius, the surface of a planet.
+    Spherical {
+        /// Center of the planet.
+        center: Vector3<F>,
+
+        /// Radius of the planet.
+        radius: F,
+    },
+}
+
+impl<F: num_traits::Float> Datum<F> {
+    /// Returns the altitude of the given point over the datum.
+    pub fn altitude(&self, point: &Vector3<F>) -> F {
+        match self {
+            Datum::Flat { origin, up } => point.vector_sub(origin).dot_product(up),
+            Datum::Spherical { center, radius } => point.vector_sub(center).magnitude() - *radius,
+        }
+    }
+}
+
+/// Air surrounding the world, getting thinner with the altitude.
+///
+/// # Remarks
+/// Set it on a `WindField` for its drag to depend on the altitude of the objects it slows
+/// down, so rockets climb into thinner air and falling bodies brake as it thickens.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Atmosphere<F: num_traits::Float = f64> {
+    /// How the density changes with the altitude.
+    pub profile: DensityProfile<F>,
+
+    /// Surface the altitude is measured from.
+    pub datum: Datum<F>,
+}
+
+impl<F: num_traits::Float> Atmosphere<F> {
+    /// Creates a new atmosphere with the given density profile over the given datum.
+    pub fn new(profile: DensityProfile<F>, datum: Datum<F>) -> Self {
+        Self { profile, datum }
+    }
+
+    /// Creates a new atmosphere like the one of the Earth, falling exponentially from the
+    /// density of air at sea level, with the XZ plane as the sea level and the Y axis up.
+    pub fn exponential() -> Self {
+        Self::new(
+            DensityProfile::Exponential {
+                density: math::real(AIR_DENSITY),
+                scale_height: math::real(EARTH_SCALE_HEIGHT),
+            },
+            Datum::Flat {
+                origin: Vector3::origin(),
+                up: Vector3::new(F::zero(), F::one(), F::zero()),
+            },
+        )
+    }
+
+    /// Creates a new atmosphere following the U.S. Standard Atmosphere, 1976, with the XZ
+    /// plane as the sea level and the Y axis up.
+    pub fn standard() -> Self {
+        Self::new(
+            DensityProfile::Table(
+                STANDARD_ATMOSPHERE
+                    .iter()
+                    .map(|(altitude, density)| (math::real(*altitude), math::real(*density)))
+                    .collect(),
+            ),
+            Datum::Flat {
+                origin: Vector3::origin(),
+                up: Vector3::new(F::zero(), F::one(), F::zero()),
+            },
+        )
+    }
+
+    /// Returns the altitude of the given point.
+    pub fn altitude(&self, point: &Vector3<F>) -> F {
+        self.datum.altitude(point)
+    }
+
+    /// Returns the density of the air at the given point.
+    pub fn density(&self, point: &Vector3<F>) -> F {
+        self.profile.density(self.altitude(point))
+    }
+}
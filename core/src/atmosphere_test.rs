@@ -0,0 +1,120 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::atmosphere::*;
+use crate::force::ForceGenerator;
+use crate::gravity::{GravityField, GravitySource};
+use crate::rigid_body::RigidBody;
+use crate::shape::{Shape, Sphere};
+use crate::wind::{WindField, AIR_DENSITY};
+use crate::world::{World, WorldConfig};
+use math::{Matrix3, Matrix4, Vector3};
+
+#[test]
+fn density_profiles() {
+    let exponential = Atmosphere::<f64>::exponential();
+    let at = |height: f64| Vector3::new(3.0, height, -2.0);
+    assert_eq!(AIR_DENSITY, exponential.density(&at(0.0)));
+    assert!(
+        (exponential.density(&at(EARTH_SCALE_HEIGHT)) - AIR_DENSITY / std::f64::consts::E).abs()
+            < 1e-12
+    );
+
+    // Tables are matched at their samples, and interpolated exponentially between them.
+    let standard = Atmosphere::<f64>::standard();
+    for (altitude, density) in STANDARD_ATMOSPHERE.iter() {
+        assert!((standard.density(&at(*altitude)) - density).abs() < 1e-12 * density);
+    }
+    let middle = standard.density(&at(5500.0));
+    assert!((middle - (1.225f64 * 0.36392).sqrt()).abs() < 1e-12);
+    assert_eq!(1.225, standard.density(&at(-100.0)));
+    let top = standard.density(&at(150000.0));
+    assert!(standard.density(&at(200000.0)) < top * 0.1);
+
+    let table = DensityProfile::Table(vec![(0.0, 1.0), (10.0, 0.0)]);
+    assert_eq!(0.5, table.density(5.0));
+    assert_eq!(0.0, table.density(20.0));
+    assert_eq!(0.0, DensityProfile::<f64>::Table(Vec::new()).density(1.0));
+
+    // Spherical planets measure the altitude from their surface in every direction.
+    let planet = Datum::<f64>::Spherical {
+        center: Vector3::new(0.0, -100.0, 0.0),
+        radius: 100.0,
+    };
+    assert!((planet.altitude(&Vector3::new(0.0, 10.0, 0.0)) - 10.0).abs() < 1e-12);
+    assert!((planet.altitude(&Vector3::new(110.0, -100.0, 0.0)) - 10.0).abs() < 1e-12);
+}
+
+#[test]
+fn thinning_drag() {
+    let mut field = WindField::atmospheric(Atmosphere::<f64>::exponential());
+    let mut bodies = vec![
+        RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()),
+        RigidBody::new(
+            Vector3::new(0.0, EARTH_SCALE_HEIGHT, 0.0),
+            1.0,
+            &Matrix3::identity(),
+        ),
+    ];
+    for body in bodies.iter_mut() {
+        body.velocity = Vector3::new(100.0, 0.0, 0.0);
+    }
+    ForceGenerator::update_force(&mut field, &mut bodies, 0, 0.1);
+    ForceGenerator::update_force(&mut field, &mut bodies, 1, 0.1);
+    let low = bodies[0].force_accum;
+    let high = bodies[1].force_accum;
+    assert!((low.x + 0.5 * AIR_DENSITY * field.area * 10000.0).abs() < 1e-9);
+    assert!((high * std::f64::consts::E - low).magnitude() < 1e-9);
+}
+
+#[test]
+fn reentry() {
+    // Allen and Eggers: a body falling straight down through an exponential atmosphere brakes
+    // the hardest where the density is `m / (Cd·A·H)`, then falls at terminal velocity.
+    let mut world = World::new(WorldConfig {
+        linear_damping: 1.0,
+        ..WorldConfig::default()
+    });
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -9.81, 0.0)));
+    let mut capsule = RigidBody::new(
+        Vector3::new(0.0, 120000.0, 0.0),
+        100.0,
+        &Matrix3::identity(),
+    );
+    capsule.velocity = Vector3::new(0.0, -2000.0, 0.0);
+    capsule.can_sleep = false;
+    let capsule = world.add_body(capsule);
+    let capsule = world.body_index(capsule).unwrap();
+    let mut field = WindField::atmospheric(Atmosphere::exponential());
+    field.set_shape(
+        capsule,
+        Shape::Sphere(Sphere::new(0.5)),
+        Matrix4::identity(),
+    );
+    world.registry.add(capsule, Box::new(field));
+
+    let area = std::f64::consts::PI * 0.25;
+    let duration = 0.01;
+    let (mut peak, mut peak_altitude) = (0.0, 0.0);
+    while world.bodies[capsule].position.y > 5000.0 {
+        let velocity = world.bodies[capsule].velocity;
+        world.start_frame();
+        world.run_physics(duration);
+        let braking = (world.bodies[capsule].velocity.y - velocity.y) / duration + 9.81;
+        if braking > peak {
+            peak = braking;
+            peak_altitude = world.bodies[capsule].position.y;
+        }
+    }
+    let expected = EARTH_SCALE_HEIGHT * (AIR_DENSITY * area * EARTH_SCALE_HEIGHT / 100.0).ln();
+    assert!((peak_altitude - expected).abs() < 2000.0);
+    assert!(peak > 100.0 && peak < 150.0);
+
+    let body = &world.bodies[capsule];
+    let density = Atmosphere::<f64>::exponential().density(&body.position);
+    let terminal = (2.0 * 100.0 * 9.81 / (density * area)).sqrt();
+    assert!((body.velocity.magnitude() - terminal).abs() < 0.05 * terminal);
+}
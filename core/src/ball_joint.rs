@@ -0,0 +1,43 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::joint::{Anchors, RowBuilder};
+use crate::rigid_body::RigidBody;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Ball-and-socket joint, keeping an anchor point of each body together
+/// while letting the bodies rotate freely around it.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BallJoint<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector3<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector3<F>,
+}
+
+impl<F: num_traits::Float> BallJoint<F> {
+    /// Creates a new ball joint between the given anchors.
+    pub fn new(local_anchor_one: Vector3<F>, local_anchor_two: Vector3<F>) -> Self {
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+        }
+    }
+
+    /// Adds the constraint rows of the joint.
+    pub(crate) fn rows(&self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let anchors = Anchors::new(
+            builder.bodies(),
+            &self.local_anchor_one,
+            &self.local_anchor_two,
+            bodies,
+        );
+        builder.point(0, &anchors);
+    }
+}
@@ -0,0 +1,378 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::query::{QueryFilter, RayHit};
+use crate::ray::Ray;
+use crate::world::World;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Number of elevation angles sampled when solving launches with drag, looking for the
+/// ranges where the trajectory goes from passing under the target to passing over it.
+const LAUNCH_ANGLE_SAMPLES: usize = 64;
+
+/// Number of bisections refining every launch angle solved with drag.
+const LAUNCH_ANGLE_BISECTIONS: usize = 60;
+
+/// Returns the linear drag coefficient, per second, matching the given linear damping of a
+/// rigid body, the fraction of its velocity kept every second.
+pub fn drag_from_damping<F: num_traits::Float>(damping: F) -> F {
+    if damping > F::zero() {
+        -damping.ln()
+    } else {
+        F::infinity()
+    }
+}
+
+/// Returns the linear damping of a rigid body, the fraction of its velocity kept every
+/// second, matching the given linear drag coefficient, per second.
+pub fn damping_from_drag<F: num_traits::Float>(drag: F) -> F {
+    (-drag).exp()
+}
+
+/// Path of a projectile launched under constant gravity and linear drag, predicted in
+/// closed form.
+///
+/// # Remarks
+/// Linear drag decelerates the projectile in proportion to its velocity, `a = g - k·v`,
+/// which is what the linear damping of rigid bodies does. The velocity then tends
+/// exponentially to the terminal velocity `g / k`:
+///
+/// `v(t) = g/k + (v0 - g/k)·e^(-k·t)`
+///
+/// `p(t) = p0 + g/k·t + (v0 - g/k)·(1 - e^(-k·t))/k`
+///
+/// Without drag, these are the usual parabolas.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Trajectory<F: num_traits::Float = f64> {
+    /// Position the projectile is launched from.
+    pub origin: Vector3<F>,
+
+    /// Velocity the projectile is launched with.
+    pub velocity: Vector3<F>,
+
+    /// Acceleration due to gravity pulling the projectile.
+    pub gravity: Vector3<F>,
+
+    /// Linear drag coefficient, per second.
+    pub drag: F,
+}
+
+impl<F: num_traits::Float> Trajectory<F> {
+    /// Creates a new trajectory without drag.
+    pub fn new(origin: Vector3<F>, velocity: Vector3<F>, gravity: Vector3<F>) -> Self {
+        Self::with_drag(origin, velocity, gravity, num_traits::zero())
+    }
+
+    /// Creates a new trajectory with the given linear drag coefficient, per second.
+    pub fn with_drag(
+        origin: Vector3<F>,
+        velocity: Vector3<F>,
+        gravity: Vector3<F>,
+        drag: F,
+    ) -> Self {
+        Self {
+            origin,
+            velocity,
+            gravity,
+            drag,
+        }
+    }
+
+    /// Predicts the trajectory of a body of the given world from its current position and
+    /// velocity, with the gravity at its position and its linear damping as drag.
+    ///
+    /// # Remarks
+    /// The gravity is taken as constant along the way, and forces other than gravity and
+    /// damping are ignored. The world integrates the body in steps, so the body drifts from
+    /// the prediction by an amount proportional to the duration of the steps.
+    pub fn from_body(world: &World<F>, body: usize) -> Self {
+        let rigid_body = &world.bodies[body];
+        let acceleration = match world.gravity_overrides.get(&body) {
+            Some(source) => source.acceleration_at(&rigid_body.position),
+            None => world.gravity.acceleration_at(&rigid_body.position),
+        };
+        let damping = rigid_body
+            .linear_damping
+            .unwrap_or(world.config.linear_damping);
+        Self::with_drag(
+            rigid_body.position,
+            rigid_body.velocity,
+            acceleration.scalar_mul(rigid_body.gravity_scale),
+            drag_from_damping(damping),
+        )
+    }
+
+    /// Returns the fraction `(1 - e^(-k·t))/k` weighting the launch velocity, which is the
+    /// time itself without drag.
+    fn decay(&self, time: F) -> F {
+        if self.drag > F::zero() {
+            -(-self.drag * time).exp_m1() / self.drag
+        } else {
+            time
+        }
+    }
+
+    /// Returns the position of the projectile at the given time after its launch.
+    pub fn position_at(&self, time: F) -> Vector3<F> {
+        let decay = self.decay(time);
+        if self.drag > F::zero() {
+            let terminal = self.gravity.scalar_div(self.drag);
+            self.origin
+                .vector_add(&terminal.scalar_mul(time))
+                .vector_add(&self.velocity.vector_sub(&terminal).scalar_mul(decay))
+        } else {
+            self.origin
+                .vector_add(&self.velocity.scalar_mul(time))
+                .vector_add(&self.gravity.scalar_mul(time * time * math::real(0.5)))
+        }
+    }
+
+    /// Returns the velocity of the projectile at the given time after its launch.
+    pub fn velocity_at(&self, time: F) -> Vector3<F> {
+        if self.drag > F::zero() {
+            let terminal = self.gravity.scalar_div(self.drag);
+            terminal.vector_add(
+                &self
+                    .velocity
+                    .vector_sub(&terminal)
+                    .scalar_mul((-self.drag * time).exp()),
+            )
+        } else {
+            self.velocity.vector_add(&self.gravity.scalar_mul(time))
+        }
+    }
+
+    /// Returns the time the projectile reaches its highest point against gravity,
+    /// or `None` if it's launched downwards, or without gravity.
+    pub fn apex_time(&self) -> Option<F> {
+        let gravity = self.gravity.magnitude();
+        if gravity <= F::zero() {
+            return None;
+        }
+        let rising = -self.velocity.dot_product(&self.gravity) / gravity;
+        if rising <= F::zero() {
+            return None;
+        }
+        if self.drag > F::zero() {
+            Some((self.drag * rising / gravity).ln_1p() / self.drag)
+        } else {
+            Some(rising / gravity)
+        }
+    }
+
+    /// Returns the highest point of the trajectory against gravity,
+    /// or `None` if the projectile is launched downwards, or without gravity.
+    pub fn apex(&self) -> Option<Vector3<F>> {
+        self.apex_time().map(|time| self.position_at(time))
+    }
+
+    /// Returns the given number of points of the trajectory evenly spaced in time over the
+    /// given duration, starting at the launch, to draw it.
+    pub fn points(&self, duration: F, count: usize) -> Vec<Vector3<F>> {
+        let steps = math::real::<F>(count.saturating_sub(1).max(1) as f64);
+        (0..count)
+            .map(|index| self.position_at(duration * math::real(index as f64) / steps))
+            .collect()
+    }
+
+    /// Casts the trajectory against the colliders and scenery planes of the given world
+    /// passing the filter, over the given duration split into the given number of straight
+    /// segments, returning the first hit and the time it happens at, if any.
+    ///
+    /// # Remarks
+    /// Turrets check their line of fire with it. The projectile is taken as a point, and
+    /// the time of the hit is interpolated along the segment hit. Casting the trajectory of
+    /// a body takes a filter excluding the body itself.
+    pub fn cast(
+        &self,
+        world: &World<F>,
+        duration: F,
+        segments: usize,
+        filter: &QueryFilter<F>,
+    ) -> Option<TrajectoryHit<F>> {
+        let segments = segments.max(1);
+        let step = duration / math::real(segments as f64);
+        let mut start = self.origin;
+        for segment in 0..segments {
+            let time = step * math::real(segment as f64);
+            let end = self.position_at(time + step);
+            let offset = end.vector_sub(&start);
+            let length = offset.magnitude();
+            if length > F::zero() {
+                let ray = Ray::new(start, offset.scalar_div(length));
+                if let Some(hit) = world.raycast(&ray, length, filter) {
+                    return Some(TrajectoryHit {
+                        time: time + step * hit.distance / length,
+                        hit,
+                    });
+                }
+            }
+            start = end;
+        }
+        None
+    }
+}
+
+/// Hit of a trajectory cast against a world.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TrajectoryHit<F: num_traits::Float = f64> {
+    /// Time after the launch the projectile hits.
+    pub time: F,
+
+    /// Collider, or scenery plane, hit, and where.
+    pub hit: RayHit<F>,
+}
+
+/// Returns the velocities, with the given speed, a projectile launched from the origin must
+/// have to go through the target, under the given gravity and linear drag coefficient, per
+/// second. The flat trajectory comes first and the lofted one second, and there are none
+/// when the target is out of reach.
+///
+/// # Remarks
+/// Without drag the launch angles are solved in closed form. With drag there's no closed
+/// form for them, so the closed form trajectory is searched for the angles instead, which
+/// is still far cheaper and more accurate than simulating the flight.
+pub fn launch_velocities<F: num_traits::Float>(
+    origin: &Vector3<F>,
+    target: &Vector3<F>,
+    speed: F,
+    gravity: &Vector3<F>,
+    drag: F,
+) -> Vec<Vector3<F>> {
+    let magnitude = gravity.magnitude();
+    let offset = target.vector_sub(origin);
+    if speed <= F::zero() || offset.squared_magnitude() <= F::zero() {
+        return Vec::new();
+    }
+
+    // Work in the vertical plane through the target, with up against gravity.
+    let up = if magnitude > F::zero() {
+        gravity.scalar_div(-magnitude)
+    } else {
+        offset.normalize()
+    };
+    let height = offset.dot_product(&up);
+    let horizontal = offset.vector_sub(&up.scalar_mul(height));
+    let distance = horizontal.magnitude();
+    let along = if distance > F::zero() {
+        horizontal.scalar_div(distance)
+    } else {
+        Vector3::origin()
+    };
+    let velocity = |angle: F| {
+        along
+            .scalar_mul(speed * angle.cos())
+            .vector_add(&up.scalar_mul(speed * angle.sin()))
+    };
+    let reaches = |angle: F| {
+        let trajectory = Trajectory::with_drag(*origin, velocity(angle), *gravity, drag);
+        let time = match trajectory.apex_time() {
+            Some(time) => time,
+            None => num_traits::zero(),
+        };
+        trajectory
+            .position_at(time)
+            .vector_sub(origin)
+            .dot_product(&up)
+            >= height
+    };
+
+    // Targets straight above or below are shot at straight.
+    let right = math::real::<F>(std::f64::consts::FRAC_PI_2);
+    if distance <= F::epsilon() * offset.magnitude() {
+        return if height < F::zero() || reaches(right) {
+            vec![offset.normalize().scalar_mul(speed)]
+        } else {
+            Vec::new()
+        };
+    }
+
+    if drag <= F::zero() {
+        // tan(θ) = (s² ± √(s⁴ - g·(g·d² + 2·h·s²))) / (g·d)
+        let squared = speed * speed;
+        let discriminant = squared * squared
+            - magnitude
+                * (magnitude * distance * distance + math::real::<F>(2.0) * height * squared);
+        if discriminant < F::zero() {
+            return Vec::new();
+        }
+        if magnitude <= F::zero() {
+            return vec![offset.normalize().scalar_mul(speed)];
+        }
+        let root = discriminant.sqrt();
+        let mut velocities = vec![velocity(((squared - root) / (magnitude * distance)).atan())];
+        if root > F::zero() {
+            velocities.push(velocity(((squared + root) / (magnitude * distance)).atan()));
+        }
+        return velocities;
+    }
+
+    // The projectile covers the horizontal distance at the time t solving
+    // d = s·cos(θ)·(1 - e^(-k·t))/k, if it ever does, since drag bounds its range.
+    // Projectiles falling short count as passing under the target.
+    let miss = |angle: F| -> F {
+        let horizontal_speed = speed * angle.cos();
+        let fraction = drag * distance / horizontal_speed;
+        if horizontal_speed <= F::zero() || fraction >= F::one() {
+            return F::neg_infinity();
+        }
+        let time = -(-fraction).ln_1p() / drag;
+        let trajectory = Trajectory::with_drag(*origin, velocity(angle), *gravity, drag);
+        trajectory
+            .position_at(time)
+            .vector_sub(origin)
+            .dot_product(&up)
+            - height
+    };
+    let mut velocities = Vec::new();
+    let samples = math::real::<F>(LAUNCH_ANGLE_SAMPLES as f64);
+    let angle_at =
+        |sample: usize| right * (math::real::<F>(2.0 * sample as f64) / samples - F::one());
+    let mut previous = angle_at(0);
+    for sample in 1..=LAUNCH_ANGLE_SAMPLES {
+        let current = angle_at(sample);
+        let (mut below, mut above) = match (miss(previous) > F::zero(), miss(current) > F::zero()) {
+            (false, true) => (previous, current),
+            (true, false) => (current, previous),
+            _ => {
+                previous = current;
+                continue;
+            }
+        };
+        for _ in 0..LAUNCH_ANGLE_BISECTIONS {
+            let middle = (below + above) * math::real(0.5);
+            if miss(middle) > F::zero() {
+                above = middle;
+            } else {
+                below = middle;
+            }
+        }
+        velocities.push(velocity((below + above) * math::real(0.5)));
+        previous = current;
+    }
+    velocities
+}
+
+/// Launches a body of the given world as a projectile with the given velocity and linear
+/// drag coefficient, per second, so it follows the trajectory predicted for it.
+///
+/// # Remarks
+/// Projectiles are fast and small, so continuous collision detection is enabled for the
+/// body, sweeping its colliders along its motion every step so it doesn't tunnel through
+/// thin walls. The drag becomes the linear damping of the body.
+pub fn launch<F: num_traits::Float>(
+    world: &mut World<F>,
+    body: usize,
+    velocity: Vector3<F>,
+    drag: F,
+) {
+    let rigid_body = &mut world.bodies[body];
+    rigid_body.velocity = velocity;
+    rigid_body.linear_damping = Some(damping_from_drag(drag));
+    rigid_body.continuous_collision = true;
+    rigid_body.set_awake(true);
+}
@@ -0,0 +1,187 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ballistics::*;
+use crate::collider::Collider;
+use crate::gravity::{GravityField, GravitySource};
+use crate::query::QueryFilter;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Cuboid, Shape, Sphere};
+use crate::world::World;
+use math::{Matrix3, Vector3};
+
+fn assert_vector_eq(expected: Vector3<f64>, actual: Vector3<f64>, tolerance: f64) {
+    assert!(
+        (expected - actual).magnitude() < tolerance,
+        "{:?} != {:?}",
+        expected,
+        actual
+    );
+}
+
+/// Returns the height of the trajectory, against the gravity pulling down the Y axis, when it
+/// gets to the horizontal distance of the target.
+fn height_at_target(trajectory: &Trajectory, target: &Vector3<f64>) -> f64 {
+    let horizontal = |point: Vector3<f64>| Vector3::new(point.x, 0.0, point.z).magnitude();
+    let distance = horizontal(*target - trajectory.origin);
+    let (mut start, mut end) = (0.0, 100.0);
+    for _ in 0..100 {
+        let middle = (start + end) * 0.5;
+        if horizontal(trajectory.position_at(middle) - trajectory.origin) < distance {
+            start = middle;
+        } else {
+            end = middle;
+        }
+    }
+    trajectory.position_at(start).y
+}
+
+#[test]
+fn trajectories() {
+    let gravity = Vector3::new(0.0, -10.0, 0.0);
+    let vacuum = Trajectory::new(
+        Vector3::new(1.0, 2.0, 3.0),
+        Vector3::new(5.0, 10.0, 0.0),
+        gravity,
+    );
+    assert_vector_eq(Vector3::new(11.0, 2.0, 3.0), vacuum.position_at(2.0), 1e-12);
+    assert_vector_eq(
+        Vector3::new(5.0, -10.0, 0.0),
+        vacuum.velocity_at(2.0),
+        1e-12,
+    );
+    assert_eq!(Some(1.0), vacuum.apex_time());
+    assert_vector_eq(Vector3::new(6.0, 7.0, 3.0), vacuum.apex().unwrap(), 1e-12);
+
+    // Drag slows the projectile down towards its terminal velocity, and the velocity is the
+    // derivative of the position.
+    let dragged = Trajectory {
+        drag: 0.5,
+        ..vacuum
+    };
+    assert_vector_eq(
+        Vector3::new(0.0, -20.0, 0.0),
+        dragged.velocity_at(100.0),
+        1e-9,
+    );
+    let step = 1e-6;
+    let derivative =
+        (dragged.position_at(1.0 + step) - dragged.position_at(1.0 - step)) / (2.0 * step);
+    assert_vector_eq(dragged.velocity_at(1.0), derivative, 1e-6);
+    let apex = dragged.apex_time().unwrap();
+    assert!(apex < 1.0);
+    assert!(dragged.velocity_at(apex).y.abs() < 1e-12);
+    assert!(dragged.apex().unwrap().y < 7.0);
+
+    // Negligible drag matches the vacuum.
+    let negligible = Trajectory {
+        drag: 1e-9,
+        ..vacuum
+    };
+    assert_vector_eq(vacuum.position_at(2.0), negligible.position_at(2.0), 1e-6);
+
+    let points = vacuum.points(2.0, 5);
+    assert_eq!(5, points.len());
+    assert_vector_eq(vacuum.origin, points[0], 1e-12);
+    assert_vector_eq(vacuum.position_at(2.0), points[4], 1e-12);
+
+    assert!(
+        Trajectory::new(Vector3::origin(), Vector3::new(0.0, -1.0, 0.0), gravity)
+            .apex()
+            .is_none()
+    );
+    assert!((damping_from_drag(drag_from_damping(0.95f64)) - 0.95).abs() < 1e-12);
+}
+
+#[test]
+fn launch_angles() {
+    let gravity = Vector3::<f64>::new(0.0, -10.0, 0.0);
+    let origin = Vector3::new(0.0, 1.0, 0.0);
+    let target = Vector3::new(30.0, 5.0, 40.0);
+
+    // Targets in reach are hit with a flat and a lofted trajectory.
+    for drag in [0.0, 0.1] {
+        let velocities = launch_velocities(&origin, &target, 40.0, &gravity, drag);
+        assert_eq!(2, velocities.len());
+        assert!(velocities[0].y < velocities[1].y);
+        for velocity in velocities.iter() {
+            assert!((velocity.magnitude() - 40.0).abs() < 1e-9);
+            let trajectory = Trajectory::with_drag(origin, *velocity, gravity, drag);
+            assert!((height_at_target(&trajectory, &target) - target.y).abs() < 1e-6);
+        }
+    }
+
+    // Drag shortens the range.
+    assert_eq!(
+        2,
+        launch_velocities(&origin, &target, 24.0, &gravity, 0.0).len()
+    );
+    assert!(launch_velocities(&origin, &target, 24.0, &gravity, 0.1).is_empty());
+    assert!(launch_velocities(&origin, &target, 20.0, &gravity, 0.0).is_empty());
+
+    // Targets straight above are shot at straight up, if they're low enough.
+    let above = Vector3::new(0.0, 20.0, 0.0);
+    assert_eq!(
+        vec![Vector3::new(0.0, 20.0, 0.0)],
+        launch_velocities(&origin, &above, 20.0, &gravity, 0.0)
+    );
+    assert!(launch_velocities(&origin, &above, 19.0, &gravity, 0.0).is_empty());
+}
+
+#[test]
+fn projectiles() {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    let wall = world.add_body(RigidBody::new(
+        Vector3::new(20.0, 0.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let wall = world.body_index(wall).unwrap();
+    world.bodies[wall].set_infinite_mass();
+    world.add_collider(Collider::new(
+        wall,
+        Shape::Cuboid(Cuboid::new(Vector3::new(0.05, 10.0, 10.0))),
+    ));
+    let shell = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    let shell = world.body_index(shell).unwrap();
+    world.add_collider(Collider::new(shell, Shape::Sphere(Sphere::new(0.05))));
+
+    // The shell flies along its predicted trajectory, up to the error of the integration.
+    let velocity = launch_velocities(
+        &Vector3::origin(),
+        &Vector3::new(19.0, 2.0, 0.0),
+        30.0,
+        &Vector3::new(0.0, -10.0, 0.0),
+        0.2,
+    )[0];
+    launch(&mut world, shell, velocity, 0.2);
+    assert!(world.bodies[shell].continuous_collision);
+    let trajectory = Trajectory::from_body(&world, shell);
+    assert!((trajectory.drag - 0.2).abs() < 1e-12);
+    assert_vector_eq(Vector3::new(0.0, -10.0, 0.0), trajectory.gravity, 1e-12);
+
+    world.start_frame();
+    world.update_colliders();
+    let mut filter = QueryFilter::new();
+    filter.exclude_body = Some(shell);
+    let hit = trajectory.cast(&world, 2.0, 32, &filter).unwrap();
+    assert_eq!(Some(wall), hit.hit.body);
+    assert!((hit.hit.point.x - 19.95).abs() < 1e-9);
+    assert!((hit.hit.point.y - trajectory.position_at(hit.time).y).abs() < 0.05);
+
+    let steps = (hit.time * 0.5 * 240.0) as usize;
+    for _ in 0..steps {
+        world.start_frame();
+        world.run_physics(1.0 / 240.0);
+    }
+    let time = steps as f64 / 240.0;
+    assert_vector_eq(
+        trajectory.position_at(time),
+        world.bodies[shell].position,
+        0.05,
+    );
+}
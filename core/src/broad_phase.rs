@@ -0,0 +1,198 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use serde::{Deserialize, Serialize};
+
+/// Default margin by which the bounding boxes of proxies are fattened.
+pub const DEFAULT_BROAD_PHASE_MARGIN: f64 = 0.1;
+
+/// Polymorphic interface for the coarse collision detection stage.
+/// A broad phase keeps track of the bounding boxes of a set of proxies, and
+/// produces the pairs of proxies that could potentially be colliding.
+///
+/// # Remarks
+/// Proxies are identified by a user provided index (e.g. the index of a collider).
+/// Implementations store fat bounding boxes, grown by a margin, so proxies moving
+/// a small amount between frames don't need to be updated in the acceleration structure.
+pub trait BroadPhase<F: num_traits::Float = f64> {
+    /// Adds a proxy with the given identifier and tight bounding box.
+    fn insert(&mut self, id: usize, aabb: &Aabb<F>);
+
+    /// Updates the tight bounding box of a proxy.
+    /// Returns true if the proxy had to be moved because it left its fat bounding box.
+    fn update(&mut self, id: usize, aabb: &Aabb<F>) -> bool;
+
+    /// Removes the proxy with the given identifier.
+    fn remove(&mut self, id: usize);
+
+    /// Returns the fat bounding box of the given proxy, if any.
+    fn fat_aabb(&self, id: usize) -> Option<Aabb<F>>;
+
+    /// Fills `pairs` with every pair of proxies whose fat bounding boxes overlap.
+    /// Each pair is reported once, with the smallest identifier first, and the
+    /// pairs are sorted in ascending order.
+    fn potential_pairs(&mut self, pairs: &mut Vec<(usize, usize)>);
+}
+
+/// Endpoint of the interval covered by a proxy along the sorting axis.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+struct Endpoint {
+    proxy: usize,
+    is_min: bool,
+}
+
+/// Incremental sweep-and-prune broad phase.
+///
+/// # Remarks
+/// The endpoints of the bounding boxes along one axis are kept in a sorted list.
+/// Because bodies move little between frames, the list is nearly sorted at each
+/// update and is re-sorted with an insertion sort in close to linear time.
+/// Sweeping the list then only tests overlapping intervals against each other.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SweepAndPrune<F: num_traits::Float = f64> {
+    /// Margin by which the bounding boxes of proxies are fattened.
+    pub margin: F,
+
+    axis: usize,
+    proxies: Vec<Option<Aabb<F>>>,
+    endpoints: Vec<Endpoint>,
+}
+
+impl<F: num_traits::Float> Default for SweepAndPrune<F> {
+    fn default() -> Self {
+        Self::new(math::real(DEFAULT_BROAD_PHASE_MARGIN))
+    }
+}
+
+impl<F: num_traits::Float> SweepAndPrune<F> {
+    /// Creates a new sweep-and-prune broad phase sorting along the x axis.
+    pub fn new(margin: F) -> Self {
+        Self::with_axis(margin, 0)
+    }
+
+    /// Creates a new sweep-and-prune broad phase sorting along the given axis
+    /// (`0` for x, `1` for y, `2` for z). The best axis is the one along
+    /// which the proxies are most spread out.
+    pub fn with_axis(margin: F, axis: usize) -> Self {
+        Self {
+            margin,
+            axis: axis.min(2),
+            proxies: Vec::new(),
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Returns the number of proxies in the broad phase.
+    pub fn len(&self) -> usize {
+        self.endpoints.len() / 2
+    }
+
+    /// Returns true if there are no proxies in the broad phase.
+    pub fn is_empty(&self) -> bool {
+        self.endpoints.is_empty()
+    }
+
+    fn value(&self, endpoint: &Endpoint) -> F {
+        let aabb = self.proxies[endpoint.proxy]
+            .as_ref()
+            .expect("endpoints only reference live proxies");
+        if endpoint.is_min {
+            aabb.min_along(self.axis)
+        } else {
+            aabb.max_along(self.axis)
+        }
+    }
+
+    /// Returns true if the endpoint `a` should go before `b` in the sorted list.
+    /// Minimum endpoints go first on ties, so touching boxes are reported as overlapping.
+    fn precedes(&self, a: &Endpoint, b: &Endpoint) -> bool {
+        let (a_value, b_value) = (self.value(a), self.value(b));
+        a_value < b_value || (a_value == b_value && a.is_min && !b.is_min)
+    }
+
+    fn sort_endpoints(&mut self) {
+        for index in 1..self.endpoints.len() {
+            let mut position = index;
+            while position > 0
+                && self.precedes(&self.endpoints[position], &self.endpoints[position - 1])
+            {
+                self.endpoints.swap(position, position - 1);
+                position -= 1;
+            }
+        }
+    }
+}
+
+impl<F: num_traits::Float> BroadPhase<F> for SweepAndPrune<F> {
+    fn insert(&mut self, id: usize, aabb: &Aabb<F>) {
+        if self.proxies.len() <= id {
+            self.proxies.resize(id + 1, None);
+        }
+
+        if self.proxies[id].is_none() {
+            self.endpoints.push(Endpoint {
+                proxy: id,
+                is_min: true,
+            });
+            self.endpoints.push(Endpoint {
+                proxy: id,
+                is_min: false,
+            });
+        }
+        self.proxies[id] = Some(aabb.loosened(self.margin));
+    }
+
+    fn update(&mut self, id: usize, aabb: &Aabb<F>) -> bool {
+        match self.proxies.get(id).copied().flatten() {
+            Some(fat) if fat.contains(aabb) => false,
+            Some(_) => {
+                self.proxies[id] = Some(aabb.loosened(self.margin));
+                true
+            }
+            None => {
+                self.insert(id, aabb);
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(proxy) = self.proxies.get_mut(id) {
+            if proxy.take().is_some() {
+                self.endpoints.retain(|endpoint| endpoint.proxy != id);
+            }
+        }
+    }
+
+    fn fat_aabb(&self, id: usize) -> Option<Aabb<F>> {
+        self.proxies.get(id).copied().flatten()
+    }
+
+    fn potential_pairs(&mut self, pairs: &mut Vec<(usize, usize)>) {
+        pairs.clear();
+        self.sort_endpoints();
+
+        let mut active: Vec<usize> = Vec::new();
+        for endpoint in self.endpoints.iter() {
+            if !endpoint.is_min {
+                active.retain(|proxy| *proxy != endpoint.proxy);
+                continue;
+            }
+
+            let aabb = self.proxies[endpoint.proxy].expect("live proxy");
+            for other in active.iter() {
+                let other_aabb = self.proxies[*other].expect("live proxy");
+                if aabb.intersects(&other_aabb) {
+                    pairs.push((endpoint.proxy.min(*other), endpoint.proxy.max(*other)));
+                }
+            }
+            active.push(endpoint.proxy);
+        }
+
+        pairs.sort_unstable();
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::broad_phase::*;
+use math::Vector3;
+
+fn cube(x: f64, y: f64, z: f64) -> Aabb<f64> {
+    Aabb::from_center(&Vector3::new(x, y, z), &Vector3::new(0.5, 0.5, 0.5))
+}
+
+fn brute_force(aabbs: &[Aabb<f64>], margin: f64) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for first in 0..aabbs.len() {
+        for second in first + 1..aabbs.len() {
+            if aabbs[first]
+                .loosened(margin)
+                .intersects(&aabbs[second].loosened(margin))
+            {
+                pairs.push((first, second));
+            }
+        }
+    }
+    pairs
+}
+
+#[test]
+fn aabb() {
+    let aabb = cube(0.0, 0.0, 0.0);
+    assert_eq!(Vector3::origin(), aabb.center());
+    assert_eq!(Vector3::new(0.5, 0.5, 0.5), aabb.half_extents());
+    assert_eq!(6.0, aabb.surface_area());
+    assert_eq!(1.0, aabb.volume());
+    assert!(aabb.intersects(&cube(1.0, 0.0, 0.0)));
+    assert!(!aabb.intersects(&cube(1.5, 0.0, 0.0)));
+    assert!(aabb.loosened(0.1).contains(&aabb));
+    assert!(!aabb.contains(&aabb.loosened(0.1)));
+    assert!(aabb.contains_point(&Vector3::new(0.5, 0.0, 0.0)));
+    assert_eq!(
+        Aabb::new(Vector3::new(-0.5, -0.5, -0.5), Vector3::new(2.5, 0.5, 0.5)),
+        aabb.merge(&cube(2.0, 0.0, 0.0))
+    );
+    assert_eq!(
+        Some(Aabb::new(
+            Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(1.0, 2.0, 0.0)
+        )),
+        Aabb::from_points(&[Vector3::new(-1.0, 2.0, 0.0), Vector3::new(1.0, 0.0, 0.0)])
+    );
+    assert_eq!(
+        4.0,
+        aabb.squared_distance_to_point(&Vector3::new(2.5, 0.0, 0.0))
+    );
+}
+
+#[test]
+fn sweep_and_prune() {
+    let mut broad_phase = SweepAndPrune::new(0.1);
+    let mut aabbs = [
+        cube(0.0, 0.0, 0.0),
+        cube(1.1, 0.0, 0.0),
+        cube(0.0, 5.0, 0.0),
+        cube(10.0, 0.0, 0.0),
+    ];
+    for (id, aabb) in aabbs.iter().enumerate() {
+        broad_phase.insert(id, aabb);
+    }
+    assert_eq!(4, broad_phase.len());
+
+    let mut pairs = Vec::new();
+    broad_phase.potential_pairs(&mut pairs);
+    assert_eq!(vec![(0, 1)], pairs);
+
+    // Small motions stay within the fat bounding boxes.
+    assert!(!broad_phase.update(0, &cube(0.05, 0.0, 0.0)));
+    assert!(broad_phase.update(3, &cube(0.5, 4.5, 0.0)));
+    aabbs[3] = cube(0.5, 4.5, 0.0);
+    broad_phase.potential_pairs(&mut pairs);
+    assert_eq!(vec![(0, 1), (2, 3)], pairs);
+    assert_eq!(Some(aabbs[3].loosened(0.1)), broad_phase.fat_aabb(3));
+
+    broad_phase.remove(1);
+    assert_eq!(None, broad_phase.fat_aabb(1));
+    broad_phase.potential_pairs(&mut pairs);
+    assert_eq!(vec![(2, 3)], pairs);
+}
+
+#[test]
+fn sweep_and_prune_matches_brute_force() {
+    let mut seed = 7u64;
+    let mut next = || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    let mut broad_phase = SweepAndPrune::with_axis(0.0, 1);
+    let mut aabbs: Vec<_> = (0..100)
+        .map(|_| cube(next() * 10.0, next() * 10.0, next() * 10.0))
+        .collect();
+    for (id, aabb) in aabbs.iter().enumerate() {
+        broad_phase.insert(id, aabb);
+    }
+
+    let mut pairs = Vec::new();
+    for _ in 0..5 {
+        for (id, aabb) in aabbs.iter_mut().enumerate() {
+            *aabb = cube(
+                aabb.center().x + next() - 0.5,
+                aabb.center().y + next() - 0.5,
+                aabb.center().z + next() - 0.5,
+            );
+            broad_phase.update(id, aabb);
+        }
+        broad_phase.potential_pairs(&mut pairs);
+        assert_eq!(brute_force(&aabbs, 0.0), pairs);
+    }
+}
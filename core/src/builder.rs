@@ -0,0 +1,231 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::gravity::{GravityField, GravitySource};
+use crate::joint::{Joint, JointKind};
+use crate::material::PhysicsMaterial;
+use crate::plane::Plane;
+use crate::rigid_body::{AxisLocks, BodyType};
+use crate::scene::{SceneBody, SceneCollider};
+use crate::shape::Shape;
+use crate::world::{World, WorldConfig};
+use math::{Quaternion, Vector3};
+
+/// Builds a world step by step, chaining calls, to set up scenes in a few lines.
+///
+/// # Remarks
+/// Bodies are numbered in the order they're added, starting at zero, so joints can refer
+/// to them. Every method takes the builder and returns it, following the Builder pattern.
+pub struct WorldBuilder<F: num_traits::Float = f64> {
+    world: World<F>,
+}
+
+impl<F: num_traits::Float> Default for WorldBuilder<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: num_traits::Float> WorldBuilder<F> {
+    /// Creates a new builder of an empty world with the default configuration.
+    pub fn new() -> Self {
+        Self {
+            world: World::default(),
+        }
+    }
+
+    /// Sets the configuration of the world.
+    pub fn config(mut self, config: WorldConfig<F>) -> Self {
+        self.world.config = config;
+        self
+    }
+
+    /// Sets a uniform gravity with the given acceleration.
+    pub fn gravity(mut self, acceleration: Vector3<F>) -> Self {
+        self.world.gravity = GravityField::new(GravitySource::Uniform(acceleration));
+        self
+    }
+
+    /// Sets the gravity field of the world.
+    pub fn gravity_field(mut self, gravity: GravityField<F>) -> Self {
+        self.world.gravity = gravity;
+        self
+    }
+
+    /// Adds a half-space to the scenery.
+    pub fn plane(mut self, plane: Plane<F>) -> Self {
+        self.world.planes.push(plane);
+        self
+    }
+
+    /// Sets the material the scenery planes are made of.
+    pub fn scenery_material(mut self, material: PhysicsMaterial<F>) -> Self {
+        self.world.scenery_material = material;
+        self
+    }
+
+    /// Adds a rigid body with its colliders.
+    pub fn body(mut self, body: BodyBuilder<F>) -> Self {
+        body.add_to(&mut self.world);
+        self
+    }
+
+    /// Adds a joint between the bodies with the given indices, or between a body and the
+    /// world when the second one is `None`.
+    pub fn joint(mut self, bodies: (usize, Option<usize>), kind: JointKind<F>) -> Self {
+        self.world.add_joint(Joint::new(bodies, kind));
+        self
+    }
+
+    /// Returns the world built.
+    pub fn build(self) -> World<F> {
+        self.world
+    }
+}
+
+/// Builds a rigid body along with its colliders, chaining calls.
+///
+/// # Remarks
+/// Colliders are made of the material set last before adding them, the default one at
+/// first. Dynamic bodies get the mass of their colliders from their densities, unless given
+/// a mass, which is then distributed like the one of the colliders. Every method takes the
+/// builder and returns it, following the Builder pattern.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BodyBuilder<F: num_traits::Float = f64> {
+    body: SceneBody<F>,
+    materials: Vec<PhysicsMaterial<F>>,
+    material: PhysicsMaterial<F>,
+}
+
+impl<F: num_traits::Float> BodyBuilder<F> {
+    /// Creates a new builder of a body of the given type, at the origin.
+    pub fn new(body_type: BodyType) -> Self {
+        Self {
+            body: SceneBody {
+                body_type,
+                ..SceneBody::default()
+            },
+            materials: Vec::new(),
+            material: PhysicsMaterial::default(),
+        }
+    }
+
+    /// Creates a new builder of a dynamic body.
+    pub fn dynamic() -> Self {
+        Self::new(BodyType::Dynamic)
+    }
+
+    /// Creates a new builder of a kinematic body.
+    pub fn kinematic() -> Self {
+        Self::new(BodyType::Kinematic)
+    }
+
+    /// Creates a new builder of a static body.
+    pub fn static_body() -> Self {
+        Self::new(BodyType::Static)
+    }
+
+    /// Sets the position of the body in world space.
+    pub fn position(mut self, position: Vector3<F>) -> Self {
+        self.body.position = position;
+        self
+    }
+
+    /// Sets the orientation of the body in world space.
+    pub fn orientation(mut self, orientation: Quaternion<F>) -> Self {
+        self.body.orientation = orientation;
+        self
+    }
+
+    /// Sets the linear velocity of the body.
+    pub fn velocity(mut self, velocity: Vector3<F>) -> Self {
+        self.body.velocity = velocity;
+        self
+    }
+
+    /// Sets the angular velocity of the body.
+    pub fn rotation(mut self, rotation: Vector3<F>) -> Self {
+        self.body.rotation = rotation;
+        self
+    }
+
+    /// Sets the mass of the body.
+    pub fn mass(mut self, mass: F) -> Self {
+        self.body.mass = Some(mass);
+        self
+    }
+
+    /// Sets the linear and angular damping of the body, instead of the ones of the world.
+    pub fn damping(mut self, linear: F, angular: F) -> Self {
+        self.body.linear_damping = Some(linear);
+        self.body.angular_damping = Some(angular);
+        self
+    }
+
+    /// Sets the multiplier of the gravity pulling the body.
+    pub fn gravity_scale(mut self, scale: F) -> Self {
+        self.body.gravity_scale = scale;
+        self
+    }
+
+    /// Sets the dominance of the body over the ones it touches.
+    pub fn dominance(mut self, dominance: i8) -> Self {
+        self.body.dominance = dominance;
+        self
+    }
+
+    /// Sets the axes of world space the body can't move along, or turn around.
+    pub fn locked_axes(mut self, locks: AxisLocks) -> Self {
+        self.body.locked_axes = locks;
+        self
+    }
+
+    /// Sets the material of the colliders added from now on.
+    pub fn material(mut self, material: PhysicsMaterial<F>) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// Adds a collider with the given shape at the origin of the body.
+    pub fn collider(self, shape: Shape<F>) -> Self {
+        self.collider_with(SceneCollider::new(shape))
+    }
+
+    /// Adds a collider with the given shape, placed in the local space of the body.
+    pub fn collider_at(
+        self,
+        shape: Shape<F>,
+        position: Vector3<F>,
+        orientation: Quaternion<F>,
+    ) -> Self {
+        self.collider_with(SceneCollider {
+            position,
+            orientation,
+            ..SceneCollider::new(shape)
+        })
+    }
+
+    /// Adds a sensor with the given shape at the origin of the body.
+    pub fn sensor(self, shape: Shape<F>) -> Self {
+        self.collider_with(SceneCollider {
+            sensor: true,
+            ..SceneCollider::new(shape)
+        })
+    }
+
+    /// Adds a collider with the settings of the given description. Its material name is
+    /// ignored, and the material of the builder is used instead.
+    pub fn collider_with(mut self, collider: SceneCollider<F>) -> Self {
+        self.body.colliders.push(collider);
+        self.materials.push(self.material);
+        self
+    }
+
+    /// Adds the body and its colliders to the world, returning the index of the body.
+    pub fn add_to(self, world: &mut World<F>) -> usize {
+        self.body.add_to(world, &self.materials).0
+    }
+}
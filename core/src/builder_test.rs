@@ -0,0 +1,87 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ball_joint::BallJoint;
+use crate::builder::*;
+use crate::joint::JointKind;
+use crate::material::PhysicsMaterial;
+use crate::plane::Plane;
+use crate::rigid_body::BodyType;
+use crate::shape::Shape;
+use crate::world::World;
+use math::{Quaternion, Vector3};
+
+#[test]
+fn world_builder() {
+    let rubber = PhysicsMaterial::new(0.8, 0.9, 2.0);
+    let mut world: World = WorldBuilder::new()
+        .gravity(Vector3::new(0.0, -10.0, 0.0))
+        .plane(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0))
+        .body(
+            BodyBuilder::dynamic()
+                .position(Vector3::new(0.0, 1.0, 0.0))
+                .collider(Shape::capsule(0.5, 0.25))
+                .mass(70.0),
+        )
+        .body(
+            BodyBuilder::dynamic()
+                .position(Vector3::new(3.0, 0.5, 0.0))
+                .material(rubber)
+                .collider(Shape::cuboid(Vector3::new(0.5, 0.5, 0.5)))
+                .sensor(Shape::sphere(2.0)),
+        )
+        .body(
+            BodyBuilder::kinematic()
+                .velocity(Vector3::new(1.0, 0.0, 0.0))
+                .collider_at(
+                    Shape::sphere(0.5),
+                    Vector3::new(0.0, 5.0, 0.0),
+                    Quaternion::identity(),
+                ),
+        )
+        .joint(
+            (0, Some(1)),
+            JointKind::Ball(BallJoint::new(
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(-2.0, 0.5, 0.0),
+            )),
+        )
+        .build();
+
+    assert_eq!(3, world.bodies.len());
+    assert_eq!(4, world.colliders.len());
+    assert_eq!(1, world.joints.len());
+    assert!((world.bodies[0].mass() - 70.0).abs() < 1e-9);
+
+    // Colliders take the material set before them, and sensors add no mass.
+    assert_eq!(PhysicsMaterial::default(), world.colliders[0].material);
+    assert_eq!(rubber, world.colliders[1].material);
+    assert!(world.colliders[2].sensor);
+    assert!((world.bodies[1].mass() - 2.0).abs() < 1e-9);
+
+    assert_eq!(BodyType::Kinematic, world.bodies[2].body_type);
+    assert_eq!(Vector3::new(1.0, 0.0, 0.0), world.bodies[2].velocity);
+    assert_eq!(
+        Vector3::new(0.0, 5.0, 0.0),
+        world.colliders[3].offset.translation()
+    );
+
+    // The capsule falls onto the plane.
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    assert!(world.bodies[0].position.y < 0.9);
+
+    // Bodies can be added to existing worlds too.
+    let mut world = World::<f64>::default();
+    let body = BodyBuilder::static_body()
+        .collider(Shape::sphere(1.0))
+        .add_to(&mut world);
+    assert_eq!(0, body);
+    assert_eq!(BodyType::Static, world.bodies[0].body_type);
+    assert_eq!(1, world.colliders.len());
+}
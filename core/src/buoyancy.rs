@@ -0,0 +1,618 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::force::ForceGenerator;
+use crate::plane::Plane;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Capsule, Shape};
+use math::{Matrix4, Vector2, Vector3};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Density of water, in kilograms per cubic meter.
+pub const WATER_DENSITY: f64 = 1000.0;
+
+/// Number of segments around the axis of the polyhedron approximating a capsule.
+const CAPSULE_SEGMENTS: usize = 16;
+
+/// Number of rings of latitude of each cap of the polyhedron approximating a capsule.
+const CAPSULE_RINGS: usize = 4;
+
+/// Maximum number of iterations finding the water displaced by Gerstner waves to a position.
+const WAVE_ITERATIONS: usize = 16;
+
+/// Surface of a body of water, which can be sampled for its height and velocity at any
+/// horizontal position.
+///
+/// # Remarks
+/// Positions are given by their X and Z coordinates, and heights along the Y axis.
+pub trait WaterSurface<F: num_traits::Float = f64> {
+    /// Returns the height of the water at the given horizontal position.
+    fn height(&self, x: F, z: F) -> F;
+
+    /// Returns the velocity of the water on the surface, at the given horizontal position.
+    fn velocity(&self, _x: F, _z: F) -> Vector3<F> {
+        Vector3::origin()
+    }
+
+    /// Returns the plane best fitting the surface over the given box, with its normal pointing
+    /// out of the water.
+    ///
+    /// # Remarks
+    /// The surface is sampled at the center of the box and at the corners of its horizontal
+    /// extent, and the plane is fitted to them with least squares, so it tilts with the waves
+    /// passing under large objects.
+    fn fit_plane(&self, bounds: &Aabb<F>) -> Plane<F> {
+        let half = math::real::<F>(0.5);
+        let center = bounds.min.vector_add(&bounds.max).scalar_mul(half);
+        let extent = bounds.max.vector_sub(&bounds.min).scalar_mul(half);
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)];
+        let mut height = self.height(center.x, center.z);
+        let (mut slope_x, mut slope_z) = (F::zero(), F::zero());
+        for (sign_x, sign_z) in corners.iter() {
+            let dx = extent.x * math::real(*sign_x);
+            let dz = extent.z * math::real(*sign_z);
+            let sample = self.height(center.x + dx, center.z + dz);
+            height = height + sample;
+            slope_x = slope_x + sample * dx;
+            slope_z = slope_z + sample * dz;
+        }
+        let four = math::real::<F>(4.0);
+        let slope = |moment: F, extent: F| {
+            if extent > F::zero() {
+                moment / (four * extent * extent)
+            } else {
+                F::zero()
+            }
+        };
+        let normal = Vector3::new(
+            -slope(slope_x, extent.x),
+            F::one(),
+            -slope(slope_z, extent.z),
+        );
+        let point = Vector3::new(center.x, height / math::real(5.0), center.z);
+        Plane::from_point(normal.normalize(), &point)
+    }
+}
+
+/// Flat water, whose height is measured along the Y axis, so it shouldn't be vertical.
+impl<F: num_traits::Float> WaterSurface<F> for Plane<F> {
+    fn height(&self, x: F, z: F) -> F {
+        (self.offset - self.normal.x * x - self.normal.z * z) / self.normal.y
+    }
+
+    fn fit_plane(&self, _bounds: &Aabb<F>) -> Plane<F> {
+        *self
+    }
+}
+
+/// Shared water surfaces can be sampled while the caller keeps a handle to them, allowing them
+/// to move between frames.
+impl<F: num_traits::Float, W: WaterSurface<F>> WaterSurface<F> for Rc<RefCell<W>> {
+    fn height(&self, x: F, z: F) -> F {
+        self.borrow().height(x, z)
+    }
+
+    fn velocity(&self, x: F, z: F) -> Vector3<F> {
+        self.borrow().velocity(x, z)
+    }
+
+    fn fit_plane(&self, bounds: &Aabb<F>) -> Plane<F> {
+        self.borrow().fit_plane(bounds)
+    }
+}
+
+/// Wave travelling over deep water, moving the water around in circles.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GerstnerWave<F: num_traits::Float = f64> {
+    /// Direction the wave travels towards, of unit length, with its `y` along the Z axis.
+    pub direction: Vector2<F>,
+
+    /// Height of the crests over the rest level of the water.
+    pub amplitude: F,
+
+    /// Distance between crests.
+    pub wavelength: F,
+
+    /// How much the water bunches up under the crests, from `0` for a sine wave to `1` for
+    /// the sharpest crests.
+    pub steepness: F,
+}
+
+impl<F: num_traits::Float> GerstnerWave<F> {
+    /// Creates a new wave travelling towards the given direction.
+    pub fn new(direction: Vector2<F>, amplitude: F, wavelength: F, steepness: F) -> Self {
+        Self {
+            direction: direction.normalize(),
+            amplitude,
+            wavelength,
+            steepness,
+        }
+    }
+
+    /// Returns the wave number, frequency and phase of the wave for the water at rest at the
+    /// given horizontal position, under the given magnitude of gravity and at the given time.
+    fn phase(&self, x: F, z: F, gravity: F, time: F) -> (F, F, F) {
+        let number = math::real::<F>(std::f64::consts::TAU) / self.wavelength;
+        let frequency = (gravity * number).sqrt();
+        let phase = number * (self.direction.x * x + self.direction.y * z) - frequency * time;
+        (number, frequency, phase)
+    }
+}
+
+/// Surface of deep water moved by a sum of Gerstner waves.
+///
+/// # Remarks
+/// Each wave moves the water at rest at a horizontal position `p` in a circle, to
+/// `p + d·(s/k)·cos(θ)` and height `A·sin(θ)`, with `θ = k·(d·p) - ω·t`, wave number
+/// `k = 2π/λ` and frequency `ω = √(g·k)`. The water at rest displaced to a sampled position
+/// is found iteratively, while the steepness of all the waves adds up to less than one;
+/// beyond that the crests fold over.
+///
+/// Advance its time once per frame, sharing it with every body floating in it.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GerstnerWaves<F: num_traits::Float = f64> {
+    /// Waves adding up to the surface.
+    pub waves: Vec<GerstnerWave<F>>,
+
+    /// Height of the water at rest.
+    pub level: F,
+
+    /// Magnitude of the acceleration due to gravity, setting the speed of the waves.
+    pub gravity: F,
+
+    /// Time the waves are sampled at.
+    pub time: F,
+}
+
+impl<F: num_traits::Float> GerstnerWaves<F> {
+    /// Creates a new calm surface at the given rest level, under the given magnitude of gravity.
+    pub fn new(level: F, gravity: F) -> Self {
+        Self {
+            waves: Vec::new(),
+            level,
+            gravity,
+            time: F::zero(),
+        }
+    }
+
+    /// Adds a wave to the surface.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the surface.
+    pub fn add(&mut self, wave: GerstnerWave<F>) -> &mut Self {
+        self.waves.push(wave);
+        self
+    }
+
+    /// Moves the time of the waves forward.
+    pub fn advance(&mut self, duration: F) {
+        self.time = self.time + duration;
+    }
+
+    /// Returns the displacement and velocity of the water at rest at the given horizontal
+    /// position.
+    pub fn displacement(&self, x: F, z: F) -> (Vector3<F>, Vector3<F>) {
+        let tau = math::real::<F>(std::f64::consts::TAU);
+        self.waves.iter().fold(
+            (Vector3::origin(), Vector3::origin()),
+            |(offset, velocity), wave| {
+                let number = tau / wave.wavelength;
+                let frequency = (self.gravity * number).sqrt();
+                let phase =
+                    number * (wave.direction.x * x + wave.direction.y * z) - frequency * self.time;
+                let (sin, cos) = phase.sin_cos();
+                let reach = wave.steepness / number;
+                let across = |scale: F, up: F| {
+                    Vector3::new(wave.direction.x * scale, up, wave.direction.y * scale)
+                };
+                (
+                    offset.vector_add(&across(reach * cos, wave.amplitude * sin)),
+                    velocity.vector_add(&across(
+                        reach * frequency * sin,
+                        -wave.amplitude * frequency * cos,
+                    )),
+                )
+            },
+        )
+    }
+
+    /// Returns the horizontal position of the water at rest displaced to the given one,
+    /// found with Newton's method.
+    fn rest_position(&self, x: F, z: F) -> (F, F) {
+        let (mut rest_x, mut rest_z) = (x, z);
+        for _ in 0..WAVE_ITERATIONS {
+            let (offset, _) = self.displacement(rest_x, rest_z);
+            let (error_x, error_z) = (x - rest_x - offset.x, z - rest_z - offset.z);
+            if error_x.abs() + error_z.abs() <= F::epsilon() * (F::one() + x.abs() + z.abs()) {
+                break;
+            }
+
+            // Jacobian of the horizontal displacement, `I - Σ s·sin(θ)·d·dᵀ`.
+            let (mut xx, mut xz, mut zz) = (F::one(), F::zero(), F::one());
+            for wave in self.waves.iter() {
+                let (_, _, phase) = wave.phase(rest_x, rest_z, self.gravity, self.time);
+                let scale = wave.steepness * phase.sin();
+                xx = xx - scale * wave.direction.x * wave.direction.x;
+                xz = xz - scale * wave.direction.x * wave.direction.y;
+                zz = zz - scale * wave.direction.y * wave.direction.y;
+            }
+            let determinant = xx * zz - xz * xz;
+            if determinant <= F::epsilon() {
+                break;
+            }
+            rest_x = rest_x + (zz * error_x - xz * error_z) / determinant;
+            rest_z = rest_z + (xx * error_z - xz * error_x) / determinant;
+        }
+        (rest_x, rest_z)
+    }
+}
+
+impl<F: num_traits::Float> WaterSurface<F> for GerstnerWaves<F> {
+    fn height(&self, x: F, z: F) -> F {
+        let (rest_x, rest_z) = self.rest_position(x, z);
+        self.level + self.displacement(rest_x, rest_z).0.y
+    }
+
+    fn velocity(&self, x: F, z: F) -> Vector3<F> {
+        let (rest_x, rest_z) = self.rest_position(x, z);
+        self.displacement(rest_x, rest_z).1
+    }
+}
+
+/// Returns the volume of a shape placed with the given transform below the surface of a
+/// liquid, along with the center of buoyancy, the centroid of that volume.
+///
+/// # Remarks
+/// The normal of the surface points out of the liquid. The volumes of spheres, boxes and
+/// convex hulls are exact, while capsules are approximated with a polyhedron, whose volume
+/// is scaled to that of the capsule. Shapes above the surface return a zero volume, and
+/// their position as the center of buoyancy.
+pub fn submerged_volume<F: num_traits::Float>(
+    shape: &Shape<F>,
+    transform: &Matrix4<F>,
+    surface: &Plane<F>,
+) -> (F, Vector3<F>) {
+    let (volume, moment) = submerged_moments(shape, transform, surface);
+    if volume <= F::zero() {
+        return (F::zero(), transform.translation());
+    }
+    (volume, moment.scalar_div(volume))
+}
+
+/// Returns the submerged volume of a shape, and its first moment of volume.
+fn submerged_moments<F: num_traits::Float>(
+    shape: &Shape<F>,
+    transform: &Matrix4<F>,
+    surface: &Plane<F>,
+) -> (F, Vector3<F>) {
+    match shape {
+        Shape::Sphere(sphere) => {
+            // Spherical cap below the surface.
+            let radius = sphere.radius;
+            let center = transform.translation();
+            let depth = (radius - surface.signed_distance(&center))
+                .max(F::zero())
+                .min(radius + radius);
+            if depth <= F::zero() {
+                return (F::zero(), Vector3::origin());
+            }
+            let three = math::real::<F>(3.0);
+            let volume =
+                math::real::<F>(std::f64::consts::PI) * depth * depth * (three * radius - depth)
+                    / three;
+            let rest = radius + radius - depth;
+            let drop = three * rest * rest / (math::real::<F>(4.0) * (three * radius - depth));
+            let centroid = center.vector_sub(&surface.normal.scalar_mul(drop));
+            (volume, centroid.scalar_mul(volume))
+        }
+        Shape::Cuboid(cuboid) => {
+            let vertices = cuboid.vertices().map(|vertex| transform.transform(&vertex));
+            let faces = [
+                [0, 1, 3, 2],
+                [4, 6, 7, 5],
+                [0, 4, 5, 1],
+                [2, 3, 7, 6],
+                [0, 2, 6, 4],
+                [1, 5, 7, 3],
+            ];
+            let polygons = faces
+                .iter()
+                .map(|face| face.iter().map(|index| vertices[*index]).collect());
+            clipped_moments(polygons, &transform.translation(), surface)
+        }
+        Shape::ConvexHull(hull) => {
+            let vertices: Vec<Vector3<F>> = hull
+                .vertices
+                .iter()
+                .map(|vertex| transform.transform(vertex))
+                .collect();
+            let polygons = hull
+                .faces
+                .iter()
+                .map(|face| face.vertices.iter().map(|index| vertices[*index]).collect());
+            clipped_moments(polygons, &transform.translation(), surface)
+        }
+        Shape::Capsule(capsule) => {
+            let (vertices, triangles) = tessellate(capsule);
+            let vertices: Vec<Vector3<F>> = vertices
+                .iter()
+                .map(|vertex| transform.transform(vertex))
+                .collect();
+            let polygons = |triangles: &Vec<[usize; 3]>| {
+                triangles
+                    .iter()
+                    .map(|triangle| triangle.iter().map(|index| vertices[*index]).collect())
+                    .collect::<Vec<Vec<Vector3<F>>>>()
+            };
+            let center = transform.translation();
+            let (volume, moment) = clipped_moments(polygons(&triangles), &center, surface);
+
+            // Scale the volume of the polyhedron to the volume of the capsule.
+            let reach = capsule.half_height + capsule.radius + capsule.radius;
+            let above = center.vector_add(&surface.normal.scalar_mul(reach));
+            let whole = Plane::from_point(surface.normal, &above);
+            let (total, _) = clipped_moments(polygons(&triangles), &center, &whole);
+            let scale = capsule.volume() / total;
+            (volume * scale, moment.scalar_mul(scale))
+        }
+        // Triangle meshes are hollow, so they displace no liquid.
+        Shape::TriMesh(_) => (F::zero(), Vector3::origin()),
+        Shape::Compound(compound) => compound.children.iter().fold(
+            (F::zero(), Vector3::origin()),
+            |(volume, moment), child| {
+                let placement = transform.matrix_mul(&child.offset);
+                let (child_volume, child_moment) =
+                    submerged_moments(&child.shape, &placement, surface);
+                (volume + child_volume, moment.vector_add(&child_moment))
+            },
+        ),
+    }
+}
+
+/// Clips the faces of a convex polyhedron, in counter-clockwise order seen from outside, to
+/// the part below the surface, returning the volume and first moment of volume of the result.
+///
+/// # Remarks
+/// The clipped faces are split into tetrahedra with their apex on the surface, right above the
+/// given point, so the face closing the clipped polyhedron along the surface adds nothing.
+fn clipped_moments<F: num_traits::Float, I: IntoIterator<Item = Vec<Vector3<F>>>>(
+    polygons: I,
+    point: &Vector3<F>,
+    surface: &Plane<F>,
+) -> (F, Vector3<F>) {
+    let apex = point.vector_sub(&surface.normal.scalar_mul(surface.signed_distance(point)));
+    let sixth = math::real::<F>(1.0 / 6.0);
+    let quarter = math::real::<F>(0.25);
+    let mut volume = F::zero();
+    let mut moment = Vector3::origin();
+    let mut clipped = Vec::new();
+    for polygon in polygons {
+        clipped.clear();
+        for (index, current) in polygon.iter().enumerate() {
+            let next = &polygon[(index + 1) % polygon.len()];
+            let current_distance = surface.signed_distance(current);
+            let next_distance = surface.signed_distance(next);
+            if current_distance <= F::zero() {
+                clipped.push(*current);
+            }
+            if (current_distance <= F::zero()) != (next_distance <= F::zero()) {
+                let t = current_distance / (current_distance - next_distance);
+                clipped.push(current.vector_add(&next.vector_sub(current).scalar_mul(t)));
+            }
+        }
+
+        for index in 1..clipped.len().saturating_sub(1) {
+            let (a, b, c) = (clipped[0], clipped[index], clipped[index + 1]);
+            let tetrahedron = a
+                .vector_sub(&apex)
+                .dot_product(&b.vector_sub(&apex).cross_product(&c.vector_sub(&apex)))
+                * sixth;
+            let centroid = apex.vector_add(&a).vector_add(&b).vector_add(&c);
+            volume = volume + tetrahedron;
+            moment.inplace_vector_add(&centroid.scalar_mul(tetrahedron * quarter));
+        }
+    }
+    (volume, moment)
+}
+
+/// Approximates a capsule with a polyhedron, returning its vertices and outward triangles.
+fn tessellate<F: num_traits::Float>(capsule: &Capsule<F>) -> (Vec<Vector3<F>>, Vec<[usize; 3]>) {
+    let radius = capsule.radius;
+    let mut vertices = vec![Vector3::new(
+        F::zero(),
+        capsule.half_height + radius,
+        F::zero(),
+    )];
+
+    // Rings from the top pole to the bottom one, two of them around the ends of the segment.
+    let rings = CAPSULE_RINGS * 2;
+    for ring in 1..=rings {
+        let (latitude, height) = if ring <= CAPSULE_RINGS {
+            (ring, capsule.half_height)
+        } else {
+            (ring - 1, -capsule.half_height)
+        };
+        let angle =
+            math::real::<F>(std::f64::consts::FRAC_PI_2 * latitude as f64 / CAPSULE_RINGS as f64);
+        for segment in 0..CAPSULE_SEGMENTS {
+            let around =
+                math::real::<F>(std::f64::consts::TAU * segment as f64 / CAPSULE_SEGMENTS as f64);
+            let ring_radius = radius * angle.sin();
+            vertices.push(Vector3::new(
+                ring_radius * around.cos(),
+                height + radius * angle.cos(),
+                -ring_radius * around.sin(),
+            ));
+        }
+    }
+    vertices.push(Vector3::new(
+        F::zero(),
+        -capsule.half_height - radius,
+        F::zero(),
+    ));
+
+    let bottom = vertices.len() - 1;
+    let at = |ring: usize, segment: usize| {
+        1 + (ring - 1) * CAPSULE_SEGMENTS + segment % CAPSULE_SEGMENTS
+    };
+    let mut triangles = Vec::new();
+    for segment in 0..CAPSULE_SEGMENTS {
+        triangles.push([0, at(1, segment), at(1, segment + 1)]);
+        for ring in 1..rings {
+            triangles.push([
+                at(ring, segment),
+                at(ring + 1, segment),
+                at(ring + 1, segment + 1),
+            ]);
+            triangles.push([
+                at(ring, segment),
+                at(ring + 1, segment + 1),
+                at(ring, segment + 1),
+            ]);
+        }
+        triangles.push([at(rings, segment), bottom, at(rings, segment + 1)]);
+    }
+    (vertices, triangles)
+}
+
+/// Force generator that makes a rigid body float in a liquid, slowing it down as it moves
+/// through it.
+///
+/// # Remarks
+/// The shape of the body, placed with its offset, is clipped to the surface of the liquid,
+/// and the weight of the liquid it displaces pushes it up from the center of buoyancy.
+/// Hydrodynamic drag opposes the flow at the center of buoyancy, with a coefficient along
+/// each axis of the body, scaled by the submerged fraction of the shape. Lift acts across
+/// the flow, from the surfaces facing each axis of the body, like a keel or a hydrofoil, and
+/// grows with the square of the speed and the sine of twice the angle the flow hits them at.
+///
+/// The surface is flat by default, and can be any `WaterSurface`, like waves shared through
+/// a handle. Waves are approximated by the plane fitted to them under the shape, so the body
+/// bobs and pitches with them, and the drag opposes its flow relative to the moving water.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Buoyancy<F: num_traits::Float = f64, W = Plane<F>> {
+    /// Shape of the body displacing the liquid.
+    pub shape: Shape<F>,
+
+    /// Placement of the shape in body space.
+    pub offset: Matrix4<F>,
+
+    /// Surface of the liquid.
+    pub surface: W,
+
+    /// Density of the liquid.
+    pub liquid_density: F,
+
+    /// Acceleration due to gravity.
+    pub gravity: Vector3<F>,
+
+    /// Drag coefficient times the reference area, along each axis in body space.
+    pub drag: Vector3<F>,
+
+    /// Lift coefficient times the area of the surfaces facing each axis in body space.
+    pub lift: Vector3<F>,
+
+    /// Coefficient of the drag growing linearly with the speed of the flow, damping slow
+    /// motions like bobbing on the surface.
+    pub linear_drag: F,
+
+    /// Coefficient of the torque opposing the rotation of the body.
+    pub angular_drag: F,
+
+    /// Velocity of the liquid, added to the one of its surface.
+    pub current: Vector3<F>,
+}
+
+impl<F: num_traits::Float, W: WaterSurface<F>> Buoyancy<F, W> {
+    /// Creates a new buoyancy generator for a shape centered on the body, in water without
+    /// currents and without drag or lift.
+    pub fn new(shape: Shape<F>, surface: W, gravity: Vector3<F>) -> Self {
+        Self {
+            shape,
+            offset: Matrix4::identity(),
+            surface,
+            liquid_density: math::real(WATER_DENSITY),
+            gravity,
+            drag: Vector3::origin(),
+            lift: Vector3::origin(),
+            linear_drag: F::zero(),
+            angular_drag: F::zero(),
+            current: Vector3::origin(),
+        }
+    }
+
+    /// Returns the submerged volume of the given body and its center of buoyancy.
+    pub fn submerged(&self, body: &RigidBody<F>) -> (F, Vector3<F>) {
+        let transform = body.transform_matrix.matrix_mul(&self.offset);
+        let surface = self.surface.fit_plane(&self.shape.aabb(&transform));
+        submerged_volume(&self.shape, &transform, &surface)
+    }
+}
+
+impl<F: num_traits::Float, W: WaterSurface<F>> ForceGenerator<F> for Buoyancy<F, W> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, _duration: F) {
+        let body = &mut bodies[body];
+
+        // Check that the body doesn't have infinite mass, and is not sleeping.
+        if !body.has_finite_mass() || !body.is_awake {
+            return;
+        }
+
+        let (volume, center) = self.submerged(body);
+        if volume <= F::zero() {
+            return;
+        }
+
+        let buoyancy = self.gravity.scalar_mul(-self.liquid_density * volume);
+        body.add_force_at_point(&buoyancy, &center);
+
+        // Drag grows with the speed of the flow and its square, and the submerged fraction.
+        let fraction = (volume / self.shape.volume()).min(F::one());
+        let water = self
+            .surface
+            .velocity(center.x, center.z)
+            .vector_add(&self.current);
+        let flow = body.velocity_at_point(&center).vector_sub(&water);
+        let local = body.direction_in_local_space(&flow);
+        let scale = -math::real::<F>(0.5) * self.liquid_density * fraction * flow.magnitude();
+        let drag = Vector3::new(
+            self.drag.x * local.x * scale,
+            self.drag.y * local.y * scale,
+            self.drag.z * local.z * scale,
+        );
+        let drag = body
+            .direction_in_world_space(&drag)
+            .vector_sub(&flow.scalar_mul(self.linear_drag * fraction));
+        body.add_force_at_point(&drag, &center);
+
+        // Lift pushes every surface across the flow, along its normal without the part
+        // along the flow.
+        let speed = flow.magnitude();
+        if speed > F::zero() {
+            let along = flow.scalar_div(speed);
+            let axes = [
+                (Vector3::new(F::one(), F::zero(), F::zero()), self.lift.x),
+                (Vector3::new(F::zero(), F::one(), F::zero()), self.lift.y),
+                (Vector3::new(F::zero(), F::zero(), F::one()), self.lift.z),
+            ];
+            let lift = axes
+                .iter()
+                .fold(Vector3::origin(), |lift, (axis, coefficient)| {
+                    let normal = body.direction_in_world_space(axis);
+                    let across = normal.vector_sub(&along.scalar_mul(normal.dot_product(&along)));
+                    lift.vector_add(&across.scalar_mul(*coefficient * flow.dot_product(&normal)))
+                });
+            body.add_force_at_point(&lift.scalar_mul(scale), &center);
+        }
+
+        let torque = body.rotation.scalar_mul(-self.angular_drag * fraction);
+        body.add_torque(&torque);
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::buoyancy::*;
+use crate::force::{ForceGenerator, Gravity};
+use crate::plane::Plane;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Capsule, Compound, ConvexHull, Cuboid, Shape, Sphere};
+use crate::world::{World, WorldConfig};
+use math::{Matrix3, Matrix4, Quaternion, Vector2, Vector3};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn placed(position: Vector3<f64>, orientation: Quaternion<f64>) -> Matrix4<f64> {
+    Matrix4::from_orientation_and_position(&orientation, &position)
+}
+
+#[test]
+fn submerged_volumes() {
+    let surface = Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.25);
+    let upright = Quaternion::identity();
+
+    // Half a sphere, with the center of buoyancy three eighths of the radius below its center.
+    let sphere = Shape::Sphere(Sphere::new(2.0));
+    let (volume, center) = submerged_volume(
+        &sphere,
+        &placed(Vector3::new(1.0, 0.25, 0.0), upright),
+        &surface,
+    );
+    assert!((volume - sphere.volume() / 2.0).abs() < 1e-9);
+    assert!((center - Vector3::new(1.0, -0.5, 0.0)).magnitude() < 1e-9);
+    let (volume, center) = submerged_volume(
+        &sphere,
+        &placed(Vector3::new(0.0, 3.0, 0.0), upright),
+        &surface,
+    );
+    assert_eq!((0.0, Vector3::new(0.0, 3.0, 0.0)), (volume, center));
+
+    // Three quarters of a box.
+    let cuboid = Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 0.5, 1.0)));
+    let (volume, center) = submerged_volume(&cuboid, &Matrix4::identity(), &surface);
+    assert!((volume - 3.0).abs() < 1e-9);
+    assert!((center - Vector3::new(0.0, -0.125, 0.0)).magnitude() < 1e-9);
+
+    // Tilted boxes displace as much as hulls with the same vertices.
+    let tilted = placed(
+        Vector3::new(0.5, 0.1, -0.2),
+        Quaternion::from_axis_angle(&Vector3::new(1.0, 2.0, 0.5).normalize(), 0.7),
+    );
+    let hull = Shape::convex_hull(
+        ConvexHull::from_points(&Cuboid::new(Vector3::new(1.0, 0.5, 1.0)).vertices()).unwrap(),
+    );
+    let (volume, center) = submerged_volume(&cuboid, &tilted, &surface);
+    let (hull_volume, hull_center) = submerged_volume(&hull, &tilted, &surface);
+    assert!(volume > 0.0 && volume < 4.0);
+    assert!((volume - hull_volume).abs() < 1e-9);
+    assert!((center - hull_center).magnitude() < 1e-9);
+
+    // Capsules lying on the surface are half submerged.
+    let capsule = Shape::Capsule(Capsule::new(1.0, 0.5));
+    let lying = placed(
+        Vector3::new(0.0, 0.25, 0.0),
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2),
+    );
+    let (volume, center) = submerged_volume(&capsule, &lying, &surface);
+    assert!((volume - capsule.volume() / 2.0).abs() < 1e-9);
+    assert!(center.y < 0.25 && center.x.abs() < 1e-9);
+    let (volume, _) = submerged_volume(
+        &capsule,
+        &placed(Vector3::new(0.0, -5.0, 0.0), upright),
+        &surface,
+    );
+    assert!((volume - capsule.volume()).abs() < 1e-9);
+
+    // Compounds add up the volumes of their children.
+    let mut compound = Compound::new();
+    compound
+        .add(
+            placed(Vector3::new(-3.0, 0.0, 0.0), upright),
+            sphere.clone(),
+        )
+        .add(Matrix4::identity(), cuboid.clone());
+    let (volume, _) = submerged_volume(&Shape::compound(compound), &Matrix4::identity(), &surface);
+    let cap = std::f64::consts::PI * 2.25 * 2.25 * (6.0 - 2.25) / 3.0;
+    assert!((volume - 3.0 - cap).abs() < 1e-9);
+}
+
+#[test]
+fn floating() {
+    let mut world = World::<f64>::new(WorldConfig {
+        linear_damping: 1.0,
+        angular_damping: 1.0,
+        ..WorldConfig::default()
+    });
+    let gravity = Vector3::new(0.0, -10.0, 0.0);
+    let surface = Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0);
+
+    // A box half as dense as water, dropped sideways with a spin.
+    let half_size = Vector3::new(1.0, 0.25, 0.5);
+    let shape = Shape::Cuboid(Cuboid::new(half_size));
+    let mass = 500.0 * shape.volume();
+    let mut body = RigidBody::new(
+        Vector3::new(0.0, 1.0, 0.0),
+        mass,
+        &Matrix3::block_inertia_tensor(&half_size, mass),
+    );
+    body.velocity = Vector3::new(3.0, 0.0, 0.0);
+    body.rotation = Vector3::new(0.0, 1.0, 0.0);
+    body.set_can_sleep(false);
+    let raft = world.add_body(body);
+    let raft = world.body_index(raft).unwrap();
+
+    let mut buoyancy = Buoyancy::new(shape, surface, gravity);
+    buoyancy.drag = Vector3::new(1.0, 2.0, 1.0);
+    buoyancy.linear_drag = 500.0;
+    buoyancy.angular_drag = 500.0;
+    world.registry.add(raft, Box::new(Gravity::new(gravity)));
+    world.registry.add(raft, Box::new(buoyancy.clone()));
+
+    for _ in 0..600 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+
+    // The raft floats half submerged, and the water stopped it.
+    let raft = &world.bodies[raft];
+    let (volume, _) = buoyancy.submerged(raft);
+    assert!((volume - 0.5).abs() < 0.01);
+    assert!(raft.position.y.abs() < 0.01);
+    assert!(raft.velocity.magnitude() < 0.05);
+    assert!(raft.rotation.magnitude() < 0.05);
+}
+
+#[test]
+fn lift() {
+    let gravity = Vector3::new(0.0, -10.0, 0.0);
+    let surface = Plane::new(Vector3::new(0.0, 1.0, 0.0), 10.0);
+    let half_size = Vector3::new(1.0, 0.05, 0.5);
+    let mut buoyancy = Buoyancy::new(Shape::Cuboid(Cuboid::new(half_size)), surface, gravity);
+    buoyancy.drag = Vector3::new(0.01, 1.0, 0.01);
+
+    // A submerged plate pitched up and pushed forward is lifted up and slowed down.
+    let mut plate = RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity());
+    plate.orientation = Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), 0.2);
+    plate.velocity = Vector3::new(5.0, 0.0, 0.0);
+    plate.calculate_derived_data();
+    let mut bodies = vec![plate];
+    buoyancy.update_force(&mut bodies, 0, 1.0 / 60.0);
+    let buoyant = 1000.0 * 10.0 * 0.2;
+    assert!(bodies[0].force_accum.y > buoyant + 1.0);
+    assert!(bodies[0].force_accum.x < 0.0);
+
+    // Without drag, the lift of the plate pushes it across the flow only.
+    buoyancy.drag = Vector3::origin();
+    buoyancy.lift = Vector3::new(0.0, 2.0, 0.0);
+    bodies[0].clear_accumulators();
+    buoyancy.update_force(&mut bodies, 0, 1.0 / 60.0);
+    let lift = bodies[0].force_accum.y - buoyant;
+    let expected = 0.5 * 1000.0 * 25.0 * 2.0 * 0.2f64.sin() * 0.2f64.cos();
+    assert!((lift - expected).abs() < 1e-6);
+    assert!(bodies[0].force_accum.x.abs() < 1e-9);
+
+    // Flow along the plate, or straight into it, gives no lift.
+    for velocity in [Vector3::new(0.0, 0.0, 5.0), Vector3::new(0.0, -5.0, 0.0)] {
+        bodies[0].orientation = Quaternion::identity();
+        bodies[0].velocity = velocity;
+        bodies[0].calculate_derived_data();
+        bodies[0].clear_accumulators();
+        buoyancy.update_force(&mut bodies, 0, 1.0 / 60.0);
+        assert!((bodies[0].force_accum.y - buoyant).abs() < 1e-6);
+        assert!(bodies[0].force_accum.x.abs() < 1e-9);
+        assert!(bodies[0].force_accum.z.abs() < 1e-9);
+    }
+}
+
+#[test]
+fn gerstner_waves() {
+    let tau = std::f64::consts::TAU;
+    let mut waves = GerstnerWaves::new(1.0, 10.0);
+    waves.add(GerstnerWave::new(Vector2::new(2.0, 0.0), 0.5, 20.0, 0.0));
+    waves.advance(1.5);
+
+    // Waves without steepness are sines travelling at their deep water speed.
+    let number = tau / 20.0;
+    let frequency = (10.0 * number).sqrt();
+    for step in 0..20 {
+        let x = step as f64 * 1.3;
+        let phase = number * x - frequency * 1.5;
+        assert!((waves.height(x, 7.0) - (1.0 + 0.5 * phase.sin())).abs() < 1e-12);
+        let rising = -0.5 * frequency * phase.cos();
+        assert!((waves.velocity(x, 7.0) - Vector3::new(0.0, rising, 0.0)).magnitude() < 1e-12);
+    }
+
+    // Steep waves sample the water displaced to each position, bunched up under the crests.
+    let mut steep = waves.clone();
+    steep.waves[0].steepness = 0.6;
+    steep.add(GerstnerWave::new(Vector2::new(1.0, 1.0), 0.2, 7.0, 0.3));
+    for step in 0..20 {
+        let rest = (step as f64 * 1.3, step as f64 * -0.7);
+        let (offset, velocity) = steep.displacement(rest.0, rest.1);
+        let (x, z) = (rest.0 + offset.x, rest.1 + offset.z);
+        assert!((steep.height(x, z) - (1.0 + offset.y)).abs() < 1e-9);
+        assert!((steep.velocity(x, z) - velocity).magnitude() < 1e-9);
+    }
+    let crest = (0..200)
+        .map(|step| steep.height(step as f64 * 0.1, 0.0))
+        .fold(f64::MIN, f64::max);
+    assert!(crest > 1.0 && crest <= 1.7 + 1e-9);
+
+    // Planes fitted to the waves tilt with them, and flat water is its own plane.
+    let bounds = Aabb::new(Vector3::new(-1.0, 0.0, -1.0), Vector3::new(1.0, 1.0, 1.0));
+    let fitted = waves.fit_plane(&bounds);
+    let slope = 0.5 * number * (-frequency * 1.5).cos();
+    assert!((fitted.normal.x / fitted.normal.y + slope).abs() < 0.01);
+    assert!(fitted.normal.z.abs() < 1e-12);
+    assert!((fitted.signed_distance(&Vector3::new(0.0, waves.height(0.0, 0.0), 0.0))).abs() < 0.01);
+    let flat = Plane::new(Vector3::new(0.0, 1.0, 0.0), 2.0);
+    assert_eq!(flat, flat.fit_plane(&bounds));
+    assert_eq!(2.0, flat.height(5.0, -3.0));
+}
+
+#[test]
+fn riding_waves() {
+    let mut world = World::new(WorldConfig {
+        linear_damping: 1.0,
+        angular_damping: 1.0,
+        ..WorldConfig::default()
+    });
+    let gravity = Vector3::new(0.0, -10.0, 0.0);
+    let mut waves = GerstnerWaves::<f64>::new(0.0, 10.0);
+    waves.add(GerstnerWave::new(Vector2::new(1.0, 0.0), 0.3, 20.0, 0.2));
+    let waves = Rc::new(RefCell::new(waves));
+
+    // A box half as dense as water, floating on a wave four times its length.
+    let half_size = Vector3::new(2.5, 0.25, 1.0);
+    let shape = Shape::Cuboid(Cuboid::new(half_size));
+    let mass = 500.0 * shape.volume();
+    let mut body = RigidBody::new(
+        Vector3::origin(),
+        mass,
+        &Matrix3::block_inertia_tensor(&half_size, mass),
+    );
+    body.set_can_sleep(false);
+    let boat = world.add_body(body);
+    let boat = world.body_index(boat).unwrap();
+    let mut buoyancy = Buoyancy::new(shape, waves.clone(), gravity);
+    buoyancy.drag = Vector3::new(1.0, 4.0, 2.0);
+    buoyancy.linear_drag = 2000.0;
+    buoyancy.angular_drag = 2000.0;
+    world.registry.add(boat, Box::new(Gravity::new(gravity)));
+    world.registry.add(boat, Box::new(buoyancy));
+
+    let (mut low, mut high, mut pitch) = (f64::MAX, f64::MIN, 0.0f64);
+    for frame in 0..1200 {
+        waves.borrow_mut().advance(1.0 / 60.0);
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+
+        // After settling, the boat rises and falls with the water under it, and pitches.
+        let body = &world.bodies[boat];
+        if frame >= 600 {
+            let water = waves.borrow().height(body.position.x, body.position.z);
+            assert!((body.position.y - water).abs() < 0.15);
+            low = low.min(body.position.y);
+            high = high.max(body.position.y);
+            let up = body.direction_in_world_space(&Vector3::new(0.0, 1.0, 0.0));
+            pitch = pitch.max(up.x.abs());
+        }
+    }
+    assert!(high - low > 0.4);
+    assert!(pitch > 0.02);
+}
@@ -0,0 +1,459 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::broad_phase::{BroadPhase, DEFAULT_BROAD_PHASE_MARGIN};
+use crate::parallel::{map_chunks, Parallel};
+use crate::ray::Ray;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Minimum number of proxies queried by every thread finding the potential pairs.
+const MIN_PROXIES_PER_THREAD: usize = 256;
+
+/// Node of the dynamic bounding volume hierarchy.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct Node<F: num_traits::Float> {
+    aabb: Aabb<F>,
+    parent: Option<usize>,
+    children: Option<[usize; 2]>,
+    proxy: usize,
+    height: usize,
+}
+
+impl<F: num_traits::Float> Node<F> {
+    fn is_leaf(&self) -> bool {
+        self.children.is_none()
+    }
+}
+
+/// Dynamic bounding volume hierarchy of axis-aligned bounding boxes.
+///
+/// # Remarks
+/// Leaves hold the fat bounding box of a proxy, and internal nodes the union
+/// of their children. New leaves are inserted next to the sibling that minimizes
+/// the growth in surface area of the tree (surface area heuristic), and the tree is
+/// kept balanced with rotations on the way back up, so queries stay logarithmic while
+/// thousands of proxies move around.
+///
+/// The same tree serves both as a broad phase producing potential pairs, and as an
+/// acceleration structure for ray and region queries.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DynamicBvh<F: num_traits::Float = f64> {
+    /// Margin by which the bounding boxes of proxies are fattened.
+    pub margin: F,
+
+    nodes: Vec<Node<F>>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    leaves: Vec<Option<usize>>,
+}
+
+impl<F: num_traits::Float> Default for DynamicBvh<F> {
+    fn default() -> Self {
+        Self::new(math::real(DEFAULT_BROAD_PHASE_MARGIN))
+    }
+}
+
+impl<F: num_traits::Float> DynamicBvh<F> {
+    /// Creates a new empty tree with the given fattening margin.
+    pub fn new(margin: F) -> Self {
+        Self {
+            margin,
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            leaves: Vec::new(),
+        }
+    }
+
+    /// Returns the number of proxies in the tree.
+    pub fn len(&self) -> usize {
+        self.leaves.iter().filter(|leaf| leaf.is_some()).count()
+    }
+
+    /// Returns true if there are no proxies in the tree.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the height of the tree. An empty tree has a height of `0`,
+    /// and a tree with a single leaf a height of `1`.
+    pub fn height(&self) -> usize {
+        self.root.map_or(0, |root| self.nodes[root].height + 1)
+    }
+
+    /// Returns the bounding box enclosing every proxy in the tree.
+    pub fn root_aabb(&self) -> Option<Aabb<F>> {
+        self.root.map(|root| self.nodes[root].aabb)
+    }
+
+    /// Sets the bounding box of a proxy without restructuring the tree,
+    /// recomputing the bounding boxes of its ancestors.
+    ///
+    /// # Remarks
+    /// Refitting is cheaper than reinserting, but the quality of the tree degrades
+    /// when proxies travel long distances. Use `update` to reinsert proxies that
+    /// leave their fat bounding box instead.
+    pub fn refit(&mut self, id: usize, aabb: &Aabb<F>) {
+        let leaf = match self.leaves.get(id).copied().flatten() {
+            Some(leaf) => leaf,
+            None => return,
+        };
+
+        self.nodes[leaf].aabb = aabb.loosened(self.margin);
+        let mut index = self.nodes[leaf].parent;
+        while let Some(node) = index {
+            let [first, second] = self.nodes[node].children.expect("internal node");
+            self.nodes[node].aabb = self.nodes[first].aabb.merge(&self.nodes[second].aabb);
+            index = self.nodes[node].parent;
+        }
+    }
+
+    /// Moves every bounding box in the tree by the opposite of the given offset, as the
+    /// origin of the world moves to it, keeping the structure of the tree.
+    pub fn shift_origin(&mut self, offset: &Vector3<F>) {
+        for node in self.nodes.iter_mut() {
+            node.aabb = Aabb::new(
+                node.aabb.min.vector_sub(offset),
+                node.aabb.max.vector_sub(offset),
+            );
+        }
+    }
+
+    /// Calls `callback` with the identifier of every proxy whose fat bounding box
+    /// overlaps the given region. The traversal stops when the callback returns false.
+    pub fn query_aabb<C: FnMut(usize) -> bool>(&self, aabb: &Aabb<F>, mut callback: C) {
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.aabb.intersects(aabb) {
+                continue;
+            }
+
+            match node.children {
+                None => {
+                    if !callback(node.proxy) {
+                        return;
+                    }
+                }
+                Some(children) => stack.extend_from_slice(&children),
+            }
+        }
+    }
+
+    /// Returns the identifiers of every proxy whose fat bounding box overlaps the given region.
+    pub fn intersecting(&self, aabb: &Aabb<F>) -> Vec<usize> {
+        let mut proxies = Vec::new();
+        self.query_aabb(aabb, |proxy| {
+            proxies.push(proxy);
+            true
+        });
+        proxies
+    }
+
+    /// Casts a ray through the tree, calling `callback` with the identifier of every
+    /// proxy whose fat bounding box is hit before the current maximum time of impact.
+    ///
+    /// # Remarks
+    /// The callback receives the proxy and the current maximum time of impact, and returns
+    /// the new maximum. Returning the time of impact of an actual hit clips the ray, so only
+    /// closer proxies are visited afterwards (closest hit search); returning the same maximum
+    /// visits every proxy along the ray, and returning a negative value stops the traversal.
+    pub fn cast_ray<C: FnMut(usize, F) -> F>(&self, ray: &Ray<F>, max_toi: F, mut callback: C) {
+        let mut max_toi = max_toi;
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.aabb.cast_ray(ray, max_toi).is_none() {
+                continue;
+            }
+
+            match node.children {
+                None => {
+                    let toi = callback(node.proxy, max_toi);
+                    if toi < num_traits::zero() {
+                        return;
+                    }
+                    max_toi = max_toi.min(toi);
+                }
+                Some(children) => stack.extend_from_slice(&children),
+            }
+        }
+    }
+
+    fn allocate(&mut self, node: Node<F>) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        let root = match self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(leaf);
+                self.nodes[leaf].parent = None;
+                return;
+            }
+        };
+
+        // Find the best sibling for the new leaf, using the surface area heuristic.
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let two: F = math::real(2.0);
+        let mut index = root;
+        while let Some([first, second]) = self.nodes[index].children {
+            let area = self.nodes[index].aabb.surface_area();
+            let combined_area = self.nodes[index].aabb.merge(&leaf_aabb).surface_area();
+
+            // Cost of creating a new parent for this node and the new leaf.
+            let cost = two * combined_area;
+
+            // Minimum cost of pushing the leaf further down the tree.
+            let inheritance_cost = two * (combined_area - area);
+            let child_cost = |child: usize| {
+                let node = &self.nodes[child];
+                let merged = node.aabb.merge(&leaf_aabb).surface_area();
+                if node.is_leaf() {
+                    merged + inheritance_cost
+                } else {
+                    merged - node.aabb.surface_area() + inheritance_cost
+                }
+            };
+            let (first_cost, second_cost) = (child_cost(first), child_cost(second));
+
+            // Descend according to the minimum cost.
+            if cost < first_cost && cost < second_cost {
+                break;
+            }
+            index = if first_cost < second_cost {
+                first
+            } else {
+                second
+            };
+        }
+
+        // Create a new parent for the sibling and the new leaf.
+        let sibling = index;
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate(Node {
+            aabb: leaf_aabb.merge(&self.nodes[sibling].aabb),
+            parent: old_parent,
+            children: Some([sibling, leaf]),
+            proxy: 0,
+            height: self.nodes[sibling].height + 1,
+        });
+        self.nodes[sibling].parent = Some(new_parent);
+        self.nodes[leaf].parent = Some(new_parent);
+        match old_parent {
+            Some(old_parent) => self.replace_child(old_parent, sibling, new_parent),
+            None => self.root = Some(new_parent),
+        }
+
+        // Walk back up the tree fixing heights and bounding boxes.
+        self.refresh_ancestors(self.nodes[leaf].parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if self.root == Some(leaf) {
+            self.root = None;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent.expect("non-root leaf has a parent");
+        let [first, second] = self.nodes[parent].children.expect("internal node");
+        let sibling = if first == leaf { second } else { first };
+        let grand_parent = self.nodes[parent].parent;
+
+        // Destroy the parent and connect the sibling to the grand parent.
+        self.nodes[sibling].parent = grand_parent;
+        self.free.push(parent);
+        match grand_parent {
+            Some(grand_parent) => {
+                self.replace_child(grand_parent, parent, sibling);
+                self.refresh_ancestors(Some(grand_parent));
+            }
+            None => self.root = Some(sibling),
+        }
+    }
+
+    fn replace_child(&mut self, parent: usize, old: usize, new: usize) {
+        if let Some(children) = self.nodes[parent].children.as_mut() {
+            if children[0] == old {
+                children[0] = new;
+            } else {
+                children[1] = new;
+            }
+        }
+    }
+
+    fn refresh(&mut self, index: usize) {
+        let [first, second] = self.nodes[index].children.expect("internal node");
+        self.nodes[index].height = 1 + self.nodes[first].height.max(self.nodes[second].height);
+        self.nodes[index].aabb = self.nodes[first].aabb.merge(&self.nodes[second].aabb);
+    }
+
+    fn refresh_ancestors(&mut self, mut index: Option<usize>) {
+        while let Some(node) = index {
+            let node = self.balance(node);
+            self.refresh(node);
+            index = self.nodes[node].parent;
+        }
+    }
+
+    /// Performs a left or right rotation if node `a` is imbalanced.
+    /// Returns the new root of the subtree.
+    fn balance(&mut self, a: usize) -> usize {
+        let [b, c] = match self.nodes[a].children {
+            Some(children) if self.nodes[a].height >= 2 => children,
+            _ => return a,
+        };
+
+        let balance = self.nodes[c].height as isize - self.nodes[b].height as isize;
+        if balance > 1 {
+            self.rotate_up(a, c, b, 1)
+        } else if balance < -1 {
+            self.rotate_up(a, b, c, 0)
+        } else {
+            a
+        }
+    }
+
+    /// Rotates the `child` of `a` up, replacing `a`, which keeps `other` as one
+    /// of its children. `slot` is the position of `child` among the children of `a`.
+    fn rotate_up(&mut self, a: usize, child: usize, other: usize, slot: usize) -> usize {
+        let [f, g] = self.nodes[child]
+            .children
+            .expect("imbalanced child is internal");
+
+        // Swap `a` and `child`.
+        let a_parent = self.nodes[a].parent;
+        self.nodes[child].parent = a_parent;
+        self.nodes[a].parent = Some(child);
+        match a_parent {
+            Some(parent) => self.replace_child(parent, a, child),
+            None => self.root = Some(child),
+        }
+
+        // The tallest grandchild stays with `child`, the other one moves to `a`.
+        let (kept, moved) = if self.nodes[f].height > self.nodes[g].height {
+            (f, g)
+        } else {
+            (g, f)
+        };
+        self.nodes[child].children = Some([a, kept]);
+        let mut a_children = [other, other];
+        a_children[slot] = moved;
+        self.nodes[a].children = Some(a_children);
+        self.nodes[moved].parent = Some(a);
+
+        self.refresh(a);
+        self.refresh(child);
+        child
+    }
+
+    /// Adds the pairs the given proxies make with the ones after them to `pairs`, unsorted.
+    fn pairs_of(&self, proxies: &[Option<usize>], first: usize, pairs: &mut Vec<(usize, usize)>) {
+        for (id, leaf) in (first..).zip(proxies.iter()) {
+            if let Some(leaf) = leaf {
+                self.query_aabb(&self.nodes[*leaf].aabb, |other| {
+                    if other > id {
+                        pairs.push((id, other));
+                    }
+                    true
+                });
+            }
+        }
+    }
+}
+
+impl<F: num_traits::Float + Parallel> DynamicBvh<F> {
+    /// Fills `pairs` with every pair of proxies whose fat bounding boxes overlap, sorted
+    /// the same way `potential_pairs` does.
+    ///
+    /// # Remarks
+    /// With the `parallel` feature, the proxies are split over multiple threads, each one
+    /// querying the tree for its own proxies.
+    pub fn potential_pairs_parallel(&self, pairs: &mut Vec<(usize, usize)>) {
+        pairs.clear();
+        let chunks = map_chunks(&self.leaves, MIN_PROXIES_PER_THREAD, |first, proxies| {
+            let mut pairs = Vec::new();
+            self.pairs_of(proxies, first, &mut pairs);
+            pairs
+        });
+        for chunk in chunks {
+            pairs.extend(chunk);
+        }
+        pairs.sort_unstable();
+    }
+}
+
+impl<F: num_traits::Float> BroadPhase<F> for DynamicBvh<F> {
+    fn insert(&mut self, id: usize, aabb: &Aabb<F>) {
+        if self.leaves.len() <= id {
+            self.leaves.resize(id + 1, None);
+        }
+
+        if let Some(leaf) = self.leaves[id] {
+            self.remove_leaf(leaf);
+            self.free.push(leaf);
+        }
+
+        let leaf = self.allocate(Node {
+            aabb: aabb.loosened(self.margin),
+            parent: None,
+            children: None,
+            proxy: id,
+            height: 0,
+        });
+        self.leaves[id] = Some(leaf);
+        self.insert_leaf(leaf);
+    }
+
+    fn update(&mut self, id: usize, aabb: &Aabb<F>) -> bool {
+        match self.leaves.get(id).copied().flatten() {
+            Some(leaf) if self.nodes[leaf].aabb.contains(aabb) => false,
+            Some(leaf) => {
+                self.remove_leaf(leaf);
+                self.nodes[leaf].aabb = aabb.loosened(self.margin);
+                self.insert_leaf(leaf);
+                true
+            }
+            None => {
+                self.insert(id, aabb);
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(leaf) = self.leaves.get_mut(id).and_then(|leaf| leaf.take()) {
+            self.remove_leaf(leaf);
+            self.free.push(leaf);
+        }
+    }
+
+    fn fat_aabb(&self, id: usize) -> Option<Aabb<F>> {
+        self.leaves
+            .get(id)
+            .copied()
+            .flatten()
+            .map(|leaf| self.nodes[leaf].aabb)
+    }
+
+    fn potential_pairs(&mut self, pairs: &mut Vec<(usize, usize)>) {
+        pairs.clear();
+        self.pairs_of(&self.leaves, 0, pairs);
+        pairs.sort_unstable();
+    }
+}
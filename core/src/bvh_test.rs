@@ -0,0 +1,153 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::broad_phase::BroadPhase;
+use crate::bvh::*;
+use crate::ray::Ray;
+use math::Vector3;
+
+fn cube(x: f64, y: f64, z: f64) -> Aabb<f64> {
+    Aabb::from_center(&Vector3::new(x, y, z), &Vector3::new(0.5, 0.5, 0.5))
+}
+
+fn random() -> impl FnMut() -> f64 {
+    let mut seed = 42u64;
+    move || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[test]
+fn general_usage() {
+    let mut tree = DynamicBvh::new(0.0);
+    assert!(tree.is_empty());
+    assert_eq!(0, tree.height());
+
+    tree.insert(0, &cube(0.0, 0.0, 0.0));
+    tree.insert(1, &cube(0.9, 0.0, 0.0));
+    tree.insert(2, &cube(5.0, 0.0, 0.0));
+    assert_eq!(3, tree.len());
+    assert_eq!(
+        Some(Aabb::new(
+            Vector3::new(-0.5, -0.5, -0.5),
+            Vector3::new(5.5, 0.5, 0.5)
+        )),
+        tree.root_aabb()
+    );
+
+    let mut pairs = Vec::new();
+    tree.potential_pairs(&mut pairs);
+    assert_eq!(vec![(0, 1)], pairs);
+
+    let mut found = tree.intersecting(&cube(5.5, 0.0, 0.0));
+    found.sort_unstable();
+    assert_eq!(vec![2], found);
+
+    // Refitting moves the proxy without restructuring.
+    tree.refit(2, &cube(1.5, 0.0, 0.0));
+    tree.potential_pairs(&mut pairs);
+    assert_eq!(vec![(0, 1), (1, 2)], pairs);
+
+    tree.remove(1);
+    assert_eq!(None, tree.fat_aabb(1));
+    tree.potential_pairs(&mut pairs);
+    assert!(pairs.is_empty());
+    tree.remove(0);
+    tree.remove(2);
+    assert!(tree.is_empty());
+}
+
+#[test]
+fn ray_queries() {
+    let mut tree = DynamicBvh::new(0.0);
+    for id in 0..10 {
+        tree.insert(id, &cube(id as f64 * 2.0, 0.0, 0.0));
+    }
+
+    // Visit every proxy along the ray.
+    let ray = Ray::new(Vector3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    let mut hits = Vec::new();
+    tree.cast_ray(&ray, 100.0, |proxy, max_toi| {
+        hits.push(proxy);
+        max_toi
+    });
+    hits.sort_unstable();
+    assert_eq!((0..10).collect::<Vec<_>>(), hits);
+
+    // Clip the ray at each hit to find the closest one.
+    let mut closest = None;
+    tree.cast_ray(&ray, 100.0, |proxy, max_toi| {
+        let toi = tree
+            .fat_aabb(proxy)
+            .unwrap()
+            .cast_ray(&ray, max_toi)
+            .unwrap();
+        closest = Some((proxy, toi));
+        toi
+    });
+    assert_eq!(Some((0, 4.5)), closest);
+
+    // Rays that miss everything.
+    let ray = Ray::new(Vector3::new(-5.0, 2.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    tree.cast_ray(&ray, 100.0, |_, _| panic!("unexpected hit"));
+}
+
+#[test]
+fn matches_brute_force() {
+    let mut next = random();
+    let mut tree = DynamicBvh::new(0.2);
+    let mut aabbs: Vec<Option<Aabb<f64>>> = (0..500)
+        .map(|_| Some(cube(next() * 30.0, next() * 30.0, next() * 30.0)))
+        .collect();
+    for (id, aabb) in aabbs.iter().enumerate() {
+        tree.insert(id, aabb.as_ref().unwrap());
+    }
+
+    let mut pairs = Vec::new();
+    for step in 0..5 {
+        for (id, aabb) in aabbs.iter_mut().enumerate() {
+            if let Some(current) = aabb {
+                if (id + step) % 50 == 0 {
+                    tree.remove(id);
+                    *aabb = None;
+                    continue;
+                }
+                let center = current.center();
+                *current = cube(
+                    center.x + next() - 0.5,
+                    center.y + next() - 0.5,
+                    center.z + next() - 0.5,
+                );
+                tree.update(id, current);
+            }
+        }
+
+        let mut expected = Vec::new();
+        for first in 0..aabbs.len() {
+            for second in first + 1..aabbs.len() {
+                if let (Some(_), Some(_)) = (aabbs[first], aabbs[second]) {
+                    let first_fat = tree.fat_aabb(first).unwrap();
+                    if first_fat.intersects(&tree.fat_aabb(second).unwrap()) {
+                        expected.push((first, second));
+                    }
+                }
+            }
+        }
+
+        tree.potential_pairs(&mut pairs);
+        assert_eq!(expected, pairs);
+        tree.potential_pairs_parallel(&mut pairs);
+        assert_eq!(expected, pairs);
+    }
+
+    // The tree stays balanced.
+    let leaves = tree.len() as f64;
+    assert!(tree.height() as f64 <= 2.0 * leaves.log2() + 2.0);
+}
@@ -0,0 +1,391 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::pbd::{derive_velocities, predict, project_distance};
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Default number of times the constraints of a cloth are projected every frame.
+pub const DEFAULT_CLOTH_ITERATIONS: usize = 8;
+
+/// Default stiffness of the structural constraints of a cloth.
+pub const DEFAULT_STRUCTURAL_STIFFNESS: f64 = 1.0;
+
+/// Default stiffness of the shear constraints of a cloth.
+pub const DEFAULT_SHEAR_STIFFNESS: f64 = 0.5;
+
+/// Default stiffness of the bend constraints of a cloth.
+pub const DEFAULT_BEND_STIFFNESS: f64 = 0.1;
+
+/// Default aerodynamic drag coefficient of a cloth, in mass per unit of area and time.
+pub const DEFAULT_CLOTH_DRAG: f64 = 1.0;
+
+/// Role of a distance constraint in the grid of a cloth.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ClothConstraintKind {
+    /// Links neighbours along the rows and columns, keeping the cloth from stretching.
+    Structural,
+
+    /// Links diagonal neighbours, keeping the cells from shearing.
+    Shear,
+
+    /// Links every other particle along the rows and columns, keeping the cloth from folding.
+    Bend,
+}
+
+/// Distance constraint between a pair of particles of a cloth.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ClothConstraint<F: num_traits::Float = f64> {
+    /// Indices of the pair of particles linked by the constraint.
+    pub particles: [usize; 2],
+
+    /// Distance kept between the particles.
+    pub rest_length: F,
+
+    /// Role of the constraint in the grid.
+    pub kind: ClothConstraintKind,
+}
+
+/// Triangle mesh of the current shape of a cloth, ready to be rendered.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ClothMesh<F: num_traits::Float = f64> {
+    /// Positions of the vertices, one per particle.
+    pub positions: Vec<Vector3<F>>,
+
+    /// Unit normals of the vertices, averaged from the triangles around them.
+    pub normals: Vec<Vector3<F>>,
+
+    /// Texture coordinates of the vertices, from `0` to `1` across the grid.
+    pub uvs: Vec<[F; 2]>,
+
+    /// Indices of the vertices of each triangle, in counter-clockwise order.
+    pub triangles: Vec<[usize; 3]>,
+}
+
+/// Sheet of cloth simulated as a grid of particles linked by distance constraints.
+///
+/// # Remarks
+/// Particles are integrated first, then the constraints are projected a number of times,
+/// moving the particles in proportion to their inverse masses and the stiffness of each
+/// constraint, and finally the velocities are derived from the corrected positions.
+/// Projecting positions instead of applying spring forces keeps the cloth stable at
+/// the timesteps of games. Pinned particles have infinite mass, and can be moved freely.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Cloth<F: num_traits::Float = f64> {
+    /// Particles of the grid, stored row by row.
+    pub particles: Vec<Particle<F>>,
+
+    /// Distance constraints between the particles.
+    pub constraints: Vec<ClothConstraint<F>>,
+
+    /// Number of particles in each row of the grid.
+    pub columns: usize,
+
+    /// Number of rows of the grid.
+    pub rows: usize,
+
+    /// Acceleration due to gravity.
+    pub gravity: Vector3<F>,
+
+    /// Velocity of the air around the cloth.
+    pub wind: Vector3<F>,
+
+    /// Aerodynamic drag coefficient, scaling the force of the air hitting the cloth.
+    pub drag: F,
+
+    /// Stiffness of the structural constraints, from `0` to `1`.
+    pub structural_stiffness: F,
+
+    /// Stiffness of the shear constraints, from `0` to `1`.
+    pub shear_stiffness: F,
+
+    /// Stiffness of the bend constraints, from `0` to `1`.
+    pub bend_stiffness: F,
+
+    /// Number of times the constraints are projected every frame.
+    pub iterations: usize,
+}
+
+impl<F: num_traits::Float> Cloth<F> {
+    /// Creates a new cloth at rest, with a grid of `columns` by `rows` particles
+    /// spanning from the origin along both edges, and the given total mass.
+    ///
+    /// # Remarks
+    /// Columns go along `edge_u` and rows along `edge_v`, so the front of the cloth faces
+    /// along `edge_u × edge_v`. Grids need at least two columns and two rows.
+    pub fn grid(
+        origin: Vector3<F>,
+        edge_u: Vector3<F>,
+        edge_v: Vector3<F>,
+        columns: usize,
+        rows: usize,
+        mass: F,
+    ) -> Self {
+        assert!(
+            columns > 1 && rows > 1,
+            "cloth grids need at least 2x2 particles"
+        );
+
+        let step_u = edge_u.scalar_div(math::real((columns - 1) as f64));
+        let step_v = edge_v.scalar_div(math::real((rows - 1) as f64));
+        let particle_mass = mass / math::real((columns * rows) as f64);
+        let mut particles = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let position = origin
+                    .vector_add(&step_u.scalar_mul(math::real(column as f64)))
+                    .vector_add(&step_v.scalar_mul(math::real(row as f64)));
+                particles.push(Particle::new(position, particle_mass));
+            }
+        }
+
+        let mut cloth = Self {
+            particles,
+            constraints: Vec::new(),
+            columns,
+            rows,
+            gravity: Vector3::origin(),
+            wind: Vector3::origin(),
+            drag: math::real(DEFAULT_CLOTH_DRAG),
+            structural_stiffness: math::real(DEFAULT_STRUCTURAL_STIFFNESS),
+            shear_stiffness: math::real(DEFAULT_SHEAR_STIFFNESS),
+            bend_stiffness: math::real(DEFAULT_BEND_STIFFNESS),
+            iterations: DEFAULT_CLOTH_ITERATIONS,
+        };
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let here = cloth.index(column, row);
+                let (right, down) = (here + 1, here + columns);
+                if column + 1 < columns {
+                    cloth.link(here, right, ClothConstraintKind::Structural);
+                }
+                if row + 1 < rows {
+                    cloth.link(here, down, ClothConstraintKind::Structural);
+                }
+                if column + 1 < columns && row + 1 < rows {
+                    cloth.link(here, down + 1, ClothConstraintKind::Shear);
+                    cloth.link(right, down, ClothConstraintKind::Shear);
+                }
+                if column + 2 < columns {
+                    cloth.link(here, here + 2, ClothConstraintKind::Bend);
+                }
+                if row + 2 < rows {
+                    cloth.link(here, here + 2 * columns, ClothConstraintKind::Bend);
+                }
+            }
+        }
+        cloth
+    }
+
+    /// Returns the index of the particle at the given column and row of the grid.
+    pub fn index(&self, column: usize, row: usize) -> usize {
+        row * self.columns + column
+    }
+
+    /// Pins the particle at the given column and row of the grid in place,
+    /// giving it infinite mass.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the cloth.
+    pub fn pin(&mut self, column: usize, row: usize) -> &mut Self {
+        let index = self.index(column, row);
+        self.particles[index].set_infinite_mass();
+        self.particles[index].velocity = Vector3::origin();
+        self
+    }
+
+    /// Sets the stiffness of the structural, shear and bend constraints.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the cloth.
+    pub fn set_stiffness(&mut self, structural: F, shear: F, bend: F) -> &mut Self {
+        self.structural_stiffness = structural;
+        self.shear_stiffness = shear;
+        self.bend_stiffness = bend;
+        self
+    }
+
+    /// Returns the stiffness of the given kind of constraint.
+    pub fn stiffness(&self, kind: ClothConstraintKind) -> F {
+        match kind {
+            ClothConstraintKind::Structural => self.structural_stiffness,
+            ClothConstraintKind::Shear => self.shear_stiffness,
+            ClothConstraintKind::Bend => self.bend_stiffness,
+        }
+    }
+
+    /// Returns the indices of the vertices of every triangle of the grid,
+    /// two per cell, in counter-clockwise order seen from the front.
+    pub fn triangles(&self) -> Vec<[usize; 3]> {
+        let mut triangles = Vec::with_capacity((self.columns - 1) * (self.rows - 1) * 2);
+        for row in 0..self.rows - 1 {
+            for column in 0..self.columns - 1 {
+                let corner = self.index(column, row);
+                let right = corner + 1;
+                let down = corner + self.columns;
+                triangles.push([corner, right, down]);
+                triangles.push([right, down + 1, down]);
+            }
+        }
+        triangles
+    }
+
+    /// Returns the triangle mesh of the current shape of the cloth.
+    pub fn mesh(&self) -> ClothMesh<F> {
+        let positions: Vec<Vector3<F>> = self
+            .particles
+            .iter()
+            .map(|particle| particle.position)
+            .collect();
+        let triangles = self.triangles();
+
+        // Weighting the normals of the triangles by their area favours the larger ones.
+        let mut normals = vec![Vector3::origin(); positions.len()];
+        for triangle in triangles.iter() {
+            let normal = triangle_normal(&positions, triangle);
+            for vertex in triangle.iter() {
+                normals[*vertex].inplace_vector_add(&normal);
+            }
+        }
+        for normal in normals.iter_mut() {
+            if normal.squared_magnitude() > num_traits::zero() {
+                *normal = normal.normalize();
+            }
+        }
+
+        let last_column = math::real::<F>((self.columns - 1) as f64);
+        let last_row = math::real::<F>((self.rows - 1) as f64);
+        let uvs = (0..positions.len())
+            .map(|index| {
+                let column = math::real::<F>((index % self.columns) as f64);
+                let row = math::real::<F>((index / self.columns) as f64);
+                [column / last_column, row / last_row]
+            })
+            .collect();
+
+        ClothMesh {
+            positions,
+            normals,
+            uvs,
+            triangles,
+        }
+    }
+
+    /// Processes all the physics for the cloth.
+    pub fn run_physics(&mut self, duration: F) {
+        // First apply gravity and the air pushing on each triangle.
+        self.apply_wind();
+        let mut previous = Vec::with_capacity(self.particles.len());
+        predict(
+            &mut self.particles,
+            &mut previous,
+            &self.gravity,
+            duration,
+            F::powf,
+        );
+
+        // Then move the particles to satisfy the constraints.
+        for _ in 0..self.iterations {
+            for constraint in self.constraints.iter() {
+                let stiffness = self.stiffness(constraint.kind);
+                project_distance(
+                    &mut self.particles,
+                    constraint.particles,
+                    constraint.rest_length,
+                    stiffness,
+                );
+            }
+        }
+
+        // Finally derive the velocities from the corrected positions.
+        derive_velocities(&mut self.particles, &previous, duration);
+    }
+
+    /// Adds the forces of the air hitting the triangles of the cloth to their particles.
+    fn apply_wind(&mut self) {
+        let wind = self.wind;
+        self.apply_air(|_| wind, num_traits::one());
+    }
+
+    /// Adds the forces of the air blowing with the velocity given at the centroid of each triangle.
+    ///
+    /// # Remarks
+    /// The force on each triangle is proportional to its area and to the relative speed of
+    /// the air along its normal, and is shared evenly between its vertices. The velocity of the
+    /// triangles is scaled by `velocity_scale`, so a scale of `0` only adds the push of the wind.
+    pub(crate) fn apply_air<W: Fn(&Vector3<F>) -> Vector3<F>>(
+        &mut self,
+        wind: W,
+        velocity_scale: F,
+    ) {
+        if self.drag <= num_traits::zero() {
+            return;
+        }
+
+        let positions: Vec<Vector3<F>> = self
+            .particles
+            .iter()
+            .map(|particle| particle.position)
+            .collect();
+        let third = math::real::<F>(1.0 / 3.0);
+        for triangle in self.triangles() {
+            // The normal of the triangle is scaled by twice its area.
+            let normal = triangle_normal(&positions, &triangle);
+            let area = normal.magnitude() * math::real(0.5);
+            if area <= num_traits::zero() {
+                continue;
+            }
+            let normal = normal.normalize();
+            let (centroid, velocity) = triangle.iter().fold(
+                (Vector3::origin(), Vector3::origin()),
+                |(centroid, velocity), vertex| {
+                    let particle = &self.particles[*vertex];
+                    (
+                        centroid.vector_add(&particle.position),
+                        velocity.vector_add(&particle.velocity),
+                    )
+                },
+            );
+            let velocity = velocity.scalar_mul(third * velocity_scale);
+            let relative = wind(&centroid.scalar_mul(third))
+                .vector_sub(&velocity)
+                .dot_product(&normal);
+            let force = normal.scalar_mul(self.drag * area * relative * third);
+            for vertex in triangle.iter() {
+                self.particles[*vertex].add_force(&force);
+            }
+        }
+    }
+
+    /// Adds a constraint keeping the given particles at their current distance.
+    fn link(&mut self, one: usize, two: usize, kind: ClothConstraintKind) {
+        let particles = [one, two];
+        let rest_length = self.particles[particles[0]]
+            .position
+            .vector_sub(&self.particles[particles[1]].position)
+            .magnitude();
+        self.constraints.push(ClothConstraint {
+            particles,
+            rest_length,
+            kind,
+        });
+    }
+}
+
+/// Returns the normal of a triangle, scaled by twice its area.
+fn triangle_normal<F: num_traits::Float>(
+    positions: &[Vector3<F>],
+    triangle: &[usize; 3],
+) -> Vector3<F> {
+    let [a, b, c] = *triangle;
+    positions[b]
+        .vector_sub(&positions[a])
+        .cross_product(&positions[c].vector_sub(&positions[a]))
+}
@@ -0,0 +1,84 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::cloth::*;
+use math::Vector3;
+
+/// Flag hanging from the two top corners of a 2x1 grid in the xy plane.
+fn flag() -> Cloth {
+    let mut cloth = Cloth::grid(
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::new(0.0, -1.0, 0.0),
+        9,
+        5,
+        1.0,
+    );
+    cloth.pin(0, 0).pin(8, 0);
+    cloth.gravity = Vector3::new(0.0, -10.0, 0.0);
+    cloth
+}
+
+#[test]
+fn grid() {
+    let cloth = flag();
+    assert_eq!(45, cloth.particles.len());
+    assert_eq!(
+        Vector3::new(2.0, 0.0, 0.0),
+        cloth.particles[cloth.index(8, 4)].position
+    );
+    assert!(!cloth.particles[cloth.index(0, 0)].has_finite_mass());
+    assert!((cloth.particles[cloth.index(1, 0)].mass() - 1.0 / 45.0).abs() < 1e-12);
+
+    let count = |kind| {
+        cloth
+            .constraints
+            .iter()
+            .filter(|constraint| constraint.kind == kind)
+            .count()
+    };
+    assert_eq!(8 * 5 + 9 * 4, count(ClothConstraintKind::Structural));
+    assert_eq!(2 * 8 * 4, count(ClothConstraintKind::Shear));
+    assert_eq!(7 * 5 + 9 * 3, count(ClothConstraintKind::Bend));
+
+    let mesh = cloth.mesh();
+    assert_eq!(64, mesh.triangles.len());
+    assert_eq!([1.0, 1.0], mesh.uvs[44]);
+    for normal in mesh.normals.iter() {
+        assert!((*normal - Vector3::new(0.0, 0.0, -1.0)).magnitude() < 1e-12);
+    }
+}
+
+#[test]
+fn hanging() {
+    let mut cloth = flag();
+    for _ in 0..300 {
+        cloth.run_physics(1.0 / 60.0);
+    }
+
+    // The pinned corners stay, and the rest of the flag sags without tearing apart.
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), cloth.particles[0].position);
+    assert_eq!(Vector3::new(2.0, 1.0, 0.0), cloth.particles[8].position);
+    let bottom = cloth.particles[cloth.index(4, 4)].position;
+    assert!(bottom.y < 0.0 && bottom.y > -0.5);
+    for constraint in cloth.constraints.iter() {
+        if constraint.kind == ClothConstraintKind::Structural {
+            let [one, two] = constraint.particles;
+            let length =
+                (cloth.particles[one].position - cloth.particles[two].position).magnitude();
+            assert!(length < constraint.rest_length * 1.2);
+        }
+    }
+
+    // Wind blowing through the flag pushes it back.
+    cloth.wind = Vector3::new(0.0, 0.0, 5.0);
+    for _ in 0..120 {
+        cloth.run_physics(1.0 / 60.0);
+    }
+    let blown = cloth.particles[cloth.index(4, 4)].position;
+    assert!(blown.z > 0.2);
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), cloth.particles[0].position);
+}
@@ -0,0 +1,222 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::mass_properties::MassProperties;
+use crate::material::PhysicsMaterial;
+use crate::rigid_body::RigidBody;
+use crate::shape::Shape;
+use math::{Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Default collision group of colliders.
+pub const DEFAULT_COLLISION_GROUP: u32 = 1;
+
+/// Default collision mask of colliders, interacting with every group.
+pub const DEFAULT_COLLISION_MASK: u32 = u32::MAX;
+
+/// Cosine of the angle past which contacts with one-way colliders are let through,
+/// so the sides of one-way platforms don't block what goes past them.
+const ONE_WAY_TOLERANCE: f64 = 1e-3;
+
+/// Shape attached to a rigid body, used to detect collisions.
+///
+/// # Remarks
+/// The shape is placed in the local space of the body with an offset, so a body
+/// doesn't need to be centered on its shape. The world transform of the collider
+/// is cached, and must be refreshed with `calculate_internals` whenever the body moves.
+///
+/// Two colliders only interact when the group of each one is in the mask of the other.
+/// Sensor colliders report the colliders overlapping them instead of colliding with them,
+/// and ignore the scenery planes.
+///
+/// Colliders with a contact margin generate speculative contacts with whatever is closer
+/// than the margin, before actually touching it. The solver lets them close the gap during
+/// the step, but no further, which stops fast bodies from tunneling through thin objects
+/// and resting ones from jittering at large time steps, without the cost of continuous
+/// collision detection. Speculative contacts never bounce.
+///
+/// Colliders with a surface velocity carry whatever rests on them as if their surface was
+/// moving, without moving themselves, like conveyor belts, treadmills or spinning platforms:
+/// friction drives the bodies touching them towards the velocity of the surface.
+///
+/// One-way colliders, like the platforms of platformers, only stop what reaches them from
+/// their solid side, and let it through from every other one. Once something starts going
+/// through, it's let through until they separate, so it isn't pushed out the wrong side
+/// halfway through. They stay solid to the scenery planes.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Collider<S, F: num_traits::Float = f64> {
+    /// Index of the rigid body the collider is attached to.
+    pub body: usize,
+
+    /// Offset of the shape from the origin of the body.
+    pub offset: Matrix4<F>,
+
+    /// Shape of the collider.
+    pub shape: S,
+
+    /// Material the collider is made of.
+    pub material: PhysicsMaterial<F>,
+
+    /// Bitfield of the collision groups the collider belongs to.
+    pub group: u32,
+
+    /// Bitfield of the collision groups the collider interacts with.
+    pub mask: u32,
+
+    /// Only detects the colliders overlapping it when true, without generating contacts.
+    pub sensor: bool,
+
+    /// Distance at which the collider starts generating speculative contacts.
+    pub contact_margin: F,
+
+    /// Linear velocity of the surface of the collider, in its local space.
+    pub surface_velocity: Vector3<F>,
+
+    /// Angular velocity of the surface of the collider around its origin, in its local space.
+    pub surface_rotation: Vector3<F>,
+
+    /// Direction, in the local space of the collider, towards which it pushes what touches it.
+    /// Other colliders only collide with it from that side when set.
+    pub one_way: Option<Vector3<F>>,
+
+    transform: Matrix4<F>,
+}
+
+impl<S, F: num_traits::Float> Collider<S, F> {
+    /// Creates a new collider with the given shape, attached to the origin of the given body.
+    pub fn new(body: usize, shape: S) -> Self {
+        Self::with_offset(body, shape, Matrix4::identity())
+    }
+
+    /// Creates a new collider with the given shape, attached to the given body with an offset.
+    pub fn with_offset(body: usize, shape: S, offset: Matrix4<F>) -> Self {
+        Self {
+            body,
+            offset,
+            shape,
+            material: PhysicsMaterial::default(),
+            group: DEFAULT_COLLISION_GROUP,
+            mask: DEFAULT_COLLISION_MASK,
+            sensor: false,
+            contact_margin: num_traits::zero(),
+            surface_velocity: Vector3::origin(),
+            surface_rotation: Vector3::origin(),
+            one_way: None,
+            transform: offset,
+        }
+    }
+
+    /// Creates a collider already placed with the given world transform,
+    /// used to collide the parts of compound shapes.
+    pub(crate) fn placed(body: usize, shape: S, transform: Matrix4<F>) -> Self {
+        Self {
+            body,
+            offset: Matrix4::identity(),
+            shape,
+            material: PhysicsMaterial::default(),
+            group: DEFAULT_COLLISION_GROUP,
+            mask: DEFAULT_COLLISION_MASK,
+            sensor: false,
+            contact_margin: num_traits::zero(),
+            surface_velocity: Vector3::origin(),
+            surface_rotation: Vector3::origin(),
+            one_way: None,
+            transform,
+        }
+    }
+
+    /// Sets the collision group and mask of the collider.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the collider.
+    pub fn with_groups(&mut self, group: u32, mask: u32) -> &mut Self {
+        self.group = group;
+        self.mask = mask;
+        self
+    }
+
+    /// Returns true if the collider interacts with the given collision group and mask.
+    pub fn interacts_with(&self, group: u32, mask: u32) -> bool {
+        self.group & mask != 0 && group & self.mask != 0
+    }
+
+    /// Calculates the world transform of the collider from the transform of its body.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the collider.
+    pub fn calculate_internals(&mut self, bodies: &[RigidBody<F>]) -> &mut Self {
+        self.transform = bodies[self.body].transform_matrix.matrix_mul(&self.offset);
+        self
+    }
+
+    /// Copies the properties of another collider placed the same way, keeping the cached
+    /// world transform of this one.
+    pub(crate) fn clone_properties_from(&mut self, other: &Self)
+    where
+        S: Clone,
+    {
+        let transform = self.transform;
+        self.clone_from(other);
+        self.transform = transform;
+    }
+
+    /// Moves the cached world transform of the collider by the opposite of the given offset,
+    /// as the origin of the world moves to it.
+    pub(crate) fn shift_origin(&mut self, offset: &Vector3<F>) {
+        self.transform.data[3] = self.transform.data[3] - offset.x;
+        self.transform.data[7] = self.transform.data[7] - offset.y;
+        self.transform.data[11] = self.transform.data[11] - offset.z;
+    }
+
+    /// Returns the world transform of the collider.
+    pub fn transform(&self) -> &Matrix4<F> {
+        &self.transform
+    }
+
+    /// Returns the position of the collider in world space.
+    pub fn position(&self) -> Vector3<F> {
+        self.transform.translation()
+    }
+
+    /// Returns the velocity of the surface of the collider at the given point, in world space,
+    /// relative to the body of the collider.
+    pub fn surface_velocity_at(&self, point: &Vector3<F>) -> Vector3<F> {
+        let local = self.transform.transform_inverse(point);
+        let velocity = self
+            .surface_velocity
+            .vector_add(&self.surface_rotation.cross_product(&local));
+        self.transform.transform_direction(&velocity)
+    }
+
+    /// Returns true if the collider blocks a contact pushing another collider away from it
+    /// along the given direction in world space, always the case when it isn't one-way.
+    pub fn blocks(&self, direction: &Vector3<F>) -> bool {
+        match self.one_way {
+            Some(solid) => {
+                let solid = self.transform.transform_direction(&solid);
+                direction.dot_product(&solid)
+                    > math::real::<F>(ONE_WAY_TOLERANCE) * solid.magnitude() * direction.magnitude()
+            }
+            None => true,
+        }
+    }
+
+    /// Returns one of the axes of the collider in world space
+    /// (`0` for x, `1` for y, `2` for z, `3` for the position).
+    pub fn axis(&self, index: usize) -> Vector3<F> {
+        self.transform.axis_vector(index)
+    }
+}
+
+impl<F: num_traits::Float> Collider<Shape<F>, F> {
+    /// Returns the mass properties of the collider in the local space of its body, from
+    /// its shape, offset and the density of its material.
+    pub fn mass_properties(&self) -> MassProperties<F> {
+        MassProperties::from_shape(&self.shape, self.material.density).transformed(&self.offset)
+    }
+}
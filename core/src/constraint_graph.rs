@@ -0,0 +1,204 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::joint::JointKind;
+use crate::rigid_body::BodyType;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Rigid body in a constraint graph, with what decides whether it can fall asleep.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GraphBody<F: num_traits::Float = f64> {
+    /// Index of the body in the world.
+    pub body: usize,
+
+    /// How the body is moved by the world.
+    pub body_type: BodyType,
+
+    /// Whether the body is awake.
+    pub is_awake: bool,
+
+    /// Whether the body is allowed to fall asleep.
+    pub can_sleep: bool,
+
+    /// Recent motion of the body, which must drop below the sleep epsilon to fall asleep.
+    pub motion: F,
+
+    /// Motion below which the body falls asleep.
+    pub sleep_epsilon: F,
+
+    /// Island the body belongs to, if it has finite mass.
+    pub island: Option<usize>,
+}
+
+/// Joint in a constraint graph, linking a body to another one or to the world (`None`).
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GraphJoint<F: num_traits::Float = f64> {
+    /// Index of the joint in the world.
+    pub joint: usize,
+
+    /// Bodies joined together.
+    pub bodies: (usize, Option<usize>),
+
+    /// Name of the kind of joint.
+    pub kind: String,
+
+    /// Whether the joint is enabled.
+    pub enabled: bool,
+
+    /// Magnitude of the force the joint applied during the last frame.
+    pub force: F,
+
+    /// Magnitude of the torque the joint applied during the last frame.
+    pub torque: F,
+}
+
+/// Contact manifold in a constraint graph, between a body and another one or the
+/// scenery (`None`).
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GraphContact<F: num_traits::Float = f64> {
+    /// Bodies in contact.
+    pub bodies: (usize, Option<usize>),
+
+    /// Number of points in the manifold.
+    pub points: usize,
+
+    /// Deepest penetration of the points.
+    pub penetration: F,
+
+    /// Sum of the normal impulses applied by the points during the last frame.
+    pub normal_impulse: F,
+}
+
+/// Bodies of a world, along with the joints and contacts linking them and the islands
+/// they form, captured by `World::export_constraint_graph`.
+///
+/// # Remarks
+/// The graph can be serialized with serde, e.g. to JSON, or turned into a Graphviz graph
+/// with `to_dot`.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ConstraintGraph<F: num_traits::Float = f64> {
+    /// Bodies of the world, by index.
+    pub bodies: Vec<GraphBody<F>>,
+
+    /// Joints of the world, by index.
+    pub joints: Vec<GraphJoint<F>>,
+
+    /// Contact manifolds of the last step, sorted by pair of bodies.
+    pub contacts: Vec<GraphContact<F>>,
+
+    /// Bodies of every island.
+    pub islands: Vec<Vec<usize>>,
+}
+
+impl<F: num_traits::Float> ConstraintGraph<F> {
+    /// Returns a description of the graph in the DOT language of Graphviz.
+    ///
+    /// # Remarks
+    /// Every island is drawn as a cluster, and bodies without an island (the ones that
+    /// can't move) outside of them. Awake bodies are filled in red and sleeping ones in blue.
+    /// Joints are drawn with bold lines, disabled ones dashed, and contacts with thin ones.
+    /// Joints and contacts with the world or the scenery point to a node of their own.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph constraints {\n    node [shape=box, style=filled];\n");
+        let number = |value: F| value.to_f64().unwrap_or(f64::NAN);
+
+        for (index, island) in self.islands.iter().enumerate() {
+            let _ = writeln!(dot, "    subgraph cluster_{} {{", index);
+            let _ = writeln!(dot, "        label=\"island {}\";", index);
+            for body in island.iter() {
+                let _ = writeln!(dot, "        b{};", body);
+            }
+            dot.push_str("    }\n");
+        }
+
+        for body in self.bodies.iter() {
+            let state = if body.is_awake { "awake" } else { "asleep" };
+            let color = if body.is_awake {
+                "lightcoral"
+            } else {
+                "lightblue"
+            };
+            let _ = writeln!(
+                dot,
+                "    b{} [label=\"body {}\\n{:?}, {}\\nmotion {:.4} / {:.4}\", fillcolor={}];",
+                body.body,
+                body.body,
+                body.body_type,
+                state,
+                number(body.motion),
+                number(body.sleep_epsilon),
+                color,
+            );
+        }
+
+        let world = self.joints.iter().any(|joint| joint.bodies.1.is_none());
+        let scenery = self
+            .contacts
+            .iter()
+            .any(|contact| contact.bodies.1.is_none());
+        if world {
+            dot.push_str("    world [shape=plaintext, style=solid];\n");
+        }
+        if scenery {
+            dot.push_str("    scenery [shape=plaintext, style=solid];\n");
+        }
+
+        for joint in self.joints.iter() {
+            let other = match joint.bodies.1 {
+                Some(body) => format!("b{}", body),
+                None => String::from("world"),
+            };
+            let style = if joint.enabled { "bold" } else { "dashed" };
+            let _ = writeln!(
+                dot,
+                "    b{} -- {} [label=\"{} joint {}\\nforce {:.4}\\ntorque {:.4}\", style={}, color=blue];",
+                joint.bodies.0,
+                other,
+                joint.kind,
+                joint.joint,
+                number(joint.force),
+                number(joint.torque),
+                style,
+            );
+        }
+
+        for contact in self.contacts.iter() {
+            let other = match contact.bodies.1 {
+                Some(body) => format!("b{}", body),
+                None => String::from("scenery"),
+            };
+            let _ = writeln!(
+                dot,
+                "    b{} -- {} [label=\"{} points\\npenetration {:.4}\\nimpulse {:.4}\", color=gray40];",
+                contact.bodies.0,
+                other,
+                contact.points,
+                number(contact.penetration),
+                number(contact.normal_impulse),
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Returns the name of a kind of joint.
+pub(crate) fn joint_name<F: num_traits::Float>(kind: &JointKind<F>) -> &'static str {
+    match kind {
+        JointKind::Ball(_) => "ball",
+        JointKind::Hinge(_) => "hinge",
+        JointKind::Fixed(_) => "fixed",
+        JointKind::Prismatic(_) => "prismatic",
+        JointKind::Distance(_) => "distance",
+        JointKind::LinearSpring(_) => "linear spring",
+        JointKind::AngularSpring(_) => "angular spring",
+        JointKind::Gear(_) => "gear",
+        JointKind::Pulley(_) => "pulley",
+        JointKind::RackAndPinion(_) => "rack and pinion",
+    }
+}
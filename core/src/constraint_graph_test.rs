@@ -0,0 +1,84 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ball_joint::BallJoint;
+use crate::collider::Collider;
+use crate::constraint_graph::*;
+use crate::joint::{Joint, JointKind};
+use crate::plane::Plane;
+use crate::rigid_body::{BodyType, RigidBody};
+use crate::shape::{Shape, Sphere};
+use crate::world::{World, WorldConfig};
+use math::Vector3;
+
+#[test]
+fn export() {
+    let mut world = World::<f64>::new(WorldConfig::default());
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let sphere = Sphere::new(0.5);
+    for position in [
+        Vector3::new(0.0, 0.49, 0.0),
+        Vector3::new(0.0, 1.48, 0.0),
+        Vector3::new(5.0, 0.49, 0.0),
+    ]
+    .iter()
+    {
+        let body = RigidBody::new(*position, 1.0, &sphere.inertia_tensor(1.0));
+        let body = world.add_body(body);
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Sphere(sphere)));
+    }
+    let ball = BallJoint::new(Vector3::origin(), Vector3::new(5.0, 3.0, 0.0));
+    let mut joint = Joint::new((2, None), JointKind::Ball(ball));
+    joint.enabled = false;
+    world.add_joint(joint);
+    world.start_frame();
+    world.run_physics(0.01);
+
+    // The stacked spheres share an island, while the one on its own gets another.
+    let graph = world.export_constraint_graph();
+    assert_eq!(vec![vec![0, 1], vec![2]], graph.islands);
+    assert_eq!(
+        vec![Some(0), Some(0), Some(1)],
+        graph.bodies.iter().map(|b| b.island).collect::<Vec<_>>()
+    );
+    assert!(graph
+        .bodies
+        .iter()
+        .all(|b| b.body_type == BodyType::Dynamic && b.is_awake));
+
+    // Every touching pair shows up once, pushing the spheres apart.
+    let mut pairs: Vec<_> = graph.contacts.iter().map(|c| c.bodies).collect();
+    pairs.sort();
+    assert_eq!(vec![(0, None), (0, Some(1)), (2, None)], pairs);
+    assert!(graph
+        .contacts
+        .iter()
+        .all(|c| c.points == 1 && c.penetration > 0.0 && c.normal_impulse > 0.0));
+    assert_eq!(
+        vec![GraphJoint {
+            joint: 0,
+            bodies: (2, None),
+            kind: String::from("ball"),
+            enabled: false,
+            force: 0.0,
+            torque: 0.0,
+        }],
+        graph.joints
+    );
+
+    // The DOT output draws the islands as clusters, with the world and the scenery as nodes.
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("graph constraints {"));
+    assert!(dot.contains("subgraph cluster_1 {"));
+    assert!(dot.contains("b0 -- b1 ["));
+    assert!(dot.contains("b0 -- scenery ["));
+    assert!(dot.contains("b2 -- world [label=\"ball joint 0"));
+    assert!(dot.contains("style=dashed"));
+    assert!(dot.ends_with("}\n"));
+}
@@ -0,0 +1,81 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Contact between two rigid bodies, or between a rigid body and the scenery.
+///
+/// # Remarks
+/// The contact normal points from the second body towards the first one,
+/// so moving the first body along the normal separates them.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Contact<F: num_traits::Float = f64> {
+    /// Indices of the bodies involved in the contact.
+    /// The second one is `None` for contacts with the scenery.
+    pub bodies: (usize, Option<usize>),
+
+    /// Indices of the colliders involved in the contact, in the order of the bodies, or of
+    /// the scenery plane for contacts with the scenery.
+    pub colliders: (usize, usize),
+
+    /// Position of the contact in world space.
+    pub contact_point: Vector3<F>,
+
+    /// Direction of the contact in world space.
+    pub contact_normal: Vector3<F>,
+
+    /// Depth of penetration at the contact point.
+    pub penetration: F,
+
+    /// Normal restitution coefficient at the contact.
+    pub restitution: F,
+
+    /// Dynamic friction coefficient at the contact, limiting the friction
+    /// between bodies sliding over each other.
+    pub friction: F,
+
+    /// Static friction coefficient at the contact, limiting the friction
+    /// that keeps bodies from starting to slide over each other.
+    pub static_friction: F,
+
+    /// Identifier of the features of both shapes that generated the contact
+    /// (e.g. a vertex against a face), used to match contacts across frames.
+    pub feature: u32,
+
+    /// Velocity along the surface friction drives the first body to, relative to the second
+    /// one, from the surface velocities of their colliders, in world space.
+    pub surface_velocity: Vector3<F>,
+
+    /// Contact margin the contact was generated within, zero unless the shapes were allowed
+    /// to generate speculative contacts before touching.
+    pub margin: F,
+}
+
+impl<F: num_traits::Float> Contact<F> {
+    /// Creates a new contact between the given bodies, without restitution, friction,
+    /// surface velocity nor contact margin.
+    pub fn new(
+        bodies: (usize, Option<usize>),
+        contact_point: Vector3<F>,
+        contact_normal: Vector3<F>,
+        penetration: F,
+    ) -> Self {
+        Self {
+            bodies,
+            colliders: (0, 0),
+            contact_point,
+            contact_normal,
+            penetration,
+            restitution: num_traits::zero(),
+            friction: num_traits::zero(),
+            static_friction: num_traits::zero(),
+            feature: 0,
+            surface_velocity: Vector3::origin(),
+            margin: num_traits::zero(),
+        }
+    }
+}
@@ -0,0 +1,452 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::collider::Collider;
+use crate::joint::{Joint, JointKind};
+use crate::shape::Shape;
+use crate::solver::tangent_basis;
+use crate::world::World;
+use math::{real, Matrix4, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Color of a debug primitive, with every channel between `0` and `1`.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DebugColor {
+    /// Red channel.
+    pub red: f32,
+
+    /// Green channel.
+    pub green: f32,
+
+    /// Blue channel.
+    pub blue: f32,
+
+    /// Opacity, where `1` is opaque.
+    pub alpha: f32,
+}
+
+impl DebugColor {
+    /// Creates a new opaque color.
+    pub fn new(red: f32, green: f32, blue: f32) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha: 1.0,
+        }
+    }
+}
+
+/// Receives the primitives extracted from a world, to draw them with any renderer.
+pub trait DebugDrawBackend<F: num_traits::Float = f64> {
+    /// Draws a line segment between two points in world space.
+    fn draw_line(&mut self, start: &Vector3<F>, end: &Vector3<F>, color: DebugColor);
+
+    /// Draws a point in world space with the given size.
+    ///
+    /// # Remarks
+    /// By default, points are drawn as three crossing lines along the world axes.
+    fn draw_point(&mut self, point: &Vector3<F>, size: F, color: DebugColor) {
+        let half = size / real(2.0);
+        let axes = [
+            Vector3::new(half, F::zero(), F::zero()),
+            Vector3::new(F::zero(), half, F::zero()),
+            Vector3::new(F::zero(), F::zero(), half),
+        ];
+        for axis in axes.iter() {
+            self.draw_line(&point.vector_sub(axis), &point.vector_add(axis), color);
+        }
+    }
+}
+
+/// Parts of a world drawn by a debug render pipeline.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct DebugRenderMode {
+    /// Wireframes of the colliders, colored by the state of their bodies.
+    pub colliders: bool,
+
+    /// Bounding boxes of the colliders.
+    pub aabbs: bool,
+
+    /// Points and normals of the contacts kept in the manifolds.
+    pub contacts: bool,
+
+    /// Anchors of the enabled joints, and the lines joining them to their bodies.
+    pub joints: bool,
+
+    /// Patches of the scenery planes, along with their normals.
+    pub planes: bool,
+}
+
+impl Default for DebugRenderMode {
+    fn default() -> Self {
+        Self {
+            colliders: true,
+            aabbs: false,
+            contacts: true,
+            joints: true,
+            planes: true,
+        }
+    }
+}
+
+/// Colors and sizes of the primitives drawn by a debug render pipeline.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DebugRenderStyle<F: num_traits::Float = f64> {
+    /// Color of the colliders of awake bodies.
+    pub awake: DebugColor,
+
+    /// Color of the colliders of sleeping bodies.
+    pub sleeping: DebugColor,
+
+    /// Color of the colliders of bodies with infinite mass.
+    pub fixed: DebugColor,
+
+    /// Color of the sensors.
+    pub sensor: DebugColor,
+
+    /// Color of the bounding boxes.
+    pub aabb: DebugColor,
+
+    /// Color of the contact points and normals.
+    pub contact: DebugColor,
+
+    /// Color of the joint anchors, and the lines joining them to their bodies.
+    pub joint: DebugColor,
+
+    /// Color of the scenery planes.
+    pub plane: DebugColor,
+
+    /// Number of segments the circles of round shapes are drawn with.
+    pub circle_segments: usize,
+
+    /// Size of the points drawn for contacts and joint anchors.
+    pub point_size: F,
+
+    /// Length of the contact and plane normals.
+    pub normal_length: F,
+
+    /// Half of the size of the patches drawn for the scenery planes.
+    pub plane_extent: F,
+}
+
+impl<F: num_traits::Float> Default for DebugRenderStyle<F> {
+    fn default() -> Self {
+        Self {
+            awake: DebugColor::new(0.2, 0.9, 0.3),
+            sleeping: DebugColor::new(0.4, 0.4, 0.6),
+            fixed: DebugColor::new(0.6, 0.6, 0.6),
+            sensor: DebugColor::new(0.9, 0.8, 0.1),
+            aabb: DebugColor::new(0.9, 0.3, 0.9),
+            contact: DebugColor::new(0.9, 0.1, 0.1),
+            joint: DebugColor::new(0.1, 0.5, 0.9),
+            plane: DebugColor::new(0.5, 0.5, 0.5),
+            circle_segments: 16,
+            point_size: real(0.1),
+            normal_length: real(0.5),
+            plane_extent: real(10.0),
+        }
+    }
+}
+
+/// Walks a world, giving lines and points describing it to a debug draw backend.
+///
+/// # Remarks
+/// Nothing is drawn by the pipeline itself, so worlds stay headless and any renderer can be
+/// plugged in by implementing `DebugDrawBackend`.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DebugRenderPipeline<F: num_traits::Float = f64> {
+    /// Parts of the world drawn.
+    pub mode: DebugRenderMode,
+
+    /// Colors and sizes of the primitives.
+    pub style: DebugRenderStyle<F>,
+}
+
+impl<F: num_traits::Float> Default for DebugRenderPipeline<F> {
+    fn default() -> Self {
+        Self::new(DebugRenderMode::default())
+    }
+}
+
+impl<F: num_traits::Float> DebugRenderPipeline<F> {
+    /// Creates a new pipeline drawing the given parts of a world with the default style.
+    pub fn new(mode: DebugRenderMode) -> Self {
+        Self {
+            mode,
+            style: DebugRenderStyle::default(),
+        }
+    }
+
+    /// Draws the parts of the world selected by the mode of the pipeline.
+    pub fn render<B: DebugDrawBackend<F>>(&self, world: &World<F>, backend: &mut B) {
+        if self.mode.colliders {
+            for collider in world.colliders.iter() {
+                let color = self.collider_color(world, collider);
+                self.draw_shape(backend, &collider.shape, collider.transform(), color);
+            }
+        }
+
+        if self.mode.aabbs {
+            for collider in world.colliders.iter() {
+                let aabb = collider.shape.aabb(collider.transform());
+                self.draw_aabb(backend, &aabb);
+            }
+        }
+
+        if self.mode.contacts {
+            for manifold in world.manifolds.iter() {
+                for point in manifold.points.iter() {
+                    let contact = &point.contact;
+                    let tip = contact
+                        .contact_point
+                        .vector_add(&contact.contact_normal.scalar_mul(self.style.normal_length));
+                    let color = self.style.contact;
+                    backend.draw_point(&contact.contact_point, self.style.point_size, color);
+                    backend.draw_line(&contact.contact_point, &tip, color);
+                }
+            }
+        }
+
+        if self.mode.joints {
+            for joint in world.joints.iter().filter(|joint| joint.enabled) {
+                self.draw_joint(backend, world, joint);
+            }
+        }
+
+        if self.mode.planes {
+            for plane in world.planes.iter() {
+                let center = plane.normal.scalar_mul(plane.offset);
+                let [first, second] = tangent_basis(&plane.normal);
+                let (first, second) = (
+                    first.scalar_mul(self.style.plane_extent),
+                    second.scalar_mul(self.style.plane_extent),
+                );
+                let corners = [
+                    center.vector_add(&first).vector_add(&second),
+                    center.vector_add(&first).vector_sub(&second),
+                    center.vector_sub(&first).vector_sub(&second),
+                    center.vector_sub(&first).vector_add(&second),
+                ];
+                for (index, corner) in corners.iter().enumerate() {
+                    backend.draw_line(corner, &corners[(index + 1) % 4], self.style.plane);
+                }
+                let tip = center.vector_add(&plane.normal.scalar_mul(self.style.normal_length));
+                backend.draw_line(&center, &tip, self.style.plane);
+            }
+        }
+    }
+
+    /// Returns the color of a collider, from the state of its body.
+    fn collider_color(&self, world: &World<F>, collider: &Collider<Shape<F>, F>) -> DebugColor {
+        let body = &world.bodies[collider.body];
+        if collider.sensor {
+            self.style.sensor
+        } else if !body.has_finite_mass() {
+            self.style.fixed
+        } else if body.is_awake {
+            self.style.awake
+        } else {
+            self.style.sleeping
+        }
+    }
+
+    /// Draws the wireframe of a shape placed with the given transform.
+    fn draw_shape<B: DebugDrawBackend<F>>(
+        &self,
+        backend: &mut B,
+        shape: &Shape<F>,
+        transform: &Matrix4<F>,
+        color: DebugColor,
+    ) {
+        let (zero, one) = (F::zero(), F::one());
+        let (x, y, z) = (
+            Vector3::new(one, zero, zero),
+            Vector3::new(zero, one, zero),
+            Vector3::new(zero, zero, one),
+        );
+        let origin = Vector3::origin();
+        let full = real::<F>(std::f64::consts::TAU);
+        match shape {
+            Shape::Sphere(sphere) => {
+                let radius = sphere.radius;
+                for (u, v) in [(&x, &y), (&y, &z), (&z, &x)] {
+                    self.draw_arc(backend, transform, &origin, (u, v), radius, full, color);
+                }
+            }
+            Shape::Cuboid(cuboid) => {
+                draw_box(backend, transform, &origin, &cuboid.half_size, color);
+            }
+            Shape::Capsule(capsule) => {
+                let (radius, half) = (capsule.radius, capsule.half_height);
+                let pi = real::<F>(std::f64::consts::PI);
+                let (top, bottom) = (y.scalar_mul(half), y.scalar_mul(-half));
+                self.draw_arc(backend, transform, &top, (&z, &x), radius, full, color);
+                self.draw_arc(backend, transform, &bottom, (&z, &x), radius, full, color);
+                for side in [&x, &z] {
+                    let flipped = side.invert();
+                    self.draw_arc(backend, transform, &top, (side, &y), radius, pi, color);
+                    self.draw_arc(
+                        backend,
+                        transform,
+                        &bottom,
+                        (&flipped, &y.invert()),
+                        radius,
+                        pi,
+                        color,
+                    );
+                    for direction in [side, &flipped] {
+                        let offset = direction.scalar_mul(radius);
+                        backend.draw_line(
+                            &transform.transform(&top.vector_add(&offset)),
+                            &transform.transform(&bottom.vector_add(&offset)),
+                            color,
+                        );
+                    }
+                }
+            }
+            Shape::ConvexHull(hull) => {
+                for [start, end] in hull.edges.iter() {
+                    backend.draw_line(
+                        &transform.transform(&hull.vertices[*start]),
+                        &transform.transform(&hull.vertices[*end]),
+                        color,
+                    );
+                }
+            }
+            Shape::TriMesh(mesh) => {
+                for index in 0..mesh.triangles().len() {
+                    let triangle = mesh.triangle(index).transformed(transform);
+                    backend.draw_line(&triangle.a, &triangle.b, color);
+                    backend.draw_line(&triangle.b, &triangle.c, color);
+                    backend.draw_line(&triangle.c, &triangle.a, color);
+                }
+            }
+            Shape::Compound(compound) => {
+                for child in compound.children.iter() {
+                    let transform = transform.matrix_mul(&child.offset);
+                    self.draw_shape(backend, &child.shape, &transform, color);
+                }
+            }
+        }
+    }
+
+    /// Draws an arc of a circle around a center in local space, starting along `u` and turning
+    /// towards `v` for the given angle.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_arc<B: DebugDrawBackend<F>>(
+        &self,
+        backend: &mut B,
+        transform: &Matrix4<F>,
+        center: &Vector3<F>,
+        (u, v): (&Vector3<F>, &Vector3<F>),
+        radius: F,
+        angle: F,
+        color: DebugColor,
+    ) {
+        let segments = self.style.circle_segments.max(1);
+        let step = angle / real(segments as f64);
+        let point = |index: usize| {
+            let (sine, cosine) = (step * real(index as f64)).sin_cos();
+            let offset = u
+                .scalar_mul(cosine * radius)
+                .vector_add(&v.scalar_mul(sine * radius));
+            transform.transform(&center.vector_add(&offset))
+        };
+        for index in 0..segments {
+            backend.draw_line(&point(index), &point(index + 1), color);
+        }
+    }
+
+    /// Draws a bounding box.
+    fn draw_aabb<B: DebugDrawBackend<F>>(&self, backend: &mut B, aabb: &Aabb<F>) {
+        let two = real::<F>(2.0);
+        let center = aabb.min.vector_add(&aabb.max).scalar_div(two);
+        let half_size = aabb.max.vector_sub(&aabb.min).scalar_div(two);
+        let identity = Matrix4::from_orientation_and_position(&Quaternion::identity(), &center);
+        draw_box(
+            backend,
+            &identity,
+            &Vector3::origin(),
+            &half_size,
+            self.style.aabb,
+        );
+    }
+
+    /// Draws the anchors of a joint, joined to the centers of their bodies.
+    ///
+    /// # Remarks
+    /// Joints without anchors are drawn as a line between the centers of their bodies.
+    fn draw_joint<B: DebugDrawBackend<F>>(
+        &self,
+        backend: &mut B,
+        world: &World<F>,
+        joint: &Joint<F>,
+    ) {
+        let anchors = match &joint.kind {
+            JointKind::Ball(kind) => (kind.local_anchor_one, kind.local_anchor_two),
+            JointKind::Hinge(kind) => (kind.local_anchor_one, kind.local_anchor_two),
+            JointKind::Fixed(kind) => (kind.local_anchor_one, kind.local_anchor_two),
+            JointKind::Prismatic(kind) => (kind.local_anchor_one, kind.local_anchor_two),
+            JointKind::Distance(kind) => (kind.local_anchor_one, kind.local_anchor_two),
+            JointKind::LinearSpring(kind) => (kind.local_anchor_one, kind.local_anchor_two),
+            JointKind::Pulley(kind) => (kind.local_anchor_one, kind.local_anchor_two),
+            JointKind::AngularSpring(_) | JointKind::Gear(_) | JointKind::RackAndPinion(_) => {
+                if let Some(two) = joint.bodies.1 {
+                    let one = &world.bodies[joint.bodies.0].position;
+                    backend.draw_line(one, &world.bodies[two].position, self.style.joint);
+                }
+                return;
+            }
+        };
+
+        let color = self.style.joint;
+        let one = &world.bodies[joint.bodies.0];
+        let anchor_one = one.point_in_world_space(&anchors.0);
+        backend.draw_line(&one.position, &anchor_one, color);
+        backend.draw_point(&anchor_one, self.style.point_size, color);
+
+        let anchor_two = match joint.bodies.1 {
+            Some(two) => {
+                let two = &world.bodies[two];
+                let anchor = two.point_in_world_space(&anchors.1);
+                backend.draw_line(&two.position, &anchor, color);
+                anchor
+            }
+            None => anchors.1,
+        };
+        backend.draw_point(&anchor_two, self.style.point_size, color);
+        backend.draw_line(&anchor_one, &anchor_two, color);
+    }
+}
+
+/// Draws the twelve edges of a box around a center in local space.
+fn draw_box<F: num_traits::Float, B: DebugDrawBackend<F>>(
+    backend: &mut B,
+    transform: &Matrix4<F>,
+    center: &Vector3<F>,
+    half_size: &Vector3<F>,
+    color: DebugColor,
+) {
+    // Corners are numbered by the sign of their coordinates, one bit per axis.
+    let corner = |index: usize| {
+        let sign = |bit: usize, half: F| if index & bit == 0 { -half } else { half };
+        let offset = Vector3::new(
+            sign(1, half_size.x),
+            sign(2, half_size.y),
+            sign(4, half_size.z),
+        );
+        transform.transform(&center.vector_add(&offset))
+    };
+    for index in 0..8 {
+        for bit in [1, 2, 4] {
+            if index & bit == 0 {
+                backend.draw_line(&corner(index), &corner(index | bit), color);
+            }
+        }
+    }
+}
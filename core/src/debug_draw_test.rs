@@ -0,0 +1,111 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ball_joint::BallJoint;
+use crate::collider::Collider;
+use crate::debug_draw::*;
+use crate::gravity::{GravityField, GravitySource};
+use crate::joint::{Joint, JointKind};
+use crate::plane::Plane;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Capsule, Cuboid, Shape, Sphere};
+use crate::world::{World, WorldConfig};
+use math::{Matrix3, Vector3};
+
+/// Backend keeping the lines it's given.
+#[derive(Default)]
+struct Lines {
+    lines: Vec<(Vector3<f64>, Vector3<f64>, DebugColor)>,
+}
+
+impl DebugDrawBackend for Lines {
+    fn draw_line(&mut self, start: &Vector3<f64>, end: &Vector3<f64>, color: DebugColor) {
+        self.lines.push((*start, *end, color));
+    }
+}
+
+impl Lines {
+    fn count(&self, color: DebugColor) -> usize {
+        self.lines.iter().filter(|line| line.2 == color).count()
+    }
+}
+
+#[test]
+fn render() {
+    let mut world = World::<f64>::new(WorldConfig::default());
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    let resting = world.add_body(RigidBody::new(
+        Vector3::new(0.0, 0.49, 0.0),
+        1.0,
+        &cuboid.inertia_tensor(1.0),
+    ));
+    let resting = world.body_index(resting).unwrap();
+    world.add_collider(Collider::new(resting, Shape::Cuboid(cuboid)));
+    let hanging = world.add_body(RigidBody::new(
+        Vector3::new(5.0, 3.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let hanging = world.body_index(hanging).unwrap();
+    world.add_collider(Collider::new(hanging, Shape::Sphere(Sphere::new(0.5))));
+    let mut sensor = Collider::new(hanging, Shape::Capsule(Capsule::new(1.0, 0.5)));
+    sensor.sensor = true;
+    world.add_collider(sensor);
+    world.joints.push(Joint::new(
+        (hanging, None),
+        JointKind::Ball(BallJoint::new(
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(5.0, 4.0, 0.0),
+        )),
+    ));
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+
+    let pipeline = DebugRenderPipeline::default();
+    let style = pipeline.style;
+    let mut lines = Lines::default();
+    pipeline.render(&world, &mut lines);
+
+    // Boxes have twelve edges, and spheres three circles.
+    let segments = style.circle_segments;
+    assert_eq!(12 + 3 * segments, lines.count(style.awake));
+    // Capsules have a circle at each end, four half circles and four sides.
+    assert_eq!(2 * segments + 4 * segments + 4, lines.count(style.sensor));
+    // Contacts are a point and a normal, anchors a point and a line to their body.
+    let contacts = world
+        .manifolds
+        .iter()
+        .map(|manifold| manifold.points.len())
+        .sum::<usize>();
+    assert_eq!(4, contacts);
+    assert_eq!(4 * contacts, lines.count(style.contact));
+    assert_eq!(3 + 1 + 3 + 1, lines.count(style.joint));
+    assert_eq!(5, lines.count(style.plane));
+    assert_eq!(0, lines.count(style.aabb));
+
+    // Every line of the box lies on its surface.
+    for (start, end, _) in lines.lines.iter().take(12) {
+        for point in [start, end] {
+            assert!((point.x.abs() - 0.5).abs() < 1e-3);
+            assert!((point.z.abs() - 0.5).abs() < 1e-3);
+        }
+    }
+
+    let pipeline = DebugRenderPipeline::<f64>::new(DebugRenderMode {
+        colliders: false,
+        aabbs: true,
+        contacts: false,
+        joints: false,
+        planes: false,
+    });
+    let mut lines = Lines::default();
+    pipeline.render(&world, &mut lines);
+    assert_eq!(3 * 12, lines.lines.len());
+}
@@ -0,0 +1,254 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::{component, Aabb};
+use crate::ray::Ray;
+use crate::shape::{Compound, ConvexHull, Shape};
+use crate::trimesh::TriMesh;
+use math::{Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Default number of voxels along the longest side of a decomposed mesh.
+pub const DEFAULT_DECOMPOSITION_RESOLUTION: usize = 24;
+
+/// Default maximum number of convex hulls a mesh is decomposed into.
+pub const DEFAULT_MAX_HULLS: usize = 16;
+
+/// Default concavity under which the parts of a decomposed mesh aren't split anymore.
+pub const DEFAULT_CONCAVITY: f64 = 0.01;
+
+/// Maximum number of splitting planes tried along each axis when splitting a part.
+const MAX_PLANES_PER_AXIS: usize = 16;
+
+/// Settings of the approximate convex decomposition of a mesh.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DecompositionSettings<F: num_traits::Float = f64> {
+    /// Number of voxels along the longest side of the mesh. Higher resolutions follow the
+    /// mesh more closely, but take longer.
+    pub resolution: usize,
+
+    /// Maximum number of convex hulls the mesh is decomposed into.
+    pub max_hulls: usize,
+
+    /// Concavity under which parts aren't split anymore: the volume their hull adds to them,
+    /// as a fraction of the volume of the whole mesh.
+    pub concavity: F,
+}
+
+impl<F: num_traits::Float> Default for DecompositionSettings<F> {
+    fn default() -> Self {
+        Self {
+            resolution: DEFAULT_DECOMPOSITION_RESOLUTION,
+            max_hulls: DEFAULT_MAX_HULLS,
+            concavity: math::real(DEFAULT_CONCAVITY),
+        }
+    }
+}
+
+/// Voxel grid covering a mesh.
+struct Grid<F: num_traits::Float> {
+    origin: Vector3<F>,
+    size: F,
+}
+
+impl<F: num_traits::Float> Grid<F> {
+    /// Returns the position of the corner of the grid with the given coordinates.
+    fn corner(&self, x: usize, y: usize, z: usize) -> Vector3<F> {
+        let position = |index: usize| F::from(index).expect("small index") * self.size;
+        self.origin
+            .vector_add(&Vector3::new(position(x), position(y), position(z)))
+    }
+}
+
+/// Set of voxels along with its convex hull, and the volume the hull adds to it.
+struct Part<F: num_traits::Float> {
+    voxels: Vec<[usize; 3]>,
+    hull: ConvexHull<F>,
+    concavity: F,
+}
+
+/// Splits a closed mesh into a compound of convex hulls that approximates it, so concave
+/// objects can be simulated as dynamic bodies. Returns `None` if the mesh doesn't enclose any
+/// voxel, e.g. when it's open or too thin for the resolution.
+///
+/// # Remarks
+/// The inside of the mesh is voxelized, and parts of it are split in two recursively, like
+/// V-HACD does: the part whose convex hull adds the most volume to it is split along the
+/// axis-aligned plane that minimizes the volume added by the hulls of both halves. Splitting
+/// stops when every part is convex enough, or there are `max_hulls` parts.
+///
+/// The hulls go through the corners of the voxels, so their surfaces stay within a voxel
+/// of the surface of the mesh.
+pub fn convex_decomposition<F: num_traits::Float>(
+    mesh: &TriMesh<F>,
+    settings: &DecompositionSettings<F>,
+) -> Option<Compound<F>> {
+    let (grid, voxels) = voxelize(mesh, settings.resolution.max(1));
+    if voxels.is_empty() {
+        return None;
+    }
+    let voxel_volume = grid.size * grid.size * grid.size;
+    let total = F::from(voxels.len()).expect("small count") * voxel_volume;
+    let threshold = settings.concavity * total;
+
+    let mut parts = vec![Part::new(&grid, voxels)?];
+    let mut convex = vec![false];
+    while parts.len() < settings.max_hulls.max(1) {
+        let candidate = (0..parts.len())
+            .filter(|index| !convex[*index] && parts[*index].concavity > threshold)
+            .max_by(|a, b| {
+                parts[*a]
+                    .concavity
+                    .partial_cmp(&parts[*b].concavity)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        let index = match candidate {
+            Some(index) => index,
+            None => break,
+        };
+        match split(&grid, &parts[index]) {
+            Some((one, two)) => {
+                parts[index] = one;
+                convex[index] = false;
+                parts.push(two);
+                convex.push(false);
+            }
+            None => convex[index] = true,
+        }
+    }
+
+    let mut compound = Compound::new();
+    for part in parts {
+        compound.add(Matrix4::identity(), Shape::convex_hull(part.hull));
+    }
+    Some(compound)
+}
+
+/// Returns the voxel grid covering the mesh, and the coordinates of the voxels whose center
+/// is inside of it.
+///
+/// # Remarks
+/// Rays are cast through the centers of every row of voxels along the x axis, and the centers
+/// with an odd number of crossings with the mesh before them are inside.
+fn voxelize<F: num_traits::Float>(
+    mesh: &TriMesh<F>,
+    resolution: usize,
+) -> (Grid<F>, Vec<[usize; 3]>) {
+    let bounds = mesh.local_aabb();
+    let extents = bounds.max.vector_sub(&bounds.min);
+    let longest = extents.x.max(extents.y).max(extents.z);
+    let size = longest / F::from(resolution).expect("small resolution");
+    let grid = Grid {
+        origin: bounds.min,
+        size,
+    };
+    if size <= F::zero() {
+        return (grid, Vec::new());
+    }
+    let count = |axis: usize| {
+        (component(&extents, axis) / size)
+            .ceil()
+            .to_usize()
+            .unwrap_or(1)
+            .max(1)
+    };
+    let (nx, ny, nz) = (count(0), count(1), count(2));
+    let half = math::real::<F>(0.5);
+    let center = |index: usize| (F::from(index).expect("small index") + half) * size;
+    let tolerance = size * math::real(1e-6);
+
+    let mut voxels = Vec::new();
+    for y in 0..ny {
+        for z in 0..nz {
+            let start = Vector3::new(
+                bounds.min.x - size,
+                bounds.min.y + center(y),
+                bounds.min.z + center(z),
+            );
+            let end = Vector3::new(bounds.max.x + size, start.y, start.z);
+            let ray = Ray::new(start, Vector3::new(F::one(), F::zero(), F::zero()));
+            let max_toi = end.x - start.x;
+            let mut crossings = Vec::new();
+            mesh.bvh().query_aabb(&Aabb::new(start, end), |index| {
+                if let Some(toi) = mesh.triangle(index).cast_ray(&ray, max_toi) {
+                    crossings.push(toi);
+                }
+                true
+            });
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+            // Rays through shared edges cross both triangles at once.
+            crossings.dedup_by(|a, b| (*a - *b).abs() <= tolerance);
+            for x in 0..nx {
+                let toi = size + center(x);
+                let before = crossings.iter().filter(|crossing| **crossing < toi).count();
+                if before % 2 == 1 {
+                    voxels.push([x, y, z]);
+                }
+            }
+        }
+    }
+    (grid, voxels)
+}
+
+impl<F: num_traits::Float> Part<F> {
+    /// Returns the part made of the given voxels, or `None` if there are none.
+    fn new(grid: &Grid<F>, voxels: Vec<[usize; 3]>) -> Option<Self> {
+        // The corners of the voxels at both ends of each row span the hull of the whole row.
+        let mut rows: BTreeMap<(usize, usize), (usize, usize)> = BTreeMap::new();
+        for [x, y, z] in voxels.iter() {
+            let row = rows.entry((*y, *z)).or_insert((*x, *x));
+            row.0 = row.0.min(*x);
+            row.1 = row.1.max(*x);
+        }
+        let mut points = Vec::with_capacity(rows.len() * 8);
+        for ((y, z), (min, max)) in rows {
+            for x in [min, max + 1].iter() {
+                for (dy, dz) in [(0, 0), (0, 1), (1, 0), (1, 1)].iter() {
+                    points.push(grid.corner(*x, y + dy, z + dz));
+                }
+            }
+        }
+
+        let hull = ConvexHull::from_points(&points)?;
+        let volume =
+            F::from(voxels.len()).expect("small count") * grid.size * grid.size * grid.size;
+        let concavity = (hull.volume() - volume).max(F::zero());
+        Some(Self {
+            voxels,
+            hull,
+            concavity,
+        })
+    }
+}
+
+/// Splits the part in two along the axis-aligned plane minimizing the volume the hulls of
+/// both halves add to them. Returns `None` if the part is a single voxel thick.
+fn split<F: num_traits::Float>(grid: &Grid<F>, part: &Part<F>) -> Option<(Part<F>, Part<F>)> {
+    let mut best: Option<(F, Part<F>, Part<F>)> = None;
+    for axis in 0..3 {
+        let min = part.voxels.iter().map(|voxel| voxel[axis]).min()?;
+        let max = part.voxels.iter().map(|voxel| voxel[axis]).max()?;
+        let step = ((max - min) / MAX_PLANES_PER_AXIS).max(1);
+        for plane in ((min + 1)..=max).step_by(step) {
+            let (one, two): (Vec<[usize; 3]>, Vec<[usize; 3]>) =
+                part.voxels.iter().partition(|voxel| voxel[axis] < plane);
+            let (one, two) = match (Part::new(grid, one), Part::new(grid, two)) {
+                (Some(one), Some(two)) => (one, two),
+                _ => continue,
+            };
+            let cost = one.concavity + two.concavity;
+            if best
+                .as_ref()
+                .is_none_or(|(best_cost, _, _)| cost < *best_cost)
+            {
+                best = Some((cost, one, two));
+            }
+        }
+    }
+    best.map(|(_, one, two)| (one, two))
+}
@@ -0,0 +1,95 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::decomposition::*;
+use crate::shape::Shape;
+use crate::trimesh::TriMesh;
+use math::Vector3;
+
+/// Returns a closed mesh of the given outline, extruded from `z = 0` to `z = 1`.
+/// The outline must be star-shaped around its first vertex.
+fn prism(outline: &[(f64, f64)]) -> TriMesh {
+    let count = outline.len();
+    let mut vertices = Vec::new();
+    for z in [0.0, 1.0].iter() {
+        vertices.extend(outline.iter().map(|(x, y)| Vector3::new(*x, *y, *z)));
+    }
+    let mut triangles = Vec::new();
+    for index in 1..count - 1 {
+        triangles.push([0, index + 1, index]);
+        triangles.push([count, count + index, count + index + 1]);
+    }
+    for index in 0..count {
+        let next = (index + 1) % count;
+        triangles.push([index, next, count + next]);
+        triangles.push([index, count + next, count + index]);
+    }
+    TriMesh::new(vertices, triangles).unwrap()
+}
+
+fn hull_volumes(shape: &Shape) -> Vec<f64> {
+    match shape {
+        Shape::Compound(compound) => compound
+            .children
+            .iter()
+            .map(|child| child.shape.volume())
+            .collect(),
+        shape => panic!("unexpected shape {:?}", shape),
+    }
+}
+
+#[test]
+fn decomposition() {
+    let settings = DecompositionSettings {
+        resolution: 20,
+        ..DecompositionSettings::default()
+    };
+
+    // Convex meshes are kept whole.
+    let square = prism(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+    let compound = Shape::compound(convex_decomposition(&square, &settings).unwrap());
+    let volumes = hull_volumes(&compound);
+    assert_eq!(1, volumes.len());
+    assert!((volumes[0] - 4.0).abs() < 1e-9);
+
+    // Concave meshes are split where the hull of the whole would add the most volume.
+    let corner = prism(&[
+        (1.0, 1.0),
+        (1.0, 2.0),
+        (0.0, 2.0),
+        (0.0, 0.0),
+        (2.0, 0.0),
+        (2.0, 1.0),
+    ]);
+    let compound = convex_decomposition(&corner, &settings).unwrap();
+    let shape = Shape::compound(compound);
+    let volumes = hull_volumes(&shape);
+    assert_eq!(2, volumes.len());
+    assert!((volumes.iter().sum::<f64>() - 3.0).abs() < 1e-9);
+
+    // Fewer hulls follow the mesh more loosely.
+    let single = DecompositionSettings {
+        max_hulls: 1,
+        ..settings
+    };
+    let volumes = hull_volumes(&Shape::compound(
+        convex_decomposition(&corner, &single).unwrap(),
+    ));
+    assert_eq!(1, volumes.len());
+    assert!((volumes[0] - 3.5).abs() < 1e-9);
+
+    // Open meshes don't enclose anything.
+    let open = TriMesh::new(
+        vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ],
+        vec![[0, 1, 2]],
+    )
+    .unwrap();
+    assert!(convex_decomposition(&open, &settings).is_none());
+}
@@ -0,0 +1,163 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::gravity::{GravityField, GravitySource};
+use crate::rigid_body::RigidBody;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Quantity conserved by a closed system of bodies.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ConservedQuantity {
+    /// Sum of the kinetic and potential energy.
+    Energy,
+
+    /// Linear momentum, conserved without external forces, like gravity.
+    LinearMomentum,
+
+    /// Angular momentum around the origin, conserved without external torques.
+    AngularMomentum,
+}
+
+/// Energy and momentum of the rigid bodies of a world at some point.
+///
+/// # Remarks
+/// Only dynamic bodies with finite mass are measured, since the rest have no energy or
+/// momentum that can be exchanged with them. Soft bodies and fluids aren't measured either.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Diagnostics<F: num_traits::Float = f64> {
+    /// Kinetic energy of the linear and angular motion of the bodies.
+    pub kinetic_energy: F,
+
+    /// Potential energy of the bodies in the gravity pulling them.
+    pub potential_energy: F,
+
+    /// Total linear momentum of the bodies.
+    pub linear_momentum: Vector3<F>,
+
+    /// Total angular momentum of the bodies around the origin.
+    pub angular_momentum: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Diagnostics<F> {
+    /// Measures the given bodies, pulled by the given gravity field or their own source.
+    pub fn measure(
+        bodies: &[RigidBody<F>],
+        gravity: &GravityField<F>,
+        overrides: &BTreeMap<usize, GravitySource<F>>,
+    ) -> Self {
+        let half = math::real::<F>(0.5);
+        let mut diagnostics = Self {
+            kinetic_energy: num_traits::zero(),
+            potential_energy: num_traits::zero(),
+            linear_momentum: Vector3::origin(),
+            angular_momentum: Vector3::origin(),
+        };
+        for (index, body) in bodies.iter().enumerate() {
+            if !body.has_finite_mass() {
+                continue;
+            }
+
+            let mass = body.mass();
+            let momentum = body.velocity.scalar_mul(mass);
+            let spin = body
+                .inverse_inertia_tensor_world
+                .inverse()
+                .transform(&body.rotation);
+            let potential = match overrides.get(&index) {
+                Some(source) => source.potential_at(&body.position),
+                None => gravity.potential_at(&body.position),
+            };
+            diagnostics.kinetic_energy = diagnostics.kinetic_energy
+                + half * (momentum.dot_product(&body.velocity) + spin.dot_product(&body.rotation));
+            diagnostics.potential_energy =
+                diagnostics.potential_energy + mass * body.gravity_scale * potential;
+            diagnostics.linear_momentum.inplace_vector_add(&momentum);
+            diagnostics
+                .angular_momentum
+                .inplace_vector_add(&body.position.cross_product(&momentum).vector_add(&spin));
+        }
+        diagnostics
+    }
+
+    /// Returns the sum of the kinetic and potential energy.
+    pub fn total_energy(&self) -> F {
+        self.kinetic_energy + self.potential_energy
+    }
+
+    /// Returns how much the given quantity changed from these diagnostics to the given
+    /// later ones, as the magnitude of the change of the momentum vectors.
+    pub fn change(&self, later: &Diagnostics<F>, quantity: ConservedQuantity) -> F {
+        match quantity {
+            ConservedQuantity::Energy => (later.total_energy() - self.total_energy()).abs(),
+            ConservedQuantity::LinearMomentum => later
+                .linear_momentum
+                .vector_sub(&self.linear_momentum)
+                .magnitude(),
+            ConservedQuantity::AngularMomentum => later
+                .angular_momentum
+                .vector_sub(&self.angular_momentum)
+                .magnitude(),
+        }
+    }
+}
+
+/// Change of a conserved quantity during a step beyond the tolerance of a monitor.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ConservationViolation<F: num_traits::Float = f64> {
+    /// Quantity that wasn't conserved.
+    pub quantity: ConservedQuantity,
+
+    /// Diagnostics before the step.
+    pub before: Diagnostics<F>,
+
+    /// Diagnostics after the step.
+    pub after: Diagnostics<F>,
+
+    /// How much the quantity changed during the step.
+    pub change: F,
+}
+
+/// Callback given the violations found by a conservation monitor.
+pub type ViolationCallback<F> = Box<dyn FnMut(&ConservationViolation<F>)>;
+
+/// Watches a conserved quantity of a world, calling back when it changes more than the
+/// tolerance during a step.
+pub struct ConservationMonitor<F: num_traits::Float = f64> {
+    /// Quantity watched.
+    pub quantity: ConservedQuantity,
+
+    /// Largest change of the quantity allowed during a step.
+    pub tolerance: F,
+
+    callback: ViolationCallback<F>,
+}
+
+impl<F: num_traits::Float> ConservationMonitor<F> {
+    /// Creates a new monitor watching the given quantity.
+    pub fn new(quantity: ConservedQuantity, tolerance: F, callback: ViolationCallback<F>) -> Self {
+        Self {
+            quantity,
+            tolerance,
+            callback,
+        }
+    }
+
+    /// Calls back if the quantity changed more than the tolerance between the given
+    /// diagnostics.
+    pub(crate) fn check(&mut self, before: &Diagnostics<F>, after: &Diagnostics<F>) {
+        let change = before.change(after, self.quantity);
+        if change > self.tolerance {
+            (self.callback)(&ConservationViolation {
+                quantity: self.quantity,
+                before: *before,
+                after: *after,
+                change,
+            });
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::diagnostics::*;
+use crate::gravity::{GravityField, GravitySource};
+use crate::rigid_body::{BodyType, RigidBody};
+use crate::shape::{Shape, Sphere};
+use crate::world::{World, WorldConfig};
+use math::{Matrix3, Vector3};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+#[test]
+fn measure() {
+    let mut body = RigidBody::new(Vector3::new(0.0, 3.0, 0.0), 2.0, &Matrix3::identity());
+    body.velocity = Vector3::new(1.0, 0.0, 0.0);
+    body.rotation = Vector3::new(0.0, 0.0, 2.0);
+    let mut fixed = body;
+    fixed.set_body_type(BodyType::Kinematic);
+    let gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+
+    // Bodies without finite mass don't count.
+    let diagnostics: Diagnostics = Diagnostics::measure(&[body, fixed], &gravity, &BTreeMap::new());
+    assert!((diagnostics.kinetic_energy - 3.0).abs() < 1e-12);
+    assert!((diagnostics.potential_energy - 60.0).abs() < 1e-12);
+    assert_eq!(63.0, diagnostics.total_energy());
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), diagnostics.linear_momentum);
+    assert_eq!(Vector3::new(0.0, 0.0, -4.0), diagnostics.angular_momentum);
+
+    // Bodies with their own gravity use its potential.
+    let mut overrides = BTreeMap::new();
+    overrides.insert(0, GravitySource::Uniform(Vector3::origin()));
+    let diagnostics = Diagnostics::measure(&[body], &gravity, &overrides);
+    assert_eq!(0.0, diagnostics.potential_energy);
+}
+
+#[test]
+fn conservation_monitors() {
+    let mut world = World::<f64>::new(WorldConfig {
+        linear_damping: 1.0,
+        angular_damping: 1.0,
+        diagnostics: true,
+        ..WorldConfig::default()
+    });
+    let sphere = Sphere::new(0.5);
+    for x in [-2.0, 2.0].iter() {
+        let mut body = RigidBody::new(Vector3::new(*x, 0.0, 0.0), 1.0, &sphere.inertia_tensor(1.0));
+        body.velocity = Vector3::new(-*x, 0.0, 0.0);
+        let body = world.add_body(body);
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Sphere(sphere)));
+    }
+    let violations = Rc::new(RefCell::new(Vec::new()));
+    for quantity in [
+        ConservedQuantity::Energy,
+        ConservedQuantity::LinearMomentum,
+        ConservedQuantity::AngularMomentum,
+    ]
+    .iter()
+    {
+        let violations = violations.clone();
+        world.conservation_monitors.push(ConservationMonitor::new(
+            *quantity,
+            1e-6,
+            Box::new(move |violation| violations.borrow_mut().push(*violation)),
+        ));
+    }
+    for _ in 0..60 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+
+    // The balls stick together when they collide, losing their energy but not their momentum.
+    let violations = violations.borrow();
+    assert!(!violations.is_empty());
+    for violation in violations.iter() {
+        assert_eq!(ConservedQuantity::Energy, violation.quantity);
+        assert!(violation.after.total_energy() < violation.before.total_energy());
+        assert!(violation.change > 1e-6);
+    }
+    let diagnostics = world.diagnostics().unwrap();
+    assert!(diagnostics.kinetic_energy < 4.0);
+    assert!(diagnostics.linear_momentum.magnitude() < 1e-9);
+
+    // Worlds without diagnostics don't measure their bodies.
+    world.config.diagnostics = false;
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+    assert!(world.diagnostics().is_none());
+}
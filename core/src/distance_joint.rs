@@ -0,0 +1,100 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::joint::{Anchors, JointLimits, JointSpring, RowBuilder};
+use crate::rigid_body::RigidBody;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Distance joint, keeping the distance between an anchor point of each body
+/// within a range.
+///
+/// # Remarks
+/// With the same minimum and maximum distance the joint behaves as a rigid rod,
+/// and with a minimum distance of zero as a rope. A spring makes the joint stretch
+/// beyond its range, pulling the anchors back elastically.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DistanceJoint<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector3<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector3<F>,
+
+    /// Smallest distance allowed between the anchors.
+    pub min_distance: F,
+
+    /// Largest distance allowed between the anchors.
+    pub max_distance: F,
+
+    /// Spring used outside of the range, if any. Without it, the range is enforced rigidly.
+    pub spring: Option<JointSpring<F>>,
+}
+
+impl<F: num_traits::Float> DistanceJoint<F> {
+    /// Creates a new rigid distance joint between the given anchors and within the given range.
+    pub fn new(
+        local_anchor_one: Vector3<F>,
+        local_anchor_two: Vector3<F>,
+        min_distance: F,
+        max_distance: F,
+    ) -> Self {
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+            min_distance,
+            max_distance,
+            spring: None,
+        }
+    }
+
+    /// Returns the current distance between the anchors.
+    pub fn distance(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> F {
+        let point_one = one.point_in_world_space(&self.local_anchor_one);
+        let point_two = match two {
+            Some(two) => two.point_in_world_space(&self.local_anchor_two),
+            None => self.local_anchor_two,
+        };
+        point_one.vector_sub(&point_two).magnitude()
+    }
+
+    /// Adds the constraint rows of the joint.
+    pub(crate) fn rows(&self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let anchors = Anchors::new(
+            builder.bodies(),
+            &self.local_anchor_one,
+            &self.local_anchor_two,
+            bodies,
+        );
+        let offset = anchors.point_one.vector_sub(&anchors.point_two);
+        let distance = offset.magnitude();
+        let direction = if distance > F::epsilon() {
+            offset.scalar_mul(F::one() / distance)
+        } else {
+            // Anchors on top of each other can be pushed apart in any direction.
+            Vector3::new(F::zero(), F::one(), F::zero())
+        };
+
+        builder.set_spring(self.spring);
+        let linear = direction;
+        let angular_one = anchors.relative_one.cross_product(&direction);
+        let angular_two = anchors.relative_two.cross_product(&direction);
+        if self.min_distance >= self.max_distance {
+            builder.add(
+                0,
+                linear,
+                angular_one,
+                angular_two,
+                distance - self.max_distance,
+            );
+        } else {
+            let mut limits = JointLimits::new(self.min_distance, self.max_distance);
+            limits.softness = self.spring;
+            builder.limit(0, &limits, distance, linear, angular_one, angular_two);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::particle_force::ParticleForceGenerator;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Default maximum number of particles alive at once for an emitter.
+pub const DEFAULT_MAX_EMITTED_PARTICLES: usize = 4096;
+
+/// Default seed of the random numbers drawn by an emitter.
+pub const DEFAULT_EMITTER_SEED: u64 = 0x853c_49e6_748f_ea9b;
+
+/// Distribution a scalar property of emitted particles is drawn from.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ScalarDistribution<F: num_traits::Float = f64> {
+    /// Always the same value.
+    Constant(F),
+
+    /// Any value between a minimum and a maximum, with the same probability.
+    Uniform(F, F),
+}
+
+/// Distribution the initial velocity of emitted particles is drawn from.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum VelocityDistribution<F: num_traits::Float = f64> {
+    /// Always the same velocity.
+    Constant(Vector3<F>),
+
+    /// Any velocity inside a box, given by its minimum and maximum corners.
+    Box(Vector3<F>, Vector3<F>),
+
+    /// Velocities inside a cone around a direction, with a speed between a minimum and a maximum.
+    Cone {
+        /// Axis of the cone, of unit length.
+        direction: Vector3<F>,
+
+        /// Angle between the axis and the surface of the cone, in radians.
+        half_angle: F,
+
+        /// Slowest speed of the particles.
+        min_speed: F,
+
+        /// Fastest speed of the particles.
+        max_speed: F,
+    },
+
+    /// Velocities in any direction, with a speed between a minimum and a maximum.
+    Sphere(F, F),
+}
+
+/// Region new particles are spawned in, relative to the position of the emitter.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum EmitterShape<F: num_traits::Float = f64> {
+    /// Every particle is spawned at the position of the emitter.
+    Point,
+
+    /// Particles are spawned inside a box with the given half-sizes.
+    Box(Vector3<F>),
+
+    /// Particles are spawned inside a sphere with the given radius.
+    Sphere(F),
+}
+
+/// Spawns particles at a steady rate, moving them until their lifetime runs out.
+///
+/// # Remarks
+/// Particles are integrated with the gravity and the force generators of the emitter, which are
+/// asked for the force of every live particle, so visual effects like sparks or debris can be
+/// simulated without a particle world. Random numbers are drawn from a generator seeded by the
+/// emitter, so the same emitter always spawns the same particles.
+pub struct Emitter<F: num_traits::Float = f64> {
+    /// Particles alive, in the order they were spawned.
+    pub particles: Vec<Particle<F>>,
+
+    /// Position new particles are spawned around.
+    pub position: Vector3<F>,
+
+    /// Region new particles are spawned in.
+    pub shape: EmitterShape<F>,
+
+    /// Number of particles spawned every second.
+    pub rate: F,
+
+    /// Distribution of the initial velocity of new particles.
+    pub velocity: VelocityDistribution<F>,
+
+    /// Distribution of the lifetime of new particles, in seconds.
+    pub lifetime: ScalarDistribution<F>,
+
+    /// Distribution of the mass of new particles.
+    pub mass: ScalarDistribution<F>,
+
+    /// Damping of new particles.
+    pub damping: F,
+
+    /// Acceleration due to gravity.
+    pub gravity: Vector3<F>,
+
+    /// Force generators applied to every live particle.
+    pub forces: Vec<Box<dyn ParticleForceGenerator<F>>>,
+
+    /// Maximum number of particles alive at once.
+    pub max_particles: usize,
+
+    /// Whether the emitter keeps spawning particles.
+    pub active: bool,
+
+    ages: Vec<F>,
+    lifetimes: Vec<F>,
+    pending: F,
+    state: u64,
+}
+
+impl<F: num_traits::Float> Emitter<F> {
+    /// Creates a new active emitter at the given position, spawning particles at rest with
+    /// unit mass and a lifetime of one second.
+    pub fn new(position: Vector3<F>, rate: F) -> Self {
+        Self {
+            particles: Vec::new(),
+            position,
+            shape: EmitterShape::Point,
+            rate,
+            velocity: VelocityDistribution::Constant(Vector3::origin()),
+            lifetime: ScalarDistribution::Constant(num_traits::one()),
+            mass: ScalarDistribution::Constant(num_traits::one()),
+            damping: num_traits::one(),
+            gravity: Vector3::origin(),
+            forces: Vec::new(),
+            max_particles: DEFAULT_MAX_EMITTED_PARTICLES,
+            active: true,
+            ages: Vec::new(),
+            lifetimes: Vec::new(),
+            pending: num_traits::zero(),
+            state: DEFAULT_EMITTER_SEED,
+        }
+    }
+
+    /// Seeds the random numbers drawn by the emitter.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the emitter.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.state = seed;
+        self
+    }
+
+    /// Returns the time a particle has been alive for.
+    pub fn age(&self, particle: usize) -> F {
+        self.ages[particle]
+    }
+
+    /// Returns the time a particle lives for.
+    pub fn lifetime(&self, particle: usize) -> F {
+        self.lifetimes[particle]
+    }
+
+    /// Returns the positions of the live particles, ready to be drawn as instances.
+    pub fn positions(&self) -> Vec<Vector3<F>> {
+        self.particles
+            .iter()
+            .map(|particle| particle.position)
+            .collect()
+    }
+
+    /// Spawns the given number of particles at once, as long as there is room for them.
+    pub fn burst(&mut self, count: usize) {
+        for _ in 0..count.min(self.max_particles.saturating_sub(self.particles.len())) {
+            self.spawn();
+        }
+    }
+
+    /// Processes all the physics for the emitter.
+    ///
+    /// # Remarks
+    /// The particles whose lifetime ran out are removed first, the rest are moved, and finally
+    /// the particles due are spawned, starting to move in the next frame.
+    pub fn run_physics(&mut self, duration: F) {
+        // First remove the particles that died.
+        let mut alive = 0;
+        for index in 0..self.particles.len() {
+            let age = self.ages[index] + duration;
+            if age < self.lifetimes[index] {
+                self.particles.swap(alive, index);
+                self.lifetimes.swap(alive, index);
+                self.ages[alive] = age;
+                alive += 1;
+            }
+        }
+        self.particles.truncate(alive);
+        self.ages.truncate(alive);
+        self.lifetimes.truncate(alive);
+
+        // Then move the particles left.
+        for index in 0..self.particles.len() {
+            for generator in self.forces.iter_mut() {
+                generator.update_force(&mut self.particles, index, duration);
+            }
+        }
+        for particle in self.particles.iter_mut() {
+            particle.acceleration = self.gravity;
+            particle.integrate(duration);
+        }
+
+        // Finally spawn the particles due in this frame.
+        if self.active {
+            self.pending = self.pending + self.rate * duration;
+            let due = self.pending.floor();
+            self.pending = self.pending - due;
+            self.burst(due.to_usize().unwrap_or(0));
+        }
+    }
+
+    /// Spawns a single particle.
+    fn spawn(&mut self) {
+        let offset = match self.shape {
+            EmitterShape::Point => Vector3::origin(),
+            EmitterShape::Box(half_size) => Vector3::new(
+                half_size.x * self.signed_unit(),
+                half_size.y * self.signed_unit(),
+                half_size.z * self.signed_unit(),
+            ),
+            EmitterShape::Sphere(radius) => {
+                let direction = self.direction();
+                direction.scalar_mul(radius * self.unit().cbrt())
+            }
+        };
+        let velocity = match self.velocity {
+            VelocityDistribution::Constant(velocity) => velocity,
+            VelocityDistribution::Box(min, max) => Vector3::new(
+                self.between(min.x, max.x),
+                self.between(min.y, max.y),
+                self.between(min.z, max.z),
+            ),
+            VelocityDistribution::Cone {
+                direction,
+                half_angle,
+                min_speed,
+                max_speed,
+            } => {
+                // Pick the direction uniformly over the cap of the unit sphere inside the cone.
+                let cosine = self.between(half_angle.cos(), F::one());
+                let sine = (F::one() - cosine * cosine).max(F::zero()).sqrt();
+                let angle = self.between(F::zero(), math::real(std::f64::consts::TAU));
+                let helper = if direction.x.abs() < math::real(0.9) {
+                    Vector3::new(F::one(), F::zero(), F::zero())
+                } else {
+                    Vector3::new(F::zero(), F::one(), F::zero())
+                };
+                let tangent = direction.cross_product(&helper).normalize();
+                let bitangent = direction.cross_product(&tangent);
+                let spread = tangent
+                    .scalar_mul(angle.cos())
+                    .vector_add(&bitangent.scalar_mul(angle.sin()));
+                direction
+                    .scalar_mul(cosine)
+                    .vector_add(&spread.scalar_mul(sine))
+                    .scalar_mul(self.between(min_speed, max_speed))
+            }
+            VelocityDistribution::Sphere(min_speed, max_speed) => {
+                let direction = self.direction();
+                direction.scalar_mul(self.between(min_speed, max_speed))
+            }
+        };
+        let lifetime = self.draw(self.lifetime);
+        let mass = self.draw(self.mass);
+
+        let mut particle = Particle::new(self.position.vector_add(&offset), mass);
+        particle.velocity = velocity;
+        particle.damping = self.damping;
+        particle.acceleration = self.gravity;
+        self.particles.push(particle);
+        self.ages.push(F::zero());
+        self.lifetimes.push(lifetime);
+    }
+
+    /// Draws a value from the given distribution.
+    fn draw(&mut self, distribution: ScalarDistribution<F>) -> F {
+        match distribution {
+            ScalarDistribution::Constant(value) => value,
+            ScalarDistribution::Uniform(min, max) => self.between(min, max),
+        }
+    }
+
+    /// Draws a direction of unit length, uniformly over the sphere.
+    fn direction(&mut self) -> Vector3<F> {
+        let z = self.signed_unit();
+        let angle = self.between(F::zero(), math::real(std::f64::consts::TAU));
+        let radius = (F::one() - z * z).max(F::zero()).sqrt();
+        Vector3::new(radius * angle.cos(), radius * angle.sin(), z)
+    }
+
+    /// Draws a number between a minimum and a maximum.
+    fn between(&mut self, min: F, max: F) -> F {
+        min + (max - min) * self.unit()
+    }
+
+    /// Draws a number between `-1` and `1`.
+    fn signed_unit(&mut self) -> F {
+        self.between(-F::one(), F::one())
+    }
+
+    /// Draws a number between `0` and `1`.
+    fn unit(&mut self) -> F {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        math::real((self.state >> 11) as f64 / (1u64 << 53) as f64)
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::emitter::*;
+use crate::particle_force::ParticleDrag;
+use math::Vector3;
+
+#[test]
+fn rate_and_lifetime() {
+    let mut emitter = Emitter::<f64>::new(Vector3::new(0.0, 1.0, 0.0), 30.0);
+    emitter.lifetime = ScalarDistribution::Constant(0.5);
+    emitter.velocity = VelocityDistribution::Constant(Vector3::new(2.0, 0.0, 0.0));
+
+    // Half a second's worth of particles is alive at any time.
+    for _ in 0..120 {
+        emitter.run_physics(1.0 / 60.0);
+        assert!(emitter.particles.len() <= 16);
+    }
+    assert!((15..=16).contains(&emitter.particles.len()));
+    for index in 0..emitter.particles.len() {
+        assert!(emitter.age(index) < emitter.lifetime(index));
+        let travelled = emitter.particles[index].position.x;
+        assert!((travelled - 2.0 * emitter.age(index)).abs() < 1e-9);
+    }
+
+    // Stopping the emitter lets the particles die out.
+    emitter.active = false;
+    for _ in 0..40 {
+        emitter.run_physics(1.0 / 60.0);
+    }
+    assert!(emitter.particles.is_empty());
+
+    // Bursts are capped to the maximum number of particles.
+    emitter.max_particles = 10;
+    emitter.burst(25);
+    assert_eq!(10, emitter.positions().len());
+}
+
+#[test]
+fn distributions() {
+    let spawn = |seed| {
+        let mut emitter = Emitter::<f64>::new(Vector3::origin(), 0.0).with_seed(seed);
+        emitter.shape = EmitterShape::Sphere(0.5);
+        emitter.velocity = VelocityDistribution::Cone {
+            direction: Vector3::new(0.0, 1.0, 0.0),
+            half_angle: 0.3,
+            min_speed: 4.0,
+            max_speed: 5.0,
+        };
+        emitter.mass = ScalarDistribution::Uniform(1.0, 2.0);
+        emitter.burst(200);
+        emitter
+    };
+
+    let emitter = spawn(7);
+    for particle in emitter.particles.iter() {
+        assert!(particle.position.magnitude() <= 0.5);
+        let speed = particle.velocity.magnitude();
+        assert!((4.0 - 1e-9..=5.0 + 1e-9).contains(&speed));
+        assert!(particle.velocity.y / speed >= 0.3f64.cos() - 1e-9);
+        assert!((1.0..=2.0).contains(&particle.mass()));
+    }
+
+    // The same seed spawns the same particles, another one doesn't.
+    assert_eq!(emitter.positions(), spawn(7).positions());
+    assert_ne!(emitter.positions(), spawn(8).positions());
+}
+
+#[test]
+fn forces() {
+    let mut emitter = Emitter::<f64>::new(Vector3::origin(), 0.0);
+    emitter.velocity = VelocityDistribution::Constant(Vector3::new(10.0, 0.0, 0.0));
+    emitter.lifetime = ScalarDistribution::Constant(10.0);
+    emitter.gravity = Vector3::new(0.0, -10.0, 0.0);
+    emitter.forces.push(Box::new(ParticleDrag::new(1.0, 0.0)));
+    emitter.burst(1);
+
+    // Gravity pulls the spark down while drag slows it down.
+    for _ in 0..60 {
+        emitter.run_physics(1.0 / 60.0);
+    }
+    let spark = &emitter.particles[0];
+    assert!(spark.position.y < 0.0);
+    assert!(spark.velocity.x < 5.0 && spark.velocity.x > 0.0);
+}
@@ -0,0 +1,127 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::manifold::ContactManifold;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Stage of the contact between a pair of colliders reported by a contact event.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ContactEventKind {
+    /// The colliders started touching this step.
+    Started,
+
+    /// The colliders were already touching, and still are.
+    Persisted,
+
+    /// The colliders stopped touching this step.
+    Stopped,
+}
+
+/// Notification of a change, or lack of it, in the contact between a pair of colliders.
+///
+/// # Remarks
+/// The contact data is the one of the manifold between the bodies of the colliders after
+/// solving the step, shared by every pair of colliders attached to those bodies.
+/// Events of colliders that stopped touching have no contact data.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ContactEvent<F: num_traits::Float = f64> {
+    /// Stage of the contact.
+    pub kind: ContactEventKind,
+
+    /// Indices of the colliders, with `None` for the scenery planes.
+    pub colliders: (usize, Option<usize>),
+
+    /// Indices of the bodies of the colliders, with `None` for the scenery planes.
+    pub bodies: (usize, Option<usize>),
+
+    /// Number of points in the manifold.
+    pub points: usize,
+
+    /// Deepest point of the manifold in world space.
+    pub point: Vector3<F>,
+
+    /// Unit normal of the contact, pointing from the second collider towards the first.
+    pub normal: Vector3<F>,
+
+    /// Penetration of the deepest point of the manifold.
+    pub penetration: F,
+
+    /// Sum of the normal impulses applied to the points of the manifold during the step.
+    pub normal_impulse: F,
+
+    /// Sum of the magnitudes of the friction impulses applied to the points of the manifold
+    /// during the step.
+    pub tangent_impulse: F,
+}
+
+impl<F: num_traits::Float> ContactEvent<F> {
+    /// Creates a new event without contact data between the given colliders and bodies.
+    pub fn new(
+        kind: ContactEventKind,
+        colliders: (usize, Option<usize>),
+        bodies: (usize, Option<usize>),
+    ) -> Self {
+        Self {
+            kind,
+            colliders,
+            bodies,
+            points: 0,
+            point: Vector3::origin(),
+            normal: Vector3::origin(),
+            penetration: num_traits::zero(),
+            normal_impulse: num_traits::zero(),
+            tangent_impulse: num_traits::zero(),
+        }
+    }
+
+    /// Fills in the contact data of the event from the given manifold.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the event.
+    pub fn with_manifold(&mut self, manifold: &ContactManifold<F>) -> &mut Self {
+        self.points = manifold.len();
+        self.normal_impulse = num_traits::zero();
+        self.tangent_impulse = num_traits::zero();
+        let mut deepest = None;
+        for point in manifold.points.iter() {
+            let [one, two] = point.tangent_impulses;
+            self.normal_impulse = self.normal_impulse + point.normal_impulse;
+            self.tangent_impulse = self.tangent_impulse + (one * one + two * two).sqrt();
+            if deepest.is_none_or(|depth| point.contact.penetration > depth) {
+                deepest = Some(point.contact.penetration);
+                self.point = point.contact.contact_point;
+                self.normal = point.contact.contact_normal;
+                self.penetration = point.contact.penetration;
+            }
+        }
+        self
+    }
+}
+
+/// Stage of the overlap between a sensor and another collider reported by a sensor event.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum SensorEventKind {
+    /// The colliders started overlapping this step.
+    Entered,
+
+    /// The colliders stopped overlapping this step.
+    Exited,
+}
+
+/// Notification of a collider entering or exiting a sensor.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct SensorEvent {
+    /// Stage of the overlap.
+    pub kind: SensorEventKind,
+
+    /// Indices of the colliders, at least one of them being a sensor.
+    pub colliders: (usize, usize),
+
+    /// Indices of the bodies of the colliders.
+    pub bodies: (usize, usize),
+}
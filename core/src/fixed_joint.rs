@@ -0,0 +1,130 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::joint::{Anchors, JointSpring, RowBuilder};
+use crate::rigid_body::RigidBody;
+use math::{Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Fixed (or weld) joint, locking the relative position and orientation of two bodies
+/// so they move as a single one.
+///
+/// # Remarks
+/// Rigid fixed joints drift back into place over a few frames when pulled apart.
+/// With some compliance, the joint behaves like a stiff spring instead, letting the
+/// bodies give in slightly under load.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FixedJoint<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector3<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector3<F>,
+
+    /// Orientation of the first body relative to the second one,
+    /// or to the world for joints with the scenery.
+    pub relative_orientation: Quaternion<F>,
+
+    /// Inverse of the stiffness of the joint. Zero makes the joint rigid.
+    pub compliance: F,
+
+    /// Damping coefficient of compliant joints, opposing the relative motion of the bodies.
+    pub damping: F,
+}
+
+impl<F: num_traits::Float> FixedJoint<F> {
+    /// Creates a new rigid fixed joint between the given anchors,
+    /// keeping the given relative orientation.
+    pub fn new(
+        local_anchor_one: Vector3<F>,
+        local_anchor_two: Vector3<F>,
+        relative_orientation: Quaternion<F>,
+    ) -> Self {
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+            relative_orientation,
+            compliance: num_traits::zero(),
+            damping: num_traits::zero(),
+        }
+    }
+
+    /// Creates a new rigid fixed joint between the given bodies at the given anchor,
+    /// in world space, keeping their current relative position and orientation.
+    pub fn from_world(one: &RigidBody<F>, two: Option<&RigidBody<F>>, anchor: &Vector3<F>) -> Self {
+        let (local_anchor_two, relative_orientation) = match two {
+            Some(two) => (
+                two.point_in_local_space(anchor),
+                two.orientation.conjugate().quaternion_mul(&one.orientation),
+            ),
+            None => (*anchor, one.orientation),
+        };
+        Self::new(
+            one.point_in_local_space(anchor),
+            local_anchor_two,
+            relative_orientation,
+        )
+    }
+
+    /// Returns the rotation, as a scaled axis in world space, taking the first body
+    /// from its target orientation to its current one.
+    pub fn angular_error(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> Vector3<F> {
+        orientation_error(one, two, &self.relative_orientation)
+    }
+
+    /// Adds the constraint rows of the joint.
+    pub(crate) fn rows(&self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        if self.compliance > F::zero() {
+            builder.set_spring(Some(JointSpring::new(
+                F::one() / self.compliance,
+                self.damping,
+            )));
+        }
+
+        let pair = builder.bodies();
+        let anchors = Anchors::new(pair, &self.local_anchor_one, &self.local_anchor_two, bodies);
+        builder.point(0, &anchors);
+
+        let error = self.angular_error(&bodies[pair.0], pair.1.map(|body| &bodies[body]));
+        let axes = [
+            Vector3::new(F::one(), F::zero(), F::zero()),
+            Vector3::new(F::zero(), F::one(), F::zero()),
+            Vector3::new(F::zero(), F::zero(), F::one()),
+        ];
+        for (index, axis) in axes.iter().enumerate() {
+            builder.add(
+                3 + index,
+                Vector3::origin(),
+                *axis,
+                *axis,
+                error.dot_product(axis),
+            );
+        }
+    }
+}
+
+/// Returns the rotation, as a scaled axis in world space, taking the first body from the
+/// given orientation relative to the second one, or to the world, to its current one.
+pub(crate) fn orientation_error<F: num_traits::Float>(
+    one: &RigidBody<F>,
+    two: Option<&RigidBody<F>>,
+    relative_orientation: &Quaternion<F>,
+) -> Vector3<F> {
+    let target = match two {
+        Some(two) => two.orientation.quaternion_mul(relative_orientation),
+        None => *relative_orientation,
+    };
+    let delta = one.orientation.quaternion_mul(&target.conjugate());
+
+    // Both signs of the quaternion are the same rotation, keep the shortest one.
+    let scale = if delta.r < F::zero() {
+        math::real(-2.0)
+    } else {
+        math::real(2.0)
+    };
+    Vector3::new(delta.i, delta.j, delta.k).scalar_mul(scale)
+}
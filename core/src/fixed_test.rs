@@ -0,0 +1,88 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::gravity::{GravityField, GravitySource};
+use crate::particle::Particle;
+use crate::particle_force::ParticleGravity;
+use crate::particle_world::ParticleWorld;
+use crate::plane::Plane;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Cuboid, Shape, Sphere};
+use crate::world::World;
+use math::{Fixed, Vector3};
+use num_traits::ToPrimitive;
+
+fn fixed(value: f64) -> Fixed {
+    math::real(value)
+}
+
+fn vector(x: f64, y: f64, z: f64) -> Vector3<Fixed> {
+    Vector3::new(fixed(x), fixed(y), fixed(z))
+}
+
+#[test]
+fn particles() {
+    let mut world = ParticleWorld::<Fixed>::new(10, 0);
+    let index = world.add_particle(Particle::new(Vector3::origin(), fixed(2.0)));
+    world.registry.add(
+        index,
+        Box::new(ParticleGravity::new(vector(0.0, -10.0, 0.0))),
+    );
+
+    world.start_frame();
+    world.run_physics(fixed(0.5));
+    assert_eq!(vector(0.0, -5.0, 0.0), world.particles[index].velocity);
+}
+
+fn stack() -> World<Fixed> {
+    let mut world = World::<Fixed>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(vector(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(vector(0.0, 1.0, 0.0), fixed(0.0)));
+
+    let sphere = Sphere::new(fixed(0.5));
+    let ball = world.add_body(RigidBody::new(
+        vector(0.0, 3.0, 0.0),
+        fixed(1.0),
+        &sphere.inertia_tensor(fixed(1.0)),
+    ));
+    let ball = world.body_index(ball).unwrap();
+    world.add_collider(Collider::new(ball, Shape::Sphere(sphere)));
+
+    let cuboid = Cuboid::new(vector(0.5, 0.5, 0.5));
+    let mut body = RigidBody::new(
+        vector(0.2, 1.0, 0.0),
+        fixed(2.0),
+        &cuboid.inertia_tensor(fixed(2.0)),
+    );
+    body.rotation = vector(0.0, 1.0, 0.5);
+    let crate_body = world.add_body(body);
+    let crate_body = world.body_index(crate_body).unwrap();
+    world.add_collider(Collider::new(crate_body, Shape::Cuboid(cuboid)));
+    world
+}
+
+#[test]
+fn rigid_bodies() {
+    let mut one = stack();
+    let mut two = stack();
+    for _ in 0..180 {
+        for world in [&mut one, &mut two] {
+            world.start_frame();
+            world.run_physics(fixed(1.0 / 60.0));
+        }
+    }
+
+    // The ball lands on the box, which rests on the ground, the same way every time.
+    let height =
+        |world: &World<Fixed>, body: usize| world.bodies[body].position.y.to_f64().unwrap();
+    assert!((height(&one, 1) - 0.5).abs() < 0.05, "{}", height(&one, 1));
+    assert!((height(&one, 0) - 1.5).abs() < 0.1, "{}", height(&one, 0));
+    assert_eq!(one.bodies[0].position, two.bodies[0].position);
+    assert_eq!(one.bodies[1].orientation, two.bodies[1].orientation);
+}
@@ -0,0 +1,319 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::plane::Plane;
+use crate::spatial::SpatialHash;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Default density of a fluid at rest, that of water in kilograms per cubic meter.
+pub const DEFAULT_FLUID_REST_DENSITY: f64 = 1000.0;
+
+/// Default stiffness of a fluid, the square of the speed of sound in it.
+pub const DEFAULT_FLUID_STIFFNESS: f64 = 50.0;
+
+/// Default dynamic viscosity of a fluid.
+pub const DEFAULT_FLUID_VISCOSITY: f64 = 2.0;
+
+/// Default number of steps a fluid takes every frame.
+pub const DEFAULT_FLUID_SUBSTEPS: usize = 4;
+
+/// Smoothing kernel used to compute the density of a fluid, given the squared distance between
+/// two particles and the smoothing radius.
+pub fn poly6<F: num_traits::Float>(squared_distance: F, radius: F) -> F {
+    let squared_radius = radius * radius;
+    if squared_distance >= squared_radius {
+        return num_traits::zero();
+    }
+
+    let difference = squared_radius - squared_distance;
+    let scale = math::real::<F>(315.0 / (64.0 * std::f64::consts::PI)) / radius.powi(9);
+    scale * difference * difference * difference
+}
+
+/// Gradient of the smoothing kernel used to compute the pressure forces of a fluid, given the
+/// offset between two particles and the smoothing radius.
+pub fn spiky_gradient<F: num_traits::Float>(offset: &Vector3<F>, radius: F) -> Vector3<F> {
+    let distance = offset.magnitude();
+    if distance >= radius || distance <= F::epsilon() {
+        return Vector3::origin();
+    }
+
+    let difference = radius - distance;
+    let scale = math::real::<F>(-45.0 / std::f64::consts::PI) / radius.powi(6);
+    offset.scalar_mul(scale * difference * difference / distance)
+}
+
+/// Laplacian of the smoothing kernel used to compute the viscosity forces of a fluid, given the
+/// distance between two particles and the smoothing radius.
+pub fn viscosity_laplacian<F: num_traits::Float>(distance: F, radius: F) -> F {
+    if distance >= radius {
+        return num_traits::zero();
+    }
+
+    let scale = math::real::<F>(45.0 / std::f64::consts::PI) / radius.powi(6);
+    scale * (radius - distance)
+}
+
+/// Way a fluid in a world interacts with its rigid bodies.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum FluidCoupling {
+    /// The rigid bodies push the fluid around, but the fluid doesn't push them back.
+    OneWay,
+
+    /// The rigid bodies and the fluid push each other.
+    TwoWay,
+}
+
+/// Fluid simulated with smoothed-particle hydrodynamics.
+///
+/// # Remarks
+/// The density of every particle is smoothed over the particles within the smoothing radius,
+/// found through a spatial hash, and the pressure grows with the compression of the fluid.
+/// The pressure and viscosity forces between neighbors then accelerate the particles, which
+/// are kept inside the boundary planes. Fluids added to a world also collide with its rigid
+/// bodies, treating the particles as small spheres, and push them back if coupled both ways.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Fluid<F: num_traits::Float = f64> {
+    /// Particles of the fluid.
+    pub particles: Vec<Particle<F>>,
+
+    /// Half-spaces containing the fluid, with their normals pointing inwards.
+    pub boundaries: Vec<Plane<F>>,
+
+    /// Acceleration due to gravity.
+    pub gravity: Vector3<F>,
+
+    /// Distance within which particles interact.
+    pub smoothing_radius: F,
+
+    /// Density of the fluid at rest.
+    pub rest_density: F,
+
+    /// Stiffness of the fluid, relating its pressure to its compression.
+    pub stiffness: F,
+
+    /// Dynamic viscosity of the fluid.
+    pub viscosity: F,
+
+    /// Radius of the particles when colliding with boundaries and rigid bodies.
+    pub radius: F,
+
+    /// Number of steps taken every frame.
+    pub substeps: usize,
+
+    /// Way the fluid interacts with the rigid bodies of a world.
+    pub coupling: FluidCoupling,
+
+    densities: Vec<F>,
+    pressures: Vec<F>,
+    accelerations: Vec<Vector3<F>>,
+    grid: SpatialHash<F>,
+}
+
+impl<F: num_traits::Float> Fluid<F> {
+    /// Creates a new fluid without particles, with the given smoothing radius.
+    pub fn new(smoothing_radius: F) -> Self {
+        Self {
+            particles: Vec::new(),
+            boundaries: Vec::new(),
+            gravity: Vector3::origin(),
+            smoothing_radius,
+            rest_density: math::real(DEFAULT_FLUID_REST_DENSITY),
+            stiffness: math::real(DEFAULT_FLUID_STIFFNESS),
+            viscosity: math::real(DEFAULT_FLUID_VISCOSITY),
+            radius: smoothing_radius * math::real(0.25),
+            substeps: DEFAULT_FLUID_SUBSTEPS,
+            coupling: FluidCoupling::TwoWay,
+            densities: Vec::new(),
+            pressures: Vec::new(),
+            accelerations: Vec::new(),
+            grid: SpatialHash::new(smoothing_radius),
+        }
+    }
+
+    /// Creates a new fluid filling a box with particles in a grid with the given spacing.
+    ///
+    /// # Remarks
+    /// The smoothing radius is twice the spacing, and the mass of the particles is chosen so
+    /// the particles inside the fluid start at its rest density.
+    pub fn block(min: &Vector3<F>, max: &Vector3<F>, spacing: F, rest_density: F) -> Self {
+        let mut fluid = Self::new(spacing * math::real(2.0));
+        fluid.rest_density = rest_density;
+
+        // Sum the kernel over the neighbors of a particle in the grid.
+        let reach = 2i32;
+        let mut weight = F::zero();
+        for x in -reach..=reach {
+            for y in -reach..=reach {
+                for z in -reach..=reach {
+                    let steps = math::real::<F>((x * x + y * y + z * z) as f64);
+                    weight = weight + poly6(steps * spacing * spacing, fluid.smoothing_radius);
+                }
+            }
+        }
+        let mass = rest_density / weight;
+
+        let count = |low: F, high: F| ((high - low) / spacing).floor().to_usize().unwrap_or(0);
+        let half = spacing * math::real(0.5);
+        for x in 0..count(min.x, max.x) {
+            for y in 0..count(min.y, max.y) {
+                for z in 0..count(min.z, max.z) {
+                    let offset = Vector3::new(
+                        math::real::<F>(x as f64) * spacing + half,
+                        math::real::<F>(y as f64) * spacing + half,
+                        math::real::<F>(z as f64) * spacing + half,
+                    );
+                    fluid.add_particle(min.vector_add(&offset), mass);
+                }
+            }
+        }
+        fluid
+    }
+
+    /// Adds a particle at rest with the given mass to the fluid, returning its index.
+    pub fn add_particle(&mut self, position: Vector3<F>, mass: F) -> usize {
+        self.particles.push(Particle::new(position, mass));
+        self.particles.len() - 1
+    }
+
+    /// Returns the density of a particle, as of the last step.
+    pub fn density(&self, particle: usize) -> F {
+        self.densities
+            .get(particle)
+            .copied()
+            .unwrap_or(self.rest_density)
+    }
+
+    /// Returns the pressure of a particle, as of the last step.
+    pub fn pressure(&self, particle: usize) -> F {
+        self.pressures
+            .get(particle)
+            .copied()
+            .unwrap_or(num_traits::zero())
+    }
+
+    /// Processes all the physics for the fluid.
+    ///
+    /// # Remarks
+    /// The forces accumulated by the particles act during every step, and are cleared at the end.
+    pub fn run_physics(&mut self, duration: F) {
+        let substeps = self.substeps.max(1);
+        let step = duration / math::real(substeps as f64);
+        for _ in 0..substeps {
+            self.step(step);
+        }
+        self.clear_accumulators();
+    }
+
+    /// Moves the particles of the fluid for a single step, keeping them inside the boundaries.
+    pub(crate) fn step(&mut self, duration: F) {
+        self.update_densities();
+        self.update_accelerations();
+
+        let zero = F::zero();
+        for (particle, acceleration) in self.particles.iter_mut().zip(self.accelerations.iter()) {
+            if !particle.has_finite_mass() {
+                continue;
+            }
+            let forces = particle.force_accum.scalar_mul(particle.inverse_mass);
+            let acceleration = acceleration.vector_add(&self.gravity).vector_add(&forces);
+            particle
+                .velocity
+                .inplace_vector_add(&acceleration.scalar_mul(duration));
+            particle
+                .position
+                .inplace_vector_add(&particle.velocity.scalar_mul(duration));
+
+            for plane in self.boundaries.iter() {
+                let depth = self.radius - plane.signed_distance(&particle.position);
+                if depth > zero {
+                    particle
+                        .position
+                        .inplace_vector_add(&plane.normal.scalar_mul(depth));
+                    let speed = particle.velocity.dot_product(&plane.normal);
+                    if speed < zero {
+                        particle
+                            .velocity
+                            .inplace_vector_sub(&plane.normal.scalar_mul(speed));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Clears the forces accumulated by the particles.
+    pub(crate) fn clear_accumulators(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.clear_accumulator();
+        }
+    }
+
+    /// Hashes the particles and smooths their densities, deriving their pressures.
+    fn update_densities(&mut self) {
+        self.grid.cell_size = self.smoothing_radius;
+        self.grid.clear();
+        for (index, particle) in self.particles.iter().enumerate() {
+            self.grid.insert(index, &particle.position);
+        }
+
+        let radius = self.smoothing_radius;
+        self.densities.clear();
+        self.pressures.clear();
+        for particle in self.particles.iter() {
+            let mut density = F::zero();
+            self.grid
+                .query_sphere(&particle.position, radius, |neighbor| {
+                    let neighbor = &self.particles[neighbor];
+                    let offset = particle.position.vector_sub(&neighbor.position);
+                    density = density + neighbor.mass() * poly6(offset.squared_magnitude(), radius);
+                    true
+                });
+            // Stretched fluid doesn't pull, so the particles don't clump together.
+            let pressure = self.stiffness * (density - self.rest_density);
+            self.densities.push(density);
+            self.pressures.push(pressure.max(F::zero()));
+        }
+    }
+
+    /// Adds up the pressure and viscosity accelerations between neighboring particles.
+    fn update_accelerations(&mut self) {
+        let radius = self.smoothing_radius;
+        self.accelerations.clear();
+        for (index, particle) in self.particles.iter().enumerate() {
+            let density = self.densities[index];
+            let pressure = self.pressures[index] / (density * density);
+            let mut acceleration = Vector3::origin();
+            self.grid.query_sphere(&particle.position, radius, |other| {
+                if other == index {
+                    return true;
+                }
+                let neighbor = &self.particles[other];
+                let mass = neighbor.mass();
+                let neighbor_density = self.densities[other];
+                let offset = particle.position.vector_sub(&neighbor.position);
+
+                let neighbor_pressure =
+                    self.pressures[other] / (neighbor_density * neighbor_density);
+                let gradient = spiky_gradient(&offset, radius);
+                acceleration.inplace_vector_sub(
+                    &gradient.scalar_mul(mass * (pressure + neighbor_pressure)),
+                );
+
+                let laplacian = viscosity_laplacian(offset.magnitude(), radius);
+                let relative = neighbor.velocity.vector_sub(&particle.velocity);
+                acceleration.inplace_vector_add(
+                    &relative.scalar_mul(
+                        self.viscosity * mass * laplacian / (neighbor_density * density),
+                    ),
+                );
+                true
+            });
+            self.accelerations.push(acceleration);
+        }
+    }
+}
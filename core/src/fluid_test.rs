@@ -0,0 +1,97 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::fluid::*;
+use crate::plane::Plane;
+use math::Vector3;
+
+#[test]
+fn kernels() {
+    // The density kernel adds up to one over its support.
+    let radius = 0.5;
+    let step = radius / 40.0;
+    let mut total = 0.0;
+    for index in 0..40 {
+        let distance = (index as f64 + 0.5) * step;
+        let shell = 4.0 * std::f64::consts::PI * distance * distance * step;
+        total += poly6(distance * distance, radius) * shell;
+    }
+    assert!((total - 1.0).abs() < 1e-3);
+    assert_eq!(0.0, poly6(0.25, radius));
+
+    // The pressure gradient points away from the neighbor, towards the particle.
+    let gradient = spiky_gradient(&Vector3::new(0.2, 0.0, 0.0), radius);
+    assert!(gradient.x < 0.0 && gradient.y == 0.0 && gradient.z == 0.0);
+    assert_eq!(
+        Vector3::origin(),
+        spiky_gradient(&Vector3::origin(), radius)
+    );
+    assert_eq!(
+        Vector3::origin(),
+        spiky_gradient(&Vector3::new(0.0, 0.6, 0.0), radius)
+    );
+    assert!(viscosity_laplacian(0.1, radius) > viscosity_laplacian(0.4, radius));
+    assert_eq!(0.0, viscosity_laplacian(0.5, radius));
+}
+
+#[test]
+fn rest_density() {
+    let mut fluid = Fluid::<f64>::block(
+        &Vector3::origin(),
+        &Vector3::new(1.0, 1.0, 1.0),
+        0.1,
+        1000.0,
+    );
+    assert_eq!(1000, fluid.particles.len());
+    assert_eq!(0.2, fluid.smoothing_radius);
+
+    // Without gravity, the inside of the block stays at rest.
+    fluid.run_physics(1.0 / 60.0);
+    let center = fluid
+        .particles
+        .iter()
+        .position(|particle| {
+            (particle.position - Vector3::new(0.45, 0.45, 0.45)).magnitude() < 1e-9
+        })
+        .unwrap();
+    assert!((fluid.density(center) - 1000.0).abs() < 1e-6);
+    assert!(fluid.pressure(center).abs() < 1e-3);
+    assert!(fluid.particles[center].velocity.magnitude() < 1e-9);
+}
+
+#[test]
+fn dam_break() {
+    let mut fluid = Fluid::<f64>::block(
+        &Vector3::origin(),
+        &Vector3::new(0.4, 0.8, 0.4),
+        0.1,
+        1000.0,
+    );
+    fluid.gravity = Vector3::new(0.0, -10.0, 0.0);
+    fluid.boundaries = vec![
+        Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0),
+        Plane::new(Vector3::new(1.0, 0.0, 0.0), 0.0),
+        Plane::new(Vector3::new(-1.0, 0.0, 0.0), -1.2),
+        Plane::new(Vector3::new(0.0, 0.0, 1.0), 0.0),
+        Plane::new(Vector3::new(0.0, 0.0, -1.0), -0.4),
+    ];
+
+    // The column of water collapses and spreads over the floor of the tank.
+    for _ in 0..180 {
+        fluid.run_physics(1.0 / 60.0);
+    }
+    let count = fluid.particles.len() as f64;
+    let mut height = 0.0;
+    let mut spread: f64 = 0.0;
+    for particle in fluid.particles.iter() {
+        assert!(particle.position.y >= fluid.radius - 1e-9);
+        assert!(particle.position.x <= 1.2 - fluid.radius + 1e-9);
+        height += particle.position.y / count;
+        spread = spread.max(particle.position.x);
+    }
+    assert!(height < 0.3);
+    assert!(spread > 0.8);
+}
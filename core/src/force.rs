@@ -0,0 +1,267 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::rigid_body::RigidBody;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A force generator can be asked to add forces to one or more rigid bodies.
+pub trait ForceGenerator<F: num_traits::Float = f64> {
+    /// Calculates and updates the force applied to the rigid body at index `body`.
+    ///
+    /// # Remarks
+    /// The whole slice of bodies is available so generators can depend on the
+    /// state of other bodies in the simulation.
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, duration: F);
+}
+
+/// Shared force generators can be registered while the caller keeps a handle to them,
+/// allowing their inputs to be changed between frames.
+impl<F: num_traits::Float, G: ForceGenerator<F>> ForceGenerator<F> for Rc<RefCell<G>> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, duration: F) {
+        self.borrow_mut().update_force(bodies, body, duration);
+    }
+}
+
+/// Force generator that applies a gravitational force.
+/// One instance can be used for multiple rigid bodies.
+///
+/// # Remarks
+/// Gravity is a persistent field rather than a disturbance, so it's not applied
+/// to sleeping bodies and never wakes them up.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Gravity<F: num_traits::Float = f64> {
+    /// Acceleration due to gravity.
+    pub gravity: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Gravity<F> {
+    /// Creates a new gravity generator with the given acceleration.
+    pub fn new(gravity: Vector3<F>) -> Self {
+        Self { gravity }
+    }
+}
+
+impl<F: num_traits::Float> ForceGenerator<F> for Gravity<F> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, _duration: F) {
+        let body = &mut bodies[body];
+
+        // Check that the body doesn't have infinite mass, and is not sleeping.
+        if !body.has_finite_mass() || !body.is_awake {
+            return;
+        }
+
+        let force = self.gravity.scalar_mul(body.mass());
+        body.add_force(&force);
+    }
+}
+
+/// Force generator that applies thrust at a fixed point of a rigid body.
+///
+/// # Remarks
+/// The direction and throttle are inputs that can be changed at each frame.
+/// Thrust applied off the center of mass also generates torque.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Thruster<F: num_traits::Float = f64> {
+    /// Point where the thrust is applied, in body space.
+    pub point: Vector3<F>,
+
+    /// Direction of the thrust, in body space.
+    pub direction: Vector3<F>,
+
+    /// Magnitude of the thrust at full throttle.
+    pub max_thrust: F,
+
+    /// Current throttle input, between `0` (off) and `1` (full thrust).
+    pub throttle: F,
+}
+
+impl<F: num_traits::Float> Thruster<F> {
+    /// Creates a new thruster, initially turned off.
+    pub fn new(point: Vector3<F>, direction: Vector3<F>, max_thrust: F) -> Self {
+        Self {
+            point,
+            direction,
+            max_thrust,
+            throttle: num_traits::zero(),
+        }
+    }
+
+    /// Sets the throttle input, clamped between `0` and `1`.
+    pub fn set_throttle(&mut self, throttle: F) -> &mut Self {
+        self.throttle = throttle.max(num_traits::zero()).min(num_traits::one());
+        self
+    }
+
+    /// Sets the direction of the thrust, in body space.
+    pub fn set_direction(&mut self, direction: Vector3<F>) -> &mut Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Returns the thrust force generated for the given rigid body, in world space.
+    pub fn thrust(&self, body: &RigidBody<F>) -> Vector3<F> {
+        body.direction_in_world_space(&self.direction.normalize())
+            .scalar_mul(self.max_thrust * self.throttle)
+    }
+}
+
+impl<F: num_traits::Float> ForceGenerator<F> for Thruster<F> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, _duration: F) {
+        if self.throttle <= num_traits::zero() {
+            return;
+        }
+
+        let body = &mut bodies[body];
+        let force = self.thrust(body);
+        body.add_force_at_body_point(&force, &self.point);
+    }
+}
+
+/// Coulomb's constant, in N m^2 / C^2.
+pub const COULOMB_CONSTANT: f64 = 8.987_551_792_3e9;
+
+/// Magnetic constant divided by `4 * PI`, in N / A^2.
+pub const MAGNETIC_CONSTANT: f64 = 1e-7;
+
+/// Returns the inverse-square force exerted on a point at `position` by another point at
+/// `other`, for the given coupling coefficient (product of the charges times the constant).
+/// Positive coefficients repel, negative coefficients attract.
+///
+/// # Remarks
+/// The squared distance is softened by `softening^2`, so the force stays bounded
+/// when both points get arbitrarily close.
+pub(crate) fn inverse_square_force<F: num_traits::Float>(
+    position: &Vector3<F>,
+    other: &Vector3<F>,
+    coefficient: F,
+    softening: F,
+) -> Vector3<F> {
+    let offset = position.vector_sub(other);
+    let squared_distance = offset.squared_magnitude() + softening * softening;
+    if squared_distance <= num_traits::zero() {
+        return Vector3::origin();
+    }
+    offset
+        .normalize()
+        .scalar_mul(coefficient / squared_distance)
+}
+
+/// Force generator that applies an inverse-square electrostatic or magnetic force
+/// between two point charges (or magnetic poles) located at the center of two rigid bodies.
+/// Like charges repel each other, opposite charges attract.
+///
+/// # Remarks
+/// The force is only applied to the body the generator is registered to;
+/// register a second generator on the other body for mutual interaction.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PointCharge<F: num_traits::Float = f64> {
+    /// Index of the rigid body holding the other charge.
+    pub other: usize,
+
+    /// Charge of the body the force is applied to.
+    pub charge: F,
+
+    /// Charge of the other body.
+    pub other_charge: F,
+
+    /// Proportionality constant of the force law.
+    pub constant: F,
+
+    /// Softening length used to avoid the singularity at zero distance.
+    pub softening: F,
+}
+
+impl<F: num_traits::Float> PointCharge<F> {
+    /// Creates a new electrostatic force generator between two charges, in coulombs.
+    pub fn electrostatic(other: usize, charge: F, other_charge: F, softening: F) -> Self {
+        Self {
+            other,
+            charge,
+            other_charge,
+            constant: math::real(COULOMB_CONSTANT),
+            softening,
+        }
+    }
+
+    /// Creates a new magnetic force generator between two magnetic pole strengths, in A m.
+    pub fn magnetic(other: usize, strength: F, other_strength: F, softening: F) -> Self {
+        Self {
+            other,
+            charge: strength,
+            other_charge: other_strength,
+            constant: math::real(MAGNETIC_CONSTANT),
+            softening,
+        }
+    }
+}
+
+impl<F: num_traits::Float> ForceGenerator<F> for PointCharge<F> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, _duration: F) {
+        let force = inverse_square_force(
+            &bodies[body].position,
+            &bodies[self.other].position,
+            self.constant * self.charge * self.other_charge,
+            self.softening,
+        );
+        bodies[body].add_force(&force);
+    }
+}
+
+/// Holds all the force generators and the rigid bodies they apply to.
+pub struct ForceRegistry<F: num_traits::Float = f64> {
+    registrations: Vec<(usize, Box<dyn ForceGenerator<F>>)>,
+}
+
+impl<F: num_traits::Float> Default for ForceRegistry<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: num_traits::Float> ForceRegistry<F> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            registrations: Vec::new(),
+        }
+    }
+
+    /// Registers the given force generator to apply to the rigid body at index `body`.
+    pub fn add(&mut self, body: usize, generator: Box<dyn ForceGenerator<F>>) {
+        self.registrations.push((body, generator));
+    }
+
+    /// Removes all the registrations for the rigid body at index `body`.
+    pub fn remove(&mut self, body: usize) {
+        self.registrations.retain(|(index, _)| *index != body);
+    }
+
+    /// Clears all registrations from the registry.
+    /// This doesn't affect the bodies themselves, only their connection to generators.
+    pub fn clear(&mut self) {
+        self.registrations.clear();
+    }
+
+    /// Returns the number of registrations.
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    /// Returns true if there are no registrations.
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+
+    /// Calls all the force generators to update the forces of their corresponding bodies.
+    pub fn update_forces(&mut self, bodies: &mut [RigidBody<F>], duration: F) {
+        for (body, generator) in self.registrations.iter_mut() {
+            generator.update_force(bodies, *body, duration);
+        }
+    }
+}
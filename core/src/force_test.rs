@@ -0,0 +1,87 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::force::*;
+use crate::rigid_body::RigidBody;
+use math::{Matrix3, Vector3};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn body() -> RigidBody {
+    RigidBody::new(Vector3::origin(), 2.0, &Matrix3::identity())
+}
+
+#[test]
+fn gravity() {
+    let mut bodies = vec![body()];
+    let mut registry = ForceRegistry::new();
+    registry.add(0, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+    registry.update_forces(&mut bodies, 0.1);
+    assert_eq!(Vector3::new(0.0, -20.0, 0.0), bodies[0].force_accum);
+
+    registry.remove(0);
+    assert!(registry.is_empty());
+}
+
+#[test]
+fn thruster() {
+    let mut bodies = vec![body()];
+    let thruster = Rc::new(RefCell::new(Thruster::new(
+        Vector3::new(0.0, -1.0, 0.0),
+        Vector3::new(0.0, 2.0, 0.0),
+        10.0,
+    )));
+    let mut registry = ForceRegistry::new();
+    registry.add(0, Box::new(thruster.clone()));
+
+    // Thrusters are off by default.
+    registry.update_forces(&mut bodies, 0.1);
+    assert_eq!(Vector3::origin(), bodies[0].force_accum);
+
+    // Centered thrust generates no torque.
+    thruster.borrow_mut().set_throttle(2.0);
+    assert_eq!(1.0, thruster.borrow().throttle);
+    registry.update_forces(&mut bodies, 0.1);
+    assert_eq!(Vector3::new(0.0, 10.0, 0.0), bodies[0].force_accum);
+    assert_eq!(Vector3::origin(), bodies[0].torque_accum);
+    bodies[0].clear_accumulators();
+
+    // Thrust off the center of mass spins the body.
+    thruster
+        .borrow_mut()
+        .set_throttle(0.5)
+        .set_direction(Vector3::new(1.0, 0.0, 0.0));
+    registry.update_forces(&mut bodies, 0.1);
+    assert_eq!(Vector3::new(5.0, 0.0, 0.0), bodies[0].force_accum);
+    assert_eq!(Vector3::new(0.0, 0.0, 5.0), bodies[0].torque_accum);
+}
+
+#[test]
+fn point_charges() {
+    let mut bodies = vec![body(), body()];
+    bodies[1].position = Vector3::new(2.0, 0.0, 0.0);
+    let mut registry = ForceRegistry::new();
+    registry.add(0, Box::new(PointCharge::electrostatic(1, 1e-5, 1e-5, 0.0)));
+    registry.add(1, Box::new(PointCharge::electrostatic(0, 1e-5, 1e-5, 0.0)));
+    registry.update_forces(&mut bodies, 0.1);
+
+    // Like charges repel each other with equal and opposite forces.
+    let expected = COULOMB_CONSTANT * 1e-10 / 4.0;
+    assert!((bodies[0].force_accum.x + expected).abs() < 1e-9);
+    assert!((bodies[1].force_accum.x - expected).abs() < 1e-9);
+    assert_eq!(0.0, bodies[0].force_accum.y);
+
+    // Opposite poles attract, and softening keeps coincident poles bounded.
+    let mut magnetic = PointCharge::magnetic(1, 1.0, -1.0, 1.0);
+    bodies[0].clear_accumulators();
+    magnetic.update_force(&mut bodies, 0, 0.1);
+    assert!((bodies[0].force_accum.x - MAGNETIC_CONSTANT / 5.0).abs() < 1e-18);
+
+    bodies[0].clear_accumulators();
+    bodies[1].position = Vector3::origin();
+    magnetic.update_force(&mut bodies, 0, 0.1);
+    assert_eq!(Vector3::origin(), bodies[0].force_accum);
+}
@@ -0,0 +1,114 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::hinge_joint::twist_angle;
+use crate::joint::{world_direction, RowBuilder};
+use crate::rigid_body::RigidBody;
+use crate::solver::tangent_basis;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Gear joint, coupling the rotation of two bodies around their own axes, usually the axes
+/// of the hinges holding them, so they turn together like a pair of gears.
+///
+/// # Remarks
+/// The first body turns `ratio` radians around its axis for every radian the second one
+/// turns around its own axis. Opposite axes make the bodies turn in opposite directions, like
+/// meshing gears do, and negative ratios too. The joint doesn't hold the bodies in place,
+/// so it's combined with hinges or other joints that do.
+///
+/// The rotations are measured relative to the world, from the first time the joint is solved,
+/// and accumulated over full turns, so the drift is corrected even with fractional ratios.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GearJoint<F: num_traits::Float = f64> {
+    /// Unit axis of the first body, in its local space.
+    pub local_axis_one: Vector3<F>,
+
+    /// Unit axis of the second body, in its local space,
+    /// or in world space for joints with the scenery.
+    pub local_axis_two: Vector3<F>,
+
+    /// Radians turned by the first body for every radian turned by the second one.
+    pub ratio: F,
+
+    local_references: [Vector3<F>; 2],
+    references: Option<[Vector3<F>; 2]>,
+    angles: [F; 2],
+}
+
+impl<F: num_traits::Float> GearJoint<F> {
+    /// Creates a new gear joint around the given axes, with the given ratio.
+    pub fn new(local_axis_one: Vector3<F>, local_axis_two: Vector3<F>, ratio: F) -> Self {
+        let local_axis_one = local_axis_one.normalize();
+        let local_axis_two = local_axis_two.normalize();
+        Self {
+            local_axis_one,
+            local_axis_two,
+            ratio,
+            local_references: [
+                tangent_basis(&local_axis_one)[0],
+                tangent_basis(&local_axis_two)[0],
+            ],
+            references: None,
+            angles: [num_traits::zero(); 2],
+        }
+    }
+
+    /// Creates a new gear joint between the given bodies, around the given axes in world space.
+    pub fn from_world(
+        one: &RigidBody<F>,
+        two: Option<&RigidBody<F>>,
+        axis_one: &Vector3<F>,
+        axis_two: &Vector3<F>,
+        ratio: F,
+    ) -> Self {
+        let local_axis_two = match two {
+            Some(two) => two.direction_in_local_space(axis_two),
+            None => *axis_two,
+        };
+        Self::new(
+            one.direction_in_local_space(axis_one),
+            local_axis_two,
+            ratio,
+        )
+    }
+
+    /// Returns the angles, in radians, turned by each body around its axis since the joint
+    /// was first solved.
+    pub fn angles(&self) -> [F; 2] {
+        self.angles
+    }
+
+    /// Adds the constraint rows of the joint, accumulating the angles turned since the
+    /// last time it was solved.
+    pub(crate) fn rows(&mut self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let pair = builder.bodies();
+        let (one, two) = (&bodies[pair.0], pair.1.map(|body| &bodies[body]));
+        let axes = [
+            one.direction_in_world_space(&self.local_axis_one),
+            world_direction(two, &self.local_axis_two),
+        ];
+        let references = [
+            one.direction_in_world_space(&self.local_references[0]),
+            world_direction(two, &self.local_references[1]),
+        ];
+        if let Some(previous) = self.references {
+            for index in 0..2 {
+                let turned = twist_angle(&axes[index], &references[index], &previous[index]);
+                self.angles[index] = self.angles[index] + turned;
+            }
+        }
+        self.references = Some(references);
+
+        builder.add(
+            0,
+            Vector3::origin(),
+            axes[0],
+            axes[1].scalar_mul(self.ratio),
+            self.angles[0] - self.ratio * self.angles[1],
+        );
+    }
+}
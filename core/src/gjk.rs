@@ -0,0 +1,317 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::shape::SupportMap;
+use crate::toi::{time_of_impact, Motion};
+use math::{Matrix4, Vector3};
+
+/// Maximum number of iterations of the GJK algorithm.
+pub const GJK_MAX_ITERATIONS: usize = 64;
+
+/// Relative tolerance under which GJK considers the distance converged.
+pub const GJK_TOLERANCE: f64 = 1e-10;
+
+/// Distance under which a cast shape is considered to touch the other one.
+pub const GJK_CAST_TOLERANCE: f64 = 1e-6;
+
+/// Result of the GJK algorithm between two convex shapes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GjkResult<F: num_traits::Float = f64> {
+    /// The shapes overlap.
+    Intersecting,
+
+    /// The shapes are separated.
+    Separated {
+        /// Distance between the shapes.
+        distance: F,
+
+        /// Point of the first shape closest to the second one, in world space.
+        point_one: Vector3<F>,
+
+        /// Point of the second shape closest to the first one, in world space.
+        point_two: Vector3<F>,
+    },
+}
+
+/// First contact found when sweeping a shape against another one.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct CastHit<F: num_traits::Float = f64> {
+    /// Time of impact, in multiples of the direction the shape was swept along.
+    pub toi: F,
+
+    /// Point of contact in world space, on the surface of the shape hit.
+    pub point: Vector3<F>,
+
+    /// Unit normal of the surface hit at the point of contact, in world space,
+    /// pointing towards the swept shape.
+    pub normal: Vector3<F>,
+}
+
+/// Vertex of the simplex, a point of the Minkowski difference of both shapes
+/// along with the support points it was made from.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct SimplexVertex<F: num_traits::Float> {
+    point: Vector3<F>,
+    one: Vector3<F>,
+    two: Vector3<F>,
+}
+
+/// Returns true if both convex shapes overlap.
+pub fn intersects<F: num_traits::Float, A: SupportMap<F>, B: SupportMap<F>>(
+    one: &A,
+    one_transform: &Matrix4<F>,
+    two: &B,
+    two_transform: &Matrix4<F>,
+) -> bool {
+    closest_points(one, one_transform, two, two_transform) == GjkResult::Intersecting
+}
+
+/// Returns the distance between both convex shapes, `0` if they overlap.
+pub fn distance<F: num_traits::Float, A: SupportMap<F>, B: SupportMap<F>>(
+    one: &A,
+    one_transform: &Matrix4<F>,
+    two: &B,
+    two_transform: &Matrix4<F>,
+) -> F {
+    match closest_points(one, one_transform, two, two_transform) {
+        GjkResult::Intersecting => num_traits::zero(),
+        GjkResult::Separated { distance, .. } => distance,
+    }
+}
+
+/// Computes whether both convex shapes overlap and, if they don't,
+/// their distance and closest points, using the GJK algorithm.
+///
+/// # Remarks
+/// GJK searches the point of the Minkowski difference of both shapes closest to the origin,
+/// refining a simplex of up to four support points. The shapes overlap when the simplex
+/// encloses the origin. Otherwise, the barycentric coordinates of the closest point on the
+/// final simplex give the closest points on both shapes.
+pub fn closest_points<F: num_traits::Float, A: SupportMap<F>, B: SupportMap<F>>(
+    one: &A,
+    one_transform: &Matrix4<F>,
+    two: &B,
+    two_transform: &Matrix4<F>,
+) -> GjkResult<F> {
+    let support = |direction: &Vector3<F>| {
+        let one = one.support_point(one_transform, direction);
+        let two = two.support_point(two_transform, &direction.invert());
+        SimplexVertex {
+            point: one.vector_sub(&two),
+            one,
+            two,
+        }
+    };
+
+    let tolerance: F = math::real(GJK_TOLERANCE);
+    let mut direction = two_transform
+        .translation()
+        .vector_sub(&one_transform.translation());
+    if direction.squared_magnitude() <= F::epsilon() {
+        direction = Vector3::new(num_traits::one(), num_traits::zero(), num_traits::zero());
+    }
+
+    let mut simplex = vec![support(&direction)];
+    let mut weights = vec![num_traits::one()];
+    let mut closest = simplex[0].point;
+    for _ in 0..GJK_MAX_ITERATIONS {
+        let squared_distance = closest.squared_magnitude();
+        if squared_distance <= tolerance * tolerance {
+            return GjkResult::Intersecting;
+        }
+
+        // Stop when the new support point doesn't get any closer to the origin.
+        let vertex = support(&closest.invert());
+        if squared_distance - closest.dot_product(&vertex.point) <= tolerance * squared_distance
+            || simplex
+                .iter()
+                .any(|existing| existing.point == vertex.point)
+        {
+            break;
+        }
+
+        simplex.push(vertex);
+        match closest_to_origin(&simplex) {
+            None => return GjkResult::Intersecting,
+            Some((reduced, reduced_weights)) => {
+                simplex = reduced;
+                weights = reduced_weights;
+            }
+        }
+        closest = combine(&simplex, &weights, |vertex| vertex.point);
+    }
+
+    GjkResult::Separated {
+        distance: closest.magnitude(),
+        point_one: combine(&simplex, &weights, |vertex| vertex.one),
+        point_two: combine(&simplex, &weights, |vertex| vertex.two),
+    }
+}
+
+/// Sweeps the first convex shape along the given direction, returning its first contact
+/// with the second one before the given maximum time of impact, if any.
+///
+/// # Remarks
+/// This is `time_of_impact` with a shape moving along the direction against a stationary one.
+pub fn cast<F: num_traits::Float, A: SupportMap<F>, B: SupportMap<F>>(
+    one: &A,
+    one_transform: &Matrix4<F>,
+    direction: &Vector3<F>,
+    two: &B,
+    two_transform: &Matrix4<F>,
+    max_toi: F,
+) -> Option<CastHit<F>> {
+    time_of_impact(
+        one,
+        &Motion::new(*one_transform, *direction, Vector3::origin()),
+        two,
+        &Motion::stationary(*two_transform),
+        max_toi,
+    )
+}
+
+/// Combines the given points of the simplex vertices with the barycentric weights.
+fn combine<F: num_traits::Float, P: Fn(&SimplexVertex<F>) -> Vector3<F>>(
+    simplex: &[SimplexVertex<F>],
+    weights: &[F],
+    point: P,
+) -> Vector3<F> {
+    simplex
+        .iter()
+        .zip(weights.iter())
+        .fold(Vector3::origin(), |sum, (vertex, weight)| {
+            sum.vector_add(&point(vertex).scalar_mul(*weight))
+        })
+}
+
+/// Finds the point of the simplex closest to the origin, returning the smallest
+/// sub-simplex containing it along with its barycentric coordinates.
+/// Returns `None` if the simplex is a tetrahedron containing the origin.
+fn closest_to_origin<F: num_traits::Float>(
+    simplex: &[SimplexVertex<F>],
+) -> Option<(Vec<SimplexVertex<F>>, Vec<F>)> {
+    match simplex.len() {
+        1 => Some((simplex.to_vec(), vec![num_traits::one()])),
+        2 => Some(closest_on_segment(simplex[0], simplex[1])),
+        3 => Some(closest_on_triangle(simplex[0], simplex[1], simplex[2])),
+        _ => closest_on_tetrahedron(simplex[0], simplex[1], simplex[2], simplex[3]),
+    }
+}
+
+fn closest_on_segment<F: num_traits::Float>(
+    a: SimplexVertex<F>,
+    b: SimplexVertex<F>,
+) -> (Vec<SimplexVertex<F>>, Vec<F>) {
+    let ab = b.point.vector_sub(&a.point);
+    let t = -a.point.dot_product(&ab);
+    if t <= num_traits::zero() {
+        return (vec![a], vec![num_traits::one()]);
+    }
+
+    let squared_length = ab.squared_magnitude();
+    if t >= squared_length {
+        return (vec![b], vec![num_traits::one()]);
+    }
+
+    let t = t / squared_length;
+    (vec![a, b], vec![num_traits::one::<F>() - t, t])
+}
+
+fn closest_on_triangle<F: num_traits::Float>(
+    a: SimplexVertex<F>,
+    b: SimplexVertex<F>,
+    c: SimplexVertex<F>,
+) -> (Vec<SimplexVertex<F>>, Vec<F>) {
+    let zero: F = num_traits::zero();
+    let one: F = num_traits::one();
+    let ab = b.point.vector_sub(&a.point);
+    let ac = c.point.vector_sub(&a.point);
+
+    // Vertex region of a.
+    let d1 = -ab.dot_product(&a.point);
+    let d2 = -ac.dot_product(&a.point);
+    if d1 <= zero && d2 <= zero {
+        return (vec![a], vec![one]);
+    }
+
+    // Vertex region of b.
+    let d3 = -ab.dot_product(&b.point);
+    let d4 = -ac.dot_product(&b.point);
+    if d3 >= zero && d4 <= d3 {
+        return (vec![b], vec![one]);
+    }
+
+    // Edge region of ab.
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= zero && d1 >= zero && d3 <= zero {
+        let v = d1 / (d1 - d3);
+        return (vec![a, b], vec![one - v, v]);
+    }
+
+    // Vertex region of c.
+    let d5 = -ab.dot_product(&c.point);
+    let d6 = -ac.dot_product(&c.point);
+    if d6 >= zero && d5 <= d6 {
+        return (vec![c], vec![one]);
+    }
+
+    // Edge region of ac.
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= zero && d2 >= zero && d6 <= zero {
+        let w = d2 / (d2 - d6);
+        return (vec![a, c], vec![one - w, w]);
+    }
+
+    // Edge region of bc.
+    let va = d3 * d6 - d5 * d4;
+    if va <= zero && d4 - d3 >= zero && d5 - d6 >= zero {
+        let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (vec![b, c], vec![one - w, w]);
+    }
+
+    // Face region.
+    let denominator = one / (va + vb + vc);
+    let v = vb * denominator;
+    let w = vc * denominator;
+    (vec![a, b, c], vec![one - v - w, v, w])
+}
+
+fn closest_on_tetrahedron<F: num_traits::Float>(
+    a: SimplexVertex<F>,
+    b: SimplexVertex<F>,
+    c: SimplexVertex<F>,
+    d: SimplexVertex<F>,
+) -> Option<(Vec<SimplexVertex<F>>, Vec<F>)> {
+    // Test the origin against each face, along with the vertex opposite to it.
+    let faces = [(a, b, c, d), (a, c, d, b), (a, d, b, c), (b, d, c, a)];
+    let mut best: Option<(Vec<SimplexVertex<F>>, Vec<F>, F)> = None;
+    for (a, b, c, opposite) in faces.iter() {
+        let normal = b
+            .point
+            .vector_sub(&a.point)
+            .cross_product(&c.point.vector_sub(&a.point));
+        let origin_side = -normal.dot_product(&a.point);
+        let opposite_side = normal.dot_product(&opposite.point.vector_sub(&a.point));
+
+        // Flat tetrahedra have no inside, so every face is tested.
+        if origin_side * opposite_side > num_traits::zero() {
+            continue;
+        }
+
+        let (vertices, weights) = closest_on_triangle(*a, *b, *c);
+        let squared_distance =
+            combine(&vertices, &weights, |vertex| vertex.point).squared_magnitude();
+        let closer = match best {
+            Some((_, _, best)) => squared_distance < best,
+            None => true,
+        };
+        if closer {
+            best = Some((vertices, weights, squared_distance));
+        }
+    }
+
+    best.map(|(vertices, weights, _)| (vertices, weights))
+}
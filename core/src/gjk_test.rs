@@ -0,0 +1,197 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::gjk::*;
+use crate::shape::{Cuboid, Sphere};
+use math::{Matrix4, Quaternion, Vector3};
+
+fn assert_vector_eq(expected: Vector3<f64>, actual: Vector3<f64>) {
+    assert!(
+        (expected - actual).magnitude() < 1e-9,
+        "{:?} != {:?}",
+        expected,
+        actual
+    );
+}
+
+fn placed(x: f64, y: f64, z: f64) -> Matrix4<f64> {
+    Matrix4::from_orientation_and_position(&Quaternion::identity(), &Vector3::new(x, y, z))
+}
+
+#[test]
+fn spheres() {
+    let one = Sphere::new(1.0);
+    let two = Sphere::new(0.5);
+
+    match closest_points(&one, &placed(0.0, 0.0, 0.0), &two, &placed(3.0, 4.0, 0.0)) {
+        GjkResult::Separated {
+            distance,
+            point_one,
+            point_two,
+        } => {
+            assert!((distance - 3.5).abs() < 1e-9);
+            assert_vector_eq(Vector3::new(0.6, 0.8, 0.0), point_one);
+            assert_vector_eq(Vector3::new(2.7, 3.6, 0.0), point_two);
+        }
+        GjkResult::Intersecting => panic!("spheres are separated"),
+    }
+
+    assert!(intersects(
+        &one,
+        &placed(0.0, 0.0, 0.0),
+        &two,
+        &placed(1.0, 1.0, 0.0)
+    ));
+    assert_eq!(
+        0.0,
+        distance(&one, &placed(0.0, 0.0, 0.0), &two, &placed(0.0, 0.0, 0.0))
+    );
+}
+
+#[test]
+fn boxes() {
+    let cuboid = Cuboid::new(Vector3::new(1.0, 1.0, 1.0));
+
+    // Face to face.
+    match closest_points(
+        &cuboid,
+        &placed(0.0, 0.0, 0.0),
+        &cuboid,
+        &placed(0.0, 3.0, 0.0),
+    ) {
+        GjkResult::Separated {
+            distance,
+            point_one,
+            point_two,
+        } => {
+            assert!((distance - 1.0).abs() < 1e-9);
+            assert!((point_one.y - 1.0).abs() < 1e-9);
+            assert!((point_two.y - 2.0).abs() < 1e-9);
+        }
+        GjkResult::Intersecting => panic!("boxes are separated"),
+    }
+
+    // Corner to corner.
+    let far = placed(3.0, 3.0, 3.0);
+    assert!(
+        (distance(&cuboid, &placed(0.0, 0.0, 0.0), &cuboid, &far) - 3.0f64.sqrt()).abs() < 1e-9
+    );
+
+    // Turned on its edge, the box reaches further.
+    let turned = Matrix4::from_orientation_and_position(
+        &Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_4),
+        &Vector3::new(0.0, 3.0, 0.0),
+    );
+    let expected = 2.0 - std::f64::consts::SQRT_2;
+    assert!((distance(&cuboid, &placed(0.0, 0.0, 0.0), &cuboid, &turned) - expected).abs() < 1e-9);
+
+    assert!(intersects(
+        &cuboid,
+        &placed(0.0, 0.0, 0.0),
+        &cuboid,
+        &placed(1.5, 1.5, -1.5)
+    ));
+    assert!(!intersects(
+        &cuboid,
+        &placed(0.0, 0.0, 0.0),
+        &cuboid,
+        &placed(2.1, 0.0, 0.0)
+    ));
+}
+
+#[test]
+fn sphere_and_box() {
+    let sphere = Sphere::new(1.0);
+    let cuboid = Cuboid::new(Vector3::new(1.0, 2.0, 3.0));
+
+    match closest_points(
+        &sphere,
+        &placed(3.0, 1.0, 0.0),
+        &cuboid,
+        &placed(0.0, 0.0, 0.0),
+    ) {
+        GjkResult::Separated {
+            distance,
+            point_one,
+            point_two,
+        } => {
+            assert!((distance - 1.0).abs() < 1e-9);
+            assert_vector_eq(Vector3::new(2.0, 1.0, 0.0), point_one);
+            assert_vector_eq(Vector3::new(1.0, 1.0, 0.0), point_two);
+        }
+        GjkResult::Intersecting => panic!("shapes are separated"),
+    }
+
+    assert!(intersects(
+        &sphere,
+        &placed(1.5, 1.5, 2.5),
+        &cuboid,
+        &placed(0.0, 0.0, 0.0)
+    ));
+    assert!(!intersects(
+        &sphere,
+        &placed(1.8, 2.8, 0.0),
+        &cuboid,
+        &placed(0.0, 0.0, 0.0)
+    ));
+}
+
+#[test]
+fn casts() {
+    // A sphere swept towards a box stops at its face.
+    let sphere = Sphere::new(0.5);
+    let cuboid = Cuboid::new(Vector3::new(1.0, 1.0, 1.0));
+    let hit = cast(
+        &sphere,
+        &Matrix4::identity(),
+        &Vector3::new(2.0, 0.0, 0.0),
+        &cuboid,
+        &placed(5.0, 0.5, 0.0),
+        10.0,
+    )
+    .unwrap();
+    assert!((hit.toi - 1.75).abs() < 1e-5);
+    assert!((hit.point - Vector3::new(4.0, 0.0, 0.0)).magnitude() < 1e-5);
+    assert!((hit.normal - Vector3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-5);
+
+    // Too short, passing by, or moving away misses.
+    let direction = Vector3::new(1.0, 0.0, 0.0);
+    let far = placed(5.0, 0.0, 0.0);
+    assert!(cast(
+        &sphere,
+        &Matrix4::identity(),
+        &direction,
+        &cuboid,
+        &far,
+        3.0
+    )
+    .is_none());
+    let aside = placed(5.0, 1.6, 0.0);
+    assert!(cast(
+        &sphere,
+        &Matrix4::identity(),
+        &direction,
+        &cuboid,
+        &aside,
+        10.0
+    )
+    .is_none());
+    let behind = placed(-5.0, 0.0, 0.0);
+    assert!(cast(
+        &sphere,
+        &Matrix4::identity(),
+        &direction,
+        &cuboid,
+        &behind,
+        10.0
+    )
+    .is_none());
+
+    // Overlapping shapes hit right away.
+    let hit = cast(&sphere, &far, &direction, &cuboid, &far, 10.0).unwrap();
+    assert_eq!(0.0, hit.toi);
+    assert_vector_eq(Vector3::new(-1.0, 0.0, 0.0), hit.normal);
+}
@@ -0,0 +1,591 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::material::PhysicsMaterial;
+use crate::rigid_body::{BodyType, RigidBody};
+use crate::shape::{Capsule, ConvexHull, Cuboid, Shape, Sphere};
+use crate::trimesh::TriMesh;
+use crate::world::World;
+use gltf::json::Value;
+use gltf::mesh::Mode;
+use gltf::{Gltf, Node};
+use math::{Matrix3, Matrix4, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Name of the glTF extension describing rigid bodies, colliders and physics materials.
+pub const KHR_PHYSICS_RIGID_BODIES: &str = "KHR_physics_rigid_bodies";
+
+/// Name of the glTF extension describing the implicit shapes used by colliders.
+pub const KHR_IMPLICIT_SHAPES: &str = "KHR_implicit_shapes";
+
+/// Colliders built for the nodes with a mesh, when the file doesn't use the physics extension.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum MeshColliders {
+    /// Meshes are ignored.
+    None,
+
+    /// Every node with a mesh becomes a static body with the convex hull of the mesh.
+    ConvexHull,
+
+    /// Every node with a mesh becomes a static body with the triangles of the mesh.
+    TriMesh,
+}
+
+/// Settings of the glTF importer.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GltfSettings<F: num_traits::Float = f64> {
+    /// Colliders built for the meshes of files without the physics extension.
+    pub meshes: MeshColliders,
+
+    /// Material of the colliders without a physics material of their own.
+    pub material: PhysicsMaterial<F>,
+}
+
+impl<F: num_traits::Float> Default for GltfSettings<F> {
+    fn default() -> Self {
+        Self {
+            meshes: MeshColliders::ConvexHull,
+            material: PhysicsMaterial::default(),
+        }
+    }
+}
+
+/// Rigid body created for a node of a glTF file.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ImportedNode {
+    /// Index of the node in the file.
+    pub node: usize,
+
+    /// Name of the node, if any.
+    pub name: Option<String>,
+
+    /// Index of the body in the world.
+    pub body: usize,
+
+    /// Indices of the colliders attached to the body, in the world.
+    pub colliders: Vec<usize>,
+}
+
+/// Error raised while importing a glTF file.
+#[derive(Debug)]
+pub enum GltfError {
+    /// The file isn't valid glTF.
+    Gltf(gltf::Error),
+
+    /// A file couldn't be read.
+    Io(std::io::Error),
+
+    /// A buffer couldn't be loaded: it's external without a base path, or a malformed data URI.
+    Buffer(usize),
+
+    /// A collider refers to something missing or unsupported, described by the message.
+    Collider(String),
+}
+
+impl std::fmt::Display for GltfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GltfError::Gltf(error) => write!(f, "invalid glTF: {}", error),
+            GltfError::Io(error) => write!(f, "I/O error: {}", error),
+            GltfError::Buffer(buffer) => write!(f, "buffer {} can't be loaded", buffer),
+            GltfError::Collider(message) => write!(f, "invalid collider: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+impl From<gltf::Error> for GltfError {
+    fn from(error: gltf::Error) -> Self {
+        GltfError::Gltf(error)
+    }
+}
+
+impl From<std::io::Error> for GltfError {
+    fn from(error: std::io::Error) -> Self {
+        GltfError::Io(error)
+    }
+}
+
+/// Imports the glTF file at the given path into the world, returning the bodies created
+/// for its nodes. External buffers are loaded relative to the file.
+pub fn import_gltf_file<F: num_traits::Float>(
+    world: &mut World<F>,
+    path: &Path,
+    settings: &GltfSettings<F>,
+) -> Result<Vec<ImportedNode>, GltfError> {
+    let data = std::fs::read(path)?;
+    import_gltf(world, &data, path.parent(), settings)
+}
+
+/// Imports a glTF file, either JSON or binary (GLB), into the world, returning the bodies
+/// created for its nodes.
+///
+/// # Remarks
+/// Nodes with a `motion` in the `KHR_physics_rigid_bodies` extension become dynamic (or
+/// kinematic) bodies, with the colliders of the node and its descendants attached. Colliders
+/// use the implicit shapes of `KHR_implicit_shapes`, or the convex hulls or triangles of
+/// meshes, and triggers become sensors. Colliders outside of any body get a static body of their own.
+/// Files without the extension get a static body for every node with a mesh instead, as
+/// chosen by the settings.
+///
+/// Buffers are read from the binary chunk, data URIs, or files relative to `base`. Scales
+/// are baked into the shapes, and bodies without a mass in the file get the mass of
+/// their colliders.
+pub fn import_gltf<F: num_traits::Float>(
+    world: &mut World<F>,
+    data: &[u8],
+    base: Option<&Path>,
+    settings: &GltfSettings<F>,
+) -> Result<Vec<ImportedNode>, GltfError> {
+    let gltf = Gltf::from_slice(data)?;
+    let buffers = load_buffers(&gltf, base)?;
+    let physics = gltf
+        .extensions_used()
+        .any(|name| name == KHR_PHYSICS_RIGID_BODIES);
+    let mut importer = Importer {
+        document: &gltf.document,
+        world,
+        settings,
+        buffers,
+        physics,
+        shapes: extension_array(gltf.extension_value(KHR_IMPLICIT_SHAPES), "shapes"),
+        materials: extension_array(
+            gltf.extension_value(KHR_PHYSICS_RIGID_BODIES),
+            "physicsMaterials",
+        ),
+        imported: Vec::new(),
+        masses: Vec::new(),
+    };
+
+    let scene = gltf.default_scene().or_else(|| gltf.scenes().next());
+    let roots: Vec<Node> = match scene {
+        Some(scene) => scene.nodes().collect(),
+        None => {
+            let children: Vec<usize> = gltf
+                .nodes()
+                .flat_map(|node| node.children().map(|child| child.index()))
+                .collect();
+            gltf.nodes()
+                .filter(|node| !children.contains(&node.index()))
+                .collect()
+        }
+    };
+    for root in roots {
+        importer.import_node(&root, &Pose::identity(), None)?;
+    }
+
+    // Bodies get their mass once all of their colliders are attached.
+    let masses = std::mem::take(&mut importer.masses);
+    for (body, mass) in masses {
+        let properties = importer.world.mass_properties(body);
+        match mass {
+            Some(mass) if properties.mass > F::zero() => importer
+                .world
+                .set_mass_properties(body, &properties.with_mass(mass)),
+            Some(mass) => {
+                importer.world.bodies[body].set_mass(mass);
+            }
+            None => {
+                importer.world.update_mass_properties(body);
+            }
+        }
+    }
+    Ok(importer.imported)
+}
+
+/// Placement of a node in world space, with its scale kept apart to be baked into shapes.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Pose<F: num_traits::Float> {
+    position: Vector3<F>,
+    orientation: Quaternion<F>,
+    scale: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Pose<F> {
+    fn identity() -> Self {
+        Self {
+            position: Vector3::origin(),
+            orientation: Quaternion::identity(),
+            scale: Vector3::new(F::one(), F::one(), F::one()),
+        }
+    }
+
+    /// Returns the pose of a child placed with the given local transform.
+    fn child(&self, node: &Node) -> Self {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        let translation = vector(translation);
+        let rotation = Quaternion::new(
+            real(rotation[3]),
+            real(rotation[0]),
+            real(rotation[1]),
+            real(rotation[2]),
+        );
+        Self {
+            position: self.position.vector_add(
+                &self
+                    .orientation
+                    .rotate(&translation.vector_mul(&self.scale)),
+            ),
+            orientation: self.orientation.quaternion_mul(&rotation).normalize(),
+            scale: self.scale.vector_mul(&vector(scale)),
+        }
+    }
+
+    fn transform(&self) -> Matrix4<F> {
+        Matrix4::from_orientation_and_position(&self.orientation, &self.position)
+    }
+}
+
+/// State of an import in progress.
+struct Importer<'a, F: num_traits::Float> {
+    document: &'a gltf::Document,
+    world: &'a mut World<F>,
+    settings: &'a GltfSettings<F>,
+    buffers: Vec<Vec<u8>>,
+    physics: bool,
+    shapes: Vec<Value>,
+    materials: Vec<Value>,
+    imported: Vec<ImportedNode>,
+    masses: Vec<(usize, Option<F>)>,
+}
+
+impl<'a, F: num_traits::Float> Importer<'a, F> {
+    /// Imports a node and its descendants, attaching their colliders to the given body.
+    fn import_node(
+        &mut self,
+        node: &Node,
+        parent: &Pose<F>,
+        body: Option<(usize, Pose<F>)>,
+    ) -> Result<(), GltfError> {
+        let pose = parent.child(node);
+        let extension = node.extension_value(KHR_PHYSICS_RIGID_BODIES);
+        let mut body = body;
+
+        if let Some(motion) = extension.and_then(|extension| extension.get("motion")) {
+            let kinematic = motion.get("isKinematic").and_then(Value::as_bool) == Some(true);
+            let index = self.add_body(node, &pose, motion_type(kinematic));
+            let rigid_body = &mut self.world.bodies[index];
+            if let Some(velocity) = vector_value(motion.get("linearVelocity")) {
+                rigid_body.velocity = velocity;
+            }
+            if let Some(rotation) = vector_value(motion.get("angularVelocity")) {
+                rigid_body.rotation = rotation;
+            }
+            if !kinematic {
+                let mass = motion.get("mass").and_then(Value::as_f64).map(math::real);
+                self.masses.push((index, mass));
+            }
+            body = Some((index, pose));
+        }
+
+        let colliders = [("collider", false), ("trigger", true)];
+        for (key, sensor) in colliders.iter() {
+            let description = match extension.and_then(|extension| extension.get(*key)) {
+                Some(description) => description,
+                None => continue,
+            };
+            let shape = self.geometry(description.get("geometry"), &pose.scale)?;
+            let material = self.material(description.get("physicsMaterial"))?;
+            self.attach(node, &pose, &mut body, shape, material, *sensor);
+        }
+
+        if let (false, Some(mesh)) = (self.physics, node.mesh()) {
+            let shape = match self.settings.meshes {
+                MeshColliders::None => None,
+                MeshColliders::ConvexHull => Some(self.hull(&mesh, &pose.scale)?),
+                MeshColliders::TriMesh => Some(self.trimesh(&mesh, &pose.scale)?),
+            };
+            if let Some(shape) = shape {
+                let material = self.settings.material;
+                self.attach(node, &pose, &mut None, shape, material, false);
+            }
+        }
+
+        for child in node.children() {
+            self.import_node(&child, &pose, body)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a body for the node, placed with its pose.
+    fn add_body(&mut self, node: &Node, pose: &Pose<F>, body_type: BodyType) -> usize {
+        let mut body = RigidBody::new(pose.position, F::one(), &Matrix3::identity());
+        body.orientation = pose.orientation;
+        body.set_body_type(body_type);
+        body.calculate_derived_data();
+        let index = self.world.add_body(body);
+        let index = self.world.body_index(index).unwrap();
+        self.imported.push(ImportedNode {
+            node: node.index(),
+            name: node.name().map(String::from),
+            body: index,
+            colliders: Vec::new(),
+        });
+        index
+    }
+
+    /// Attaches a collider placed at the node to the body, or to a new static one.
+    fn attach(
+        &mut self,
+        node: &Node,
+        pose: &Pose<F>,
+        body: &mut Option<(usize, Pose<F>)>,
+        shape: Shape<F>,
+        material: PhysicsMaterial<F>,
+        sensor: bool,
+    ) {
+        let (index, body_pose) = match body {
+            Some(body) => *body,
+            None => (self.add_body(node, pose, BodyType::Static), *pose),
+        };
+        let offset = body_pose
+            .transform()
+            .inverse()
+            .matrix_mul(&pose.transform());
+        let mut collider = Collider::with_offset(index, shape, offset);
+        collider.material = material;
+        collider.sensor = sensor;
+        let collider = self.world.add_collider(collider);
+        let collider = self.world.collider_index(collider).unwrap();
+        if let Some(imported) = self.imported.iter_mut().find(|i| i.body == index) {
+            imported.colliders.push(collider);
+        }
+    }
+
+    /// Returns the shape described by the geometry of a collider, with the given scale.
+    fn geometry(
+        &self,
+        geometry: Option<&Value>,
+        scale: &Vector3<F>,
+    ) -> Result<Shape<F>, GltfError> {
+        let geometry = geometry.ok_or_else(|| invalid("missing geometry"))?;
+        if let Some(shape) = geometry.get("shape").and_then(Value::as_u64) {
+            let shape = self
+                .shapes
+                .get(shape as usize)
+                .ok_or_else(|| invalid("missing implicit shape"))?;
+            return implicit_shape(shape, scale);
+        }
+        let mesh = geometry
+            .get("mesh")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| invalid("geometry without shape nor mesh"))?;
+        let mesh = self
+            .document
+            .meshes()
+            .nth(mesh as usize)
+            .ok_or_else(|| invalid("missing mesh"))?;
+        if geometry.get("convexHull").and_then(Value::as_bool) == Some(true) {
+            self.hull(&mesh, scale)
+        } else {
+            self.trimesh(&mesh, scale)
+        }
+    }
+
+    /// Returns the convex hull of the vertices of every primitive of the mesh, scaled.
+    fn hull(&self, mesh: &gltf::Mesh, scale: &Vector3<F>) -> Result<Shape<F>, GltfError> {
+        let mut points = Vec::new();
+        for primitive in mesh.primitives() {
+            let reader =
+                primitive.reader(|buffer| self.buffers.get(buffer.index()).map(Vec::as_slice));
+            if let Some(positions) = reader.read_positions() {
+                points.extend(positions.map(|position| vector(position).vector_mul(scale)));
+            }
+        }
+        ConvexHull::from_points(&points)
+            .map(Shape::convex_hull)
+            .ok_or_else(|| invalid("flat mesh"))
+    }
+
+    /// Returns the triangles of every primitive of the mesh drawn as triangles, scaled.
+    fn trimesh(&self, mesh: &gltf::Mesh, scale: &Vector3<F>) -> Result<Shape<F>, GltfError> {
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for primitive in mesh.primitives() {
+            if primitive.mode() != Mode::Triangles {
+                continue;
+            }
+            let reader =
+                primitive.reader(|buffer| self.buffers.get(buffer.index()).map(Vec::as_slice));
+            let first = vertices.len();
+            if let Some(positions) = reader.read_positions() {
+                vertices.extend(positions.map(|position| vector(position).vector_mul(scale)));
+            }
+            let indices: Vec<usize> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().map(|index| index as usize).collect(),
+                None => (0..vertices.len() - first).collect(),
+            };
+            triangles.extend(indices.chunks_exact(3).map(|triangle| {
+                [
+                    first + triangle[0],
+                    first + triangle[1],
+                    first + triangle[2],
+                ]
+            }));
+        }
+        TriMesh::new(vertices, triangles)
+            .map(Shape::trimesh)
+            .ok_or_else(|| invalid("mesh without triangles"))
+    }
+
+    /// Returns the physics material with the given index, or the default one.
+    fn material(&self, index: Option<&Value>) -> Result<PhysicsMaterial<F>, GltfError> {
+        let index = match index.and_then(Value::as_u64) {
+            Some(index) => index as usize,
+            None => return Ok(self.settings.material),
+        };
+        let description = self
+            .materials
+            .get(index)
+            .ok_or_else(|| invalid("missing physics material"))?;
+        let number = |key: &str| description.get(key).and_then(Value::as_f64).map(math::real);
+        let mut material = self.settings.material;
+        if let Some(friction) = number("dynamicFriction") {
+            material.friction = friction;
+        }
+        if let Some(friction) = number("staticFriction") {
+            material.static_friction = friction;
+        }
+        if let Some(restitution) = number("restitution") {
+            material.restitution = restitution;
+        }
+        Ok(material)
+    }
+}
+
+/// Returns the body type of a body with a motion.
+fn motion_type(kinematic: bool) -> BodyType {
+    if kinematic {
+        BodyType::Kinematic
+    } else {
+        BodyType::Dynamic
+    }
+}
+
+/// Returns the shape described by an implicit shape of `KHR_implicit_shapes`, with the
+/// given scale.
+fn implicit_shape<F: num_traits::Float>(
+    shape: &Value,
+    scale: &Vector3<F>,
+) -> Result<Shape<F>, GltfError> {
+    let kind = shape
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let properties = shape.get(kind);
+    let number = |key: &str| {
+        properties
+            .and_then(|properties| properties.get(key))
+            .and_then(Value::as_f64)
+            .map(math::real::<F>)
+    };
+    let half: F = math::real(0.5);
+    let widest = scale.x.abs().max(scale.y.abs()).max(scale.z.abs());
+    match kind {
+        "sphere" => {
+            let radius = number("radius").unwrap_or(half);
+            Ok(Shape::Sphere(Sphere::new(radius * widest)))
+        }
+        "box" => {
+            let size = vector_value(properties.and_then(|properties| properties.get("size")))
+                .unwrap_or_else(|| Vector3::new(F::one(), F::one(), F::one()));
+            let half_size = size.vector_mul(scale).scalar_mul(half);
+            Ok(Shape::Cuboid(Cuboid::new(Vector3::new(
+                half_size.x.abs(),
+                half_size.y.abs(),
+                half_size.z.abs(),
+            ))))
+        }
+        "capsule" => {
+            let height = number("height").unwrap_or_else(F::one);
+            let radius = number("radiusTop")
+                .unwrap_or(half)
+                .max(number("radiusBottom").unwrap_or(half));
+            let across = scale.x.abs().max(scale.z.abs());
+            Ok(Shape::Capsule(Capsule::new(
+                height * half * scale.y.abs(),
+                radius * across,
+            )))
+        }
+        _ => Err(invalid(&format!("unsupported implicit shape {:?}", kind))),
+    }
+}
+
+/// Returns the contents of every buffer of the file.
+fn load_buffers(gltf: &Gltf, base: Option<&Path>) -> Result<Vec<Vec<u8>>, GltfError> {
+    let mut buffers = Vec::new();
+    for buffer in gltf.buffers() {
+        let data = match buffer.source() {
+            gltf::buffer::Source::Bin => gltf.blob.clone(),
+            gltf::buffer::Source::Uri(uri) if uri.starts_with("data:") => uri
+                .find(";base64,")
+                .and_then(|start| decode_base64(&uri[start + ";base64,".len()..])),
+            gltf::buffer::Source::Uri(uri) => match base {
+                Some(base) => Some(std::fs::read(base.join(uri))?),
+                None => None,
+            },
+        };
+        match data {
+            Some(data) if data.len() >= buffer.length() => buffers.push(data),
+            _ => return Err(GltfError::Buffer(buffer.index())),
+        }
+    }
+    Ok(buffers)
+}
+
+/// Decodes base64 text, as used by data URIs, returning `None` if it's malformed.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    let (mut bits, mut count) = (0u32, 0);
+    for byte in text.bytes().filter(|byte| *byte != b'=') {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | u32::from(value);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            data.push((bits >> count) as u8);
+        }
+    }
+    Some(data)
+}
+
+/// Returns the array under the given key of an extension, or an empty one.
+fn extension_array(extension: Option<&Value>, key: &str) -> Vec<Value> {
+    extension
+        .and_then(|extension| extension.get(key))
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Returns the vector held by a JSON array of three numbers.
+fn vector_value<F: num_traits::Float>(value: Option<&Value>) -> Option<Vector3<F>> {
+    let array = value?.as_array()?;
+    let number = |index: usize| array.get(index).and_then(Value::as_f64).map(math::real);
+    Some(Vector3::new(number(0)?, number(1)?, number(2)?))
+}
+
+fn vector<F: num_traits::Float>(value: [f32; 3]) -> Vector3<F> {
+    Vector3::new(real(value[0]), real(value[1]), real(value[2]))
+}
+
+fn real<F: num_traits::Float>(value: f32) -> F {
+    math::real(f64::from(value))
+}
+
+fn invalid(message: &str) -> GltfError {
+    GltfError::Collider(String::from(message))
+}
@@ -0,0 +1,188 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::gltf_import::*;
+use crate::rigid_body::BodyType;
+use crate::shape::Shape;
+use crate::world::{World, WorldConfig};
+use math::Vector3;
+
+/// Vertices of a unit cube, as returned by `cube`, encoded in base64.
+const CUBE: &str = "AAAAvwAAAL8AAAC/AAAAvwAAAL8AAAA/AAAAvwAAAD8AAAC/AAAAvwAAAD8AAAA/\
+AAAAPwAAAL8AAAC/AAAAPwAAAL8AAAA/AAAAPwAAAD8AAAC/AAAAPwAAAD8AAAA/";
+
+/// Returns the glTF JSON of a file with a cube mesh, and the given nodes and extensions.
+fn gltf(buffer: &str, nodes: &str, extensions: &str) -> String {
+    format!(
+        r#"{{
+            "asset": {{"version": "2.0"}},
+            {}
+            "scene": 0,
+            "scenes": [{{"nodes": [0, 2, 3, 4]}}],
+            "nodes": [{}],
+            "buffers": [{{{} "byteLength": 96}}],
+            "bufferViews": [{{"buffer": 0, "byteLength": 96}}],
+            "accessors": [{{
+                "bufferView": 0, "componentType": 5126, "count": 8, "type": "VEC3",
+                "min": [-0.5, -0.5, -0.5], "max": [0.5, 0.5, 0.5]
+            }}],
+            "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}}}]}}]
+        }}"#,
+        extensions, nodes, buffer
+    )
+}
+
+/// Returns the vertices of a unit cube, as little-endian `f32` triples.
+fn cube() -> Vec<u8> {
+    let mut data = Vec::new();
+    for index in 0..8 {
+        for axis in [4, 2, 1].iter() {
+            let value: f32 = if index & axis == 0 { -0.5 } else { 0.5 };
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    data
+}
+
+/// Returns a binary glTF file with the given JSON and binary chunks.
+fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let mut json = json.as_bytes().to_vec();
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut data = Vec::new();
+    data.extend_from_slice(b"glTF");
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&(length as u32).to_le_bytes());
+    data.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    data.extend_from_slice(b"JSON");
+    data.extend_from_slice(&json);
+    data.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    data.extend_from_slice(b"BIN\0");
+    data.extend_from_slice(bin);
+    data
+}
+
+#[test]
+fn physics_extension() {
+    let nodes = r#"
+        {"name": "crate", "translation": [0, 2, 0], "children": [1], "extensions": {
+            "KHR_physics_rigid_bodies": {
+                "motion": {"mass": 4, "linearVelocity": [1, 0, 0]},
+                "collider": {"geometry": {"shape": 0}, "physicsMaterial": 0}
+            }
+        }},
+        {"name": "handle", "translation": [1, 0, 0], "extensions": {
+            "KHR_physics_rigid_bodies": {"collider": {"geometry": {"shape": 1}}}
+        }},
+        {"name": "rock", "mesh": 0, "translation": [5, 0, 0], "scale": [2, 2, 2], "extensions": {
+            "KHR_physics_rigid_bodies": {"collider": {"geometry": {"mesh": 0, "convexHull": true}}}
+        }},
+        {"name": "zone", "extensions": {
+            "KHR_physics_rigid_bodies": {"trigger": {"geometry": {"shape": 0}}}
+        }},
+        {"name": "decoration", "mesh": 0}
+    "#;
+    let extensions = r#"
+        "extensionsUsed": ["KHR_physics_rigid_bodies", "KHR_implicit_shapes"],
+        "extensions": {
+            "KHR_implicit_shapes": {"shapes": [
+                {"type": "box", "box": {"size": [1, 1, 1]}},
+                {"type": "sphere", "sphere": {"radius": 0.25}}
+            ]},
+            "KHR_physics_rigid_bodies": {"physicsMaterials": [
+                {"dynamicFriction": 0.2, "staticFriction": 0.3, "restitution": 0.7}
+            ]}
+        },
+    "#;
+    let data = glb(&gltf("", nodes, extensions), &cube());
+    let mut world = World::<f64>::new(WorldConfig::default());
+    let imported = import_gltf(&mut world, &data, None, &GltfSettings::default()).unwrap();
+
+    // Bodies with a motion take the colliders of their descendants.
+    let names: Vec<_> = imported
+        .iter()
+        .map(|i| i.name.as_deref().unwrap())
+        .collect();
+    assert_eq!(vec!["crate", "rock", "zone"], names);
+    assert_eq!(vec![0, 1], imported[0].colliders);
+    let body = &world.bodies[imported[0].body];
+    assert_eq!(BodyType::Dynamic, body.body_type);
+    assert!((body.mass() - 4.0).abs() < 1e-9);
+    assert_eq!(Vector3::new(1.0, 0.0, 0.0), body.velocity);
+    assert!(body.position.x > 0.0 && (body.position.y - 2.0).abs() < 1e-6);
+    let material = world.colliders[0].material;
+    assert_eq!((0.2, 0.7), (material.friction, material.restitution));
+    let handle = world.colliders[1].offset.translation();
+    assert!((body.position.x + handle.x - 1.0).abs() < 1e-6);
+
+    // Colliders on their own get static bodies, with scaled hulls for meshes.
+    let rock = &world.bodies[imported[1].body];
+    assert_eq!(BodyType::Static, rock.body_type);
+    assert_eq!(Vector3::new(5.0, 0.0, 0.0), rock.position);
+    match &world.colliders[imported[1].colliders[0]].shape {
+        Shape::ConvexHull(hull) => assert!((hull.volume() - 8.0).abs() < 1e-6),
+        shape => panic!("unexpected shape {:?}", shape),
+    }
+    assert!(world.colliders[imported[2].colliders[0]].sensor);
+    assert_eq!(3, world.bodies.len());
+}
+
+#[test]
+fn meshes() {
+    let nodes = r#"
+        {"name": "floor", "mesh": 0, "translation": [0, -1, 0]},
+        {"name": "lamp", "mesh": 0},
+        {"name": "wall", "mesh": 0, "children": [1]},
+        {"name": "empty"},
+        {"name": "table", "mesh": 0, "rotation": [0, 0.7071068, 0, 0.7071068]}
+    "#;
+    let buffer = format!(r#""uri": "data:application/octet-stream;base64,{}","#, CUBE);
+    let json = gltf(&buffer, nodes, "");
+
+    // Files without the physics extension get static hulls for their meshes.
+    let mut world = World::<f64>::new(WorldConfig::default());
+    let imported =
+        import_gltf(&mut world, json.as_bytes(), None, &GltfSettings::default()).unwrap();
+    let names: Vec<_> = imported
+        .iter()
+        .map(|i| i.name.as_deref().unwrap())
+        .collect();
+    assert_eq!(vec!["floor", "wall", "lamp", "table"], names);
+    assert!(world.bodies.iter().all(|b| b.body_type == BodyType::Static));
+    assert_eq!(Vector3::new(0.0, -1.0, 0.0), world.bodies[0].position);
+    assert!((world.bodies[3].orientation.j - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+
+    // Or triangle meshes, made of the vertices in order when they aren't indexed.
+    let settings = GltfSettings {
+        meshes: MeshColliders::TriMesh,
+        ..GltfSettings::default()
+    };
+    let mut world = World::<f64>::new(WorldConfig::default());
+    import_gltf(&mut world, json.as_bytes(), None, &settings).unwrap();
+    match &world.colliders[0].shape {
+        Shape::TriMesh(mesh) => assert_eq!(&[[0, 1, 2], [3, 4, 5]], mesh.triangles()),
+        shape => panic!("unexpected shape {:?}", shape),
+    }
+
+    // Unless they're ignored.
+    let settings = GltfSettings {
+        meshes: MeshColliders::None,
+        ..GltfSettings::default()
+    };
+    let mut world = World::<f64>::new(WorldConfig::default());
+    assert!(import_gltf(&mut world, json.as_bytes(), None, &settings)
+        .unwrap()
+        .is_empty());
+
+    // External buffers need a base path.
+    let json = gltf(r#""uri": "cube.bin","#, nodes, "");
+    match import_gltf(&mut world, json.as_bytes(), None, &settings) {
+        Err(GltfError::Buffer(0)) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+}
@@ -0,0 +1,573 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use math::Vector3;
+use wgpu::util::DeviceExt;
+
+/// Size in bytes of every particle in the particle buffer: four `vec4<f32>` holding the
+/// position and inverse mass, the velocity and damping, the acceleration, and the
+/// accumulated force.
+pub const PARTICLE_STRIDE: u64 = 64;
+
+/// Maximum number of particles a cell of the neighborhood grid holds. Particles beyond it
+/// aren't found as neighbors of anything.
+pub const CELL_CAPACITY: u32 = 32;
+
+/// Number of invocations of every workgroup of the compute shaders.
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Size in bytes of the parameters shared by every compute shader.
+const PARAMS_SIZE: u64 = 32;
+
+/// Compute shaders integrating the particles and finding their neighbors.
+const SHADER: &str = r#"
+const CELL_CAPACITY: u32 = 32u;
+
+struct Params {
+    count: u32,
+    table: u32,
+    max_neighbors: u32,
+    padding: u32,
+    radius: f32,
+    duration: f32,
+}
+
+struct Particle {
+    position: vec4<f32>,
+    velocity: vec4<f32>,
+    acceleration: vec4<f32>,
+    force: vec4<f32>,
+}
+
+struct Cell {
+    count: atomic<u32>,
+    slots: array<u32, CELL_CAPACITY>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(2) var<storage, read_write> cells: array<Cell>;
+@group(0) @binding(3) var<storage, read_write> neighbors: array<u32>;
+
+fn bucket(cell: vec3<i32>) -> u32 {
+    let hashed = bitcast<vec3<u32>>(cell) * vec3<u32>(73856093u, 19349663u, 83492791u);
+    return (hashed.x ^ hashed.y ^ hashed.z) % params.table;
+}
+
+fn cell_of(position: vec3<f32>) -> vec3<i32> {
+    return vec3<i32>(floor(position / params.radius));
+}
+
+@compute @workgroup_size(64)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    let particle = particles[index];
+    let inverse_mass = particle.position.w;
+    if (inverse_mass <= 0.0) {
+        return;
+    }
+    let duration = params.duration;
+    let acceleration = particle.acceleration.xyz + particle.force.xyz * inverse_mass;
+    let drag = pow(particle.velocity.w, duration);
+    let velocity = (particle.velocity.xyz + acceleration * duration) * drag;
+    let position = particle.position.xyz + particle.velocity.xyz * duration;
+    particles[index].position = vec4<f32>(position, inverse_mass);
+    particles[index].velocity = vec4<f32>(velocity, particle.velocity.w);
+    particles[index].force = vec4<f32>(0.0);
+}
+
+@compute @workgroup_size(64)
+fn clear(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x < params.table) {
+        atomicStore(&cells[id.x].count, 0u);
+    }
+}
+
+@compute @workgroup_size(64)
+fn insert(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    let cell = bucket(cell_of(particles[index].position.xyz));
+    let slot = atomicAdd(&cells[cell].count, 1u);
+    if (slot < CELL_CAPACITY) {
+        cells[cell].slots[slot] = index;
+    }
+}
+
+@compute @workgroup_size(64)
+fn find_neighbors(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    let position = particles[index].position.xyz;
+    let center = cell_of(position);
+    let stride = params.max_neighbors + 1u;
+    let reach = params.radius * params.radius;
+
+    // Cells around the particle may share a bucket, which is only gone through once.
+    var visited: array<u32, 27>;
+    var buckets = 0u;
+    var found = 0u;
+    for (var z = -1; z <= 1; z = z + 1) {
+        for (var y = -1; y <= 1; y = y + 1) {
+            for (var x = -1; x <= 1; x = x + 1) {
+                let cell = bucket(center + vec3<i32>(x, y, z));
+                var seen = false;
+                for (var other = 0u; other < buckets; other = other + 1u) {
+                    seen = seen || visited[other] == cell;
+                }
+                if (seen) {
+                    continue;
+                }
+                visited[buckets] = cell;
+                buckets = buckets + 1u;
+
+                let count = min(atomicLoad(&cells[cell].count), CELL_CAPACITY);
+                for (var slot = 0u; slot < count; slot = slot + 1u) {
+                    let other = cells[cell].slots[slot];
+                    let offset = particles[other].position.xyz - position;
+                    if (other != index && dot(offset, offset) <= reach
+                        && found < params.max_neighbors) {
+                        neighbors[index * stride + 1u + found] = other;
+                        found = found + 1u;
+                    }
+                }
+            }
+        }
+    }
+    neighbors[index * stride] = found;
+}
+"#;
+
+/// Error raised while running particles on the GPU.
+#[derive(Debug)]
+pub enum GpuError {
+    /// No GPU adapter is available.
+    NoAdapter,
+
+    /// The GPU device couldn't be created.
+    Device(wgpu::RequestDeviceError),
+
+    /// A buffer couldn't be read back from the GPU.
+    Map(wgpu::BufferAsyncError),
+}
+
+impl std::fmt::Display for GpuError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuError::NoAdapter => write!(f, "no GPU adapter available"),
+            GpuError::Device(error) => write!(f, "GPU device unavailable: {}", error),
+            GpuError::Map(error) => write!(f, "GPU buffer can't be read: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for GpuError {}
+
+impl From<wgpu::RequestDeviceError> for GpuError {
+    fn from(error: wgpu::RequestDeviceError) -> Self {
+        GpuError::Device(error)
+    }
+}
+
+impl From<wgpu::BufferAsyncError> for GpuError {
+    fn from(error: wgpu::BufferAsyncError) -> Self {
+        GpuError::Map(error)
+    }
+}
+
+/// Particles integrated, and their neighbors found, by compute shaders on the GPU.
+///
+/// # Remarks
+/// Particles are uploaded once, then stay on the GPU between steps, in single precision.
+/// Renderers sharing the device can draw them straight from `particle_buffer`, laid out
+/// as described by `PARTICLE_STRIDE`, and read the neighbors found from `neighbor_buffer`.
+/// Reading them back with `read_particles` and `read_neighbors` waits for the GPU.
+///
+/// Integration follows `Particle::integrate`, though the GPU may round differently. The
+/// neighborhood search hashes the particles into a grid with cells as large as the search
+/// radius, holding up to `CELL_CAPACITY` particles each.
+pub struct GpuParticles {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    integrate: wgpu::ComputePipeline,
+    clear: wgpu::ComputePipeline,
+    insert: wgpu::ComputePipeline,
+    find_neighbors: wgpu::ComputePipeline,
+    params: wgpu::Buffer,
+    particles: wgpu::Buffer,
+    cells: wgpu::Buffer,
+    neighbors: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    len: u32,
+    table: u32,
+    max_neighbors: u32,
+    radius: f32,
+}
+
+impl GpuParticles {
+    /// Creates a new set of particles without particles, on the default GPU adapter.
+    pub fn new() -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+                .ok_or(GpuError::NoAdapter)?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("phust particles"),
+                required_features: wgpu::Features::empty(),
+                required_limits: adapter.limits(),
+                memory_hints: wgpu::MemoryHints::default(),
+            },
+            None,
+        ))?;
+        Ok(Self::with_device(device, queue))
+    }
+
+    /// Creates a new set of particles without particles, on the given device, so a renderer
+    /// using it can draw them without reading them back.
+    pub fn with_device(device: wgpu::Device, queue: wgpu::Queue) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("phust particles"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let storage = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("phust particles"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1),
+                storage(2),
+                storage(3),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("phust particles"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None,
+            })
+        };
+        let (integrate, clear, insert, find_neighbors) = (
+            pipeline("integrate"),
+            pipeline("clear"),
+            pipeline("insert"),
+            pipeline("find_neighbors"),
+        );
+
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("phust parameters"),
+            size: PARAMS_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let particles = storage_buffer(&device, "phust particles", PARTICLE_STRIDE);
+        let cells = storage_buffer(&device, "phust cells", (CELL_CAPACITY as u64 + 1) * 4);
+        let neighbors = storage_buffer(&device, "phust neighbors", 4);
+        let bind_group = bind(&device, &layout, [&params, &particles, &cells, &neighbors]);
+        Self {
+            device,
+            queue,
+            layout,
+            integrate,
+            clear,
+            insert,
+            find_neighbors,
+            params,
+            particles,
+            cells,
+            neighbors,
+            bind_group,
+            len: 0,
+            table: 1,
+            max_neighbors: 0,
+            radius: 1.0,
+        }
+    }
+
+    /// Returns the device the particles live on.
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    /// Returns the queue the compute shaders are submitted to.
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// Returns the number of particles.
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns true if there are no particles.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the buffer holding the particles, `PARTICLE_STRIDE` bytes each, which can be
+    /// bound as a storage or vertex buffer.
+    pub fn particle_buffer(&self) -> &wgpu::Buffer {
+        &self.particles
+    }
+
+    /// Returns the buffer holding the neighbors found by the last search: for every
+    /// particle, the number of neighbors found, followed by room for the maximum number of
+    /// neighbor indices, all as `u32`.
+    pub fn neighbor_buffer(&self) -> &wgpu::Buffer {
+        &self.neighbors
+    }
+
+    /// Replaces the particles on the GPU with the given ones.
+    pub fn upload<F: num_traits::Float>(&mut self, particles: &[Particle<F>]) {
+        let single = |value: F| value.to_f32().unwrap_or(f32::NAN);
+        let mut bytes = Vec::with_capacity(particles.len() * PARTICLE_STRIDE as usize);
+        for particle in particles {
+            for (vector, w) in [
+                (particle.position, particle.inverse_mass),
+                (particle.velocity, particle.damping),
+                (particle.acceleration, F::zero()),
+                (particle.force_accum, F::zero()),
+            ] {
+                for value in [vector.x, vector.y, vector.z, w] {
+                    bytes.extend_from_slice(&single(value).to_le_bytes());
+                }
+            }
+        }
+        if bytes.is_empty() {
+            bytes.resize(PARTICLE_STRIDE as usize, 0);
+        }
+
+        self.particles = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("phust particles"),
+                contents: &bytes,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::VERTEX
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+            });
+        self.len = particles.len() as u32;
+        self.rebind();
+    }
+
+    /// Integrates every particle forward in time by the given amount (in seconds), the same
+    /// way `Particle::integrate` does.
+    pub fn integrate(&mut self, duration: f32) {
+        self.write_params(duration);
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        self.dispatch(&mut encoder, &self.integrate, self.len);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    /// Finds up to the given number of neighbors of every particle, closer than the given
+    /// radius, into the neighbor buffer.
+    pub fn find_neighbors(&mut self, radius: f32, max_neighbors: u32) {
+        let table = (self.len * 2).next_power_of_two().max(WORKGROUP_SIZE);
+        let size = table as u64 * (CELL_CAPACITY as u64 + 1) * 4;
+        if self.cells.size() != size {
+            self.cells = storage_buffer(&self.device, "phust cells", size);
+        }
+        let size = (self.len.max(1) as u64) * (max_neighbors as u64 + 1) * 4;
+        if self.neighbors.size() != size {
+            self.neighbors = storage_buffer(&self.device, "phust neighbors", size);
+        }
+        self.table = table;
+        self.max_neighbors = max_neighbors;
+        self.rebind();
+        self.radius = radius;
+        self.write_params(0.0);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        self.dispatch(&mut encoder, &self.clear, self.table);
+        self.dispatch(&mut encoder, &self.insert, self.len);
+        self.dispatch(&mut encoder, &self.find_neighbors, self.len);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    /// Reads the particles back from the GPU, waiting for it to finish its work.
+    pub fn read_particles(&self) -> Result<Vec<Particle<f32>>, GpuError> {
+        let bytes = self.read(&self.particles, self.len as u64 * PARTICLE_STRIDE)?;
+        let vector = |values: &[f32]| Vector3::new(values[0], values[1], values[2]);
+        Ok(floats(&bytes)
+            .chunks(16)
+            .map(|values| Particle {
+                position: vector(&values[0..4]),
+                velocity: vector(&values[4..8]),
+                acceleration: vector(&values[8..12]),
+                damping: values[7],
+                inverse_mass: values[3],
+                force_accum: vector(&values[12..16]),
+            })
+            .collect())
+    }
+
+    /// Reads back the indices of the neighbors found by the last search for every particle,
+    /// in no particular order, waiting for the GPU to finish its work.
+    pub fn read_neighbors(&self) -> Result<Vec<Vec<usize>>, GpuError> {
+        let stride = self.max_neighbors as usize + 1;
+        let size = (self.len as usize * stride * 4) as u64;
+        let bytes = self.read(&self.neighbors, size.min(self.neighbors.size()))?;
+        Ok(bytes
+            .chunks(stride * 4)
+            .map(|particle| {
+                let found =
+                    u32::from_le_bytes([particle[0], particle[1], particle[2], particle[3]]);
+                particle[4..4 + found as usize * 4]
+                    .chunks(4)
+                    .map(|index| u32::from_le_bytes([index[0], index[1], index[2], index[3]]))
+                    .map(|index| index as usize)
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Writes the parameters of the compute shaders, with the given step duration.
+    fn write_params(&self, duration: f32) {
+        let mut bytes = Vec::with_capacity(PARAMS_SIZE as usize);
+        for value in [self.len, self.table, self.max_neighbors, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [self.radius, duration, 0.0, 0.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        self.queue.write_buffer(&self.params, 0, &bytes);
+    }
+
+    /// Records a compute pass running the given pipeline over the given number of items.
+    fn dispatch(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        items: u32,
+    ) {
+        if items == 0 {
+            return;
+        }
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch_workgroups(items.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Binds the current buffers to the compute shaders.
+    fn rebind(&mut self) {
+        self.bind_group = bind(
+            &self.device,
+            &self.layout,
+            [&self.params, &self.particles, &self.cells, &self.neighbors],
+        );
+    }
+
+    /// Copies the first bytes of the given buffer back from the GPU.
+    fn read(&self, buffer: &wgpu::Buffer, size: u64) -> Result<Vec<u8>, GpuError> {
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("phust readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().expect("buffer mapped after waiting")?;
+        let bytes = slice.get_mapped_range().to_vec();
+        staging.unmap();
+        Ok(bytes)
+    }
+}
+
+/// Creates a storage buffer of the given size, which can be copied from and to.
+fn storage_buffer(device: &wgpu::Device, label: &str, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size,
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Binds the parameters, particles, cells and neighbors buffers to the compute shaders.
+fn bind(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    buffers: [&wgpu::Buffer; 4],
+) -> wgpu::BindGroup {
+    let entries: Vec<_> = buffers
+        .iter()
+        .enumerate()
+        .map(|(binding, buffer)| wgpu::BindGroupEntry {
+            binding: binding as u32,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("phust particles"),
+        layout,
+        entries: &entries,
+    })
+}
+
+/// Reads the given bytes as little-endian single precision floats.
+fn floats(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks(4)
+        .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+        .collect()
+}
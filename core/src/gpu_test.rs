@@ -0,0 +1,100 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::gpu::*;
+use crate::particle::Particle;
+use math::Vector3;
+
+/// Returns particles on the default adapter, or `None` on machines without any, where the
+/// GPU tests are skipped.
+fn gpu() -> Option<GpuParticles> {
+    match GpuParticles::new() {
+        Ok(gpu) => Some(gpu),
+        Err(GpuError::NoAdapter) => None,
+        Err(error) => panic!("{}", error),
+    }
+}
+
+/// Returns particles scattered over a few cells, some of them falling.
+fn scattered() -> Vec<Particle<f32>> {
+    (0..500)
+        .map(|index| {
+            let offset = |factor: usize| ((index * factor) % 97) as f32 * 0.05;
+            let mut particle = Particle::new(Vector3::new(offset(7), offset(13), offset(31)), 2.0);
+            particle.velocity = Vector3::new(1.0, 0.0, -0.5);
+            particle.acceleration = Vector3::new(0.0, -9.8, 0.0);
+            particle.damping = 0.95;
+            particle
+        })
+        .collect()
+}
+
+#[test]
+fn integration() {
+    let mut gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+    let mut particles = scattered();
+    particles[1].set_infinite_mass();
+    particles[2].add_force(&Vector3::new(4.0, 0.0, 0.0));
+    gpu.upload(&particles);
+    assert_eq!(particles.len(), gpu.len());
+
+    for _ in 0..60 {
+        for particle in particles.iter_mut() {
+            particle.integrate(1.0 / 60.0);
+        }
+        gpu.integrate(1.0 / 60.0);
+    }
+
+    // The GPU follows the particles integrated on the CPU, up to rounding.
+    let read = gpu.read_particles().unwrap();
+    for (particle, read) in particles.iter().zip(read.iter()) {
+        assert!((particle.position - read.position).magnitude() < 1e-4);
+        assert!((particle.velocity - read.velocity).magnitude() < 1e-4);
+        assert_eq!(Vector3::origin(), read.force_accum);
+    }
+    assert_eq!(particles[1].position, read[1].position);
+}
+
+#[test]
+fn neighbors() {
+    let mut gpu = match gpu() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+    let particles = scattered();
+    gpu.upload(&particles);
+    gpu.find_neighbors(0.3, 64);
+
+    // Every particle finds the same neighbors as checking against every other particle.
+    let found = gpu.read_neighbors().unwrap();
+    assert_eq!(particles.len(), found.len());
+    for (index, found) in found.iter().enumerate() {
+        let mut found = found.clone();
+        found.sort_unstable();
+        let expected: Vec<usize> = (0..particles.len())
+            .filter(|other| {
+                let offset = particles[*other].position - particles[index].position;
+                *other != index && offset.squared_magnitude() <= 0.09
+            })
+            .collect();
+        assert_eq!(expected, found);
+    }
+
+    // Searches can be repeated after the particles move, with fewer neighbors kept.
+    gpu.integrate(0.1);
+    gpu.find_neighbors(0.3, 2);
+    assert!(gpu
+        .read_neighbors()
+        .unwrap()
+        .iter()
+        .all(|found| found.len() <= 2));
+    gpu.upload::<f32>(&[]);
+    assert!(gpu.is_empty());
+    assert!(gpu.read_particles().unwrap().is_empty());
+}
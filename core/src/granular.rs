@@ -0,0 +1,502 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::particle_batch::{Lanes, ParticleBatch, LANES};
+use crate::plane::Plane;
+use crate::spatial::SpatialHash;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Default fraction of the Rayleigh time step a granular medium steps with.
+pub const DEFAULT_GRANULAR_TIME_STEP_FRACTION: f64 = 0.2;
+
+/// Material the grains of a granular medium are made of.
+///
+/// # Remarks
+/// Real minerals are millions of times stiffer than what can be simulated at interactive
+/// rates, so the Young's modulus is usually lowered, which lets grains overlap a bit more
+/// but barely changes how the medium flows and piles up.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GranularMaterial<F: num_traits::Float = f64> {
+    /// Young's modulus of the grains, in pascals.
+    pub youngs_modulus: F,
+
+    /// Poisson's ratio of the grains.
+    pub poisson_ratio: F,
+
+    /// Coefficient of restitution of the collisions between grains.
+    pub restitution: F,
+
+    /// Coefficient of sliding friction between grains.
+    pub friction: F,
+
+    /// Coefficient of rolling friction between grains, the lever arm of the torque resisting
+    /// their rolling relative to their radius.
+    pub rolling_friction: F,
+
+    /// Density of the grains, in kilograms per cubic meter.
+    pub density: F,
+}
+
+impl<F: num_traits::Float> GranularMaterial<F> {
+    /// Creates a new material like softened dry sand.
+    pub fn sand() -> Self {
+        Self {
+            youngs_modulus: math::real(1e7),
+            poisson_ratio: math::real(0.3),
+            restitution: math::real(0.5),
+            friction: math::real(0.5),
+            rolling_friction: math::real(0.1),
+            density: math::real(2600.0),
+        }
+    }
+
+    /// Returns the effective Young's modulus of the contact between two grains.
+    fn contact_modulus(&self) -> F {
+        let ratio = self.poisson_ratio;
+        self.youngs_modulus / (math::real::<F>(2.0) * (F::one() - ratio * ratio))
+    }
+
+    /// Returns the effective shear modulus of the contact between two grains.
+    fn contact_shear_modulus(&self) -> F {
+        let ratio = self.poisson_ratio;
+        let shear = self.youngs_modulus / (math::real::<F>(2.0) * (F::one() + ratio));
+        shear / (math::real::<F>(2.0) * (math::real::<F>(2.0) - ratio))
+    }
+
+    /// Returns the damping ratio matching the coefficient of restitution, negative.
+    fn damping_ratio(&self) -> F {
+        if self.restitution <= F::zero() {
+            return -F::one();
+        }
+        let log = self.restitution.min(F::one()).ln();
+        let pi = math::real::<F>(std::f64::consts::PI);
+        log / (log * log + pi * pi).sqrt()
+    }
+}
+
+/// Granular medium, like sand or gravel, simulated with the discrete element method.
+///
+/// # Remarks
+/// Every grain is a sphere pushing its neighbors with Hertzian contacts, the normal force
+/// growing with the overlap to the power of `3/2`, and damped to match the restitution. The
+/// tangential force follows Mindlin's spring, which remembers how far each contact sheared
+/// while it lasts, capped by Coulomb friction, and rolling friction opposes the grains
+/// turning against each other. The same laws hold the grains against the boundary planes.
+///
+/// Neighbors are found through a spatial hash, and the grains are moved in the SIMD batches
+/// of a particle batch. Contacts need short steps to be stable, so every frame is split
+/// into steps of a fraction of the Rayleigh time step of the smallest grain.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GranularMedium<F: Lanes<LANES> = f64> {
+    /// Half-spaces containing the grains, with their normals pointing inwards.
+    pub boundaries: Vec<Plane<F>>,
+
+    /// Acceleration due to gravity.
+    pub gravity: Vector3<F>,
+
+    /// Material of the grains.
+    pub material: GranularMaterial<F>,
+
+    /// Fraction of the Rayleigh time step the medium steps with.
+    pub time_step_fraction: F,
+
+    grains: ParticleBatch<F>,
+    radii: Vec<F>,
+    rotations: Vec<Vector3<F>>,
+    torques: Vec<Vector3<F>>,
+    grid: SpatialHash<F>,
+    springs: HashMap<(usize, usize), Vector3<F>>,
+    wall_springs: HashMap<(usize, usize), Vector3<F>>,
+}
+
+impl<F: Lanes<LANES>> GranularMedium<F> {
+    /// Creates a new granular medium without grains, made of the given material.
+    pub fn new(material: GranularMaterial<F>) -> Self {
+        Self {
+            boundaries: Vec::new(),
+            gravity: Vector3::origin(),
+            material,
+            time_step_fraction: math::real(DEFAULT_GRANULAR_TIME_STEP_FRACTION),
+            grains: ParticleBatch::new(),
+            radii: Vec::new(),
+            rotations: Vec::new(),
+            torques: Vec::new(),
+            grid: SpatialHash::new(F::one()),
+            springs: HashMap::new(),
+            wall_springs: HashMap::new(),
+        }
+    }
+
+    /// Creates a new granular medium filling a box with grains of the given radius.
+    ///
+    /// # Remarks
+    /// Grains are placed touching each other in layers, every other layer shifted by a radius
+    /// so they don't stack in perfect columns.
+    pub fn block(
+        min: &Vector3<F>,
+        max: &Vector3<F>,
+        radius: F,
+        material: GranularMaterial<F>,
+    ) -> Self {
+        let mut medium = Self::new(material);
+        let diameter = radius + radius;
+        let count = |low: F, high: F| {
+            ((high - low - radius) / diameter)
+                .floor()
+                .to_usize()
+                .unwrap_or(0)
+        };
+        for y in 0..count(min.y, max.y) {
+            let shift = if y % 2 == 0 { F::zero() } else { radius };
+            for x in 0..count(min.x + shift, max.x) {
+                for z in 0..count(min.z + shift, max.z) {
+                    let at = |index: usize, shift: F| {
+                        math::real::<F>(index as f64) * diameter + radius + shift
+                    };
+                    let offset = Vector3::new(at(x, shift), at(y, F::zero()), at(z, shift));
+                    medium.add_grain(min.vector_add(&offset), radius);
+                }
+            }
+        }
+        medium
+    }
+
+    /// Adds a grain at rest with the given radius, returning its index.
+    pub fn add_grain(&mut self, position: Vector3<F>, radius: F) -> usize {
+        let volume = math::real::<F>(4.0 / 3.0 * std::f64::consts::PI) * radius * radius * radius;
+        self.grains
+            .push(&Particle::new(position, volume * self.material.density));
+        self.radii.push(radius);
+        self.rotations.push(Vector3::origin());
+        self.torques.push(Vector3::origin());
+        self.radii.len() - 1
+    }
+
+    /// Returns the number of grains.
+    pub fn len(&self) -> usize {
+        self.radii.len()
+    }
+
+    /// Returns true if there are no grains.
+    pub fn is_empty(&self) -> bool {
+        self.radii.is_empty()
+    }
+
+    /// Returns the position of the grain with the given index.
+    pub fn position(&self, grain: usize) -> Vector3<F> {
+        self.grains.position(grain)
+    }
+
+    /// Returns the velocity of the grain with the given index.
+    pub fn velocity(&self, grain: usize) -> Vector3<F> {
+        self.grains.velocity(grain)
+    }
+
+    /// Sets the velocity of the grain with the given index.
+    pub fn set_velocity(&mut self, grain: usize, velocity: Vector3<F>) {
+        let mut particle = self.grains.get(grain);
+        particle.velocity = velocity;
+        self.grains.set(grain, &particle);
+    }
+
+    /// Returns the angular velocity of the grain with the given index.
+    pub fn rotation(&self, grain: usize) -> Vector3<F> {
+        self.rotations[grain]
+    }
+
+    /// Sets the angular velocity of the grain with the given index.
+    pub fn set_rotation(&mut self, grain: usize, rotation: Vector3<F>) {
+        self.rotations[grain] = rotation;
+    }
+
+    /// Returns the radius of the grain with the given index.
+    pub fn radius(&self, grain: usize) -> F {
+        self.radii[grain]
+    }
+
+    /// Returns the mass of the grain with the given index.
+    pub fn mass(&self, grain: usize) -> F {
+        self.grains.get(grain).mass()
+    }
+
+    /// Returns the number of grains touching each other, and the boundaries, as of the last
+    /// step.
+    pub fn contacts(&self) -> usize {
+        self.springs.len() + self.wall_springs.len()
+    }
+
+    /// Returns the kinetic energy of the grains, including their rotation.
+    pub fn kinetic_energy(&self) -> F {
+        let half = math::real::<F>(0.5);
+        (0..self.len()).fold(F::zero(), |energy, grain| {
+            let mass = self.mass(grain);
+            let inertia = self.inertia(grain, mass);
+            energy
+                + half * mass * self.velocity(grain).squared_magnitude()
+                + half * inertia * self.rotations[grain].squared_magnitude()
+        })
+    }
+
+    /// Returns the Rayleigh time step of the smallest grain, the time a shear wave takes to
+    /// go around it, which bounds the steps contacts are stable with.
+    pub fn rayleigh_time_step(&self) -> F {
+        let radius = self
+            .radii
+            .iter()
+            .fold(F::infinity(), |smallest, radius| smallest.min(*radius));
+        if radius == F::infinity() {
+            return F::infinity();
+        }
+        let material = &self.material;
+        let ratio = material.poisson_ratio;
+        let shear = material.youngs_modulus / (math::real::<F>(2.0) * (F::one() + ratio));
+        math::real::<F>(std::f64::consts::PI) * radius * (material.density / shear).sqrt()
+            / (math::real::<F>(0.1631) * ratio + math::real(0.8766))
+    }
+
+    /// Processes all the physics for the granular medium, in as many steps as needed to keep
+    /// them below the fraction of the Rayleigh time step.
+    pub fn run_physics(&mut self, duration: F) {
+        if self.is_empty() || duration <= F::zero() {
+            return;
+        }
+        let limit = self.rayleigh_time_step() * self.time_step_fraction;
+        let steps = (duration / limit).ceil().to_usize().unwrap_or(1).max(1);
+        let step = duration / math::real(steps as f64);
+        for _ in 0..steps {
+            self.step(step);
+        }
+    }
+
+    /// Moves the grains for a single step.
+    fn step(&mut self, duration: F) {
+        for grain in 0..self.len() {
+            let weight = self.gravity.scalar_mul(self.mass(grain));
+            self.grains.add_force(grain, &weight);
+            self.torques[grain] = Vector3::origin();
+        }
+        self.collide_grains(duration);
+        self.collide_boundaries(duration);
+
+        self.grains.integrate_symplectic(duration);
+        for grain in 0..self.len() {
+            let inertia = self.inertia(grain, self.mass(grain));
+            let change = self.torques[grain].scalar_mul(duration / inertia);
+            self.rotations[grain].inplace_vector_add(&change);
+        }
+    }
+
+    /// Returns the moment of inertia of the grain with the given index and mass.
+    fn inertia(&self, grain: usize, mass: F) -> F {
+        let radius = self.radii[grain];
+        math::real::<F>(0.4) * mass * radius * radius
+    }
+
+    /// Hashes the grains and adds the contact forces between the ones touching each other.
+    fn collide_grains(&mut self, duration: F) {
+        let largest = self
+            .radii
+            .iter()
+            .fold(F::zero(), |largest, radius| largest.max(*radius));
+        self.grid.cell_size = largest + largest;
+        self.grid.clear();
+        for grain in 0..self.len() {
+            self.grid.insert(grain, &self.grains.position(grain));
+        }
+
+        let mut pairs = Vec::new();
+        for grain in 0..self.len() {
+            let position = self.grains.position(grain);
+            let reach = self.radii[grain] + largest;
+            self.grid.query_sphere(&position, reach, |other| {
+                if other > grain {
+                    pairs.push((grain, other));
+                }
+                true
+            });
+        }
+
+        let mut springs = HashMap::with_capacity(pairs.len());
+        for (one, two) in pairs {
+            let offset = self
+                .grains
+                .position(one)
+                .vector_sub(&self.grains.position(two));
+            let distance = offset.magnitude();
+            let (radius_one, radius_two) = (self.radii[one], self.radii[two]);
+            let overlap = radius_one + radius_two - distance;
+            if overlap <= F::zero() || distance <= F::zero() {
+                continue;
+            }
+
+            let normal = offset.scalar_div(distance);
+            let (mass_one, mass_two) = (self.mass(one), self.mass(two));
+            let contact = Contact {
+                normal,
+                overlap,
+                radius: radius_one * radius_two / (radius_one + radius_two),
+                mass: mass_one * mass_two / (mass_one + mass_two),
+                inertia: {
+                    let (inertia_one, inertia_two) =
+                        (self.inertia(one, mass_one), self.inertia(two, mass_two));
+                    inertia_one * inertia_two / (inertia_one + inertia_two)
+                },
+                velocity: self
+                    .grains
+                    .velocity(one)
+                    .vector_sub(
+                        &self.rotations[one]
+                            .cross_product(&normal)
+                            .scalar_mul(radius_one),
+                    )
+                    .vector_sub(
+                        &self.grains.velocity(two).vector_add(
+                            &self.rotations[two]
+                                .cross_product(&normal)
+                                .scalar_mul(radius_two),
+                        ),
+                    ),
+                rotation: self.rotations[one].vector_sub(&self.rotations[two]),
+            };
+            let spring = self.springs.get(&(one, two)).copied();
+            let (force, shear, rolling, spring) = contact.forces(&self.material, spring, duration);
+            springs.insert((one, two), spring);
+
+            self.grains.add_force(one, &force);
+            self.grains.add_force(two, &force.invert());
+            let twist = normal.cross_product(&shear);
+            self.torques[one]
+                .inplace_vector_sub(&twist.scalar_mul(radius_one).vector_add(&rolling));
+            self.torques[two]
+                .inplace_vector_sub(&twist.scalar_mul(radius_two).vector_sub(&rolling));
+        }
+        self.springs = springs;
+    }
+
+    /// Adds the contact forces between the grains and the boundary planes.
+    fn collide_boundaries(&mut self, duration: F) {
+        let mut springs = HashMap::new();
+        for grain in 0..self.len() {
+            let position = self.grains.position(grain);
+            let radius = self.radii[grain];
+            for (index, plane) in self.boundaries.iter().enumerate() {
+                let overlap = radius - plane.signed_distance(&position);
+                if overlap <= F::zero() {
+                    continue;
+                }
+
+                let normal = plane.normal;
+                let mass = self.mass(grain);
+                let contact = Contact {
+                    normal,
+                    overlap,
+                    radius,
+                    mass,
+                    inertia: self.inertia(grain, mass),
+                    velocity: self.grains.velocity(grain).vector_sub(
+                        &self.rotations[grain]
+                            .cross_product(&normal)
+                            .scalar_mul(radius),
+                    ),
+                    rotation: self.rotations[grain],
+                };
+                let spring = self.wall_springs.get(&(grain, index)).copied();
+                let (force, shear, rolling, spring) =
+                    contact.forces(&self.material, spring, duration);
+                springs.insert((grain, index), spring);
+
+                self.grains.add_force(grain, &force);
+                let twist = normal.cross_product(&shear).scalar_mul(radius);
+                self.torques[grain].inplace_vector_sub(&twist.vector_add(&rolling));
+            }
+        }
+        self.wall_springs = springs;
+    }
+}
+
+/// Contact between a grain and another grain or a boundary, seen from the first grain.
+struct Contact<F: num_traits::Float> {
+    /// Direction pushing the first grain out of the contact.
+    normal: Vector3<F>,
+
+    /// Depth the grains overlap by.
+    overlap: F,
+
+    /// Effective radius of the contact.
+    radius: F,
+
+    /// Effective mass of the contact.
+    mass: F,
+
+    /// Effective moment of inertia of the contact.
+    inertia: F,
+
+    /// Velocity of the first grain relative to the second one, at the contact point.
+    velocity: Vector3<F>,
+
+    /// Angular velocity of the first grain relative to the second one.
+    rotation: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Contact<F> {
+    /// Returns the force on the first grain, its tangential part, the rolling friction
+    /// torque on the first grain, and the sheared spring of the contact after the given step.
+    fn forces(
+        &self,
+        material: &GranularMaterial<F>,
+        spring: Option<Vector3<F>>,
+        duration: F,
+    ) -> (Vector3<F>, Vector3<F>, Vector3<F>, Vector3<F>) {
+        let damping = math::real::<F>(2.0 * (5.0f64 / 6.0).sqrt()) * material.damping_ratio();
+        let contact = (self.radius * self.overlap).sqrt();
+
+        // Hertzian normal force, damped to match the restitution, and never pulling.
+        let stiffness = math::real::<F>(2.0) * material.contact_modulus() * contact;
+        let approach = self.velocity.dot_product(&self.normal);
+        let normal_force = (math::real::<F>(2.0 / 3.0) * stiffness * self.overlap
+            + damping * (stiffness * self.mass).sqrt() * approach)
+            .max(F::zero());
+
+        // Mindlin tangential spring, kept in the plane of the contact and capped by friction.
+        let sliding = self.velocity.vector_sub(&self.normal.scalar_mul(approach));
+        let shear_stiffness = math::real::<F>(8.0) * material.contact_shear_modulus() * contact;
+        let spring = spring.unwrap_or_else(Vector3::origin);
+        let spring = spring
+            .vector_sub(&self.normal.scalar_mul(spring.dot_product(&self.normal)))
+            .vector_add(&sliding.scalar_mul(duration));
+        let mut shear = spring
+            .scalar_mul(-shear_stiffness)
+            .vector_add(&sliding.scalar_mul(damping * (shear_stiffness * self.mass).sqrt()));
+        let limit = material.friction * normal_force;
+        let magnitude = shear.magnitude();
+        let spring = if magnitude > limit {
+            shear = shear.scalar_mul(limit / magnitude);
+            shear.scalar_div(-shear_stiffness)
+        } else {
+            spring
+        };
+
+        // Rolling friction, never reversing the relative rotation within the step.
+        let turning = self.rotation.magnitude();
+        let rolling = if turning > F::zero() {
+            let torque = (material.rolling_friction * self.radius * normal_force)
+                .min(turning * self.inertia / duration);
+            self.rotation.scalar_mul(torque / turning)
+        } else {
+            Vector3::origin()
+        };
+
+        (
+            self.normal.scalar_mul(normal_force).vector_add(&shear),
+            shear,
+            rolling,
+            spring,
+        )
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::granular::*;
+use crate::plane::Plane;
+use math::Vector3;
+
+fn floor() -> Plane<f64> {
+    Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0)
+}
+
+#[test]
+fn collisions() {
+    let mut medium = GranularMedium::new(GranularMaterial::<f64>::sand());
+    let one = medium.add_grain(Vector3::new(-0.02, 0.0, 0.0), 0.01);
+    let two = medium.add_grain(Vector3::new(0.02, 0.0, 0.0), 0.01);
+    medium.set_velocity(one, Vector3::new(1.0, 0.0, 0.0));
+    medium.set_velocity(two, Vector3::new(-1.0, 0.0, 0.0));
+    let energy = medium.kinetic_energy();
+
+    // Grains bounce off each other with about the restitution of their material, the damping
+    // of Hertzian contacts matching it only approximately.
+    for _ in 0..6 {
+        medium.run_physics(1.0 / 240.0);
+    }
+    let (first, second) = (medium.velocity(one), medium.velocity(two));
+    assert!((first + second).magnitude() < 1e-12);
+    assert!(((second - first).x - 2.0 * 0.5).abs() < 0.1);
+    assert!(medium.kinetic_energy() < energy);
+    assert_eq!(0, medium.contacts());
+
+    // Grains dropped on the floor bounce the same way, and come to rest on it.
+    let mut medium = GranularMedium::new(GranularMaterial::<f64>::sand());
+    medium.boundaries.push(floor());
+    medium.gravity = Vector3::new(0.0, -10.0, 0.0);
+    let grain = medium.add_grain(Vector3::new(0.0, 0.0105, 0.0), 0.01);
+    medium.set_velocity(grain, Vector3::new(0.0, -2.0, 0.0));
+    let mut highest = 0.0f64;
+    for _ in 0..120 {
+        medium.run_physics(1.0 / 240.0);
+        highest = highest.max(medium.velocity(grain).y);
+    }
+    assert!((highest - 1.0).abs() < 0.1);
+    for _ in 0..240 {
+        medium.run_physics(1.0 / 240.0);
+    }
+    assert!(medium.velocity(grain).magnitude() < 1e-3);
+    assert!((medium.position(grain).y - 0.01).abs() < 1e-4);
+    assert_eq!(1, medium.contacts());
+}
+
+#[test]
+fn rolling_friction() {
+    let rolled = |rolling_friction: f64| {
+        let material = GranularMaterial {
+            rolling_friction,
+            ..GranularMaterial::sand()
+        };
+        let mut medium = GranularMedium::new(material);
+        medium.boundaries.push(floor());
+        medium.gravity = Vector3::new(0.0, -10.0, 0.0);
+        let grain = medium.add_grain(Vector3::new(0.0, 0.01, 0.0), 0.01);
+        medium.set_velocity(grain, Vector3::new(1.0, 0.0, 0.0));
+        for _ in 0..60 {
+            medium.run_physics(1.0 / 60.0);
+        }
+        (
+            medium.velocity(grain),
+            medium.rotation(grain),
+            medium.position(grain),
+        )
+    };
+
+    // Friction turns sliding into rolling without slipping, at five sevenths of the speed.
+    let (velocity, rotation, _) = rolled(0.0);
+    assert!((velocity.x - 5.0 / 7.0).abs() < 0.01);
+    assert!((rotation.z * 0.01 + velocity.x).abs() < 1e-3);
+
+    // Rolling friction brings the grain to a stop.
+    let (velocity, rotation, position) = rolled(0.1);
+    assert!(velocity.magnitude() < 1e-3);
+    assert!(rotation.magnitude() < 0.1);
+    assert!(position.x > 0.1 && position.x < 1.0);
+}
+
+#[test]
+fn piles() {
+    // Grains poured on the floor pile up with friction, and spread out without it.
+    let pour = |friction: f64, rolling_friction: f64| {
+        let material = GranularMaterial {
+            youngs_modulus: 1e5,
+            friction,
+            rolling_friction,
+            ..GranularMaterial::sand()
+        };
+        let mut medium = GranularMedium::block(
+            &Vector3::new(-0.1, 0.0, -0.1),
+            &Vector3::new(0.1, 0.6, 0.1),
+            0.02,
+            material,
+        );
+        medium.boundaries.push(floor());
+        medium.gravity = Vector3::new(0.0, -10.0, 0.0);
+        for _ in 0..90 {
+            medium.run_physics(1.0 / 60.0);
+        }
+        let height =
+            (0..medium.len()).fold(0.0f64, |height, grain| height.max(medium.position(grain).y));
+        (height, medium.kinetic_energy())
+    };
+
+    // Frictional grains settle into a pile a few grains high.
+    let (height, energy) = pour(0.6, 0.2);
+    assert!(height > 0.05);
+    assert!(energy < 0.01);
+
+    // Frictionless grains keep sliding apart until they lie in a single layer.
+    let (height, energy) = pour(0.0, 0.0);
+    assert!(height < 0.02);
+    assert!(energy > 1.0);
+}
@@ -0,0 +1,232 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Source of the acceleration due to gravity.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GravitySource<F: num_traits::Float = f64> {
+    /// The same acceleration everywhere.
+    Uniform(Vector3<F>),
+
+    /// Acceleration pulling towards a center, like the one of a planet.
+    Point {
+        /// Point everything falls towards.
+        center: Vector3<F>,
+
+        /// Magnitude of the acceleration, or the gravitational parameter of the planet
+        /// (its mass times the gravitational constant) if it falls off with the distance.
+        strength: F,
+
+        /// Whether the acceleration falls off with the square of the distance to the center.
+        inverse_square: bool,
+    },
+}
+
+impl<F: num_traits::Float> GravitySource<F> {
+    /// Returns the acceleration due to gravity at the given point.
+    ///
+    /// # Remarks
+    /// Point sources don't pull anything exactly at their center.
+    pub fn acceleration_at(&self, point: &Vector3<F>) -> Vector3<F> {
+        match self {
+            GravitySource::Uniform(acceleration) => *acceleration,
+            GravitySource::Point {
+                center,
+                strength,
+                inverse_square,
+            } => {
+                let offset = center.vector_sub(point);
+                let squared_distance = offset.squared_magnitude();
+                if squared_distance <= F::epsilon() {
+                    return Vector3::origin();
+                }
+                let magnitude = if *inverse_square {
+                    *strength / squared_distance
+                } else {
+                    *strength
+                };
+                offset.normalize().scalar_mul(magnitude)
+            }
+        }
+    }
+
+    /// Returns the source moved by the opposite of the given offset, as the origin of the
+    /// world moves to it.
+    pub(crate) fn shifted(&self, offset: &Vector3<F>) -> Self {
+        match *self {
+            GravitySource::Uniform(acceleration) => GravitySource::Uniform(acceleration),
+            GravitySource::Point {
+                center,
+                strength,
+                inverse_square,
+            } => GravitySource::Point {
+                center: center.vector_sub(offset),
+                strength,
+                inverse_square,
+            },
+        }
+    }
+
+    /// Returns the potential energy per unit of mass at the given point.
+    ///
+    /// # Remarks
+    /// Uniform gravity has zero potential at the origin. Point sources have zero potential
+    /// at their center when the acceleration is constant, and infinitely far away when it
+    /// falls off with the square of the distance, where the center is taken as zero too.
+    pub fn potential_at(&self, point: &Vector3<F>) -> F {
+        match self {
+            GravitySource::Uniform(acceleration) => -acceleration.dot_product(point),
+            GravitySource::Point {
+                center,
+                strength,
+                inverse_square,
+            } => {
+                let distance = center.vector_sub(point).magnitude();
+                if !*inverse_square {
+                    *strength * distance
+                } else if distance <= F::epsilon() {
+                    num_traits::zero()
+                } else {
+                    -*strength / distance
+                }
+            }
+        }
+    }
+}
+
+/// Region of space covered by a gravity zone.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GravityRegion<F: num_traits::Float = f64> {
+    /// Axis-aligned box.
+    Box(Aabb<F>),
+
+    /// Sphere with a center and a radius.
+    Sphere(Vector3<F>, F),
+}
+
+impl<F: num_traits::Float> GravityRegion<F> {
+    /// Returns true if the given point is inside the region.
+    pub fn contains(&self, point: &Vector3<F>) -> bool {
+        match self {
+            GravityRegion::Box(aabb) => aabb.contains_point(point),
+            GravityRegion::Sphere(center, radius) => {
+                point.vector_sub(center).squared_magnitude() <= *radius * *radius
+            }
+        }
+    }
+}
+
+/// Volume of space with its own gravity, replacing the global one inside it.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GravityZone<F: num_traits::Float = f64> {
+    /// Region covered by the zone.
+    pub region: GravityRegion<F>,
+
+    /// Gravity inside the zone.
+    pub source: GravitySource<F>,
+
+    /// Priority of the zone over the ones overlapping it; higher priorities win.
+    pub priority: i32,
+}
+
+/// Gravity of a world, made of a global source and zones overriding it.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GravityField<F: num_traits::Float = f64> {
+    /// Gravity outside every zone.
+    pub global: GravitySource<F>,
+
+    /// Zones with their own gravity.
+    #[serde(default = "Vec::new")]
+    pub zones: Vec<GravityZone<F>>,
+}
+
+impl<F: num_traits::Float> Default for GravityField<F> {
+    fn default() -> Self {
+        Self::new(GravitySource::Uniform(Vector3::origin()))
+    }
+}
+
+impl<F: num_traits::Float> GravityField<F> {
+    /// Creates a new gravity field with the given global source and no zones.
+    pub fn new(global: GravitySource<F>) -> Self {
+        Self {
+            global,
+            zones: Vec::new(),
+        }
+    }
+
+    /// Adds a zone with its own gravity to the field.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the gravity field.
+    pub fn add_zone(
+        &mut self,
+        region: GravityRegion<F>,
+        source: GravitySource<F>,
+        priority: i32,
+    ) -> &mut Self {
+        self.zones.push(GravityZone {
+            region,
+            source,
+            priority,
+        });
+        self
+    }
+
+    /// Moves the point sources and the regions of the zones of the field by the opposite of
+    /// the given offset, as the origin of the world moves to it.
+    pub(crate) fn shift_origin(&mut self, offset: &Vector3<F>) {
+        self.global = self.global.shifted(offset);
+        for zone in self.zones.iter_mut() {
+            zone.source = zone.source.shifted(offset);
+            zone.region = match zone.region {
+                GravityRegion::Box(aabb) => GravityRegion::Box(Aabb::new(
+                    aabb.min.vector_sub(offset),
+                    aabb.max.vector_sub(offset),
+                )),
+                GravityRegion::Sphere(center, radius) => {
+                    GravityRegion::Sphere(center.vector_sub(offset), radius)
+                }
+            };
+        }
+    }
+
+    /// Returns the source of gravity at the given point.
+    ///
+    /// # Remarks
+    /// Of the zones containing the point, the one with the highest priority wins, and the
+    /// first one added among those with the same priority.
+    pub fn source_at(&self, point: &Vector3<F>) -> &GravitySource<F> {
+        let mut source = &self.global;
+        let mut best = None;
+        for zone in self.zones.iter() {
+            if best.is_none_or(|priority| zone.priority > priority) && zone.region.contains(point) {
+                source = &zone.source;
+                best = Some(zone.priority);
+            }
+        }
+        source
+    }
+
+    /// Returns the acceleration due to gravity at the given point.
+    pub fn acceleration_at(&self, point: &Vector3<F>) -> Vector3<F> {
+        self.source_at(point).acceleration_at(point)
+    }
+
+    /// Returns the potential energy per unit of mass at the given point, given by the
+    /// source of gravity there.
+    ///
+    /// # Remarks
+    /// Zones don't share their potential with the gravity around them, so bodies crossing
+    /// their boundaries gain or lose potential energy without any work being done.
+    pub fn potential_at(&self, point: &Vector3<F>) -> F {
+        self.source_at(point).potential_at(point)
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::gravity::*;
+use crate::rigid_body::RigidBody;
+use crate::world::{World, WorldConfig};
+use math::{Matrix3, Vector3};
+
+#[test]
+fn sources_and_zones() {
+    let planet = GravitySource::Point {
+        center: Vector3::new(0.0, -10.0, 0.0),
+        strength: 400.0,
+        inverse_square: true,
+    };
+    assert_eq!(
+        Vector3::new(0.0, -4.0, 0.0),
+        planet.acceleration_at(&Vector3::origin())
+    );
+    assert_eq!(
+        Vector3::new(0.0, 1.0, 0.0),
+        planet.acceleration_at(&Vector3::new(0.0, -30.0, 0.0))
+    );
+    assert_eq!(
+        Vector3::origin(),
+        planet.acceleration_at(&Vector3::new(0.0, -10.0, 0.0))
+    );
+    let constant = GravitySource::Point {
+        center: Vector3::origin(),
+        strength: 9.8,
+        inverse_square: false,
+    };
+    assert_eq!(
+        Vector3::new(-9.8, 0.0, 0.0),
+        constant.acceleration_at(&Vector3::new(100.0, 0.0, 0.0))
+    );
+
+    // The zone with the highest priority containing a point wins over the global gravity.
+    let down = Vector3::new(0.0, -9.8, 0.0);
+    let mut field = GravityField::new(GravitySource::Uniform(down));
+    field
+        .add_zone(
+            GravityRegion::Box(Aabb::new(
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(10.0, 10.0, 10.0),
+            )),
+            GravitySource::Uniform(Vector3::origin()),
+            0,
+        )
+        .add_zone(
+            GravityRegion::Sphere(Vector3::new(5.0, 5.0, 5.0), 1.0),
+            planet,
+            1,
+        )
+        .add_zone(
+            GravityRegion::Sphere(Vector3::new(5.0, 5.0, 5.0), 2.0),
+            GravitySource::Uniform(Vector3::new(1.0, 0.0, 0.0)),
+            1,
+        );
+    assert_eq!(down, field.acceleration_at(&Vector3::new(-1.0, 0.0, 0.0)));
+    assert_eq!(
+        Vector3::origin(),
+        field.acceleration_at(&Vector3::new(1.0, 1.0, 1.0))
+    );
+    assert_eq!(planet, *field.source_at(&Vector3::new(5.0, 5.5, 5.0)));
+    assert_eq!(
+        Vector3::new(1.0, 0.0, 0.0),
+        field.acceleration_at(&Vector3::new(5.0, 6.5, 5.0))
+    );
+}
+
+#[test]
+fn planets() {
+    let mut world = World::<f64>::new(WorldConfig {
+        linear_damping: 1.0,
+        angular_damping: 1.0,
+        ..WorldConfig::default()
+    });
+    world.gravity = GravityField::new(GravitySource::Point {
+        center: Vector3::origin(),
+        strength: 100.0,
+        inverse_square: true,
+    });
+
+    // A moon in a circular orbit keeps its distance to the planet.
+    let mut moon = RigidBody::new(Vector3::new(10.0, 0.0, 0.0), 1.0, &Matrix3::identity());
+    moon.velocity = Vector3::new(0.0, 0.0, (100.0f64 / 10.0).sqrt());
+    moon.set_can_sleep(false);
+    let moon = world.add_body(moon);
+    let moon = world.body_index(moon).unwrap();
+
+    // A probe with its own gravity ignores the planet.
+    let probe = world.add_body(RigidBody::new(
+        Vector3::new(0.0, 20.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let probe = world.body_index(probe).unwrap();
+    world
+        .gravity_overrides
+        .insert(probe, GravitySource::Uniform(Vector3::new(1.0, 0.0, 0.0)));
+
+    let period = 2.0 * std::f64::consts::PI * 10.0 / 10f64.sqrt();
+    let steps = (period * 120.0) as usize;
+    for step in 0..steps {
+        world.start_frame();
+        world.run_physics(1.0 / 120.0);
+        if step % 60 == 0 {
+            assert!((world.bodies[moon].position.magnitude() - 10.0).abs() < 0.1);
+        }
+    }
+    assert!((world.bodies[moon].position - Vector3::new(10.0, 0.0, 0.0)).magnitude() < 0.5);
+    let time = steps as f64 / 120.0;
+    assert!((world.bodies[probe].velocity - Vector3::new(time, 0.0, 0.0)).magnitude() < 1e-9);
+    assert_eq!(20.0, world.bodies[probe].position.y);
+}
+
+#[test]
+fn gravity_scale() {
+    let mut world = World::<f64>::new(WorldConfig {
+        linear_damping: 1.0,
+        ..WorldConfig::default()
+    });
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+
+    // Bodies fall as fast as their scale of the gravity, floating without it,
+    // and rising with a negative one.
+    let scales = [1.0, 0.25, 0.0, -0.5];
+    for scale in scales.iter() {
+        let mut body = RigidBody::new(Vector3::origin(), 2.0, &Matrix3::identity());
+        body.gravity_scale = *scale;
+        body.set_can_sleep(false);
+        world.add_body(body);
+    }
+    for _ in 0..60 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    for (body, scale) in world.bodies.iter().zip(scales.iter()) {
+        assert!((body.velocity.y + 10.0 * scale).abs() < 1e-9);
+    }
+
+    // The potential energy of the bodies is scaled the same way.
+    let diagnostics = world.measure_diagnostics();
+    let heights: f64 = world
+        .bodies
+        .iter()
+        .zip(scales.iter())
+        .map(|(body, scale)| body.position.y * scale)
+        .sum();
+    assert!((diagnostics.potential_energy - 2.0 * 10.0 * heights).abs() < 1e-9);
+}
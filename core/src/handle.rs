@@ -0,0 +1,226 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use serde::{Deserialize, Serialize};
+
+/// Handle of a rigid body of a world, which keeps referring to it as other bodies are removed.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct BodyHandle {
+    slot: usize,
+    generation: u32,
+}
+
+/// Handle of a collider of a world, which keeps referring to it as other colliders are removed.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct ColliderHandle {
+    slot: usize,
+    generation: u32,
+}
+
+/// Handle of a joint of a world, which keeps referring to it as other joints are removed.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub struct JointHandle {
+    slot: usize,
+    generation: u32,
+}
+
+/// Conversions between the handles of a kind of object and their slots and generations.
+pub(crate) trait Handle: Copy {
+    /// Creates a new handle for the given slot and generation.
+    fn new(slot: usize, generation: u32) -> Self;
+
+    /// Returns the slot and generation of the handle.
+    fn parts(&self) -> (usize, u32);
+}
+
+impl Handle for BodyHandle {
+    fn new(slot: usize, generation: u32) -> Self {
+        Self { slot, generation }
+    }
+
+    fn parts(&self) -> (usize, u32) {
+        (self.slot, self.generation)
+    }
+}
+
+impl Handle for ColliderHandle {
+    fn new(slot: usize, generation: u32) -> Self {
+        Self { slot, generation }
+    }
+
+    fn parts(&self) -> (usize, u32) {
+        (self.slot, self.generation)
+    }
+}
+
+impl Handle for JointHandle {
+    fn new(slot: usize, generation: u32) -> Self {
+        Self { slot, generation }
+    }
+
+    fn parts(&self) -> (usize, u32) {
+        (self.slot, self.generation)
+    }
+}
+
+impl BodyHandle {
+    /// Returns the handle packed into an integer, to hold it outside of Rust.
+    pub fn to_bits(&self) -> u64 {
+        (u64::from(self.generation) << 32) | self.slot as u64
+    }
+
+    /// Returns the handle packed into an integer by `to_bits`.
+    pub fn from_bits(bits: u64) -> Self {
+        Self {
+            slot: (bits & u64::from(u32::MAX)) as usize,
+            generation: (bits >> 32) as u32,
+        }
+    }
+}
+
+impl ColliderHandle {
+    /// Returns the handle packed into an integer, to hold it outside of Rust.
+    pub fn to_bits(&self) -> u64 {
+        (u64::from(self.generation) << 32) | self.slot as u64
+    }
+
+    /// Returns the handle packed into an integer by `to_bits`.
+    pub fn from_bits(bits: u64) -> Self {
+        Self {
+            slot: (bits & u64::from(u32::MAX)) as usize,
+            generation: (bits >> 32) as u32,
+        }
+    }
+}
+
+impl JointHandle {
+    /// Returns the handle packed into an integer, to hold it outside of Rust.
+    pub fn to_bits(&self) -> u64 {
+        (u64::from(self.generation) << 32) | self.slot as u64
+    }
+
+    /// Returns the handle packed into an integer by `to_bits`.
+    pub fn from_bits(bits: u64) -> Self {
+        Self {
+            slot: (bits & u64::from(u32::MAX)) as usize,
+            generation: (bits >> 32) as u32,
+        }
+    }
+}
+
+/// Owner of the indices left vacant by removed objects, which no slot points to.
+const VACANT: usize = usize::MAX;
+
+/// Slot a handle points to, holding the current index of its object.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+struct Slot {
+    generation: u32,
+    index: Option<usize>,
+}
+
+/// Handles given out for the objects of a list, mapping them to the current index of the objects.
+///
+/// # Remarks
+/// Handles are given out the first time they're asked for, so objects added to the list
+/// by the engine itself get one as well. Removing an object bumps the generation of its
+/// slot, so its handles stop resolving even after the slot is reused, and every slot
+/// remembers the object it was given out for, so a handle never resolves to another one.
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub(crate) struct Handles {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    owners: Vec<usize>,
+}
+
+impl Handles {
+    /// Returns the handle of the object with the given index, giving one out if needed.
+    pub(crate) fn handle<H: Handle>(&mut self, index: usize) -> H {
+        while self.owners.len() <= index {
+            let index = Some(self.owners.len());
+            let slot = match self.free.pop() {
+                Some(slot) => {
+                    self.slots[slot].index = index;
+                    slot
+                }
+                None => {
+                    self.slots.push(Slot {
+                        generation: 0,
+                        index,
+                    });
+                    self.slots.len() - 1
+                }
+            };
+            self.owners.push(slot);
+        }
+        let slot = self.owners[index];
+        H::new(slot, self.slots[slot].generation)
+    }
+
+    /// Returns the current index of the object of a handle, if it's still in a list
+    /// of the given length.
+    pub(crate) fn index<H: Handle>(&self, handle: H, len: usize) -> Option<usize> {
+        let (slot, generation) = handle.parts();
+        self.slots
+            .get(slot)
+            .filter(|entry| entry.generation == generation)
+            .and_then(|entry| entry.index)
+            .filter(|index| *index < len && self.owners.get(*index) == Some(&slot))
+    }
+
+    /// Forgets the handles of the objects removed from the list, keeping the ones for which
+    /// `keep` returns true and moving them to their new indices.
+    pub(crate) fn retain<K: FnMut(usize) -> bool>(&mut self, mut keep: K) {
+        let mut owners = Vec::with_capacity(self.owners.len());
+        for (index, slot) in self.owners.iter().enumerate() {
+            if keep(index) {
+                self.slots[*slot].index = Some(owners.len());
+                owners.push(*slot);
+            } else {
+                self.slots[*slot].generation = self.slots[*slot].generation.wrapping_add(1);
+                self.slots[*slot].index = None;
+                self.free.push(*slot);
+            }
+        }
+        self.owners = owners;
+    }
+
+    /// Forgets the handles of the object with the given index, and moves the handles of the
+    /// last object of the list to that index, as it's moved there to fill the gap.
+    ///
+    /// # Remarks
+    /// The last object must have been given a handle already.
+    pub(crate) fn swap_remove(&mut self, index: usize) {
+        let slot = self.owners.swap_remove(index);
+        self.slots[slot].generation = self.slots[slot].generation.wrapping_add(1);
+        self.slots[slot].index = None;
+        self.free.push(slot);
+        if let Some(moved) = self.owners.get(index) {
+            self.slots[*moved].index = Some(index);
+        }
+    }
+
+    /// Forgets the handles of the object with the given index, leaving the index vacant
+    /// so it's never given a handle again, while the other objects keep theirs.
+    ///
+    /// # Remarks
+    /// The object must have been given a handle already.
+    pub(crate) fn vacate(&mut self, index: usize) {
+        let slot = std::mem::replace(&mut self.owners[index], VACANT);
+        self.slots[slot].generation = self.slots[slot].generation.wrapping_add(1);
+        self.slots[slot].index = None;
+        self.free.push(slot);
+    }
+
+    /// Returns whether the object with the given index was removed with `vacate`.
+    pub(crate) fn is_vacant(&self, index: usize) -> bool {
+        self.owners.get(index) == Some(&VACANT)
+    }
+
+    /// Returns the number of indices left vacant with `vacate`.
+    pub(crate) fn vacant(&self) -> usize {
+        self.owners.iter().filter(|slot| **slot == VACANT).count()
+    }
+}
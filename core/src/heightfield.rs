@@ -0,0 +1,204 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::shape::Shape;
+use crate::trimesh::TriMesh;
+use crate::wind::noise;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Grid of heights sampled over the local xz plane, describing terrain.
+///
+/// # Remarks
+/// Samples are laid out in rows along the z axis, each holding a column per sample along the
+/// x axis, and the grid is centered on the origin. Heights are usually between `0` and `1`,
+/// and are stretched by the scale along y, while the scale along x and z is the size of the
+/// cells between samples.
+///
+/// Cells can be marked as holes, letting everything through them, to cut tunnels and caves
+/// into the terrain. Heightfields collide as the triangle mesh of their cells that aren't holes.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HeightField<F: num_traits::Float = f64> {
+    /// Size of the cells along x and z, and multiplier of the heights along y.
+    pub scale: Vector3<F>,
+
+    rows: usize,
+    columns: usize,
+    heights: Vec<F>,
+    holes: Vec<bool>,
+}
+
+impl<F: num_traits::Float> HeightField<F> {
+    /// Creates a new heightfield with the given heights, row after row, and scale.
+    /// Returns `None` if there are less than two rows or columns, or the number of heights
+    /// doesn't match them.
+    pub fn new(rows: usize, columns: usize, heights: Vec<F>, scale: Vector3<F>) -> Option<Self> {
+        if rows < 2 || columns < 2 || heights.len() != rows * columns {
+            return None;
+        }
+        Some(Self {
+            scale,
+            rows,
+            columns,
+            heights,
+            holes: vec![false; (rows - 1) * (columns - 1)],
+        })
+    }
+
+    /// Creates a new heightfield from the pixels of an 8 bits grayscale image, row after row,
+    /// with black at height `0` and white at height `1`.
+    /// Returns `None` if the image is smaller than two by two pixels, or the number of pixels
+    /// doesn't match its size.
+    pub fn from_image(
+        width: usize,
+        height: usize,
+        pixels: &[u8],
+        scale: Vector3<F>,
+    ) -> Option<Self> {
+        let heights = pixels
+            .iter()
+            .map(|pixel| math::real::<F>(f64::from(*pixel) / f64::from(u8::MAX)))
+            .collect();
+        Self::new(height, width, heights, scale)
+    }
+
+    /// Creates a new heightfield from the pixels of a 16 bits grayscale image, row after row,
+    /// with black at height `0` and white at height `1`.
+    /// Returns `None` if the image is smaller than two by two pixels, or the number of pixels
+    /// doesn't match its size.
+    pub fn from_image_16(
+        width: usize,
+        height: usize,
+        pixels: &[u16],
+        scale: Vector3<F>,
+    ) -> Option<Self> {
+        let heights = pixels
+            .iter()
+            .map(|pixel| math::real::<F>(f64::from(*pixel) / f64::from(u16::MAX)))
+            .collect();
+        Self::new(height, width, heights, scale)
+    }
+
+    /// Creates a new heightfield with the given number of rows and columns from smooth noise,
+    /// sampled `frequency` times per unit of distance, with heights between `0` and `1`.
+    ///
+    /// # Remarks
+    /// Heightfields with the same seed and frequency match where they overlap, so terrain can
+    /// be generated in pieces. Different seeds give unrelated terrain.
+    ///
+    /// # Panics
+    /// Panics if there are less than two rows or columns.
+    pub fn from_noise(
+        rows: usize,
+        columns: usize,
+        scale: Vector3<F>,
+        frequency: F,
+        seed: F,
+    ) -> Self {
+        let mut heights = Vec::with_capacity(rows * columns);
+        for row in 0..rows {
+            for column in 0..columns {
+                let point = Vector3::new(
+                    math::real::<F>(column as f64) * scale.x * frequency,
+                    seed,
+                    math::real::<F>(row as f64) * scale.z * frequency,
+                );
+                heights.push((noise(&point) + F::one()) * math::real(0.5));
+            }
+        }
+        Self::new(rows, columns, heights, scale).expect("at least two rows and columns")
+    }
+
+    /// Returns the number of rows of samples, along the z axis.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns of samples, along the x axis.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Returns the heights of the samples, row after row, before scaling.
+    pub fn heights(&self) -> &[F] {
+        &self.heights
+    }
+
+    /// Returns the height of the sample at the given row and column, before scaling.
+    pub fn height(&self, row: usize, column: usize) -> F {
+        self.heights[row * self.columns + column]
+    }
+
+    /// Returns the local position of the sample at the given row and column, after scaling.
+    pub fn point(&self, row: usize, column: usize) -> Vector3<F> {
+        let centered =
+            |index: usize, count: usize| math::real::<F>(index as f64 - (count - 1) as f64 * 0.5);
+        Vector3::new(
+            centered(column, self.columns) * self.scale.x,
+            self.height(row, column) * self.scale.y,
+            centered(row, self.rows) * self.scale.z,
+        )
+    }
+
+    /// Returns true if the cell between the given row and column and the next ones is a hole.
+    pub fn is_hole(&self, row: usize, column: usize) -> bool {
+        self.holes[row * (self.columns - 1) + column]
+    }
+
+    /// Sets whether the cell between the given row and column and the next ones is a hole.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the heightfield.
+    pub fn set_hole(&mut self, row: usize, column: usize, hole: bool) -> &mut Self {
+        self.holes[row * (self.columns - 1) + column] = hole;
+        self
+    }
+
+    /// Marks the cells set in the given mask as holes, and the rest as solid, given row after
+    /// row of cells, one less than the rows and columns of samples. Cells missing from a short
+    /// mask are left solid.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the heightfield.
+    pub fn set_hole_mask(&mut self, mask: &[bool]) -> &mut Self {
+        for (index, hole) in self.holes.iter_mut().enumerate() {
+            *hole = mask.get(index).copied().unwrap_or(false);
+        }
+        self
+    }
+
+    /// Returns the triangle mesh of the cells of the heightfield that aren't holes, facing up,
+    /// or `None` if every cell is a hole.
+    pub fn to_trimesh(&self) -> Option<TriMesh<F>> {
+        let mut vertices = Vec::with_capacity(self.heights.len());
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                vertices.push(self.point(row, column));
+            }
+        }
+
+        let mut triangles = Vec::new();
+        for row in 0..self.rows - 1 {
+            for column in 0..self.columns - 1 {
+                if self.is_hole(row, column) {
+                    continue;
+                }
+                let corner = row * self.columns + column;
+                let (right, below) = (corner + 1, corner + self.columns);
+                triangles.push([corner, below, right]);
+                triangles.push([right, below, below + 1]);
+            }
+        }
+        TriMesh::new(vertices, triangles)
+    }
+
+    /// Returns the shape of a collider made of the heightfield, or `None` if every cell is a hole.
+    pub fn to_shape(&self) -> Option<Shape<F>> {
+        self.to_trimesh().map(Shape::trimesh)
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::heightfield::*;
+use crate::query::QueryFilter;
+use crate::ray::Ray;
+use crate::rigid_body::RigidBody;
+use crate::world::World;
+use math::{Matrix3, Vector3};
+
+#[test]
+fn image() {
+    let scale = Vector3::new(2.0, 10.0, 1.0);
+    let pixels = [0, 51, 255, 102, 153, 204];
+    let field = HeightField::<f64>::from_image(3, 2, &pixels, scale).unwrap();
+    assert_eq!((2, 3), (field.rows(), field.columns()));
+    assert!((field.height(0, 1) - 0.2).abs() < 1e-9);
+    assert!((field.height(1, 2) - 0.8).abs() < 1e-9);
+
+    // The grid is centered on the origin, and the heights stretched.
+    let corner = field.point(0, 0);
+    assert!(
+        corner
+            .vector_sub(&Vector3::new(-2.0, 0.0, -0.5))
+            .magnitude()
+            < 1e-9
+    );
+    let corner = field.point(1, 2);
+    assert!(corner.vector_sub(&Vector3::new(2.0, 8.0, 0.5)).magnitude() < 1e-9);
+
+    let pixels = [0, u16::MAX, u16::MAX / 2, 0];
+    let field = HeightField::<f64>::from_image_16(2, 2, &pixels, scale).unwrap();
+    assert!((field.height(0, 1) - 1.0).abs() < 1e-9);
+    assert!((field.height(1, 0) - 0.5).abs() < 1e-4);
+
+    assert!(HeightField::<f64>::from_image(3, 2, &[0; 5], scale).is_none());
+    assert!(HeightField::<f64>::from_image(1, 2, &[0, 0], scale).is_none());
+}
+
+#[test]
+fn noise() {
+    let scale = Vector3::new(0.5, 20.0, 0.5);
+    let field = HeightField::<f64>::from_noise(32, 32, scale, 0.25, 0.0);
+    assert!(field
+        .heights()
+        .iter()
+        .all(|height| (0.0..=1.0).contains(height)));
+    assert!(field
+        .heights()
+        .iter()
+        .any(|height| (height - field.height(0, 0)).abs() > 0.05));
+
+    // Terrain generated in pieces matches where they overlap.
+    assert_eq!(field, HeightField::from_noise(32, 32, scale, 0.25, 0.0));
+    let piece = HeightField::<f64>::from_noise(8, 8, scale, 0.25, 0.0);
+    assert_eq!(field.height(7, 5), piece.height(7, 5));
+    assert_ne!(field, HeightField::from_noise(32, 32, scale, 0.25, 7.5));
+}
+
+#[test]
+fn holes() {
+    let mut field =
+        HeightField::<f64>::new(3, 3, vec![0.0; 9], Vector3::new(1.0, 1.0, 1.0)).unwrap();
+    field.set_hole(1, 1, true);
+    assert!(field.is_hole(1, 1));
+    assert_eq!(6, field.to_trimesh().unwrap().triangles().len());
+
+    field.set_hole_mask(&[true, true, true]);
+    assert!(field.is_hole(1, 0) && !field.is_hole(1, 1));
+    field.set_hole_mask(&[true; 4]);
+    assert!(field.to_trimesh().is_none());
+
+    // Rays go through holes, and hit the terrain facing up everywhere else.
+    field.set_hole_mask(&[false, false, false, true]);
+    let mut world = World::<f64>::default();
+    let ground = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    let ground = world.body_index(ground).unwrap();
+    world.add_collider(Collider::new(ground, field.to_shape().unwrap()));
+    world.start_frame();
+    world.update_colliders();
+    let filter = QueryFilter::new();
+    let down = Vector3::new(0.0, -1.0, 0.0);
+    let hit = world
+        .raycast(
+            &Ray::new(Vector3::new(-0.5, 5.0, -0.5), down),
+            10.0,
+            &filter,
+        )
+        .unwrap();
+    assert!((hit.toi - 5.0).abs() < 1e-6);
+    assert!(
+        hit.normal
+            .vector_sub(&Vector3::new(0.0, 1.0, 0.0))
+            .magnitude()
+            < 1e-6
+    );
+    assert!(world
+        .raycast(&Ray::new(Vector3::new(0.5, 5.0, 0.5), down), 10.0, &filter)
+        .is_none());
+}
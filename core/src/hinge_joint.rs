@@ -0,0 +1,169 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::joint::{world_direction, Anchors, JointLimits, JointMotor, RowBuilder};
+use crate::rigid_body::RigidBody;
+use crate::solver::tangent_basis;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Hinge (or revolute) joint, keeping an anchor point of each body together and
+/// their axes aligned, so the bodies can only rotate relative to each other around the axis.
+///
+/// # Remarks
+/// The angle of the hinge is measured between a reference direction of each body,
+/// perpendicular to its axis, and grows as the first body rotates counterclockwise
+/// around the axis relative to the second one.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HingeJoint<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector3<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector3<F>,
+
+    /// Unit axis of the hinge in the local space of the first body.
+    pub local_axis_one: Vector3<F>,
+
+    /// Unit axis of the hinge in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_axis_two: Vector3<F>,
+
+    /// Unit direction perpendicular to the axis of the first body, in its local space,
+    /// from which the angle is measured.
+    pub local_reference_one: Vector3<F>,
+
+    /// Unit direction perpendicular to the axis of the second body, in its local space
+    /// or in world space for joints with the scenery, from which the angle is measured.
+    pub local_reference_two: Vector3<F>,
+
+    /// Range of angles allowed, in radians, if any.
+    pub limits: Option<JointLimits<F>>,
+
+    /// Motor driving the angular speed, or the angle, of the hinge, if any.
+    pub motor: Option<JointMotor<F>>,
+}
+
+impl<F: num_traits::Float> HingeJoint<F> {
+    /// Creates a new hinge joint between the given anchors and around the given axes,
+    /// without limits nor motor.
+    ///
+    /// # Remarks
+    /// The reference directions are chosen perpendicular to each axis, so the initial angle
+    /// isn't necessarily zero. Use `from_world` to start at angle zero.
+    pub fn new(
+        local_anchor_one: Vector3<F>,
+        local_anchor_two: Vector3<F>,
+        local_axis_one: Vector3<F>,
+        local_axis_two: Vector3<F>,
+    ) -> Self {
+        let local_axis_one = local_axis_one.normalize();
+        let local_axis_two = local_axis_two.normalize();
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+            local_axis_one,
+            local_axis_two,
+            local_reference_one: tangent_basis(&local_axis_one)[0],
+            local_reference_two: tangent_basis(&local_axis_two)[0],
+            limits: None,
+            motor: None,
+        }
+    }
+
+    /// Creates a new hinge joint between the given bodies, around an axis passing through
+    /// the given anchor, both in world space. The current angle of the hinge is zero.
+    pub fn from_world(
+        one: &RigidBody<F>,
+        two: Option<&RigidBody<F>>,
+        anchor: &Vector3<F>,
+        axis: &Vector3<F>,
+    ) -> Self {
+        let axis = axis.normalize();
+        let reference = tangent_basis(&axis)[0];
+        let local = |body: Option<&RigidBody<F>>, direction: &Vector3<F>| match body {
+            Some(body) => body.direction_in_local_space(direction),
+            None => *direction,
+        };
+        Self {
+            local_anchor_one: one.point_in_local_space(anchor),
+            local_anchor_two: two.map_or(*anchor, |two| two.point_in_local_space(anchor)),
+            local_axis_one: one.direction_in_local_space(&axis),
+            local_axis_two: local(two, &axis),
+            local_reference_one: one.direction_in_local_space(&reference),
+            local_reference_two: local(two, &reference),
+            limits: None,
+            motor: None,
+        }
+    }
+
+    /// Returns the current angle of the hinge, in radians within `[-PI, PI]`.
+    pub fn angle(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> F {
+        let axis = one.direction_in_world_space(&self.local_axis_one);
+        let reference_one = one.direction_in_world_space(&self.local_reference_one);
+        let reference_two = world_direction(two, &self.local_reference_two);
+        twist_angle(&axis, &reference_one, &reference_two)
+    }
+
+    /// Returns the current angular speed of the hinge, in radians per second.
+    pub fn angular_speed(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> F {
+        let axis = one.direction_in_world_space(&self.local_axis_one);
+        let rotation = match two {
+            Some(two) => one.rotation.vector_sub(&two.rotation),
+            None => one.rotation,
+        };
+        rotation.dot_product(&axis)
+    }
+
+    /// Adds the constraint rows of the joint.
+    pub(crate) fn rows(&self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let pair = builder.bodies();
+        let anchors = Anchors::new(pair, &self.local_anchor_one, &self.local_anchor_two, bodies);
+        builder.point(0, &anchors);
+
+        // Keep the axis of the second body perpendicular to two directions
+        // perpendicular to the axis of the first one.
+        let one = &bodies[pair.0];
+        let two = pair.1.map(|body| &bodies[body]);
+        let axis_one = one.direction_in_world_space(&self.local_axis_one);
+        let axis_two = world_direction(two, &self.local_axis_two);
+        for (index, perpendicular) in tangent_basis(&axis_one).iter().enumerate() {
+            let angular = perpendicular.cross_product(&axis_two);
+            builder.add(
+                3 + index,
+                Vector3::origin(),
+                angular,
+                angular,
+                axis_two.dot_product(perpendicular),
+            );
+        }
+
+        let angle = self.angle(one, two);
+        if let Some(limits) = &self.limits {
+            builder.limit(5, limits, angle, Vector3::origin(), axis_one, axis_one);
+        }
+        if let Some(motor) = &self.motor {
+            builder.motor(6, motor, angle, Vector3::origin(), axis_one, axis_one);
+        }
+    }
+}
+
+/// Returns the angle, in radians within `[-PI, PI]`, from the second reference direction
+/// to the first one, counterclockwise around the given axis.
+///
+/// # Remarks
+/// The angle is computed in software, so joint limits and motors act the same on every platform.
+pub(crate) fn twist_angle<F: num_traits::Float>(
+    axis: &Vector3<F>,
+    reference_one: &Vector3<F>,
+    reference_two: &Vector3<F>,
+) -> F {
+    math::soft::atan2(
+        reference_two.cross_product(reference_one).dot_product(axis),
+        reference_two.dot_product(reference_one),
+    )
+}
@@ -0,0 +1,348 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::rigid_body::RigidBody;
+use crate::solver::tangent_basis;
+use math::{Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Default number of iterations run by the inverse kinematics solvers.
+pub const DEFAULT_IK_ITERATIONS: usize = 16;
+
+/// Default distance from the target at which the inverse kinematics solvers stop.
+pub const DEFAULT_IK_TOLERANCE: f64 = 0.001;
+
+/// Algorithm solving an inverse kinematics chain.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum IkAlgorithm {
+    /// Forward And Backward Reaching Inverse Kinematics, moving the joints along the chain
+    /// from the end effector to the target and back from the root. Converges fast and
+    /// spreads the bending over the whole chain, but slowly on chains with tight limits.
+    Fabrik,
+
+    /// Cyclic Coordinate Descent, rotating every joint in turn from the end effector to the
+    /// root to point the end effector at the target. Bends the joints closest to the end
+    /// effector the most.
+    Ccd,
+}
+
+/// Settings of the inverse kinematics solvers.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct IkSettings<F: num_traits::Float = f64> {
+    /// Algorithm solving the chain.
+    pub algorithm: IkAlgorithm,
+
+    /// Maximum number of iterations run.
+    pub iterations: usize,
+
+    /// Distance from the target at which the end effector counts as reaching it.
+    pub tolerance: F,
+}
+
+impl<F: num_traits::Float> Default for IkSettings<F> {
+    fn default() -> Self {
+        Self {
+            algorithm: IkAlgorithm::Fabrik,
+            iterations: DEFAULT_IK_ITERATIONS,
+            tolerance: math::real(DEFAULT_IK_TOLERANCE),
+        }
+    }
+}
+
+/// Limit on how a joint of an inverse kinematics chain bends the segment after it
+/// relative to the segment before it (or to the reference direction of the chain, for
+/// the root).
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum IkLimit<F: num_traits::Float = f64> {
+    /// Keeps the segment within a cone of the given half angle, in radians, around the
+    /// previous one, like the swing limit of a ball joint.
+    Cone(F),
+
+    /// Keeps the segment in the plane perpendicular to the axis, in world space, and its
+    /// angle around it from the previous one within the lower and upper bounds, in
+    /// radians, like the limits of a hinge joint.
+    Hinge {
+        /// Axis the joint rotates around.
+        axis: Vector3<F>,
+
+        /// Lowest angle around the axis.
+        lower: F,
+
+        /// Highest angle around the axis.
+        upper: F,
+    },
+}
+
+/// Outcome of solving an inverse kinematics chain.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct IkSolution<F: num_traits::Float = f64> {
+    /// Number of iterations run.
+    pub iterations: usize,
+
+    /// Distance left between the end effector and the target.
+    pub error: F,
+
+    /// Whether the end effector got within the tolerance of the target.
+    pub reached: bool,
+}
+
+/// Chain of joints connected by rigid segments, solved so its end effector (the last
+/// joint) reaches a target while its root (the first joint) stays in place.
+///
+/// # Remarks
+/// The chain can be built from the joint positions directly, or from a chain of rigid
+/// bodies such as the limbs of a ragdoll, and the solved pose written back to them.
+/// Limits are honored by both algorithms, so the pose may fall short of targets they
+/// keep out of reach.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct IkChain<F: num_traits::Float = f64> {
+    /// Positions of the joints, from the root to the end effector.
+    pub points: Vec<Vector3<F>>,
+
+    /// Limit of every joint but the end effector, if any.
+    pub limits: Vec<Option<IkLimit<F>>>,
+
+    /// Direction the limit of the root is measured from.
+    pub reference: Vector3<F>,
+
+    lengths: Vec<F>,
+}
+
+impl<F: num_traits::Float> IkChain<F> {
+    /// Creates a chain through the given joint positions, without limits and with the
+    /// direction of its first segment as reference.
+    ///
+    /// # Remarks
+    /// The lengths of the segments are taken from the positions, and kept while solving.
+    pub fn new(points: Vec<Vector3<F>>) -> Self {
+        let lengths: Vec<F> = points
+            .windows(2)
+            .map(|segment| segment[1].vector_sub(&segment[0]).magnitude())
+            .collect();
+        let reference = match points.get(1) {
+            Some(second) => second.vector_sub(&points[0]).normalize(),
+            None => Vector3::new(F::one(), F::zero(), F::zero()),
+        };
+        Self {
+            limits: vec![None; lengths.len()],
+            points,
+            reference,
+            lengths,
+        }
+    }
+
+    /// Creates a chain through the positions of the given bodies, from the root to the
+    /// end effector.
+    pub fn from_bodies(bodies: &[RigidBody<F>], chain: &[usize]) -> Self {
+        Self::new(chain.iter().map(|body| bodies[*body].position).collect())
+    }
+
+    /// Sets the limit of the given joint.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the chain.
+    pub fn with_limit(&mut self, joint: usize, limit: IkLimit<F>) -> &mut Self {
+        self.limits[joint] = Some(limit);
+        self
+    }
+
+    /// Returns the length of every segment.
+    pub fn lengths(&self) -> &[F] {
+        &self.lengths
+    }
+
+    /// Returns the position of the end effector.
+    pub fn end_effector(&self) -> Vector3<F> {
+        self.points[self.points.len() - 1]
+    }
+
+    /// Moves the joints so the end effector reaches the target, returning how close it got.
+    pub fn solve(&mut self, target: &Vector3<F>, settings: &IkSettings<F>) -> IkSolution<F> {
+        let mut iterations = 0;
+        let mut error = self.end_effector().vector_sub(target).magnitude();
+        while error > settings.tolerance
+            && iterations < settings.iterations
+            && !self.lengths.is_empty()
+        {
+            match settings.algorithm {
+                IkAlgorithm::Fabrik => self.fabrik(target),
+                IkAlgorithm::Ccd => self.ccd(target),
+            }
+            iterations += 1;
+            error = self.end_effector().vector_sub(target).magnitude();
+        }
+        IkSolution {
+            iterations,
+            error,
+            reached: error <= settings.tolerance,
+        }
+    }
+
+    /// Moves the given bodies to the joints of the chain, rotating each along with the
+    /// segment that starts at it (the end effector along with the last one).
+    ///
+    /// # Remarks
+    /// The bodies are teleported and woken up. Dynamic bodies held by joints can instead
+    /// be driven towards the pose with joint motors, to keep interacting with the world.
+    pub fn pose_bodies(&self, bodies: &mut [RigidBody<F>], chain: &[usize]) {
+        let previous: Vec<Vector3<F>> = chain.iter().map(|body| bodies[*body].position).collect();
+        for (index, body) in chain.iter().enumerate() {
+            let segment = index.min(self.lengths.len().saturating_sub(1));
+            let body = &mut bodies[*body];
+            if segment + 1 < previous.len() {
+                let rotation = rotation_between(
+                    &previous[segment + 1].vector_sub(&previous[segment]),
+                    &self.points[segment + 1].vector_sub(&self.points[segment]),
+                );
+                body.orientation = rotation.quaternion_mul(&body.orientation).normalize();
+            }
+            body.position = self.points[index];
+            body.calculate_derived_data();
+            body.set_awake(true);
+        }
+    }
+
+    /// Returns the direction the segment starting at the given joint is measured from.
+    fn parent_direction(&self, joint: usize) -> Vector3<F> {
+        if joint == 0 {
+            self.reference
+        } else {
+            self.points[joint]
+                .vector_sub(&self.points[joint - 1])
+                .normalize()
+        }
+    }
+
+    /// Runs one iteration of FABRIK.
+    fn fabrik(&mut self, target: &Vector3<F>) {
+        let root = self.points[0];
+        let last = self.points.len() - 1;
+
+        // Drag the end effector to the target, and every joint along after it.
+        self.points[last] = *target;
+        for joint in (0..last).rev() {
+            let direction = direction_or(
+                &self.points[joint].vector_sub(&self.points[joint + 1]),
+                &self.parent_direction(joint).invert(),
+            );
+            self.points[joint] =
+                self.points[joint + 1].vector_add(&direction.scalar_mul(self.lengths[joint]));
+        }
+
+        // Then drag the root back, constraining every segment on the way to the end effector.
+        self.points[0] = root;
+        for joint in 0..last {
+            let parent = self.parent_direction(joint);
+            let direction = direction_or(
+                &self.points[joint + 1].vector_sub(&self.points[joint]),
+                &parent,
+            );
+            let direction = self.constrain(joint, &parent, &direction);
+            self.points[joint + 1] =
+                self.points[joint].vector_add(&direction.scalar_mul(self.lengths[joint]));
+        }
+    }
+
+    /// Runs one iteration of CCD.
+    fn ccd(&mut self, target: &Vector3<F>) {
+        for joint in (0..self.lengths.len()).rev() {
+            let pivot = self.points[joint];
+            let rotation = rotation_between(
+                &self.end_effector().vector_sub(&pivot),
+                &target.vector_sub(&pivot),
+            );
+            self.rotate_after(joint, &rotation);
+
+            // Rotating the rest of the chain rigidly keeps the bends of the joints after this
+            // one, but may turn them out of the planes of their hinges, so constrain them all.
+            for limited in joint..self.lengths.len() {
+                let parent = self.parent_direction(limited);
+                let direction = self.points[limited + 1]
+                    .vector_sub(&self.points[limited])
+                    .normalize();
+                let constrained = self.constrain(limited, &parent, &direction);
+                self.rotate_after(limited, &rotation_between(&direction, &constrained));
+            }
+        }
+    }
+
+    /// Rotates the joints after the given one around it.
+    fn rotate_after(&mut self, joint: usize, rotation: &Quaternion<F>) {
+        let pivot = self.points[joint];
+        for point in self.points[joint + 1..].iter_mut() {
+            *point = pivot.vector_add(&rotation.rotate(&point.vector_sub(&pivot)));
+        }
+    }
+
+    /// Returns the closest direction to the given one allowed by the limit of the joint.
+    fn constrain(&self, joint: usize, parent: &Vector3<F>, direction: &Vector3<F>) -> Vector3<F> {
+        match self.limits[joint] {
+            None => *direction,
+            Some(IkLimit::Cone(angle)) => {
+                if angle_between(parent, direction) <= angle {
+                    return *direction;
+                }
+                let mut axis = parent.cross_product(direction);
+                if axis.squared_magnitude() <= F::epsilon() {
+                    axis = tangent_basis(parent)[0];
+                }
+                Quaternion::from_axis_angle(&axis, angle).rotate(parent)
+            }
+            Some(IkLimit::Hinge { axis, lower, upper }) => {
+                let axis = axis.normalize();
+                let plane = |vector: &Vector3<F>| {
+                    vector.vector_sub(&axis.scalar_mul(vector.dot_product(&axis)))
+                };
+                let (reference, projected) = (plane(parent), plane(direction));
+                if reference.squared_magnitude() <= F::epsilon() {
+                    return *direction;
+                }
+                let angle = if projected.squared_magnitude() <= F::epsilon() {
+                    F::zero()
+                } else {
+                    reference
+                        .cross_product(&projected)
+                        .dot_product(&axis)
+                        .atan2(reference.dot_product(&projected))
+                };
+                let angle = angle.max(lower).min(upper);
+                Quaternion::from_axis_angle(&axis, angle).rotate(&reference.normalize())
+            }
+        }
+    }
+}
+
+/// Returns the angle between two vectors, in radians, clamping the roundoff of parallel ones.
+fn angle_between<F: num_traits::Float>(one: &Vector3<F>, two: &Vector3<F>) -> F {
+    let cos = one.normalize().dot_product(&two.normalize());
+    cos.max(-F::one()).min(F::one()).acos()
+}
+
+/// Returns the given vector normalized, or the fallback direction if it's too short.
+fn direction_or<F: num_traits::Float>(vector: &Vector3<F>, fallback: &Vector3<F>) -> Vector3<F> {
+    if vector.squared_magnitude() <= F::epsilon() {
+        *fallback
+    } else {
+        vector.normalize()
+    }
+}
+
+/// Returns the shortest rotation turning one direction into another.
+fn rotation_between<F: num_traits::Float>(from: &Vector3<F>, to: &Vector3<F>) -> Quaternion<F> {
+    if from.squared_magnitude() <= F::epsilon() || to.squared_magnitude() <= F::epsilon() {
+        return Quaternion::identity();
+    }
+    let angle = angle_between(from, to);
+    let mut axis = from.cross_product(to);
+    if axis.squared_magnitude() <= F::epsilon() {
+        if angle < math::real(1.0) {
+            return Quaternion::identity();
+        }
+        axis = tangent_basis(&from.normalize())[0];
+    }
+    Quaternion::from_axis_angle(&axis, angle)
+}
@@ -0,0 +1,132 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ik::*;
+use crate::rigid_body::RigidBody;
+use math::{Matrix3, Vector3};
+
+fn arm() -> IkChain {
+    IkChain::new((0..4).map(|x| Vector3::new(x as f64, 0.0, 0.0)).collect())
+}
+
+fn settings(algorithm: IkAlgorithm) -> IkSettings {
+    IkSettings {
+        algorithm,
+        iterations: 64,
+        ..IkSettings::default()
+    }
+}
+
+fn assert_lengths(chain: &IkChain) {
+    for (segment, length) in chain.points.windows(2).zip(chain.lengths()) {
+        assert!((segment[1].vector_sub(&segment[0]).magnitude() - length).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn reach() {
+    for algorithm in [IkAlgorithm::Fabrik, IkAlgorithm::Ccd].iter() {
+        // Reachable targets are reached, keeping the root in place and the segments rigid.
+        let mut chain = arm();
+        let target = Vector3::new(1.0, 1.5, 0.5);
+        let solution = chain.solve(&target, &settings(*algorithm));
+        assert!(solution.reached);
+        assert!(solution.error <= DEFAULT_IK_TOLERANCE);
+        assert!(chain.end_effector().vector_sub(&target).magnitude() <= DEFAULT_IK_TOLERANCE);
+        assert_eq!(Vector3::origin(), chain.points[0]);
+        assert_lengths(&chain);
+
+        // Targets out of reach stretch the chain towards them.
+        let mut chain = arm();
+        let solution = chain.solve(&Vector3::new(0.0, 10.0, 0.0), &settings(*algorithm));
+        assert!(!solution.reached);
+        assert!((solution.error - 7.0).abs() < 0.01);
+        assert!((chain.end_effector().y - 3.0).abs() < 0.01);
+        assert_lengths(&chain);
+    }
+
+    // Stiff joints make the chain turn at the root only, which CCD handles best.
+    let mut chain = arm();
+    chain
+        .with_limit(1, IkLimit::Cone(0.0))
+        .with_limit(2, IkLimit::Cone(0.0));
+    let solution = chain.solve(&Vector3::new(0.0, 3.0, 0.0), &settings(IkAlgorithm::Ccd));
+    assert!(solution.reached);
+    assert!(chain.points.iter().all(|point| point.x.abs() < 0.01));
+
+    // Chains already at the target don't iterate.
+    let mut chain = arm();
+    let solution = chain.solve(&Vector3::new(3.0, 0.0, 0.0), &IkSettings::default());
+    assert_eq!(0, solution.iterations);
+    assert!(solution.reached);
+}
+
+#[test]
+fn limits() {
+    for algorithm in [IkAlgorithm::Fabrik, IkAlgorithm::Ccd].iter() {
+        // Cones keep the joints from bending too much.
+        let mut chain = arm();
+        chain
+            .with_limit(1, IkLimit::Cone(0.4))
+            .with_limit(2, IkLimit::Cone(0.4));
+        let solution = chain.solve(&Vector3::new(0.5, 2.857, 0.0), &settings(*algorithm));
+        assert!(solution.reached);
+        for joint in 1..3 {
+            let before = chain.points[joint].vector_sub(&chain.points[joint - 1]);
+            let after = chain.points[joint + 1].vector_sub(&chain.points[joint]);
+            assert!(before.theta(&after) <= 0.4 + 1e-6);
+        }
+        assert_lengths(&chain);
+
+        // Hinges keep the chain in their plane and within their angles.
+        let mut chain = arm();
+        let axis = Vector3::new(0.0, 0.0, 1.0);
+        chain.with_limit(
+            0,
+            IkLimit::Hinge {
+                axis,
+                lower: 0.0,
+                upper: 0.5,
+            },
+        );
+        for joint in 1..3 {
+            chain.with_limit(
+                joint,
+                IkLimit::Hinge {
+                    axis,
+                    lower: -0.1,
+                    upper: 0.1,
+                },
+            );
+        }
+        let solution = chain.solve(&Vector3::new(-1.0, 2.0, 1.0), &settings(*algorithm));
+        assert!(!solution.reached);
+        assert!(chain.points.iter().all(|point| point.z.abs() < 1e-9));
+        let first = chain.points[1];
+        assert!(first.y >= 0.0 && first.y.atan2(first.x) <= 0.5 + 1e-9);
+        assert_lengths(&chain);
+    }
+}
+
+#[test]
+fn pose_bodies() {
+    let mut bodies: Vec<RigidBody> = (0..3)
+        .map(|x| RigidBody::new(Vector3::new(x as f64, 0.0, 0.0), 1.0, &Matrix3::identity()))
+        .collect();
+    let chain_bodies = [0, 1, 2];
+    let mut chain = IkChain::from_bodies(&bodies, &chain_bodies);
+    chain.with_limit(1, IkLimit::Cone(0.0));
+    chain.solve(&Vector3::new(0.0, 2.0, 0.0), &settings(IkAlgorithm::Ccd));
+    chain.pose_bodies(&mut bodies, &chain_bodies);
+
+    // Every body moves to its joint and turns along with its segment.
+    for (body, point) in bodies.iter().zip(chain.points.iter()) {
+        assert!(body.position.vector_sub(point).magnitude() < 1e-9);
+        let x = body.orientation.rotate(&Vector3::new(1.0, 0.0, 0.0));
+        assert!(x.vector_sub(&Vector3::new(0.0, 1.0, 0.0)).magnitude() < 0.01);
+        assert!(body.is_awake);
+    }
+}
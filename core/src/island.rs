@@ -0,0 +1,133 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::rigid_body::RigidBody;
+
+/// Disjoint set forest used to merge the bodies of an island together.
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(count: usize) -> Self {
+        Self {
+            parents: (0..count).collect(),
+        }
+    }
+
+    fn find(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            // Path halving keeps the trees flat.
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+        index
+    }
+
+    fn union(&mut self, first: usize, second: usize) {
+        let first = self.find(first);
+        let second = self.find(second);
+        if first != second {
+            self.parents[second.max(first)] = first.min(second);
+        }
+    }
+}
+
+/// Groups of rigid bodies that interact with each other through contacts or joints.
+/// Bodies in different islands can't affect each other during a simulation step.
+///
+/// # Remarks
+/// Bodies with infinite mass don't belong to any island, and don't link islands together:
+/// two boxes resting on the same static floor are in different islands.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Islands {
+    islands: Vec<Vec<usize>>,
+    body_islands: Vec<Option<usize>>,
+}
+
+impl Islands {
+    /// Builds the islands for the given rigid bodies, connected by the given pairs of
+    /// body indices (typically, bodies in contact or linked by a joint).
+    pub fn build<F: num_traits::Float>(bodies: &[RigidBody<F>], pairs: &[(usize, usize)]) -> Self {
+        let mut sets = UnionFind::new(bodies.len());
+        for (first, second) in pairs.iter() {
+            if bodies[*first].has_finite_mass() && bodies[*second].has_finite_mass() {
+                sets.union(*first, *second);
+            }
+        }
+
+        let mut islands = Vec::new();
+        let mut roots = vec![None; bodies.len()];
+        let mut body_islands = vec![None; bodies.len()];
+        for (index, body) in bodies.iter().enumerate() {
+            if !body.has_finite_mass() {
+                continue;
+            }
+
+            let root = sets.find(index);
+            let island = *roots[root].get_or_insert_with(|| {
+                islands.push(Vec::new());
+                islands.len() - 1
+            });
+            islands[island].push(index);
+            body_islands[index] = Some(island);
+        }
+
+        Self {
+            islands,
+            body_islands,
+        }
+    }
+
+    /// Returns the number of islands.
+    pub fn len(&self) -> usize {
+        self.islands.len()
+    }
+
+    /// Returns true if there are no islands.
+    pub fn is_empty(&self) -> bool {
+        self.islands.is_empty()
+    }
+
+    /// Returns the indices of the bodies in the given island.
+    pub fn bodies(&self, island: usize) -> &[usize] {
+        &self.islands[island]
+    }
+
+    /// Returns an iterator over the bodies of each island.
+    pub fn iter(&self) -> impl Iterator<Item = &[usize]> {
+        self.islands.iter().map(|island| island.as_slice())
+    }
+
+    /// Returns the island the body at index `body` belongs to,
+    /// or `None` if the body has infinite mass.
+    pub fn island_of(&self, body: usize) -> Option<usize> {
+        self.body_islands.get(body).copied().flatten()
+    }
+
+    /// Updates the sleep state of the bodies, one island at a time.
+    ///
+    /// # Remarks
+    /// An island only falls asleep when every body in it is at rest. Otherwise,
+    /// any sleeping body in an island with moving bodies is woken up, which
+    /// is how contacts and joints with moving bodies wake sleeping ones.
+    pub fn update_sleep<F: num_traits::Float>(&self, bodies: &mut [RigidBody<F>]) {
+        for island in self.islands.iter() {
+            let at_rest = island
+                .iter()
+                .all(|body| !bodies[*body].is_awake || bodies[*body].is_sleep_candidate());
+
+            for body in island.iter() {
+                let body = &mut bodies[*body];
+                if at_rest && body.is_awake {
+                    body.set_awake(false);
+                } else if !at_rest && !body.is_awake {
+                    body.set_awake(true);
+                }
+            }
+        }
+    }
+}
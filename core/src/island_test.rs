@@ -0,0 +1,67 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::island::*;
+use crate::rigid_body::RigidBody;
+use math::{Matrix3, Vector3};
+
+fn bodies(count: usize) -> Vec<RigidBody> {
+    (0..count)
+        .map(|index| {
+            RigidBody::new(
+                Vector3::new(index as f64, 0.0, 0.0),
+                1.0,
+                &Matrix3::identity(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn build() {
+    let mut bodies = bodies(6);
+    bodies[5].set_infinite_mass();
+    let islands = Islands::build(&bodies, &[(0, 1), (2, 1), (3, 5), (4, 5)]);
+
+    // The static body doesn't link bodies 3 and 4 together.
+    assert_eq!(3, islands.len());
+    assert_eq!(&[0, 1, 2], islands.bodies(0));
+    assert_eq!(&[3], islands.bodies(1));
+    assert_eq!(&[4], islands.bodies(2));
+    assert_eq!(Some(0), islands.island_of(2));
+    assert_eq!(None, islands.island_of(5));
+    assert_eq!(3, islands.iter().count());
+}
+
+#[test]
+fn sleep() {
+    let mut bodies = bodies(3);
+    for body in bodies.iter_mut() {
+        body.motion = 0.0;
+    }
+    bodies[1].velocity = Vector3::new(1.0, 0.0, 0.0);
+    bodies[1].motion = 1.0;
+    let islands = Islands::build(&bodies, &[(0, 1)]);
+
+    // The moving body keeps its whole island awake.
+    islands.update_sleep(&mut bodies);
+    assert!(bodies[0].is_awake);
+    assert!(bodies[1].is_awake);
+    assert!(!bodies[2].is_awake);
+
+    // Islands fall asleep together once every body is at rest.
+    bodies[1].motion = 0.0;
+    islands.update_sleep(&mut bodies);
+    assert!(!bodies[0].is_awake);
+    assert!(!bodies[1].is_awake);
+    assert_eq!(Vector3::origin(), bodies[1].velocity);
+
+    // Disturbing a sleeping body wakes its island up.
+    bodies[0].add_force(&Vector3::new(0.0, 1.0, 0.0));
+    assert!(bodies[0].is_awake);
+    islands.update_sleep(&mut bodies);
+    assert!(bodies[1].is_awake);
+}
@@ -0,0 +1,767 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ball_joint::BallJoint;
+use crate::distance_joint::DistanceJoint;
+use crate::fixed_joint::FixedJoint;
+use crate::gear_joint::GearJoint;
+use crate::hinge_joint::HingeJoint;
+use crate::prismatic_joint::PrismaticJoint;
+use crate::pulley_joint::PulleyJoint;
+use crate::rack_and_pinion_joint::RackAndPinionJoint;
+use crate::rigid_body::RigidBody;
+use crate::solver::{is_moving, row_impulse, ImpulseConstraint, RESTITUTION_VELOCITY_LIMIT};
+use crate::spring_joint::{AngularSpringJoint, LinearSpringJoint};
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Kinds of joints, with the data specific to each one of them.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum JointKind<F: num_traits::Float = f64> {
+    /// Keeps two anchor points together, letting the bodies rotate freely.
+    Ball(BallJoint<F>),
+
+    /// Keeps two anchor points together and two axes aligned,
+    /// letting the bodies rotate around the axis only.
+    Hinge(HingeJoint<F>),
+
+    /// Locks the relative position and orientation of the bodies.
+    Fixed(FixedJoint<F>),
+
+    /// Locks the relative orientation of the bodies and keeps two anchor points on an axis,
+    /// letting the bodies slide along the axis only.
+    Prismatic(PrismaticJoint<F>),
+
+    /// Keeps the distance between two anchor points within a range.
+    Distance(DistanceJoint<F>),
+
+    /// Pulls two anchor points towards a rest position along an axis with a damped spring.
+    LinearSpring(LinearSpringJoint<F>),
+
+    /// Twists two bodies towards a rest angle around an axis with a damped spring.
+    AngularSpring(AngularSpringJoint<F>),
+
+    /// Couples the rotation of the bodies around their own axes by a ratio.
+    Gear(GearJoint<F>),
+
+    /// Hangs two anchor points from a rope running over two fixed pulleys.
+    Pulley(PulleyJoint<F>),
+
+    /// Couples the rotation of the first body around its axis with the translation
+    /// of the second one along its axis by a ratio.
+    RackAndPinion(RackAndPinionJoint<F>),
+}
+
+impl<F: num_traits::Float> JointKind<F> {
+    /// Returns the maximum number of constraint rows the joint is solved with.
+    fn max_rows(&self) -> usize {
+        match self {
+            JointKind::Ball(_) => 3,
+            JointKind::Hinge(_) => 7,
+            JointKind::Fixed(_) => 6,
+            JointKind::Prismatic(_) => 7,
+            JointKind::Distance(_) => 1,
+            JointKind::LinearSpring(_) => 1,
+            JointKind::AngularSpring(_) => 1,
+            JointKind::Gear(_) => 1,
+            JointKind::Pulley(_) => 1,
+            JointKind::RackAndPinion(_) => 1,
+        }
+    }
+}
+
+/// Range of motion allowed by a joint along, or around, one of its axes.
+///
+/// # Remarks
+/// Rigid limits are solved ahead of time, so the joint stops right at the limit instead
+/// of going past it and being pushed back.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct JointLimits<F: num_traits::Float = f64> {
+    /// Lowest position (or angle) allowed.
+    pub lower: F,
+
+    /// Highest position (or angle) allowed.
+    pub upper: F,
+
+    /// Restitution coefficient of the limits, from `0` (the joint stops at the limit)
+    /// to `1` (the joint bounces back at the speed it hit the limit with).
+    pub restitution: F,
+
+    /// Damped spring pushing the joint back within the limits once past them, if any.
+    /// Rigid limits are used when `None`.
+    pub softness: Option<JointSpring<F>>,
+}
+
+impl<F: num_traits::Float> JointLimits<F> {
+    /// Creates new rigid limits with the given range, without restitution.
+    pub fn new(lower: F, upper: F) -> Self {
+        Self {
+            lower,
+            upper,
+            restitution: num_traits::zero(),
+            softness: None,
+        }
+    }
+}
+
+/// Limit of a joint pushing back the bodies.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum LimitState {
+    /// The joint is held at its lower limit.
+    Lower,
+
+    /// The joint is held at its upper limit.
+    Upper,
+}
+
+/// Target a joint motor drives the joint towards.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum MotorTarget<F: num_traits::Float = f64> {
+    /// Relative speed (or angular speed) the motor keeps the bodies moving at.
+    Velocity(F),
+
+    /// Position (or angle) the motor moves the bodies to and holds them at.
+    Position(F),
+}
+
+/// Motor driving a joint along, or around, one of its axes.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct JointMotor<F: num_traits::Float = f64> {
+    /// Target the motor drives the joint towards.
+    pub target: MotorTarget<F>,
+
+    /// Largest force (or torque) the motor can apply.
+    pub max_force: F,
+}
+
+impl<F: num_traits::Float> JointMotor<F> {
+    /// Creates a new motor with the given target and strength.
+    pub fn new(target: MotorTarget<F>, max_force: F) -> Self {
+        Self { target, max_force }
+    }
+
+    /// Creates a new motor driving the joint at the given speed.
+    pub fn velocity(target_velocity: F, max_force: F) -> Self {
+        Self::new(MotorTarget::Velocity(target_velocity), max_force)
+    }
+
+    /// Creates a new motor driving the joint to the given position.
+    pub fn position(target_position: F, max_force: F) -> Self {
+        Self::new(MotorTarget::Position(target_position), max_force)
+    }
+}
+
+/// Damped spring replacing the rigid constraint of a joint.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct JointSpring<F: num_traits::Float = f64> {
+    /// Force (or torque) applied per unit of displacement from the rest position.
+    pub stiffness: F,
+
+    /// Force (or torque) applied per unit of relative velocity, opposing it.
+    pub damping: F,
+}
+
+impl<F: num_traits::Float> JointSpring<F> {
+    /// Creates a new spring with the given stiffness and damping coefficient.
+    pub fn new(stiffness: F, damping: F) -> Self {
+        Self { stiffness, damping }
+    }
+}
+
+/// Event reported when a joint breaks, exceeding its break thresholds.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct JointBreak<F: num_traits::Float = f64> {
+    /// Index of the joint, now disabled.
+    pub joint: usize,
+
+    /// Force applied by the joint in the frame it broke.
+    pub force: Vector3<F>,
+
+    /// Torque applied by the joint in the frame it broke.
+    pub torque: Vector3<F>,
+}
+
+/// Constraint between two rigid bodies, or between a rigid body and the scenery,
+/// restricting their relative motion.
+///
+/// # Remarks
+/// Joints are solved by the contact solver alongside the contacts, and keep the impulses
+/// accumulated in the last frame to warm start the next one. The drift accumulated by
+/// the joint is corrected by the solver, so the bodies don't slowly come apart.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Joint<F: num_traits::Float = f64> {
+    /// Indices of the bodies joined together.
+    /// The second one is `None` for joints attached to the scenery.
+    pub bodies: (usize, Option<usize>),
+
+    /// Kind of the joint.
+    pub kind: JointKind<F>,
+
+    /// Disabled joints are ignored by the solver.
+    pub enabled: bool,
+
+    /// Largest force the joint withstands before breaking, if breakable.
+    pub break_force: Option<F>,
+
+    /// Largest torque the joint withstands before breaking, if breakable.
+    pub break_torque: Option<F>,
+
+    active_limit: Option<LimitState>,
+    force: Vector3<F>,
+    torque: Vector3<F>,
+    impulses: Vec<F>,
+}
+
+impl<F: num_traits::Float> Joint<F> {
+    /// Creates a new enabled joint between the given bodies.
+    pub fn new(bodies: (usize, Option<usize>), kind: JointKind<F>) -> Self {
+        Self {
+            bodies,
+            kind,
+            enabled: true,
+            break_force: None,
+            break_torque: None,
+            active_limit: None,
+            force: Vector3::origin(),
+            torque: Vector3::origin(),
+            impulses: vec![num_traits::zero(); kind.max_rows()],
+        }
+    }
+
+    /// Returns the impulses accumulated by the solver in the last frame,
+    /// one per constraint row of the joint.
+    pub fn impulses(&self) -> &[F] {
+        &self.impulses
+    }
+
+    /// Returns the force applied by the joint on the first body in the last frame.
+    pub fn force(&self) -> Vector3<F> {
+        self.force
+    }
+
+    /// Returns the torque applied by the joint on the first body in the last frame,
+    /// besides the one caused by the force.
+    pub fn torque(&self) -> Vector3<F> {
+        self.torque
+    }
+
+    /// Returns true if the force or the torque applied by the joint in the last frame
+    /// exceeded its break thresholds.
+    pub fn is_overloaded(&self) -> bool {
+        self.break_force
+            .is_some_and(|threshold| self.force.magnitude() > threshold)
+            || self
+                .break_torque
+                .is_some_and(|threshold| self.torque.magnitude() > threshold)
+    }
+
+    /// Returns the limit of the joint that pushed back the bodies in the last frame, if any.
+    pub fn active_limit(&self) -> Option<LimitState> {
+        self.active_limit
+    }
+
+    /// Adds the constraint rows of the joint, as of the current state of the bodies,
+    /// removing the given fraction of the drift of rigid rows every frame.
+    pub(crate) fn rows(
+        &mut self,
+        joint: usize,
+        bodies: &[RigidBody<F>],
+        duration: F,
+        baumgarte: f64,
+        rows: &mut Vec<JointRow<F>>,
+    ) {
+        let mut builder = RowBuilder {
+            joint,
+            bodies: self.bodies,
+            rigid_bodies: bodies,
+            duration,
+            baumgarte: math::real(baumgarte),
+            spring: None,
+            rows,
+        };
+        match &mut self.kind {
+            JointKind::Ball(ball) => ball.rows(bodies, &mut builder),
+            JointKind::Hinge(hinge) => hinge.rows(bodies, &mut builder),
+            JointKind::Fixed(fixed) => fixed.rows(bodies, &mut builder),
+            JointKind::Prismatic(prismatic) => prismatic.rows(bodies, &mut builder),
+            JointKind::Distance(distance) => distance.rows(bodies, &mut builder),
+            JointKind::LinearSpring(spring) => spring.rows(bodies, &mut builder),
+            JointKind::AngularSpring(spring) => spring.rows(bodies, &mut builder),
+            JointKind::Gear(gear) => gear.rows(bodies, &mut builder),
+            JointKind::Pulley(pulley) => pulley.rows(bodies, &mut builder),
+            JointKind::RackAndPinion(rack) => rack.rows(bodies, &mut builder),
+        }
+    }
+
+    /// Moves the points of the joint given in world space by the opposite of the given
+    /// offset, as the origin of the world moves to it.
+    pub(crate) fn shift_origin(&mut self, offset: &Vector3<F>) {
+        let scenery = self.bodies.1.is_none();
+        let shift = |anchor: &mut Vector3<F>| {
+            if scenery {
+                *anchor = anchor.vector_sub(offset);
+            }
+        };
+        match &mut self.kind {
+            JointKind::Ball(ball) => shift(&mut ball.local_anchor_two),
+            JointKind::Hinge(hinge) => shift(&mut hinge.local_anchor_two),
+            JointKind::Fixed(fixed) => shift(&mut fixed.local_anchor_two),
+            JointKind::Prismatic(prismatic) => shift(&mut prismatic.local_anchor_two),
+            JointKind::Distance(distance) => shift(&mut distance.local_anchor_two),
+            JointKind::LinearSpring(spring) => shift(&mut spring.local_anchor_two),
+            JointKind::Pulley(pulley) => {
+                shift(&mut pulley.local_anchor_two);
+                pulley.pulley_one = pulley.pulley_one.vector_sub(offset);
+                pulley.pulley_two = pulley.pulley_two.vector_sub(offset);
+            }
+            JointKind::RackAndPinion(rack) => {
+                if !scenery {
+                    rack.shift_origin(offset);
+                }
+            }
+            JointKind::AngularSpring(_) | JointKind::Gear(_) => {}
+        }
+    }
+
+    /// Clears the accumulated impulses, before storing the ones of this frame.
+    pub(crate) fn reset_impulses(&mut self) {
+        let length = self.kind.max_rows();
+        self.active_limit = None;
+        self.force = Vector3::origin();
+        self.torque = Vector3::origin();
+        self.impulses.clear();
+        self.impulses.resize(length, num_traits::zero());
+    }
+
+    /// Stores the impulse accumulated by one of the rows of the joint,
+    /// adding it to the force and torque applied by the joint during the frame.
+    pub(crate) fn store_row(&mut self, row: &JointRow<F>, duration: F) {
+        self.impulses[row.slot] = row.impulse;
+        if row.impulse > F::zero() && row.limit.is_some() {
+            self.active_limit = row.limit;
+        }
+
+        let magnitude = row.impulse / duration;
+        if row.linear.squared_magnitude() > F::zero() {
+            self.force
+                .inplace_vector_add(&row.linear.scalar_mul(magnitude));
+        } else {
+            self.torque
+                .inplace_vector_add(&row.angular_one.scalar_mul(magnitude));
+        }
+    }
+
+    /// Returns the impulse accumulated by one of the rows of the joint in the last frame.
+    pub(crate) fn impulse(&self, slot: usize) -> F {
+        self.impulses.get(slot).copied().unwrap_or_else(F::zero)
+    }
+}
+
+/// World positions of the anchors of a joint, and their offsets from the bodies.
+pub(crate) struct Anchors<F: num_traits::Float> {
+    /// Anchor of the first body in world space.
+    pub point_one: Vector3<F>,
+
+    /// Anchor of the second body in world space.
+    pub point_two: Vector3<F>,
+
+    /// Anchor of the first body relative to its center of mass.
+    pub relative_one: Vector3<F>,
+
+    /// Anchor of the second body relative to its center of mass.
+    pub relative_two: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Anchors<F> {
+    /// Places the anchors, given in the local spaces of the bodies, in world space.
+    /// The anchor of the second body is in world space for joints with the scenery.
+    pub fn new(
+        pair: (usize, Option<usize>),
+        local_one: &Vector3<F>,
+        local_two: &Vector3<F>,
+        bodies: &[RigidBody<F>],
+    ) -> Self {
+        let one = &bodies[pair.0];
+        let point_one = one.point_in_world_space(local_one);
+        let (point_two, relative_two) = match pair.1 {
+            Some(body) => {
+                let point = bodies[body].point_in_world_space(local_two);
+                (point, point.vector_sub(&bodies[body].position))
+            }
+            None => (*local_two, Vector3::origin()),
+        };
+        Self {
+            point_one,
+            point_two,
+            relative_one: point_one.vector_sub(&one.position),
+            relative_two,
+        }
+    }
+}
+
+/// Converts a direction given in the local space of a body into world space.
+/// Directions of the scenery are already in world space.
+pub(crate) fn world_direction<F: num_traits::Float>(
+    body: Option<&RigidBody<F>>,
+    direction: &Vector3<F>,
+) -> Vector3<F> {
+    match body {
+        Some(body) => body.direction_in_world_space(direction),
+        None => *direction,
+    }
+}
+
+/// Collects the constraint rows of a joint.
+pub(crate) struct RowBuilder<'a, F: num_traits::Float> {
+    joint: usize,
+    bodies: (usize, Option<usize>),
+    rigid_bodies: &'a [RigidBody<F>],
+    duration: F,
+    baumgarte: F,
+    spring: Option<JointSpring<F>>,
+    rows: &'a mut Vec<JointRow<F>>,
+}
+
+impl<'a, F: num_traits::Float> RowBuilder<'a, F> {
+    /// Returns the indices of the bodies joined together.
+    pub fn bodies(&self) -> (usize, Option<usize>) {
+        self.bodies
+    }
+
+    /// Returns the duration of the frame the rows are solved for.
+    pub fn duration(&self) -> F {
+        self.duration
+    }
+
+    /// Makes the rows added from now on behave as the given damped spring, or rigid when `None`.
+    ///
+    /// # Remarks
+    /// Springs are solved implicitly, so they stay stable no matter how stiff they are.
+    /// An infinitely stiff spring without damping removes the whole error in one frame.
+    pub fn set_spring(&mut self, spring: Option<JointSpring<F>>) {
+        self.spring = spring;
+    }
+
+    /// Adds a row constraining the relative velocity of the bodies along the given
+    /// directions, correcting the given position error.
+    ///
+    /// # Remarks
+    /// The relative velocity is `linear·(v1 - v2) + angular_one·w1 - angular_two·w2`.
+    /// The row returned can be further adjusted (e.g. with impulse bounds, or a different
+    /// linear direction for the second body).
+    pub fn add(
+        &mut self,
+        slot: usize,
+        linear: Vector3<F>,
+        angular_one: Vector3<F>,
+        angular_two: Vector3<F>,
+        error: F,
+    ) -> &mut JointRow<F> {
+        let duration = self.duration;
+        let (bias, softness, limit) = match self.spring {
+            // Soft constraint: the impulse is found for the state at the end of the frame,
+            // with the spring force `-stiffness * error - damping * velocity`.
+            Some(spring) => {
+                let coefficient = spring.damping + duration * spring.stiffness;
+                if coefficient > F::zero() {
+                    (
+                        -spring.stiffness * error / coefficient,
+                        F::one() / (duration * coefficient),
+                        F::infinity(),
+                    )
+                } else {
+                    // A spring without stiffness nor damping doesn't constrain anything.
+                    (num_traits::zero(), num_traits::zero(), num_traits::zero())
+                }
+            }
+            None => (
+                -self.baumgarte * error / duration,
+                num_traits::zero(),
+                F::infinity(),
+            ),
+        };
+        self.rows.push(JointRow {
+            joint: self.joint,
+            slot,
+            bodies: self.bodies,
+            linear,
+            linear_two: linear,
+            angular_one,
+            angular_two,
+            bias,
+            softness,
+            lower: -limit,
+            upper: limit,
+            mass: num_traits::zero(),
+            impulse: num_traits::zero(),
+            limit: None,
+        });
+        self.rows.last_mut().expect("just added")
+    }
+
+    /// Adds the rows keeping both anchors together, starting at the given slot.
+    pub fn point(&mut self, slot: usize, anchors: &Anchors<F>) {
+        let error = anchors.point_one.vector_sub(&anchors.point_two);
+        let axes = [
+            Vector3::new(F::one(), F::zero(), F::zero()),
+            Vector3::new(F::zero(), F::one(), F::zero()),
+            Vector3::new(F::zero(), F::zero(), F::one()),
+        ];
+        for (index, axis) in axes.iter().enumerate() {
+            self.add(
+                slot + index,
+                *axis,
+                anchors.relative_one.cross_product(axis),
+                anchors.relative_two.cross_product(axis),
+                error.dot_product(axis),
+            );
+        }
+    }
+
+    /// Adds the row keeping the given position (or angle) within the nearest limit.
+    /// The relative velocity along the given directions must be the rate of change
+    /// of the position.
+    ///
+    /// # Remarks
+    /// Soft limits only add the row once the position is past the limit.
+    pub fn limit(
+        &mut self,
+        slot: usize,
+        limits: &JointLimits<F>,
+        position: F,
+        linear: Vector3<F>,
+        angular_one: Vector3<F>,
+        angular_two: Vector3<F>,
+    ) {
+        let (state, gap, linear, angular_one, angular_two) =
+            if position - limits.lower <= limits.upper - position {
+                (
+                    LimitState::Lower,
+                    position - limits.lower,
+                    linear,
+                    angular_one,
+                    angular_two,
+                )
+            } else {
+                (
+                    LimitState::Upper,
+                    limits.upper - position,
+                    linear.invert(),
+                    angular_one.invert(),
+                    angular_two.invert(),
+                )
+            };
+
+        // Approaching velocity, positive towards the inside of the limits.
+        let duration = self.duration;
+        let velocity = relative_velocity(
+            self.rigid_bodies,
+            self.bodies,
+            (&linear, &linear),
+            &angular_one,
+            &angular_two,
+        );
+        if gap > F::zero() && limits.softness.is_some() {
+            return;
+        }
+
+        let spring = self.spring;
+        self.set_spring(limits.softness);
+        let row = self.add(slot, linear, angular_one, angular_two, gap);
+        row.lower = num_traits::zero();
+        row.limit = Some(state);
+        if limits.softness.is_none() {
+            if gap > F::zero() {
+                // Speculative row: let the joint move up to the limit, but not past it.
+                row.bias = -gap / duration;
+            }
+            let reached = gap + velocity * duration <= F::zero();
+            if reached && velocity < -math::real::<F>(RESTITUTION_VELOCITY_LIMIT) {
+                row.bias = row.bias.max(-limits.restitution * velocity);
+            }
+        }
+        self.set_spring(spring);
+    }
+
+    /// Adds the row driving the relative velocity along the given directions towards the
+    /// target of the motor. The relative velocity must be the rate of change of the position.
+    ///
+    /// # Remarks
+    /// Position targets are reached as fast as the strength of the motor allows,
+    /// taking at least one frame.
+    pub fn motor(
+        &mut self,
+        slot: usize,
+        motor: &JointMotor<F>,
+        position: F,
+        linear: Vector3<F>,
+        angular_one: Vector3<F>,
+        angular_two: Vector3<F>,
+    ) {
+        let max_impulse = motor.max_force * self.duration;
+        let duration = self.duration;
+        let row = self.add(slot, linear, angular_one, angular_two, num_traits::zero());
+        row.bias = match motor.target {
+            MotorTarget::Velocity(velocity) => velocity,
+            MotorTarget::Position(target) => (target - position) / duration,
+        };
+        row.softness = num_traits::zero();
+        row.lower = -max_impulse;
+        row.upper = max_impulse;
+    }
+}
+
+/// Single degree of freedom removed by a joint, prepared for the solver.
+pub(crate) struct JointRow<F: num_traits::Float> {
+    /// Index of the joint the row belongs to.
+    pub joint: usize,
+
+    /// Index of the row in the joint, used to keep its impulse across frames.
+    pub slot: usize,
+
+    /// Indices of the bodies joined together.
+    pub bodies: (usize, Option<usize>),
+
+    /// Direction of the impulse applied to the first body.
+    pub linear: Vector3<F>,
+
+    /// Direction of the impulse applied to the second body, opposed,
+    /// the same as the one of the first body unless changed.
+    pub linear_two: Vector3<F>,
+
+    /// Direction of the torque impulse applied to the first body.
+    pub angular_one: Vector3<F>,
+
+    /// Direction of the torque impulse applied to the second body, opposed.
+    pub angular_two: Vector3<F>,
+
+    /// Relative velocity the row drives the bodies towards.
+    pub bias: F,
+
+    /// Softness of the row, letting it give in proportion to the accumulated impulse.
+    pub softness: F,
+
+    /// Smallest impulse the row can accumulate.
+    pub lower: F,
+
+    /// Largest impulse the row can accumulate.
+    pub upper: F,
+
+    /// Mass the row opposes to impulses.
+    pub mass: F,
+
+    /// Impulse accumulated by the row.
+    pub impulse: F,
+
+    /// Limit the row keeps the joint within, if any.
+    pub limit: Option<LimitState>,
+}
+
+impl<F: num_traits::Float> JointRow<F> {
+    /// Computes the mass of the row, and applies the impulse it starts with.
+    pub fn prepare(&mut self, bodies: &mut [RigidBody<F>]) {
+        let mut inverse = self.softness;
+        let one = &bodies[self.bodies.0];
+        if is_moving(one) {
+            inverse = inverse
+                + one.velocity_change(&self.linear).dot_product(&self.linear)
+                + self.angular_one.dot_product(
+                    &one.inverse_inertia_tensor_world
+                        .transform(&self.angular_one),
+                );
+        }
+        if let Some(two) = self.bodies.1.map(|body| &bodies[body]) {
+            if is_moving(two) {
+                inverse = inverse
+                    + two
+                        .velocity_change(&self.linear_two)
+                        .dot_product(&self.linear_two)
+                    + self.angular_two.dot_product(
+                        &two.inverse_inertia_tensor_world
+                            .transform(&self.angular_two),
+                    );
+            }
+        }
+        self.mass = if inverse > F::zero() {
+            F::one() / inverse
+        } else {
+            num_traits::zero()
+        };
+
+        self.impulse = self.impulse.max(self.lower).min(self.upper);
+        self.apply(self.impulse, bodies);
+    }
+
+    /// Returns the relative velocity of the bodies along the row.
+    fn velocity(&self, bodies: &[RigidBody<F>]) -> F {
+        relative_velocity(
+            bodies,
+            self.bodies,
+            (&self.linear, &self.linear_two),
+            &self.angular_one,
+            &self.angular_two,
+        )
+    }
+
+    /// Applies the given impulse along the row.
+    fn apply(&self, impulse: F, bodies: &mut [RigidBody<F>]) {
+        let one = &mut bodies[self.bodies.0];
+        if is_moving(one) {
+            let change = one.velocity_change(&self.linear.scalar_mul(impulse));
+            one.velocity.inplace_vector_add(&change);
+            let torque = self.angular_one.scalar_mul(impulse);
+            one.rotation
+                .inplace_vector_add(&one.inverse_inertia_tensor_world.transform(&torque));
+        }
+        if let Some(body) = self.bodies.1 {
+            let two = &mut bodies[body];
+            if is_moving(two) {
+                let change = two.velocity_change(&self.linear_two.scalar_mul(impulse));
+                two.velocity.inplace_vector_sub(&change);
+                let torque = self.angular_two.scalar_mul(impulse);
+                two.rotation
+                    .inplace_vector_sub(&two.inverse_inertia_tensor_world.transform(&torque));
+            }
+        }
+    }
+}
+
+impl<F: num_traits::Float> ImpulseConstraint<RigidBody<F>> for JointRow<F> {
+    /// Runs one iteration of the solver over the row.
+    fn solve(&mut self, bodies: &mut [RigidBody<F>]) {
+        let velocity = self.velocity(bodies);
+        let previous = self.impulse;
+        self.impulse = row_impulse(
+            previous,
+            self.bias - velocity,
+            self.softness,
+            self.mass,
+            (self.lower, self.upper),
+        );
+        self.apply(self.impulse - previous, bodies);
+    }
+}
+
+/// Returns the relative velocity of the given bodies along the given directions,
+/// `linear_one·v1 - linear_two·v2 + angular_one·w1 - angular_two·w2`.
+fn relative_velocity<F: num_traits::Float>(
+    bodies: &[RigidBody<F>],
+    pair: (usize, Option<usize>),
+    (linear_one, linear_two): (&Vector3<F>, &Vector3<F>),
+    angular_one: &Vector3<F>,
+    angular_two: &Vector3<F>,
+) -> F {
+    let one = &bodies[pair.0];
+    let mut velocity =
+        linear_one.dot_product(&one.velocity) + angular_one.dot_product(&one.rotation);
+    if let Some(body) = pair.1 {
+        let two = &bodies[body];
+        velocity = velocity
+            - linear_two.dot_product(&two.velocity)
+            - angular_two.dot_product(&two.rotation);
+    }
+    velocity
+}
@@ -0,0 +1,639 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ball_joint::BallJoint;
+use crate::distance_joint::DistanceJoint;
+use crate::fixed_joint::FixedJoint;
+use crate::force::Gravity;
+use crate::gear_joint::GearJoint;
+use crate::hinge_joint::HingeJoint;
+use crate::joint::*;
+use crate::prismatic_joint::PrismaticJoint;
+use crate::pulley_joint::PulleyJoint;
+use crate::rack_and_pinion_joint::RackAndPinionJoint;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Cuboid, Sphere};
+use crate::spring_joint::{AngularSpringJoint, LinearSpringJoint};
+use crate::world::World;
+use math::Vector3;
+
+fn ball(world: &mut World, position: Vector3<f64>) -> usize {
+    let sphere = Sphere::new(0.25);
+    let mut body = RigidBody::new(position, 1.0, &sphere.inertia_tensor(1.0));
+    body.can_sleep = false;
+    let index = world.add_body(body);
+    let index = world.body_index(index).unwrap();
+    world
+        .registry
+        .add(index, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+    index
+}
+
+fn step(world: &mut World, frames: usize) {
+    for _ in 0..frames {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+}
+
+#[test]
+fn ball_joint_pendulum() {
+    let mut world = World::default();
+    let bob = ball(&mut world, Vector3::new(1.0, 0.0, 0.0));
+    world.add_joint(Joint::new(
+        (bob, None),
+        JointKind::Ball(BallJoint::new(
+            Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::origin(),
+        )),
+    ));
+
+    // The bob swings down and up the other side while hanging from the pivot.
+    let mut lowest: f64 = 0.0;
+    for _ in 0..6 {
+        step(&mut world, 10);
+        let anchor = world.bodies[bob].point_in_world_space(&Vector3::new(-1.0, 0.0, 0.0));
+        assert!(anchor.magnitude() < 0.02);
+        lowest = lowest.min(world.bodies[bob].position.y);
+    }
+    assert!(lowest < -0.9);
+    assert!(world.bodies[bob].position.x < -0.9);
+    assert!(world.joints[0]
+        .impulses()
+        .iter()
+        .any(|impulse| *impulse != 0.0));
+}
+
+#[test]
+fn ball_joint_chain() {
+    let mut world = World::default();
+    let top = ball(&mut world, Vector3::new(0.0, -1.0, 0.0));
+    let bottom = ball(&mut world, Vector3::new(0.0, -2.0, 0.0));
+    let joint = BallJoint::new(Vector3::new(0.0, 1.0, 0.0), Vector3::origin());
+    world.add_joint(Joint::new((top, None), JointKind::Ball(joint)));
+    let link = BallJoint::new(Vector3::new(0.0, 0.5, 0.0), Vector3::new(0.0, -0.5, 0.0));
+    world.add_joint(Joint::new((bottom, Some(top)), JointKind::Ball(link)));
+
+    // Hanging at rest, the chain holds the weight of both bodies.
+    step(&mut world, 60);
+    assert!((world.bodies[bottom].position.y + 2.0).abs() < 0.02);
+    let holding = world.joints[0].impulses()[1];
+    assert!((holding - 2.0 * 10.0 / 60.0).abs() < 0.01);
+
+    // Kicking the bottom body drags the top one along.
+    world.bodies[bottom].velocity = Vector3::new(2.0, 0.0, 0.0);
+    step(&mut world, 10);
+    assert!(world.bodies[top].position.x > 0.0);
+    let upper = world.bodies[top].point_in_world_space(&Vector3::new(0.0, -0.5, 0.0));
+    let lower = world.bodies[bottom].point_in_world_space(&Vector3::new(0.0, 0.5, 0.0));
+    assert!(upper.vector_sub(&lower).magnitude() < 0.05);
+
+    // Disabled joints let go of the bodies.
+    world.joints[1].enabled = false;
+    world.bodies[bottom].velocity = Vector3::origin();
+    step(&mut world, 30);
+    assert!(world.bodies[bottom].position.y < -2.5);
+}
+
+fn door(world: &mut World) -> usize {
+    let cuboid = Cuboid::new(Vector3::new(0.5, 1.0, 0.05));
+    let mut body = RigidBody::new(
+        Vector3::new(0.5, 0.0, 0.0),
+        1.0,
+        &cuboid.inertia_tensor(1.0),
+    );
+    body.can_sleep = false;
+    let door = world.add_body(body);
+    let door = world.body_index(door).unwrap();
+    let hinge = HingeJoint::from_world(
+        &world.bodies[door],
+        None,
+        &Vector3::origin(),
+        &Vector3::new(0.0, 1.0, 0.0),
+    );
+    world.add_joint(Joint::new((door, None), JointKind::Hinge(hinge)));
+    door
+}
+
+fn hinge(world: &World) -> &HingeJoint {
+    match &world.joints[0].kind {
+        JointKind::Hinge(hinge) => hinge,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn hinge_joint() {
+    let mut world = World::default();
+    let door = door(&mut world);
+    assert_eq!(0.0, hinge(&world).angle(&world.bodies[door], None));
+
+    // Only the rotation around the axis of the hinge is kept.
+    world.bodies[door].rotation = Vector3::new(1.0, 1.0, 1.0);
+    world.bodies[door].velocity = Vector3::new(1.0, 1.0, -1.0);
+    step(&mut world, 30);
+    let body = &world.bodies[door];
+    let axis = body.direction_in_world_space(&Vector3::new(0.0, 1.0, 0.0));
+    assert!(axis.vector_sub(&Vector3::new(0.0, 1.0, 0.0)).magnitude() < 0.01);
+    assert!(
+        body.point_in_world_space(&Vector3::new(-0.5, 0.0, 0.0))
+            .magnitude()
+            < 0.01
+    );
+
+    let angle = hinge(&world).angle(body, None);
+    let speed = hinge(&world).angular_speed(body, None);
+    assert!(angle > 0.1);
+    assert!(speed > 0.1);
+    assert!((body.orientation.j - (angle * 0.5).sin()).abs() < 0.01);
+}
+
+#[test]
+fn hinge_joint_limits_and_motor() {
+    let mut world = World::default();
+    let door = door(&mut world);
+    if let JointKind::Hinge(hinge) = &mut world.joints[0].kind {
+        hinge.limits = Some(JointLimits::new(-0.5, 0.5));
+    }
+
+    // The door swings until it hits the limit.
+    world.bodies[door].rotation = Vector3::new(0.0, -2.0, 0.0);
+    step(&mut world, 60);
+    let angle = hinge(&world).angle(&world.bodies[door], None);
+    assert!((angle + 0.5).abs() < 0.03);
+
+    // The motor opens the door at a steady pace, up to the other limit.
+    if let JointKind::Hinge(hinge) = &mut world.joints[0].kind {
+        hinge.motor = Some(JointMotor::velocity(1.0, 100.0));
+    }
+    step(&mut world, 30);
+    let speed = hinge(&world).angular_speed(&world.bodies[door], None);
+    assert!((speed - 1.0).abs() < 1e-3);
+    step(&mut world, 60);
+    let angle = hinge(&world).angle(&world.bodies[door], None);
+    assert!((angle - 0.5).abs() < 0.03);
+
+    // A weak motor can't keep up against the damping of the body.
+    if let JointKind::Hinge(hinge) = &mut world.joints[0].kind {
+        hinge.motor = Some(JointMotor::velocity(-1.0, 0.01));
+    }
+    step(&mut world, 30);
+    let speed = hinge(&world).angular_speed(&world.bodies[door], None);
+    assert!(speed > -0.5);
+
+    // A position motor swings the door to the given angle and holds it there.
+    if let JointKind::Hinge(hinge) = &mut world.joints[0].kind {
+        hinge.motor = Some(JointMotor::position(-0.25, 100.0));
+    }
+    step(&mut world, 60);
+    let angle = hinge(&world).angle(&world.bodies[door], None);
+    assert!((angle + 0.25).abs() < 1e-3);
+    let speed = hinge(&world).angular_speed(&world.bodies[door], None);
+    assert!(speed.abs() < 1e-3);
+}
+
+fn cantilever(compliance: f64, damping: f64) -> World {
+    let mut world = World::default();
+    let beam = ball(&mut world, Vector3::new(1.0, 0.0, 0.0));
+    let mut weld = FixedJoint::from_world(&world.bodies[beam], None, &Vector3::origin());
+    weld.compliance = compliance;
+    weld.damping = damping;
+    world.add_joint(Joint::new((beam, None), JointKind::Fixed(weld)));
+    step(&mut world, 120);
+    world
+}
+
+#[test]
+fn fixed_joint() {
+    // A rigid weld holds the beam in place against gravity.
+    let rigid = cantilever(0.0, 0.0);
+    let beam = &rigid.bodies[0];
+    assert!(
+        beam.position
+            .vector_sub(&Vector3::new(1.0, 0.0, 0.0))
+            .magnitude()
+            < 0.01
+    );
+    assert!(beam.orientation.r > 0.9999);
+
+    // A compliant weld sags under the load, settling into place.
+    let soft = cantilever(0.01, 20.0);
+    let beam = &soft.bodies[0];
+    assert!(beam.position.y < -0.05);
+    assert!(beam.position.y > -0.5);
+    assert!(beam.velocity.magnitude() < 0.05);
+
+    // Welded bodies move as one.
+    let mut world = World::default();
+    let one = ball(&mut world, Vector3::new(0.0, 0.0, 0.0));
+    let two = ball(&mut world, Vector3::new(1.0, 0.0, 0.0));
+    let weld = FixedJoint::from_world(
+        &world.bodies[one],
+        Some(&world.bodies[two]),
+        &Vector3::new(0.5, 0.0, 0.0),
+    );
+    world.add_joint(Joint::new((one, Some(two)), JointKind::Fixed(weld)));
+    world.bodies[one].rotation = Vector3::new(0.0, 0.0, 1.0);
+    step(&mut world, 30);
+    let offset = world.bodies[two]
+        .position
+        .vector_sub(&world.bodies[one].position);
+    assert!((offset.magnitude() - 1.0).abs() < 0.01);
+    assert!(offset.y > 0.01);
+    assert!(
+        weld.angular_error(&world.bodies[one], Some(&world.bodies[two]))
+            .magnitude()
+            < 0.01
+    );
+}
+
+fn tether(joint: DistanceJoint, frames: usize) -> World {
+    let mut world = World::default();
+    let bob = ball(&mut world, Vector3::new(0.0, -1.0, 0.0));
+    world.add_joint(Joint::new((bob, None), JointKind::Distance(joint)));
+    step(&mut world, frames);
+    world
+}
+
+fn distance(world: &World) -> f64 {
+    match &world.joints[0].kind {
+        JointKind::Distance(joint) => joint.distance(&world.bodies[0], None),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn distance_joint() {
+    // A rope lets the body fall until it's taut.
+    let rope = DistanceJoint::new(Vector3::origin(), Vector3::origin(), 0.0, 2.0);
+    let slack = tether(rope, 10);
+    assert!(distance(&slack) > 1.1 && distance(&slack) < 2.0);
+    let taut = tether(rope, 120);
+    assert!((distance(&taut) - 2.0).abs() < 0.01);
+    assert!(taut.bodies[0].velocity.magnitude() < 0.05);
+
+    // A rod keeps its length even when pushed.
+    let rod = DistanceJoint::new(Vector3::origin(), Vector3::origin(), 1.0, 1.0);
+    let mut world = tether(rod, 30);
+    world.bodies[0].velocity = Vector3::new(1.0, 3.0, 0.0);
+    step(&mut world, 10);
+    assert!((distance(&world) - 1.0).abs() < 0.01);
+
+    // An elastic rope stretches under the weight of the body.
+    let mut bungee = rope;
+    bungee.spring = Some(JointSpring::new(100.0, 20.0));
+    let stretched = tether(bungee, 240);
+    assert!((distance(&stretched) - 2.1).abs() < 0.01);
+}
+
+#[test]
+fn spring_joints() {
+    // A suspension settles below its rest position under the weight of the body.
+    let mut world = World::default();
+    let wheel = ball(&mut world, Vector3::new(0.0, -1.0, 0.0));
+    let suspension = LinearSpringJoint::new(
+        Vector3::origin(),
+        Vector3::origin(),
+        Vector3::new(0.0, 1.0, 0.0),
+        -1.0,
+        JointSpring::new(100.0, 20.0),
+    );
+    world.add_joint(Joint::new(
+        (wheel, None),
+        JointKind::LinearSpring(suspension),
+    ));
+    step(&mut world, 240);
+    assert!((suspension.position(&world.bodies[wheel], None) + 1.1).abs() < 0.01);
+    assert!(world.bodies[wheel].velocity.magnitude() < 0.01);
+
+    // A bump compresses it, and it swings back into place.
+    world.bodies[wheel].velocity = Vector3::new(0.0, 5.0, 0.0);
+    step(&mut world, 5);
+    assert!(suspension.position(&world.bodies[wheel], None) > -1.0);
+    step(&mut world, 240);
+    assert!((suspension.position(&world.bodies[wheel], None) + 1.1).abs() < 0.01);
+
+    // Even absurdly stiff springs stay stable.
+    world.joints[0].kind = JointKind::LinearSpring(LinearSpringJoint {
+        spring: JointSpring::new(1e9, 1e3),
+        ..suspension
+    });
+    step(&mut world, 60);
+    assert!((suspension.position(&world.bodies[wheel], None) + 1.0).abs() < 0.01);
+
+    // A sign twists towards its rest angle, wobbling around it.
+    let mut world = World::default();
+    let sign = ball(&mut world, Vector3::origin());
+    let axis = Vector3::new(0.0, 1.0, 0.0);
+    let torsion = AngularSpringJoint::from_world(
+        &world.bodies[sign],
+        None,
+        &axis,
+        0.5,
+        JointSpring::new(1.0, 0.1),
+    );
+    world.add_joint(Joint::new((sign, None), JointKind::AngularSpring(torsion)));
+    step(&mut world, 30);
+    assert!(torsion.angle(&world.bodies[sign], None) > 0.5);
+    step(&mut world, 600);
+    assert!((torsion.angle(&world.bodies[sign], None) - 0.5).abs() < 0.01);
+    assert!(world.bodies[sign].rotation.magnitude() < 0.01);
+}
+
+/// Adds a wheel spinning around the z axis on a hinge at the given position.
+fn wheel(world: &mut World, position: Vector3<f64>) -> usize {
+    let wheel = ball(world, position);
+    let axle = HingeJoint::from_world(
+        &world.bodies[wheel],
+        None,
+        &position,
+        &Vector3::new(0.0, 0.0, 1.0),
+    );
+    world.add_joint(Joint::new((wheel, None), JointKind::Hinge(axle)));
+    wheel
+}
+
+#[test]
+fn gear_joint() {
+    // Meshing gears turn in opposite directions, the small one twice as fast.
+    let mut world = World::default();
+    let small = wheel(&mut world, Vector3::origin());
+    let large = wheel(&mut world, Vector3::new(1.5, 0.0, 0.0));
+    let axis = Vector3::new(0.0, 0.0, 1.0);
+    let gear = GearJoint::from_world(
+        &world.bodies[small],
+        Some(&world.bodies[large]),
+        &axis,
+        &axis.invert(),
+        2.0,
+    );
+    world.add_joint(Joint::new((small, Some(large)), JointKind::Gear(gear)));
+    world.bodies[small].rotation = Vector3::new(0.0, 0.0, 20.0);
+    step(&mut world, 120);
+    let (one, two) = (world.bodies[small].rotation, world.bodies[large].rotation);
+    assert!(one.z > 1.0);
+    assert!((one.z + 2.0 * two.z).abs() < 0.05);
+
+    // The angles are kept in step over many turns.
+    let angles = match &world.joints[2].kind {
+        JointKind::Gear(gear) => gear.angles(),
+        _ => unreachable!(),
+    };
+    assert!(angles[0] > 4.0 * std::f64::consts::PI);
+    assert!((angles[0] - 2.0 * angles[1]).abs() < 0.01);
+
+    // Geared to the scenery, the wheel is held still.
+    let mut world = World::default();
+    let stuck = wheel(&mut world, Vector3::origin());
+    let gear = GearJoint::new(axis, axis, 1.0);
+    world.add_joint(Joint::new((stuck, None), JointKind::Gear(gear)));
+    world.bodies[stuck].rotation = Vector3::new(0.0, 0.0, 5.0);
+    step(&mut world, 30);
+    assert!(world.bodies[stuck].rotation.magnitude() < 0.01);
+}
+
+#[test]
+fn pulley_joint() {
+    // The heavier load goes down, lifting the lighter one on the other side.
+    let mut world = World::default();
+    let light = ball(&mut world, Vector3::new(-1.0, 0.0, 0.0));
+    let heavy = ball(&mut world, Vector3::new(1.0, 0.0, 0.0));
+    world.bodies[heavy].set_mass(2.0);
+    let pulleys = (Vector3::new(-1.0, 2.0, 0.0), Vector3::new(1.0, 2.0, 0.0));
+    let pulley = PulleyJoint::from_world(
+        &world.bodies[light],
+        Some(&world.bodies[heavy]),
+        (&world.bodies[light].position, &world.bodies[heavy].position),
+        (&pulleys.0, &pulleys.1),
+        1.0,
+    );
+    assert_eq!(4.0, pulley.length);
+    world.add_joint(Joint::new((light, Some(heavy)), JointKind::Pulley(pulley)));
+    step(&mut world, 30);
+    let (one, two) = pulley.lengths(&world.bodies[light], Some(&world.bodies[heavy]));
+    assert!(one < 1.9);
+    assert!((one + two - 4.0).abs() < 0.01);
+
+    // With a ratio of two, the second side holds twice the load of the first one.
+    let mut world = World::default();
+    let load = ball(&mut world, Vector3::new(-1.0, 0.0, 0.0));
+    let counterweight = ball(&mut world, Vector3::new(1.0, 0.0, 0.0));
+    world.bodies[counterweight].set_mass(2.0);
+    let pulley = PulleyJoint::from_world(
+        &world.bodies[load],
+        Some(&world.bodies[counterweight]),
+        (
+            &world.bodies[load].position,
+            &world.bodies[counterweight].position,
+        ),
+        (&pulleys.0, &pulleys.1),
+        2.0,
+    );
+    world.add_joint(Joint::new(
+        (load, Some(counterweight)),
+        JointKind::Pulley(pulley),
+    ));
+    step(&mut world, 60);
+    assert!((world.bodies[load].position.y).abs() < 0.01);
+    assert!((world.bodies[counterweight].position.y).abs() < 0.01);
+
+    // Like ropes, pulleys don't push: thrown up, a load leaves the rope slack.
+    world.bodies[load].velocity = Vector3::new(0.0, 3.0, 0.0);
+    step(&mut world, 5);
+    let (one, two) = pulley.lengths(&world.bodies[load], Some(&world.bodies[counterweight]));
+    assert!(world.bodies[load].position.y > 0.15);
+    assert!(one + 2.0 * two < pulley.length - 0.05);
+}
+
+#[test]
+fn rack_and_pinion_joint() {
+    // Turning the pinion moves the rack along by its radius for every radian.
+    let mut world = World::default();
+    let pinion = wheel(&mut world, Vector3::origin());
+    let rack = ball(&mut world, Vector3::new(0.0, -0.75, 0.0));
+    world.bodies[rack].set_mass(0.1);
+    let axis = Vector3::new(1.0, 0.0, 0.0);
+    let slider = PrismaticJoint::from_world(&world.bodies[rack], None, &Vector3::origin(), &axis);
+    world.add_joint(Joint::new((rack, None), JointKind::Prismatic(slider)));
+    let gearing = RackAndPinionJoint::from_world(
+        &world.bodies[pinion],
+        Some(&world.bodies[rack]),
+        &Vector3::new(0.0, 0.0, 1.0),
+        &axis,
+        0.5,
+    );
+    world.add_joint(Joint::new(
+        (pinion, Some(rack)),
+        JointKind::RackAndPinion(gearing),
+    ));
+    world.bodies[pinion].rotation = Vector3::new(0.0, 0.0, 10.0);
+    step(&mut world, 120);
+    let turning = world.bodies[pinion].rotation.z;
+    assert!(turning > 1.0);
+    assert!((world.bodies[rack].velocity.x - 0.5 * turning).abs() < 0.05);
+    let gearing = match &world.joints[2].kind {
+        JointKind::RackAndPinion(gearing) => *gearing,
+        _ => unreachable!(),
+    };
+    assert!(gearing.angle() > 2.0 * std::f64::consts::PI);
+    assert!((gearing.translation() - 0.5 * gearing.angle()).abs() < 0.01);
+
+    // Driving the rack turns the pinion, like a jack.
+    if let JointKind::Prismatic(slider) = &mut world.joints[1].kind {
+        slider.motor = Some(JointMotor::velocity(-1.0, 100.0));
+    }
+    step(&mut world, 30);
+    assert!((world.bodies[rack].velocity.x + 1.0).abs() < 0.01);
+    assert!((world.bodies[pinion].rotation.z + 2.0).abs() < 0.05);
+}
+
+fn slider(world: &World) -> &PrismaticJoint {
+    match &world.joints[0].kind {
+        JointKind::Prismatic(slider) => slider,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn prismatic_joint() {
+    let mut world = World::default();
+    let cart = ball(&mut world, Vector3::origin());
+    let joint = PrismaticJoint::from_world(
+        &world.bodies[cart],
+        None,
+        &Vector3::origin(),
+        &Vector3::new(1.0, 0.0, 0.0),
+    );
+    world.add_joint(Joint::new((cart, None), JointKind::Prismatic(joint)));
+
+    // Only the motion along the axis is kept, without turning.
+    world.bodies[cart].velocity = Vector3::new(1.0, 1.0, 1.0);
+    world.bodies[cart].rotation = Vector3::new(1.0, 1.0, 1.0);
+    step(&mut world, 30);
+    let body = &world.bodies[cart];
+    assert!((joint.translation(body, None) - 0.5).abs() < 0.02);
+    // Only the damping of the body slows it down.
+    assert!((joint.linear_speed(body, None) - 0.975).abs() < 0.01);
+    assert!(body.position.y.abs() < 0.01 && body.position.z.abs() < 0.01);
+    assert!(body.orientation.r > 0.9999);
+
+    // The cart stops at the limit.
+    if let JointKind::Prismatic(slider) = &mut world.joints[0].kind {
+        slider.limits = Some(JointLimits::new(-1.0, 1.0));
+    }
+    let mut furthest: f64 = 0.0;
+    for _ in 0..12 {
+        step(&mut world, 10);
+        furthest = furthest.max(slider(&world).translation(&world.bodies[cart], None));
+    }
+    assert!((furthest - 1.0).abs() < 0.02);
+
+    // The motor drives it back at a steady pace, and then to the given position.
+    if let JointKind::Prismatic(slider) = &mut world.joints[0].kind {
+        slider.motor = Some(JointMotor::velocity(-2.0, 100.0));
+    }
+    step(&mut world, 15);
+    let speed = slider(&world).linear_speed(&world.bodies[cart], None);
+    assert!((speed + 2.0).abs() < 1e-3);
+    if let JointKind::Prismatic(slider) = &mut world.joints[0].kind {
+        slider.motor = Some(JointMotor::position(0.25, 100.0));
+    }
+    step(&mut world, 60);
+    let translation = slider(&world).translation(&world.bodies[cart], None);
+    assert!((translation - 0.25).abs() < 1e-3);
+}
+
+fn elevator(limits: JointLimits) -> World {
+    let mut world = World::default();
+    let cart = ball(&mut world, Vector3::origin());
+    let mut joint = PrismaticJoint::from_world(
+        &world.bodies[cart],
+        None,
+        &Vector3::origin(),
+        &Vector3::new(0.0, 1.0, 0.0),
+    );
+    joint.limits = Some(limits);
+    world.add_joint(Joint::new((cart, None), JointKind::Prismatic(joint)));
+    world
+}
+
+#[test]
+fn joint_limits() {
+    // Rigid limits stop the cart right at the limit, holding it there.
+    let mut world = elevator(JointLimits::new(-0.5, 0.5));
+    step(&mut world, 20);
+    assert_eq!(None, world.joints[0].active_limit());
+    step(&mut world, 40);
+    let translation = slider(&world).translation(&world.bodies[0], None);
+    assert!((translation + 0.5).abs() < 1e-3);
+    assert!(world.bodies[0].velocity.magnitude() < 1e-3);
+    assert_eq!(Some(LimitState::Lower), world.joints[0].active_limit());
+
+    // Bouncy limits send it back up.
+    let mut limits = JointLimits::new(-0.5, 0.5);
+    limits.restitution = 1.0;
+    let mut world = elevator(limits);
+    let mut highest: f64 = -0.5;
+    for _ in 0..60 {
+        step(&mut world, 1);
+        let translation = slider(&world).translation(&world.bodies[0], None);
+        if world.bodies[0].velocity.y > 0.0 {
+            highest = highest.max(translation);
+        }
+        assert!(translation > -0.5 - 1e-3);
+    }
+    assert!(highest > -0.1);
+
+    // Soft limits give in under the weight of the cart.
+    limits.restitution = 0.0;
+    limits.softness = Some(JointSpring::new(100.0, 20.0));
+    let mut world = elevator(limits);
+    step(&mut world, 240);
+    let translation = slider(&world).translation(&world.bodies[0], None);
+    assert!((translation + 0.6).abs() < 0.01);
+    assert_eq!(Some(LimitState::Lower), world.joints[0].active_limit());
+}
+
+#[test]
+fn breakable_joints() {
+    // The weld holds the weight of the beam, and the torque of its lever,
+    // minus what the damping of the body takes care of.
+    let mut world = cantilever(0.0, 0.0);
+    let joint = &world.joints[0];
+    assert!(
+        joint
+            .force()
+            .vector_sub(&Vector3::new(0.0, 10.0, 0.0))
+            .magnitude()
+            < 0.25
+    );
+    assert!(
+        joint
+            .torque()
+            .vector_sub(&Vector3::new(0.0, 0.0, 10.0))
+            .magnitude()
+            < 0.25
+    );
+    assert!(!joint.is_overloaded());
+
+    // Strong enough joints don't break.
+    world.joints[0].break_force = Some(15.0);
+    world.joints[0].break_torque = Some(15.0);
+    step(&mut world, 10);
+    assert!(world.joints[0].enabled);
+    assert!(world.joint_breaks.is_empty());
+
+    // Weaker ones break, letting the beam fall.
+    world.joints[0].break_torque = Some(5.0);
+    step(&mut world, 1);
+    assert!(!world.joints[0].enabled);
+    assert_eq!(1, world.joint_breaks.len());
+    assert_eq!(0, world.joint_breaks[0].joint);
+    assert!(world.joint_breaks[0].torque.magnitude() > 5.0);
+    step(&mut world, 10);
+    assert!(world.joint_breaks.is_empty());
+    assert!(world.bodies[0].position.y < -0.1);
+}
@@ -1,3 +1,207 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+#[cfg(feature = "gltf")]
+extern crate gltf;
+extern crate math;
+extern crate num_traits;
+#[cfg(feature = "gpu")]
+extern crate pollster;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "ron")]
+extern crate ron;
+extern crate serde;
+#[cfg(feature = "toml")]
+extern crate toml;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "gpu")]
+extern crate wgpu;
+extern crate wide;
+
+pub mod aabb;
+pub mod atmosphere;
+pub mod ball_joint;
+pub mod ballistics;
+pub mod broad_phase;
+pub mod builder;
+pub mod buoyancy;
+pub mod bvh;
+pub mod cloth;
+pub mod collider;
+pub mod constraint_graph;
+pub mod contact;
+pub mod debug_draw;
+pub mod decomposition;
+pub mod diagnostics;
+pub mod distance_joint;
+pub mod emitter;
+pub mod event;
+pub mod fixed_joint;
+pub mod fluid;
+pub mod force;
+pub mod gear_joint;
+pub mod gjk;
+#[cfg(feature = "gltf")]
+pub mod gltf_import;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod granular;
+pub mod gravity;
+pub mod handle;
+pub mod heightfield;
+pub mod hinge_joint;
+pub mod ik;
+pub mod island;
+pub mod joint;
+pub mod manifold;
+pub mod mass_properties;
+pub mod material;
+pub mod mesh_loader;
+pub mod narrow_phase;
+pub mod nbody;
+pub mod orbit;
+pub mod parallel;
+pub mod particle;
+pub mod particle_batch;
+pub mod particle_contact;
+pub mod particle_force;
+pub mod particle_link;
+pub mod particle_world;
+pub mod pbd;
+pub mod phust2d;
+pub mod pid;
+pub mod plane;
+pub mod prismatic_joint;
+pub mod pulley_joint;
+pub mod query;
+pub mod query_pipeline;
+pub mod rack_and_pinion_joint;
+pub mod ray;
+pub mod recording;
+pub mod rigid_body;
+pub mod scene;
+pub mod shape;
+pub mod soft_body;
+pub mod solver;
+pub mod spatial;
+pub mod spring_joint;
+pub mod stats;
+pub mod terrain;
+pub mod toi;
+pub mod trimesh;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod wind;
+pub mod world;
+
+#[cfg(test)]
+mod atmosphere_test;
+#[cfg(test)]
+mod ballistics_test;
+#[cfg(test)]
+mod broad_phase_test;
+#[cfg(test)]
+mod builder_test;
+#[cfg(test)]
+mod buoyancy_test;
+#[cfg(test)]
+mod bvh_test;
+#[cfg(test)]
+mod cloth_test;
+#[cfg(test)]
+mod constraint_graph_test;
+#[cfg(test)]
+mod debug_draw_test;
+#[cfg(test)]
+mod decomposition_test;
+#[cfg(test)]
+mod diagnostics_test;
+#[cfg(test)]
+mod emitter_test;
+#[cfg(all(test, feature = "fixed"))]
+mod fixed_test;
+#[cfg(test)]
+mod fluid_test;
+#[cfg(test)]
+mod force_test;
+#[cfg(test)]
+mod gjk_test;
+#[cfg(all(test, feature = "gltf"))]
+mod gltf_import_test;
+#[cfg(all(test, feature = "gpu"))]
+mod gpu_test;
+#[cfg(test)]
+mod granular_test;
+#[cfg(test)]
+mod gravity_test;
+#[cfg(test)]
+mod heightfield_test;
+#[cfg(test)]
+mod ik_test;
+#[cfg(test)]
+mod island_test;
+#[cfg(test)]
+mod joint_test;
+#[cfg(test)]
+mod manifold_test;
+#[cfg(test)]
+mod mass_properties_test;
+#[cfg(test)]
+mod material_test;
+#[cfg(test)]
+mod mesh_loader_test;
+#[cfg(test)]
+mod narrow_phase_test;
+#[cfg(test)]
+mod nbody_test;
+#[cfg(test)]
+mod orbit_test;
+#[cfg(test)]
+mod particle_batch_test;
+#[cfg(test)]
+mod particle_link_test;
+#[cfg(test)]
+mod particle_test;
+#[cfg(test)]
+mod particle_world_test;
+#[cfg(test)]
+mod pbd_test;
+#[cfg(test)]
+mod pid_test;
+#[cfg(test)]
+mod query_pipeline_test;
+#[cfg(test)]
+mod recording_test;
+#[cfg(test)]
+mod rigid_body_test;
+#[cfg(test)]
+mod scene_test;
+#[cfg(test)]
+mod shape_test;
+#[cfg(test)]
+mod soft_body_test;
+#[cfg(test)]
+mod solver_test;
+#[cfg(test)]
+mod spatial_test;
+#[cfg(test)]
+mod terrain_test;
+#[cfg(test)]
+mod toi_test;
+#[cfg(test)]
+mod trimesh_test;
+#[cfg(all(test, feature = "wasm"))]
+mod wasm_test;
+#[cfg(test)]
+mod wind_test;
+#[cfg(test)]
+mod world_test;
+
 #[cfg(test)]
 mod tests {
     #[test]
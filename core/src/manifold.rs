@@ -0,0 +1,293 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::contact::Contact;
+use crate::narrow_phase::reduce_manifold;
+use crate::rigid_body::RigidBody;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Maximum number of points kept in a contact manifold for every pair of colliders.
+pub const MAX_MANIFOLD_POINTS: usize = 4;
+
+/// Default distance a contact point can drift before being considered stale.
+pub const DEFAULT_CONTACT_BREAKING_THRESHOLD: f64 = 0.02;
+
+/// Contact point cached in a manifold across frames.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ManifoldPoint<F: num_traits::Float = f64> {
+    /// Contact as of the last update.
+    pub contact: Contact<F>,
+
+    /// Deepest point of the first body, in its local space.
+    pub local_point_one: Vector3<F>,
+
+    /// Deepest point of the second body in its local space,
+    /// or in world space for contacts with the scenery.
+    pub local_point_two: Vector3<F>,
+
+    /// Number of updates the point has survived.
+    pub lifetime: usize,
+
+    /// Normal impulse accumulated by the contact solver in the last frame,
+    /// used as the initial guess in the next one.
+    pub normal_impulse: F,
+
+    /// Friction impulses accumulated by the contact solver in the last frame,
+    /// along both tangent directions of the contact.
+    pub tangent_impulses: [F; 2],
+}
+
+impl<F: num_traits::Float> ManifoldPoint<F> {
+    /// Creates a new manifold point from a contact, anchoring it to both bodies.
+    pub fn new(contact: Contact<F>, bodies: &[RigidBody<F>]) -> Self {
+        let mut point = Self {
+            contact,
+            local_point_one: Vector3::origin(),
+            local_point_two: Vector3::origin(),
+            lifetime: 0,
+            normal_impulse: num_traits::zero(),
+            tangent_impulses: [num_traits::zero(); 2],
+        };
+        point.anchor(bodies);
+        point
+    }
+
+    /// Stores the deepest points of both bodies in their local spaces.
+    fn anchor(&mut self, bodies: &[RigidBody<F>]) {
+        let contact = &self.contact;
+        let half_depth = contact
+            .contact_normal
+            .scalar_mul(contact.penetration * math::real(0.5));
+        let point_one = contact.contact_point.vector_sub(&half_depth);
+        let point_two = contact.contact_point.vector_add(&half_depth);
+
+        self.local_point_one = bodies[contact.bodies.0].point_in_local_space(&point_one);
+        self.local_point_two = match contact.bodies.1 {
+            Some(body) => bodies[body].point_in_local_space(&point_two),
+            None => point_two,
+        };
+    }
+}
+
+/// Set of contact points between a pair of bodies, persisted across frames.
+///
+/// # Remarks
+/// Narrow phase routines generate contacts from scratch every frame. The manifold
+/// matches them with the points of the previous frame through their colliders and feature
+/// identifiers, so a point keeps its identity and its accumulated impulses while the bodies
+/// stay in contact. Points that aren't generated again are kept while the bodies don't
+/// drift apart, which completes the manifolds of routines generating a single contact.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ContactManifold<F: num_traits::Float = f64> {
+    /// Indices of the bodies in contact.
+    pub bodies: (usize, Option<usize>),
+
+    /// Contact points of the manifold.
+    pub points: Vec<ManifoldPoint<F>>,
+
+    /// Distance a point can drift, along or across the normal, before being removed.
+    pub breaking_threshold: F,
+}
+
+impl<F: num_traits::Float> ContactManifold<F> {
+    /// Creates a new empty manifold between the given bodies.
+    pub fn new(bodies: (usize, Option<usize>), breaking_threshold: F) -> Self {
+        Self {
+            bodies,
+            points: Vec::with_capacity(MAX_MANIFOLD_POINTS),
+            breaking_threshold,
+        }
+    }
+
+    /// Returns the number of points in the manifold.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns true if there are no points in the manifold.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Recomputes the contact points from the current positions of the bodies,
+    /// and removes the ones that drifted further than the breaking threshold.
+    pub fn refresh(&mut self, bodies: &[RigidBody<F>]) {
+        let threshold = self.breaking_threshold;
+        self.points.retain(|point| {
+            let (point_one, point_two) = world_points(point, bodies);
+            let offset = point_one.vector_sub(&point_two);
+            let normal = &point.contact.contact_normal;
+            let separation = offset.dot_product(normal);
+            let drift = offset.vector_sub(&normal.scalar_mul(separation));
+            separation <= threshold && drift.squared_magnitude() <= threshold * threshold
+        });
+
+        for point in self.points.iter_mut() {
+            let (point_one, point_two) = world_points(point, bodies);
+            point.contact.contact_point =
+                point_one.vector_add(&point_two).scalar_mul(math::real(0.5));
+            point.contact.penetration = point_two
+                .vector_sub(&point_one)
+                .dot_product(&point.contact.contact_normal);
+        }
+    }
+
+    /// Updates the manifold with the contacts generated this frame between its bodies.
+    ///
+    /// # Remarks
+    /// Existing points are refreshed first, then each new contact replaces the point of the
+    /// same colliders with the same feature identifier, or is added as a new point. When a
+    /// pair of colliders has more than `MAX_MANIFOLD_POINTS` points, the deepest ones
+    /// spanning the largest area are kept.
+    pub fn update(&mut self, contacts: &[Contact<F>], bodies: &[RigidBody<F>]) {
+        self.refresh(bodies);
+        for point in self.points.iter_mut() {
+            point.lifetime += 1;
+        }
+
+        for contact in contacts.iter() {
+            let existing = self.points.iter_mut().find(|point| {
+                point.contact.colliders == contact.colliders
+                    && point.contact.feature == contact.feature
+            });
+            match existing {
+                Some(point) => {
+                    point.contact = *contact;
+                    point.anchor(bodies);
+                }
+                None => self.points.push(ManifoldPoint::new(*contact, bodies)),
+            }
+        }
+
+        if self.points.len() > MAX_MANIFOLD_POINTS {
+            self.reduce();
+        }
+    }
+
+    /// Keeps the deepest points spanning the largest area of every pair of colliders with
+    /// more than `MAX_MANIFOLD_POINTS` points, in their order in the manifold.
+    fn reduce(&mut self) {
+        let mut pairs: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+        for (index, point) in self.points.iter().enumerate() {
+            pairs
+                .entry(point.contact.colliders)
+                .or_default()
+                .push(index);
+        }
+
+        let mut kept = Vec::with_capacity(self.points.len());
+        for indices in pairs.values() {
+            if indices.len() <= MAX_MANIFOLD_POINTS {
+                kept.extend_from_slice(indices);
+                continue;
+            }
+            let point = |index: &usize| &self.points[*index].contact;
+            let positions: Vec<Vector3<F>> = indices
+                .iter()
+                .map(|index| point(index).contact_point)
+                .collect();
+            let depths: Vec<F> = indices
+                .iter()
+                .map(|index| point(index).penetration)
+                .collect();
+            let normal = point(&indices[0]).contact_normal;
+            let reduced = reduce_manifold(&positions, &depths, &normal);
+            kept.extend(reduced.iter().map(|index| indices[*index]));
+        }
+        kept.sort_unstable();
+        self.points = kept.iter().map(|index| self.points[*index]).collect();
+    }
+}
+
+/// Returns the current world positions of the deepest points of both bodies.
+pub(crate) fn world_points<F: num_traits::Float>(
+    point: &ManifoldPoint<F>,
+    bodies: &[RigidBody<F>],
+) -> (Vector3<F>, Vector3<F>) {
+    let point_one = bodies[point.contact.bodies.0].point_in_world_space(&point.local_point_one);
+    let point_two = match point.contact.bodies.1 {
+        Some(body) => bodies[body].point_in_world_space(&point.local_point_two),
+        None => point.local_point_two,
+    };
+    (point_one, point_two)
+}
+
+/// Keeps the contact manifolds of every pair of bodies in contact.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ManifoldCache<F: num_traits::Float = f64> {
+    /// Distance a point can drift before being removed from its manifold.
+    pub breaking_threshold: F,
+
+    manifolds: BTreeMap<(usize, Option<usize>), ContactManifold<F>>,
+}
+
+impl<F: num_traits::Float> Default for ManifoldCache<F> {
+    fn default() -> Self {
+        Self::new(math::real(DEFAULT_CONTACT_BREAKING_THRESHOLD))
+    }
+}
+
+impl<F: num_traits::Float> ManifoldCache<F> {
+    /// Creates a new empty cache with the given breaking threshold.
+    pub fn new(breaking_threshold: F) -> Self {
+        Self {
+            breaking_threshold,
+            manifolds: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of manifolds in the cache.
+    pub fn len(&self) -> usize {
+        self.manifolds.len()
+    }
+
+    /// Returns true if there are no manifolds in the cache.
+    pub fn is_empty(&self) -> bool {
+        self.manifolds.is_empty()
+    }
+
+    /// Returns the manifold between the given bodies, if any.
+    pub fn get(&self, bodies: (usize, Option<usize>)) -> Option<&ContactManifold<F>> {
+        self.manifolds.get(&bodies)
+    }
+
+    /// Returns an iterator over the manifolds, sorted by pair of bodies.
+    pub fn iter(&self) -> impl Iterator<Item = &ContactManifold<F>> {
+        self.manifolds.values()
+    }
+
+    /// Returns a mutable iterator over the manifolds, sorted by pair of bodies.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ContactManifold<F>> {
+        self.manifolds.values_mut()
+    }
+
+    /// Removes all the manifolds.
+    pub fn clear(&mut self) {
+        self.manifolds.clear();
+    }
+
+    /// Updates the manifolds with the contacts generated this frame.
+    ///
+    /// # Remarks
+    /// Pairs of bodies without contacts this frame have separated, so their manifolds are removed.
+    pub fn update(&mut self, contacts: &[Contact<F>], bodies: &[RigidBody<F>]) {
+        let mut pairs: BTreeMap<(usize, Option<usize>), Vec<Contact<F>>> = BTreeMap::new();
+        for contact in contacts.iter() {
+            pairs.entry(contact.bodies).or_default().push(*contact);
+        }
+
+        self.manifolds.retain(|pair, _| pairs.contains_key(pair));
+        for (pair, contacts) in pairs.iter() {
+            let threshold = self.breaking_threshold;
+            self.manifolds
+                .entry(*pair)
+                .or_insert_with(|| ContactManifold::new(*pair, threshold))
+                .update(contacts, bodies);
+        }
+    }
+}
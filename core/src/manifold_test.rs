@@ -0,0 +1,185 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::contact::Contact;
+use crate::manifold::*;
+use crate::narrow_phase::{box_and_box, box_and_half_space, CollisionData};
+use crate::plane::Plane;
+use crate::rigid_body::RigidBody;
+use crate::shape::Cuboid;
+use math::Vector3;
+
+fn block(position: Vector3<f64>) -> RigidBody {
+    let cuboid = Cuboid::new(Vector3::new(1.0, 1.0, 1.0));
+    let mut body = RigidBody::new(position, 1.0, &cuboid.inertia_tensor(1.0));
+    body.calculate_derived_data();
+    body
+}
+
+fn generate(bodies: &[RigidBody]) -> Vec<Contact> {
+    let mut colliders: Vec<Collider<Cuboid>> = (0..bodies.len())
+        .map(|body| Collider::new(body, Cuboid::new(Vector3::new(1.0, 1.0, 1.0))))
+        .collect();
+    for collider in colliders.iter_mut() {
+        collider.calculate_internals(bodies);
+    }
+
+    let mut data = CollisionData::new(20);
+    let ground = Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0);
+    box_and_half_space(&colliders[0], &ground, &mut data);
+    box_and_box(&colliders[1], &colliders[0], &mut data);
+    data.contacts
+}
+
+#[test]
+fn persistent_points() {
+    let mut bodies = vec![
+        block(Vector3::new(0.0, 0.99, 0.0)),
+        block(Vector3::new(0.5, 2.97, 0.0)),
+    ];
+    let mut cache = ManifoldCache::default();
+    cache.update(&generate(&bodies), &bodies);
+    assert_eq!(2, cache.len());
+    let ground = cache.get((0, None)).unwrap();
+    assert_eq!(4, ground.len());
+    assert!(ground.points.iter().all(|point| point.lifetime == 0));
+    let stacked = cache.get((1, Some(0))).unwrap();
+    assert_eq!(4, stacked.len());
+
+    // The same contacts are matched with the points of the previous frame.
+    bodies[1].position.x = 0.505;
+    bodies[1].calculate_derived_data();
+    cache.update(&generate(&bodies), &bodies);
+    assert_eq!(4, cache.get((0, None)).unwrap().len());
+    let stacked = cache.get((1, Some(0))).unwrap();
+    assert_eq!(4, stacked.len());
+    assert!(stacked.points.iter().all(|point| point.lifetime == 1));
+
+    // Bodies that stop touching lose their manifold.
+    bodies[1].position.y = 4.0;
+    bodies[1].calculate_derived_data();
+    cache.update(&generate(&bodies), &bodies);
+    assert_eq!(1, cache.len());
+    assert_eq!(None, cache.get((1, Some(0))));
+    assert!(cache
+        .get((0, None))
+        .unwrap()
+        .points
+        .iter()
+        .all(|point| point.lifetime == 2));
+}
+
+#[test]
+fn stale_points() {
+    let mut bodies = vec![block(Vector3::new(0.0, 0.0, 0.0))];
+    let contact = |x: f64, feature: u32| {
+        let mut contact = Contact::new(
+            (0, None),
+            Vector3::new(x, -1.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            0.1,
+        );
+        contact.feature = feature;
+        contact
+    };
+
+    // Points that aren't generated again are kept while they stay in place.
+    let mut manifold = ContactManifold::new((0, None), 0.02);
+    manifold.update(&[contact(-1.0, 1)], &bodies);
+    manifold.update(&[contact(1.0, 2)], &bodies);
+    assert_eq!(2, manifold.len());
+    assert!((manifold.points[0].contact.penetration - 0.1).abs() < 1e-12);
+
+    // Rising slightly keeps them, with less penetration.
+    bodies[0].position.y = 0.05;
+    bodies[0].calculate_derived_data();
+    manifold.refresh(&bodies);
+    assert_eq!(2, manifold.len());
+    assert!((manifold.points[0].contact.penetration - 0.05).abs() < 1e-12);
+
+    // Sliding or separating further than the threshold removes them.
+    bodies[0].position.x = 0.05;
+    bodies[0].calculate_derived_data();
+    manifold.update(&[contact(1.05, 2)], &bodies);
+    assert_eq!(1, manifold.len());
+    assert_eq!(2, manifold.points[0].contact.feature);
+    bodies[0].position.y = 0.2;
+    bodies[0].calculate_derived_data();
+    manifold.refresh(&bodies);
+    assert!(manifold.is_empty());
+}
+
+#[test]
+fn reduced_points() {
+    let bodies = vec![block(Vector3::new(0.0, 0.0, 0.0))];
+
+    // An octagon of contacts is reduced to four points, keeping the deepest one.
+    let contacts: Vec<Contact> = (0..8)
+        .map(|index| {
+            let angle = index as f64 * std::f64::consts::FRAC_PI_4;
+            let mut contact = Contact::new(
+                (0, None),
+                Vector3::new(angle.cos(), -1.0, angle.sin()),
+                Vector3::new(0.0, 1.0, 0.0),
+                if index == 5 { 0.2 } else { 0.1 },
+            );
+            contact.feature = index;
+            contact
+        })
+        .collect();
+    let mut manifold = ContactManifold::new((0, None), 0.02);
+    manifold.update(&contacts, &bodies);
+    assert_eq!(MAX_MANIFOLD_POINTS, manifold.len());
+    assert!(manifold
+        .points
+        .iter()
+        .any(|point| point.contact.feature == 5));
+}
+
+#[test]
+fn points_per_collider() {
+    let bodies = vec![block(Vector3::new(0.0, 0.0, 0.0))];
+    let contact = |x: f64, z: f64, collider: usize, feature: u32| {
+        let mut contact = Contact::new(
+            (0, None),
+            Vector3::new(x, -1.0, z),
+            Vector3::new(0.0, 1.0, 0.0),
+            0.1,
+        );
+        contact.colliders = (collider, 0);
+        contact.feature = feature;
+        contact
+    };
+
+    // Colliders of the same body generating the same feature keep a point each.
+    let mut manifold = ContactManifold::new((0, None), 0.02);
+    let pair = [contact(-1.0, 0.0, 0, 1), contact(1.0, 0.0, 1, 1)];
+    manifold.update(&pair, &bodies);
+    manifold.update(&pair, &bodies);
+    assert_eq!(2, manifold.len());
+    assert!(manifold.points.iter().all(|point| point.lifetime == 1));
+
+    // Points are reduced for every pair of colliders on its own.
+    let mut contacts: Vec<Contact> = (0..8)
+        .map(|index| {
+            let angle = index as f64 * std::f64::consts::FRAC_PI_4;
+            contact(angle.cos(), angle.sin(), 0, index)
+        })
+        .collect();
+    contacts.push(contact(3.0, 0.0, 1, 0));
+    let mut manifold = ContactManifold::new((0, None), 0.02);
+    manifold.update(&contacts, &bodies);
+    assert_eq!(MAX_MANIFOLD_POINTS + 1, manifold.len());
+    assert_eq!(
+        1,
+        manifold
+            .points
+            .iter()
+            .filter(|point| point.contact.colliders == (1, 0))
+            .count()
+    );
+}
@@ -0,0 +1,124 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::shape::Shape;
+use math::{Matrix3, Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Mass, center of mass and inertia tensor of an object, like a shape or a rigid body
+/// made of several colliders.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MassProperties<F: num_traits::Float = f64> {
+    /// Mass of the object.
+    pub mass: F,
+
+    /// Center of mass of the object, in its local space.
+    pub center_of_mass: Vector3<F>,
+
+    /// Inertia tensor of the object relative to its center of mass, along the axes of
+    /// its local space.
+    pub inertia_tensor: Matrix3<F>,
+}
+
+impl<F: num_traits::Float> Default for MassProperties<F> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<F: num_traits::Float> MassProperties<F> {
+    /// Returns the mass properties of an object without mass.
+    pub fn zero() -> Self {
+        Self {
+            mass: num_traits::zero(),
+            center_of_mass: Vector3::origin(),
+            inertia_tensor: Matrix3::zero(),
+        }
+    }
+
+    /// Returns the mass properties of a solid shape with the given uniform density.
+    pub fn from_shape(shape: &Shape<F>, density: F) -> Self {
+        let mass = shape.volume() * density;
+        Self {
+            mass,
+            center_of_mass: shape.center_of_mass(),
+            inertia_tensor: shape.inertia_tensor(mass),
+        }
+    }
+
+    /// Returns the mass properties of the object placed with the given transform, in the
+    /// space the transform places it in.
+    pub fn transformed(&self, transform: &Matrix4<F>) -> Self {
+        let rotation = transform.rotation();
+        Self {
+            mass: self.mass,
+            center_of_mass: transform.transform(&self.center_of_mass),
+            inertia_tensor: rotation
+                .matrix_mul(&self.inertia_tensor)
+                .matrix_mul(&rotation.transpose()),
+        }
+    }
+
+    /// Returns the mass properties of the object with the given mass instead, keeping its
+    /// center of mass and scaling its inertia tensor to match.
+    pub fn with_mass(&self, mass: F) -> Self {
+        let scale = if self.mass > F::zero() {
+            mass / self.mass
+        } else {
+            F::zero()
+        };
+        Self {
+            mass,
+            center_of_mass: self.center_of_mass,
+            inertia_tensor: self.inertia_tensor.scalar_mul(scale),
+        }
+    }
+
+    /// Returns the mass properties of the object made of this one and the given one, both
+    /// in the same space.
+    ///
+    /// # Remarks
+    /// The inertia tensors of both are moved to the common center of mass with the
+    /// parallel axis theorem.
+    pub fn merge(&self, other: &MassProperties<F>) -> Self {
+        let mass = self.mass + other.mass;
+        if mass <= F::zero() {
+            return Self::zero();
+        }
+
+        let center = self
+            .center_of_mass
+            .scalar_mul(self.mass)
+            .vector_add(&other.center_of_mass.scalar_mul(other.mass))
+            .scalar_div(mass);
+        let inertia = [self, other].iter().fold(Matrix3::zero(), |inertia, part| {
+            let offset = part.center_of_mass.vector_sub(&center);
+            inertia
+                .matrix_add(&part.inertia_tensor)
+                .matrix_add(&parallel_axis(&offset, part.mass))
+        });
+        Self {
+            mass,
+            center_of_mass: center,
+            inertia_tensor: inertia,
+        }
+    }
+}
+
+/// Returns the inertia tensor a point mass adds around an origin it's offset from,
+/// which moves inertia tensors away from the center of mass with the parallel axis theorem.
+pub(crate) fn parallel_axis<F: num_traits::Float>(offset: &Vector3<F>, mass: F) -> Matrix3<F> {
+    let d = offset;
+    Matrix3::inertia_tensor(
+        d.y * d.y + d.z * d.z,
+        d.x * d.x + d.z * d.z,
+        d.x * d.x + d.y * d.y,
+        d.x * d.y,
+        d.x * d.z,
+        d.y * d.z,
+    )
+    .scalar_mul(mass)
+}
@@ -0,0 +1,58 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::mass_properties::*;
+use crate::shape::{Cuboid, Shape};
+use math::{Matrix3, Matrix4, Quaternion, Vector3};
+
+fn assert_matrix_eq(expected: &Matrix3<f64>, actual: &Matrix3<f64>) {
+    for (expected, actual) in expected.data.iter().zip(actual.data.iter()) {
+        assert!(
+            (expected - actual).abs() < 1e-9,
+            "{:?} != {:?}",
+            expected,
+            actual
+        );
+    }
+}
+
+#[test]
+fn merge() {
+    // Two cubes side by side weigh and spin like the box they make up.
+    let cube = Shape::Cuboid(Cuboid::new(Vector3::new(0.5, 0.5, 0.5)));
+    let place = |x: f64| {
+        Matrix4::from_orientation_and_position(&Quaternion::identity(), &Vector3::new(x, 1.0, 0.0))
+    };
+    let merged = MassProperties::from_shape(&cube, 2.0)
+        .transformed(&place(-0.5))
+        .merge(&MassProperties::from_shape(&cube, 2.0).transformed(&place(0.5)));
+    let block = Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 0.5, 0.5)));
+    let expected = MassProperties::from_shape(&block, 2.0).transformed(&place(0.0));
+    assert_eq!(4.0, merged.mass);
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), merged.center_of_mass);
+    assert_matrix_eq(&expected.inertia_tensor, &merged.inertia_tensor);
+    assert_eq!(merged, merged.merge(&MassProperties::zero()));
+
+    // Rotating the box a quarter turn around Z swaps its inertia along X and Y.
+    let turn = Matrix4::from_orientation_and_position(
+        &Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2),
+        &Vector3::origin(),
+    );
+    let turned = merged.transformed(&turn);
+    let inertia = &merged.inertia_tensor.data;
+    assert_matrix_eq(
+        &Matrix3::diagonal(inertia[4], inertia[0], inertia[8]),
+        &turned.inertia_tensor,
+    );
+
+    // Overriding the mass scales the inertia along with it.
+    let lighter = merged.with_mass(1.0);
+    assert_eq!(merged.center_of_mass, lighter.center_of_mass);
+    assert_matrix_eq(
+        &merged.inertia_tensor.scalar_mul(0.25),
+        &lighter.inertia_tensor,
+    );
+}
@@ -0,0 +1,115 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use serde::{Deserialize, Serialize};
+
+/// Default dynamic friction coefficient of materials.
+pub const DEFAULT_FRICTION: f64 = 0.5;
+
+/// Default static friction coefficient of materials.
+pub const DEFAULT_STATIC_FRICTION: f64 = 0.6;
+
+/// Default restitution coefficient of materials.
+pub const DEFAULT_RESTITUTION: f64 = 0.0;
+
+/// Default density of materials, in mass per unit of volume.
+pub const DEFAULT_DENSITY: f64 = 1.0;
+
+/// Rule used to combine the coefficients of two materials in contact.
+///
+/// # Remarks
+/// When both materials use different rules, the one declared last wins,
+/// so the precedence is `Average`, `Min`, `Multiply` and `Max`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+pub enum CombineRule {
+    /// Uses the mean of both coefficients.
+    Average,
+
+    /// Uses the smallest coefficient.
+    Min,
+
+    /// Uses the product of both coefficients.
+    Multiply,
+
+    /// Uses the largest coefficient.
+    Max,
+}
+
+impl CombineRule {
+    /// Combines both coefficients with the rule.
+    pub fn combine<F: num_traits::Float>(self, one: F, two: F) -> F {
+        match self {
+            CombineRule::Average => (one + two) * math::real(0.5),
+            CombineRule::Min => one.min(two),
+            CombineRule::Multiply => one * two,
+            CombineRule::Max => one.max(two),
+        }
+    }
+}
+
+/// Surface and bulk properties of the matter a collider is made of.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PhysicsMaterial<F: num_traits::Float = f64> {
+    /// Restitution coefficient, from `0` (no bounce) to `1` (perfectly elastic).
+    pub restitution: F,
+
+    /// Dynamic friction coefficient, used while sliding.
+    pub friction: F,
+
+    /// Static friction coefficient, used to start sliding.
+    pub static_friction: F,
+
+    /// Mass per unit of volume.
+    pub density: F,
+
+    /// Rule used to combine the friction coefficients with the ones of other materials.
+    pub friction_combine: CombineRule,
+
+    /// Rule used to combine the restitution coefficient with the one of other materials.
+    pub restitution_combine: CombineRule,
+}
+
+impl<F: num_traits::Float> Default for PhysicsMaterial<F> {
+    fn default() -> Self {
+        Self::new(
+            math::real(DEFAULT_RESTITUTION),
+            math::real(DEFAULT_FRICTION),
+            math::real(DEFAULT_DENSITY),
+        )
+    }
+}
+
+impl<F: num_traits::Float> PhysicsMaterial<F> {
+    /// Creates a new material with the given coefficients and density,
+    /// combined by averaging them.
+    ///
+    /// # Remarks
+    /// The static friction coefficient is set to the dynamic one, scaled by the same ratio
+    /// as the default coefficients.
+    pub fn new(restitution: F, friction: F, density: F) -> Self {
+        let ratio = math::real::<F>(DEFAULT_STATIC_FRICTION) / math::real(DEFAULT_FRICTION);
+        Self {
+            restitution,
+            friction,
+            static_friction: friction * ratio,
+            density,
+            friction_combine: CombineRule::Average,
+            restitution_combine: CombineRule::Average,
+        }
+    }
+
+    /// Returns the dynamic friction, static friction and restitution coefficients
+    /// of a contact between both materials.
+    pub fn combine(&self, other: &PhysicsMaterial<F>) -> (F, F, F) {
+        let friction_combine = self.friction_combine.max(other.friction_combine);
+        let restitution_combine = self.restitution_combine.max(other.restitution_combine);
+        (
+            friction_combine.combine(self.friction, other.friction),
+            friction_combine.combine(self.static_friction, other.static_friction),
+            restitution_combine.combine(self.restitution, other.restitution),
+        )
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::material::*;
+
+#[test]
+fn combine_rules() {
+    assert_eq!(0.5, CombineRule::Average.combine(0.25, 0.75));
+    assert_eq!(0.25, CombineRule::Min.combine(0.25, 0.75));
+    assert_eq!(0.1875, CombineRule::Multiply.combine(0.25, 0.75));
+    assert_eq!(0.75, CombineRule::Max.combine(0.25, 0.75));
+}
+
+#[test]
+fn combine_materials() {
+    let ice = PhysicsMaterial::<f64> {
+        friction_combine: CombineRule::Min,
+        ..PhysicsMaterial::new(0.0, 0.05, 0.9)
+    };
+    let rubber = PhysicsMaterial::<f64> {
+        restitution_combine: CombineRule::Max,
+        ..PhysicsMaterial::new(0.8, 1.0, 1.1)
+    };
+    let concrete = PhysicsMaterial::new(0.1, 0.7, 2.4);
+    assert_eq!(0.6 * 0.7 / 0.5, concrete.static_friction);
+
+    // Rules with higher precedence win, no matter the order of the materials.
+    let (friction, static_friction, restitution) = rubber.combine(&ice);
+    assert_eq!(0.05, friction);
+    assert_eq!(ice.static_friction, static_friction);
+    assert_eq!(0.8, restitution);
+    assert_eq!(
+        (friction, static_friction, restitution),
+        ice.combine(&rubber)
+    );
+
+    let (friction, static_friction, restitution) = rubber.combine(&concrete);
+    assert_eq!(0.85, friction);
+    assert_eq!(
+        (rubber.static_friction + concrete.static_friction) * 0.5,
+        static_friction
+    );
+    assert_eq!(0.8, restitution);
+}
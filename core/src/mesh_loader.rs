@@ -0,0 +1,325 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::trimesh::TriMesh;
+use math::Vector3;
+use std::path::Path;
+
+/// Error raised while loading a mesh file.
+#[derive(Debug)]
+pub enum MeshError {
+    /// A file couldn't be read.
+    Io(std::io::Error),
+
+    /// The file format isn't supported, described by the message.
+    Format(String),
+
+    /// The file is malformed, described by the message.
+    Syntax(String),
+
+    /// The file has no triangles, or they refer to missing vertices.
+    Mesh,
+}
+
+impl std::fmt::Display for MeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshError::Io(error) => write!(f, "I/O error: {}", error),
+            MeshError::Format(message) => write!(f, "unsupported format: {}", message),
+            MeshError::Syntax(message) => write!(f, "malformed file: {}", message),
+            MeshError::Mesh => write!(f, "invalid mesh"),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+impl From<std::io::Error> for MeshError {
+    fn from(error: std::io::Error) -> Self {
+        MeshError::Io(error)
+    }
+}
+
+/// Loads the triangle mesh in the OBJ or PLY file at the given path, chosen by its extension,
+/// and builds its hierarchy.
+pub fn load_mesh_file<F: num_traits::Float>(path: &Path) -> Result<TriMesh<F>, MeshError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("obj") => load_obj(&std::fs::read_to_string(path)?),
+        Some("ply") => load_ply(&std::fs::read(path)?),
+        _ => Err(MeshError::Format(format!("{}", path.display()))),
+    }
+}
+
+/// Loads a triangle mesh from the contents of a Wavefront OBJ file, and builds its hierarchy.
+///
+/// # Remarks
+/// Only the vertex positions and the faces are read, and faces with more than three vertices
+/// are split into fans of triangles. Every object and group of the file goes into the same
+/// mesh, and negative indices count back from the last vertex read, as usual.
+pub fn load_obj<F: num_traits::Float>(data: &str) -> Result<TriMesh<F>, MeshError> {
+    let mut vertices = Vec::new();
+    let mut triangles = Vec::new();
+    for (number, line) in data.lines().enumerate() {
+        let error = |message: &str| MeshError::Syntax(format!("line {}: {}", number + 1, message));
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let mut coordinate = || -> Result<F, MeshError> {
+                    let value = tokens.next().ok_or_else(|| error("missing coordinate"))?;
+                    let value: f64 = value.parse().map_err(|_| error("invalid coordinate"))?;
+                    Ok(math::real(value))
+                };
+                vertices.push(Vector3::new(coordinate()?, coordinate()?, coordinate()?));
+            }
+            Some("f") => {
+                let face = tokens
+                    .map(|token| {
+                        // Only the position of `position/texture/normal` matters.
+                        let position = token.split('/').next().unwrap_or_default();
+                        let index: isize = position.parse().map_err(|_| error("invalid index"))?;
+                        let count = vertices.len() as isize;
+                        let index = if index < 0 { count + index } else { index - 1 };
+                        if index < 0 || index >= count {
+                            return Err(error("index out of bounds"));
+                        }
+                        Ok(index as usize)
+                    })
+                    .collect::<Result<Vec<usize>, MeshError>>()?;
+                if face.len() < 3 {
+                    return Err(error("faces need at least three vertices"));
+                }
+                for pair in face[1..].windows(2) {
+                    triangles.push([face[0], pair[0], pair[1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    TriMesh::new(vertices, triangles).ok_or(MeshError::Mesh)
+}
+
+/// Encoding of the data of a PLY file.
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum PlyEncoding {
+    Ascii,
+    LittleEndian,
+    BigEndian,
+}
+
+/// Property of an element of a PLY file, either a scalar or a list, with the types of its
+/// values given by their size in bytes, and whether they're floating point or signed.
+#[derive(Clone, PartialEq, Debug)]
+struct PlyProperty {
+    name: String,
+    count: Option<PlyType>,
+    value: PlyType,
+}
+
+/// Type of a value of a PLY file.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct PlyType {
+    size: usize,
+    float: bool,
+    signed: bool,
+}
+
+/// Element of a PLY file, repeated `count` times.
+#[derive(Clone, PartialEq, Debug)]
+struct PlyElement {
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>,
+}
+
+/// Loads a triangle mesh from the contents of a PLY file, in ASCII or binary, and builds
+/// its hierarchy.
+///
+/// # Remarks
+/// The `x`, `y` and `z` properties of the `vertex` elements are the positions, and the
+/// `vertex_indices` (or `vertex_index`) list of the `face` elements the faces. Faces with
+/// more than three vertices are split into fans of triangles, and other elements and
+/// properties are skipped.
+pub fn load_ply<F: num_traits::Float>(data: &[u8]) -> Result<TriMesh<F>, MeshError> {
+    let syntax = |message: &str| MeshError::Syntax(message.to_owned());
+    let end = b"end_header";
+    let header_end = data
+        .windows(end.len())
+        .position(|window| window == end)
+        .ok_or_else(|| syntax("missing end of header"))?;
+    let header = std::str::from_utf8(&data[..header_end]).map_err(|_| syntax("invalid header"))?;
+    let mut body = header_end + end.len();
+    if data.get(body) == Some(&b'\r') {
+        body += 1;
+    }
+    body += 1;
+
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err(MeshError::Format("not a PLY file".to_owned()));
+    }
+    let mut encoding = None;
+    let mut elements: Vec<PlyElement> = Vec::new();
+    for line in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", format, _] => {
+                encoding = Some(match *format {
+                    "ascii" => PlyEncoding::Ascii,
+                    "binary_little_endian" => PlyEncoding::LittleEndian,
+                    "binary_big_endian" => PlyEncoding::BigEndian,
+                    _ => return Err(MeshError::Format(format!("PLY encoding {}", format))),
+                });
+            }
+            ["element", name, count] => elements.push(PlyElement {
+                name: (*name).to_owned(),
+                count: count.parse().map_err(|_| syntax("invalid element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, value, name] => elements
+                .last_mut()
+                .ok_or_else(|| syntax("property outside of an element"))?
+                .properties
+                .push(PlyProperty {
+                    name: (*name).to_owned(),
+                    count: Some(ply_type(count)?),
+                    value: ply_type(value)?,
+                }),
+            ["property", value, name] => elements
+                .last_mut()
+                .ok_or_else(|| syntax("property outside of an element"))?
+                .properties
+                .push(PlyProperty {
+                    name: (*name).to_owned(),
+                    count: None,
+                    value: ply_type(value)?,
+                }),
+            _ => {}
+        }
+    }
+    let encoding = encoding.ok_or_else(|| syntax("missing format"))?;
+
+    let mut reader = PlyReader {
+        encoding,
+        data: data.get(body..).unwrap_or_default(),
+        tokens: None,
+    };
+    let mut vertices = Vec::new();
+    let mut triangles = Vec::new();
+    for element in elements.iter() {
+        let position = |name: &str| element.properties.iter().position(|p| p.name == name);
+        let axes = [position("x"), position("y"), position("z")];
+        let indices = position("vertex_indices").or_else(|| position("vertex_index"));
+        for _ in 0..element.count {
+            let mut coordinates = [0.0; 3];
+            for (index, property) in element.properties.iter().enumerate() {
+                let values = match property.count {
+                    Some(count) => {
+                        let count = reader.read(count)? as usize;
+                        (0..count)
+                            .map(|_| reader.read(property.value))
+                            .collect::<Result<Vec<f64>, MeshError>>()?
+                    }
+                    None => vec![reader.read(property.value)?],
+                };
+                if element.name == "vertex" {
+                    if let Some(axis) = axes.iter().position(|axis| *axis == Some(index)) {
+                        coordinates[axis] = values[0];
+                    }
+                } else if element.name == "face" && Some(index) == indices {
+                    if values.len() < 3 {
+                        return Err(syntax("faces need at least three vertices"));
+                    }
+                    if values.iter().any(|index| *index < 0.0) {
+                        return Err(syntax("negative index"));
+                    }
+                    for pair in values[1..].windows(2) {
+                        triangles.push([values[0] as usize, pair[0] as usize, pair[1] as usize]);
+                    }
+                }
+            }
+            if element.name == "vertex" {
+                let [x, y, z] = coordinates;
+                vertices.push(Vector3::new(math::real(x), math::real(y), math::real(z)));
+            }
+        }
+    }
+    TriMesh::new(vertices, triangles).ok_or(MeshError::Mesh)
+}
+
+/// Returns the PLY type with the given name.
+fn ply_type(name: &str) -> Result<PlyType, MeshError> {
+    let (size, float, signed) = match name {
+        "char" | "int8" => (1, false, true),
+        "uchar" | "uint8" => (1, false, false),
+        "short" | "int16" => (2, false, true),
+        "ushort" | "uint16" => (2, false, false),
+        "int" | "int32" => (4, false, true),
+        "uint" | "uint32" => (4, false, false),
+        "float" | "float32" => (4, true, true),
+        "double" | "float64" => (8, true, true),
+        _ => return Err(MeshError::Syntax(format!("unknown type {}", name))),
+    };
+    Ok(PlyType {
+        size,
+        float,
+        signed,
+    })
+}
+
+/// Reads the values of the body of a PLY file, one at a time.
+struct PlyReader<'a> {
+    encoding: PlyEncoding,
+    data: &'a [u8],
+    tokens: Option<std::str::SplitAsciiWhitespace<'a>>,
+}
+
+impl<'a> PlyReader<'a> {
+    /// Reads the next value, of the given type.
+    fn read(&mut self, value: PlyType) -> Result<f64, MeshError> {
+        let truncated = || MeshError::Syntax("truncated data".to_owned());
+        if self.encoding == PlyEncoding::Ascii {
+            if self.tokens.is_none() {
+                let text = std::str::from_utf8(self.data)
+                    .map_err(|_| MeshError::Syntax("invalid data".to_owned()))?;
+                self.tokens = Some(text.split_ascii_whitespace());
+            }
+            let token = self
+                .tokens
+                .as_mut()
+                .and_then(|tokens| tokens.next())
+                .ok_or_else(truncated)?;
+            return token
+                .parse()
+                .map_err(|_| MeshError::Syntax(format!("invalid value {}", token)));
+        }
+
+        if self.data.len() < value.size {
+            return Err(truncated());
+        }
+        let (bytes, rest) = self.data.split_at(value.size);
+        self.data = rest;
+        let mut buffer = [0u8; 8];
+        buffer[..value.size].copy_from_slice(bytes);
+        if self.encoding == PlyEncoding::BigEndian {
+            buffer[..value.size].reverse();
+        }
+        let [b0, b1, b2, b3, ..] = buffer;
+        Ok(match (value.size, value.float, value.signed) {
+            (8, _, _) => f64::from_le_bytes(buffer),
+            (4, true, _) => f64::from(f32::from_le_bytes([b0, b1, b2, b3])),
+            (4, false, true) => f64::from(i32::from_le_bytes([b0, b1, b2, b3])),
+            (4, false, false) => f64::from(u32::from_le_bytes([b0, b1, b2, b3])),
+            (2, _, true) => f64::from(i16::from_le_bytes([b0, b1])),
+            (2, _, false) => f64::from(u16::from_le_bytes([b0, b1])),
+            (_, _, true) => f64::from(b0 as i8),
+            (_, _, false) => f64::from(b0),
+        })
+    }
+}
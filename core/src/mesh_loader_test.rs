@@ -0,0 +1,113 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::mesh_loader::*;
+use crate::trimesh::TriMesh;
+use math::Vector3;
+
+/// Header of a PLY file with a square made of four vertices, with colors, and one face.
+const SQUARE_HEADER: &str = "ply
+format {} 1.0
+comment made by hand
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+fn square_header(format: &str) -> Vec<u8> {
+    SQUARE_HEADER.replace("{}", format).into_bytes()
+}
+
+fn assert_square(mesh: &TriMesh) {
+    assert_eq!(4, mesh.vertices().len());
+    assert_eq!(Vector3::new(1.0, 0.0, 1.0), mesh.vertices()[2]);
+    assert_eq!(&[[0, 1, 2], [0, 2, 3]], mesh.triangles());
+}
+
+#[test]
+fn obj() {
+    let data = "
+        # A square, and a triangle using relative indices.
+        mtllib square.mtl
+        o square
+        v 0 0 0
+        v 0 0 1
+        v 1 0 1
+        v 1 0 0
+        vn 0 1 0
+        vt 0 0
+        f 1/1/1 2/1/1 3//1 4
+        v 0 1 0
+        f -1 -4 -3
+    ";
+    let mesh: TriMesh = load_obj(data).unwrap();
+    assert_eq!(5, mesh.vertices().len());
+    assert_eq!(&[[0, 1, 2], [0, 2, 3], [4, 1, 2]], mesh.triangles());
+
+    match load_obj::<f64>("v 0 0 0\nf 1 2 3") {
+        Err(MeshError::Syntax(message)) => assert_eq!("line 2: index out of bounds", message),
+        result => panic!("unexpected result {:?}", result),
+    }
+    match load_obj::<f64>("v 0 0\n") {
+        Err(MeshError::Syntax(message)) => assert_eq!("line 1: missing coordinate", message),
+        result => panic!("unexpected result {:?}", result),
+    }
+    assert!(matches!(load_obj::<f64>("v 0 0 0\n"), Err(MeshError::Mesh)));
+}
+
+#[test]
+fn ply() {
+    // ASCII files.
+    let mut data = square_header("ascii");
+    data.extend_from_slice(b"0 0 0 255\n0 0 1 0\n1 0 1 0\n1 0 0 0\n4 0 1 2 3\n");
+    assert_square(&load_ply(&data).unwrap());
+
+    // Binary files, in both byte orders.
+    for (format, little) in [("binary_little_endian", true), ("binary_big_endian", false)].iter() {
+        let mut data = square_header(format);
+        let corners = [
+            [0.0f32, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0],
+        ];
+        for corner in corners.iter() {
+            for value in corner.iter() {
+                let bytes = if *little {
+                    value.to_le_bytes()
+                } else {
+                    value.to_be_bytes()
+                };
+                data.extend_from_slice(&bytes);
+            }
+            data.push(7);
+        }
+        data.push(4);
+        for index in 0..4i32 {
+            let bytes = if *little {
+                index.to_le_bytes()
+            } else {
+                index.to_be_bytes()
+            };
+            data.extend_from_slice(&bytes);
+        }
+        assert_square(&load_ply(&data).unwrap());
+
+        // Missing data is reported.
+        data.pop();
+        assert!(matches!(load_ply::<f64>(&data), Err(MeshError::Syntax(_))));
+    }
+
+    assert!(matches!(
+        load_ply::<f64>(b"obj\nend_header\n"),
+        Err(MeshError::Format(_))
+    ));
+}
@@ -0,0 +1,1236 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::{component, Aabb};
+use crate::collider::Collider;
+use crate::contact::Contact;
+use crate::material::PhysicsMaterial;
+use crate::plane::Plane;
+use crate::shape::{Capsule, Cuboid, Shape, Sphere};
+use crate::trimesh::{TriMesh, Triangle};
+use math::{Matrix4, Vector3};
+
+/// Fraction of the penetration along a face axis that another axis must improve on
+/// to be chosen instead. Preferring face contacts keeps the manifold stable between frames.
+const AXIS_PREFERENCE: f64 = 0.95;
+
+/// Bit set in the feature identifiers of edge-edge contacts.
+const EDGE_FEATURE: u32 = 1 << 31;
+
+/// Squared length below which the cross product of two edges is considered degenerate.
+const PARALLEL_EPSILON: f64 = 1e-6;
+
+/// Tolerance under which a contact with a triangle mesh is considered to lie on the plane
+/// of another contact, and their normals to be the same.
+const MESH_EDGE_TOLERANCE: f64 = 1e-6;
+
+/// Holds the contacts generated by the narrow phase, and the settings used to create them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CollisionData<F: num_traits::Float = f64> {
+    /// Contacts generated so far.
+    pub contacts: Vec<Contact<F>>,
+
+    /// Maximum number of contacts that can be generated.
+    pub max_contacts: usize,
+
+    /// Dynamic friction coefficient written into the generated contacts.
+    pub friction: F,
+
+    /// Static friction coefficient written into the generated contacts.
+    pub static_friction: F,
+
+    /// Restitution coefficient written into the generated contacts.
+    pub restitution: F,
+
+    /// Distance under which shapes that don't touch yet generate speculative contacts,
+    /// with negative penetration.
+    pub margin: F,
+}
+
+impl<F: num_traits::Float> CollisionData<F> {
+    /// Creates a new empty collision data that can hold up to `max_contacts` contacts.
+    pub fn new(max_contacts: usize) -> Self {
+        Self {
+            contacts: Vec::with_capacity(max_contacts),
+            max_contacts,
+            friction: num_traits::zero(),
+            static_friction: num_traits::zero(),
+            restitution: num_traits::zero(),
+            margin: num_traits::zero(),
+        }
+    }
+
+    /// Returns the number of contacts that can still be generated.
+    pub fn contacts_left(&self) -> usize {
+        self.max_contacts.saturating_sub(self.contacts.len())
+    }
+
+    /// Sets the friction coefficients and restitution written into the generated contacts
+    /// to the ones of a contact between the given materials.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other methods that
+    /// modify the collision data.
+    pub fn set_materials(
+        &mut self,
+        one: &PhysicsMaterial<F>,
+        two: &PhysicsMaterial<F>,
+    ) -> &mut Self {
+        let (friction, static_friction, restitution) = one.combine(two);
+        self.friction = friction;
+        self.static_friction = static_friction;
+        self.restitution = restitution;
+        self
+    }
+
+    /// Removes all the generated contacts.
+    pub fn reset(&mut self) {
+        self.contacts.clear();
+    }
+
+    /// Adds the given contacts while there is room for them, returning true if any was added.
+    ///
+    /// # Remarks
+    /// Every contact is taken from the iterator, even the ones that don't fit.
+    pub(crate) fn keep<I: Iterator<Item = Contact<F>>>(&mut self, contacts: I) -> bool {
+        let mut kept = false;
+        for contact in contacts {
+            if self.contacts_left() > 0 {
+                self.contacts.push(contact);
+                kept = true;
+            }
+        }
+        kept
+    }
+
+    /// Adds a contact with the friction coefficients and restitution of the collision data,
+    /// returning it so the caller can fill in its feature identifier.
+    pub(crate) fn add_contact(
+        &mut self,
+        bodies: (usize, Option<usize>),
+        contact_point: Vector3<F>,
+        contact_normal: Vector3<F>,
+        penetration: F,
+    ) -> &mut Contact<F> {
+        let mut contact = Contact::new(bodies, contact_point, contact_normal, penetration);
+        contact.friction = self.friction;
+        contact.static_friction = self.static_friction;
+        contact.restitution = self.restitution;
+        contact.margin = self.margin;
+        self.contacts.push(contact);
+        self.contacts.last_mut().expect("just added")
+    }
+}
+
+/// Generates the contact between two spheres, if they overlap.
+/// Returns the number of contacts generated.
+pub fn sphere_and_sphere<F: num_traits::Float>(
+    one: &Collider<Sphere<F>, F>,
+    two: &Collider<Sphere<F>, F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    if data.contacts_left() == 0 {
+        return 0;
+    }
+
+    spheres_contact(
+        (one.body, Some(two.body)),
+        (&one.position(), one.shape.radius),
+        (&two.position(), two.shape.radius),
+        data,
+    )
+}
+
+/// Generates the contact between two spheres given by their center and radius.
+fn spheres_contact<F: num_traits::Float>(
+    bodies: (usize, Option<usize>),
+    one: (&Vector3<F>, F),
+    two: (&Vector3<F>, F),
+    data: &mut CollisionData<F>,
+) -> usize {
+    // Find the vector between the objects, and check that they're close enough.
+    let midline = one.0.vector_sub(two.0);
+    let size = midline.magnitude();
+    let radii = one.1 + two.1;
+    if size <= num_traits::zero() || size >= radii + data.margin {
+        return 0;
+    }
+
+    let normal = midline.scalar_div(size);
+    data.add_contact(
+        bodies,
+        two.0.vector_add(&midline.scalar_mul(math::real(0.5))),
+        normal,
+        radii - size,
+    );
+    1
+}
+
+/// Generates the contact between a sphere and a half-space, if they overlap.
+/// Returns the number of contacts generated.
+///
+/// # Remarks
+/// Everything behind the plane is considered solid, so a sphere fully behind it
+/// is still in contact, and is pushed out along the normal of the plane.
+pub fn sphere_and_half_space<F: num_traits::Float>(
+    sphere: &Collider<Sphere<F>, F>,
+    plane: &Plane<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    if data.contacts_left() == 0 {
+        return 0;
+    }
+
+    sphere_contact_with_half_space(
+        sphere.body,
+        &sphere.position(),
+        sphere.shape.radius,
+        plane,
+        data,
+    )
+}
+
+/// Generates the contact between a sphere given by its center and radius and a half-space.
+fn sphere_contact_with_half_space<F: num_traits::Float>(
+    body: usize,
+    center: &Vector3<F>,
+    radius: F,
+    plane: &Plane<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    let distance = plane.signed_distance(center) - radius;
+    if distance >= data.margin {
+        return 0;
+    }
+
+    // The contact point is the deepest point of the sphere, projected onto the plane.
+    data.add_contact(
+        (body, None),
+        center.vector_sub(&plane.normal.scalar_mul(distance + radius)),
+        plane.normal,
+        -distance,
+    );
+    1
+}
+
+/// Generates the contact between a sphere and a two-sided plane, if they overlap.
+/// Returns the number of contacts generated.
+///
+/// # Remarks
+/// Unlike half-spaces, the sphere is pushed towards whichever side of the plane its
+/// center is on, so spheres can rest on either side.
+pub fn sphere_and_true_plane<F: num_traits::Float>(
+    sphere: &Collider<Sphere<F>, F>,
+    plane: &Plane<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    if data.contacts_left() == 0 {
+        return 0;
+    }
+
+    let position = sphere.position();
+    let center_distance = plane.signed_distance(&position);
+    if center_distance.abs() >= sphere.shape.radius + data.margin {
+        return 0;
+    }
+
+    // Check which side of the plane the sphere is on.
+    let (normal, penetration) = if center_distance < num_traits::zero() {
+        (plane.normal.invert(), sphere.shape.radius + center_distance)
+    } else {
+        (plane.normal, sphere.shape.radius - center_distance)
+    };
+    data.add_contact(
+        (sphere.body, None),
+        position.vector_sub(&plane.normal.scalar_mul(center_distance)),
+        normal,
+        penetration,
+    );
+    1
+}
+
+/// Generates the contacts between a box and a half-space, one for each vertex of the box
+/// behind the plane. Returns the number of contacts generated.
+pub fn box_and_half_space<F: num_traits::Float>(
+    cuboid: &Collider<Cuboid<F>, F>,
+    plane: &Plane<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    vertices_and_half_space(
+        cuboid.body,
+        &cuboid.shape.vertices(),
+        cuboid.transform(),
+        plane,
+        data,
+    )
+}
+
+/// Generates the contacts between a set of local space vertices placed with
+/// the given transform and a half-space, one for each vertex behind the plane.
+fn vertices_and_half_space<F: num_traits::Float>(
+    body: usize,
+    vertices: &[Vector3<F>],
+    transform: &Matrix4<F>,
+    plane: &Plane<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    let mut generated = 0;
+    for (index, vertex) in vertices.iter().enumerate() {
+        if data.contacts_left() == 0 {
+            break;
+        }
+
+        let vertex = transform.transform(vertex);
+        let distance = plane.signed_distance(&vertex);
+        if distance < data.margin {
+            data.add_contact(
+                (body, None),
+                vertex.vector_sub(&plane.normal.scalar_mul(distance)),
+                plane.normal,
+                -distance,
+            )
+            .feature = index as u32;
+            generated += 1;
+        }
+    }
+    generated
+}
+
+/// Generates the contact between a box and a sphere, if they overlap.
+/// Returns the number of contacts generated.
+pub fn box_and_sphere<F: num_traits::Float>(
+    cuboid: &Collider<Cuboid<F>, F>,
+    sphere: &Collider<Sphere<F>, F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    if data.contacts_left() == 0 {
+        return 0;
+    }
+
+    // Find the point of the box closest to the center of the sphere, in the space of the box.
+    let center = sphere.position();
+    let radius = sphere.shape.radius;
+    let relative = cuboid.transform().transform_inverse(&center);
+    let half_size = &cuboid.shape.half_size;
+    let closest = Vector3::new(
+        relative.x.max(-half_size.x).min(half_size.x),
+        relative.y.max(-half_size.y).min(half_size.y),
+        relative.z.max(-half_size.z).min(half_size.z),
+    );
+
+    let squared_distance = closest.vector_sub(&relative).squared_magnitude();
+    let reach = radius + data.margin;
+    if squared_distance >= reach * reach {
+        return 0;
+    }
+
+    let bodies = (cuboid.body, Some(sphere.body));
+    if squared_distance > num_traits::zero() {
+        let closest = cuboid.transform().transform(&closest);
+        data.add_contact(
+            bodies,
+            closest,
+            closest.vector_sub(&center).normalize(),
+            radius - squared_distance.sqrt(),
+        );
+        return 1;
+    }
+
+    // The center is inside the box, so push it out through the nearest face.
+    let mut axis = 0;
+    let mut depth = F::infinity();
+    for index in 0..3 {
+        let face_depth = component(half_size, index) - component(&relative, index).abs();
+        if face_depth < depth {
+            axis = index;
+            depth = face_depth;
+        }
+    }
+    let mut normal = cuboid.axis(axis);
+    if component(&relative, axis) > num_traits::zero() {
+        normal.inplace_invert();
+    }
+    data.add_contact(bodies, center, normal, depth + radius);
+    1
+}
+
+/// Generates the contacts between two boxes, if they overlap, using the separating axis theorem.
+/// Returns the number of contacts generated.
+///
+/// # Remarks
+/// The fifteen potential separating axes are the face normals of both boxes and the
+/// cross products of their edges. When the axis of least penetration is a face normal,
+/// the most anti-parallel face of the other box is clipped against the sides of the
+/// reference face, producing a manifold of up to four points. When it's an edge-edge
+/// axis, a single contact is generated between the closest points of both edges.
+pub fn box_and_box<F: num_traits::Float>(
+    one: &Collider<Cuboid<F>, F>,
+    two: &Collider<Cuboid<F>, F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    if data.contacts_left() == 0 {
+        return 0;
+    }
+
+    let to_center = two.position().vector_sub(&one.position());
+    let preference: F = math::real(AXIS_PREFERENCE);
+
+    // Check the face axes of both boxes.
+    let mut best_face: Option<(usize, F)> = None;
+    for index in 0..6 {
+        let axis = if index < 3 {
+            one.axis(index)
+        } else {
+            two.axis(index - 3)
+        };
+        let penetration = match penetration_on_axis(one, two, &axis, &to_center, data.margin) {
+            Some(penetration) => penetration,
+            None => return 0,
+        };
+        let better = match best_face {
+            Some((_, best)) => penetration < best * preference,
+            None => true,
+        };
+        if better {
+            best_face = Some((index, penetration));
+        }
+    }
+    let (face, face_penetration) = best_face.expect("face axes are always tested");
+
+    // Check the edge-edge axes.
+    let mut best_edge: Option<(usize, usize, Vector3<F>, F)> = None;
+    for one_index in 0..3 {
+        for two_index in 0..3 {
+            let axis = one.axis(one_index).cross_product(&two.axis(two_index));
+            if axis.squared_magnitude() < math::real(PARALLEL_EPSILON) {
+                continue;
+            }
+
+            let axis = axis.normalize();
+            let penetration = match penetration_on_axis(one, two, &axis, &to_center, data.margin) {
+                Some(penetration) => penetration,
+                None => return 0,
+            };
+            let best = best_edge.map_or(face_penetration * preference, |edge| edge.3);
+            if penetration < best {
+                best_edge = Some((one_index, two_index, axis, penetration));
+            }
+        }
+    }
+
+    match best_edge {
+        Some((one_index, two_index, axis, penetration)) => {
+            edge_and_edge(one, two, one_index, two_index, axis, penetration, data)
+        }
+        None if face < 3 => face_and_box(one, two, face, false, data),
+        None => face_and_box(two, one, face - 3, true, data),
+    }
+}
+
+/// Returns the penetration of both boxes along the given axis,
+/// or `None` if the axis separates them by more than the margin.
+fn penetration_on_axis<F: num_traits::Float>(
+    one: &Collider<Cuboid<F>, F>,
+    two: &Collider<Cuboid<F>, F>,
+    axis: &Vector3<F>,
+    to_center: &Vector3<F>,
+    margin: F,
+) -> Option<F> {
+    let penetration =
+        project_to_axis(one, axis) + project_to_axis(two, axis) - to_center.dot_product(axis).abs();
+    if penetration < -margin {
+        None
+    } else {
+        Some(penetration)
+    }
+}
+
+/// Returns the half-length of the projection of the box onto the given axis.
+fn project_to_axis<F: num_traits::Float>(cuboid: &Collider<Cuboid<F>, F>, axis: &Vector3<F>) -> F {
+    let half_size = &cuboid.shape.half_size;
+    half_size.x * axis.dot_product(&cuboid.axis(0)).abs()
+        + half_size.y * axis.dot_product(&cuboid.axis(1)).abs()
+        + half_size.z * axis.dot_product(&cuboid.axis(2)).abs()
+}
+
+/// Generates the contacts of a face of the `reference` box against the `incident` box,
+/// by clipping the incident face against the sides of the reference face.
+/// `swapped` is true when the reference box is the second box of the pair.
+fn face_and_box<F: num_traits::Float>(
+    reference: &Collider<Cuboid<F>, F>,
+    incident: &Collider<Cuboid<F>, F>,
+    face: usize,
+    swapped: bool,
+    data: &mut CollisionData<F>,
+) -> usize {
+    let zero: F = num_traits::zero();
+    let reference_center = reference.position();
+    let incident_center = incident.position();
+
+    // Orient the reference face normal towards the incident box.
+    let mut normal = reference.axis(face);
+    if normal.dot_product(&incident_center.vector_sub(&reference_center)) < zero {
+        normal.inplace_invert();
+    }
+    let face_offset =
+        normal.dot_product(&reference_center) + component(&reference.shape.half_size, face);
+
+    // Find the face of the incident box most anti-parallel to the normal.
+    let mut incident_axis = 0;
+    let mut alignment = zero;
+    for index in 0..3 {
+        let dot = incident.axis(index).dot_product(&normal);
+        if dot.abs() > alignment.abs() {
+            incident_axis = index;
+            alignment = dot;
+        }
+    }
+    let incident_normal = if alignment > zero {
+        incident.axis(incident_axis).invert()
+    } else {
+        incident.axis(incident_axis)
+    };
+    let incident_face = incident_center.vector_add(
+        &incident_normal.scalar_mul(component(&incident.shape.half_size, incident_axis)),
+    );
+    let s_index = (incident_axis + 1) % 3;
+    let t_index = (incident_axis + 2) % 3;
+    let s = incident
+        .axis(s_index)
+        .scalar_mul(component(&incident.shape.half_size, s_index));
+    let t = incident
+        .axis(t_index)
+        .scalar_mul(component(&incident.shape.half_size, t_index));
+    let mut polygon = vec![
+        (incident_face.vector_add(&s).vector_add(&t), 0),
+        (incident_face.vector_sub(&s).vector_add(&t), 1),
+        (incident_face.vector_sub(&s).vector_sub(&t), 2),
+        (incident_face.vector_add(&s).vector_sub(&t), 3),
+    ];
+
+    // Clip the incident face against the four sides of the reference face.
+    for (plane, side) in [(face + 1) % 3, (face + 2) % 3].iter().enumerate() {
+        let axis = reference.axis(*side);
+        let extent = component(&reference.shape.half_size, *side);
+        let center = axis.dot_product(&reference_center);
+        polygon = clip(&polygon, &axis, center + extent, plane as u32 * 2);
+        polygon = clip(
+            &polygon,
+            &axis.invert(),
+            extent - center,
+            plane as u32 * 2 + 1,
+        );
+    }
+
+    // Keep the points behind the reference face.
+    let (mut points, mut depths): (Vec<(Vector3<F>, u32)>, Vec<F>) = polygon
+        .into_iter()
+        .filter_map(|point| {
+            let depth = face_offset - normal.dot_product(&point.0);
+            if depth >= -data.margin {
+                Some((point, depth))
+            } else {
+                None
+            }
+        })
+        .unzip();
+    if points.len() > 4 {
+        let positions: Vec<Vector3<F>> = points.iter().map(|point| point.0).collect();
+        let kept = reduce_manifold(&positions, &depths, &normal);
+        points = kept.iter().map(|index| points[*index]).collect();
+        depths = kept.iter().map(|index| depths[*index]).collect();
+    }
+
+    // The contact normal points towards the first box of the pair.
+    let contact_normal = if swapped { normal } else { normal.invert() };
+    let bodies = if swapped {
+        (incident.body, Some(reference.body))
+    } else {
+        (reference.body, Some(incident.body))
+    };
+    let face_feature = (face as u32 + if swapped { 4 } else { 1 }) << 16;
+    let half: F = math::real(0.5);
+    let mut generated = 0;
+    for ((point, id), depth) in points.into_iter().zip(depths).take(data.contacts_left()) {
+        // Place the contact halfway between the incident point and the reference face.
+        let contact_point = point.vector_add(&normal.scalar_mul(depth * half));
+        data.add_contact(bodies, contact_point, contact_normal, depth)
+            .feature = face_feature | (id & 0xffff);
+        generated += 1;
+    }
+    generated
+}
+
+/// Clips the polygon against the plane, keeping the part where `normal · p <= offset`.
+///
+/// # Remarks
+/// Each point carries an identifier of the features it comes from, so the same point can
+/// be recognized across frames. Points created by the clipping combine the identifiers of
+/// the edge they lie on with the index of the clipping plane.
+fn clip<F: num_traits::Float>(
+    polygon: &[(Vector3<F>, u32)],
+    normal: &Vector3<F>,
+    offset: F,
+    plane: u32,
+) -> Vec<(Vector3<F>, u32)> {
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (index, (start, start_id)) in polygon.iter().enumerate() {
+        let (end, end_id) = &polygon[(index + 1) % polygon.len()];
+        let start_distance = normal.dot_product(start) - offset;
+        let end_distance = normal.dot_product(end) - offset;
+
+        if start_distance <= num_traits::zero() {
+            clipped.push((*start, *start_id));
+        }
+        if (start_distance < num_traits::zero()) != (end_distance < num_traits::zero()) {
+            let fraction = start_distance / (start_distance - end_distance);
+            let id = combine_features(
+                combine_features(*start_id.min(end_id), *start_id.max(end_id)),
+                plane + 1,
+            );
+            clipped.push((
+                start.vector_add(&end.vector_sub(start).scalar_mul(fraction)),
+                id,
+            ));
+        }
+    }
+    clipped
+}
+
+/// Combines two feature identifiers into a new one.
+pub(crate) fn combine_features(one: u32, two: u32) -> u32 {
+    one.wrapping_mul(0x9e37_79b1).rotate_left(5) ^ two
+}
+
+/// Reduces a manifold to four points: the deepest one, the furthest from it,
+/// and the ones spanning the largest area on either side of the first two.
+/// Returns the indices of the points to keep.
+pub(crate) fn reduce_manifold<F: num_traits::Float>(
+    points: &[Vector3<F>],
+    depths: &[F],
+    normal: &Vector3<F>,
+) -> Vec<usize> {
+    let max_by = |score: &dyn Fn(usize) -> F| {
+        (0..points.len())
+            .max_by(|a, b| {
+                score(*a)
+                    .partial_cmp(&score(*b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .expect("there are more than four points")
+    };
+
+    let first = max_by(&|index| depths[index]);
+    let origin = points[first];
+    let second = max_by(&|index| points[index].vector_sub(&origin).squared_magnitude());
+    let edge = points[second].vector_sub(&origin);
+    let area = |index: usize| {
+        edge.cross_product(&points[index].vector_sub(&origin))
+            .dot_product(normal)
+    };
+    let third = max_by(&area);
+    let fourth = max_by(&|index| -area(index));
+
+    let mut indices = vec![first, second, third, fourth];
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// Generates the contact between two edges, at the midpoint of their closest points.
+fn edge_and_edge<F: num_traits::Float>(
+    one: &Collider<Cuboid<F>, F>,
+    two: &Collider<Cuboid<F>, F>,
+    one_index: usize,
+    two_index: usize,
+    axis: Vector3<F>,
+    penetration: F,
+    data: &mut CollisionData<F>,
+) -> usize {
+    // Orient the axis from the first box towards the second one.
+    let mut axis = axis;
+    if axis.dot_product(&two.position().vector_sub(&one.position())) < num_traits::zero() {
+        axis.inplace_invert();
+    }
+
+    // Find the edges of both boxes closest to each other: the ones furthest
+    // along the axis for the first box, and against it for the second one.
+    let edge_point = |cuboid: &Collider<Cuboid<F>, F>, edge: usize, direction: &Vector3<F>| {
+        let mut point = cuboid.position();
+        for index in 0..3 {
+            if index == edge {
+                continue;
+            }
+            let extent = component(&cuboid.shape.half_size, index);
+            let axis = cuboid.axis(index);
+            let sign = if axis.dot_product(direction) > num_traits::zero() {
+                extent
+            } else {
+                -extent
+            };
+            point.inplace_vector_add(&axis.scalar_mul(sign));
+        }
+        point
+    };
+    let point_one = edge_point(one, one_index, &axis);
+    let point_two = edge_point(two, two_index, &axis.invert());
+
+    // Find the closest points of both edges, as infinite lines.
+    let direction_one = one.axis(one_index);
+    let direction_two = two.axis(two_index);
+    let offset = point_one.vector_sub(&point_two);
+    let b = direction_one.dot_product(&direction_two);
+    let d = direction_one.dot_product(&offset);
+    let e = direction_two.dot_product(&offset);
+    let denominator = num_traits::one::<F>() - b * b;
+    let (a, c) = if denominator.abs() < F::epsilon() {
+        (num_traits::zero(), e)
+    } else {
+        ((b * e - d) / denominator, (e - b * d) / denominator)
+    };
+    let closest_one = point_one.vector_add(&direction_one.scalar_mul(a));
+    let closest_two = point_two.vector_add(&direction_two.scalar_mul(c));
+
+    data.add_contact(
+        (one.body, Some(two.body)),
+        closest_one
+            .vector_add(&closest_two)
+            .scalar_mul(math::real(0.5)),
+        axis.invert(),
+        penetration,
+    )
+    .feature = EDGE_FEATURE | (one_index * 3 + two_index) as u32;
+    1
+}
+
+/// Generates the contact between a capsule and a sphere, if they overlap.
+/// Returns the number of contacts generated.
+pub fn capsule_and_sphere<F: num_traits::Float>(
+    capsule: &Collider<Capsule<F>, F>,
+    sphere: &Collider<Sphere<F>, F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    if data.contacts_left() == 0 {
+        return 0;
+    }
+
+    let center = sphere.position();
+    let (start, end) = capsule.shape.segment(capsule.transform());
+    let closest = closest_point_on_segment(&center, &start, &end);
+    spheres_contact(
+        (capsule.body, Some(sphere.body)),
+        (&closest, capsule.shape.radius),
+        (&center, sphere.shape.radius),
+        data,
+    )
+}
+
+/// Generates the contacts between two capsules, if they overlap.
+/// Returns the number of contacts generated.
+///
+/// # Remarks
+/// Capsules lying side by side generate two contacts at the ends of the overlapping
+/// part of their segments, so they can rest on each other without rolling around.
+pub fn capsule_and_capsule<F: num_traits::Float>(
+    one: &Collider<Capsule<F>, F>,
+    two: &Collider<Capsule<F>, F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    if data.contacts_left() == 0 {
+        return 0;
+    }
+
+    let bodies = (one.body, Some(two.body));
+    let radii = (one.shape.radius, two.shape.radius);
+    let (start_one, end_one) = one.shape.segment(one.transform());
+    let (start_two, end_two) = two.shape.segment(two.transform());
+    let direction_one = end_one.vector_sub(&start_one);
+    let direction_two = end_two.vector_sub(&start_two);
+
+    // Parallel segments touch along an interval.
+    let length = direction_one.squared_magnitude();
+    let parallel = direction_one
+        .cross_product(&direction_two)
+        .squared_magnitude()
+        <= math::real::<F>(PARALLEL_EPSILON) * length * direction_two.squared_magnitude();
+    if parallel && length > num_traits::zero() {
+        let project = |point: &Vector3<F>| {
+            (point.vector_sub(&start_one).dot_product(&direction_one) / length)
+                .max(num_traits::zero())
+                .min(num_traits::one())
+        };
+        let (first, second) = (project(&start_two), project(&end_two));
+        let (from, to) = (first.min(second), first.max(second));
+        if to - from > F::epsilon() {
+            let mut generated = 0;
+            for (feature, t) in [from, to].iter().enumerate() {
+                if data.contacts_left() == 0 {
+                    break;
+                }
+                let point = start_one.vector_add(&direction_one.scalar_mul(*t));
+                let closest = closest_point_on_segment(&point, &start_two, &end_two);
+                let added = spheres_contact(bodies, (&point, radii.0), (&closest, radii.1), data);
+                label_last(data, added, feature as u32);
+                generated += added;
+            }
+            return generated;
+        }
+    }
+
+    let (closest_one, closest_two) =
+        closest_points_of_segments((&start_one, &end_one), (&start_two, &end_two));
+    spheres_contact(
+        bodies,
+        (&closest_one, radii.0),
+        (&closest_two, radii.1),
+        data,
+    )
+}
+
+/// Generates the contacts between a capsule and a half-space, one for each end
+/// of the capsule behind the plane. Returns the number of contacts generated.
+pub fn capsule_and_half_space<F: num_traits::Float>(
+    capsule: &Collider<Capsule<F>, F>,
+    plane: &Plane<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    let (start, end) = capsule.shape.segment(capsule.transform());
+    let mut generated = 0;
+    for (feature, center) in [start, end].iter().enumerate() {
+        if data.contacts_left() == 0 {
+            break;
+        }
+        let added =
+            sphere_contact_with_half_space(capsule.body, center, capsule.shape.radius, plane, data);
+        label_last(data, added, feature as u32);
+        generated += added;
+    }
+    generated
+}
+
+/// Generates the contacts between a sphere and a triangle mesh, one for each triangle
+/// the sphere touches. Returns the number of contacts generated.
+///
+/// # Remarks
+/// Triangles are two-sided, so the sphere is pushed out of whichever side its center is on.
+pub fn sphere_and_trimesh<F: num_traits::Float>(
+    sphere: &Collider<Sphere<F>, F>,
+    mesh: &Collider<TriMesh<F>, F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    sphere_and_placed_mesh(sphere, (mesh.body, &mesh.shape, mesh.transform()), data)
+}
+
+/// Generates the contacts between a capsule and a triangle mesh, for each triangle the
+/// capsule touches: one at each end of the capsule, and one at the point of its segment
+/// closest to the triangle. Returns the number of contacts generated.
+pub fn capsule_and_trimesh<F: num_traits::Float>(
+    capsule: &Collider<Capsule<F>, F>,
+    mesh: &Collider<TriMesh<F>, F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    capsule_and_placed_mesh(capsule, (mesh.body, &mesh.shape, mesh.transform()), data)
+}
+
+/// Triangle mesh placed in the world, along with the body it belongs to.
+type PlacedMesh<'a, F> = (usize, &'a TriMesh<F>, &'a Matrix4<F>);
+
+fn sphere_and_placed_mesh<F: num_traits::Float>(
+    sphere: &Collider<Sphere<F>, F>,
+    mesh: PlacedMesh<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    let center = sphere.position();
+    let reach = sphere.shape.aabb(sphere.transform()).loosened(data.margin);
+    let bodies = (sphere.body, Some(mesh.0));
+    mesh_contacts(bodies, sphere.shape.radius, &reach, mesh, data, |_| {
+        vec![center]
+    })
+}
+
+fn capsule_and_placed_mesh<F: num_traits::Float>(
+    capsule: &Collider<Capsule<F>, F>,
+    mesh: PlacedMesh<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    let (start, end) = capsule.shape.segment(capsule.transform());
+    let reach = capsule
+        .shape
+        .aabb(capsule.transform())
+        .loosened(data.margin);
+    let bodies = (capsule.body, Some(mesh.0));
+    let tolerance = math::real::<F>(PARALLEL_EPSILON) * end.vector_sub(&start).squared_magnitude();
+    mesh_contacts(
+        bodies,
+        capsule.shape.radius,
+        &reach,
+        mesh,
+        data,
+        |triangle| {
+            let middle = closest_point_of_segment_to_triangle(&start, &end, triangle);
+            let mut centers = vec![start, end];
+            if centers
+                .iter()
+                .all(|end| end.vector_sub(&middle).squared_magnitude() > tolerance)
+            {
+                centers.push(middle);
+            }
+            centers
+        },
+    )
+}
+
+/// Generates the contacts between spheres of the given radius and the triangles of a mesh
+/// overlapping the given region. The centers of the spheres tested against each triangle,
+/// in world space, are given by `centers`.
+///
+/// # Remarks
+/// Shapes sliding over a flat mesh also touch the edges of the triangles next to the one
+/// they're on, with normals tilted towards those edges that would make them bump. Contacts are
+/// kept from the deepest one, dropping the ones lying on the plane of a kept contact with a
+/// different normal, which only leaves the contacts of faces and creases.
+fn mesh_contacts<F: num_traits::Float, C: Fn(&Triangle<F>) -> Vec<Vector3<F>>>(
+    bodies: (usize, Option<usize>),
+    radius: F,
+    reach: &Aabb<F>,
+    (_, mesh, transform): PlacedMesh<F>,
+    data: &mut CollisionData<F>,
+    centers: C,
+) -> usize {
+    let mut candidates = Vec::new();
+    for index in mesh.triangles_near(reach, transform) {
+        let triangle = mesh.triangle(index).transformed(transform);
+        for (feature, center) in centers(&triangle).iter().enumerate() {
+            if let Some(contact) = sphere_and_triangle(center, radius, &triangle, data.margin) {
+                let feature = combine_features(index as u32 + 1, feature as u32);
+                candidates.push((feature, contact));
+            }
+        }
+    }
+    candidates.sort_by(|(_, (_, _, one)), (_, (_, _, two))| {
+        two.partial_cmp(one).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let tolerance = math::real::<F>(MESH_EDGE_TOLERANCE);
+    let mut kept: Vec<(Vector3<F>, Vector3<F>)> = Vec::new();
+    for (feature, (point, normal, penetration)) in candidates {
+        if data.contacts_left() == 0 {
+            break;
+        }
+        let shadowed = kept.iter().any(|(kept_point, kept_normal)| {
+            kept_normal.dot_product(&normal) < F::one() - tolerance
+                && point.vector_sub(kept_point).dot_product(kept_normal) <= tolerance
+        });
+        if !shadowed {
+            data.add_contact(bodies, point, normal, penetration).feature = feature;
+            kept.push((point, normal));
+        }
+    }
+    kept.len()
+}
+
+/// Returns the contact point, normal and penetration of a sphere given by its center and
+/// radius and a triangle, if they're closer than the given margin.
+fn sphere_and_triangle<F: num_traits::Float>(
+    center: &Vector3<F>,
+    radius: F,
+    triangle: &Triangle<F>,
+    margin: F,
+) -> Option<(Vector3<F>, Vector3<F>, F)> {
+    let closest = triangle.closest_point(center);
+    let offset = center.vector_sub(&closest);
+    let distance = offset.magnitude();
+    if distance >= radius + margin {
+        return None;
+    }
+
+    // Centers lying on the triangle are pushed out of its front.
+    let normal = if distance > F::epsilon() {
+        offset.scalar_div(distance)
+    } else {
+        triangle.normal()
+    };
+    if normal.squared_magnitude() <= num_traits::zero() {
+        return None;
+    }
+    Some((closest, normal, radius - distance))
+}
+
+/// Returns the point of the segment closest to the triangle.
+///
+/// # Remarks
+/// The closest point is either an end of the segment, the point closest to an edge of the
+/// triangle, or the point where the segment crosses the triangle.
+fn closest_point_of_segment_to_triangle<F: num_traits::Float>(
+    start: &Vector3<F>,
+    end: &Vector3<F>,
+    triangle: &Triangle<F>,
+) -> Vector3<F> {
+    let normal = triangle.normal();
+    let (from, to) = (
+        normal.dot_product(&start.vector_sub(&triangle.a)),
+        normal.dot_product(&end.vector_sub(&triangle.a)),
+    );
+    if from * to < num_traits::zero() {
+        let crossing = start.vector_add(&end.vector_sub(start).scalar_mul(from / (from - to)));
+        let projected = triangle.closest_point(&crossing);
+        if projected.vector_sub(&crossing).squared_magnitude() <= F::epsilon() {
+            return crossing;
+        }
+    }
+
+    let edges = [
+        (&triangle.a, &triangle.b),
+        (&triangle.b, &triangle.c),
+        (&triangle.c, &triangle.a),
+    ];
+    let mut candidates: Vec<Vector3<F>> = edges
+        .iter()
+        .map(|edge| closest_points_of_segments((start, end), *edge).0)
+        .collect();
+    candidates.push(*start);
+    candidates.push(*end);
+    let distance = |point: &Vector3<F>| {
+        triangle
+            .closest_point(point)
+            .vector_sub(point)
+            .squared_magnitude()
+    };
+    candidates
+        .into_iter()
+        .min_by(|a, b| {
+            distance(a)
+                .partial_cmp(&distance(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .expect("there are candidates")
+}
+
+/// Returns the point of the segment closest to the given point.
+fn closest_point_on_segment<F: num_traits::Float>(
+    point: &Vector3<F>,
+    start: &Vector3<F>,
+    end: &Vector3<F>,
+) -> Vector3<F> {
+    let direction = end.vector_sub(start);
+    let length = direction.squared_magnitude();
+    if length <= num_traits::zero() {
+        return *start;
+    }
+
+    let t = point.vector_sub(start).dot_product(&direction) / length;
+    start.vector_add(&direction.scalar_mul(t.max(num_traits::zero()).min(num_traits::one())))
+}
+
+/// Returns the closest points of two segments.
+fn closest_points_of_segments<F: num_traits::Float>(
+    one: (&Vector3<F>, &Vector3<F>),
+    two: (&Vector3<F>, &Vector3<F>),
+) -> (Vector3<F>, Vector3<F>) {
+    let zero: F = num_traits::zero();
+    let one_f: F = num_traits::one();
+    let clamp = |value: F| value.max(zero).min(one_f);
+
+    let d1 = one.1.vector_sub(one.0);
+    let d2 = two.1.vector_sub(two.0);
+    let r = one.0.vector_sub(two.0);
+    let a = d1.squared_magnitude();
+    let e = d2.squared_magnitude();
+    let f = d2.dot_product(&r);
+
+    let (s, t) = if a <= F::epsilon() && e <= F::epsilon() {
+        // Both segments degenerate into points.
+        (zero, zero)
+    } else if a <= F::epsilon() {
+        (zero, clamp(f / e))
+    } else {
+        let c = d1.dot_product(&r);
+        if e <= F::epsilon() {
+            (clamp(-c / a), zero)
+        } else {
+            let b = d1.dot_product(&d2);
+            let denominator = a * e - b * b;
+            let s = if denominator > zero {
+                clamp((b * f - c * e) / denominator)
+            } else {
+                zero
+            };
+
+            // Find the point of the second segment closest to the first one,
+            // and recompute the first one if it had to be clamped.
+            let t = (b * s + f) / e;
+            if t < zero {
+                (clamp(-c / a), zero)
+            } else if t > one_f {
+                (clamp((b - c) / a), one_f)
+            } else {
+                (s, t)
+            }
+        }
+    };
+
+    (
+        one.0.vector_add(&d1.scalar_mul(s)),
+        two.0.vector_add(&d2.scalar_mul(t)),
+    )
+}
+
+/// Generates the contacts between two colliders of any shape.
+/// Returns the number of contacts generated.
+///
+/// # Remarks
+/// Compound shapes are split into their children, so each child is tested on its own
+/// against the other shape. Pairs of shapes without a specialized routine (e.g. convex
+/// hulls against other shapes, or boxes against triangle meshes) don't generate contacts yet.
+pub fn collide<F: num_traits::Float>(
+    one: &Collider<Shape<F>, F>,
+    two: &Collider<Shape<F>, F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    collide_shapes(
+        (one.body, &one.shape, one.transform()),
+        (two.body, &two.shape, two.transform()),
+        data,
+    )
+}
+
+/// Generates the contacts between a collider of any shape and a half-space.
+/// Returns the number of contacts generated.
+pub fn collide_with_half_space<F: num_traits::Float>(
+    collider: &Collider<Shape<F>, F>,
+    plane: &Plane<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    shape_and_half_space(
+        (collider.body, &collider.shape, collider.transform()),
+        plane,
+        data,
+    )
+}
+
+/// Shape placed in the world, along with the body it belongs to.
+pub(crate) type PlacedShape<'a, F> = (usize, &'a Shape<F>, &'a Matrix4<F>);
+
+/// Generates the contacts between two placed shapes of any kind.
+/// Returns the number of contacts generated.
+pub(crate) fn collide_shapes<F: num_traits::Float>(
+    one: PlacedShape<F>,
+    two: PlacedShape<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    match (one.1, two.1) {
+        (Shape::Compound(compound), _) => {
+            let mut generated = 0;
+            for (index, child) in compound.children.iter().enumerate() {
+                let start = data.contacts.len();
+                let transform = one.2.matrix_mul(&child.offset);
+                generated += collide_shapes((one.0, &child.shape, &transform), two, data);
+                label_child(data, start, index);
+            }
+            generated
+        }
+        (_, Shape::Compound(_)) => flipped(data, |data| collide_shapes(two, one, data)),
+        (Shape::Sphere(a), Shape::Sphere(b)) => {
+            sphere_and_sphere(&placed(one, *a), &placed(two, *b), data)
+        }
+        (Shape::Cuboid(a), Shape::Cuboid(b)) => {
+            box_and_box(&placed(one, *a), &placed(two, *b), data)
+        }
+        (Shape::Cuboid(a), Shape::Sphere(b)) => {
+            box_and_sphere(&placed(one, *a), &placed(two, *b), data)
+        }
+        (Shape::Capsule(a), Shape::Sphere(b)) => {
+            capsule_and_sphere(&placed(one, *a), &placed(two, *b), data)
+        }
+        (Shape::Capsule(a), Shape::Capsule(b)) => {
+            capsule_and_capsule(&placed(one, *a), &placed(two, *b), data)
+        }
+        (Shape::Sphere(a), Shape::TriMesh(b)) => {
+            sphere_and_placed_mesh(&placed(one, *a), (two.0, b, two.2), data)
+        }
+        (Shape::Capsule(a), Shape::TriMesh(b)) => {
+            capsule_and_placed_mesh(&placed(one, *a), (two.0, b, two.2), data)
+        }
+        (Shape::Sphere(_), Shape::Cuboid(_))
+        | (Shape::Sphere(_), Shape::Capsule(_))
+        | (Shape::TriMesh(_), Shape::Sphere(_))
+        | (Shape::TriMesh(_), Shape::Capsule(_)) => {
+            flipped(data, |data| collide_shapes(two, one, data))
+        }
+        _ => 0,
+    }
+}
+
+/// Sets the feature identifier of the last contact, if the generator added one.
+fn label_last<F: num_traits::Float>(data: &mut CollisionData<F>, added: usize, feature: u32) {
+    if added > 0 {
+        if let Some(contact) = data.contacts.last_mut() {
+            contact.feature = feature;
+        }
+    }
+}
+
+/// Mixes the index of a compound child into the features of the contacts it generated,
+/// so contacts of different children don't share identifiers.
+fn label_child<F: num_traits::Float>(data: &mut CollisionData<F>, start: usize, child: usize) {
+    for contact in data.contacts[start..].iter_mut() {
+        contact.feature = combine_features(child as u32 + 1, contact.feature);
+    }
+}
+
+/// Creates a collider for one of the shapes of a placed shape.
+fn placed<S, F: num_traits::Float>(
+    (body, _, transform): PlacedShape<F>,
+    shape: S,
+) -> Collider<S, F> {
+    Collider::placed(body, shape, *transform)
+}
+
+/// Runs the given generator, and flips the contacts it generates
+/// so they are seen from the other body.
+fn flipped<F: num_traits::Float, G: FnOnce(&mut CollisionData<F>) -> usize>(
+    data: &mut CollisionData<F>,
+    generator: G,
+) -> usize {
+    let start = data.contacts.len();
+    let generated = generator(data);
+    for contact in data.contacts[start..].iter_mut() {
+        if let (one, Some(two)) = contact.bodies {
+            contact.bodies = (two, Some(one));
+        }
+        contact.contact_normal.inplace_invert();
+    }
+    generated
+}
+
+fn shape_and_half_space<F: num_traits::Float>(
+    shape: PlacedShape<F>,
+    plane: &Plane<F>,
+    data: &mut CollisionData<F>,
+) -> usize {
+    let (body, _, transform) = shape;
+    match shape.1 {
+        Shape::Sphere(sphere) => sphere_and_half_space(&placed(shape, *sphere), plane, data),
+        Shape::Cuboid(cuboid) => box_and_half_space(&placed(shape, *cuboid), plane, data),
+        Shape::Capsule(capsule) => capsule_and_half_space(&placed(shape, *capsule), plane, data),
+        Shape::ConvexHull(hull) => {
+            vertices_and_half_space(body, &hull.vertices, transform, plane, data)
+        }
+        Shape::TriMesh(mesh) => {
+            vertices_and_half_space(body, mesh.vertices(), transform, plane, data)
+        }
+        Shape::Compound(compound) => {
+            let mut generated = 0;
+            for (index, child) in compound.children.iter().enumerate() {
+                let start = data.contacts.len();
+                let transform = transform.matrix_mul(&child.offset);
+                generated += shape_and_half_space((body, &child.shape, &transform), plane, data);
+                label_child(data, start, index);
+            }
+            generated
+        }
+    }
+}
@@ -0,0 +1,425 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::narrow_phase::*;
+use crate::plane::Plane;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Capsule, Compound, Cuboid, Shape, Sphere};
+use math::{Matrix4, Quaternion, Vector3};
+
+fn assert_vector_eq(expected: Vector3<f64>, actual: Vector3<f64>) {
+    assert!(
+        (expected - actual).magnitude() < 1e-12,
+        "{:?} != {:?}",
+        expected,
+        actual
+    );
+}
+
+fn ball(position: Vector3<f64>) -> RigidBody {
+    let sphere = Sphere::new(1.0);
+    let mut body = RigidBody::new(position, 1.0, &sphere.inertia_tensor(1.0));
+    body.calculate_derived_data();
+    body
+}
+
+#[test]
+fn sphere_and_sphere_contacts() {
+    let bodies = vec![
+        ball(Vector3::new(0.0, 0.0, 0.0)),
+        ball(Vector3::new(1.5, 0.0, 0.0)),
+        ball(Vector3::new(5.0, 0.0, 0.0)),
+    ];
+    let mut colliders: Vec<Collider<Sphere>> = (0..3)
+        .map(|body| Collider::new(body, Sphere::new(1.0)))
+        .collect();
+    for collider in colliders.iter_mut() {
+        collider.calculate_internals(&bodies);
+    }
+
+    let mut data = CollisionData::new(10);
+    data.restitution = 0.5;
+    assert_eq!(
+        1,
+        sphere_and_sphere(&colliders[1], &colliders[0], &mut data)
+    );
+    assert_eq!(
+        0,
+        sphere_and_sphere(&colliders[1], &colliders[2], &mut data)
+    );
+    assert_eq!(1, data.contacts.len());
+
+    let contact = data.contacts[0];
+    assert_eq!((1, Some(0)), contact.bodies);
+    assert_vector_eq(Vector3::new(1.0, 0.0, 0.0), contact.contact_normal);
+    assert_vector_eq(Vector3::new(0.75, 0.0, 0.0), contact.contact_point);
+    assert!((contact.penetration - 0.5).abs() < 1e-12);
+    assert_eq!(0.5, contact.restitution);
+
+    // No more contacts are generated once the limit is reached.
+    let mut data = CollisionData::new(0);
+    assert_eq!(
+        0,
+        sphere_and_sphere(&colliders[1], &colliders[0], &mut data)
+    );
+    assert_eq!(0, data.contacts_left());
+}
+
+#[test]
+fn offset_colliders() {
+    let mut bodies = vec![
+        ball(Vector3::new(0.0, 0.0, 0.0)),
+        ball(Vector3::new(0.0, 3.0, 0.0)),
+    ];
+    bodies[0].orientation =
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2);
+    bodies[0].calculate_derived_data();
+
+    // The sphere sits two units along the local x axis, which is rotated onto the world y axis.
+    let offset = Matrix4::from_orientation_and_position(
+        &Quaternion::identity(),
+        &Vector3::new(2.0, 0.0, 0.0),
+    );
+    let mut one = Collider::with_offset(0, Sphere::new(1.0), offset);
+    let mut two = Collider::new(1, Sphere::new(0.5));
+    one.calculate_internals(&bodies);
+    two.calculate_internals(&bodies);
+    assert_vector_eq(Vector3::new(0.0, 2.0, 0.0), one.position());
+
+    let mut data = CollisionData::new(1);
+    assert_eq!(1, sphere_and_sphere(&one, &two, &mut data));
+    assert_vector_eq(
+        Vector3::new(0.0, -1.0, 0.0),
+        data.contacts[0].contact_normal,
+    );
+    assert!((data.contacts[0].penetration - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn sphere_and_plane_contacts() {
+    let bodies = vec![
+        ball(Vector3::new(0.0, 0.5, 0.0)),
+        ball(Vector3::new(0.0, -0.5, 0.0)),
+        ball(Vector3::new(0.0, 2.0, 0.0)),
+    ];
+    let colliders: Vec<Collider<Sphere>> = (0..3)
+        .map(|body| {
+            let mut collider = Collider::new(body, Sphere::new(1.0));
+            collider.calculate_internals(&bodies);
+            collider
+        })
+        .collect();
+    let ground = Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0);
+
+    let mut data = CollisionData::new(10);
+    assert_eq!(1, sphere_and_half_space(&colliders[0], &ground, &mut data));
+    assert_eq!(1, sphere_and_half_space(&colliders[1], &ground, &mut data));
+    assert_eq!(0, sphere_and_half_space(&colliders[2], &ground, &mut data));
+
+    // Half-spaces always push up, no matter how deep the sphere is.
+    assert_eq!((0, None), data.contacts[0].bodies);
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), data.contacts[0].contact_normal);
+    assert_vector_eq(Vector3::new(0.0, 0.0, 0.0), data.contacts[0].contact_point);
+    assert!((data.contacts[0].penetration - 0.5).abs() < 1e-12);
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), data.contacts[1].contact_normal);
+    assert!((data.contacts[1].penetration - 1.5).abs() < 1e-12);
+
+    // True planes push towards the side of the center.
+    data.reset();
+    assert_eq!(1, sphere_and_true_plane(&colliders[0], &ground, &mut data));
+    assert_eq!(1, sphere_and_true_plane(&colliders[1], &ground, &mut data));
+    assert_eq!(0, sphere_and_true_plane(&colliders[2], &ground, &mut data));
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), data.contacts[0].contact_normal);
+    assert!((data.contacts[0].penetration - 0.5).abs() < 1e-12);
+    assert_vector_eq(
+        Vector3::new(0.0, -1.0, 0.0),
+        data.contacts[1].contact_normal,
+    );
+    assert_vector_eq(Vector3::new(0.0, 0.0, 0.0), data.contacts[1].contact_point);
+    assert!((data.contacts[1].penetration - 0.5).abs() < 1e-12);
+}
+
+fn block(position: Vector3<f64>, orientation: Quaternion<f64>) -> RigidBody {
+    let cuboid = Cuboid::new(Vector3::new(1.0, 1.0, 1.0));
+    let mut body = RigidBody::new(position, 1.0, &cuboid.inertia_tensor(1.0));
+    body.orientation = orientation;
+    body.calculate_derived_data();
+    body
+}
+
+fn colliders(bodies: &[RigidBody], half_size: &[f64]) -> Vec<Collider<Cuboid>> {
+    half_size
+        .iter()
+        .enumerate()
+        .map(|(body, half_size)| {
+            let shape = Cuboid::new(Vector3::new(*half_size, *half_size, *half_size));
+            let mut collider = Collider::new(body, shape);
+            collider.calculate_internals(bodies);
+            collider
+        })
+        .collect()
+}
+
+#[test]
+fn box_and_half_space_contacts() {
+    let bodies = vec![block(Vector3::new(0.0, 0.9, 0.0), Quaternion::identity())];
+    let colliders = colliders(&bodies, &[1.0]);
+    let ground = Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0);
+
+    let mut data = CollisionData::new(10);
+    assert_eq!(4, box_and_half_space(&colliders[0], &ground, &mut data));
+    for contact in data.contacts.iter() {
+        assert_eq!((0, None), contact.bodies);
+        assert_eq!(0.0, contact.contact_point.y);
+        assert!((contact.penetration - 0.1).abs() < 1e-12);
+    }
+
+    let mut data = CollisionData::new(2);
+    assert_eq!(2, box_and_half_space(&colliders[0], &ground, &mut data));
+}
+
+#[test]
+fn box_and_box_face_contacts() {
+    let bodies = vec![
+        block(Vector3::new(0.0, 0.0, 0.0), Quaternion::identity()),
+        block(Vector3::new(0.2, 1.9, -0.3), Quaternion::identity()),
+        block(Vector3::new(0.0, 2.1, 0.0), Quaternion::identity()),
+    ];
+    let colliders = colliders(&bodies, &[1.0, 1.0, 1.0]);
+
+    // Stacked boxes touch along a whole face.
+    let mut data = CollisionData::new(10);
+    assert_eq!(4, box_and_box(&colliders[1], &colliders[0], &mut data));
+    for contact in data.contacts.iter() {
+        assert_eq!((1, Some(0)), contact.bodies);
+        assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), contact.contact_normal);
+        assert!((contact.penetration - 0.1).abs() < 1e-12);
+        assert!((contact.contact_point.y - 0.95).abs() < 1e-12);
+        assert!(contact.contact_point.x >= -0.8 - 1e-12 && contact.contact_point.x <= 1.0 + 1e-12);
+        assert!(contact.contact_point.z >= -1.0 - 1e-12 && contact.contact_point.z <= 0.7 + 1e-12);
+    }
+
+    // The order of the boxes flips the normal.
+    data.reset();
+    assert_eq!(4, box_and_box(&colliders[0], &colliders[1], &mut data));
+    assert_eq!((0, Some(1)), data.contacts[0].bodies);
+    assert_vector_eq(
+        Vector3::new(0.0, -1.0, 0.0),
+        data.contacts[0].contact_normal,
+    );
+
+    data.reset();
+    assert_eq!(0, box_and_box(&colliders[2], &colliders[0], &mut data));
+}
+
+#[test]
+fn box_and_box_clipped_manifold() {
+    // A box turned 45 degrees over a smaller one: clipping produces an octagon,
+    // which is reduced to four points.
+    let turn =
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), std::f64::consts::FRAC_PI_4);
+    let bodies = vec![
+        block(Vector3::new(0.0, 1.7, 0.0), turn),
+        block(Vector3::new(0.0, 0.0, 0.0), Quaternion::identity()),
+    ];
+    let colliders = colliders(&bodies, &[1.0, 0.8]);
+
+    let mut data = CollisionData::new(10);
+    assert_eq!(4, box_and_box(&colliders[0], &colliders[1], &mut data));
+    for contact in data.contacts.iter() {
+        assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), contact.contact_normal);
+        assert!((contact.penetration - 0.1).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn box_and_box_edge_contact() {
+    // Two boxes standing on edges, crossed at right angles.
+    let bodies = vec![
+        block(
+            Vector3::new(0.0, 0.0, 0.0),
+            Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_4),
+        ),
+        block(
+            Vector3::new(0.0, 2.0 * std::f64::consts::SQRT_2 - 0.1, 0.0),
+            Quaternion::from_axis_angle(&Vector3::new(1.0, 0.0, 0.0), std::f64::consts::FRAC_PI_4),
+        ),
+    ];
+    let colliders = colliders(&bodies, &[1.0, 1.0]);
+
+    let mut data = CollisionData::new(10);
+    assert_eq!(1, box_and_box(&colliders[1], &colliders[0], &mut data));
+    let contact = data.contacts[0];
+    assert_eq!((1, Some(0)), contact.bodies);
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), contact.contact_normal);
+    assert_vector_eq(
+        Vector3::new(0.0, std::f64::consts::SQRT_2 - 0.05, 0.0),
+        contact.contact_point,
+    );
+    assert!((contact.penetration - 0.1).abs() < 1e-12);
+}
+
+fn capsule(
+    bodies: &mut Vec<RigidBody>,
+    position: Vector3<f64>,
+    orientation: Quaternion<f64>,
+) -> Collider<Capsule> {
+    let shape = Capsule::new(1.0, 0.5);
+    let mut body = RigidBody::new(position, 1.0, &shape.inertia_tensor(1.0));
+    body.orientation = orientation;
+    body.calculate_derived_data();
+    bodies.push(body);
+
+    let mut collider = Collider::new(bodies.len() - 1, shape);
+    collider.calculate_internals(bodies);
+    collider
+}
+
+#[test]
+fn capsule_contacts() {
+    let lying =
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2);
+    let mut bodies = Vec::new();
+    let standing = capsule(
+        &mut bodies,
+        Vector3::new(0.0, 0.0, 0.0),
+        Quaternion::identity(),
+    );
+    let parallel = capsule(
+        &mut bodies,
+        Vector3::new(0.9, 0.5, 0.0),
+        Quaternion::identity(),
+    );
+    let crossed = capsule(&mut bodies, Vector3::new(0.0, 1.6, 0.0), lying);
+    bodies.push(ball(Vector3::new(0.0, -2.0, 0.0)));
+    let mut sphere = Collider::new(3, Sphere::new(1.0));
+    sphere.calculate_internals(&bodies);
+
+    // Capsule against sphere, at the bottom cap.
+    let mut data = CollisionData::new(10);
+    assert_eq!(1, capsule_and_sphere(&standing, &sphere, &mut data));
+    assert_eq!((0, Some(3)), data.contacts[0].bodies);
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), data.contacts[0].contact_normal);
+    assert!((data.contacts[0].penetration - 0.5).abs() < 1e-12);
+
+    // Capsules side by side touch at both ends of the overlap.
+    data.reset();
+    assert_eq!(2, capsule_and_capsule(&parallel, &standing, &mut data));
+    for contact in data.contacts.iter() {
+        assert_eq!((1, Some(0)), contact.bodies);
+        assert_vector_eq(Vector3::new(1.0, 0.0, 0.0), contact.contact_normal);
+        assert!((contact.penetration - 0.1).abs() < 1e-12);
+    }
+    assert!((data.contacts[0].contact_point.y + 0.5).abs() < 1e-12);
+    assert!((data.contacts[1].contact_point.y - 1.0).abs() < 1e-12);
+
+    // Crossed capsules touch at a single point.
+    data.reset();
+    assert_eq!(1, capsule_and_capsule(&crossed, &standing, &mut data));
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), data.contacts[0].contact_normal);
+    assert_vector_eq(Vector3::new(0.0, 1.3, 0.0), data.contacts[0].contact_point);
+    assert!((data.contacts[0].penetration - 0.4).abs() < 1e-12);
+
+    // Capsules against planes touch at both ends when lying down.
+    data.reset();
+    let ground = Plane::new(Vector3::new(0.0, 1.0, 0.0), 1.2);
+    assert_eq!(2, capsule_and_half_space(&crossed, &ground, &mut data));
+    assert_eq!(
+        1,
+        capsule_and_half_space(
+            &standing,
+            &Plane::new(Vector3::new(0.0, 1.0, 0.0), -1.2),
+            &mut data
+        )
+    );
+    assert!((data.contacts[0].penetration - 0.1).abs() < 1e-12);
+    assert!((data.contacts[2].penetration - 0.3).abs() < 1e-12);
+}
+
+#[test]
+fn box_and_sphere_contacts() {
+    let bodies = vec![
+        block(Vector3::new(0.0, 0.0, 0.0), Quaternion::identity()),
+        ball(Vector3::new(1.5, 0.5, 0.0)),
+        ball(Vector3::new(0.0, 0.7, 0.0)),
+    ];
+    let cuboid = colliders(&bodies, &[1.0]).remove(0);
+    let spheres: Vec<Collider<Sphere>> = (1..3)
+        .map(|body| {
+            let mut collider = Collider::new(body, Sphere::new(1.0));
+            collider.calculate_internals(&bodies);
+            collider
+        })
+        .collect();
+
+    let mut data = CollisionData::new(10);
+    assert_eq!(1, box_and_sphere(&cuboid, &spheres[0], &mut data));
+    assert_eq!((0, Some(1)), data.contacts[0].bodies);
+    assert_vector_eq(
+        Vector3::new(-1.0, 0.0, 0.0),
+        data.contacts[0].contact_normal,
+    );
+    assert_vector_eq(Vector3::new(1.0, 0.5, 0.0), data.contacts[0].contact_point);
+    assert!((data.contacts[0].penetration - 0.5).abs() < 1e-12);
+
+    // Spheres centered inside the box are pushed out through the nearest face.
+    assert_eq!(1, box_and_sphere(&cuboid, &spheres[1], &mut data));
+    assert_vector_eq(
+        Vector3::new(0.0, -1.0, 0.0),
+        data.contacts[1].contact_normal,
+    );
+    assert!((data.contacts[1].penetration - 1.3).abs() < 1e-12);
+}
+
+#[test]
+fn compound_contacts() {
+    // A dumbbell: two spheres joined along the x axis.
+    let mut dumbbell = Compound::new();
+    dumbbell
+        .add(
+            Matrix4::from_orientation_and_position(
+                &Quaternion::identity(),
+                &Vector3::new(-2.0, 0.0, 0.0),
+            ),
+            Shape::Sphere(Sphere::new(1.0)),
+        )
+        .add(
+            Matrix4::from_orientation_and_position(
+                &Quaternion::identity(),
+                &Vector3::new(2.0, 0.0, 0.0),
+            ),
+            Shape::Sphere(Sphere::new(1.0)),
+        );
+    let bodies = vec![
+        ball(Vector3::new(0.0, 0.9, 0.0)),
+        ball(Vector3::new(2.0, 2.5, 0.0)),
+    ];
+    let mut compound = Collider::new(0, Shape::compound(dumbbell));
+    let mut sphere = Collider::new(1, Shape::Sphere(Sphere::new(1.0)));
+    compound.calculate_internals(&bodies);
+    sphere.calculate_internals(&bodies);
+
+    // Both ends rest on the ground.
+    let mut data = CollisionData::new(10);
+    let ground = Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0);
+    assert_eq!(2, collide_with_half_space(&compound, &ground, &mut data));
+    assert_vector_eq(Vector3::new(-2.0, 0.0, 0.0), data.contacts[0].contact_point);
+    assert_vector_eq(Vector3::new(2.0, 0.0, 0.0), data.contacts[1].contact_point);
+
+    // Only one end touches the sphere, and the contact is seen from the first collider.
+    data.reset();
+    assert_eq!(1, collide(&compound, &sphere, &mut data));
+    assert_eq!((0, Some(1)), data.contacts[0].bodies);
+    assert_vector_eq(
+        Vector3::new(0.0, -1.0, 0.0),
+        data.contacts[0].contact_normal,
+    );
+    assert_eq!(1, collide(&sphere, &compound, &mut data));
+    assert_eq!((1, Some(0)), data.contacts[1].bodies);
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), data.contacts[1].contact_normal);
+    assert!((data.contacts[1].penetration - 0.4).abs() < 1e-12);
+}
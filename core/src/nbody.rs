@@ -0,0 +1,295 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::force::inverse_square_force;
+use crate::rigid_body::RigidBody;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Universal gravitational constant, in N m^2 / kg^2.
+pub const GRAVITATIONAL_CONSTANT: f64 = 6.674_30e-11;
+
+/// Maximum depth of the octree. Bodies sharing a cell at this depth are
+/// handled pairwise, which bounds the tree size for coincident bodies.
+const MAX_DEPTH: usize = 32;
+
+/// Node of the Barnes-Hut octree.
+struct Node<F: num_traits::Float> {
+    center: Vector3<F>,
+    half_size: F,
+    mass: F,
+    weighted_position: Vector3<F>,
+    first_child: Option<usize>,
+    entries: Vec<usize>,
+}
+
+impl<F: num_traits::Float> Node<F> {
+    fn new(center: Vector3<F>, half_size: F) -> Self {
+        Self {
+            center,
+            half_size,
+            mass: num_traits::zero(),
+            weighted_position: Vector3::origin(),
+            first_child: None,
+            entries: Vec::new(),
+        }
+    }
+
+    fn center_of_mass(&self) -> Vector3<F> {
+        self.weighted_position.scalar_div(self.mass)
+    }
+
+    fn contains(&self, point: &Vector3<F>) -> bool {
+        (point.x - self.center.x).abs() <= self.half_size
+            && (point.y - self.center.y).abs() <= self.half_size
+            && (point.z - self.center.z).abs() <= self.half_size
+    }
+
+    fn octant(&self, point: &Vector3<F>) -> usize {
+        let mut octant = 0;
+        if point.x >= self.center.x {
+            octant |= 1;
+        }
+        if point.y >= self.center.y {
+            octant |= 2;
+        }
+        if point.z >= self.center.z {
+            octant |= 4;
+        }
+        octant
+    }
+}
+
+/// Octree holding the mass distribution of a set of point masses.
+struct Octree<F: num_traits::Float> {
+    nodes: Vec<Node<F>>,
+}
+
+impl<F: num_traits::Float> Octree<F> {
+    fn build(points: &[(Vector3<F>, F)]) -> Self {
+        let mut min = points[0].0;
+        let mut max = points[0].0;
+        for (point, _) in points.iter() {
+            min = Vector3::new(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z));
+            max = Vector3::new(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z));
+        }
+
+        let half: F = math::real(0.5);
+        let extents = max.vector_sub(&min);
+        let half_size = extents.x.max(extents.y).max(extents.z) * half + F::epsilon();
+        let center = min.vector_add(&max).scalar_mul(half);
+
+        let mut tree = Self {
+            nodes: vec![Node::new(center, half_size)],
+        };
+        for entry in 0..points.len() {
+            tree.insert(entry, points);
+        }
+        tree
+    }
+
+    fn insert(&mut self, entry: usize, points: &[(Vector3<F>, F)]) {
+        let (position, mass) = points[entry];
+        let mut node = 0;
+        let mut depth = 0;
+
+        loop {
+            self.nodes[node].mass = self.nodes[node].mass + mass;
+            self.nodes[node]
+                .weighted_position
+                .inplace_vector_add(&position.scalar_mul(mass));
+
+            if let Some(first) = self.nodes[node].first_child {
+                node = first + self.nodes[node].octant(&position);
+                depth += 1;
+                continue;
+            }
+
+            if self.nodes[node].entries.is_empty() || depth >= MAX_DEPTH {
+                self.nodes[node].entries.push(entry);
+                return;
+            }
+
+            // Occupied leaf, subdivide it and push the existing entries down.
+            let first = self.subdivide(node);
+            for existing in std::mem::take(&mut self.nodes[node].entries) {
+                let (existing_position, existing_mass) = points[existing];
+                let octant = self.nodes[node].octant(&existing_position);
+                let child = &mut self.nodes[first + octant];
+                child.mass = child.mass + existing_mass;
+                child
+                    .weighted_position
+                    .inplace_vector_add(&existing_position.scalar_mul(existing_mass));
+                child.entries.push(existing);
+            }
+
+            node = first + self.nodes[node].octant(&position);
+            depth += 1;
+        }
+    }
+
+    fn subdivide(&mut self, node: usize) -> usize {
+        let first = self.nodes.len();
+        let half_size = self.nodes[node].half_size * math::real(0.5);
+        let center = self.nodes[node].center;
+        for octant in 0..8 {
+            let offset = |bit: usize| {
+                if octant & bit != 0 {
+                    half_size
+                } else {
+                    -half_size
+                }
+            };
+            self.nodes.push(Node::new(
+                center.vector_add(&Vector3::new(offset(1), offset(2), offset(4))),
+                half_size,
+            ));
+        }
+        self.nodes[node].first_child = Some(first);
+        first
+    }
+}
+
+/// Mutual gravitational attraction between all the registered rigid bodies,
+/// approximated with the Barnes-Hut algorithm.
+///
+/// # Remarks
+/// At each update an octree is built with the mass distribution of the bodies.
+/// Distant groups of bodies are approximated by their center of mass whenever the ratio
+/// between the size of their octree cell and their distance is below `theta`,
+/// bringing the cost from `O(n^2)` down to `O(n log n)`. A `theta` of `0` computes
+/// the exact (brute force) interaction between every pair of bodies.
+///
+/// Bodies with infinite mass are ignored.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NBodyGravity<F: num_traits::Float = f64> {
+    /// Indices of the rigid bodies affected by the mutual gravity.
+    pub bodies: Vec<usize>,
+
+    /// Gravitational constant used by the force law.
+    pub gravitational_constant: F,
+
+    /// Opening angle threshold of the Barnes-Hut approximation.
+    pub theta: F,
+
+    /// Softening length used to avoid the singularity at zero distance.
+    pub softening: F,
+}
+
+impl<F: num_traits::Float> NBodyGravity<F> {
+    /// Creates a new N-body gravity system with the universal gravitational constant.
+    pub fn new(theta: F, softening: F) -> Self {
+        Self::with_constant(math::real(GRAVITATIONAL_CONSTANT), theta, softening)
+    }
+
+    /// Creates a new N-body gravity system with the given gravitational constant.
+    pub fn with_constant(gravitational_constant: F, theta: F, softening: F) -> Self {
+        Self {
+            bodies: Vec::new(),
+            gravitational_constant,
+            theta,
+            softening,
+        }
+    }
+
+    /// Registers the rigid body at index `body` into the system.
+    pub fn add(&mut self, body: usize) {
+        self.bodies.push(body);
+    }
+
+    /// Removes the rigid body at index `body` from the system.
+    pub fn remove(&mut self, body: usize) {
+        self.bodies.retain(|index| *index != body);
+    }
+
+    /// Calculates the gravitational force acting on each of the registered bodies.
+    /// The returned forces are in the same order as the registered bodies.
+    pub fn forces(&self, bodies: &[RigidBody<F>]) -> Vec<Vector3<F>> {
+        let mut forces = vec![Vector3::origin(); self.bodies.len()];
+
+        let (registered, points): (Vec<usize>, Vec<(Vector3<F>, F)>) = self
+            .bodies
+            .iter()
+            .enumerate()
+            .filter(|(_, body)| bodies[**body].has_finite_mass())
+            .map(|(index, body)| (index, (bodies[*body].position, bodies[*body].mass())))
+            .unzip();
+
+        if points.len() < 2 {
+            return forces;
+        }
+
+        let tree = Octree::build(&points);
+        let mut stack = Vec::new();
+        for (entry, (position, mass)) in points.iter().enumerate() {
+            let mut force = Vector3::origin();
+            stack.push(0);
+
+            while let Some(node) = stack.pop() {
+                let node = &tree.nodes[node];
+                if node.mass <= num_traits::zero() {
+                    continue;
+                }
+
+                match node.first_child {
+                    None => {
+                        for other in node.entries.iter().filter(|other| **other != entry) {
+                            let (other_position, other_mass) = points[*other];
+                            force.inplace_vector_add(&self.attraction(
+                                position,
+                                *mass,
+                                &other_position,
+                                other_mass,
+                            ));
+                        }
+                    }
+                    Some(first) => {
+                        let center_of_mass = node.center_of_mass();
+                        let distance = center_of_mass.vector_sub(position).magnitude();
+                        let size = node.half_size * math::real(2.0);
+                        if !node.contains(position) && size < self.theta * distance {
+                            force.inplace_vector_add(&self.attraction(
+                                position,
+                                *mass,
+                                &center_of_mass,
+                                node.mass,
+                            ));
+                        } else {
+                            stack.extend(first..first + 8);
+                        }
+                    }
+                }
+            }
+
+            forces[registered[entry]] = force;
+        }
+
+        forces
+    }
+
+    /// Adds the gravitational force acting on each of the registered bodies to their accumulators.
+    pub fn update_forces(&self, bodies: &mut [RigidBody<F>]) {
+        let forces = self.forces(bodies);
+        for (body, force) in self.bodies.iter().zip(forces.iter()) {
+            bodies[*body].add_force(force);
+        }
+    }
+
+    fn attraction(
+        &self,
+        position: &Vector3<F>,
+        mass: F,
+        other: &Vector3<F>,
+        other_mass: F,
+    ) -> Vector3<F> {
+        inverse_square_force(
+            position,
+            other,
+            -self.gravitational_constant * mass * other_mass,
+            self.softening,
+        )
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::nbody::*;
+use crate::rigid_body::RigidBody;
+use math::{Matrix3, Vector3};
+
+fn field(count: usize) -> Vec<RigidBody> {
+    // Deterministic pseudo-random asteroid field.
+    let mut seed = 12345u64;
+    let mut next = || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..count)
+        .map(|_| {
+            RigidBody::new(
+                Vector3::new(next() * 100.0, next() * 100.0, next() * 100.0),
+                1.0 + next() * 10.0,
+                &Matrix3::identity(),
+            )
+        })
+        .collect()
+}
+
+fn brute_force(bodies: &[RigidBody], constant: f64) -> Vec<Vector3<f64>> {
+    bodies
+        .iter()
+        .enumerate()
+        .map(|(index, body)| {
+            let mut force = Vector3::origin();
+            for (other_index, other) in bodies.iter().enumerate() {
+                if index != other_index {
+                    let offset = other.position - body.position;
+                    force += offset.normalize()
+                        * (constant * body.mass() * other.mass() / offset.squared_magnitude());
+                }
+            }
+            force
+        })
+        .collect()
+}
+
+#[test]
+fn two_bodies() {
+    let mut bodies = vec![
+        RigidBody::new(Vector3::origin(), 2.0, &Matrix3::identity()),
+        RigidBody::new(Vector3::new(0.0, 2.0, 0.0), 3.0, &Matrix3::identity()),
+    ];
+    let mut gravity = NBodyGravity::with_constant(1.0, 0.5, 0.0);
+    gravity.add(0);
+    gravity.add(1);
+    gravity.update_forces(&mut bodies);
+    assert_eq!(Vector3::new(0.0, 1.5, 0.0), bodies[0].force_accum);
+    assert_eq!(Vector3::new(0.0, -1.5, 0.0), bodies[1].force_accum);
+
+    // Immovable bodies are ignored.
+    bodies[1].set_infinite_mass();
+    assert_eq!(vec![Vector3::origin(); 2], gravity.forces(&bodies));
+
+    gravity.remove(1);
+    assert_eq!(vec![0], gravity.bodies);
+}
+
+#[test]
+fn barnes_hut() {
+    let bodies = field(200);
+    let expected = brute_force(&bodies, 1.0);
+
+    let mut gravity = NBodyGravity::with_constant(1.0, 0.0, 0.0);
+    for index in 0..bodies.len() {
+        gravity.add(index);
+    }
+
+    // A zero opening angle is the exact brute force solution.
+    for (expected, actual) in expected.iter().zip(gravity.forces(&bodies).iter()) {
+        assert!((*expected - *actual).magnitude() <= 1e-9 * expected.magnitude());
+    }
+
+    // Larger opening angles trade accuracy for speed.
+    gravity.theta = 0.5;
+    for (expected, actual) in expected.iter().zip(gravity.forces(&bodies).iter()) {
+        assert!((*expected - *actual).magnitude() <= 0.05 * expected.magnitude());
+    }
+}
+
+#[test]
+fn coincident_bodies() {
+    let bodies = vec![
+        RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()),
+        RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()),
+        RigidBody::new(Vector3::new(1.0, 0.0, 0.0), 1.0, &Matrix3::identity()),
+    ];
+    let mut gravity = NBodyGravity::with_constant(1.0, 0.5, 1.0);
+    gravity.bodies = vec![0, 1, 2];
+
+    let forces = gravity.forces(&bodies);
+    assert_eq!(forces[0], forces[1]);
+    assert!(forces[0].x > 0.0);
+    assert!(forces[2].x < 0.0);
+}
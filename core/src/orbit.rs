@@ -0,0 +1,513 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::rigid_body::BodyType;
+use crate::world::World;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Distance of the eccentricity to one below which orbits are treated as parabolas.
+const PARABOLIC_TOLERANCE: f64 = 1e-9;
+
+/// Eccentricity and inclination below which orbits are treated as circular and equatorial,
+/// measuring their angles from the X axis instead of the undefined periapsis and node.
+const DEGENERATE_TOLERANCE: f64 = 1e-11;
+
+/// Maximum number of Newton iterations solving Kepler's equation.
+const KEPLER_ITERATIONS: usize = 64;
+
+/// Shape and orientation of a two-body Keplerian orbit, and the position along it.
+///
+/// # Remarks
+/// Angles are in radians and measured in the reference frame with the XY plane as the
+/// reference plane and the Z axis as its pole. The size of the orbit is given by its
+/// semi-latus rectum, which is finite for every kind of conic: ellipses, parabolas and
+/// hyperbolas.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OrbitalElements<F: num_traits::Float = f64> {
+    /// Semi-latus rectum, the distance to the primary when a quarter turn past the periapsis.
+    pub semi_latus_rectum: F,
+
+    /// Eccentricity, zero for circles, below one for ellipses and above one for hyperbolas.
+    pub eccentricity: F,
+
+    /// Inclination of the orbital plane against the reference plane.
+    pub inclination: F,
+
+    /// Angle from the X axis to the ascending node, where the orbit crosses the reference
+    /// plane going up.
+    pub longitude_of_ascending_node: F,
+
+    /// Angle from the ascending node to the periapsis, along the orbit.
+    pub argument_of_periapsis: F,
+
+    /// Angle from the periapsis to the orbiting body, along the orbit.
+    pub true_anomaly: F,
+}
+
+/// Orbit of a body around a primary, attracted only by it, which can be propagated
+/// analytically.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Orbit<F: num_traits::Float = f64> {
+    /// Elements of the orbit, at the current position of the orbiting body.
+    pub elements: OrbitalElements<F>,
+
+    /// Gravitational parameter of the primary, its mass times the gravitational constant.
+    ///
+    /// # Remarks
+    /// When the mass of the orbiting body isn't negligible, the sum of both masses times the
+    /// gravitational constant describes their relative motion.
+    pub gravitational_parameter: F,
+}
+
+impl<F: num_traits::Float> Orbit<F> {
+    /// Creates a new orbit with the given elements around a primary with the given
+    /// gravitational parameter.
+    pub fn new(elements: OrbitalElements<F>, gravitational_parameter: F) -> Self {
+        Self {
+            elements,
+            gravitational_parameter,
+        }
+    }
+
+    /// Creates the orbit a body follows from its position and velocity relative to a primary
+    /// with the given gravitational parameter.
+    ///
+    /// # Remarks
+    /// Angles that are undefined for circular or equatorial orbits are measured from the
+    /// X axis instead: the ascending node of equatorial orbits is placed on it, and the
+    /// periapsis of circular orbits is placed on the ascending node.
+    pub fn from_state(
+        position: &Vector3<F>,
+        velocity: &Vector3<F>,
+        gravitational_parameter: F,
+    ) -> Self {
+        let mu = gravitational_parameter;
+        let distance = position.magnitude();
+        let momentum = position.cross_product(velocity);
+        let pole = momentum.normalize();
+
+        let eccentricity = position
+            .scalar_mul(velocity.squared_magnitude() - mu / distance)
+            .vector_sub(&velocity.scalar_mul(position.dot_product(velocity)))
+            .scalar_div(mu);
+
+        let degenerate = math::real::<F>(DEGENERATE_TOLERANCE);
+        let node = Vector3::new(F::zero(), F::zero(), F::one()).cross_product(&momentum);
+        let node = if node.magnitude() > degenerate * momentum.magnitude() {
+            node.normalize()
+        } else {
+            Vector3::new(F::one(), F::zero(), F::zero())
+        };
+        let periapsis = if eccentricity.magnitude() > degenerate {
+            eccentricity.normalize()
+        } else {
+            node
+        };
+
+        Self {
+            elements: OrbitalElements {
+                semi_latus_rectum: momentum.squared_magnitude() / mu,
+                eccentricity: eccentricity.magnitude(),
+                inclination: pole.z.max(-F::one()).min(F::one()).acos(),
+                longitude_of_ascending_node: node.y.atan2(node.x),
+                argument_of_periapsis: angle_between(&node, &periapsis, &pole),
+                true_anomaly: angle_between(&periapsis, position, &pole),
+            },
+            gravitational_parameter,
+        }
+    }
+
+    /// Returns the position and velocity of the orbiting body relative to the primary.
+    pub fn state(&self) -> (Vector3<F>, Vector3<F>) {
+        let elements = &self.elements;
+        let (sin, cos) = elements.true_anomaly.sin_cos();
+        let e = elements.eccentricity;
+        let p = elements.semi_latus_rectum;
+        let distance = p / (F::one() + e * cos);
+        let speed = (self.gravitational_parameter / p).sqrt();
+
+        let position = Vector3::new(distance * cos, distance * sin, F::zero());
+        let velocity = Vector3::new(-speed * sin, speed * (e + cos), F::zero());
+        (
+            self.rotate_to_reference(&position),
+            self.rotate_to_reference(&velocity),
+        )
+    }
+
+    /// Returns the position and velocity of the orbiting body relative to the primary,
+    /// after the given time (in seconds) has passed.
+    pub fn state_after(&self, duration: F) -> (Vector3<F>, Vector3<F>) {
+        self.propagate(duration).state()
+    }
+
+    /// Returns the orbit with the orbiting body moved along it for the given time (in seconds),
+    /// solving Kepler's equation.
+    ///
+    /// # Remarks
+    /// Negative durations move the body back in time.
+    pub fn propagate(&self, duration: F) -> Self {
+        let e = self.elements.eccentricity;
+        let mean_anomaly = self.mean_anomaly() + self.mean_motion() * duration;
+        let true_anomaly = if self.is_parabolic() {
+            // Barker's equation, solved in closed form.
+            let half = math::real::<F>(1.5) * mean_anomaly;
+            let root = (half + (half * half + F::one()).sqrt()).cbrt();
+            (root - root.recip()).atan() * math::real(2.0)
+        } else if e < F::one() {
+            let pi = math::real::<F>(std::f64::consts::PI);
+            let tau = pi + pi;
+            let mean_anomaly = mean_anomaly - tau * ((mean_anomaly + pi) / tau).floor();
+            let mut anomaly = if e <= math::real(0.8) {
+                mean_anomaly
+            } else if mean_anomaly < F::zero() {
+                -pi
+            } else {
+                pi
+            };
+            for _ in 0..KEPLER_ITERATIONS {
+                let (sin, cos) = anomaly.sin_cos();
+                let step = (anomaly - e * sin - mean_anomaly) / (F::one() - e * cos);
+                anomaly = anomaly - step;
+                if step.abs() <= F::epsilon() * tau {
+                    break;
+                }
+            }
+            let half = anomaly * math::real(0.5);
+            ((F::one() + e).sqrt() * half.sin()).atan2((F::one() - e).sqrt() * half.cos())
+                * math::real(2.0)
+        } else {
+            let mut anomaly = (mean_anomaly / e).asinh();
+            for _ in 0..KEPLER_ITERATIONS {
+                let step =
+                    (e * anomaly.sinh() - anomaly - mean_anomaly) / (e * anomaly.cosh() - F::one());
+                anomaly = anomaly - step;
+                if step.abs() <= F::epsilon() * anomaly.abs().max(F::one()) {
+                    break;
+                }
+            }
+            (((e + F::one()) / (e - F::one())).sqrt() * (anomaly * math::real(0.5)).tanh()).atan()
+                * math::real(2.0)
+        };
+
+        Self {
+            elements: OrbitalElements {
+                true_anomaly,
+                ..self.elements
+            },
+            ..*self
+        }
+    }
+
+    /// Returns the mean anomaly of the orbiting body, growing linearly with time.
+    ///
+    /// # Remarks
+    /// Elliptic orbits use `M = E - e·sin(E)` from the eccentric anomaly `E`, hyperbolic orbits
+    /// `M = e·sinh(H) - H` from the hyperbolic anomaly `H`, and parabolic orbits
+    /// `M = D + D³/3` from `D = tan(ν/2)`.
+    pub fn mean_anomaly(&self) -> F {
+        let e = self.elements.eccentricity;
+        let (sin, cos) = self.elements.true_anomaly.sin_cos();
+        if self.is_parabolic() {
+            let tangent = (self.elements.true_anomaly * math::real(0.5)).tan();
+            tangent + tangent * tangent * tangent / math::real(3.0)
+        } else if e < F::one() {
+            let anomaly = ((F::one() - e * e).sqrt() * sin).atan2(e + cos);
+            anomaly - e * anomaly.sin()
+        } else {
+            let anomaly = ((e * e - F::one()).sqrt() * sin / (F::one() + e * cos)).asinh();
+            e * anomaly.sinh() - anomaly
+        }
+    }
+
+    /// Returns the rate at which the mean anomaly grows (in radians per second).
+    pub fn mean_motion(&self) -> F {
+        let mu = self.gravitational_parameter;
+        if self.is_parabolic() {
+            let p = self.elements.semi_latus_rectum;
+            (mu / (p * p * p)).sqrt() * math::real(2.0)
+        } else {
+            let a = self.semi_major_axis().abs();
+            (mu / (a * a * a)).sqrt()
+        }
+    }
+
+    /// Returns the semi-major axis of the orbit.
+    ///
+    /// # Remarks
+    /// It's negative for hyperbolic orbits, and infinite for parabolic ones.
+    pub fn semi_major_axis(&self) -> F {
+        if self.is_parabolic() {
+            F::infinity()
+        } else {
+            let e = self.elements.eccentricity;
+            self.elements.semi_latus_rectum / (F::one() - e * e)
+        }
+    }
+
+    /// Returns the closest distance to the primary along the orbit.
+    pub fn periapsis(&self) -> F {
+        self.elements.semi_latus_rectum / (F::one() + self.elements.eccentricity)
+    }
+
+    /// Returns the farthest distance to the primary along the orbit, if it's bound.
+    pub fn apoapsis(&self) -> Option<F> {
+        if self.is_bound() {
+            Some(self.elements.semi_latus_rectum / (F::one() - self.elements.eccentricity))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the time (in seconds) it takes to go around the orbit, if it's bound.
+    pub fn period(&self) -> Option<F> {
+        if self.is_bound() {
+            Some(math::real::<F>(std::f64::consts::TAU) / self.mean_motion())
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the orbit is an ellipse, which the orbiting body never escapes.
+    pub fn is_bound(&self) -> bool {
+        !self.is_parabolic() && self.elements.eccentricity < F::one()
+    }
+
+    /// Returns whether the orbit is close enough to a parabola to be treated as one.
+    fn is_parabolic(&self) -> bool {
+        (self.elements.eccentricity - F::one()).abs() < math::real(PARABOLIC_TOLERANCE)
+    }
+
+    /// Rotates the given vector from the perifocal frame of the orbit, with the periapsis along
+    /// the X axis and the pole along the Z axis, into the reference frame.
+    fn rotate_to_reference(&self, vector: &Vector3<F>) -> Vector3<F> {
+        let elements = &self.elements;
+        let vector = rotate_z(vector, elements.argument_of_periapsis);
+        let (sin, cos) = elements.inclination.sin_cos();
+        let vector = Vector3::new(
+            vector.x,
+            vector.y * cos - vector.z * sin,
+            vector.y * sin + vector.z * cos,
+        );
+        rotate_z(&vector, elements.longitude_of_ascending_node)
+    }
+}
+
+/// Returns the given vector rotated around the Z axis by the given angle.
+fn rotate_z<F: num_traits::Float>(vector: &Vector3<F>, angle: F) -> Vector3<F> {
+    let (sin, cos) = angle.sin_cos();
+    Vector3::new(
+        vector.x * cos - vector.y * sin,
+        vector.x * sin + vector.y * cos,
+        vector.z,
+    )
+}
+
+/// Returns the angle to turn `from` into `to` around the given axis, between -π and π.
+fn angle_between<F: num_traits::Float>(from: &Vector3<F>, to: &Vector3<F>, axis: &Vector3<F>) -> F {
+    from.cross_product(to)
+        .dot_product(axis)
+        .atan2(from.dot_product(to))
+}
+
+/// Point a body on rails orbits around.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Primary<F: num_traits::Float = f64> {
+    /// Fixed point in world space.
+    Fixed(Vector3<F>),
+
+    /// Position of the rigid body at the given index, which can be on rails itself.
+    Body(usize),
+}
+
+/// Rigid body moved along its orbit instead of being simulated.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RailedBody<F: num_traits::Float = f64> {
+    /// Primary the body orbits around.
+    pub primary: Primary<F>,
+
+    /// Orbit the body follows, at the current time of the world.
+    pub orbit: Orbit<F>,
+
+    /// Distance to other bodies below which the body leaves the rails to be simulated.
+    pub encounter_radius: F,
+
+    /// Type of the body before it was put on rails, restored when it leaves them.
+    body_type: BodyType,
+}
+
+/// Rigid bodies following Keplerian orbits on rails, until they get close to other bodies.
+///
+/// # Remarks
+/// Bodies on rails are turned kinematic and given the velocity that takes them to the next
+/// point of their orbit every step, so they push the simulated bodies they run into. When
+/// any other body gets within their encounter radius, other than their primary and their
+/// own satellites, they're given back their type and their orbital velocity, to be simulated
+/// from then on. Encounters are patched into the N-body integrator by registering the
+/// bodies and their primaries in an `NBodyGravity`, which ignores them while they're on
+/// rails, since kinematic bodies have infinite mass. The primaries of the bodies leaving
+/// the rails should then be simulated too, for them to keep attracting their satellites.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct OrbitRails<F: num_traits::Float = f64> {
+    /// Bodies on rails, by index of the rigid body.
+    pub bodies: BTreeMap<usize, RailedBody<F>>,
+}
+
+impl<F: num_traits::Float> OrbitRails<F> {
+    /// Creates a new set of orbit rails without bodies.
+    pub fn new() -> Self {
+        Self {
+            bodies: BTreeMap::new(),
+        }
+    }
+
+    /// Puts the rigid body at index `body` on rails, along the orbit given by its position
+    /// and velocity relative to the primary, with the given gravitational parameter.
+    /// Returns the orbit.
+    pub fn attach(
+        &mut self,
+        world: &mut World<F>,
+        body: usize,
+        primary: Primary<F>,
+        gravitational_parameter: F,
+        encounter_radius: F,
+    ) -> Orbit<F> {
+        let (center, center_velocity) = self.primary_state(world, &primary);
+        let rigid_body = &mut world.bodies[body];
+        let orbit = Orbit::from_state(
+            &rigid_body.position.vector_sub(&center),
+            &rigid_body.velocity.vector_sub(&center_velocity),
+            gravitational_parameter,
+        );
+        let body_type = match self.bodies.get(&body) {
+            Some(railed) => railed.body_type,
+            None => rigid_body.body_type,
+        };
+        rigid_body.set_body_type(BodyType::Kinematic);
+        self.bodies.insert(
+            body,
+            RailedBody {
+                primary,
+                orbit,
+                encounter_radius,
+                body_type,
+            },
+        );
+        orbit
+    }
+
+    /// Takes the rigid body at index `body` off the rails, restoring its type and giving it
+    /// the velocity of its orbit. Returns the orbit it was following, if it was on rails.
+    pub fn detach(&mut self, world: &mut World<F>, body: usize) -> Option<Orbit<F>> {
+        let railed = self.bodies.get(&body).copied()?;
+        let (_, center_velocity) = self.primary_state(world, &railed.primary);
+        let (_, velocity) = railed.orbit.state();
+        self.bodies.remove(&body);
+
+        let rigid_body = &mut world.bodies[body];
+        rigid_body.set_body_type(railed.body_type);
+        rigid_body.velocity = velocity.vector_add(&center_velocity);
+        Some(railed.orbit)
+    }
+
+    /// Returns whether the rigid body at index `body` is on rails.
+    pub fn is_on_rails(&self, body: usize) -> bool {
+        self.bodies.contains_key(&body)
+    }
+
+    /// Moves the bodies on rails along their orbits for the given time (in seconds),
+    /// after taking off the rails those in an encounter. Returns the bodies taken off.
+    ///
+    /// # Remarks
+    /// This should be called before every step of the world, with the same duration, so the
+    /// velocities given to the bodies on rails take them to their next orbital positions.
+    /// Primaries can't orbit their own satellites.
+    pub fn advance(&mut self, world: &mut World<F>, duration: F) -> Vec<usize> {
+        let encounters: Vec<usize> = self
+            .bodies
+            .iter()
+            .filter(|(body, railed)| self.in_encounter(world, **body, railed))
+            .map(|(body, _)| *body)
+            .collect();
+        for body in encounters.iter() {
+            self.detach(world, *body);
+        }
+
+        let targets: Vec<(usize, Vector3<F>)> = self
+            .bodies
+            .keys()
+            .map(|body| (*body, self.target(world, &Primary::Body(*body), duration)))
+            .collect();
+        for (body, target) in targets {
+            let rigid_body = &mut world.bodies[body];
+            rigid_body.set_awake(true);
+            rigid_body.velocity = if duration > F::zero() {
+                target.vector_sub(&rigid_body.position).scalar_div(duration)
+            } else {
+                Vector3::origin()
+            };
+        }
+        for railed in self.bodies.values_mut() {
+            railed.orbit = railed.orbit.propagate(duration);
+        }
+
+        encounters
+    }
+
+    /// Returns whether any other body is within the encounter radius of the given body on
+    /// rails, other than its primary and its own satellites.
+    fn in_encounter(&self, world: &World<F>, body: usize, railed: &RailedBody<F>) -> bool {
+        let position = &world.bodies[body].position;
+        let radius = railed.encounter_radius;
+        world.bodies.iter().enumerate().any(|(other, rigid_body)| {
+            other != body
+                && railed.primary != Primary::Body(other)
+                && self
+                    .bodies
+                    .get(&other)
+                    .is_none_or(|satellite| satellite.primary != Primary::Body(body))
+                && rigid_body.position.vector_sub(position).squared_magnitude() < radius * radius
+        })
+    }
+
+    /// Returns the position and velocity of the given primary.
+    fn primary_state(&self, world: &World<F>, primary: &Primary<F>) -> (Vector3<F>, Vector3<F>) {
+        match primary {
+            Primary::Fixed(position) => (*position, Vector3::origin()),
+            Primary::Body(body) => {
+                let rigid_body = &world.bodies[*body];
+                let velocity = match self.bodies.get(body) {
+                    Some(railed) => {
+                        let (_, center_velocity) = self.primary_state(world, &railed.primary);
+                        railed.orbit.state().1.vector_add(&center_velocity)
+                    }
+                    None => rigid_body.velocity,
+                };
+                (rigid_body.position, velocity)
+            }
+        }
+    }
+
+    /// Returns the position of the given primary after the given time (in seconds), following
+    /// its orbit if it's on rails, or moving with its velocity otherwise.
+    fn target(&self, world: &World<F>, primary: &Primary<F>, duration: F) -> Vector3<F> {
+        match primary {
+            Primary::Fixed(position) => *position,
+            Primary::Body(body) => match self.bodies.get(body) {
+                Some(railed) => self
+                    .target(world, &railed.primary, duration)
+                    .vector_add(&railed.orbit.state_after(duration).0),
+                None => {
+                    let rigid_body = &world.bodies[*body];
+                    rigid_body
+                        .position
+                        .vector_add(&rigid_body.velocity.scalar_mul(duration))
+                }
+            },
+        }
+    }
+}
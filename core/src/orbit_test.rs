@@ -0,0 +1,271 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::nbody::NBodyGravity;
+use crate::orbit::*;
+use crate::rigid_body::{BodyType, RigidBody};
+use crate::world::{World, WorldConfig};
+use math::{Matrix3, Vector3};
+use std::f64::consts::PI;
+
+fn assert_vector_eq(expected: Vector3<f64>, actual: Vector3<f64>, tolerance: f64) {
+    assert!(
+        (expected - actual).magnitude() < tolerance,
+        "{:?} != {:?}",
+        expected,
+        actual
+    );
+}
+
+/// Integrates the motion of a body around a primary at the origin with fourth order
+/// Runge-Kutta, returning its final position.
+fn integrate(position: Vector3<f64>, velocity: Vector3<f64>, mu: f64, time: f64) -> Vector3<f64> {
+    let acceleration =
+        |position: Vector3<f64>| position * (-mu / position.squared_magnitude().powf(1.5));
+    let steps = 20000;
+    let step = time / steps as f64;
+    let (mut position, mut velocity) = (position, velocity);
+    for _ in 0..steps {
+        let (p1, v1) = (velocity, acceleration(position));
+        let (p2, v2) = (
+            velocity + v1 * (step * 0.5),
+            acceleration(position + p1 * (step * 0.5)),
+        );
+        let (p3, v3) = (
+            velocity + v2 * (step * 0.5),
+            acceleration(position + p2 * (step * 0.5)),
+        );
+        let (p4, v4) = (velocity + v3 * step, acceleration(position + p3 * step));
+        position += (p1 + p2 * 2.0 + p3 * 2.0 + p4) * (step / 6.0);
+        velocity += (v1 + v2 * 2.0 + v3 * 2.0 + v4) * (step / 6.0);
+    }
+    position
+}
+
+/// States of an inclined ellipse, a very eccentric ellipse, a hyperbola, a parabola and a
+/// retrograde circle, around a primary with a unit gravitational parameter.
+fn states() -> Vec<(Vector3<f64>, Vector3<f64>)> {
+    vec![
+        (Vector3::new(1.0, 0.5, 0.3), Vector3::new(-0.2, 0.9, 0.4)),
+        (
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.95f64.sqrt(), 0.0),
+        ),
+        (Vector3::new(1.0, 0.5, 0.3), Vector3::new(-0.2, 1.6, 0.6)),
+        (Vector3::new(2.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+        (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+    ]
+}
+
+#[test]
+fn elements() {
+    let circle = Orbit::from_state(
+        &Vector3::new(1.0, 0.0, 0.0),
+        &Vector3::new(0.0, 1.0, 0.0),
+        1.0,
+    );
+    assert_eq!(
+        OrbitalElements {
+            semi_latus_rectum: 1.0,
+            eccentricity: 0.0,
+            inclination: 0.0,
+            longitude_of_ascending_node: 0.0,
+            argument_of_periapsis: 0.0,
+            true_anomaly: 0.0,
+        },
+        circle.elements
+    );
+    assert_eq!(Some(2.0 * PI), circle.period());
+    assert_eq!(1.0, circle.semi_major_axis());
+
+    // Polar orbits cross the reference plane with the X axis as their ascending node.
+    let polar = Orbit::from_state(
+        &Vector3::new(1.0, 0.0, 0.0),
+        &Vector3::new(0.0, 0.0, 1.0),
+        1.0,
+    );
+    assert!((polar.elements.inclination - PI * 0.5).abs() < 1e-12);
+    assert!(polar.elements.longitude_of_ascending_node.abs() < 1e-12);
+
+    let orbits: Vec<Orbit> = states()
+        .iter()
+        .map(|(position, velocity)| Orbit::from_state(position, velocity, 1.0))
+        .collect();
+    assert!(orbits[0].is_bound());
+    assert!((orbits[1].periapsis() - 1.0).abs() < 1e-12);
+    assert!((orbits[1].apoapsis().unwrap() - 39.0).abs() < 1e-9);
+    assert!(orbits[2].elements.eccentricity > 1.0);
+    assert!(orbits[2].semi_major_axis() < 0.0);
+    assert_eq!(None, orbits[2].period());
+    assert!((orbits[3].elements.eccentricity - 1.0).abs() < 1e-12);
+    assert_eq!(f64::INFINITY, orbits[3].semi_major_axis());
+    assert_eq!(None, orbits[3].apoapsis());
+    assert!((orbits[4].elements.inclination - PI).abs() < 1e-12);
+
+    // The state is recovered from the elements.
+    for (orbit, (position, velocity)) in orbits.iter().zip(states().iter()) {
+        let state = orbit.state();
+        assert_vector_eq(*position, state.0, 1e-12);
+        assert_vector_eq(*velocity, state.1, 1e-12);
+    }
+}
+
+#[test]
+fn kepler_propagation() {
+    for (position, velocity) in states() {
+        let orbit = Orbit::from_state(&position, &velocity, 1.0);
+        assert_vector_eq(
+            integrate(position, velocity, 1.0, 3.0),
+            orbit.state_after(3.0).0,
+            1e-7,
+        );
+
+        // Going back in time undoes the propagation.
+        let back = orbit.propagate(3.0).propagate(-3.0).state();
+        assert_vector_eq(position, back.0, 1e-9);
+        assert_vector_eq(velocity, back.1, 1e-9);
+
+        // Bound orbits come back to the same state after every period.
+        if let Some(period) = orbit.period() {
+            assert_vector_eq(position, orbit.state_after(period * 5.0).0, 1e-8);
+        }
+    }
+
+    // Half a period away from the periapsis is the apoapsis.
+    let (position, velocity) = states()[1];
+    let eccentric = Orbit::from_state(&position, &velocity, 1.0);
+    let half = eccentric.propagate(eccentric.period().unwrap() * 0.5);
+    assert!((half.state().0.magnitude() - 39.0).abs() < 1e-9);
+    assert!((half.elements.true_anomaly.abs() - PI).abs() < 1e-9);
+}
+
+#[test]
+fn rails() {
+    let mut world = World::new(WorldConfig {
+        linear_damping: 1.0,
+        angular_damping: 1.0,
+        ..WorldConfig::default()
+    });
+    let planet = world.add_body(RigidBody::new(
+        Vector3::origin(),
+        1000.0,
+        &Matrix3::identity(),
+    ));
+    let planet = world.body_index(planet).unwrap();
+    let mut satellite = RigidBody::new(Vector3::new(10.0, 0.0, 0.0), 1e-6, &Matrix3::identity());
+    satellite.velocity = Vector3::new(0.0, 8.0, 6.0);
+    let satellite = world.add_body(satellite);
+    let satellite = world.body_index(satellite).unwrap();
+    let mut gravity = NBodyGravity::with_constant(1.0, 0.0, 0.0);
+    gravity.add(planet);
+    gravity.add(satellite);
+
+    let mut rails = OrbitRails::new();
+    let orbit = rails.attach(&mut world, satellite, Primary::Body(planet), 1000.0, 0.5);
+    assert!(rails.is_on_rails(satellite));
+    assert_eq!(BodyType::Kinematic, world.bodies[satellite].body_type);
+
+    let period = orbit.period().unwrap();
+    let duration = period / 720.0;
+    let step = |world: &mut World, rails: &mut OrbitRails| {
+        let left = rails.advance(world, duration);
+        world.start_frame();
+        gravity.update_forces(&mut world.bodies);
+        world.run_physics(duration);
+        left
+    };
+
+    // On rails, the satellite follows its orbit exactly.
+    for _ in 0..180 {
+        assert!(step(&mut world, &mut rails).is_empty());
+    }
+    assert_vector_eq(
+        orbit.state_after(period * 0.25).0,
+        world.bodies[satellite].position,
+        1e-9,
+    );
+    assert_vector_eq(Vector3::origin(), world.bodies[planet].position, 1e-12);
+
+    // Getting close to another body takes it off the rails, keeping its orbital velocity.
+    let asteroid = world.add_body(RigidBody::new(
+        orbit.state_after(period * 0.5).0,
+        1e-6,
+        &Matrix3::identity(),
+    ));
+    let asteroid = world.body_index(asteroid).unwrap();
+    let mut steps = 0;
+    while step(&mut world, &mut rails).is_empty() {
+        steps += 1;
+        assert!(steps < 180);
+    }
+    assert!(!rails.is_on_rails(satellite));
+    assert_eq!(BodyType::Dynamic, world.bodies[satellite].body_type);
+    let distance = world.bodies[satellite].position - world.bodies[asteroid].position;
+    assert!(distance.magnitude() < 0.5 + 10.0 * duration);
+    assert!((world.bodies[satellite].velocity.magnitude() - 10.0).abs() < 1e-3);
+
+    // Simulated by the N-body integrator, it stays close to the orbit.
+    let start = Orbit::from_state(
+        &world.bodies[satellite].position,
+        &world.bodies[satellite].velocity,
+        1000.0,
+    );
+    for _ in 0..180 {
+        step(&mut world, &mut rails);
+    }
+    assert_vector_eq(
+        start.state_after(duration * 180.0).0,
+        world.bodies[satellite].position,
+        0.1,
+    );
+
+    // Put back on rails, it follows the orbit it's in.
+    let back = rails.attach(&mut world, satellite, Primary::Body(planet), 1000.0, 0.1);
+    assert!((back.elements.semi_latus_rectum - 10.0).abs() < 0.1);
+    assert!(back.elements.eccentricity < 0.01);
+    assert!((back.elements.inclination - orbit.elements.inclination).abs() < 1e-3);
+    rails.detach(&mut world, satellite);
+    assert_eq!(BodyType::Dynamic, world.bodies[satellite].body_type);
+    assert_eq!(None, rails.detach(&mut world, satellite));
+}
+
+#[test]
+fn nested_rails() {
+    let mut world = World::<f64>::default();
+    let mut planet = RigidBody::new(Vector3::new(100.0, 0.0, 0.0), 1.0, &Matrix3::identity());
+    planet.velocity = Vector3::new(0.0, 1.0, 0.0);
+    let planet = world.add_body(planet);
+    let planet = world.body_index(planet).unwrap();
+    let mut moon = RigidBody::new(Vector3::new(102.0, 0.0, 0.0), 1.0, &Matrix3::identity());
+    moon.velocity = Vector3::new(0.0, 1.5, 0.0);
+    let moon = world.add_body(moon);
+    let moon = world.body_index(moon).unwrap();
+
+    let mut rails = OrbitRails::new();
+    let sun = rails.attach(
+        &mut world,
+        planet,
+        Primary::Fixed(Vector3::origin()),
+        100.0,
+        1.0,
+    );
+    let month = rails.attach(&mut world, moon, Primary::Body(planet), 1.0, 1.0);
+    assert_vector_eq(Vector3::new(0.0, 0.5, 0.0), month.state().1, 1e-12);
+
+    // The moon follows the planet, without an encounter with it.
+    for _ in 0..600 {
+        assert!(rails.advance(&mut world, 1.0 / 60.0).is_empty());
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    let planet_position = sun.state_after(10.0).0;
+    assert_vector_eq(planet_position, world.bodies[planet].position, 1e-9);
+    assert_vector_eq(
+        planet_position + month.state_after(10.0).0,
+        world.bodies[moon].position,
+        1e-9,
+    );
+}
@@ -0,0 +1,118 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+/// Bounds the scalar type of a world needs to meet to be processed on multiple threads, which
+/// every type meets unless the `parallel` feature is enabled.
+#[cfg(feature = "parallel")]
+pub trait Parallel: Send + Sync {}
+
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync> Parallel for T {}
+
+/// Bounds the scalar type of a world needs to meet to be processed on multiple threads, which
+/// every type meets unless the `parallel` feature is enabled.
+#[cfg(not(feature = "parallel"))]
+pub trait Parallel {}
+
+#[cfg(not(feature = "parallel"))]
+impl<T> Parallel for T {}
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Returns the number of threads of the rayon pool to split the given number of items over,
+/// so every thread gets at least the given number of items.
+///
+/// # Remarks
+/// Platforms without threads, such as `wasm32-unknown-unknown`, get a pool with a single
+/// one, so the work is never split there, unless the pool is run on web workers with the
+/// `wasm-threads` feature.
+#[cfg(feature = "parallel")]
+fn threads(items: usize, min_items: usize) -> usize {
+    rayon::current_num_threads()
+        .min(items / min_items.max(1))
+        .max(1)
+}
+
+/// Splits the items into contiguous chunks of at least the given length, calling `work`
+/// with the index of the first item of each chunk and the chunk on the rayon thread pool, and
+/// returns the results in the order of the chunks.
+///
+/// # Remarks
+/// Without the `parallel` feature, `work` is called once with all the items.
+#[cfg(feature = "parallel")]
+pub(crate) fn map_chunks<T: Sync, R: Send>(
+    items: &[T],
+    min_items: usize,
+    work: impl Fn(usize, &[T]) -> R + Sync,
+) -> Vec<R> {
+    let threads = threads(items.len(), min_items);
+    if threads == 1 {
+        return vec![work(0, items)];
+    }
+
+    let length = items.len().div_ceil(threads);
+    items
+        .par_chunks(length)
+        .enumerate()
+        .map(|(index, chunk)| work(index * length, chunk))
+        .collect()
+}
+
+/// Splits the items into contiguous chunks of at least the given length, calling `work`
+/// with the index of the first item of each chunk and the chunk on the rayon thread pool, and
+/// returns the results in the order of the chunks.
+///
+/// # Remarks
+/// Without the `parallel` feature, `work` is called once with all the items.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map_chunks<T, R>(
+    items: &[T],
+    _min_items: usize,
+    work: impl Fn(usize, &[T]) -> R,
+) -> Vec<R> {
+    vec![work(0, items)]
+}
+
+/// Splits the items into contiguous chunks of at least the given length, calling `work`
+/// with the index of the first item of each chunk and the chunk on the rayon thread pool, and
+/// returns the results in the order of the chunks.
+///
+/// # Remarks
+/// Without the `parallel` feature, `work` is called once with all the items.
+#[cfg(feature = "parallel")]
+pub(crate) fn map_chunks_mut<T: Send, R: Send>(
+    items: &mut [T],
+    min_items: usize,
+    work: impl Fn(usize, &mut [T]) -> R + Sync,
+) -> Vec<R> {
+    let threads = threads(items.len(), min_items);
+    if threads == 1 {
+        return vec![work(0, items)];
+    }
+
+    let length = items.len().div_ceil(threads);
+    items
+        .par_chunks_mut(length)
+        .enumerate()
+        .map(|(index, chunk)| work(index * length, chunk))
+        .collect()
+}
+
+/// Splits the items into contiguous chunks of at least the given length, calling `work`
+/// with the index of the first item of each chunk and the chunk on the rayon thread pool, and
+/// returns the results in the order of the chunks.
+///
+/// # Remarks
+/// Without the `parallel` feature, `work` is called once with all the items.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map_chunks_mut<T, R>(
+    items: &mut [T],
+    _min_items: usize,
+    work: impl Fn(usize, &mut [T]) -> R,
+) -> Vec<R> {
+    vec![work(0, items)]
+}
@@ -0,0 +1,135 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// A particle is the simplest object that can be simulated in the physics engine.
+/// It has a position and mass, but no orientation.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Particle<F: num_traits::Float = f64> {
+    /// Linear position of the particle in world space.
+    pub position: Vector3<F>,
+
+    /// Linear velocity of the particle in world space.
+    pub velocity: Vector3<F>,
+
+    /// Constant acceleration of the particle, typically used for gravity.
+    pub acceleration: Vector3<F>,
+
+    /// Amount of damping applied to linear motion. Damping is required
+    /// to remove energy added through numerical instability in the integrator.
+    /// A value of `1` means no damping, `0` means the velocity is completely removed.
+    pub damping: F,
+
+    /// Holds the inverse of the mass of the particle. It's more useful to hold
+    /// the inverse mass because integration is simpler, and because in real-time
+    /// simulation it's more useful to have objects with infinite mass (immovable)
+    /// than zero mass (completely unstable in numerical simulation).
+    pub inverse_mass: F,
+
+    /// Accumulated force to be applied at the next simulation iteration only.
+    /// This value is zeroed at each integration step.
+    pub force_accum: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Particle<F> {
+    /// Creates a new particle at rest in the specified position with the given mass.
+    pub fn new(position: Vector3<F>, mass: F) -> Self {
+        let mut particle = Self {
+            position,
+            velocity: Vector3::origin(),
+            acceleration: Vector3::origin(),
+            damping: num_traits::one(),
+            inverse_mass: num_traits::one(),
+            force_accum: Vector3::origin(),
+        };
+        particle.set_mass(mass);
+        particle
+    }
+
+    /// Returns the mass of the particle.
+    /// Particles with infinite mass return the largest representable value.
+    pub fn mass(&self) -> F {
+        if self.inverse_mass == num_traits::zero() {
+            F::max_value()
+        } else {
+            F::one() / self.inverse_mass
+        }
+    }
+
+    /// Sets the mass of the particle.
+    ///
+    /// # Remarks
+    /// A zero mass would result in an infinite inverse mass, which is not supported;
+    /// in that case the particle is treated as having infinite mass.
+    pub fn set_mass(&mut self, mass: F) -> &mut Self {
+        if mass == num_traits::zero() {
+            self.inverse_mass = num_traits::zero();
+        } else {
+            self.inverse_mass = F::one() / mass;
+        }
+        self
+    }
+
+    /// Marks the particle as immovable by setting its inverse mass to `0`.
+    pub fn set_infinite_mass(&mut self) -> &mut Self {
+        self.inverse_mass = num_traits::zero();
+        self
+    }
+
+    /// Returns true if the mass of the particle is not infinite.
+    pub fn has_finite_mass(&self) -> bool {
+        self.inverse_mass > num_traits::zero()
+    }
+
+    /// Adds the given force to the particle, to be applied at the next integration only.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the particle.
+    pub fn add_force(&mut self, force: &Vector3<F>) -> &mut Self {
+        self.force_accum.inplace_vector_add(force);
+        self
+    }
+
+    /// Clears the forces applied to the particle.
+    /// This is called automatically after each integration step.
+    pub fn clear_accumulator(&mut self) -> &mut Self {
+        self.force_accum = Vector3::origin();
+        self
+    }
+
+    /// Integrates the particle forward in time by the given amount (in seconds).
+    /// Uses a Newton-Euler integration method, which is a linear approximation
+    /// to the correct integral.
+    ///
+    /// # Remarks
+    /// Particles with infinite mass are never integrated.
+    pub fn integrate(&mut self, duration: F) -> &mut Self {
+        if self.inverse_mass <= num_traits::zero() {
+            return self;
+        }
+
+        debug_assert!(duration > num_traits::zero());
+
+        // Update linear position.
+        self.position
+            .inplace_vector_add(&self.velocity.scalar_mul(duration));
+
+        // Work out the acceleration from the force.
+        let resulting_acc = self
+            .acceleration
+            .vector_add(&self.force_accum.scalar_mul(self.inverse_mass));
+
+        // Update linear velocity from the acceleration, and impose drag.
+        self.velocity
+            .inplace_vector_add(&resulting_acc.scalar_mul(duration))
+            .inplace_scalar_mul(self.damping.powf(duration));
+
+        self.clear_accumulator()
+    }
+}
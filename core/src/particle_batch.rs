@@ -0,0 +1,359 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::iter::FromIterator;
+use std::ops::{Add, Mul};
+use wide::CmpGt;
+
+/// Number of particles of a batch integrated together, unless another lane count is chosen.
+pub const LANES: usize = 4;
+
+/// Scalars a particle batch can integrate `N` particles of at a time.
+///
+/// # Remarks
+/// `f32` and `f64` are integrated in SIMD registers of 4 or 8 lanes, through `wide`. Other
+/// scalars, like the fixed-point one, go through every lane of a plain array instead. On
+/// wasm32, `wide` only uses SIMD instructions with the `simd128` target feature enabled.
+pub trait Lanes<const N: usize>: num_traits::Float {
+    /// Vector holding one scalar per lane, operated on all at once.
+    type Vector: Copy + Add<Output = Self::Vector> + Mul<Output = Self::Vector>;
+
+    /// Loads the lanes of the given array into a vector.
+    fn load(lanes: [Self; N]) -> Self::Vector;
+
+    /// Stores the lanes of the given vector into an array.
+    fn store(vector: Self::Vector) -> [Self; N];
+
+    /// Returns a vector with the given scalar in every lane.
+    fn splat(scalar: Self) -> Self::Vector;
+
+    /// Returns a vector with the lanes of `positive` where `mask` is positive, and the lanes
+    /// of `other` everywhere else.
+    fn select_positive(
+        mask: Self::Vector,
+        positive: Self::Vector,
+        other: Self::Vector,
+    ) -> Self::Vector;
+}
+
+/// Implements `Lanes` for a scalar with the given `wide` vector.
+macro_rules! simd_lanes {
+    ($scalar:ty, $lanes:expr, $vector:ty) => {
+        impl Lanes<$lanes> for $scalar {
+            type Vector = $vector;
+
+            fn load(lanes: [Self; $lanes]) -> Self::Vector {
+                <$vector>::new(lanes)
+            }
+
+            fn store(vector: Self::Vector) -> [Self; $lanes] {
+                vector.to_array()
+            }
+
+            fn splat(scalar: Self) -> Self::Vector {
+                <$vector>::splat(scalar)
+            }
+
+            fn select_positive(
+                mask: Self::Vector,
+                positive: Self::Vector,
+                other: Self::Vector,
+            ) -> Self::Vector {
+                mask.simd_gt(<$vector>::ZERO).blend(positive, other)
+            }
+        }
+    };
+}
+
+simd_lanes!(f32, 4, wide::f32x4);
+simd_lanes!(f32, 8, wide::f32x8);
+simd_lanes!(f64, 4, wide::f64x4);
+simd_lanes!(f64, 8, wide::f64x8);
+
+#[cfg(feature = "fixed")]
+impl<const N: usize> Lanes<N> for math::Fixed {
+    type Vector = ScalarLanes<Self, N>;
+
+    fn load(lanes: [Self; N]) -> Self::Vector {
+        ScalarLanes(lanes)
+    }
+
+    fn store(vector: Self::Vector) -> [Self; N] {
+        vector.0
+    }
+
+    fn splat(scalar: Self) -> Self::Vector {
+        ScalarLanes([scalar; N])
+    }
+
+    fn select_positive(
+        mask: Self::Vector,
+        positive: Self::Vector,
+        other: Self::Vector,
+    ) -> Self::Vector {
+        let mut lanes = other.0;
+        for (lane, value) in lanes.iter_mut().enumerate() {
+            if mask.0[lane] > num_traits::zero() {
+                *value = positive.0[lane];
+            }
+        }
+        ScalarLanes(lanes)
+    }
+}
+
+/// Lanes of scalars without SIMD registers, operated on one after another.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ScalarLanes<F, const N: usize>(pub [F; N]);
+
+impl<F: num_traits::Float, const N: usize> Add for ScalarLanes<F, N> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        for (lane, value) in self.0.iter_mut().enumerate() {
+            *value = *value + other.0[lane];
+        }
+        self
+    }
+}
+
+impl<F: num_traits::Float, const N: usize> Mul for ScalarLanes<F, N> {
+    type Output = Self;
+
+    fn mul(mut self, other: Self) -> Self {
+        for (lane, value) in self.0.iter_mut().enumerate() {
+            *value = *value * other.0[lane];
+        }
+        self
+    }
+}
+
+/// Particles of a batch stored together, with every component of every vector in its own array.
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Block<F, const N: usize> {
+    position: [[F; N]; 3],
+    velocity: [[F; N]; 3],
+    acceleration: [[F; N]; 3],
+    force_accum: [[F; N]; 3],
+    damping: [F; N],
+    inverse_mass: [F; N],
+}
+
+impl<F: Lanes<N>, const N: usize> Block<F, N> {
+    /// Creates a new block of particles with infinite mass, which are never integrated.
+    fn new() -> Self {
+        let zero = [[F::zero(); N]; 3];
+        Self {
+            position: zero,
+            velocity: zero,
+            acceleration: zero,
+            force_accum: zero,
+            damping: [F::one(); N],
+            inverse_mass: [F::zero(); N],
+        }
+    }
+
+    /// Returns the vector stored in the given lane of the given arrays.
+    fn vector(components: &[[F; N]; 3], lane: usize) -> Vector3<F> {
+        Vector3::new(
+            components[0][lane],
+            components[1][lane],
+            components[2][lane],
+        )
+    }
+
+    /// Stores the vector in the given lane of the given arrays.
+    fn set_vector(components: &mut [[F; N]; 3], lane: usize, vector: &Vector3<F>) {
+        components[0][lane] = vector.x;
+        components[1][lane] = vector.y;
+        components[2][lane] = vector.z;
+    }
+}
+
+/// Particles stored as a structure of arrays, integrated several at a time.
+///
+/// # Remarks
+/// Particles are kept in blocks of `N` particles, `LANES` unless chosen otherwise, where
+/// every component is stored in an array of its own. Integration loads those arrays into SIMD
+/// registers and runs every operation over all the lanes at once, instead of going through
+/// the fields of one `Particle` after another.
+///
+/// Integrating a batch gives exactly the same results as integrating its particles one
+/// by one, so both can be mixed freely.
+///
+/// Batches are serialized as the list of their particles.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(
+    bound(serialize = "F: Serialize", deserialize = "F: Deserialize<'de>"),
+    into = "Vec<Particle<F>>",
+    from = "Vec<Particle<F>>"
+)]
+pub struct ParticleBatch<F: Lanes<N> = f64, const N: usize = LANES> {
+    blocks: Vec<Block<F, N>>,
+    len: usize,
+}
+
+impl<F: Lanes<N>, const N: usize> Default for ParticleBatch<F, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Lanes<N>, const N: usize> ParticleBatch<F, N> {
+    /// Creates a new empty batch of particles.
+    pub fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of particles in the batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the batch has no particles.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds a particle to the batch, returning its index.
+    pub fn push(&mut self, particle: &Particle<F>) -> usize {
+        if self.len.is_multiple_of(N) {
+            self.blocks.push(Block::new());
+        }
+        self.len += 1;
+        self.set(self.len - 1, particle);
+        self.len - 1
+    }
+
+    /// Returns a copy of the particle with the given index.
+    pub fn get(&self, index: usize) -> Particle<F> {
+        assert!(index < self.len, "particle index out of bounds");
+        let (block, lane) = (&self.blocks[index / N], index % N);
+        Particle {
+            position: Block::vector(&block.position, lane),
+            velocity: Block::vector(&block.velocity, lane),
+            acceleration: Block::vector(&block.acceleration, lane),
+            damping: block.damping[lane],
+            inverse_mass: block.inverse_mass[lane],
+            force_accum: Block::vector(&block.force_accum, lane),
+        }
+    }
+
+    /// Replaces the particle with the given index.
+    pub fn set(&mut self, index: usize, particle: &Particle<F>) {
+        assert!(index < self.len, "particle index out of bounds");
+        let (block, lane) = (&mut self.blocks[index / N], index % N);
+        Block::set_vector(&mut block.position, lane, &particle.position);
+        Block::set_vector(&mut block.velocity, lane, &particle.velocity);
+        Block::set_vector(&mut block.acceleration, lane, &particle.acceleration);
+        Block::set_vector(&mut block.force_accum, lane, &particle.force_accum);
+        block.damping[lane] = particle.damping;
+        block.inverse_mass[lane] = particle.inverse_mass;
+    }
+
+    /// Returns the position of the particle with the given index.
+    pub fn position(&self, index: usize) -> Vector3<F> {
+        assert!(index < self.len, "particle index out of bounds");
+        Block::vector(&self.blocks[index / N].position, index % N)
+    }
+
+    /// Returns the velocity of the particle with the given index.
+    pub fn velocity(&self, index: usize) -> Vector3<F> {
+        assert!(index < self.len, "particle index out of bounds");
+        Block::vector(&self.blocks[index / N].velocity, index % N)
+    }
+
+    /// Adds the given force to the particle with the given index, to be applied at the next
+    /// integration only.
+    pub fn add_force(&mut self, index: usize, force: &Vector3<F>) {
+        assert!(index < self.len, "particle index out of bounds");
+        let (block, lane) = (&mut self.blocks[index / N], index % N);
+        let accumulated = Block::vector(&block.force_accum, lane).vector_add(force);
+        Block::set_vector(&mut block.force_accum, lane, &accumulated);
+    }
+
+    /// Integrates every particle forward in time by the given amount (in seconds), the same
+    /// way `Particle::integrate` does.
+    ///
+    /// # Remarks
+    /// Particles with infinite mass are never integrated.
+    pub fn integrate(&mut self, duration: F) {
+        self.advance(duration, false);
+    }
+
+    /// Integrates every particle forward in time by the given amount (in seconds), moving them
+    /// with their updated velocity instead of the one they had before the step.
+    ///
+    /// # Remarks
+    /// This semi-implicit Euler integration keeps stiff springs, like the contacts between
+    /// grains, from gaining energy as they oscillate.
+    pub fn integrate_symplectic(&mut self, duration: F) {
+        self.advance(duration, true);
+    }
+
+    /// Integrates every particle forward in time, moving them with their updated velocity if
+    /// symplectic.
+    fn advance(&mut self, duration: F, symplectic: bool) {
+        debug_assert!(duration > num_traits::zero());
+
+        // Particles of an effect tend to share their damping, so the drag is only worked out
+        // again for blocks with different damping than the previous one.
+        let mut damping = [F::one(); N];
+        let mut drag = [F::one(); N];
+        let step = F::splat(duration);
+        for block in self.blocks.iter_mut() {
+            if block.damping != damping {
+                damping = block.damping;
+                for (drag, damping) in drag.iter_mut().zip(damping.iter()) {
+                    *drag = damping.powf(duration);
+                }
+            }
+
+            // Particles with infinite mass keep their state, picked back per lane.
+            let (drag, inverse_mass) = (F::load(drag), F::load(block.inverse_mass));
+            for axis in 0..3 {
+                let position = F::load(block.position[axis]);
+                let velocity = F::load(block.velocity[axis]);
+                let force = F::load(block.force_accum[axis]);
+                let acceleration = F::load(block.acceleration[axis]) + force * inverse_mass;
+                let updated = (velocity + acceleration * step) * drag;
+                let moved = position + if symplectic { updated } else { velocity } * step;
+                block.position[axis] = F::store(F::select_positive(inverse_mass, moved, position));
+                block.velocity[axis] =
+                    F::store(F::select_positive(inverse_mass, updated, velocity));
+                block.force_accum[axis] =
+                    F::store(F::select_positive(inverse_mass, F::splat(F::zero()), force));
+            }
+        }
+    }
+}
+
+impl<'a, F: Lanes<N>, const N: usize> FromIterator<&'a Particle<F>> for ParticleBatch<F, N> {
+    fn from_iter<I: IntoIterator<Item = &'a Particle<F>>>(particles: I) -> Self {
+        let mut batch = Self::new();
+        for particle in particles {
+            batch.push(particle);
+        }
+        batch
+    }
+}
+
+impl<F: Lanes<N>, const N: usize> From<Vec<Particle<F>>> for ParticleBatch<F, N> {
+    fn from(particles: Vec<Particle<F>>) -> Self {
+        particles.iter().collect()
+    }
+}
+
+impl<F: Lanes<N>, const N: usize> From<ParticleBatch<F, N>> for Vec<Particle<F>> {
+    fn from(batch: ParticleBatch<F, N>) -> Self {
+        (0..batch.len()).map(|index| batch.get(index)).collect()
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::particle_batch::*;
+use math::Vector3;
+
+/// Integrates particles one by one and as a batch of the given lane count, checking both
+/// give exactly the same results.
+fn matches_particles<F: Lanes<N> + std::fmt::Debug, const N: usize>() {
+    let real = |value: f64| F::from(value).unwrap();
+    let mut particles: Vec<Particle<F>> = (0..N * 2 + 3)
+        .map(|index| {
+            let offset = real(index as f64);
+            let mut particle =
+                Particle::new(Vector3::new(offset, real(2.0) * offset, -offset), real(1.5));
+            particle.velocity = Vector3::new(real(0.3) * offset, F::one(), real(-0.7));
+            particle.acceleration = Vector3::new(F::zero(), real(-9.8), F::zero());
+            particle.damping = real(0.9) + real(0.01) * offset;
+            particle
+        })
+        .collect();
+    particles[3].set_infinite_mass();
+    let mut batch: ParticleBatch<F, N> = particles.iter().collect();
+    assert_eq!(particles.len(), batch.len());
+
+    for step in 0..100 {
+        let force = Vector3::new(F::one(), real(0.5 * step as f64), F::zero());
+        particles[5].add_force(&force);
+        batch.add_force(5, &force);
+        for particle in particles.iter_mut() {
+            particle.integrate(real(1.0 / 60.0));
+        }
+        batch.integrate(real(1.0 / 60.0));
+    }
+
+    // Batches integrate their particles exactly the way particles integrate themselves.
+    for (index, particle) in particles.iter().enumerate() {
+        assert_eq!(*particle, batch.get(index));
+        assert_eq!(particle.position, batch.position(index));
+    }
+    assert_eq!(
+        Vector3::new(real(3.0), real(6.0), real(-3.0)),
+        batch.position(3)
+    );
+
+    let mut moved = particles[0];
+    moved.position = Vector3::new(F::one(), real(2.0), real(3.0));
+    batch.set(0, &moved);
+    assert_eq!(moved, batch.get(0));
+}
+
+#[test]
+fn lanes() {
+    matches_particles::<f32, 4>();
+    matches_particles::<f32, 8>();
+    matches_particles::<f64, 4>();
+    matches_particles::<f64, 8>();
+}
+
+#[test]
+fn serialization() {
+    let particles: Vec<Particle<f64>> = (0..LANES + 1)
+        .map(|index| Particle::new(Vector3::new(index as f64, 0.0, 0.0), 1.0))
+        .collect();
+    let batch: ParticleBatch = particles.iter().collect();
+
+    // Batches are stored as their particles, so any lane count can read them back.
+    let bytes = bincode::serialize(&batch).unwrap();
+    assert_eq!(bincode::serialize(&particles).unwrap(), bytes);
+    let wide: ParticleBatch<f64, 8> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(particles, Vec::from(wide));
+}
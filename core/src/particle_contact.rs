@@ -0,0 +1,263 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// A contact represents two particles in contact (or a particle against immovable scenery).
+/// Resolving a contact removes their interpenetration and applies sufficient impulse to
+/// keep them apart. Colliding bodies may also rebound.
+///
+/// # Remarks
+/// Particles are referenced by their index in the slice of particles the contact
+/// is resolved against. When the second particle is `None`, the contact is against
+/// the scenery.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParticleContact<F: num_traits::Float = f64> {
+    /// Indices of the particles involved in the contact.
+    pub particles: (usize, Option<usize>),
+
+    /// Normal restitution coefficient at the contact.
+    pub restitution: F,
+
+    /// Direction of the contact in world coordinates,
+    /// from the perspective of the first particle.
+    pub contact_normal: Vector3<F>,
+
+    /// Depth of penetration at the contact.
+    pub penetration: F,
+
+    /// Amount each particle is moved by during interpenetration resolution.
+    pub particle_movement: [Vector3<F>; 2],
+}
+
+impl<F: num_traits::Float> ParticleContact<F> {
+    /// Creates a new contact between the given particles.
+    pub fn new(
+        particles: (usize, Option<usize>),
+        restitution: F,
+        contact_normal: Vector3<F>,
+        penetration: F,
+    ) -> Self {
+        Self {
+            particles,
+            restitution,
+            contact_normal,
+            penetration,
+            particle_movement: [Vector3::origin(), Vector3::origin()],
+        }
+    }
+
+    /// Resolves this contact, for both velocity and interpenetration.
+    pub fn resolve(&mut self, particles: &mut [Particle<F>], duration: F) {
+        self.resolve_velocity(particles, duration);
+        self.resolve_interpenetration(particles);
+    }
+
+    /// Calculates the separating velocity at this contact.
+    pub fn separating_velocity(&self, particles: &[Particle<F>]) -> F {
+        let mut relative_velocity = particles[self.particles.0].velocity;
+        if let Some(other) = self.particles.1 {
+            relative_velocity.inplace_vector_sub(&particles[other].velocity);
+        }
+        relative_velocity.dot_product(&self.contact_normal)
+    }
+
+    /// Returns the sum of the inverse masses of the particles in contact.
+    fn total_inverse_mass(&self, particles: &[Particle<F>]) -> F {
+        let mut total = particles[self.particles.0].inverse_mass;
+        if let Some(other) = self.particles.1 {
+            total = total + particles[other].inverse_mass;
+        }
+        total
+    }
+
+    /// Handles the impulse calculations for this collision.
+    fn resolve_velocity(&self, particles: &mut [Particle<F>], duration: F) {
+        // Find the velocity in the direction of the contact.
+        let separating_velocity = self.separating_velocity(particles);
+
+        // Check if it needs to be resolved.
+        if separating_velocity > num_traits::zero() {
+            // The contact is either separating or stationary, no impulse is required.
+            return;
+        }
+
+        let mut new_sep_velocity = -separating_velocity * self.restitution;
+
+        // Check the velocity build-up due to acceleration only.
+        let mut acc_caused_velocity = particles[self.particles.0].acceleration;
+        if let Some(other) = self.particles.1 {
+            acc_caused_velocity.inplace_vector_sub(&particles[other].acceleration);
+        }
+        let acc_caused_sep_velocity =
+            acc_caused_velocity.dot_product(&self.contact_normal) * duration;
+
+        // If we've got a closing velocity due to acceleration build-up,
+        // remove it from the new separating velocity.
+        if acc_caused_sep_velocity < num_traits::zero() {
+            new_sep_velocity = new_sep_velocity + self.restitution * acc_caused_sep_velocity;
+            if new_sep_velocity < num_traits::zero() {
+                new_sep_velocity = num_traits::zero();
+            }
+        }
+
+        let delta_velocity = new_sep_velocity - separating_velocity;
+
+        // Apply the change in velocity to each object in proportion to its inverse mass.
+        let total_inverse_mass = self.total_inverse_mass(particles);
+        if total_inverse_mass <= num_traits::zero() {
+            // Both particles have infinite mass, so impulses have no effect.
+            return;
+        }
+
+        let impulse = delta_velocity / total_inverse_mass;
+        let impulse_per_imass = self.contact_normal.scalar_mul(impulse);
+
+        let first = &mut particles[self.particles.0];
+        let delta = impulse_per_imass.scalar_mul(first.inverse_mass);
+        first.velocity.inplace_vector_add(&delta);
+
+        if let Some(other) = self.particles.1 {
+            let second = &mut particles[other];
+            let delta = impulse_per_imass.scalar_mul(-second.inverse_mass);
+            second.velocity.inplace_vector_add(&delta);
+        }
+    }
+
+    /// Handles the interpenetration resolution for this contact.
+    fn resolve_interpenetration(&mut self, particles: &mut [Particle<F>]) {
+        self.particle_movement = [Vector3::origin(), Vector3::origin()];
+
+        if self.penetration <= num_traits::zero() {
+            return;
+        }
+
+        let total_inverse_mass = self.total_inverse_mass(particles);
+        if total_inverse_mass <= num_traits::zero() {
+            return;
+        }
+
+        // Movement is proportional to the inverse mass of each particle.
+        let move_per_imass = self
+            .contact_normal
+            .scalar_mul(self.penetration / total_inverse_mass);
+
+        let first = &mut particles[self.particles.0];
+        self.particle_movement[0] = move_per_imass.scalar_mul(first.inverse_mass);
+        first
+            .position
+            .inplace_vector_add(&self.particle_movement[0]);
+
+        if let Some(other) = self.particles.1 {
+            let second = &mut particles[other];
+            self.particle_movement[1] = move_per_imass.scalar_mul(-second.inverse_mass);
+            second
+                .position
+                .inplace_vector_add(&self.particle_movement[1]);
+        }
+    }
+}
+
+/// Polymorphic interface for objects that generate contacts between particles.
+pub trait ParticleContactGenerator<F: num_traits::Float = f64> {
+    /// Fills `contacts` with the generated contacts, writing at most `limit` of them.
+    /// Returns the number of contacts that have been written.
+    fn add_contact(
+        &self,
+        particles: &[Particle<F>],
+        contacts: &mut Vec<ParticleContact<F>>,
+        limit: usize,
+    ) -> usize;
+}
+
+/// Contact resolution routine for particle contacts.
+/// One resolver instance can be shared for the whole simulation.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ParticleContactResolver {
+    /// Number of iterations allowed.
+    pub iterations: usize,
+
+    /// Performance tracking value of the actual number of iterations used.
+    pub iterations_used: usize,
+}
+
+impl ParticleContactResolver {
+    /// Creates a new contact resolver.
+    pub fn new(iterations: usize) -> Self {
+        Self {
+            iterations,
+            iterations_used: 0,
+        }
+    }
+
+    /// Resolves a set of particle contacts for both penetration and velocity.
+    ///
+    /// # Remarks
+    /// Contacts that cannot interact with each other should be passed to separate
+    /// calls, as the resolution algorithm takes much longer for lots of contacts
+    /// than it does for the same number of contacts in small sets.
+    ///
+    /// The number of iterations should be at least the number of contacts
+    /// (ideally twice that number) to have all of them resolved.
+    pub fn resolve_contacts<F: num_traits::Float>(
+        &mut self,
+        contacts: &mut [ParticleContact<F>],
+        particles: &mut [Particle<F>],
+        duration: F,
+    ) {
+        self.iterations_used = 0;
+
+        while self.iterations_used < self.iterations {
+            // Find the contact with the largest closing velocity.
+            let mut max = F::max_value();
+            let mut max_index = None;
+            for (index, contact) in contacts.iter().enumerate() {
+                let separating_velocity = contact.separating_velocity(particles);
+                if separating_velocity < max
+                    && (separating_velocity < num_traits::zero()
+                        || contact.penetration > num_traits::zero())
+                {
+                    max = separating_velocity;
+                    max_index = Some(index);
+                }
+            }
+
+            // Check if there's anything worth resolving.
+            let max_index = match max_index {
+                Some(index) => index,
+                None => break,
+            };
+
+            contacts[max_index].resolve(particles, duration);
+
+            // Update the interpenetrations for all particles.
+            let resolved = contacts[max_index];
+            let movement = resolved.particle_movement;
+            for contact in contacts.iter_mut() {
+                let normal = contact.contact_normal;
+                if contact.particles.0 == resolved.particles.0 {
+                    contact.penetration = contact.penetration - movement[0].dot_product(&normal);
+                } else if Some(contact.particles.0) == resolved.particles.1 {
+                    contact.penetration = contact.penetration - movement[1].dot_product(&normal);
+                }
+
+                if let Some(other) = contact.particles.1 {
+                    if other == resolved.particles.0 {
+                        contact.penetration =
+                            contact.penetration + movement[0].dot_product(&normal);
+                    } else if Some(other) == resolved.particles.1 {
+                        contact.penetration =
+                            contact.penetration + movement[1].dot_product(&normal);
+                    }
+                }
+            }
+
+            self.iterations_used += 1;
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::force::{inverse_square_force, COULOMB_CONSTANT, MAGNETIC_CONSTANT};
+use crate::particle::Particle;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A force generator can be asked to add a force to one or more particles.
+pub trait ParticleForceGenerator<F: num_traits::Float = f64> {
+    /// Calculates and updates the force applied to the particle at index `particle`.
+    ///
+    /// # Remarks
+    /// The whole slice of particles is available so generators can depend on the
+    /// state of other particles in the simulation.
+    fn update_force(&mut self, particles: &mut [Particle<F>], particle: usize, duration: F);
+}
+
+/// Shared force generators can be registered while the caller keeps a handle to them,
+/// allowing their inputs to be changed between frames.
+impl<F: num_traits::Float, G: ParticleForceGenerator<F>> ParticleForceGenerator<F>
+    for Rc<RefCell<G>>
+{
+    fn update_force(&mut self, particles: &mut [Particle<F>], particle: usize, duration: F) {
+        self.borrow_mut()
+            .update_force(particles, particle, duration);
+    }
+}
+
+/// Force generator that applies a gravitational force.
+/// One instance can be used for multiple particles.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParticleGravity<F: num_traits::Float = f64> {
+    /// Acceleration due to gravity.
+    pub gravity: Vector3<F>,
+}
+
+impl<F: num_traits::Float> ParticleGravity<F> {
+    /// Creates a new gravity generator with the given acceleration.
+    pub fn new(gravity: Vector3<F>) -> Self {
+        Self { gravity }
+    }
+}
+
+impl<F: num_traits::Float> ParticleForceGenerator<F> for ParticleGravity<F> {
+    fn update_force(&mut self, particles: &mut [Particle<F>], particle: usize, _duration: F) {
+        let particle = &mut particles[particle];
+
+        // Check that the particle doesn't have infinite mass.
+        if !particle.has_finite_mass() {
+            return;
+        }
+
+        let force = self.gravity.scalar_mul(particle.mass());
+        particle.add_force(&force);
+    }
+}
+
+/// Force generator that applies a drag force.
+/// One instance can be used for multiple particles.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParticleDrag<F: num_traits::Float = f64> {
+    /// Velocity drag coefficient.
+    pub k1: F,
+
+    /// Velocity squared drag coefficient.
+    pub k2: F,
+}
+
+impl<F: num_traits::Float> ParticleDrag<F> {
+    /// Creates a new drag generator with the given coefficients.
+    pub fn new(k1: F, k2: F) -> Self {
+        Self { k1, k2 }
+    }
+}
+
+impl<F: num_traits::Float> ParticleForceGenerator<F> for ParticleDrag<F> {
+    fn update_force(&mut self, particles: &mut [Particle<F>], particle: usize, _duration: F) {
+        let particle = &mut particles[particle];
+
+        // Calculate the total drag coefficient.
+        let speed = particle.velocity.magnitude();
+        let drag_coeff = self.k1 * speed + self.k2 * speed * speed;
+
+        // Calculate the final force and apply it.
+        let force = particle.velocity.normalize().scalar_mul(-drag_coeff);
+        particle.add_force(&force);
+    }
+}
+
+/// Force generator that applies an inverse-square electrostatic or magnetic force
+/// between two charged particles. Like charges repel each other, opposite charges attract.
+///
+/// # Remarks
+/// The force is only applied to the particle the generator is registered to;
+/// register a second generator on the other particle for mutual interaction.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParticlePointCharge<F: num_traits::Float = f64> {
+    /// Index of the particle holding the other charge.
+    pub other: usize,
+
+    /// Charge of the particle the force is applied to.
+    pub charge: F,
+
+    /// Charge of the other particle.
+    pub other_charge: F,
+
+    /// Proportionality constant of the force law.
+    pub constant: F,
+
+    /// Softening length used to avoid the singularity at zero distance.
+    pub softening: F,
+}
+
+impl<F: num_traits::Float> ParticlePointCharge<F> {
+    /// Creates a new electrostatic force generator between two charges, in coulombs.
+    pub fn electrostatic(other: usize, charge: F, other_charge: F, softening: F) -> Self {
+        Self {
+            other,
+            charge,
+            other_charge,
+            constant: math::real(COULOMB_CONSTANT),
+            softening,
+        }
+    }
+
+    /// Creates a new magnetic force generator between two magnetic pole strengths, in A m.
+    pub fn magnetic(other: usize, strength: F, other_strength: F, softening: F) -> Self {
+        Self {
+            other,
+            charge: strength,
+            other_charge: other_strength,
+            constant: math::real(MAGNETIC_CONSTANT),
+            softening,
+        }
+    }
+}
+
+impl<F: num_traits::Float> ParticleForceGenerator<F> for ParticlePointCharge<F> {
+    fn update_force(&mut self, particles: &mut [Particle<F>], particle: usize, _duration: F) {
+        let force = inverse_square_force(
+            &particles[particle].position,
+            &particles[self.other].position,
+            self.constant * self.charge * self.other_charge,
+            self.softening,
+        );
+        particles[particle].add_force(&force);
+    }
+}
+
+/// Holds all the force generators and the particles they apply to.
+pub struct ParticleForceRegistry<F: num_traits::Float = f64> {
+    registrations: Vec<(usize, Box<dyn ParticleForceGenerator<F>>)>,
+}
+
+impl<F: num_traits::Float> Default for ParticleForceRegistry<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: num_traits::Float> ParticleForceRegistry<F> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            registrations: Vec::new(),
+        }
+    }
+
+    /// Registers the given force generator to apply to the particle at index `particle`.
+    pub fn add(&mut self, particle: usize, generator: Box<dyn ParticleForceGenerator<F>>) {
+        self.registrations.push((particle, generator));
+    }
+
+    /// Removes all the registrations for the particle at index `particle`.
+    pub fn remove(&mut self, particle: usize) {
+        self.registrations.retain(|(index, _)| *index != particle);
+    }
+
+    /// Clears all registrations from the registry.
+    /// This doesn't affect the particles themselves, only their connection to generators.
+    pub fn clear(&mut self) {
+        self.registrations.clear();
+    }
+
+    /// Returns the number of registrations.
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    /// Returns true if there are no registrations.
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+
+    /// Calls all the force generators to update the forces of their corresponding particles.
+    pub fn update_forces(&mut self, particles: &mut [Particle<F>], duration: F) {
+        for (particle, generator) in self.registrations.iter_mut() {
+            generator.update_force(particles, *particle, duration);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::particle_contact::{ParticleContact, ParticleContactGenerator};
+use serde::{Deserialize, Serialize};
+
+/// Returns the current length of the link between two particles.
+fn current_length<F: num_traits::Float>(particles: &[Particle<F>], link: &[usize; 2]) -> F {
+    particles[link[0]]
+        .position
+        .vector_sub(&particles[link[1]].position)
+        .magnitude()
+}
+
+/// Cables link a pair of particles, generating a contact if they stray too far apart.
+///
+/// # Remarks
+/// To anchor a cable to a fixed point, link it to a particle with infinite mass.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParticleCable<F: num_traits::Float = f64> {
+    /// Indices of the pair of particles connected by the cable.
+    pub particles: [usize; 2],
+
+    /// Maximum length of the cable.
+    pub max_length: F,
+
+    /// Restitution (bounciness) of the cable.
+    pub restitution: F,
+}
+
+impl<F: num_traits::Float> ParticleCable<F> {
+    /// Creates a new cable between the given particles.
+    pub fn new(particles: [usize; 2], max_length: F, restitution: F) -> Self {
+        Self {
+            particles,
+            max_length,
+            restitution,
+        }
+    }
+
+    /// Returns the current length of the cable.
+    pub fn current_length(&self, particles: &[Particle<F>]) -> F {
+        current_length(particles, &self.particles)
+    }
+}
+
+impl<F: num_traits::Float> ParticleContactGenerator<F> for ParticleCable<F> {
+    fn add_contact(
+        &self,
+        particles: &[Particle<F>],
+        contacts: &mut Vec<ParticleContact<F>>,
+        limit: usize,
+    ) -> usize {
+        let length = self.current_length(particles);
+
+        // Check if the cable is overextended.
+        if limit == 0 || length < self.max_length {
+            return 0;
+        }
+
+        let normal = particles[self.particles[1]]
+            .position
+            .vector_sub(&particles[self.particles[0]].position)
+            .normalize();
+
+        contacts.push(ParticleContact::new(
+            (self.particles[0], Some(self.particles[1])),
+            self.restitution,
+            normal,
+            length - self.max_length,
+        ));
+        1
+    }
+}
+
+/// Rods link a pair of particles, generating a contact if they stray too far apart or too close.
+///
+/// # Remarks
+/// To anchor a rod to a fixed point, link it to a particle with infinite mass.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ParticleRod<F: num_traits::Float = f64> {
+    /// Indices of the pair of particles connected by the rod.
+    pub particles: [usize; 2],
+
+    /// Length of the rod.
+    pub length: F,
+}
+
+impl<F: num_traits::Float> ParticleRod<F> {
+    /// Creates a new rod between the given particles.
+    pub fn new(particles: [usize; 2], length: F) -> Self {
+        Self { particles, length }
+    }
+
+    /// Returns the current length of the rod.
+    pub fn current_length(&self, particles: &[Particle<F>]) -> F {
+        current_length(particles, &self.particles)
+    }
+}
+
+impl<F: num_traits::Float> ParticleContactGenerator<F> for ParticleRod<F> {
+    fn add_contact(
+        &self,
+        particles: &[Particle<F>],
+        contacts: &mut Vec<ParticleContact<F>>,
+        limit: usize,
+    ) -> usize {
+        let current_length = self.current_length(particles);
+
+        // Check if the rod is overextended or compressed.
+        if limit == 0 || current_length == self.length {
+            return 0;
+        }
+
+        let normal = particles[self.particles[1]]
+            .position
+            .vector_sub(&particles[self.particles[0]].position)
+            .normalize();
+
+        // The contact normal depends on whether the rod is extending or compressing.
+        let (normal, penetration) = if current_length > self.length {
+            (normal, current_length - self.length)
+        } else {
+            (normal.invert(), self.length - current_length)
+        };
+
+        // Rods never bounce.
+        contacts.push(ParticleContact::new(
+            (self.particles[0], Some(self.particles[1])),
+            num_traits::zero(),
+            normal,
+            penetration,
+        ));
+        1
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::particle_contact::*;
+use crate::particle_link::*;
+use math::Vector3;
+
+fn pair(distance: f64) -> Vec<Particle> {
+    vec![
+        Particle::new(Vector3::origin(), 1.0),
+        Particle::new(Vector3::new(distance, 0.0, 0.0), 1.0),
+    ]
+}
+
+#[test]
+fn cable() {
+    let cable = ParticleCable::new([0, 1], 2.0, 0.5);
+    let mut contacts = Vec::new();
+
+    // Slack cables generate no contacts.
+    assert_eq!(0, cable.add_contact(&pair(1.0), &mut contacts, 1));
+    assert!(contacts.is_empty());
+
+    let mut particles = pair(3.0);
+    assert_eq!(0, cable.add_contact(&particles, &mut contacts, 0));
+    assert_eq!(1, cable.add_contact(&particles, &mut contacts, 1));
+    assert_eq!(Vector3::new(1.0, 0.0, 0.0), contacts[0].contact_normal);
+    assert_eq!(1.0, contacts[0].penetration);
+    assert_eq!(0.5, contacts[0].restitution);
+
+    // Particles flying apart are pulled back together.
+    particles[0].velocity = Vector3::new(-1.0, 0.0, 0.0);
+    particles[1].velocity = Vector3::new(1.0, 0.0, 0.0);
+    contacts[0].resolve(&mut particles, 0.1);
+    assert_eq!(2.0, cable.current_length(&particles));
+    assert_eq!(Vector3::new(0.5, 0.0, 0.0), particles[0].velocity);
+    assert_eq!(Vector3::new(-0.5, 0.0, 0.0), particles[1].velocity);
+}
+
+#[test]
+fn rod() {
+    let rod = ParticleRod::new([0, 1], 2.0);
+    let mut contacts = Vec::new();
+
+    assert_eq!(0, rod.add_contact(&pair(2.0), &mut contacts, 1));
+
+    // Compressed rods push the particles apart.
+    let mut particles = pair(1.0);
+    assert_eq!(1, rod.add_contact(&particles, &mut contacts, 1));
+    assert_eq!(Vector3::new(-1.0, 0.0, 0.0), contacts[0].contact_normal);
+    assert_eq!(1.0, contacts[0].penetration);
+    assert_eq!(0.0, contacts[0].restitution);
+    contacts[0].resolve(&mut particles, 0.1);
+    assert_eq!(2.0, rod.current_length(&particles));
+
+    // Extended rods pull the particles together, and immovable anchors stay put.
+    let mut particles = pair(3.0);
+    particles[0].set_infinite_mass();
+    contacts.clear();
+    assert_eq!(1, rod.add_contact(&particles, &mut contacts, 1));
+    contacts[0].resolve(&mut particles, 0.1);
+    assert_eq!(Vector3::origin(), particles[0].position);
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), particles[1].position);
+}
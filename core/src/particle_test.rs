@@ -0,0 +1,40 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::*;
+use math::Vector3;
+
+#[test]
+fn mass() {
+    let mut particle = Particle::<f64>::new(Vector3::origin(), 2.0);
+    assert_eq!(0.5, particle.inverse_mass);
+    assert_eq!(2.0, particle.mass());
+    assert!(particle.has_finite_mass());
+
+    particle.set_infinite_mass();
+    assert!(!particle.has_finite_mass());
+    assert_eq!(f64::MAX, particle.mass());
+
+    particle.set_mass(0.0);
+    assert!(!particle.has_finite_mass());
+}
+
+#[test]
+fn integrate() {
+    let mut particle = Particle::<f64>::new(Vector3::origin(), 2.0);
+    particle.velocity = Vector3::new(1.0, 0.0, 0.0);
+    particle.add_force(&Vector3::new(0.0, 4.0, 0.0));
+    particle.integrate(0.5);
+
+    assert_eq!(Vector3::new(0.5, 0.0, 0.0), particle.position);
+    assert_eq!(Vector3::new(1.0, 1.0, 0.0), particle.velocity);
+    assert_eq!(Vector3::origin(), particle.force_accum);
+
+    // Immovable particles are never integrated.
+    particle.set_infinite_mass();
+    particle.integrate(0.5);
+    assert_eq!(Vector3::new(0.5, 0.0, 0.0), particle.position);
+}
@@ -0,0 +1,119 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::particle_contact::{ParticleContact, ParticleContactGenerator, ParticleContactResolver};
+use crate::particle_force::ParticleForceRegistry;
+
+/// Keeps track of a set of particles, and provides the means to update them all.
+///
+/// # Remarks
+/// Each call to `run_physics` goes through the whole mass-aggregate pipeline:
+/// start-frame reset, force generation, integration, contact generation and resolution.
+pub struct ParticleWorld<F: num_traits::Float = f64> {
+    /// Particles simulated by the world.
+    pub particles: Vec<Particle<F>>,
+
+    /// Force generators applied to the particles of the world.
+    pub registry: ParticleForceRegistry<F>,
+
+    /// Contact generators run at each frame.
+    pub contact_generators: Vec<Box<dyn ParticleContactGenerator<F>>>,
+
+    /// Resolver used to solve the contacts generated at each frame.
+    pub resolver: ParticleContactResolver,
+
+    /// Maximum number of contacts allowed per frame.
+    pub max_contacts: usize,
+
+    /// When true, the resolver is given twice as many iterations
+    /// as contacts generated at each frame.
+    pub calculate_iterations: bool,
+
+    contacts: Vec<ParticleContact<F>>,
+}
+
+impl<F: num_traits::Float> ParticleWorld<F> {
+    /// Creates a new particle world that can handle up to the given number of contacts per frame.
+    ///
+    /// # Remarks
+    /// When `iterations` is `0`, the number of iterations used by the resolver is
+    /// calculated at each frame as twice the number of contacts generated.
+    pub fn new(max_contacts: usize, iterations: usize) -> Self {
+        Self {
+            particles: Vec::new(),
+            registry: ParticleForceRegistry::new(),
+            contact_generators: Vec::new(),
+            resolver: ParticleContactResolver::new(iterations),
+            max_contacts,
+            calculate_iterations: iterations == 0,
+            contacts: Vec::with_capacity(max_contacts),
+        }
+    }
+
+    /// Adds a particle to the world, returning its index.
+    pub fn add_particle(&mut self, particle: Particle<F>) -> usize {
+        self.particles.push(particle);
+        self.particles.len() - 1
+    }
+
+    /// Returns the contacts generated during the last frame.
+    pub fn contacts(&self) -> &[ParticleContact<F>] {
+        &self.contacts
+    }
+
+    /// Initializes the world for a simulation frame.
+    /// This clears the force accumulators for the particles in the world.
+    pub fn start_frame(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.clear_accumulator();
+        }
+    }
+
+    /// Calls each of the registered contact generators to report their contacts.
+    /// Returns the number of generated contacts.
+    pub fn generate_contacts(&mut self) -> usize {
+        self.contacts.clear();
+
+        for generator in self.contact_generators.iter() {
+            let limit = self.max_contacts - self.contacts.len();
+            generator.add_contact(&self.particles, &mut self.contacts, limit);
+
+            // We've run out of contacts to fill; this means we're missing contacts.
+            if self.contacts.len() >= self.max_contacts {
+                break;
+            }
+        }
+
+        self.contacts.len()
+    }
+
+    /// Integrates all the particles in the world forward in time by the given duration.
+    pub fn integrate(&mut self, duration: F) {
+        for particle in self.particles.iter_mut() {
+            particle.integrate(duration);
+        }
+    }
+
+    /// Processes all the physics for the particle world.
+    pub fn run_physics(&mut self, duration: F) {
+        // First apply the force generators.
+        self.registry.update_forces(&mut self.particles, duration);
+
+        // Then integrate the objects.
+        self.integrate(duration);
+
+        // Generate contacts and process them.
+        let used_contacts = self.generate_contacts();
+        if used_contacts > 0 {
+            if self.calculate_iterations {
+                self.resolver.iterations = used_contacts * 2;
+            }
+            self.resolver
+                .resolve_contacts(&mut self.contacts, &mut self.particles, duration);
+        }
+    }
+}
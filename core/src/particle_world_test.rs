@@ -0,0 +1,96 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::particle_force::*;
+use crate::particle_link::*;
+use crate::particle_world::*;
+use math::Vector3;
+
+#[test]
+fn free_fall() {
+    let mut world = ParticleWorld::<f64>::new(10, 0);
+    let index = world.add_particle(Particle::new(Vector3::origin(), 2.0));
+    world.registry.add(
+        index,
+        Box::new(ParticleGravity::new(Vector3::new(0.0, -10.0, 0.0))),
+    );
+
+    world.start_frame();
+    world.run_physics(0.5);
+    assert_eq!(
+        Vector3::new(0.0, -5.0, 0.0),
+        world.particles[index].velocity
+    );
+    assert_eq!(0, world.generate_contacts());
+}
+
+#[test]
+fn pendulum() {
+    let mut world = ParticleWorld::<f64>::new(10, 0);
+    let mut anchor = Particle::new(Vector3::origin(), 1.0);
+    anchor.set_infinite_mass();
+    let anchor = world.add_particle(anchor);
+    let bob = world.add_particle(Particle::new(Vector3::new(1.0, 0.0, 0.0), 1.0));
+    world.registry.add(
+        bob,
+        Box::new(ParticleGravity::new(Vector3::new(0.0, -10.0, 0.0))),
+    );
+    world
+        .contact_generators
+        .push(Box::new(ParticleRod::new([anchor, bob], 1.0)));
+
+    for _ in 0..100 {
+        world.start_frame();
+        world.run_physics(0.01);
+        let length = world.particles[bob].position.magnitude();
+        assert!((length - 1.0).abs() < 1e-9);
+    }
+
+    assert_eq!(Vector3::origin(), world.particles[anchor].position);
+    assert!(world.particles[bob].position.y < 0.0);
+    assert!(world.resolver.iterations_used > 0);
+}
+
+#[test]
+fn max_contacts() {
+    let mut world = ParticleWorld::<f64>::new(1, 0);
+    let first = world.add_particle(Particle::new(Vector3::origin(), 1.0));
+    let second = world.add_particle(Particle::new(Vector3::new(3.0, 0.0, 0.0), 1.0));
+    world
+        .contact_generators
+        .push(Box::new(ParticleCable::new([first, second], 1.0, 0.0)));
+    world
+        .contact_generators
+        .push(Box::new(ParticleRod::new([first, second], 1.0)));
+
+    assert_eq!(1, world.generate_contacts());
+    assert_eq!(1, world.contacts().len());
+}
+
+#[test]
+fn point_charges() {
+    let mut world = ParticleWorld::<f64>::new(10, 0);
+    let first = world.add_particle(Particle::new(Vector3::origin(), 1.0));
+    let second = world.add_particle(Particle::new(Vector3::new(0.0, 1.0, 0.0), 1.0));
+    world.registry.add(
+        first,
+        Box::new(ParticlePointCharge::electrostatic(second, 1e-6, -1e-6, 0.0)),
+    );
+    world.registry.add(
+        second,
+        Box::new(ParticlePointCharge::electrostatic(first, -1e-6, 1e-6, 0.0)),
+    );
+
+    world.start_frame();
+    world.run_physics(0.01);
+    assert!(world.particles[first].velocity.y > 0.0);
+    assert!(world.particles[second].velocity.y < 0.0);
+    assert_eq!(
+        world.particles[first].velocity.y,
+        -world.particles[second].velocity.y
+    );
+}
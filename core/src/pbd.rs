@@ -0,0 +1,516 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::particle_force::ParticleForceRegistry;
+use crate::plane::Plane;
+use crate::rigid_body::Power;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Default number of times the constraints of a position-based world are projected every substep.
+pub const DEFAULT_PBD_ITERATIONS: usize = 8;
+
+/// Default number of substeps a position-based world splits every frame into.
+pub const DEFAULT_PBD_SUBSTEPS: usize = 1;
+
+/// Polymorphic interface for constraints projected by position-based dynamics.
+///
+/// # Remarks
+/// Constraints follow extended position-based dynamics (XPBD): each one has a compliance,
+/// the inverse of its stiffness, and accumulates its Lagrange multiplier over the iterations
+/// of a substep. The correction then converges to the one of a physical spring, independently
+/// of the timestep and of the number of iterations, and a zero compliance is perfectly rigid.
+pub trait PbdConstraint<F: num_traits::Float = f64> {
+    /// Clears the Lagrange multiplier accumulated by the constraint, before the first iteration
+    /// of each substep.
+    fn reset(&mut self);
+
+    /// Moves the particles of the constraint towards satisfying it, for a substep of the given
+    /// duration.
+    fn project(&mut self, particles: &mut [Particle<F>], duration: F);
+}
+
+/// Keeps a pair of particles at a given distance.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DistanceConstraint<F: num_traits::Float = f64> {
+    /// Indices of the pair of particles linked by the constraint.
+    pub particles: [usize; 2],
+
+    /// Distance kept between the particles.
+    pub rest_length: F,
+
+    /// Inverse of the stiffness of the constraint, in distance per unit of force.
+    pub compliance: F,
+
+    lambda: F,
+}
+
+impl<F: num_traits::Float> DistanceConstraint<F> {
+    /// Creates a new rigid constraint keeping the given particles at their current distance.
+    pub fn new(particles: [usize; 2], positions: &[Particle<F>]) -> Self {
+        Self {
+            particles,
+            rest_length: positions[particles[0]]
+                .position
+                .vector_sub(&positions[particles[1]].position)
+                .magnitude(),
+            compliance: num_traits::zero(),
+            lambda: num_traits::zero(),
+        }
+    }
+}
+
+impl<F: num_traits::Float> PbdConstraint<F> for DistanceConstraint<F> {
+    fn reset(&mut self) {
+        self.lambda = num_traits::zero();
+    }
+
+    fn project(&mut self, particles: &mut [Particle<F>], duration: F) {
+        if let Some((error, gradients)) =
+            distance_error(particles, self.particles, self.rest_length)
+        {
+            let weight = weight(particles, &self.particles, &gradients);
+            let delta = multiplier(error, weight, self.compliance, &mut self.lambda, duration);
+            correct(particles, &self.particles, &gradients, delta);
+        }
+    }
+}
+
+/// Keeps the signed volume of the tetrahedron between four particles.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct VolumeConstraint<F: num_traits::Float = f64> {
+    /// Indices of the particles at the corners of the tetrahedron.
+    pub particles: [usize; 4],
+
+    /// Signed volume kept by the tetrahedron.
+    pub rest_volume: F,
+
+    /// Inverse of the stiffness of the constraint, in volume per unit of pressure.
+    pub compliance: F,
+
+    lambda: F,
+}
+
+impl<F: num_traits::Float> VolumeConstraint<F> {
+    /// Creates a new rigid constraint keeping the current volume of the given particles.
+    pub fn new(particles: [usize; 4], positions: &[Particle<F>]) -> Self {
+        let corners = particles.map(|particle| positions[particle].position);
+        Self {
+            particles,
+            rest_volume: signed_volume(&corners),
+            compliance: num_traits::zero(),
+            lambda: num_traits::zero(),
+        }
+    }
+}
+
+impl<F: num_traits::Float> PbdConstraint<F> for VolumeConstraint<F> {
+    fn reset(&mut self) {
+        self.lambda = num_traits::zero();
+    }
+
+    fn project(&mut self, particles: &mut [Particle<F>], duration: F) {
+        let (error, gradients) = volume_error(particles, self.particles, self.rest_volume);
+        let weight = weight(particles, &self.particles, &gradients);
+        let delta = multiplier(error, weight, self.compliance, &mut self.lambda, duration);
+        correct(particles, &self.particles, &gradients, delta);
+    }
+}
+
+/// Attaches a particle to a point in space, like a ball joint with the scenery.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct AttachmentConstraint<F: num_traits::Float = f64> {
+    /// Index of the attached particle.
+    pub particle: usize,
+
+    /// Point the particle is attached to, in world space.
+    pub point: Vector3<F>,
+
+    /// Inverse of the stiffness of the constraint, in distance per unit of force.
+    pub compliance: F,
+
+    lambda: F,
+}
+
+impl<F: num_traits::Float> AttachmentConstraint<F> {
+    /// Creates a new rigid constraint attaching the given particle to a point.
+    pub fn new(particle: usize, point: Vector3<F>) -> Self {
+        Self {
+            particle,
+            point,
+            compliance: num_traits::zero(),
+            lambda: num_traits::zero(),
+        }
+    }
+}
+
+impl<F: num_traits::Float> PbdConstraint<F> for AttachmentConstraint<F> {
+    fn reset(&mut self) {
+        self.lambda = num_traits::zero();
+    }
+
+    fn project(&mut self, particles: &mut [Particle<F>], duration: F) {
+        let offset = particles[self.particle].position.vector_sub(&self.point);
+        let distance = offset.magnitude();
+        if distance <= num_traits::zero() {
+            return;
+        }
+
+        let gradients = [offset.scalar_div(distance)];
+        let indices = [self.particle];
+        let weight = weight(particles, &indices, &gradients);
+        let delta = multiplier(
+            distance,
+            weight,
+            self.compliance,
+            &mut self.lambda,
+            duration,
+        );
+        correct(particles, &indices, &gradients, delta);
+    }
+}
+
+/// Keeps every particle on the positive side of a plane, as contacts with the ground.
+///
+/// # Remarks
+/// The contacts only push the particles out, so their multipliers never pull them back in.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HalfSpaceConstraint<F: num_traits::Float = f64> {
+    /// Plane bounding the half-space the particles are kept out of.
+    pub plane: Plane<F>,
+
+    /// Radius of the particles, kept above the plane.
+    pub radius: F,
+
+    /// Inverse of the stiffness of the contacts, in distance per unit of force.
+    pub compliance: F,
+
+    lambdas: Vec<F>,
+}
+
+impl<F: num_traits::Float> HalfSpaceConstraint<F> {
+    /// Creates a new constraint keeping particles of the given radius above the plane,
+    /// with rigid contacts.
+    pub fn new(plane: Plane<F>, radius: F) -> Self {
+        Self {
+            plane,
+            radius,
+            compliance: num_traits::zero(),
+            lambdas: Vec::new(),
+        }
+    }
+}
+
+impl<F: num_traits::Float> PbdConstraint<F> for HalfSpaceConstraint<F> {
+    fn reset(&mut self) {
+        self.lambdas.clear();
+    }
+
+    fn project(&mut self, particles: &mut [Particle<F>], duration: F) {
+        self.lambdas.resize(particles.len(), num_traits::zero());
+        let gradients = [self.plane.normal];
+        for (index, lambda) in self.lambdas.iter_mut().enumerate() {
+            let error = self.plane.signed_distance(&particles[index].position) - self.radius;
+            if error >= num_traits::zero() || !particles[index].has_finite_mass() {
+                continue;
+            }
+
+            let indices = [index];
+            let weight = weight(particles, &indices, &gradients);
+            let previous = *lambda;
+            multiplier(error, weight, self.compliance, lambda, duration);
+            *lambda = lambda.max(num_traits::zero());
+            correct(particles, &indices, &gradients, *lambda - previous);
+        }
+    }
+}
+
+/// Keeps track of a set of particles linked by constraints, simulated with position-based
+/// dynamics as an alternative to the impulses of a `ParticleWorld`.
+///
+/// # Remarks
+/// Particles are integrated first, then the constraints are projected a number of times,
+/// moving the particles straight to positions satisfying them, and finally the velocities
+/// are derived from the corrected positions. Because positions can't drift away from the
+/// constraints, stiff ropes, cloth and soft bodies stay stable at large timesteps.
+///
+/// Frames can be split into substeps, each one integrating and projecting the constraints
+/// with the forces of the frame, which converges faster than more iterations.
+pub struct PbdWorld<F: num_traits::Float = f64> {
+    /// Particles simulated by the world.
+    pub particles: Vec<Particle<F>>,
+
+    /// Force generators applied to the particles of the world.
+    pub registry: ParticleForceRegistry<F>,
+
+    /// Constraints projected at each frame, in order.
+    pub constraints: Vec<Box<dyn PbdConstraint<F>>>,
+
+    /// Acceleration due to gravity, applied to every particle.
+    pub gravity: Vector3<F>,
+
+    /// Number of times the constraints are projected every substep.
+    pub iterations: usize,
+
+    /// Number of substeps every frame is split into.
+    pub substeps: usize,
+
+    previous: Vec<Vector3<F>>,
+}
+
+impl<F: num_traits::Float> Default for PbdWorld<F> {
+    fn default() -> Self {
+        Self::new(DEFAULT_PBD_ITERATIONS)
+    }
+}
+
+impl<F: num_traits::Float> PbdWorld<F> {
+    /// Creates a new empty world projecting its constraints the given number of times.
+    pub fn new(iterations: usize) -> Self {
+        Self {
+            particles: Vec::new(),
+            registry: ParticleForceRegistry::new(),
+            constraints: Vec::new(),
+            gravity: Vector3::origin(),
+            iterations,
+            substeps: DEFAULT_PBD_SUBSTEPS,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Adds a particle to the world, returning its index.
+    pub fn add_particle(&mut self, particle: Particle<F>) -> usize {
+        self.particles.push(particle);
+        self.particles.len() - 1
+    }
+
+    /// Adds a constraint to the world, returning its index.
+    pub fn add_constraint(&mut self, constraint: Box<dyn PbdConstraint<F>>) -> usize {
+        self.constraints.push(constraint);
+        self.constraints.len() - 1
+    }
+
+    /// Initializes the world for a simulation frame.
+    /// This clears the force accumulators for the particles in the world.
+    pub fn start_frame(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.clear_accumulator();
+        }
+    }
+
+    /// Processes all the physics for the world.
+    pub fn run_physics(&mut self, duration: F) {
+        // First apply the force generators, keeping the forces for every substep.
+        self.registry.update_forces(&mut self.particles, duration);
+        let forces: Vec<Vector3<F>> = self
+            .particles
+            .iter()
+            .map(|particle| particle.force_accum)
+            .collect();
+
+        let substeps = self.substeps.max(1);
+        let substep = duration / math::real(substeps as f64);
+        for _ in 0..substeps {
+            for (particle, force) in self.particles.iter_mut().zip(forces.iter()) {
+                particle.force_accum = *force;
+            }
+
+            // Then move the particles freely, and project them back onto the constraints.
+            predict(
+                &mut self.particles,
+                &mut self.previous,
+                &self.gravity,
+                substep,
+                F::powf,
+            );
+            for constraint in self.constraints.iter_mut() {
+                constraint.reset();
+            }
+            for _ in 0..self.iterations {
+                for constraint in self.constraints.iter_mut() {
+                    constraint.project(&mut self.particles, substep);
+                }
+            }
+
+            // Finally derive the velocities from the corrected positions.
+            derive_velocities(&mut self.particles, &self.previous, substep);
+        }
+    }
+}
+
+/// Moves the particles with their velocities after applying gravity and their forces,
+/// remembering where they started in `previous`.
+pub(crate) fn predict<F: num_traits::Float>(
+    particles: &mut [Particle<F>],
+    previous: &mut Vec<Vector3<F>>,
+    gravity: &Vector3<F>,
+    duration: F,
+    power: Power<F>,
+) {
+    previous.clear();
+    for particle in particles.iter_mut() {
+        previous.push(particle.position);
+        if particle.has_finite_mass() {
+            let acceleration =
+                gravity.vector_add(&particle.force_accum.scalar_mul(particle.inverse_mass));
+            particle
+                .velocity
+                .inplace_vector_add(&acceleration.scalar_mul(duration))
+                .inplace_scalar_mul(power(particle.damping, duration));
+            particle
+                .position
+                .inplace_vector_add(&particle.velocity.scalar_mul(duration));
+        }
+        particle.clear_accumulator();
+    }
+}
+
+/// Derives the velocities of the particles from the distance they moved since `previous`.
+pub(crate) fn derive_velocities<F: num_traits::Float>(
+    particles: &mut [Particle<F>],
+    previous: &[Vector3<F>],
+    duration: F,
+) {
+    for (particle, previous) in particles.iter_mut().zip(previous.iter()) {
+        if particle.has_finite_mass() {
+            particle.velocity = particle.position.vector_sub(previous).scalar_div(duration);
+        }
+    }
+}
+
+/// Returns the signed volume of the tetrahedron with the given corners.
+pub(crate) fn signed_volume<F: num_traits::Float>(corners: &[Vector3<F>; 4]) -> F {
+    let one = corners[1].vector_sub(&corners[0]);
+    let two = corners[2].vector_sub(&corners[0]);
+    let three = corners[3].vector_sub(&corners[0]);
+    one.dot_product(&two.cross_product(&three)) / math::real(6.0)
+}
+
+/// Moves a pair of particles towards the given distance,
+/// in proportion to their inverse masses and the given stiffness.
+pub(crate) fn project_distance<F: num_traits::Float>(
+    particles: &mut [Particle<F>],
+    pair: [usize; 2],
+    rest_length: F,
+    stiffness: F,
+) {
+    if let Some((error, gradients)) = distance_error(particles, pair, rest_length) {
+        let weight = weight(particles, &pair, &gradients);
+        if weight > num_traits::zero() {
+            correct(particles, &pair, &gradients, -error * stiffness / weight);
+        }
+    }
+}
+
+/// Moves the corners of a tetrahedron along the gradient of its volume towards
+/// the given volume, in proportion to their inverse masses and the given stiffness.
+pub(crate) fn project_volume<F: num_traits::Float>(
+    particles: &mut [Particle<F>],
+    corners: [usize; 4],
+    rest_volume: F,
+    stiffness: F,
+) {
+    let (error, gradients) = volume_error(particles, corners, rest_volume);
+    let weight = weight(particles, &corners, &gradients);
+    if weight > num_traits::zero() {
+        correct(particles, &corners, &gradients, -error * stiffness / weight);
+    }
+}
+
+/// Returns how much longer than the given distance a pair of particles are,
+/// and the gradients of that error, unless the particles are on top of each other.
+fn distance_error<F: num_traits::Float>(
+    particles: &[Particle<F>],
+    [one, two]: [usize; 2],
+    rest_length: F,
+) -> Option<(F, [Vector3<F>; 2])> {
+    let offset = particles[two].position.vector_sub(&particles[one].position);
+    let length = offset.magnitude();
+    if length <= num_traits::zero() {
+        return None;
+    }
+
+    let direction = offset.scalar_div(length);
+    Some((length - rest_length, [direction.invert(), direction]))
+}
+
+/// Returns how much larger than the given volume a tetrahedron is,
+/// and the gradients of that error.
+fn volume_error<F: num_traits::Float>(
+    particles: &[Particle<F>],
+    corners: [usize; 4],
+    rest_volume: F,
+) -> (F, [Vector3<F>; 4]) {
+    let positions = corners.map(|corner| particles[corner].position);
+    let one = positions[1].vector_sub(&positions[0]);
+    let two = positions[2].vector_sub(&positions[0]);
+    let three = positions[3].vector_sub(&positions[0]);
+    let sixth = math::real::<F>(1.0 / 6.0);
+
+    let mut gradients = [
+        Vector3::origin(),
+        two.cross_product(&three).scalar_mul(sixth),
+        three.cross_product(&one).scalar_mul(sixth),
+        one.cross_product(&two).scalar_mul(sixth),
+    ];
+    gradients[0] = gradients[1]
+        .vector_add(&gradients[2])
+        .vector_add(&gradients[3])
+        .invert();
+    (signed_volume(&positions) - rest_volume, gradients)
+}
+
+/// Returns the sum of the squared gradients of a constraint, weighted by the inverse masses
+/// of their particles.
+fn weight<F: num_traits::Float>(
+    particles: &[Particle<F>],
+    indices: &[usize],
+    gradients: &[Vector3<F>],
+) -> F {
+    indices
+        .iter()
+        .zip(gradients.iter())
+        .fold(F::zero(), |sum, (index, gradient)| {
+            sum + particles[*index].inverse_mass * gradient.squared_magnitude()
+        })
+}
+
+/// Returns the change of the Lagrange multiplier of a compliant constraint with the given
+/// error and weight for a substep of the given duration, adding it to the multiplier.
+fn multiplier<F: num_traits::Float>(
+    error: F,
+    weight: F,
+    compliance: F,
+    lambda: &mut F,
+    duration: F,
+) -> F {
+    let scaled_compliance = compliance / (duration * duration);
+    let denominator = weight + scaled_compliance;
+    if denominator <= num_traits::zero() {
+        return num_traits::zero();
+    }
+
+    let delta = (-error - scaled_compliance * *lambda) / denominator;
+    *lambda = *lambda + delta;
+    delta
+}
+
+/// Moves the particles of a constraint along its gradients, scaled by the given multiplier
+/// and their inverse masses.
+fn correct<F: num_traits::Float>(
+    particles: &mut [Particle<F>],
+    indices: &[usize],
+    gradients: &[Vector3<F>],
+    multiplier: F,
+) {
+    for (index, gradient) in indices.iter().zip(gradients.iter()) {
+        let inverse_mass = particles[*index].inverse_mass;
+        particles[*index]
+            .position
+            .inplace_vector_add(&gradient.scalar_mul(multiplier * inverse_mass));
+    }
+}
@@ -0,0 +1,114 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::pbd::*;
+use crate::plane::Plane;
+use math::Vector3;
+
+/// Rope of ten links hanging sideways from a fixed anchor at the origin.
+fn rope(world: &mut PbdWorld) {
+    let mut anchor = Particle::new(Vector3::origin(), 1.0);
+    anchor.set_infinite_mass();
+    world.add_particle(anchor);
+    for link in 1..=10 {
+        let index = world.add_particle(Particle::new(
+            Vector3::new(link as f64 * 0.5, 0.0, 0.0),
+            0.1,
+        ));
+        let constraint = DistanceConstraint::new([index - 1, index], &world.particles);
+        world.add_constraint(Box::new(constraint));
+    }
+    world.gravity = Vector3::new(0.0, -10.0, 0.0);
+}
+
+#[test]
+fn rope_at_large_timesteps() {
+    let mut world = PbdWorld::<f64>::new(20);
+    rope(&mut world);
+
+    // Even at ten frames per second, the rope swings down without stretching.
+    for _ in 0..50 {
+        world.start_frame();
+        world.run_physics(0.1);
+    }
+    for pair in world.particles.windows(2) {
+        let length = (pair[1].position - pair[0].position).magnitude();
+        assert!((length - 0.5).abs() < 0.05);
+    }
+    let end = world.particles[10];
+    assert!(end.position.y < -3.0);
+    assert!(end.velocity.magnitude().is_finite());
+    assert_eq!(Vector3::origin(), world.particles[0].position);
+}
+
+#[test]
+fn constraints() {
+    let mut world = PbdWorld::<f64>::default();
+    for corner in [
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(1.0, 1.0, 0.0),
+        Vector3::new(0.0, 2.0, 0.0),
+        Vector3::new(0.0, 1.0, 1.0),
+    ] {
+        world.add_particle(Particle::new(corner, 1.0));
+    }
+    let volume = VolumeConstraint::new([0, 1, 2, 3], &world.particles);
+    assert!((volume.rest_volume - 1.0 / 6.0).abs() < 1e-12);
+    world.add_constraint(Box::new(volume));
+    for edge in [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]] {
+        let mut constraint = DistanceConstraint::new(edge, &world.particles);
+        constraint.compliance = 1e-4;
+        world.add_constraint(Box::new(constraint));
+    }
+    world.add_constraint(Box::new(HalfSpaceConstraint::new(
+        Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0),
+        0.1,
+    )));
+    world.gravity = Vector3::new(0.0, -10.0, 0.0);
+
+    // The soft tetrahedron falls onto the ground, and comes to rest keeping its volume.
+    for _ in 0..60 {
+        world.start_frame();
+        world.run_physics(1.0 / 30.0);
+    }
+    for particle in world.particles.iter() {
+        assert!(particle.position.y >= 0.1 - 1e-9);
+        assert!(particle.velocity.magnitude() < 0.05);
+    }
+    let corners = [0, 1, 2, 3].map(|index| world.particles[index].position);
+    let one = corners[1] - corners[0];
+    let two = corners[2] - corners[0];
+    let three = corners[3] - corners[0];
+    let volume = one.dot_product(&two.cross_product(&three)) / 6.0;
+    assert!((volume - 1.0 / 6.0).abs() < 0.01);
+}
+
+/// Returns how much a weight of one unit of mass stretches a compliant cord attached to
+/// the origin, once it comes to rest.
+fn stretch(duration: f64, substeps: usize) -> f64 {
+    let mut world = PbdWorld::<f64>::new(4);
+    world.substeps = substeps;
+    world.gravity = Vector3::new(0.0, -10.0, 0.0);
+    let weight = world.add_particle(Particle::new(Vector3::new(0.0, -0.05, 0.0), 1.0));
+    let mut cord = AttachmentConstraint::new(weight, Vector3::origin());
+    cord.compliance = 0.001;
+    world.add_constraint(Box::new(cord));
+
+    for _ in 0..(20.0 / duration) as usize {
+        world.start_frame();
+        world.run_physics(duration);
+    }
+    world.particles[weight].position.magnitude()
+}
+
+#[test]
+fn compliance() {
+    // The cord behaves like a spring with a stiffness of 1000, whatever the timestep.
+    for (duration, substeps) in [(1.0 / 30.0, 1), (1.0 / 120.0, 1), (1.0 / 30.0, 4)] {
+        assert!((stretch(duration, substeps) - 0.01).abs() < 1e-6);
+    }
+}
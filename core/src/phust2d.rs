@@ -0,0 +1,22 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+pub mod collider;
+pub mod contact;
+pub mod joint;
+pub mod narrow_phase;
+pub mod rigid_body;
+pub mod shape;
+pub mod solver;
+pub mod transform;
+pub mod world;
+
+#[cfg(test)]
+mod narrow_phase_test;
+#[cfg(test)]
+mod shape_test;
+#[cfg(test)]
+mod world_test;
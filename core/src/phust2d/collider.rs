@@ -0,0 +1,90 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::{DEFAULT_COLLISION_GROUP, DEFAULT_COLLISION_MASK};
+use crate::material::PhysicsMaterial;
+use crate::phust2d::rigid_body::RigidBody2D;
+use crate::phust2d::shape::Shape2D;
+use crate::phust2d::transform::Transform2D;
+use serde::{Deserialize, Serialize};
+
+/// Shape attached to a rigid body in 2D, used to detect collisions.
+///
+/// # Remarks
+/// Like the colliders in 3D, the shape is placed in the local space of the body with an
+/// offset, and its world transform is cached, refreshed by `calculate_internals` whenever
+/// the body moves. Two colliders only interact when the group of each one is in the mask
+/// of the other.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Collider2D<F: num_traits::Float = f64> {
+    /// Index of the rigid body the collider is attached to.
+    pub body: usize,
+
+    /// Offset of the shape from the origin of the body.
+    pub offset: Transform2D<F>,
+
+    /// Shape of the collider.
+    pub shape: Shape2D<F>,
+
+    /// Material the collider is made of, with its density per unit of area.
+    pub material: PhysicsMaterial<F>,
+
+    /// Bitfield of the collision groups the collider belongs to.
+    pub group: u32,
+
+    /// Bitfield of the collision groups the collider interacts with.
+    pub mask: u32,
+
+    transform: Transform2D<F>,
+}
+
+impl<F: num_traits::Float> Collider2D<F> {
+    /// Creates a new collider with the given shape, attached to the origin of the given body.
+    pub fn new(body: usize, shape: Shape2D<F>) -> Self {
+        Self::with_offset(body, shape, Transform2D::identity())
+    }
+
+    /// Creates a new collider with the given shape, attached to the given body with an offset.
+    pub fn with_offset(body: usize, shape: Shape2D<F>, offset: Transform2D<F>) -> Self {
+        Self {
+            body,
+            offset,
+            shape,
+            material: PhysicsMaterial::default(),
+            group: DEFAULT_COLLISION_GROUP,
+            mask: DEFAULT_COLLISION_MASK,
+            transform: offset,
+        }
+    }
+
+    /// Returns true if the collider interacts with the given collision group and mask.
+    pub fn interacts_with(&self, group: u32, mask: u32) -> bool {
+        self.group & mask != 0 && group & self.mask != 0
+    }
+
+    /// Calculates the world transform of the collider from the transform of its body.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the collider.
+    pub fn calculate_internals(&mut self, bodies: &[RigidBody2D<F>]) -> &mut Self {
+        self.transform = bodies[self.body].transform().compose(&self.offset);
+        self
+    }
+
+    /// Returns the world transform of the collider.
+    pub fn transform(&self) -> &Transform2D<F> {
+        &self.transform
+    }
+
+    /// Returns the mass of the collider, from its shape and the density of its material,
+    /// and its moment of inertia around the origin of its body.
+    pub fn mass_properties(&self) -> (F, F) {
+        let (mass, center, inertia) = self.shape.mass_properties(self.material.density);
+        let center = self.offset.transform(&center);
+        (mass, inertia + mass * center.squared_magnitude())
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use math::Vector2;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Contact between two colliders in 2D, or between a collider and the scenery.
+///
+/// # Remarks
+/// Like in 3D, the contact normal points from the second collider towards the first one,
+/// so moving the first body along the normal separates them.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Contact2D<F: num_traits::Float = f64> {
+    /// Indices of the bodies involved in the contact.
+    /// The second one is `None` for contacts with the scenery.
+    pub bodies: (usize, Option<usize>),
+
+    /// Indices of the colliders involved in the contact, or of the scenery plane.
+    pub colliders: (usize, usize),
+
+    /// Position of the contact in world space.
+    pub contact_point: Vector2<F>,
+
+    /// Direction of the contact in world space.
+    pub contact_normal: Vector2<F>,
+
+    /// Depth of penetration at the contact point.
+    pub penetration: F,
+
+    /// Normal restitution coefficient at the contact.
+    pub restitution: F,
+
+    /// Dynamic friction coefficient at the contact.
+    pub friction: F,
+
+    /// Static friction coefficient at the contact.
+    pub static_friction: F,
+
+    /// Identifier of the features of both shapes that generated the contact,
+    /// used to match contacts across frames.
+    pub feature: u32,
+}
+
+impl<F: num_traits::Float> Contact2D<F> {
+    /// Creates a new contact between the given bodies, without restitution nor friction.
+    pub fn new(
+        bodies: (usize, Option<usize>),
+        contact_point: Vector2<F>,
+        contact_normal: Vector2<F>,
+        penetration: F,
+    ) -> Self {
+        Self {
+            bodies,
+            colliders: (0, 0),
+            contact_point,
+            contact_normal,
+            penetration,
+            restitution: num_traits::zero(),
+            friction: num_traits::zero(),
+            static_friction: num_traits::zero(),
+            feature: 0,
+        }
+    }
+}
+
+/// Contact point cached in a manifold across frames, with the impulses accumulated by
+/// the solver to warm start the next frame.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ManifoldPoint2D<F: num_traits::Float = f64> {
+    /// Contact as of the last update.
+    pub contact: Contact2D<F>,
+
+    /// Normal impulse accumulated by the contact solver in the last frame.
+    pub normal_impulse: F,
+
+    /// Friction impulse accumulated by the contact solver in the last frame.
+    pub tangent_impulse: F,
+}
+
+/// Contact points between a pair of bodies in 2D.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ContactManifold2D<F: num_traits::Float = f64> {
+    /// Indices of the bodies in contact.
+    pub bodies: (usize, Option<usize>),
+
+    /// Contact points of the manifold.
+    pub points: Vec<ManifoldPoint2D<F>>,
+}
+
+/// Keeps the contact manifolds of every pair of bodies in contact in 2D.
+///
+/// # Remarks
+/// The narrow phase in 2D generates full manifolds every frame, so the points of the
+/// previous frame are replaced, only passing on their accumulated impulses to the new
+/// points generated by the same features of the same colliders.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ManifoldCache2D<F: num_traits::Float = f64> {
+    manifolds: BTreeMap<(usize, Option<usize>), ContactManifold2D<F>>,
+}
+
+impl<F: num_traits::Float> Default for ManifoldCache2D<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: num_traits::Float> ManifoldCache2D<F> {
+    /// Creates a new empty cache.
+    pub fn new() -> Self {
+        Self {
+            manifolds: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of manifolds in the cache.
+    pub fn len(&self) -> usize {
+        self.manifolds.len()
+    }
+
+    /// Returns true if there are no manifolds in the cache.
+    pub fn is_empty(&self) -> bool {
+        self.manifolds.is_empty()
+    }
+
+    /// Returns the manifold between the given bodies, if any.
+    pub fn get(&self, bodies: (usize, Option<usize>)) -> Option<&ContactManifold2D<F>> {
+        self.manifolds.get(&bodies)
+    }
+
+    /// Returns an iterator over the manifolds, sorted by pair of bodies.
+    pub fn iter(&self) -> impl Iterator<Item = &ContactManifold2D<F>> {
+        self.manifolds.values()
+    }
+
+    /// Returns a mutable iterator over the manifolds, sorted by pair of bodies.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ContactManifold2D<F>> {
+        self.manifolds.values_mut()
+    }
+
+    /// Removes all the manifolds.
+    pub fn clear(&mut self) {
+        self.manifolds.clear();
+    }
+
+    /// Replaces the manifolds with the contacts generated this frame.
+    ///
+    /// # Remarks
+    /// Pairs of bodies without contacts this frame have separated, so their manifolds are removed.
+    pub fn update(&mut self, contacts: &[Contact2D<F>]) {
+        let mut manifolds: BTreeMap<_, ContactManifold2D<F>> = BTreeMap::new();
+        for contact in contacts.iter() {
+            let previous = self.manifolds.get(&contact.bodies).and_then(|manifold| {
+                manifold.points.iter().find(|point| {
+                    point.contact.colliders == contact.colliders
+                        && point.contact.feature == contact.feature
+                })
+            });
+            let point = ManifoldPoint2D {
+                contact: *contact,
+                normal_impulse: previous.map_or_else(F::zero, |point| point.normal_impulse),
+                tangent_impulse: previous.map_or_else(F::zero, |point| point.tangent_impulse),
+            };
+            manifolds
+                .entry(contact.bodies)
+                .or_insert_with(|| ContactManifold2D {
+                    bodies: contact.bodies,
+                    points: Vec::new(),
+                })
+                .points
+                .push(point);
+        }
+        self.manifolds = manifolds;
+    }
+}
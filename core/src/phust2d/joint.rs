@@ -0,0 +1,566 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::joint::{JointLimits, JointMotor, JointSpring, MotorTarget};
+use crate::phust2d::rigid_body::RigidBody2D;
+use crate::solver::{row_impulse, ImpulseConstraint};
+use math::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// Joint letting two bodies turn freely around a shared anchor point, like a pin.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RevoluteJoint2D<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector2<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector2<F>,
+
+    /// Angle of the first body relative to the second one at which the joint is at rest.
+    pub reference_angle: F,
+
+    /// Range of angles allowed, if any.
+    pub limits: Option<JointLimits<F>>,
+
+    /// Motor driving the angular speed, or the angle, of the joint, if any.
+    pub motor: Option<JointMotor<F>>,
+}
+
+impl<F: num_traits::Float> RevoluteJoint2D<F> {
+    /// Creates a new revolute joint between the given anchors, without limits nor motor.
+    pub fn new(local_anchor_one: Vector2<F>, local_anchor_two: Vector2<F>) -> Self {
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+            reference_angle: num_traits::zero(),
+            limits: None,
+            motor: None,
+        }
+    }
+
+    /// Creates a new revolute joint between the given bodies around the given point in
+    /// world space, at rest in their current placement.
+    pub fn from_world(
+        one: &RigidBody2D<F>,
+        two: Option<&RigidBody2D<F>>,
+        anchor: &Vector2<F>,
+    ) -> Self {
+        let (local_anchor_two, angle_two) = local_anchor(two, anchor);
+        Self {
+            reference_angle: one.angle - angle_two,
+            ..Self::new(one.point_in_local_space(anchor), local_anchor_two)
+        }
+    }
+
+    /// Returns the angle of the joint, the one of the first body relative to the second one
+    /// measured from the reference angle.
+    pub fn angle(&self, one: &RigidBody2D<F>, two: Option<&RigidBody2D<F>>) -> F {
+        one.angle - two.map_or_else(F::zero, |two| two.angle) - self.reference_angle
+    }
+}
+
+/// Joint keeping the anchors of two bodies at a given distance, like a rod.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DistanceJoint2D<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector2<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector2<F>,
+
+    /// Distance kept between the anchors.
+    pub length: F,
+
+    /// Damped spring replacing the rigid rod, if any.
+    pub spring: Option<JointSpring<F>>,
+}
+
+impl<F: num_traits::Float> DistanceJoint2D<F> {
+    /// Creates a new rigid distance joint between the given anchors.
+    pub fn new(local_anchor_one: Vector2<F>, local_anchor_two: Vector2<F>, length: F) -> Self {
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+            length,
+            spring: None,
+        }
+    }
+
+    /// Creates a new rigid distance joint between the given points in world space, keeping
+    /// their current distance.
+    pub fn from_world(
+        one: &RigidBody2D<F>,
+        two: Option<&RigidBody2D<F>>,
+        point_one: &Vector2<F>,
+        point_two: &Vector2<F>,
+    ) -> Self {
+        Self::new(
+            one.point_in_local_space(point_one),
+            local_anchor(two, point_two).0,
+            point_one.vector_sub(point_two).magnitude(),
+        )
+    }
+}
+
+/// Joint gluing two bodies together at an anchor, so they move as one.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct WeldJoint2D<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector2<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector2<F>,
+
+    /// Angle of the first body relative to the second one kept by the joint.
+    pub reference_angle: F,
+}
+
+impl<F: num_traits::Float> WeldJoint2D<F> {
+    /// Creates a new weld joint between the given bodies at the given point in world space,
+    /// keeping their current placement.
+    pub fn from_world(
+        one: &RigidBody2D<F>,
+        two: Option<&RigidBody2D<F>>,
+        anchor: &Vector2<F>,
+    ) -> Self {
+        let (local_anchor_two, angle_two) = local_anchor(two, anchor);
+        Self {
+            local_anchor_one: one.point_in_local_space(anchor),
+            local_anchor_two,
+            reference_angle: one.angle - angle_two,
+        }
+    }
+}
+
+/// Kind of joint in 2D, with its settings.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum JointKind2D<F: num_traits::Float = f64> {
+    /// Pin letting the bodies turn around a shared anchor.
+    Revolute(RevoluteJoint2D<F>),
+
+    /// Rod, or spring, keeping the anchors at a distance.
+    Distance(DistanceJoint2D<F>),
+
+    /// Weld keeping the bodies together.
+    Weld(WeldJoint2D<F>),
+}
+
+impl<F: num_traits::Float> JointKind2D<F> {
+    /// Returns the maximum number of constraint rows the joint is solved with.
+    fn max_rows(&self) -> usize {
+        match self {
+            JointKind2D::Revolute(_) => 4,
+            JointKind2D::Distance(_) => 1,
+            JointKind2D::Weld(_) => 3,
+        }
+    }
+}
+
+/// Constraint between two rigid bodies in 2D, or between a rigid body and the scenery.
+///
+/// # Remarks
+/// Like in 3D, joints are made of constraint rows solved alongside the contacts, keeping
+/// the impulses accumulated in the last frame to warm start the next one, and correcting
+/// their drift with the Baumgarte fraction of the solver.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Joint2D<F: num_traits::Float = f64> {
+    /// Indices of the bodies joined together.
+    /// The second one is `None` for joints attached to the scenery.
+    pub bodies: (usize, Option<usize>),
+
+    /// Kind of the joint.
+    pub kind: JointKind2D<F>,
+
+    /// Disabled joints are ignored by the solver.
+    pub enabled: bool,
+
+    impulses: Vec<F>,
+}
+
+impl<F: num_traits::Float> Joint2D<F> {
+    /// Creates a new enabled joint between the given bodies.
+    pub fn new(bodies: (usize, Option<usize>), kind: JointKind2D<F>) -> Self {
+        Self {
+            bodies,
+            kind,
+            enabled: true,
+            impulses: vec![num_traits::zero(); kind.max_rows()],
+        }
+    }
+
+    /// Returns the impulses accumulated by the solver in the last frame,
+    /// one per constraint row of the joint.
+    pub fn impulses(&self) -> &[F] {
+        &self.impulses
+    }
+
+    /// Adds the constraint rows of the joint, as of the current state of the bodies,
+    /// removing the given fraction of the drift of rigid rows every frame.
+    pub(crate) fn rows(
+        &self,
+        joint: usize,
+        bodies: &[RigidBody2D<F>],
+        duration: F,
+        baumgarte: F,
+        rows: &mut Vec<JointRow2D<F>>,
+    ) {
+        let mut builder = RowBuilder2D {
+            joint,
+            bodies: self.bodies,
+            duration,
+            baumgarte,
+            rows,
+        };
+        let (one, two) = (
+            &bodies[self.bodies.0],
+            self.bodies.1.map(|body| &bodies[body]),
+        );
+        match &self.kind {
+            JointKind2D::Revolute(revolute) => {
+                let anchors = Anchors2D::new(
+                    one,
+                    two,
+                    &revolute.local_anchor_one,
+                    &revolute.local_anchor_two,
+                );
+                builder.point(0, &anchors);
+                let angle = revolute.angle(one, two);
+                if let Some(limits) = &revolute.limits {
+                    builder.limit(2, limits, angle);
+                }
+                if let Some(motor) = &revolute.motor {
+                    builder.motor(3, motor, angle);
+                }
+            }
+            JointKind2D::Distance(distance) => {
+                let anchors = Anchors2D::new(
+                    one,
+                    two,
+                    &distance.local_anchor_one,
+                    &distance.local_anchor_two,
+                );
+                let offset = anchors.point_one.vector_sub(&anchors.point_two);
+                let current = offset.magnitude();
+                if current <= F::epsilon() {
+                    return;
+                }
+                let direction = offset.scalar_div(current);
+                let row = builder.add(
+                    0,
+                    direction,
+                    anchors.relative_one.cross_product(&direction),
+                    anchors.relative_two.cross_product(&direction),
+                    current - distance.length,
+                );
+                if let Some(spring) = &distance.spring {
+                    row.soften(spring, current - distance.length, duration);
+                }
+            }
+            JointKind2D::Weld(weld) => {
+                let anchors =
+                    Anchors2D::new(one, two, &weld.local_anchor_one, &weld.local_anchor_two);
+                builder.point(0, &anchors);
+                let angle = one.angle - two.map_or_else(F::zero, |two| two.angle);
+                builder.add(
+                    2,
+                    Vector2::origin(),
+                    F::one(),
+                    F::one(),
+                    angle - weld.reference_angle,
+                );
+            }
+        }
+    }
+
+    /// Clears the accumulated impulses, before storing the ones of this frame.
+    pub(crate) fn reset_impulses(&mut self) {
+        let length = self.kind.max_rows();
+        self.impulses.clear();
+        self.impulses.resize(length, num_traits::zero());
+    }
+
+    /// Stores the impulse accumulated by one of the rows of the joint.
+    pub(crate) fn store_row(&mut self, row: &JointRow2D<F>) {
+        self.impulses[row.slot] = row.impulse;
+    }
+
+    /// Returns the impulse accumulated by one of the rows of the joint in the last frame.
+    pub(crate) fn impulse(&self, slot: usize) -> F {
+        self.impulses.get(slot).copied().unwrap_or_else(F::zero)
+    }
+}
+
+/// Returns the given point in the local space of the given body, along with the angle of
+/// the body, or the point itself and a zero angle for the scenery.
+fn local_anchor<F: num_traits::Float>(
+    body: Option<&RigidBody2D<F>>,
+    point: &Vector2<F>,
+) -> (Vector2<F>, F) {
+    match body {
+        Some(body) => (body.point_in_local_space(point), body.angle),
+        None => (*point, num_traits::zero()),
+    }
+}
+
+/// World positions of the anchors of a joint, and their offsets from the bodies.
+struct Anchors2D<F: num_traits::Float> {
+    point_one: Vector2<F>,
+    point_two: Vector2<F>,
+    relative_one: Vector2<F>,
+    relative_two: Vector2<F>,
+}
+
+impl<F: num_traits::Float> Anchors2D<F> {
+    /// Places the anchors, given in the local spaces of the bodies, in world space.
+    /// The anchor of the second body is in world space for joints with the scenery.
+    fn new(
+        one: &RigidBody2D<F>,
+        two: Option<&RigidBody2D<F>>,
+        local_one: &Vector2<F>,
+        local_two: &Vector2<F>,
+    ) -> Self {
+        let point_one = one.point_in_world_space(local_one);
+        let (point_two, relative_two) = match two {
+            Some(two) => {
+                let point = two.point_in_world_space(local_two);
+                (point, point.vector_sub(&two.position))
+            }
+            None => (*local_two, Vector2::origin()),
+        };
+        Self {
+            point_one,
+            point_two,
+            relative_one: point_one.vector_sub(&one.position),
+            relative_two,
+        }
+    }
+}
+
+/// Collects the constraint rows of a joint.
+struct RowBuilder2D<'a, F: num_traits::Float> {
+    joint: usize,
+    bodies: (usize, Option<usize>),
+    duration: F,
+    baumgarte: F,
+    rows: &'a mut Vec<JointRow2D<F>>,
+}
+
+impl<'a, F: num_traits::Float> RowBuilder2D<'a, F> {
+    /// Adds a row constraining the relative velocity
+    /// `linear·(v1 - v2) + angular_one·w1 - angular_two·w2`, correcting the given
+    /// position error.
+    fn add(
+        &mut self,
+        slot: usize,
+        linear: Vector2<F>,
+        angular_one: F,
+        angular_two: F,
+        error: F,
+    ) -> &mut JointRow2D<F> {
+        self.rows.push(JointRow2D {
+            joint: self.joint,
+            slot,
+            bodies: self.bodies,
+            linear,
+            angular_one,
+            angular_two,
+            bias: -self.baumgarte * error / self.duration,
+            softness: num_traits::zero(),
+            lower: F::neg_infinity(),
+            upper: F::infinity(),
+            mass: num_traits::zero(),
+            impulse: num_traits::zero(),
+        });
+        self.rows.last_mut().expect("just added")
+    }
+
+    /// Adds the rows keeping both anchors together, starting at the given slot.
+    fn point(&mut self, slot: usize, anchors: &Anchors2D<F>) {
+        let error = anchors.point_one.vector_sub(&anchors.point_two);
+        let axes = [
+            Vector2::new(F::one(), F::zero()),
+            Vector2::new(F::zero(), F::one()),
+        ];
+        for (index, axis) in axes.iter().enumerate() {
+            self.add(
+                slot + index,
+                *axis,
+                anchors.relative_one.cross_product(axis),
+                anchors.relative_two.cross_product(axis),
+                error.dot_product(axis),
+            );
+        }
+    }
+
+    /// Adds the row keeping the given angle within the nearest limit.
+    ///
+    /// # Remarks
+    /// Rigid limits let the joint turn up to the limit, but not past it, while soft ones
+    /// only push back once past it.
+    fn limit(&mut self, slot: usize, limits: &JointLimits<F>, angle: F) {
+        let (gap, sign) = if angle - limits.lower <= limits.upper - angle {
+            (angle - limits.lower, F::one())
+        } else {
+            (limits.upper - angle, -F::one())
+        };
+        if gap > F::zero() && limits.softness.is_some() {
+            return;
+        }
+
+        let duration = self.duration;
+        let row = self.add(slot, Vector2::origin(), sign, sign, gap);
+        row.lower = num_traits::zero();
+        match &limits.softness {
+            Some(spring) => row.soften(spring, gap, duration),
+            None if gap > F::zero() => row.bias = -gap / duration,
+            None => {}
+        }
+    }
+
+    /// Adds the row driving the angular speed of the joint towards the target of the motor.
+    fn motor(&mut self, slot: usize, motor: &JointMotor<F>, angle: F) {
+        let max_impulse = motor.max_force * self.duration;
+        let duration = self.duration;
+        let row = self.add(slot, Vector2::origin(), F::one(), F::one(), F::zero());
+        row.bias = match motor.target {
+            MotorTarget::Velocity(velocity) => velocity,
+            MotorTarget::Position(target) => (target - angle) / duration,
+        };
+        row.lower = -max_impulse;
+        row.upper = max_impulse;
+    }
+}
+
+/// Single degree of freedom removed by a joint in 2D, prepared for the solver.
+pub(crate) struct JointRow2D<F: num_traits::Float> {
+    /// Index of the joint the row belongs to.
+    pub joint: usize,
+
+    /// Index of the row in the joint, used to keep its impulse across frames.
+    pub slot: usize,
+
+    /// Indices of the bodies joined together.
+    pub bodies: (usize, Option<usize>),
+
+    /// Direction of the impulse applied to the first body, and opposed to the second one.
+    pub linear: Vector2<F>,
+
+    /// Torque impulse applied to the first body per unit of impulse.
+    pub angular_one: F,
+
+    /// Torque impulse applied to the second body per unit of impulse, opposed.
+    pub angular_two: F,
+
+    /// Relative velocity the row drives the bodies towards.
+    pub bias: F,
+
+    /// Softness of the row, letting it give in proportion to the accumulated impulse.
+    pub softness: F,
+
+    /// Smallest impulse the row can accumulate.
+    pub lower: F,
+
+    /// Largest impulse the row can accumulate.
+    pub upper: F,
+
+    /// Mass the row opposes to impulses.
+    pub mass: F,
+
+    /// Impulse accumulated by the row.
+    pub impulse: F,
+}
+
+impl<F: num_traits::Float> JointRow2D<F> {
+    /// Makes the row behave as the given damped spring, solved implicitly, with the given
+    /// position error.
+    fn soften(&mut self, spring: &JointSpring<F>, error: F, duration: F) {
+        let coefficient = spring.damping + duration * spring.stiffness;
+        if coefficient > F::zero() {
+            self.bias = -spring.stiffness * error / coefficient;
+            self.softness = F::one() / (duration * coefficient);
+        } else {
+            // A spring without stiffness nor damping doesn't constrain anything.
+            self.bias = num_traits::zero();
+            self.lower = num_traits::zero();
+            self.upper = num_traits::zero();
+        }
+    }
+
+    /// Computes the mass of the row, and applies the impulse it starts with.
+    pub fn prepare(&mut self, bodies: &mut [RigidBody2D<F>]) {
+        let mut inverse = self.softness;
+        let one = &bodies[self.bodies.0];
+        if one.has_finite_mass() {
+            inverse = inverse
+                + one.inverse_mass * self.linear.squared_magnitude()
+                + one.inverse_inertia * self.angular_one * self.angular_one;
+        }
+        if let Some(two) = self.bodies.1.map(|body| &bodies[body]) {
+            if two.has_finite_mass() {
+                inverse = inverse
+                    + two.inverse_mass * self.linear.squared_magnitude()
+                    + two.inverse_inertia * self.angular_two * self.angular_two;
+            }
+        }
+        self.mass = if inverse > F::zero() {
+            F::one() / inverse
+        } else {
+            num_traits::zero()
+        };
+
+        self.impulse = self.impulse.max(self.lower).min(self.upper);
+        self.apply(self.impulse, bodies);
+    }
+
+    /// Returns the relative velocity of the bodies along the row.
+    fn velocity(&self, bodies: &[RigidBody2D<F>]) -> F {
+        let one = &bodies[self.bodies.0];
+        let mut velocity = self.linear.dot_product(&one.velocity) + self.angular_one * one.rotation;
+        if let Some(body) = self.bodies.1 {
+            let two = &bodies[body];
+            velocity =
+                velocity - self.linear.dot_product(&two.velocity) - self.angular_two * two.rotation;
+        }
+        velocity
+    }
+
+    /// Applies the given impulse along the row.
+    fn apply(&self, impulse: F, bodies: &mut [RigidBody2D<F>]) {
+        let one = &mut bodies[self.bodies.0];
+        if one.has_finite_mass() {
+            one.velocity
+                .inplace_vector_add(&self.linear.scalar_mul(impulse * one.inverse_mass));
+            one.rotation = one.rotation + self.angular_one * impulse * one.inverse_inertia;
+        }
+        if let Some(body) = self.bodies.1 {
+            let two = &mut bodies[body];
+            if two.has_finite_mass() {
+                two.velocity
+                    .inplace_vector_sub(&self.linear.scalar_mul(impulse * two.inverse_mass));
+                two.rotation = two.rotation - self.angular_two * impulse * two.inverse_inertia;
+            }
+        }
+    }
+}
+
+impl<F: num_traits::Float> ImpulseConstraint<RigidBody2D<F>> for JointRow2D<F> {
+    /// Runs one iteration of the solver over the row.
+    fn solve(&mut self, bodies: &mut [RigidBody2D<F>]) {
+        let velocity = self.velocity(bodies);
+        let previous = self.impulse;
+        self.impulse = row_impulse(
+            previous,
+            self.bias - velocity,
+            self.softness,
+            self.mass,
+            (self.lower, self.upper),
+        );
+        self.apply(self.impulse - previous, bodies);
+    }
+}
@@ -0,0 +1,364 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::phust2d::collider::Collider2D;
+use crate::phust2d::contact::Contact2D;
+use crate::phust2d::shape::{Plane2D, Shape2D};
+use crate::phust2d::transform::Transform2D;
+use math::Vector2;
+
+/// Separation by which the second polygon of a pair must beat the first one to provide the
+/// reference edge, so the edge chosen doesn't flip back and forth between frames.
+const REFERENCE_TOLERANCE: f64 = 1e-3;
+
+/// Feature identifier bit set on the contacts of polygon pairs whose reference edge
+/// belongs to the second polygon.
+const FLIPPED_FEATURE: u32 = 1 << 31;
+
+/// Feature identifier bit set on the contacts made by clipping the incident edge against
+/// the sides of the reference edge, instead of one of its vertices.
+const CLIPPED_FEATURE: u32 = 1 << 15;
+
+/// Shape placed in world space, ready to be collided.
+enum Placed<F: num_traits::Float> {
+    /// Circle with its center and radius.
+    Circle(Vector2<F>, F),
+
+    /// Convex polygon with its vertices and outward edge normals, counter-clockwise.
+    Polygon(Vec<Vector2<F>>, Vec<Vector2<F>>),
+}
+
+impl<F: num_traits::Float> Placed<F> {
+    /// Places the given shape with the given transform.
+    fn new(shape: &Shape2D<F>, transform: &Transform2D<F>) -> Self {
+        let polygon = |vertices: &[Vector2<F>], normals: &[Vector2<F>]| {
+            Placed::Polygon(
+                vertices
+                    .iter()
+                    .map(|vertex| transform.transform(vertex))
+                    .collect(),
+                normals
+                    .iter()
+                    .map(|normal| transform.transform_direction(normal))
+                    .collect(),
+            )
+        };
+        match shape {
+            Shape2D::Circle(circle) => Placed::Circle(transform.position, circle.radius),
+            Shape2D::Rectangle(rectangle) => {
+                let rectangle = rectangle.to_polygon();
+                polygon(rectangle.vertices(), rectangle.normals())
+            }
+            Shape2D::Polygon(shape) => polygon(shape.vertices(), shape.normals()),
+        }
+    }
+}
+
+/// Point of contact found between two placed shapes, before it's attached to any body.
+struct Touch<F: num_traits::Float> {
+    point: Vector2<F>,
+    normal: Vector2<F>,
+    penetration: F,
+    feature: u32,
+}
+
+/// Generates the contacts between two colliders, given with their indices, and appends
+/// them to `contacts`. Colliders must have their world transforms calculated.
+///
+/// # Remarks
+/// Circles touch at a single point, while polygons, and boxes, touch at up to two points,
+/// found by clipping the edge of one polygon most facing the other one against the sides
+/// of the edge of the other polygon separating them the most.
+pub fn collide<F: num_traits::Float>(
+    indices: (usize, usize),
+    one: &Collider2D<F>,
+    two: &Collider2D<F>,
+    contacts: &mut Vec<Contact2D<F>>,
+) {
+    let placed_one = Placed::new(&one.shape, one.transform());
+    let placed_two = Placed::new(&two.shape, two.transform());
+    let touches = match (&placed_one, &placed_two) {
+        (Placed::Circle(center_one, radius_one), Placed::Circle(center_two, radius_two)) => {
+            circles(center_one, *radius_one, center_two, *radius_two)
+        }
+        (Placed::Polygon(vertices, normals), Placed::Circle(center, radius)) => {
+            polygon_circle(vertices, normals, center, *radius)
+                .into_iter()
+                .map(|touch| Touch {
+                    normal: touch.normal.invert(),
+                    ..touch
+                })
+                .collect()
+        }
+        (Placed::Circle(center, radius), Placed::Polygon(vertices, normals)) => {
+            polygon_circle(vertices, normals, center, *radius)
+        }
+        (
+            Placed::Polygon(vertices_one, normals_one),
+            Placed::Polygon(vertices_two, normals_two),
+        ) => polygons((vertices_one, normals_one), (vertices_two, normals_two)),
+    };
+    for touch in touches {
+        contacts.push(Contact2D {
+            colliders: indices,
+            feature: touch.feature,
+            ..Contact2D::new(
+                (one.body, Some(two.body)),
+                touch.point,
+                touch.normal,
+                touch.penetration,
+            )
+        });
+    }
+}
+
+/// Generates the contacts between a collider and a scenery plane, given with their indices,
+/// and appends them to `contacts`. The collider must have its world transform calculated.
+pub fn collide_with_plane<F: num_traits::Float>(
+    indices: (usize, usize),
+    collider: &Collider2D<F>,
+    plane: &Plane2D<F>,
+    contacts: &mut Vec<Contact2D<F>>,
+) {
+    let mut push = |point: Vector2<F>, penetration: F, feature: u32| {
+        contacts.push(Contact2D {
+            colliders: indices,
+            feature,
+            ..Contact2D::new((collider.body, None), point, plane.normal, penetration)
+        });
+    };
+    match Placed::new(&collider.shape, collider.transform()) {
+        Placed::Circle(center, radius) => {
+            let penetration = radius - plane.signed_distance(&center);
+            if penetration >= F::zero() {
+                let depth = radius - penetration * math::real(0.5);
+                push(
+                    center.vector_sub(&plane.normal.scalar_mul(depth)),
+                    penetration,
+                    0,
+                );
+            }
+        }
+        Placed::Polygon(vertices, _) => {
+            for (index, vertex) in vertices.iter().enumerate() {
+                let penetration = -plane.signed_distance(vertex);
+                if penetration >= F::zero() {
+                    let point =
+                        vertex.vector_add(&plane.normal.scalar_mul(penetration * math::real(0.5)));
+                    push(point, penetration, index as u32);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the contact between two circles, with the normal pointing towards the first one.
+fn circles<F: num_traits::Float>(
+    center_one: &Vector2<F>,
+    radius_one: F,
+    center_two: &Vector2<F>,
+    radius_two: F,
+) -> Vec<Touch<F>> {
+    let offset = center_one.vector_sub(center_two);
+    let distance = offset.magnitude();
+    let penetration = radius_one + radius_two - distance;
+    if penetration < F::zero() {
+        return Vec::new();
+    }
+
+    // Concentric circles are pushed apart along an arbitrary direction.
+    let normal = if distance > F::epsilon() {
+        offset.scalar_div(distance)
+    } else {
+        Vector2::new(F::zero(), F::one())
+    };
+    let depth = radius_two - penetration * math::real(0.5);
+    vec![Touch {
+        point: center_two.vector_add(&normal.scalar_mul(depth)),
+        normal,
+        penetration,
+        feature: 0,
+    }]
+}
+
+/// Returns the contact between a polygon and a circle, with the normal pointing
+/// from the polygon towards the circle.
+fn polygon_circle<F: num_traits::Float>(
+    vertices: &[Vector2<F>],
+    normals: &[Vector2<F>],
+    center: &Vector2<F>,
+    radius: F,
+) -> Vec<Touch<F>> {
+    // Find the edge the center is the furthest out of.
+    let mut separation = F::neg_infinity();
+    let mut face = 0;
+    for (index, (vertex, normal)) in vertices.iter().zip(normals.iter()).enumerate() {
+        let distance = normal.dot_product(&center.vector_sub(vertex));
+        if distance > radius {
+            return Vec::new();
+        }
+        if distance > separation {
+            separation = distance;
+            face = index;
+        }
+    }
+
+    // Centers beyond the ends of the edge touch the vertex there instead.
+    let next = (face + 1) % vertices.len();
+    let (start, end) = (&vertices[face], &vertices[next]);
+    let corner = if separation <= F::zero() {
+        None
+    } else if center.vector_sub(start).dot_product(&end.vector_sub(start)) <= F::zero() {
+        Some((start, face))
+    } else if center.vector_sub(end).dot_product(&start.vector_sub(end)) <= F::zero() {
+        Some((end, next))
+    } else {
+        None
+    };
+    let (normal, penetration, feature) = match corner {
+        Some((vertex, index)) => {
+            let offset = center.vector_sub(vertex);
+            let distance = offset.magnitude();
+            if distance > radius {
+                return Vec::new();
+            }
+            (
+                offset.normalize(),
+                radius - distance,
+                CLIPPED_FEATURE | index as u32,
+            )
+        }
+        None => (normals[face], radius - separation, face as u32),
+    };
+    let depth = radius - penetration * math::real(0.5);
+    vec![Touch {
+        point: center.vector_sub(&normal.scalar_mul(depth)),
+        normal,
+        penetration,
+        feature,
+    }]
+}
+
+/// Returns the contacts between two polygons, with the normal pointing towards the first one.
+fn polygons<F: num_traits::Float>(
+    one: (&[Vector2<F>], &[Vector2<F>]),
+    two: (&[Vector2<F>], &[Vector2<F>]),
+) -> Vec<Touch<F>> {
+    let (separation_one, edge_one) = max_separation(one, two);
+    if separation_one > F::zero() {
+        return Vec::new();
+    }
+    let (separation_two, edge_two) = max_separation(two, one);
+    if separation_two > F::zero() {
+        return Vec::new();
+    }
+
+    let flipped = separation_two > separation_one + math::real(REFERENCE_TOLERANCE);
+    let (reference, incident, edge) = if flipped {
+        (two, one, edge_two)
+    } else {
+        (one, two, edge_one)
+    };
+    let reference_normal = reference.1[edge];
+
+    // The incident edge is the one facing the reference edge the most.
+    let incident_edge = (0..incident.1.len())
+        .min_by(|first, second| {
+            let first = incident.1[*first].dot_product(&reference_normal);
+            let second = incident.1[*second].dot_product(&reference_normal);
+            first
+                .partial_cmp(&second)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap_or(0);
+    let incident_next = (incident_edge + 1) % incident.0.len();
+    let mut points = vec![
+        (incident.0[incident_edge], incident_edge as u32),
+        (incident.0[incident_next], incident_next as u32),
+    ];
+
+    // Keep the part of the incident edge between the sides of the reference edge.
+    let start = reference.0[edge];
+    let end = reference.0[(edge + 1) % reference.0.len()];
+    let tangent = end.vector_sub(&start).normalize();
+    points = clip(&points, &tangent.invert(), -tangent.dot_product(&start), 0);
+    points = clip(&points, &tangent, tangent.dot_product(&end), 1);
+
+    let normal = if flipped {
+        reference_normal
+    } else {
+        reference_normal.invert()
+    };
+    let flag = if flipped { FLIPPED_FEATURE } else { 0 };
+    points
+        .into_iter()
+        .filter_map(|(point, id)| {
+            let penetration = -reference_normal.dot_product(&point.vector_sub(&start));
+            if penetration < F::zero() {
+                return None;
+            }
+            Some(Touch {
+                point: point
+                    .vector_add(&reference_normal.scalar_mul(penetration * math::real(0.5))),
+                normal,
+                penetration,
+                feature: flag | (edge as u32) << 16 | id,
+            })
+        })
+        .collect()
+}
+
+/// Returns the largest separation between the edges of the first polygon and the
+/// vertices of the second one, along with the edge separating them the most.
+fn max_separation<F: num_traits::Float>(
+    one: (&[Vector2<F>], &[Vector2<F>]),
+    two: (&[Vector2<F>], &[Vector2<F>]),
+) -> (F, usize) {
+    let mut best = (F::neg_infinity(), 0);
+    for (index, (vertex, normal)) in one.0.iter().zip(one.1.iter()).enumerate() {
+        let separation = two
+            .0
+            .iter()
+            .map(|other| normal.dot_product(&other.vector_sub(vertex)))
+            .fold(F::infinity(), F::min);
+        if separation > best.0 {
+            best = (separation, index);
+        }
+    }
+    best
+}
+
+/// Keeps the part of a segment behind a line, given by its normal and offset, replacing
+/// the end in front of it with the point where the segment crosses the line.
+fn clip<F: num_traits::Float>(
+    points: &[(Vector2<F>, u32)],
+    normal: &Vector2<F>,
+    offset: F,
+    side: u32,
+) -> Vec<(Vector2<F>, u32)> {
+    if points.len() < 2 {
+        return points.to_vec();
+    }
+
+    let (first, second) = (points[0], points[1]);
+    let distance_first = normal.dot_product(&first.0) - offset;
+    let distance_second = normal.dot_product(&second.0) - offset;
+    let mut clipped = Vec::with_capacity(2);
+    if distance_first <= F::zero() {
+        clipped.push(first);
+    }
+    if distance_second <= F::zero() {
+        clipped.push(second);
+    }
+    if distance_first * distance_second < F::zero() {
+        let fraction = distance_first / (distance_first - distance_second);
+        let point = first
+            .0
+            .vector_add(&second.0.vector_sub(&first.0).scalar_mul(fraction));
+        clipped.push((point, CLIPPED_FEATURE | side));
+    }
+    clipped
+}
@@ -0,0 +1,123 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::phust2d::collider::Collider2D;
+use crate::phust2d::narrow_phase::*;
+use crate::phust2d::rigid_body::RigidBody2D;
+use crate::phust2d::shape::{Plane2D, Shape2D};
+use math::Vector2;
+
+fn placed(body: usize, shape: Shape2D, bodies: &[RigidBody2D]) -> Collider2D {
+    let mut collider = Collider2D::new(body, shape);
+    collider.calculate_internals(bodies);
+    collider
+}
+
+#[test]
+fn circles() {
+    let bodies = [
+        RigidBody2D::new(Vector2::new(0.0, 1.5), 1.0, 1.0),
+        RigidBody2D::new(Vector2::new(0.0, 0.0), 1.0, 1.0),
+    ];
+    let one = placed(0, Shape2D::circle(1.0), &bodies);
+    let two = placed(1, Shape2D::circle(1.0), &bodies);
+
+    let mut contacts = Vec::new();
+    collide((0, 1), &one, &two, &mut contacts);
+    assert_eq!(1, contacts.len());
+    let contact = &contacts[0];
+    assert_eq!((0, Some(1)), contact.bodies);
+    assert_eq!((0, 1), contact.colliders);
+    assert!((contact.contact_normal - Vector2::new(0.0, 1.0)).magnitude() < 1e-12);
+    assert!((contact.penetration - 0.5).abs() < 1e-12);
+    assert!((contact.contact_point - Vector2::new(0.0, 0.75)).magnitude() < 1e-12);
+
+    // Separated circles don't touch.
+    let far = [RigidBody2D::new(Vector2::new(3.0, 0.0), 1.0, 1.0)];
+    contacts.clear();
+    collide(
+        (0, 1),
+        &placed(0, Shape2D::circle(1.0), &far),
+        &two,
+        &mut contacts,
+    );
+    assert!(contacts.is_empty());
+}
+
+#[test]
+fn circle_and_box() {
+    let bodies = [
+        RigidBody2D::new(Vector2::new(0.0, 0.0), 1.0, 1.0),
+        RigidBody2D::new(Vector2::new(0.5, 1.4), 1.0, 1.0),
+    ];
+    let ground = placed(0, Shape2D::rectangle(Vector2::new(2.0, 1.0)), &bodies);
+    let ball = placed(1, Shape2D::circle(0.5), &bodies);
+
+    // The normal always points towards the first collider.
+    let mut contacts = Vec::new();
+    collide((1, 0), &ball, &ground, &mut contacts);
+    collide((0, 1), &ground, &ball, &mut contacts);
+    assert_eq!(2, contacts.len());
+    assert!((contacts[0].contact_normal - Vector2::new(0.0, 1.0)).magnitude() < 1e-12);
+    assert!((contacts[1].contact_normal - Vector2::new(0.0, -1.0)).magnitude() < 1e-12);
+    for contact in contacts.iter() {
+        assert!((contact.penetration - 0.1).abs() < 1e-12);
+        assert!((contact.contact_point - Vector2::new(0.5, 0.95)).magnitude() < 1e-12);
+    }
+}
+
+#[test]
+fn boxes() {
+    let bodies = [
+        RigidBody2D::new(Vector2::new(0.25, 1.9), 1.0, 1.0),
+        RigidBody2D::new(Vector2::new(0.0, 0.0), 1.0, 1.0),
+    ];
+    let top = placed(0, Shape2D::rectangle(Vector2::new(1.0, 1.0)), &bodies);
+    let bottom = placed(1, Shape2D::rectangle(Vector2::new(2.0, 1.0)), &bodies);
+
+    // Resting boxes touch at both corners of the narrowest one.
+    let mut contacts = Vec::new();
+    collide((0, 1), &top, &bottom, &mut contacts);
+    assert_eq!(2, contacts.len());
+    let mut xs: Vec<f64> = contacts
+        .iter()
+        .map(|contact| contact.contact_point.x)
+        .collect();
+    xs.sort_by(|one, two| one.partial_cmp(two).unwrap());
+    assert!((xs[0] + 0.75).abs() < 1e-12);
+    assert!((xs[1] - 1.25).abs() < 1e-12);
+    for contact in contacts.iter() {
+        assert!((contact.contact_normal - Vector2::new(0.0, 1.0)).magnitude() < 1e-12);
+        assert!((contact.penetration - 0.1).abs() < 1e-12);
+        assert!((contact.contact_point.y - 0.95).abs() < 1e-12);
+    }
+    assert_ne!(contacts[0].feature, contacts[1].feature);
+}
+
+#[test]
+fn planes() {
+    let mut body = RigidBody2D::new(Vector2::new(0.0, 0.9), 1.0, 1.0);
+    body.angle = std::f64::consts::FRAC_PI_4;
+    let bodies = [body];
+    let ground = Plane2D::new(Vector2::new(0.0, 1.0), 0.0);
+
+    // A box standing on a corner touches with that corner only.
+    let diamond = placed(0, Shape2D::rectangle(Vector2::new(1.0, 1.0)), &bodies);
+    let mut contacts = Vec::new();
+    collide_with_plane((0, 0), &diamond, &ground, &mut contacts);
+    assert_eq!(1, contacts.len());
+    let contact = &contacts[0];
+    assert_eq!((0, None), contact.bodies);
+    assert_eq!(Vector2::new(0.0, 1.0), contact.contact_normal);
+    assert!((contact.penetration - (2.0f64.sqrt() - 0.9)).abs() < 1e-12);
+
+    let ball = placed(0, Shape2D::circle(1.0), &bodies);
+    contacts.clear();
+    collide_with_plane((0, 0), &ball, &ground, &mut contacts);
+    assert_eq!(1, contacts.len());
+    assert!((contacts[0].penetration - 0.1).abs() < 1e-12);
+    assert!((contacts[0].contact_point - Vector2::new(0.0, -0.05)).magnitude() < 1e-12);
+}
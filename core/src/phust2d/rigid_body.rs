@@ -0,0 +1,234 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::phust2d::transform::Transform2D;
+use crate::rigid_body::BodyType;
+use math::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// Rigid body moving in the plane, turning around the axis perpendicular to it.
+///
+/// # Remarks
+/// Like the rigid bodies in 3D, the body holds the inverse of its mass and of its moment
+/// of inertia, so bodies with infinite mass have zero in both. Its origin is its center
+/// of mass. Angles and angular velocities are counter-clockwise.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RigidBody2D<F: num_traits::Float = f64> {
+    /// Holds the inverse of the mass of the rigid body.
+    pub inverse_mass: F,
+
+    /// Holds the inverse of the moment of inertia of the rigid body around its center of mass.
+    pub inverse_inertia: F,
+
+    /// Holds the linear position of the rigid body in world space.
+    pub position: Vector2<F>,
+
+    /// Holds the angle of the rigid body in world space, in radians.
+    pub angle: F,
+
+    /// Holds the linear velocity of the rigid body in world space.
+    pub velocity: Vector2<F>,
+
+    /// Holds the angular velocity of the rigid body, in radians per second.
+    pub rotation: F,
+
+    /// Holds the accumulated force to be applied at the next integration step.
+    pub force_accum: Vector2<F>,
+
+    /// Holds the accumulated torque to be applied at the next integration step.
+    pub torque_accum: F,
+
+    /// Fraction of the linear velocity kept every second, instead of the one of the world.
+    pub linear_damping: Option<F>,
+
+    /// Fraction of the angular velocity kept every second, instead of the one of the world.
+    pub angular_damping: Option<F>,
+
+    /// How the body is moved by the world it's simulated in.
+    pub body_type: BodyType,
+
+    /// Multiplier of the gravity pulling the body.
+    pub gravity_scale: F,
+}
+
+impl<F: num_traits::Float> RigidBody2D<F> {
+    /// Creates a new dynamic rigid body at the given position, with the given mass
+    /// and moment of inertia.
+    pub fn new(position: Vector2<F>, mass: F, inertia: F) -> Self {
+        let mut body = Self {
+            inverse_mass: num_traits::one(),
+            inverse_inertia: num_traits::one(),
+            position,
+            angle: num_traits::zero(),
+            velocity: Vector2::origin(),
+            rotation: num_traits::zero(),
+            force_accum: Vector2::origin(),
+            torque_accum: num_traits::zero(),
+            linear_damping: None,
+            angular_damping: None,
+            body_type: BodyType::Dynamic,
+            gravity_scale: num_traits::one(),
+        };
+        body.set_mass(mass);
+        body.set_inertia(inertia);
+        body
+    }
+
+    /// Returns the mass of the rigid body.
+    /// Bodies with infinite mass return the largest representable value.
+    pub fn mass(&self) -> F {
+        if self.inverse_mass == num_traits::zero() {
+            F::max_value()
+        } else {
+            F::one() / self.inverse_mass
+        }
+    }
+
+    /// Sets the mass of the rigid body. A zero mass is treated as an infinite one.
+    pub fn set_mass(&mut self, mass: F) -> &mut Self {
+        self.inverse_mass = if mass == num_traits::zero() {
+            num_traits::zero()
+        } else {
+            F::one() / mass
+        };
+        self
+    }
+
+    /// Sets the moment of inertia of the rigid body. A zero moment of inertia keeps the
+    /// body from turning.
+    pub fn set_inertia(&mut self, inertia: F) -> &mut Self {
+        self.inverse_inertia = if inertia == num_traits::zero() {
+            num_traits::zero()
+        } else {
+            F::one() / inertia
+        };
+        self
+    }
+
+    /// Marks the rigid body as immovable by setting its inverse mass
+    /// and inverse moment of inertia to `0`.
+    pub fn set_infinite_mass(&mut self) -> &mut Self {
+        self.inverse_mass = num_traits::zero();
+        self.inverse_inertia = num_traits::zero();
+        self
+    }
+
+    /// Returns true if the rigid body is dynamic and its mass is not infinite, so forces,
+    /// impulses, contacts and joints can move it.
+    pub fn has_finite_mass(&self) -> bool {
+        self.body_type == BodyType::Dynamic && self.inverse_mass > num_traits::zero()
+    }
+
+    /// Returns the transform placing the local space of the body in world space.
+    pub fn transform(&self) -> Transform2D<F> {
+        Transform2D::new(self.position, self.angle)
+    }
+
+    /// Converts the given point from the local space of the body into world space.
+    pub fn point_in_world_space(&self, point: &Vector2<F>) -> Vector2<F> {
+        self.transform().transform(point)
+    }
+
+    /// Converts the given point from world space into the local space of the body.
+    pub fn point_in_local_space(&self, point: &Vector2<F>) -> Vector2<F> {
+        self.transform().transform_inverse(point)
+    }
+
+    /// Returns the velocity of the given point of the body, in world space.
+    pub fn velocity_at(&self, point: &Vector2<F>) -> Vector2<F> {
+        let relative = point.vector_sub(&self.position);
+        self.velocity
+            .vector_add(&relative.scalar_cross(self.rotation))
+    }
+
+    /// Adds the given force to the center of mass of the rigid body.
+    pub fn add_force(&mut self, force: &Vector2<F>) -> &mut Self {
+        self.force_accum.inplace_vector_add(force);
+        self
+    }
+
+    /// Adds the given force to the given point of the rigid body, both in world space.
+    pub fn add_force_at_point(&mut self, force: &Vector2<F>, point: &Vector2<F>) -> &mut Self {
+        let relative = point.vector_sub(&self.position);
+        self.force_accum.inplace_vector_add(force);
+        self.torque_accum = self.torque_accum + relative.cross_product(force);
+        self
+    }
+
+    /// Adds the given counter-clockwise torque to the rigid body.
+    pub fn add_torque(&mut self, torque: F) -> &mut Self {
+        self.torque_accum = self.torque_accum + torque;
+        self
+    }
+
+    /// Changes the velocities of the body by an impulse applied at the given point,
+    /// relative to its center of mass. Bodies that can't move are left untouched.
+    pub fn apply_impulse(&mut self, impulse: &Vector2<F>, relative: &Vector2<F>) -> &mut Self {
+        if self.has_finite_mass() {
+            self.velocity
+                .inplace_vector_add(&impulse.scalar_mul(self.inverse_mass));
+            self.rotation = self.rotation + self.inverse_inertia * relative.cross_product(impulse);
+        }
+        self
+    }
+
+    /// Clears the forces and torques accumulated since the last integration step.
+    pub fn clear_accumulators(&mut self) -> &mut Self {
+        self.force_accum = Vector2::origin();
+        self.torque_accum = num_traits::zero();
+        self
+    }
+
+    /// Integrates the velocities of the rigid body forward in time by the given amount
+    /// (in seconds), from the given gravity, the accumulated forces and the given damping
+    /// coefficients unless the body has its own.
+    ///
+    /// # Remarks
+    /// Damping is applied as `damping^duration`, so the result is independent of the frame rate.
+    /// Rigid bodies with infinite mass are never integrated.
+    pub fn integrate_velocity(
+        &mut self,
+        duration: F,
+        gravity: &Vector2<F>,
+        default_linear_damping: F,
+        default_angular_damping: F,
+    ) -> &mut Self {
+        if !self.has_finite_mass() {
+            return self;
+        }
+
+        let acceleration = gravity
+            .scalar_mul(self.gravity_scale)
+            .vector_add(&self.force_accum.scalar_mul(self.inverse_mass));
+        self.velocity
+            .inplace_vector_add(&acceleration.scalar_mul(duration));
+        self.rotation = self.rotation + self.torque_accum * self.inverse_inertia * duration;
+
+        let linear_damping = self.linear_damping.unwrap_or(default_linear_damping);
+        let angular_damping = self.angular_damping.unwrap_or(default_angular_damping);
+        self.velocity = self.velocity.scalar_mul(linear_damping.powf(duration));
+        self.rotation = self.rotation * angular_damping.powf(duration);
+        self
+    }
+
+    /// Moves the rigid body with its velocities for the given duration (in seconds).
+    ///
+    /// # Remarks
+    /// Static bodies, and dynamic bodies with infinite mass, never move.
+    pub fn integrate_position(&mut self, duration: F) -> &mut Self {
+        let moves = match self.body_type {
+            BodyType::Dynamic => self.has_finite_mass(),
+            BodyType::Kinematic => true,
+            BodyType::Static => false,
+        };
+        if moves {
+            self.position
+                .inplace_vector_add(&self.velocity.scalar_mul(duration));
+            self.angle = self.angle + self.rotation * duration;
+        }
+        self
+    }
+}
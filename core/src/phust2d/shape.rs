@@ -0,0 +1,294 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::phust2d::transform::Transform2D;
+use math::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// Circle centered on the origin of its local space.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Circle<F: num_traits::Float = f64> {
+    /// Radius of the circle.
+    pub radius: F,
+}
+
+impl<F: num_traits::Float> Circle<F> {
+    /// Creates a new circle with the given radius.
+    pub fn new(radius: F) -> Self {
+        Self { radius }
+    }
+}
+
+/// Box centered on the origin of its local space, aligned with its axes.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Rectangle<F: num_traits::Float = f64> {
+    /// Half of the size of the box along each axis.
+    pub half_size: Vector2<F>,
+}
+
+impl<F: num_traits::Float> Rectangle<F> {
+    /// Creates a new box with the given half size.
+    pub fn new(half_size: Vector2<F>) -> Self {
+        Self { half_size }
+    }
+
+    /// Returns the box as a polygon, with its corners counter-clockwise.
+    pub fn to_polygon(&self) -> Polygon<F> {
+        let Vector2 { x, y } = self.half_size;
+        Polygon::from_hull(vec![
+            Vector2::new(-x, -y),
+            Vector2::new(x, -y),
+            Vector2::new(x, y),
+            Vector2::new(-x, y),
+        ])
+    }
+}
+
+/// Convex polygon in its local space.
+///
+/// # Remarks
+/// The vertices are kept counter-clockwise, along with the outward unit normal of the edge
+/// starting at each of them.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Polygon<F: num_traits::Float = f64> {
+    vertices: Vec<Vector2<F>>,
+    normals: Vec<Vector2<F>>,
+}
+
+impl<F: num_traits::Float> Polygon<F> {
+    /// Creates a new polygon from the convex hull of the given points.
+    /// Returns `None` when the points don't enclose any area.
+    pub fn new(points: &[Vector2<F>]) -> Option<Self> {
+        let hull = convex_hull(points);
+        if hull.len() < 3 {
+            return None;
+        }
+        Some(Self::from_hull(hull))
+    }
+
+    /// Creates a polygon from vertices already forming a counter-clockwise convex hull.
+    fn from_hull(vertices: Vec<Vector2<F>>) -> Self {
+        let count = vertices.len();
+        let normals = (0..count)
+            .map(|index| {
+                let edge = vertices[(index + 1) % count].vector_sub(&vertices[index]);
+                Vector2::new(edge.y, -edge.x).normalize()
+            })
+            .collect();
+        Self { vertices, normals }
+    }
+
+    /// Returns the vertices of the polygon, counter-clockwise.
+    pub fn vertices(&self) -> &[Vector2<F>] {
+        &self.vertices
+    }
+
+    /// Returns the outward unit normals of the edges of the polygon,
+    /// the one of the edge starting at every vertex.
+    pub fn normals(&self) -> &[Vector2<F>] {
+        &self.normals
+    }
+}
+
+/// Shape of a collider in 2D.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Shape2D<F: num_traits::Float = f64> {
+    /// Circle around the origin.
+    Circle(Circle<F>),
+
+    /// Box around the origin.
+    Rectangle(Rectangle<F>),
+
+    /// Convex polygon.
+    Polygon(Polygon<F>),
+}
+
+impl<F: num_traits::Float> Shape2D<F> {
+    /// Creates a new circle with the given radius.
+    pub fn circle(radius: F) -> Self {
+        Shape2D::Circle(Circle::new(radius))
+    }
+
+    /// Creates a new box with the given half size.
+    pub fn rectangle(half_size: Vector2<F>) -> Self {
+        Shape2D::Rectangle(Rectangle::new(half_size))
+    }
+
+    /// Creates a new polygon from the convex hull of the given points.
+    /// Returns `None` when the points don't enclose any area.
+    pub fn polygon(points: &[Vector2<F>]) -> Option<Self> {
+        Polygon::new(points).map(Shape2D::Polygon)
+    }
+
+    /// Returns the mass of the shape made of the given density, per unit of area, its center
+    /// of mass in its local space, and its moment of inertia around its center of mass.
+    pub fn mass_properties(&self, density: F) -> (F, Vector2<F>, F) {
+        match self {
+            Shape2D::Circle(circle) => {
+                let radius = circle.radius;
+                let mass = density * math::real(std::f64::consts::PI) * radius * radius;
+                (
+                    mass,
+                    Vector2::origin(),
+                    mass * radius * radius * math::real(0.5),
+                )
+            }
+            Shape2D::Rectangle(rectangle) => {
+                let Vector2 { x, y } = rectangle.half_size;
+                let mass = density * math::real(4.0) * x * y;
+                (
+                    mass,
+                    Vector2::origin(),
+                    mass * (x * x + y * y) / math::real(3.0),
+                )
+            }
+            Shape2D::Polygon(polygon) => {
+                // Sum the triangles fanning out of the origin.
+                let count = polygon.vertices.len();
+                let mut area = F::zero();
+                let mut center = Vector2::origin();
+                let mut inertia = F::zero();
+                for index in 0..count {
+                    let one = &polygon.vertices[index];
+                    let two = &polygon.vertices[(index + 1) % count];
+                    let cross = one.cross_product(two);
+                    area = area + cross * math::real(0.5);
+                    center.inplace_vector_add(
+                        &one.vector_add(two).scalar_mul(cross / math::real(6.0)),
+                    );
+                    inertia = inertia
+                        + cross
+                            * (one.dot_product(one) + one.dot_product(two) + two.dot_product(two))
+                            / math::real(12.0);
+                }
+                let mass = density * area;
+                let center = center.scalar_div(area);
+                (
+                    mass,
+                    center,
+                    density * inertia - mass * center.squared_magnitude(),
+                )
+            }
+        }
+    }
+
+    /// Returns the bounding box of the shape placed with the given transform.
+    pub fn aabb(&self, transform: &Transform2D<F>) -> Aabb2D<F> {
+        let points = |vertices: &[Vector2<F>]| {
+            let first = transform.transform(&vertices[0]);
+            vertices
+                .iter()
+                .skip(1)
+                .fold(Aabb2D::new(first, first), |aabb, vertex| {
+                    let point = transform.transform(vertex);
+                    Aabb2D::new(
+                        Vector2::new(aabb.min.x.min(point.x), aabb.min.y.min(point.y)),
+                        Vector2::new(aabb.max.x.max(point.x), aabb.max.y.max(point.y)),
+                    )
+                })
+        };
+        match self {
+            Shape2D::Circle(circle) => {
+                let extent = Vector2::new(circle.radius, circle.radius);
+                Aabb2D::new(
+                    transform.position.vector_sub(&extent),
+                    transform.position.vector_add(&extent),
+                )
+            }
+            Shape2D::Rectangle(rectangle) => points(rectangle.to_polygon().vertices()),
+            Shape2D::Polygon(polygon) => points(polygon.vertices()),
+        }
+    }
+}
+
+/// Axis-aligned bounding box in 2D, represented by its minimum and maximum corners.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Aabb2D<F: num_traits::Float = f64> {
+    /// Corner of the box with the smallest coordinates.
+    pub min: Vector2<F>,
+
+    /// Corner of the box with the largest coordinates.
+    pub max: Vector2<F>,
+}
+
+impl<F: num_traits::Float> Aabb2D<F> {
+    /// Creates a new bounding box with the given corners.
+    pub fn new(min: Vector2<F>, max: Vector2<F>) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns true if both bounding boxes overlap.
+    pub fn intersects(&self, other: &Aabb2D<F>) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+}
+
+/// Half-plane of the scenery in 2D, bounded by a line, which colliders can't go past.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Plane2D<F: num_traits::Float = f64> {
+    /// Unit normal of the line, pointing out of the solid side.
+    pub normal: Vector2<F>,
+
+    /// Distance of the line from the origin along its normal.
+    pub offset: F,
+}
+
+impl<F: num_traits::Float> Plane2D<F> {
+    /// Creates a new half-plane with the given normal and offset.
+    pub fn new(normal: Vector2<F>, offset: F) -> Self {
+        Self { normal, offset }
+    }
+
+    /// Returns the distance from the line to the given point, negative on the solid side.
+    pub fn signed_distance(&self, point: &Vector2<F>) -> F {
+        self.normal.dot_product(point) - self.offset
+    }
+}
+
+/// Returns the convex hull of the given points, counter-clockwise, with the monotone chain
+/// algorithm. Points on the edges of the hull are left out.
+fn convex_hull<F: num_traits::Float>(points: &[Vector2<F>]) -> Vec<Vector2<F>> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|one, two| {
+        one.x
+            .partial_cmp(&two.x)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(
+                one.y
+                    .partial_cmp(&two.y)
+                    .unwrap_or(std::cmp::Ordering::Equal),
+            )
+    });
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let turns_left = |hull: &[Vector2<F>], point: &Vector2<F>| {
+        let count = hull.len();
+        let edge = hull[count - 1].vector_sub(&hull[count - 2]);
+        edge.cross_product(&point.vector_sub(&hull[count - 2])) > F::zero()
+    };
+    let mut hull: Vec<Vector2<F>> = Vec::with_capacity(sorted.len() * 2);
+    for point in sorted.iter() {
+        while hull.len() >= 2 && !turns_left(&hull, point) {
+            hull.pop();
+        }
+        hull.push(*point);
+    }
+    let lower = hull.len() + 1;
+    for point in sorted.iter().rev().skip(1) {
+        while hull.len() >= lower && !turns_left(&hull, point) {
+            hull.pop();
+        }
+        hull.push(*point);
+    }
+    hull.pop();
+    hull
+}
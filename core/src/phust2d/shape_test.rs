@@ -0,0 +1,97 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::phust2d::shape::*;
+use crate::phust2d::transform::Transform2D;
+use math::Vector2;
+
+#[test]
+fn convex_hull() {
+    let polygon = Polygon::new(&[
+        Vector2::new(1.0, 1.0),
+        Vector2::new(-1.0, -1.0),
+        Vector2::new(0.0, 0.0),
+        Vector2::new(1.0, -1.0),
+        Vector2::new(0.0, -1.0),
+        Vector2::new(-1.0, 1.0),
+    ])
+    .unwrap();
+
+    // Interior points, and points along the edges, are left out.
+    assert_eq!(
+        &[
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(1.0, -1.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(-1.0, 1.0),
+        ],
+        polygon.vertices()
+    );
+    assert_eq!(
+        &[
+            Vector2::new(0.0, -1.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 1.0),
+            Vector2::new(-1.0, 0.0),
+        ],
+        polygon.normals()
+    );
+    assert_eq!(Rectangle::new(Vector2::new(1.0, 1.0)).to_polygon(), polygon);
+
+    assert!(Polygon::<f64>::new(&[Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0)]).is_none());
+    assert!(Polygon::new(&[
+        Vector2::new(0.0, 0.0),
+        Vector2::new(1.0, 1.0),
+        Vector2::new(2.0, 2.0),
+    ])
+    .is_none());
+}
+
+#[test]
+fn mass_properties() {
+    let (mass, center, inertia) = Shape2D::circle(2.0).mass_properties(1.0);
+    assert!((mass - std::f64::consts::PI * 4.0).abs() < 1e-12);
+    assert_eq!(Vector2::origin(), center);
+    assert!((inertia - mass * 2.0).abs() < 1e-12);
+
+    let (mass, center, inertia) =
+        Shape2D::<f64>::rectangle(Vector2::new(2.0, 1.0)).mass_properties(0.5);
+    assert_eq!(4.0, mass);
+    assert_eq!(Vector2::origin(), center);
+    assert!((inertia - 4.0 * (16.0 + 4.0) / 12.0).abs() < 1e-12);
+
+    // The same box as a polygon away from the origin has the same inertia around its center.
+    let shifted = Shape2D::polygon(&[
+        Vector2::new(1.0, 2.0),
+        Vector2::new(5.0, 2.0),
+        Vector2::new(5.0, 4.0),
+        Vector2::new(1.0, 4.0),
+    ])
+    .unwrap();
+    let (polygon_mass, center, polygon_inertia) = shifted.mass_properties(0.5);
+    assert!((polygon_mass - mass).abs() < 1e-12);
+    assert!((center - Vector2::new(3.0, 3.0)).magnitude() < 1e-12);
+    assert!((polygon_inertia - inertia).abs() < 1e-12);
+}
+
+#[test]
+fn bounds() {
+    let quarter_turn = Transform2D::new(Vector2::new(1.0, 1.0), std::f64::consts::FRAC_PI_2);
+    let aabb = Shape2D::rectangle(Vector2::new(2.0, 1.0)).aabb(&quarter_turn);
+    assert!((aabb.min - Vector2::new(0.0, -1.0)).magnitude() < 1e-12);
+    assert!((aabb.max - Vector2::new(2.0, 3.0)).magnitude() < 1e-12);
+
+    let aabb = Shape2D::circle(0.5).aabb(&quarter_turn);
+    assert_eq!(
+        Aabb2D::new(Vector2::new(0.5, 0.5), Vector2::new(1.5, 1.5)),
+        aabb
+    );
+    assert!(aabb.intersects(&Aabb2D::new(Vector2::new(1.5, 1.5), Vector2::new(2.0, 2.0))));
+    assert!(!aabb.intersects(&Aabb2D::new(Vector2::new(1.6, 0.0), Vector2::new(2.0, 2.0))));
+
+    let ground = Plane2D::new(Vector2::new(0.0, 1.0), -1.0);
+    assert_eq!(3.0, ground.signed_distance(&Vector2::new(5.0, 2.0)));
+}
@@ -0,0 +1,226 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::phust2d::contact::{Contact2D, ManifoldCache2D, ManifoldPoint2D};
+use crate::phust2d::joint::Joint2D;
+use crate::phust2d::rigid_body::RigidBody2D;
+use crate::solver::{
+    friction_scale, iterate_impulses, separating_velocity, ContactSolver, ImpulseConstraint,
+};
+use math::Vector2;
+
+/// Solves the contacts of the given manifolds and the given joints together, with the
+/// iterations, warm starting and penetration correction shared with the 3D solver,
+/// changing the velocities of the bodies.
+///
+/// # Remarks
+/// The iterations, warm starting, Baumgarte fraction and allowed penetration of the given
+/// settings are honored. Penetration is always corrected with Baumgarte, and shock
+/// propagation is ignored. The impulses accumulated are stored back into the manifolds
+/// and the joints, to warm start the next frame.
+pub fn solve<F: num_traits::Float>(
+    settings: &ContactSolver,
+    manifolds: &mut ManifoldCache2D<F>,
+    joints: &mut [Joint2D<F>],
+    bodies: &mut [RigidBody2D<F>],
+    duration: F,
+) {
+    if duration <= F::zero() {
+        return;
+    }
+    let baumgarte = math::real::<F>(settings.baumgarte);
+    let bias = (baumgarte, math::real::<F>(settings.allowed_penetration));
+
+    let mut constraints: Vec<ContactConstraint2D<F>> = manifolds
+        .iter()
+        .flat_map(|manifold| manifold.points.iter())
+        .map(|point| ContactConstraint2D::new(point, bodies, bias, duration))
+        .collect();
+
+    let mut rows = Vec::new();
+    for (index, joint) in joints.iter().enumerate() {
+        if joint.enabled {
+            joint.rows(index, bodies, duration, baumgarte, &mut rows);
+        }
+    }
+    for row in rows.iter_mut() {
+        if settings.warm_starting {
+            row.impulse = joints[row.joint].impulse(row.slot);
+        }
+        row.prepare(bodies);
+    }
+
+    if !settings.warm_starting {
+        for constraint in constraints.iter_mut() {
+            constraint.normal_impulse = num_traits::zero();
+            constraint.tangent_impulse = num_traits::zero();
+        }
+    }
+    iterate_impulses(settings.iterations, &mut rows, &mut constraints, bodies);
+
+    let mut solved = constraints.iter();
+    for point in manifolds
+        .iter_mut()
+        .flat_map(|manifold| manifold.points.iter_mut())
+    {
+        let constraint = solved.next().expect("one constraint per point");
+        point.normal_impulse = constraint.normal_impulse;
+        point.tangent_impulse = constraint.tangent_impulse;
+    }
+    for joint in joints.iter_mut() {
+        joint.reset_impulses();
+    }
+    for row in rows.iter() {
+        joints[row.joint].store_row(row);
+    }
+}
+
+/// Contact point prepared for the solver in 2D, with a single friction direction.
+struct ContactConstraint2D<F: num_traits::Float> {
+    bodies: (usize, Option<usize>),
+    relative_one: Vector2<F>,
+    relative_two: Vector2<F>,
+    normal: Vector2<F>,
+    tangent: Vector2<F>,
+    normal_mass: F,
+    tangent_mass: F,
+    target_velocity: F,
+    dynamic_friction: F,
+    static_friction: F,
+    normal_impulse: F,
+    tangent_impulse: F,
+}
+
+impl<F: num_traits::Float> ContactConstraint2D<F> {
+    /// Prepares the given manifold point for the solver, pushing penetrated bodies apart
+    /// with the given Baumgarte fraction, past the allowed penetration.
+    fn new(
+        point: &ManifoldPoint2D<F>,
+        bodies: &[RigidBody2D<F>],
+        bias: (F, F),
+        duration: F,
+    ) -> Self {
+        let contact: &Contact2D<F> = &point.contact;
+        let relative_one = contact
+            .contact_point
+            .vector_sub(&bodies[contact.bodies.0].position);
+        let relative_two = match contact.bodies.1 {
+            Some(body) => contact.contact_point.vector_sub(&bodies[body].position),
+            None => Vector2::origin(),
+        };
+        let normal = contact.contact_normal;
+        let tangent = normal.perpendicular();
+
+        let mut constraint = Self {
+            bodies: contact.bodies,
+            relative_one,
+            relative_two,
+            normal,
+            tangent,
+            normal_mass: num_traits::zero(),
+            tangent_mass: num_traits::zero(),
+            target_velocity: num_traits::zero(),
+            dynamic_friction: contact.friction,
+            static_friction: contact.static_friction.max(contact.friction),
+            normal_impulse: point.normal_impulse,
+            tangent_impulse: point.tangent_impulse,
+        };
+        constraint.normal_mass = constraint.effective_mass(&normal, bodies);
+        constraint.tangent_mass = constraint.effective_mass(&tangent, bodies);
+
+        let closing_velocity = constraint.relative_velocity(bodies).dot_product(&normal);
+        constraint.target_velocity = separating_velocity(
+            closing_velocity,
+            contact.restitution,
+            contact.penetration,
+            Some(bias),
+            duration,
+        );
+        constraint
+    }
+
+    /// Returns the mass the contact opposes to an impulse along the given direction.
+    fn effective_mass(&self, direction: &Vector2<F>, bodies: &[RigidBody2D<F>]) -> F {
+        let along = |body: &RigidBody2D<F>, relative: &Vector2<F>| {
+            if body.has_finite_mass() {
+                let arm = relative.cross_product(direction);
+                body.inverse_mass + body.inverse_inertia * arm * arm
+            } else {
+                F::zero()
+            }
+        };
+        let mut inverse = along(&bodies[self.bodies.0], &self.relative_one);
+        if let Some(body) = self.bodies.1 {
+            inverse = inverse + along(&bodies[body], &self.relative_two);
+        }
+
+        if inverse > F::zero() {
+            F::one() / inverse
+        } else {
+            num_traits::zero()
+        }
+    }
+
+    /// Returns the velocity of the first body relative to the second one, at the contact point.
+    fn relative_velocity(&self, bodies: &[RigidBody2D<F>]) -> Vector2<F> {
+        let one = &bodies[self.bodies.0];
+        let mut velocity = one
+            .velocity
+            .vector_add(&self.relative_one.scalar_cross(one.rotation));
+        if let Some(body) = self.bodies.1 {
+            let two = &bodies[body];
+            velocity.inplace_vector_sub(
+                &two.velocity
+                    .vector_add(&self.relative_two.scalar_cross(two.rotation)),
+            );
+        }
+        velocity
+    }
+
+    /// Applies the given impulse to the first body at the contact point,
+    /// and its opposite to the second body.
+    fn apply_impulse(&self, impulse: &Vector2<F>, bodies: &mut [RigidBody2D<F>]) {
+        bodies[self.bodies.0].apply_impulse(impulse, &self.relative_one);
+        if let Some(body) = self.bodies.1 {
+            bodies[body].apply_impulse(&impulse.invert(), &self.relative_two);
+        }
+    }
+}
+
+impl<F: num_traits::Float> ImpulseConstraint<RigidBody2D<F>> for ContactConstraint2D<F> {
+    /// Applies the impulses the contact starts with.
+    fn warm_start(&self, bodies: &mut [RigidBody2D<F>]) {
+        let impulse = self
+            .normal
+            .scalar_mul(self.normal_impulse)
+            .vector_add(&self.tangent.scalar_mul(self.tangent_impulse));
+        self.apply_impulse(&impulse, bodies);
+    }
+
+    /// Runs one iteration of the solver over the contact.
+    fn solve(&mut self, bodies: &mut [RigidBody2D<F>]) {
+        // Friction is solved first, since the normal impulse is the more important one.
+        let sliding = self.relative_velocity(bodies).dot_product(&self.tangent);
+        let previous = self.tangent_impulse;
+        let tangent_impulse = previous - sliding * self.tangent_mass;
+        self.tangent_impulse = tangent_impulse
+            * friction_scale(
+                tangent_impulse.abs(),
+                self.normal_impulse,
+                self.static_friction,
+                self.dynamic_friction,
+            );
+        let impulse = self.tangent.scalar_mul(self.tangent_impulse - previous);
+        self.apply_impulse(&impulse, bodies);
+
+        let velocity = self.relative_velocity(bodies).dot_product(&self.normal);
+        let previous = self.normal_impulse;
+        self.normal_impulse =
+            (previous + (self.target_velocity - velocity) * self.normal_mass).max(F::zero());
+        let impulse = self.normal.scalar_mul(self.normal_impulse - previous);
+        self.apply_impulse(&impulse, bodies);
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use math::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// Placement in the plane, made of a rotation around the origin followed by a translation.
+///
+/// # Remarks
+/// The sine and cosine of the angle are kept alongside it, so transforming points
+/// doesn't evaluate them again.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Transform2D<F: num_traits::Float = f64> {
+    /// Translation of the transform.
+    pub position: Vector2<F>,
+
+    angle: F,
+    sine: F,
+    cosine: F,
+}
+
+impl<F: num_traits::Float> Default for Transform2D<F> {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl<F: num_traits::Float> Transform2D<F> {
+    /// Creates a transform that leaves everything in place.
+    pub fn identity() -> Self {
+        Self::new(Vector2::origin(), num_traits::zero())
+    }
+
+    /// Creates a transform rotating counter-clockwise by the given angle (in radians),
+    /// then moving to the given position.
+    pub fn new(position: Vector2<F>, angle: F) -> Self {
+        let (sine, cosine) = angle.sin_cos();
+        Self {
+            position,
+            angle,
+            sine,
+            cosine,
+        }
+    }
+
+    /// Returns the angle of the rotation of the transform, in radians.
+    pub fn angle(&self) -> F {
+        self.angle
+    }
+
+    /// Rotates the given direction.
+    pub fn transform_direction(&self, direction: &Vector2<F>) -> Vector2<F> {
+        Vector2::new(
+            self.cosine * direction.x - self.sine * direction.y,
+            self.sine * direction.x + self.cosine * direction.y,
+        )
+    }
+
+    /// Rotates the given direction back.
+    pub fn transform_inverse_direction(&self, direction: &Vector2<F>) -> Vector2<F> {
+        Vector2::new(
+            self.cosine * direction.x + self.sine * direction.y,
+            -self.sine * direction.x + self.cosine * direction.y,
+        )
+    }
+
+    /// Transforms the given point.
+    pub fn transform(&self, point: &Vector2<F>) -> Vector2<F> {
+        self.transform_direction(point).vector_add(&self.position)
+    }
+
+    /// Transforms the given point back.
+    pub fn transform_inverse(&self, point: &Vector2<F>) -> Vector2<F> {
+        self.transform_inverse_direction(&point.vector_sub(&self.position))
+    }
+
+    /// Returns the transform applying the given one first, then this one.
+    pub fn compose(&self, other: &Transform2D<F>) -> Self {
+        Self::new(self.transform(&other.position), self.angle + other.angle)
+    }
+}
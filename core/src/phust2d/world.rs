@@ -0,0 +1,238 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::material::PhysicsMaterial;
+use crate::phust2d::collider::Collider2D;
+use crate::phust2d::contact::{Contact2D, ManifoldCache2D};
+use crate::phust2d::joint::Joint2D;
+use crate::phust2d::narrow_phase::{collide, collide_with_plane};
+use crate::phust2d::rigid_body::RigidBody2D;
+use crate::phust2d::shape::Plane2D;
+use crate::phust2d::solver::solve;
+use crate::world::WorldConfig;
+use math::Vector2;
+use serde::{Deserialize, Serialize};
+
+/// World of rigid bodies moving in the plane.
+///
+/// # Remarks
+/// The world steps like the one in 3D: velocities are integrated, collisions are detected
+/// from the current placement of the colliders, the contacts and joints are solved together
+/// and the positions are integrated. The damping, solver and substeps of the configuration
+/// are honored, with collisions detected anew every substep, while diagnostics and the
+/// deterministic flag don't apply to 2D worlds, which always visit everything in order.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct World2D<F: num_traits::Float = f64> {
+    /// Rigid bodies simulated by the world.
+    pub bodies: Vec<RigidBody2D<F>>,
+
+    /// Colliders attached to the bodies of the world.
+    pub colliders: Vec<Collider2D<F>>,
+
+    /// Half-planes that are part of the scenery, colliding with every collider.
+    pub planes: Vec<Plane2D<F>>,
+
+    /// Material the scenery half-planes are made of.
+    pub scenery_material: PhysicsMaterial<F>,
+
+    /// Joints between the bodies of the world.
+    pub joints: Vec<Joint2D<F>>,
+
+    /// Gravity pulling the bodies of the world, none by default.
+    pub gravity: Vector2<F>,
+
+    /// Configuration of the world.
+    pub config: WorldConfig<F>,
+
+    /// Contacts generated during the last substep.
+    pub contacts: Vec<Contact2D<F>>,
+
+    /// Contact manifolds persisted across frames.
+    pub manifolds: ManifoldCache2D<F>,
+}
+
+impl<F: num_traits::Float> Default for World2D<F> {
+    fn default() -> Self {
+        Self::new(WorldConfig::default())
+    }
+}
+
+impl<F: num_traits::Float> World2D<F> {
+    /// Creates a new empty world with the given configuration.
+    pub fn new(config: WorldConfig<F>) -> Self {
+        Self {
+            bodies: Vec::new(),
+            colliders: Vec::new(),
+            planes: Vec::new(),
+            scenery_material: PhysicsMaterial::default(),
+            joints: Vec::new(),
+            gravity: Vector2::origin(),
+            config,
+            contacts: Vec::new(),
+            manifolds: ManifoldCache2D::new(),
+        }
+    }
+
+    /// Adds a rigid body to the world, returning its index.
+    pub fn add_body(&mut self, body: RigidBody2D<F>) -> usize {
+        self.bodies.push(body);
+        self.bodies.len() - 1
+    }
+
+    /// Adds a collider to the world, returning its index.
+    pub fn add_collider(&mut self, mut collider: Collider2D<F>) -> usize {
+        collider.calculate_internals(&self.bodies);
+        self.colliders.push(collider);
+        self.colliders.len() - 1
+    }
+
+    /// Adds a joint to the world, returning its index.
+    pub fn add_joint(&mut self, joint: Joint2D<F>) -> usize {
+        self.joints.push(joint);
+        self.joints.len() - 1
+    }
+
+    /// Gives a rigid body the mass and moment of inertia of its colliders, returning them.
+    ///
+    /// # Remarks
+    /// The inertia is taken around the origin of the body, so its colliders should be
+    /// placed around its center of mass. Bodies without colliders with mass are left
+    /// untouched.
+    pub fn update_mass_properties(&mut self, body: usize) -> (F, F) {
+        let (mass, inertia) = self
+            .colliders
+            .iter()
+            .filter(|collider| collider.body == body)
+            .map(Collider2D::mass_properties)
+            .fold((F::zero(), F::zero()), |(mass, inertia), properties| {
+                (mass + properties.0, inertia + properties.1)
+            });
+        if mass > F::zero() {
+            self.bodies[body].set_mass(mass).set_inertia(inertia);
+        }
+        (mass, inertia)
+    }
+
+    /// Initializes the world for a simulation frame.
+    /// This clears the force and torque accumulators for the bodies in the world.
+    pub fn start_frame(&mut self) {
+        for body in self.bodies.iter_mut() {
+            body.clear_accumulators();
+        }
+    }
+
+    /// Processes all the physics of the world over the given duration.
+    pub fn run_physics(&mut self, duration: F) {
+        let substeps = self.config.substeps.max(1);
+        let duration = duration / math::real(substeps as f64);
+        for _ in 0..substeps {
+            self.substep(duration);
+        }
+    }
+
+    /// Runs a single substep of the simulation.
+    fn substep(&mut self, duration: F) {
+        for body in self.bodies.iter_mut() {
+            body.integrate_velocity(
+                duration,
+                &self.gravity,
+                self.config.linear_damping,
+                self.config.angular_damping,
+            );
+        }
+
+        self.generate_contacts();
+        self.manifolds.update(&self.contacts);
+        solve(
+            &self.config.solver,
+            &mut self.manifolds,
+            &mut self.joints,
+            &mut self.bodies,
+            duration,
+        );
+
+        for body in self.bodies.iter_mut() {
+            body.integrate_position(duration);
+        }
+        for collider in self.colliders.iter_mut() {
+            collider.calculate_internals(&self.bodies);
+        }
+    }
+
+    /// Detects the contacts between the colliders, and between them and the scenery.
+    ///
+    /// # Remarks
+    /// Candidate pairs are found by sweeping the bounding boxes of the colliders along the
+    /// horizontal axis, then sorted so contacts are always generated in the same order.
+    fn generate_contacts(&mut self) {
+        self.contacts.clear();
+        for collider in self.colliders.iter_mut() {
+            collider.calculate_internals(&self.bodies);
+        }
+
+        let boxes: Vec<_> = self
+            .colliders
+            .iter()
+            .map(|collider| collider.shape.aabb(collider.transform()))
+            .collect();
+        let mut order: Vec<usize> = (0..boxes.len()).collect();
+        order.sort_by(|&one, &two| {
+            boxes[one]
+                .min
+                .x
+                .partial_cmp(&boxes[two].min.x)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let mut pairs = Vec::new();
+        for (position, &one) in order.iter().enumerate() {
+            for &two in order[position + 1..].iter() {
+                if boxes[two].min.x > boxes[one].max.x {
+                    break;
+                }
+                if boxes[one].intersects(&boxes[two]) {
+                    pairs.push((one.min(two), one.max(two)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+
+        for (one, two) in pairs {
+            let (first, second) = (&self.colliders[one], &self.colliders[two]);
+            let movable = |body: usize| self.bodies[body].has_finite_mass();
+            if first.body == second.body
+                || !(movable(first.body) || movable(second.body))
+                || !first.interacts_with(second.group, second.mask)
+            {
+                continue;
+            }
+            let start = self.contacts.len();
+            collide((one, two), first, second, &mut self.contacts);
+            let (friction, static_friction, restitution) = first.material.combine(&second.material);
+            for contact in self.contacts[start..].iter_mut() {
+                contact.friction = friction;
+                contact.static_friction = static_friction;
+                contact.restitution = restitution;
+            }
+        }
+
+        for (index, collider) in self.colliders.iter().enumerate() {
+            if !self.bodies[collider.body].has_finite_mass() {
+                continue;
+            }
+            let (friction, static_friction, restitution) =
+                collider.material.combine(&self.scenery_material);
+            for (plane_index, plane) in self.planes.iter().enumerate() {
+                let start = self.contacts.len();
+                collide_with_plane((index, plane_index), collider, plane, &mut self.contacts);
+                for contact in self.contacts[start..].iter_mut() {
+                    contact.friction = friction;
+                    contact.static_friction = static_friction;
+                    contact.restitution = restitution;
+                }
+            }
+        }
+    }
+}
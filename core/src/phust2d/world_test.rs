@@ -0,0 +1,181 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::joint::{JointLimits, JointMotor};
+use crate::phust2d::collider::Collider2D;
+use crate::phust2d::joint::*;
+use crate::phust2d::rigid_body::RigidBody2D;
+use crate::phust2d::shape::{Plane2D, Shape2D};
+use crate::phust2d::world::*;
+use math::Vector2;
+
+fn falling_world() -> World2D {
+    World2D {
+        gravity: Vector2::new(0.0, -10.0),
+        ..World2D::default()
+    }
+}
+
+fn ground_world() -> World2D {
+    let mut world = falling_world();
+    world.planes.push(Plane2D::new(Vector2::new(0.0, 1.0), 0.0));
+    world
+}
+
+fn add_shape(world: &mut World2D, position: Vector2<f64>, shape: Shape2D) -> usize {
+    let body = world.add_body(RigidBody2D::new(position, 1.0, 1.0));
+    world.add_collider(Collider2D::new(body, shape));
+    world.update_mass_properties(body);
+    body
+}
+
+fn run(world: &mut World2D, frames: usize) {
+    for _ in 0..frames {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+}
+
+#[test]
+fn resting_stack() {
+    let mut world = ground_world();
+    let boxes: Vec<usize> = (0..4)
+        .map(|level| {
+            let position = Vector2::new(0.0, 0.5 + level as f64);
+            add_shape(
+                &mut world,
+                position,
+                Shape2D::rectangle(Vector2::new(0.5, 0.5)),
+            )
+        })
+        .collect();
+    assert!((world.bodies[boxes[0]].mass() - 1.0).abs() < 1e-12);
+
+    run(&mut world, 300);
+    for (level, body) in boxes.iter().enumerate() {
+        let body = &world.bodies[*body];
+        assert!(body.position.x.abs() < 1e-2);
+        assert!((body.position.y - (0.5 + level as f64)).abs() < 0.03);
+        assert!(body.angle.abs() < 1e-2);
+        assert!(body.velocity.magnitude() < 1e-2);
+    }
+    assert_eq!(4, world.manifolds.len());
+}
+
+#[test]
+fn bounce_and_friction() {
+    let mut world = ground_world();
+    let ball = add_shape(&mut world, Vector2::new(0.0, 5.0), Shape2D::circle(0.5));
+    world.colliders[0].material.restitution = 1.0;
+    world.scenery_material.restitution = 1.0;
+
+    // A ball dropped on an elastic floor bounces back close to the height it fell from.
+    let mut highest_after_bounce: f64 = 0.0;
+    let mut bounced = false;
+    for _ in 0..120 {
+        run(&mut world, 1);
+        let body = &world.bodies[ball];
+        bounced |= body.velocity.y > 0.0;
+        if bounced {
+            highest_after_bounce = highest_after_bounce.max(body.position.y);
+        }
+    }
+    assert!(bounced);
+    assert!(highest_after_bounce > 4.5);
+
+    // A box sliding on a rough floor comes to a stop.
+    let mut world = ground_world();
+    let sliding = add_shape(
+        &mut world,
+        Vector2::new(0.0, 0.5),
+        Shape2D::rectangle(Vector2::new(0.5, 0.5)),
+    );
+    world.colliders[0].material.friction = 0.5;
+    world.bodies[sliding].velocity = Vector2::new(5.0, 0.0);
+    run(&mut world, 120);
+    let body = &world.bodies[sliding];
+    assert!(body.velocity.magnitude() < 1e-2);
+    // Sliding 5 m/s under a deceleration of 5 m/s² stops after 2.5 m.
+    assert!((body.position.x - 2.5).abs() < 0.2);
+}
+
+#[test]
+fn revolute_joint() {
+    let mut world = falling_world();
+    let pendulum = add_shape(&mut world, Vector2::new(2.0, 0.0), Shape2D::circle(0.25));
+    let pivot = Vector2::origin();
+    let revolute = RevoluteJoint2D::from_world(&world.bodies[pendulum], None, &pivot);
+    world.add_joint(Joint2D::new(
+        (pendulum, None),
+        JointKind2D::Revolute(revolute),
+    ));
+
+    // The pendulum swings down, keeping its distance to the pivot.
+    let mut lowest: f64 = 0.0;
+    for _ in 0..60 {
+        run(&mut world, 1);
+        let body = &world.bodies[pendulum];
+        assert!((body.point_in_world_space(&revolute.local_anchor_one) - pivot).magnitude() < 0.02);
+        lowest = lowest.min(body.position.y);
+    }
+    assert!(lowest < -1.9);
+    assert_eq!(4, world.joints[0].impulses().len());
+
+    // Limits stop a rod from swinging down past them, and motors turn it.
+    let mut world = falling_world();
+    let rod = Shape2D::rectangle(Vector2::new(1.0, 0.1));
+    let pendulum = add_shape(&mut world, Vector2::new(1.0, 0.0), rod);
+    let mut revolute = RevoluteJoint2D::from_world(&world.bodies[pendulum], None, &pivot);
+    revolute.limits = Some(JointLimits::new(-0.5, 0.5));
+    world.add_joint(Joint2D::new(
+        (pendulum, None),
+        JointKind2D::Revolute(revolute),
+    ));
+    run(&mut world, 120);
+    let angle = revolute.angle(&world.bodies[pendulum], None);
+    assert!((angle + 0.5).abs() < 0.02);
+
+    if let JointKind2D::Revolute(revolute) = &mut world.joints[0].kind {
+        revolute.limits = None;
+        revolute.motor = Some(JointMotor::velocity(1.0, 1000.0));
+    }
+    run(&mut world, 30);
+    assert!((world.bodies[pendulum].rotation - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn distance_and_weld_joints() {
+    let mut world = falling_world();
+    let shape = || Shape2D::rectangle(Vector2::new(0.5, 0.25));
+    let one = add_shape(&mut world, Vector2::new(1.0, 0.0), shape());
+    let two = add_shape(&mut world, Vector2::new(2.0, 0.0), shape());
+    world.colliders[1].mask = 0;
+
+    let rod = DistanceJoint2D::from_world(
+        &world.bodies[one],
+        None,
+        &Vector2::new(1.0, 0.0),
+        &Vector2::new(0.0, 3.0),
+    );
+    world.add_joint(Joint2D::new((one, None), JointKind2D::Distance(rod)));
+    let weld = WeldJoint2D::from_world(
+        &world.bodies[two],
+        Some(&world.bodies[one]),
+        &Vector2::new(1.5, 0.0),
+    );
+    world.add_joint(Joint2D::new((two, Some(one)), JointKind2D::Weld(weld)));
+
+    run(&mut world, 120);
+    let (first, second) = (&world.bodies[one], &world.bodies[two]);
+    let length = first
+        .position
+        .vector_sub(&Vector2::new(0.0, 3.0))
+        .magnitude();
+    assert!((length - rod.length).abs() < 0.02);
+    assert!((first.angle - second.angle).abs() < 0.02);
+    let offset = first.point_in_local_space(&second.position);
+    assert!((offset - Vector2::new(1.0, 0.0)).magnitude() < 0.02);
+}
@@ -0,0 +1,371 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::force::ForceGenerator;
+use crate::joint::{JointMotor, MotorTarget};
+use crate::rigid_body::RigidBody;
+use math::{Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Gains of a proportional-integral-derivative controller.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PidGains<F: num_traits::Float = f64> {
+    /// Output per unit of error.
+    pub proportional: F,
+
+    /// Output per unit of error accumulated over time, removing steady errors like the
+    /// ones caused by gravity.
+    pub integral: F,
+
+    /// Output per unit of rate of change of the error, damping the response.
+    pub derivative: F,
+}
+
+impl<F: num_traits::Float> PidGains<F> {
+    /// Creates new gains for a PID controller.
+    pub fn new(proportional: F, integral: F, derivative: F) -> Self {
+        Self {
+            proportional,
+            integral,
+            derivative,
+        }
+    }
+
+    /// Creates new gains for a PD controller, without integral term.
+    pub fn pd(proportional: F, derivative: F) -> Self {
+        Self::new(proportional, F::zero(), derivative)
+    }
+}
+
+/// Proportional-integral-derivative controller, turning the error of a scalar quantity
+/// (like the angle of a hinge) into the output that corrects it.
+///
+/// # Remarks
+/// The integral is clamped to `max_integral` to keep it from winding up while the output
+/// saturates, and the output to `max_output`. The derivative is taken from the change
+/// of the error between updates, unless given by the caller, which avoids kicks when the
+/// target jumps.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PidController<F: num_traits::Float = f64> {
+    /// Gains of the controller.
+    pub gains: PidGains<F>,
+
+    /// Largest magnitude of the integral of the error, if any.
+    pub max_integral: Option<F>,
+
+    /// Largest magnitude of the output, if any.
+    pub max_output: Option<F>,
+
+    integral: F,
+    previous_error: Option<F>,
+}
+
+impl<F: num_traits::Float> PidController<F> {
+    /// Creates a new controller with the given gains, without limits.
+    pub fn new(gains: PidGains<F>) -> Self {
+        Self {
+            gains,
+            max_integral: None,
+            max_output: None,
+            integral: F::zero(),
+            previous_error: None,
+        }
+    }
+
+    /// Returns the integral of the error accumulated so far.
+    pub fn integral(&self) -> F {
+        self.integral
+    }
+
+    /// Forgets the accumulated integral and the previous error, e.g. when the target changes.
+    pub fn reset(&mut self) {
+        self.integral = F::zero();
+        self.previous_error = None;
+    }
+
+    /// Returns the output correcting the given error after the given time, deriving the
+    /// error from its previous value.
+    pub fn update(&mut self, error: F, duration: F) -> F {
+        let derivative = match self.previous_error {
+            Some(previous) if duration > F::zero() => (error - previous) / duration,
+            _ => F::zero(),
+        };
+        self.update_with_derivative(error, derivative, duration)
+    }
+
+    /// Returns the output correcting the given error after the given time, with the given
+    /// rate of change of the error (e.g. the opposite of the measured speed when holding
+    /// a position).
+    pub fn update_with_derivative(&mut self, error: F, derivative: F, duration: F) -> F {
+        self.previous_error = Some(error);
+        self.integral = self.integral + error * duration;
+        if let Some(max) = self.max_integral {
+            self.integral = self.integral.max(-max).min(max);
+        }
+        let output = self.gains.proportional * error
+            + self.gains.integral * self.integral
+            + self.gains.derivative * derivative;
+        match self.max_output {
+            Some(max) => output.max(-max).min(max),
+            None => output,
+        }
+    }
+
+    /// Sets the motor to the speed correcting the given error of the position (or angle) of
+    /// its joint, so the motor's maximum force bounds the correction.
+    pub fn drive_motor(&mut self, motor: &mut JointMotor<F>, error: F, duration: F) {
+        motor.target = MotorTarget::Velocity(self.update(error, duration));
+    }
+}
+
+/// Proportional-integral-derivative controller for the error of a vector quantity, like
+/// a position or a rotation.
+///
+/// # Remarks
+/// Works like `PidController` on every component at once, clamping the magnitudes of
+/// the integral and the output.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct VectorPidController<F: num_traits::Float = f64> {
+    /// Gains of the controller.
+    pub gains: PidGains<F>,
+
+    /// Largest magnitude of the integral of the error, if any.
+    pub max_integral: Option<F>,
+
+    /// Largest magnitude of the output, if any.
+    pub max_output: Option<F>,
+
+    integral: Vector3<F>,
+    previous_error: Option<Vector3<F>>,
+}
+
+impl<F: num_traits::Float> VectorPidController<F> {
+    /// Creates a new controller with the given gains, without limits.
+    pub fn new(gains: PidGains<F>) -> Self {
+        Self {
+            gains,
+            max_integral: None,
+            max_output: None,
+            integral: Vector3::origin(),
+            previous_error: None,
+        }
+    }
+
+    /// Returns the integral of the error accumulated so far.
+    pub fn integral(&self) -> Vector3<F> {
+        self.integral
+    }
+
+    /// Forgets the accumulated integral and the previous error, e.g. when the target changes.
+    pub fn reset(&mut self) {
+        self.integral = Vector3::origin();
+        self.previous_error = None;
+    }
+
+    /// Returns the output correcting the given error after the given time, deriving the
+    /// error from its previous value.
+    pub fn update(&mut self, error: &Vector3<F>, duration: F) -> Vector3<F> {
+        let derivative = match self.previous_error {
+            Some(previous) if duration > F::zero() => {
+                error.vector_sub(&previous).scalar_div(duration)
+            }
+            _ => Vector3::origin(),
+        };
+        self.update_with_derivative(error, &derivative, duration)
+    }
+
+    /// Returns the output correcting the given error after the given time, with the given
+    /// rate of change of the error.
+    pub fn update_with_derivative(
+        &mut self,
+        error: &Vector3<F>,
+        derivative: &Vector3<F>,
+        duration: F,
+    ) -> Vector3<F> {
+        self.previous_error = Some(*error);
+        self.integral = clamp_magnitude(
+            &self.integral.vector_add(&error.scalar_mul(duration)),
+            self.max_integral,
+        );
+        let output = error
+            .scalar_mul(self.gains.proportional)
+            .vector_add(&self.integral.scalar_mul(self.gains.integral))
+            .vector_add(&derivative.scalar_mul(self.gains.derivative));
+        clamp_magnitude(&output, self.max_output)
+    }
+}
+
+/// Force generator that pushes a rigid body towards a target position at its center of mass.
+///
+/// # Remarks
+/// The derivative is taken from the velocity of the body relative to the target velocity,
+/// so moving targets are followed without lag. The integral term holds the body against
+/// steady forces like gravity, which makes it hover.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PositionController<F: num_traits::Float = f64> {
+    /// Position the body is driven to.
+    pub target: Vector3<F>,
+
+    /// Velocity of the target.
+    pub target_velocity: Vector3<F>,
+
+    /// Controller turning the error of the position into a force.
+    pub controller: VectorPidController<F>,
+}
+
+impl<F: num_traits::Float> PositionController<F> {
+    /// Creates a new generator driving a body to the given position with the given gains.
+    pub fn new(target: Vector3<F>, gains: PidGains<F>) -> Self {
+        Self {
+            target,
+            target_velocity: Vector3::origin(),
+            controller: VectorPidController::new(gains),
+        }
+    }
+}
+
+impl<F: num_traits::Float> ForceGenerator<F> for PositionController<F> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, duration: F) {
+        let body = &mut bodies[body];
+        if !body.has_finite_mass() {
+            return;
+        }
+
+        let error = self.target.vector_sub(&body.position);
+        let derivative = self.target_velocity.vector_sub(&body.velocity);
+        let force = self
+            .controller
+            .update_with_derivative(&error, &derivative, duration);
+        body.add_force(&force);
+    }
+}
+
+/// Force generator that turns a rigid body towards a target orientation.
+///
+/// # Remarks
+/// The error is the rotation vector (axis times angle) of the shortest rotation from the
+/// orientation of the body to the target, and the derivative the opposite of the angular
+/// velocity of the body, so self-balancing bodies are kept upright without overshooting.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OrientationController<F: num_traits::Float = f64> {
+    /// Orientation the body is turned to.
+    pub target: Quaternion<F>,
+
+    /// Controller turning the error of the orientation into a torque.
+    pub controller: VectorPidController<F>,
+}
+
+impl<F: num_traits::Float> OrientationController<F> {
+    /// Creates a new generator turning a body to the given orientation with the given gains.
+    pub fn new(target: Quaternion<F>, gains: PidGains<F>) -> Self {
+        Self {
+            target,
+            controller: VectorPidController::new(gains),
+        }
+    }
+}
+
+impl<F: num_traits::Float> ForceGenerator<F> for OrientationController<F> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, duration: F) {
+        let body = &mut bodies[body];
+        if !body.has_finite_mass() {
+            return;
+        }
+
+        let error = rotation_vector(&self.target.quaternion_mul(&body.orientation.conjugate()));
+        let torque =
+            self.controller
+                .update_with_derivative(&error, &body.rotation.invert(), duration);
+        body.add_torque(&torque);
+    }
+}
+
+/// Force generator that keeps the linear or angular velocity of a rigid body at a target.
+///
+/// # Remarks
+/// Controls the linear velocity with a force at the center of mass, or the angular
+/// velocity with a torque. PI gains are usually enough, as the derivative term reacts
+/// to accelerations.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct VelocityController<F: num_traits::Float = f64> {
+    /// Velocity the body is kept at, in world space.
+    pub target: Vector3<F>,
+
+    /// Whether the target is an angular velocity rather than a linear one.
+    pub angular: bool,
+
+    /// Controller turning the error of the velocity into a force or torque.
+    pub controller: VectorPidController<F>,
+}
+
+impl<F: num_traits::Float> VelocityController<F> {
+    /// Creates a new generator keeping a body at the given linear velocity.
+    pub fn linear(target: Vector3<F>, gains: PidGains<F>) -> Self {
+        Self {
+            target,
+            angular: false,
+            controller: VectorPidController::new(gains),
+        }
+    }
+
+    /// Creates a new generator keeping a body at the given angular velocity.
+    pub fn angular(target: Vector3<F>, gains: PidGains<F>) -> Self {
+        Self {
+            target,
+            angular: true,
+            controller: VectorPidController::new(gains),
+        }
+    }
+}
+
+impl<F: num_traits::Float> ForceGenerator<F> for VelocityController<F> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], body: usize, duration: F) {
+        let body = &mut bodies[body];
+        if !body.has_finite_mass() {
+            return;
+        }
+
+        let velocity = if self.angular {
+            body.rotation
+        } else {
+            body.velocity
+        };
+        let output = self
+            .controller
+            .update(&self.target.vector_sub(&velocity), duration);
+        if self.angular {
+            body.add_torque(&output);
+        } else {
+            body.add_force(&output);
+        }
+    }
+}
+
+/// Returns the vector scaled down to the given magnitude, if it's longer.
+fn clamp_magnitude<F: num_traits::Float>(vector: &Vector3<F>, max: Option<F>) -> Vector3<F> {
+    match max {
+        Some(max) if vector.magnitude() > max => vector.normalize().scalar_mul(max),
+        _ => *vector,
+    }
+}
+
+/// Returns the rotation vector (axis times angle, in radians) of the shortest rotation
+/// represented by the quaternion.
+pub(crate) fn rotation_vector<F: num_traits::Float>(rotation: &Quaternion<F>) -> Vector3<F> {
+    // Both signs represent the same rotation, but only the positive real part is the shortest.
+    let sign = if rotation.r < F::zero() {
+        -F::one()
+    } else {
+        F::one()
+    };
+    let axis = Vector3::new(rotation.i, rotation.j, rotation.k).scalar_mul(sign);
+    let sin = axis.magnitude();
+    if sin <= F::epsilon() {
+        return axis.scalar_mul(math::real(2.0));
+    }
+    let angle = math::real::<F>(2.0) * sin.atan2(rotation.r * sign);
+    axis.scalar_mul(angle / sin)
+}
@@ -0,0 +1,153 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::force::Gravity;
+use crate::joint::{JointMotor, MotorTarget};
+use crate::pid::*;
+use crate::rigid_body::RigidBody;
+use crate::world::{World, WorldConfig};
+use math::{Matrix3, Quaternion, Vector3};
+
+fn world() -> World {
+    let mut world = World::new(WorldConfig::default());
+    world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    world
+}
+
+fn run(world: &mut World, frames: usize) {
+    for _ in 0..frames {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+}
+
+#[test]
+fn controller() {
+    let mut pid = PidController::new(PidGains::new(2.0, 1.0, 0.5));
+    assert_eq!(2.5, pid.update(1.0, 0.5));
+    assert_eq!(1.25, pid.update(0.5, 0.5));
+    assert_eq!(0.75, pid.integral());
+
+    // The integral and the output are clamped.
+    pid.reset();
+    pid.max_integral = Some(0.25);
+    pid.max_output = Some(2.0);
+    assert_eq!(2.0, pid.update(1.0, 0.5));
+    assert_eq!(0.25, pid.integral());
+    assert_eq!(
+        0.25 * 2.0 + 0.25 - 1.0,
+        pid.update_with_derivative(0.25, -2.0, 0.5)
+    );
+
+    // Joint motors get driven at the speed correcting the error of the joint.
+    let mut motor = JointMotor::position(0.0, 10.0);
+    let mut pd = PidController::new(PidGains::pd(4.0, 0.0));
+    pd.drive_motor(&mut motor, -0.5, 0.1);
+    assert_eq!(MotorTarget::Velocity(-2.0), motor.target);
+
+    // Vector controllers clamp magnitudes instead.
+    let mut pid: VectorPidController = VectorPidController::new(PidGains::new(1.0, 1.0, 0.0));
+    pid.max_integral = Some(0.5);
+    pid.max_output = Some(1.0);
+    let output = pid.update(&Vector3::new(0.0, 3.0, 4.0), 1.0);
+    assert_eq!(0.5, pid.integral().magnitude());
+    assert!((output.magnitude() - 1.0).abs() < 1e-12);
+    assert!((output.y * 4.0 - output.z * 3.0).abs() < 1e-12);
+}
+
+#[test]
+fn position_controller() {
+    // The integral term makes up for gravity, hovering the body at the target.
+    let mut world = world();
+    world
+        .registry
+        .add(0, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+    world.registry.add(
+        0,
+        Box::new(PositionController::new(
+            Vector3::new(1.0, 2.0, 0.0),
+            PidGains::new(40.0, 20.0, 12.0),
+        )),
+    );
+    run(&mut world, 900);
+    let body = &world.bodies[0];
+    assert!(
+        body.position
+            .vector_sub(&Vector3::new(1.0, 2.0, 0.0))
+            .magnitude()
+            < 0.01
+    );
+    assert!(body.velocity.magnitude() < 0.01);
+}
+
+#[test]
+fn orientation_controller() {
+    let mut world = world();
+    world.bodies[0].orientation = Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), 2.5);
+    world.bodies[0].calculate_derived_data();
+    let target = Quaternion::from_axis_angle(&Vector3::new(1.0, 0.0, 0.0), 0.5);
+    world.registry.add(
+        0,
+        Box::new(OrientationController::new(target, PidGains::pd(20.0, 8.0))),
+    );
+    run(&mut world, 300);
+
+    // The body turns the shortest way and settles at the target.
+    let body = &world.bodies[0];
+    let x = Vector3::new(1.0, 0.0, 0.0);
+    let y = Vector3::new(0.0, 1.0, 0.0);
+    assert!(
+        body.orientation
+            .rotate(&x)
+            .vector_sub(&target.rotate(&x))
+            .magnitude()
+            < 0.01
+    );
+    assert!(
+        body.orientation
+            .rotate(&y)
+            .vector_sub(&target.rotate(&y))
+            .magnitude()
+            < 0.01
+    );
+    assert!(body.rotation.magnitude() < 0.01);
+}
+
+#[test]
+fn velocity_controller() {
+    let mut world = world();
+    let gains = PidGains::new(10.0, 5.0, 0.0);
+    world.registry.add(
+        0,
+        Box::new(VelocityController::linear(
+            Vector3::new(1.0, 0.0, 0.0),
+            gains,
+        )),
+    );
+    world.registry.add(
+        0,
+        Box::new(VelocityController::angular(
+            Vector3::new(0.0, 2.0, 0.0),
+            gains,
+        )),
+    );
+    run(&mut world, 300);
+
+    // Both velocities are held despite the damping of the world.
+    let body = &world.bodies[0];
+    assert!(
+        body.velocity
+            .vector_sub(&Vector3::new(1.0, 0.0, 0.0))
+            .magnitude()
+            < 0.01
+    );
+    assert!(
+        body.rotation
+            .vector_sub(&Vector3::new(0.0, 2.0, 0.0))
+            .magnitude()
+            < 0.01
+    );
+}
@@ -0,0 +1,68 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Plane in 3D space, represented by its normal and its offset from the origin.
+///
+/// # Remarks
+/// Points `p` on the plane satisfy `normal · p = offset`. The normal is expected
+/// to be normalized, and points towards the positive side of the plane, so the
+/// plane also describes the half-space behind it.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Plane<F: num_traits::Float = f64> {
+    /// Unit normal of the plane.
+    pub normal: Vector3<F>,
+
+    /// Distance of the plane from the origin along its normal.
+    pub offset: F,
+}
+
+impl<F: num_traits::Float> Plane<F> {
+    /// Creates a new plane with the given normal and offset.
+    pub fn new(normal: Vector3<F>, offset: F) -> Self {
+        Self { normal, offset }
+    }
+
+    /// Creates a new plane with the given normal, passing through the given point.
+    pub fn from_point(normal: Vector3<F>, point: &Vector3<F>) -> Self {
+        Self {
+            normal,
+            offset: normal.dot_product(point),
+        }
+    }
+
+    /// Returns the signed distance from the plane to the given point.
+    /// Points on the side the normal points to are at a positive distance.
+    pub fn signed_distance(&self, point: &Vector3<F>) -> F {
+        self.normal.dot_product(point) - self.offset
+    }
+
+    /// Returns true if some part of the bounding box is on the positive side of the plane.
+    pub fn intersects_aabb(&self, aabb: &Aabb<F>) -> bool {
+        // Test the corner furthest along the normal.
+        let corner = Vector3::new(
+            if self.normal.x >= num_traits::zero() {
+                aabb.max.x
+            } else {
+                aabb.min.x
+            },
+            if self.normal.y >= num_traits::zero() {
+                aabb.max.y
+            } else {
+                aabb.min.y
+            },
+            if self.normal.z >= num_traits::zero() {
+                aabb.max.z
+            } else {
+                aabb.min.z
+            },
+        );
+        self.signed_distance(&corner) >= num_traits::zero()
+    }
+}
@@ -0,0 +1,154 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::fixed_joint::orientation_error;
+use crate::joint::{Anchors, JointLimits, JointMotor, RowBuilder};
+use crate::rigid_body::RigidBody;
+use crate::solver::tangent_basis;
+use math::{Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Prismatic (or slider) joint, locking the relative orientation of two bodies and keeping
+/// the anchor of the first body on an axis through the anchor of the second one,
+/// so the bodies can only slide relative to each other along the axis.
+///
+/// # Remarks
+/// The translation of the joint is the position of the anchor of the first body along
+/// the axis, measured from the anchor of the second one.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PrismaticJoint<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector3<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector3<F>,
+
+    /// Unit axis of the joint in the local space of the first body.
+    pub local_axis_one: Vector3<F>,
+
+    /// Orientation of the first body relative to the second one,
+    /// or to the world for joints with the scenery.
+    pub relative_orientation: Quaternion<F>,
+
+    /// Range of translations allowed, if any.
+    pub limits: Option<JointLimits<F>>,
+
+    /// Motor driving the speed, or the translation, of the joint, if any.
+    pub motor: Option<JointMotor<F>>,
+}
+
+impl<F: num_traits::Float> PrismaticJoint<F> {
+    /// Creates a new prismatic joint between the given anchors and along the given axis,
+    /// keeping the given relative orientation, without limits nor motor.
+    pub fn new(
+        local_anchor_one: Vector3<F>,
+        local_anchor_two: Vector3<F>,
+        local_axis_one: Vector3<F>,
+        relative_orientation: Quaternion<F>,
+    ) -> Self {
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+            local_axis_one: local_axis_one.normalize(),
+            relative_orientation,
+            limits: None,
+            motor: None,
+        }
+    }
+
+    /// Creates a new prismatic joint between the given bodies, along an axis passing through
+    /// the given anchor, both in world space. The current translation of the joint is zero.
+    pub fn from_world(
+        one: &RigidBody<F>,
+        two: Option<&RigidBody<F>>,
+        anchor: &Vector3<F>,
+        axis: &Vector3<F>,
+    ) -> Self {
+        let (local_anchor_two, relative_orientation) = match two {
+            Some(two) => (
+                two.point_in_local_space(anchor),
+                two.orientation.conjugate().quaternion_mul(&one.orientation),
+            ),
+            None => (*anchor, one.orientation),
+        };
+        Self::new(
+            one.point_in_local_space(anchor),
+            local_anchor_two,
+            one.direction_in_local_space(axis),
+            relative_orientation,
+        )
+    }
+
+    /// Returns the current translation of the joint.
+    pub fn translation(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> F {
+        let point_two = match two {
+            Some(two) => two.point_in_world_space(&self.local_anchor_two),
+            None => self.local_anchor_two,
+        };
+        one.point_in_world_space(&self.local_anchor_one)
+            .vector_sub(&point_two)
+            .dot_product(&one.direction_in_world_space(&self.local_axis_one))
+    }
+
+    /// Returns the current speed of the joint along its axis.
+    pub fn linear_speed(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> F {
+        let velocity = match two {
+            Some(two) => one.velocity.vector_sub(&two.velocity),
+            None => one.velocity,
+        };
+        velocity.dot_product(&one.direction_in_world_space(&self.local_axis_one))
+    }
+
+    /// Adds the constraint rows of the joint.
+    pub(crate) fn rows(&self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let pair = builder.bodies();
+        let one = &bodies[pair.0];
+        let two = pair.1.map(|body| &bodies[body]);
+
+        let error = orientation_error(one, two, &self.relative_orientation);
+        let axes = [
+            Vector3::new(F::one(), F::zero(), F::zero()),
+            Vector3::new(F::zero(), F::one(), F::zero()),
+            Vector3::new(F::zero(), F::zero(), F::one()),
+        ];
+        for (index, axis) in axes.iter().enumerate() {
+            builder.add(
+                index,
+                Vector3::origin(),
+                *axis,
+                *axis,
+                error.dot_product(axis),
+            );
+        }
+
+        // The axis turns with the first body, so its rotation moves the anchors
+        // as if applied at the anchor of the second body.
+        let anchors = Anchors::new(pair, &self.local_anchor_one, &self.local_anchor_two, bodies);
+        let axis = one.direction_in_world_space(&self.local_axis_one);
+        let offset = anchors.point_one.vector_sub(&anchors.point_two);
+        let lever = anchors.point_two.vector_sub(&one.position);
+        for (index, perpendicular) in tangent_basis(&axis).iter().enumerate() {
+            builder.add(
+                3 + index,
+                *perpendicular,
+                lever.cross_product(perpendicular),
+                anchors.relative_two.cross_product(perpendicular),
+                offset.dot_product(perpendicular),
+            );
+        }
+
+        let translation = offset.dot_product(&axis);
+        let angular_one = lever.cross_product(&axis);
+        let angular_two = anchors.relative_two.cross_product(&axis);
+        if let Some(limits) = &self.limits {
+            builder.limit(5, limits, translation, axis, angular_one, angular_two);
+        }
+        if let Some(motor) = &self.motor {
+            builder.motor(6, motor, translation, axis, angular_one, angular_two);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::joint::{Anchors, RowBuilder};
+use crate::rigid_body::RigidBody;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Pulley joint, hanging an anchor point of each body from a rope running over two fixed
+/// pulleys, so one side gets shorter as much as the other one gets longer.
+///
+/// # Remarks
+/// The length of the first side plus `ratio` times the length of the second one is kept
+/// constant, which makes the second side a block and tackle when the ratio is above one.
+/// Like a rope, the joint only pulls the bodies towards the pulleys.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PulleyJoint<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector3<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector3<F>,
+
+    /// Pulley the first body hangs from, in world space.
+    pub pulley_one: Vector3<F>,
+
+    /// Pulley the second body hangs from, in world space.
+    pub pulley_two: Vector3<F>,
+
+    /// Length of rope the second side uses for every unit of length of the first one.
+    pub ratio: F,
+
+    /// Length of the first side plus `ratio` times the length of the second one.
+    pub length: F,
+}
+
+impl<F: num_traits::Float> PulleyJoint<F> {
+    /// Creates a new pulley joint between the given anchors and pulleys, with the given
+    /// ratio and total length.
+    pub fn new(
+        local_anchor_one: Vector3<F>,
+        local_anchor_two: Vector3<F>,
+        pulley_one: Vector3<F>,
+        pulley_two: Vector3<F>,
+        ratio: F,
+        length: F,
+    ) -> Self {
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+            pulley_one,
+            pulley_two,
+            ratio,
+            length,
+        }
+    }
+
+    /// Creates a new pulley joint between the given bodies, hanging the given anchors from
+    /// the given pulleys, all in world space. The rope is taut at the current lengths.
+    pub fn from_world(
+        one: &RigidBody<F>,
+        two: Option<&RigidBody<F>>,
+        anchors: (&Vector3<F>, &Vector3<F>),
+        pulleys: (&Vector3<F>, &Vector3<F>),
+        ratio: F,
+    ) -> Self {
+        let length = anchors.0.vector_sub(pulleys.0).magnitude()
+            + ratio * anchors.1.vector_sub(pulleys.1).magnitude();
+        Self::new(
+            one.point_in_local_space(anchors.0),
+            two.map_or(*anchors.1, |two| two.point_in_local_space(anchors.1)),
+            *pulleys.0,
+            *pulleys.1,
+            ratio,
+            length,
+        )
+    }
+
+    /// Returns the current lengths of both sides of the rope.
+    pub fn lengths(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> (F, F) {
+        let point_two = match two {
+            Some(two) => two.point_in_world_space(&self.local_anchor_two),
+            None => self.local_anchor_two,
+        };
+        (
+            one.point_in_world_space(&self.local_anchor_one)
+                .vector_sub(&self.pulley_one)
+                .magnitude(),
+            point_two.vector_sub(&self.pulley_two).magnitude(),
+        )
+    }
+
+    /// Adds the constraint rows of the joint.
+    pub(crate) fn rows(&self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let anchors = Anchors::new(
+            builder.bodies(),
+            &self.local_anchor_one,
+            &self.local_anchor_two,
+            bodies,
+        );
+        let side = |point: &Vector3<F>, pulley: &Vector3<F>| {
+            let offset = point.vector_sub(pulley);
+            let length = offset.magnitude();
+            if length > F::epsilon() {
+                (offset.scalar_mul(F::one() / length), length)
+            } else {
+                // Anchors right at their pulley can only be pulled down.
+                (Vector3::new(F::zero(), -F::one(), F::zero()), length)
+            }
+        };
+        let (direction_one, length_one) = side(&anchors.point_one, &self.pulley_one);
+        let (direction_two, length_two) = side(&anchors.point_two, &self.pulley_two);
+
+        // Lengthening the rope on one side shortens it on the other, so the second body
+        // is pushed along its side as the first one is pulled back along its own.
+        let direction_two = direction_two.scalar_mul(-self.ratio);
+        let error = length_one + self.ratio * length_two - self.length;
+        let duration = builder.duration();
+        let row = builder.add(
+            0,
+            direction_one,
+            anchors.relative_one.cross_product(&direction_one),
+            anchors.relative_two.cross_product(&direction_two),
+            error,
+        );
+        row.linear_two = direction_two;
+        row.upper = num_traits::zero();
+        if error < F::zero() {
+            // Slack rope: let the bodies move until it's taut, but not past it.
+            row.bias = -error / duration;
+        }
+    }
+}
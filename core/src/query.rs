@@ -0,0 +1,586 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::collider::{Collider, DEFAULT_COLLISION_MASK};
+use crate::gjk::{cast, closest_points, intersects, CastHit, GjkResult};
+use crate::narrow_phase::{collide_shapes, CollisionData};
+use crate::plane::Plane;
+use crate::shape::{HullFace, Shape, Sphere, SupportMap};
+use crate::trimesh::TriMesh;
+use math::{Matrix4, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of contact points generated between two shapes by contact queries.
+const MAX_CONTACT_POINTS: usize = 64;
+
+/// Hit of a ray cast against the colliders and scenery planes of a world.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RayHit<F: num_traits::Float = f64> {
+    /// Index of the collider hit, or `None` for scenery planes.
+    pub collider: Option<usize>,
+
+    /// Index of the body the collider hit is attached to, or `None` for scenery planes.
+    pub body: Option<usize>,
+
+    /// Point hit in world space.
+    pub point: Vector3<F>,
+
+    /// Unit normal of the surface hit, in world space.
+    pub normal: Vector3<F>,
+
+    /// Time of impact along the ray, in multiples of its direction.
+    pub toi: F,
+
+    /// Distance from the origin of the ray to the point hit.
+    pub distance: F,
+}
+
+/// First hit of a shape swept against the colliders and scenery planes of a world.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ShapeCastHit<F: num_traits::Float = f64> {
+    /// Index of the collider hit, or `None` for scenery planes.
+    pub collider: Option<usize>,
+
+    /// Index of the body the collider hit is attached to, or `None` for scenery planes.
+    pub body: Option<usize>,
+
+    /// Point of contact in world space.
+    pub point: Vector3<F>,
+
+    /// Unit normal of the surface hit at the point of contact, in world space.
+    pub normal: Vector3<F>,
+
+    /// Distance travelled by the shape until the hit.
+    pub distance: F,
+}
+
+/// Projection of a point onto the closest collider or scenery plane of a world.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PointProjection<F: num_traits::Float = f64> {
+    /// Index of the closest collider, or `None` for scenery planes.
+    pub collider: Option<usize>,
+
+    /// Index of the body the closest collider is attached to, or `None` for scenery planes.
+    pub body: Option<usize>,
+
+    /// Point on the surface closest to the projected point, in world space.
+    pub point: Vector3<F>,
+
+    /// True if the projected point is inside the collider, or behind the plane.
+    pub is_inside: bool,
+}
+
+/// Contact between two shapes, seen from the first one.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ShapeContact<F: num_traits::Float = f64> {
+    /// Unit normal of the deepest contact, in world space, pushing the first shape away from
+    /// the second one.
+    pub normal: Vector3<F>,
+
+    /// Depth of the deepest contact, negative when the shapes don't touch yet.
+    pub penetration: F,
+
+    /// Points of contact in world space, along with their depth of penetration, deepest first.
+    pub points: Vec<(Vector3<F>, F)>,
+}
+
+/// Predicate deciding whether a collider, given along with its index, passes a filter.
+pub type ColliderPredicate<'a, F> = &'a dyn Fn(usize, &Collider<Shape<F>, F>) -> bool;
+
+/// Filter deciding which colliders, and scenery planes, are considered by a query.
+///
+/// # Remarks
+/// Everything passes the default filter, which belongs to every collision group.
+#[derive(Copy, Clone)]
+pub struct QueryFilter<'a, F: num_traits::Float = f64> {
+    /// Body whose colliders are ignored, if any (e.g. the body casting the ray).
+    pub exclude_body: Option<usize>,
+
+    /// Ignores the scenery planes when true.
+    pub exclude_scenery: bool,
+
+    /// Bitfield of the collision groups the query belongs to, checked against the mask
+    /// of the colliders.
+    pub group: u32,
+
+    /// Bitfield of the collision groups of the colliders the query considers.
+    pub mask: u32,
+
+    /// Predicate called with the index of each collider and the collider itself,
+    /// ignoring the colliders it returns false for.
+    pub predicate: Option<ColliderPredicate<'a, F>>,
+}
+
+impl<'a, F: num_traits::Float> Default for QueryFilter<'a, F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, F: num_traits::Float> QueryFilter<'a, F> {
+    /// Creates a new filter letting everything pass.
+    pub fn new() -> Self {
+        Self {
+            exclude_body: None,
+            exclude_scenery: false,
+            group: DEFAULT_COLLISION_MASK,
+            mask: DEFAULT_COLLISION_MASK,
+            predicate: None,
+        }
+    }
+
+    /// Returns true if the given collider passes the filter.
+    pub fn test(&self, index: usize, collider: &Collider<Shape<F>, F>) -> bool {
+        self.exclude_body != Some(collider.body)
+            && collider.interacts_with(self.group, self.mask)
+            && self
+                .predicate
+                .is_none_or(|predicate| predicate(index, collider))
+    }
+}
+
+/// Sweeps a convex shape along the given direction against any shape, returning the
+/// first contact before the given maximum time of impact, if any.
+pub(crate) fn cast_against<F: num_traits::Float, S: SupportMap<F>>(
+    moving: &S,
+    transform: &Matrix4<F>,
+    direction: &Vector3<F>,
+    target: &Shape<F>,
+    target_transform: &Matrix4<F>,
+    max_toi: F,
+) -> Option<CastHit<F>> {
+    match target {
+        Shape::Sphere(shape) => cast(
+            moving,
+            transform,
+            direction,
+            shape,
+            target_transform,
+            max_toi,
+        ),
+        Shape::Cuboid(shape) => cast(
+            moving,
+            transform,
+            direction,
+            shape,
+            target_transform,
+            max_toi,
+        ),
+        Shape::Capsule(shape) => cast(
+            moving,
+            transform,
+            direction,
+            shape,
+            target_transform,
+            max_toi,
+        ),
+        Shape::ConvexHull(shape) => cast(
+            moving,
+            transform,
+            direction,
+            shape,
+            target_transform,
+            max_toi,
+        ),
+        Shape::Compound(compound) => compound
+            .children
+            .iter()
+            .filter_map(|child| {
+                let child_transform = target_transform.matrix_mul(&child.offset);
+                cast_against(
+                    moving,
+                    transform,
+                    direction,
+                    &child.shape,
+                    &child_transform,
+                    max_toi,
+                )
+            })
+            .min_by(|one, two| one.toi.partial_cmp(&two.toi).expect("finite times")),
+        Shape::TriMesh(mesh) => {
+            // Only the triangles overlapping the region swept by the shape can be hit.
+            let start = support_aabb(moving, transform);
+            let offset = direction.scalar_mul(max_toi);
+            let end = Aabb::new(start.min.vector_add(&offset), start.max.vector_add(&offset));
+            mesh.triangles_near(&start.merge(&end), target_transform)
+                .into_iter()
+                .filter_map(|index| {
+                    cast(
+                        moving,
+                        transform,
+                        direction,
+                        &mesh.triangle(index),
+                        target_transform,
+                        max_toi,
+                    )
+                })
+                .min_by(|one, two| one.toi.partial_cmp(&two.toi).expect("finite times"))
+        }
+    }
+}
+
+/// Returns true if a convex shape overlaps any shape.
+pub(crate) fn overlaps<F: num_traits::Float, S: SupportMap<F>>(
+    shape: &S,
+    transform: &Matrix4<F>,
+    target: &Shape<F>,
+    target_transform: &Matrix4<F>,
+) -> bool {
+    match target {
+        Shape::Sphere(other) => intersects(shape, transform, other, target_transform),
+        Shape::Cuboid(other) => intersects(shape, transform, other, target_transform),
+        Shape::Capsule(other) => intersects(shape, transform, other, target_transform),
+        Shape::ConvexHull(other) => intersects(shape, transform, other, target_transform),
+        Shape::Compound(compound) => compound.children.iter().any(|child| {
+            overlaps(
+                shape,
+                transform,
+                &child.shape,
+                &target_transform.matrix_mul(&child.offset),
+            )
+        }),
+        Shape::TriMesh(mesh) => mesh
+            .triangles_near(&support_aabb(shape, transform), target_transform)
+            .into_iter()
+            .any(|index| intersects(shape, transform, &mesh.triangle(index), target_transform)),
+    }
+}
+
+/// Returns the point of any shape closest to the given point, or `None` if the point is inside it.
+/// Triangle meshes are hollow, so points are never inside them.
+pub(crate) fn closest_point<F: num_traits::Float>(
+    point: &Vector3<F>,
+    target: &Shape<F>,
+    target_transform: &Matrix4<F>,
+) -> Option<Vector3<F>> {
+    match target {
+        Shape::Sphere(other) => closest_convex_point(point, other, target_transform),
+        Shape::Cuboid(other) => closest_convex_point(point, other, target_transform),
+        Shape::Capsule(other) => closest_convex_point(point, other, target_transform),
+        Shape::ConvexHull(other) => closest_convex_point(point, other, target_transform),
+        Shape::Compound(compound) => {
+            let mut best: Option<(F, Vector3<F>)> = None;
+            for child in compound.children.iter() {
+                let child_transform = target_transform.matrix_mul(&child.offset);
+                let candidate = closest_point(point, &child.shape, &child_transform)?;
+                let distance = candidate.vector_sub(point).squared_magnitude();
+                if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                    best = Some((distance, candidate));
+                }
+            }
+            best.map(|(_, candidate)| candidate)
+        }
+        Shape::TriMesh(mesh) => Some(closest_mesh_point(point, mesh, target_transform)),
+    }
+}
+
+/// Computes whether two shapes of any kind overlap and, if they don't, their distance and
+/// closest points.
+///
+/// # Remarks
+/// Convex shapes are compared with GJK. Compound shapes are split into their children, and
+/// triangle meshes into the triangles near the other shape, keeping the closest pair found.
+/// Triangle meshes are hollow, so shapes inside them don't overlap them.
+pub fn shape_distance<F: num_traits::Float>(
+    one: &Shape<F>,
+    one_transform: &Matrix4<F>,
+    two: &Shape<F>,
+    two_transform: &Matrix4<F>,
+) -> GjkResult<F> {
+    match one {
+        Shape::Sphere(shape) => convex_distance(shape, one_transform, two, two_transform),
+        Shape::Cuboid(shape) => convex_distance(shape, one_transform, two, two_transform),
+        Shape::Capsule(shape) => convex_distance(shape, one_transform, two, two_transform),
+        Shape::ConvexHull(shape) => convex_distance(shape, one_transform, two, two_transform),
+        Shape::Compound(compound) => closest_of(compound.children.iter().map(|child| {
+            let child_transform = one_transform.matrix_mul(&child.offset);
+            shape_distance(&child.shape, &child_transform, two, two_transform)
+        })),
+        Shape::TriMesh(mesh) => closest_of((0..mesh.triangles().len()).map(|index| {
+            convex_distance(&mesh.triangle(index), one_transform, two, two_transform)
+        })),
+    }
+}
+
+/// Computes the distance and closest points between a convex shape and a shape of any kind.
+fn convex_distance<F: num_traits::Float, S: SupportMap<F>>(
+    shape: &S,
+    transform: &Matrix4<F>,
+    target: &Shape<F>,
+    target_transform: &Matrix4<F>,
+) -> GjkResult<F> {
+    match target {
+        Shape::Sphere(other) => closest_points(shape, transform, other, target_transform),
+        Shape::Cuboid(other) => closest_points(shape, transform, other, target_transform),
+        Shape::Capsule(other) => closest_points(shape, transform, other, target_transform),
+        Shape::ConvexHull(other) => closest_points(shape, transform, other, target_transform),
+        Shape::Compound(compound) => closest_of(compound.children.iter().map(|child| {
+            let child_transform = target_transform.matrix_mul(&child.offset);
+            convex_distance(shape, transform, &child.shape, &child_transform)
+        })),
+        Shape::TriMesh(mesh) => {
+            // The first triangle bounds the distance, so only the triangles within that
+            // distance of the shape are searched afterwards.
+            let first = closest_points(shape, transform, &mesh.triangle(0), target_transform);
+            let reach = match first {
+                GjkResult::Intersecting => return first,
+                GjkResult::Separated { distance, .. } => distance,
+            };
+            let region = support_aabb(shape, transform).loosened(reach);
+            let near = mesh.triangles_near(&region, target_transform);
+            closest_of(std::iter::once(first).chain(near.into_iter().map(|index| {
+                closest_points(shape, transform, &mesh.triangle(index), target_transform)
+            })))
+        }
+    }
+}
+
+/// Returns the closest of the given results, or `Intersecting` if any of them is.
+/// Nothing is infinitely far away from nothing.
+fn closest_of<F: num_traits::Float, I: Iterator<Item = GjkResult<F>>>(results: I) -> GjkResult<F> {
+    let mut closest = GjkResult::Separated {
+        distance: F::infinity(),
+        point_one: Vector3::origin(),
+        point_two: Vector3::origin(),
+    };
+    for result in results {
+        match (result, closest) {
+            (GjkResult::Intersecting, _) => return result,
+            (
+                GjkResult::Separated { distance, .. },
+                GjkResult::Separated {
+                    distance: best_distance,
+                    ..
+                },
+            ) if distance < best_distance => closest = result,
+            _ => {}
+        }
+    }
+    closest
+}
+
+/// Generates the contact between two shapes of any kind, if they overlap or are closer than
+/// the given prediction distance.
+///
+/// # Remarks
+/// This runs the same narrow phase as the worlds, so pairs of shapes it doesn't generate
+/// contacts for yet (e.g. convex hulls) never are in contact.
+pub fn contact<F: num_traits::Float>(
+    one: &Shape<F>,
+    one_transform: &Matrix4<F>,
+    two: &Shape<F>,
+    two_transform: &Matrix4<F>,
+    prediction: F,
+) -> Option<ShapeContact<F>> {
+    let mut data = CollisionData::new(MAX_CONTACT_POINTS);
+    data.margin = prediction;
+    collide_shapes((0, one, one_transform), (1, two, two_transform), &mut data);
+
+    let mut points: Vec<(Vector3<F>, F)> = data
+        .contacts
+        .iter()
+        .map(|contact| (contact.contact_point, contact.penetration))
+        .collect();
+    points.sort_by(|one, two| two.1.partial_cmp(&one.1).expect("finite depths"));
+    let deepest = data.contacts.iter().max_by(|one, two| {
+        one.penetration
+            .partial_cmp(&two.penetration)
+            .expect("finite depths")
+    })?;
+    Some(ShapeContact {
+        normal: deepest.contact_normal,
+        penetration: deepest.penetration,
+        points,
+    })
+}
+
+/// Returns the point on the surface of any shape closest to the given point, and true if the
+/// point is inside the shape.
+pub(crate) fn project_point<F: num_traits::Float>(
+    point: &Vector3<F>,
+    target: &Shape<F>,
+    target_transform: &Matrix4<F>,
+) -> (Vector3<F>, bool) {
+    match closest_point(point, target, target_transform) {
+        Some(closest) => (closest, false),
+        None => (surface_point(point, target, target_transform), true),
+    }
+}
+
+/// Returns the point on the surface of a shape closest to a point inside it.
+///
+/// # Remarks
+/// Points inside compound shapes leave them through the closest surface of the children they're
+/// in, which may still be inside another child.
+fn surface_point<F: num_traits::Float>(
+    point: &Vector3<F>,
+    target: &Shape<F>,
+    transform: &Matrix4<F>,
+) -> Vector3<F> {
+    let local = transform.transform_inverse(point);
+    let surface = match target {
+        Shape::Sphere(sphere) => outward(&local).scalar_mul(sphere.radius),
+        Shape::Cuboid(cuboid) => {
+            let coordinates = [local.x, local.y, local.z];
+            let half_size = [cuboid.half_size.x, cuboid.half_size.y, cuboid.half_size.z];
+            let mut closest = coordinates;
+            let axis = (0..3)
+                .min_by(|&one, &two| {
+                    let depth = |axis: usize| half_size[axis] - coordinates[axis].abs();
+                    depth(one).partial_cmp(&depth(two)).expect("finite depths")
+                })
+                .expect("three axes");
+            closest[axis] = if coordinates[axis] < F::zero() {
+                -half_size[axis]
+            } else {
+                half_size[axis]
+            };
+            Vector3::new(closest[0], closest[1], closest[2])
+        }
+        Shape::Capsule(capsule) => {
+            let height = local.y.max(-capsule.half_height).min(capsule.half_height);
+            let center = Vector3::new(F::zero(), height, F::zero());
+            center.vector_add(&outward(&local.vector_sub(&center)).scalar_mul(capsule.radius))
+        }
+        Shape::ConvexHull(hull) => {
+            let distance = |face: &HullFace<F>| face.normal.dot_product(&local) - face.offset;
+            let face = hull
+                .faces
+                .iter()
+                .max_by(|one, two| {
+                    distance(one)
+                        .partial_cmp(&distance(two))
+                        .expect("finite distances")
+                })
+                .expect("hulls have faces");
+            local.vector_sub(&face.normal.scalar_mul(distance(face)))
+        }
+        Shape::Compound(compound) => {
+            return compound
+                .children
+                .iter()
+                .filter_map(|child| {
+                    let child_transform = transform.matrix_mul(&child.offset);
+                    match project_point(point, &child.shape, &child_transform) {
+                        (closest, true) => Some(closest),
+                        (_, false) => None,
+                    }
+                })
+                .min_by(|one, two| {
+                    let distance =
+                        |candidate: &Vector3<F>| candidate.vector_sub(point).squared_magnitude();
+                    distance(one)
+                        .partial_cmp(&distance(two))
+                        .expect("finite distances")
+                })
+                .unwrap_or(*point);
+        }
+        Shape::TriMesh(mesh) => return closest_mesh_point(point, mesh, transform),
+    };
+    transform.transform(&surface)
+}
+
+/// Returns the unit direction of a local offset from the center of a shape, or the local
+/// y axis at the center, where every direction is as good.
+fn outward<F: num_traits::Float>(offset: &Vector3<F>) -> Vector3<F> {
+    if offset.squared_magnitude() > F::zero() {
+        offset.normalize()
+    } else {
+        Vector3::new(F::zero(), F::one(), F::zero())
+    }
+}
+
+/// Returns the point of a triangle mesh closest to the given point.
+///
+/// # Remarks
+/// The first triangle gives an upper bound of the distance, and only the triangles within that
+/// distance of the point are searched afterwards.
+fn closest_mesh_point<F: num_traits::Float>(
+    point: &Vector3<F>,
+    mesh: &TriMesh<F>,
+    transform: &Matrix4<F>,
+) -> Vector3<F> {
+    let local = transform.transform_inverse(point);
+    let mut best = mesh.triangle(0).closest_point(&local);
+    let mut best_distance = best.vector_sub(&local).squared_magnitude();
+    let reach = best_distance.sqrt();
+    let region = Aabb::from_center(&local, &Vector3::new(reach, reach, reach));
+    mesh.bvh().query_aabb(&region, |index| {
+        let candidate = mesh.triangle(index).closest_point(&local);
+        let distance = candidate.vector_sub(&local).squared_magnitude();
+        if distance < best_distance {
+            best = candidate;
+            best_distance = distance;
+        }
+        true
+    });
+    transform.transform(&best)
+}
+
+/// Returns the point of a convex shape closest to the given point, or `None` if the point is inside it.
+fn closest_convex_point<F: num_traits::Float, S: SupportMap<F>>(
+    point: &Vector3<F>,
+    shape: &S,
+    transform: &Matrix4<F>,
+) -> Option<Vector3<F>> {
+    let probe = Matrix4::from_orientation_and_position(&Quaternion::identity(), point);
+    match closest_points(&Sphere::new(F::zero()), &probe, shape, transform) {
+        GjkResult::Intersecting => None,
+        GjkResult::Separated { point_two, .. } => Some(point_two),
+    }
+}
+
+/// Sweeps a convex shape along the given direction against the half-space behind a plane,
+/// returning the first contact before the given maximum time of impact, if any.
+pub(crate) fn cast_against_plane<F: num_traits::Float, S: SupportMap<F>>(
+    moving: &S,
+    transform: &Matrix4<F>,
+    direction: &Vector3<F>,
+    plane: &Plane<F>,
+    max_toi: F,
+) -> Option<CastHit<F>> {
+    // The deepest point of the shape is the first one to touch the plane.
+    let deepest = moving.support_point(transform, &plane.normal.invert());
+    let distance = plane.signed_distance(&deepest);
+    let toi = if distance <= num_traits::zero() {
+        num_traits::zero()
+    } else {
+        let speed = -plane.normal.dot_product(direction);
+        if speed <= num_traits::zero() {
+            return None;
+        }
+        distance / speed
+    };
+
+    if toi > max_toi {
+        return None;
+    }
+    Some(CastHit {
+        toi,
+        point: deepest.vector_add(&direction.scalar_mul(toi)),
+        normal: plane.normal,
+    })
+}
+
+/// Returns the bounding box of a convex shape placed with the given transform,
+/// from its support points along the world axes.
+pub(crate) fn support_aabb<F: num_traits::Float, S: SupportMap<F>>(
+    shape: &S,
+    transform: &Matrix4<F>,
+) -> Aabb<F> {
+    let axes = [
+        Vector3::new(F::one(), F::zero(), F::zero()),
+        Vector3::new(F::zero(), F::one(), F::zero()),
+        Vector3::new(F::zero(), F::zero(), F::one()),
+    ];
+    let mut aabb = Aabb::new(transform.translation(), transform.translation());
+    for axis in axes.iter() {
+        aabb.inplace_merge_point(&shape.support_point(transform, axis));
+        aabb.inplace_merge_point(&shape.support_point(transform, &axis.invert()));
+    }
+    aabb
+}
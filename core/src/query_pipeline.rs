@@ -0,0 +1,582 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::broad_phase::BroadPhase;
+use crate::bvh::DynamicBvh;
+use crate::collider::Collider;
+use crate::gjk::GjkResult;
+use crate::plane::Plane;
+use crate::query::{
+    cast_against, cast_against_plane, overlaps, project_point, shape_distance, support_aabb,
+    PointProjection, QueryFilter, RayHit, ShapeCastHit,
+};
+use crate::ray::Ray;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Cuboid, Shape, Sphere, SupportMap};
+use crate::world::{World, WorldState};
+use math::{Matrix4, Quaternion, Vector3};
+
+/// Acceleration structure used to cast rays and shapes against colliders and scenery planes,
+/// and to find the colliders overlapping a region, independently of any world.
+///
+/// # Remarks
+/// The pipeline keeps its own copy of the colliders and planes, placed as they were the last
+/// time it was updated, so it can be queried while a world steps, or against a snapshot of
+/// it, and is only as fresh as its last update. Colliders are identified by their index in
+/// the world or state it was updated with. The copies share the geometry of hulls, compounds
+/// and meshes with the colliders they were made from.
+#[derive(Clone, Debug, Default)]
+pub struct QueryPipeline<F: num_traits::Float = f64> {
+    colliders: Vec<Collider<Shape<F>, F>>,
+    poses: Vec<Matrix4<F>>,
+    planes: Vec<Plane<F>>,
+    broad_phase: DynamicBvh<F>,
+}
+
+impl<F: num_traits::Float> QueryPipeline<F> {
+    /// Creates a new empty query pipeline.
+    pub fn new() -> Self {
+        Self {
+            colliders: Vec::new(),
+            poses: Vec::new(),
+            planes: Vec::new(),
+            broad_phase: DynamicBvh::default(),
+        }
+    }
+
+    /// Creates a new query pipeline with the colliders and planes of the given world.
+    pub fn from_world(world: &World<F>) -> Self {
+        let mut pipeline = Self::new();
+        pipeline.update_with_world(world);
+        pipeline
+    }
+
+    /// Updates the pipeline with the colliders and planes of the given world, placed with
+    /// the current transforms of their bodies.
+    pub fn update_with_world(&mut self, world: &World<F>) {
+        self.update(&world.bodies, &world.colliders, &world.planes);
+    }
+
+    /// Updates the pipeline with the colliders and planes of the given world state, placed
+    /// with the transforms their bodies had when it was captured.
+    pub fn update_with_state(&mut self, state: &WorldState<F>) {
+        self.update(state.bodies(), state.colliders(), state.planes());
+    }
+
+    /// Updates the pipeline with the given colliders and planes, placing the colliders with
+    /// the transforms of the given bodies.
+    ///
+    /// # Remarks
+    /// Only the colliders whose body, offset or shape changed since the last update are
+    /// placed again, and the ones that barely moved don't need to be moved in the
+    /// acceleration structure, so updating every frame is cheaper than building it again.
+    pub fn update(
+        &mut self,
+        bodies: &[RigidBody<F>],
+        colliders: &[Collider<Shape<F>, F>],
+        planes: &[Plane<F>],
+    ) {
+        for index in colliders.len()..self.colliders.len() {
+            self.broad_phase.remove(index);
+        }
+        self.colliders.truncate(colliders.len());
+        self.poses.truncate(colliders.len());
+        for (index, collider) in colliders.iter().enumerate() {
+            let pose = &bodies[collider.body].transform_matrix;
+            match self.colliders.get_mut(index) {
+                Some(copy)
+                    if copy.body == collider.body
+                        && copy.offset == collider.offset
+                        && copy.shape.shares(&collider.shape)
+                        && self.poses[index] == *pose =>
+                {
+                    copy.clone_properties_from(collider);
+                    continue;
+                }
+                Some(copy) => {
+                    copy.clone_from(collider);
+                    self.poses[index] = *pose;
+                }
+                None => {
+                    self.colliders.push(collider.clone());
+                    self.poses.push(*pose);
+                }
+            }
+            let collider = &mut self.colliders[index];
+            collider.calculate_internals(bodies);
+            let aabb = collider.shape.aabb(collider.transform());
+            self.broad_phase.update(index, &aabb);
+        }
+        self.planes.clear();
+        self.planes.extend_from_slice(planes);
+    }
+
+    /// Returns the colliders of the pipeline, as placed the last time it was updated.
+    pub fn colliders(&self) -> &[Collider<Shape<F>, F>] {
+        &self.colliders
+    }
+
+    /// Returns the scenery planes of the pipeline.
+    pub fn planes(&self) -> &[Plane<F>] {
+        &self.planes
+    }
+
+    /// Returns a view of the pipeline to query it.
+    fn view(&self) -> QueryView<'_, F> {
+        QueryView {
+            colliders: &self.colliders,
+            broad_phase: &self.broad_phase,
+            planes: &self.planes,
+        }
+    }
+
+    /// Casts a ray against the colliders and scenery planes passing the filter,
+    /// returning the closest hit before the given maximum time of impact, if any.
+    pub fn raycast(&self, ray: &Ray<F>, max_toi: F, filter: &QueryFilter<F>) -> Option<RayHit<F>> {
+        self.view().raycast(ray, max_toi, filter)
+    }
+
+    /// Casts a ray against the colliders and scenery planes passing the filter,
+    /// returning every hit before the given maximum time of impact, closest first.
+    ///
+    /// # Remarks
+    /// Each collider is hit at most once, where the ray enters it.
+    pub fn raycast_all(&self, ray: &Ray<F>, max_toi: F, filter: &QueryFilter<F>) -> Vec<RayHit<F>> {
+        self.view().raycast_all(ray, max_toi, filter)
+    }
+
+    /// Sweeps a convex shape, placed with the given transform, along the given direction
+    /// against the colliders and scenery planes passing the filter, returning the first hit
+    /// before the given maximum distance, if any.
+    ///
+    /// # Remarks
+    /// Shapes overlapping something from the start hit it at distance `0`.
+    pub fn shape_cast<S: SupportMap<F>>(
+        &self,
+        shape: &S,
+        transform: &Matrix4<F>,
+        direction: &Vector3<F>,
+        max_distance: F,
+        filter: &QueryFilter<F>,
+    ) -> Option<ShapeCastHit<F>> {
+        self.view()
+            .shape_cast(shape, transform, direction, max_distance, filter)
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the given
+    /// bounding box, in ascending order.
+    pub fn intersections_with_aabb(&self, aabb: &Aabb<F>, filter: &QueryFilter<F>) -> Vec<usize> {
+        self.view().intersections_with_aabb(aabb, filter)
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the sphere
+    /// with the given center and radius, in ascending order.
+    pub fn intersections_with_sphere(
+        &self,
+        center: &Vector3<F>,
+        radius: F,
+        filter: &QueryFilter<F>,
+    ) -> Vec<usize> {
+        self.view()
+            .intersections_with_sphere(center, radius, filter)
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the convex shape
+    /// placed with the given transform, in ascending order.
+    pub fn intersections_with_shape<S: SupportMap<F>>(
+        &self,
+        shape: &S,
+        transform: &Matrix4<F>,
+        filter: &QueryFilter<F>,
+    ) -> Vec<usize> {
+        self.view()
+            .intersections_with_shape(shape, transform, filter)
+    }
+    /// Projects a point onto the closest collider or scenery plane passing the filter, if any.
+    ///
+    /// # Remarks
+    /// Points inside something are at distance zero from it, and are projected onto its
+    /// closest surface.
+    pub fn project_point(
+        &self,
+        point: &Vector3<F>,
+        filter: &QueryFilter<F>,
+    ) -> Option<PointProjection<F>> {
+        self.view().project_point(point, filter)
+    }
+    /// Computes whether the colliders with the given indices overlap and, if they don't,
+    /// their distance and closest points, as placed the last time the pipeline was updated.
+    pub fn distance(&self, one: usize, two: usize) -> GjkResult<F> {
+        self.view().distance(one, two)
+    }
+}
+
+/// Borrowed colliders, scenery planes and acceleration structure to query, shared by the
+/// worlds and the query pipelines.
+pub(crate) struct QueryView<'a, F: num_traits::Float> {
+    pub colliders: &'a [Collider<Shape<F>, F>],
+    pub broad_phase: &'a DynamicBvh<F>,
+    pub planes: &'a [Plane<F>],
+}
+
+impl<'a, F: num_traits::Float> QueryView<'a, F> {
+    /// Casts a ray against the colliders and scenery planes passing the filter,
+    /// returning the closest hit before the given maximum time of impact, if any.
+    pub fn raycast(&self, ray: &Ray<F>, max_toi: F, filter: &QueryFilter<F>) -> Option<RayHit<F>> {
+        let mut closest = self.raycast_planes(ray, max_toi, filter).min_by(by_toi);
+        let max_toi = closest.map_or(max_toi, |hit| hit.toi);
+        self.broad_phase.cast_ray(ray, max_toi, |index, max_toi| {
+            match self.raycast_collider(index, ray, max_toi, filter) {
+                Some(hit) => {
+                    closest = Some(hit);
+                    hit.toi
+                }
+                None => max_toi,
+            }
+        });
+        closest
+    }
+
+    /// Casts a ray against the colliders and scenery planes passing the filter,
+    /// returning every hit before the given maximum time of impact, closest first.
+    ///
+    /// # Remarks
+    /// Each collider is hit at most once, where the ray enters it.
+    pub fn raycast_all(&self, ray: &Ray<F>, max_toi: F, filter: &QueryFilter<F>) -> Vec<RayHit<F>> {
+        let mut hits: Vec<RayHit<F>> = self.raycast_planes(ray, max_toi, filter).collect();
+        self.broad_phase.cast_ray(ray, max_toi, |index, max_toi| {
+            hits.extend(self.raycast_collider(index, ray, max_toi, filter));
+            max_toi
+        });
+        hits.sort_by(by_toi);
+        hits
+    }
+
+    /// Sweeps a convex shape, placed with the given transform, along the given direction
+    /// against the colliders and scenery planes passing the filter, returning the first hit
+    /// before the given maximum distance, if any.
+    ///
+    /// # Remarks
+    /// Shapes overlapping something from the start hit it at distance `0`.
+    pub fn shape_cast<S: SupportMap<F>>(
+        &self,
+        shape: &S,
+        transform: &Matrix4<F>,
+        direction: &Vector3<F>,
+        max_distance: F,
+        filter: &QueryFilter<F>,
+    ) -> Option<ShapeCastHit<F>> {
+        if direction.squared_magnitude() <= num_traits::zero() {
+            return None;
+        }
+        let direction = direction.normalize();
+        self.shape_hits(shape, transform, &direction, max_distance, filter)
+            .into_iter()
+            .min_by(|one, two| {
+                one.distance
+                    .partial_cmp(&two.distance)
+                    .expect("finite distances")
+            })
+    }
+
+    /// Sweeps a convex shape along the given unit direction against the colliders and
+    /// scenery planes passing the filter, returning every one of them hit before the given
+    /// maximum distance.
+    fn shape_hits<S: SupportMap<F>>(
+        &self,
+        shape: &S,
+        transform: &Matrix4<F>,
+        direction: &Vector3<F>,
+        max_distance: F,
+        filter: &QueryFilter<F>,
+    ) -> Vec<ShapeCastHit<F>> {
+        let planes = self
+            .planes
+            .iter()
+            .filter(|_| !filter.exclude_scenery)
+            .filter_map(|plane| {
+                let hit = cast_against_plane(shape, transform, direction, plane, max_distance)?;
+                Some((None, hit))
+            });
+
+        // Only the colliders overlapping the region swept by the shape can be hit.
+        let start = support_aabb(shape, transform);
+        let offset = direction.scalar_mul(max_distance);
+        let end = Aabb::new(start.min.vector_add(&offset), start.max.vector_add(&offset));
+        let candidates = self.broad_phase.intersecting(&start.merge(&end));
+        let colliders = candidates.into_iter().filter_map(|index| {
+            let collider = &self.colliders[index];
+            if !filter.test(index, collider) {
+                return None;
+            }
+            let hit = cast_against(
+                shape,
+                transform,
+                direction,
+                &collider.shape,
+                collider.transform(),
+                max_distance,
+            )?;
+            Some((Some(index), hit))
+        });
+
+        planes
+            .chain(colliders)
+            .map(|(collider, hit)| ShapeCastHit {
+                collider,
+                body: collider.map(|index| self.colliders[index].body),
+                point: hit.point,
+                normal: hit.normal,
+                distance: hit.toi,
+            })
+            .collect()
+    }
+
+    /// Sweeps any shape along the given unit direction, collecting its hits.
+    pub(crate) fn sweep(
+        &self,
+        shape: &Shape<F>,
+        transform: &Matrix4<F>,
+        direction: &Vector3<F>,
+        max_distance: F,
+        filter: &QueryFilter<F>,
+        hits: &mut Vec<ShapeCastHit<F>>,
+    ) {
+        match shape {
+            Shape::Sphere(shape) => {
+                hits.extend(self.shape_hits(shape, transform, direction, max_distance, filter))
+            }
+            Shape::Cuboid(shape) => {
+                hits.extend(self.shape_hits(shape, transform, direction, max_distance, filter))
+            }
+            Shape::Capsule(shape) => {
+                hits.extend(self.shape_hits(shape, transform, direction, max_distance, filter))
+            }
+            Shape::ConvexHull(shape) => {
+                hits.extend(self.shape_hits(shape, transform, direction, max_distance, filter))
+            }
+            Shape::TriMesh(mesh) => {
+                for index in 0..mesh.triangles().len() {
+                    let triangle = mesh.triangle(index);
+                    hits.extend(self.shape_hits(
+                        &triangle,
+                        transform,
+                        direction,
+                        max_distance,
+                        filter,
+                    ))
+                }
+            }
+            Shape::Compound(compound) => {
+                for child in compound.children.iter() {
+                    let child_transform = transform.matrix_mul(&child.offset);
+                    self.sweep(
+                        &child.shape,
+                        &child_transform,
+                        direction,
+                        max_distance,
+                        filter,
+                        hits,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the given
+    /// bounding box, in ascending order.
+    pub fn intersections_with_aabb(&self, aabb: &Aabb<F>, filter: &QueryFilter<F>) -> Vec<usize> {
+        let transform =
+            Matrix4::from_orientation_and_position(&Quaternion::identity(), &aabb.center());
+        self.intersections_with_shape(&Cuboid::new(aabb.half_extents()), &transform, filter)
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the sphere
+    /// with the given center and radius, in ascending order.
+    pub fn intersections_with_sphere(
+        &self,
+        center: &Vector3<F>,
+        radius: F,
+        filter: &QueryFilter<F>,
+    ) -> Vec<usize> {
+        let transform = Matrix4::from_orientation_and_position(&Quaternion::identity(), center);
+        self.intersections_with_shape(&Sphere::new(radius), &transform, filter)
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the convex shape
+    /// placed with the given transform, in ascending order.
+    pub fn intersections_with_shape<S: SupportMap<F>>(
+        &self,
+        shape: &S,
+        transform: &Matrix4<F>,
+        filter: &QueryFilter<F>,
+    ) -> Vec<usize> {
+        let mut intersections: Vec<usize> = self
+            .broad_phase
+            .intersecting(&support_aabb(shape, transform))
+            .into_iter()
+            .filter(|index| {
+                let collider = &self.colliders[*index];
+                filter.test(*index, collider)
+                    && overlaps(shape, transform, &collider.shape, collider.transform())
+            })
+            .collect();
+        intersections.sort_unstable();
+        intersections
+    }
+
+    /// Projects a point onto the closest collider or scenery plane passing the filter, if any.
+    ///
+    /// # Remarks
+    /// Points inside something are at distance zero from it, and are projected onto its
+    /// closest surface.
+    pub fn project_point(
+        &self,
+        point: &Vector3<F>,
+        filter: &QueryFilter<F>,
+    ) -> Option<PointProjection<F>> {
+        let distance = |projection: &PointProjection<F>| {
+            if projection.is_inside {
+                F::zero()
+            } else {
+                projection.point.vector_sub(point).magnitude()
+            }
+        };
+        let mut closest = self
+            .planes
+            .iter()
+            .filter(|_| !filter.exclude_scenery)
+            .map(|plane| {
+                let height = plane.signed_distance(point);
+                PointProjection {
+                    collider: None,
+                    body: None,
+                    point: point.vector_sub(&plane.normal.scalar_mul(height)),
+                    is_inside: height < F::zero(),
+                }
+            })
+            .min_by(|one, two| {
+                distance(one)
+                    .partial_cmp(&distance(two))
+                    .expect("finite distances")
+            });
+
+        // Any collider bounds the distance to the closest one, so only the colliders within
+        // that distance of the point need to be searched.
+        if closest.is_none() {
+            closest = (0..self.colliders.len())
+                .find(|index| {
+                    self.broad_phase.fat_aabb(*index).is_some()
+                        && filter.test(*index, &self.colliders[*index])
+                })
+                .map(|index| self.project_point_on_collider(index, point));
+        }
+        let mut best = closest.as_ref().map(distance)?;
+        let region = Aabb::from_center(point, &Vector3::new(best, best, best));
+        self.broad_phase.query_aabb(&region, |index| {
+            if filter.test(index, &self.colliders[index]) {
+                let projection = self.project_point_on_collider(index, point);
+                if distance(&projection) < best {
+                    best = distance(&projection);
+                    closest = Some(projection);
+                }
+            }
+            true
+        });
+        closest
+    }
+
+    /// Computes whether two of the colliders overlap and, if they don't, their distance and
+    /// closest points.
+    pub fn distance(&self, one: usize, two: usize) -> GjkResult<F> {
+        let (one, two) = (&self.colliders[one], &self.colliders[two]);
+        shape_distance(&one.shape, one.transform(), &two.shape, two.transform())
+    }
+
+    /// Projects a point onto one of the colliders.
+    fn project_point_on_collider(&self, index: usize, point: &Vector3<F>) -> PointProjection<F> {
+        let collider = &self.colliders[index];
+        let (closest, is_inside) = project_point(point, &collider.shape, collider.transform());
+        PointProjection {
+            collider: Some(index),
+            body: Some(collider.body),
+            point: closest,
+            is_inside,
+        }
+    }
+
+    /// Casts a ray against one of the colliders, if it passes the filter.
+    fn raycast_collider(
+        &self,
+        index: usize,
+        ray: &Ray<F>,
+        max_toi: F,
+        filter: &QueryFilter<F>,
+    ) -> Option<RayHit<F>> {
+        let collider = &self.colliders[index];
+        if !filter.test(index, collider) {
+            return None;
+        }
+
+        let hit = cast_against(
+            &Sphere::new(num_traits::zero()),
+            &ray_transform(ray),
+            &ray.direction,
+            &collider.shape,
+            collider.transform(),
+            max_toi,
+        )?;
+        Some(RayHit {
+            collider: Some(index),
+            body: Some(collider.body),
+            point: hit.point,
+            normal: hit.normal,
+            toi: hit.toi,
+            distance: hit.toi * ray.direction.magnitude(),
+        })
+    }
+
+    /// Casts a ray against the scenery planes, if they pass the filter.
+    fn raycast_planes<'b>(
+        &'b self,
+        ray: &'b Ray<F>,
+        max_toi: F,
+        filter: &QueryFilter<F>,
+    ) -> impl Iterator<Item = RayHit<F>> + 'b {
+        let exclude_scenery = filter.exclude_scenery;
+        self.planes
+            .iter()
+            .filter(move |_| !exclude_scenery)
+            .filter_map(move |plane| {
+                let hit = cast_against_plane(
+                    &Sphere::new(num_traits::zero()),
+                    &ray_transform(ray),
+                    &ray.direction,
+                    plane,
+                    max_toi,
+                )?;
+                Some(RayHit {
+                    collider: None,
+                    body: None,
+                    point: hit.point,
+                    normal: hit.normal,
+                    toi: hit.toi,
+                    distance: hit.toi * ray.direction.magnitude(),
+                })
+            })
+    }
+}
+
+/// Returns the transform placing a point at the origin of the ray.
+fn ray_transform<F: num_traits::Float>(ray: &Ray<F>) -> Matrix4<F> {
+    Matrix4::from_orientation_and_position(&Quaternion::identity(), &ray.origin)
+}
+
+/// Orders ray hits by their time of impact.
+fn by_toi<F: num_traits::Float>(one: &RayHit<F>, two: &RayHit<F>) -> std::cmp::Ordering {
+    one.toi.partial_cmp(&two.toi).expect("finite times")
+}
@@ -0,0 +1,134 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::gravity::{GravityField, GravitySource};
+use crate::plane::Plane;
+use crate::query::QueryFilter;
+use crate::query_pipeline::*;
+use crate::ray::Ray;
+use crate::rigid_body::RigidBody;
+use crate::shape::{ConvexHull, Cuboid, Shape, Sphere};
+use crate::world::World;
+use math::{Matrix3, Vector3};
+use std::sync::Arc;
+
+/// Returns a world without gravity with a ball moving away from the origin along x,
+/// a crate further along and a floor below them.
+fn world() -> World {
+    let mut world = World::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::origin()));
+    let mut ball = RigidBody::new(Vector3::new(5.0, 0.0, 0.0), 1.0, &Matrix3::identity());
+    ball.velocity = Vector3::new(60.0, 0.0, 0.0);
+    let ball = world.add_body(ball);
+    let ball = world.body_index(ball).unwrap();
+    world.add_collider(Collider::new(ball, Shape::Sphere(Sphere::new(1.0))));
+    let crate_ = world.add_body(RigidBody::new(
+        Vector3::new(10.0, 4.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let crate_ = world.body_index(crate_).unwrap();
+    world.add_collider(Collider::new(
+        crate_,
+        Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 1.0, 1.0))),
+    ));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), -2.0));
+    world
+}
+
+#[test]
+fn queries() {
+    let mut world = world();
+    let filter = QueryFilter::new();
+    let ray = Ray::new(Vector3::origin(), Vector3::new(1.0, 0.0, 0.0));
+    let down = Ray::new(Vector3::new(10.0, 10.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+
+    // The pipeline places the colliders itself, without updating the world.
+    let mut pipeline = QueryPipeline::from_world(&world);
+    assert_eq!(2, pipeline.colliders().len());
+    assert_eq!(1, pipeline.planes().len());
+    let hit = pipeline.raycast(&ray, 100.0, &filter).unwrap();
+    assert_eq!(Some(0), hit.collider);
+    assert!((hit.toi - 4.0).abs() < 1e-4);
+    let hits = pipeline.raycast_all(&down, 100.0, &filter);
+    assert_eq!(
+        vec![Some(1), None],
+        hits.iter().map(|hit| hit.collider).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![0],
+        pipeline.intersections_with_sphere(&Vector3::new(5.0, 0.0, 0.0), 0.5, &filter)
+    );
+
+    // It's only as fresh as its last update.
+    let state = world.snapshot();
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+    let hit = pipeline.raycast(&ray, 100.0, &filter).unwrap();
+    assert!((hit.toi - 4.0).abs() < 1e-4);
+    pipeline.update_with_world(&world);
+    let hit = pipeline.raycast(&ray, 100.0, &filter).unwrap();
+    assert!((hit.toi - 5.0).abs() < 1e-2);
+
+    // Worlds place their colliders before moving the bodies, so they lag a step behind.
+    assert!((world.raycast(&ray, 100.0, &filter).unwrap().toi - 4.0).abs() < 1e-4);
+    world.update_colliders();
+    assert_eq!(hit, world.raycast(&ray, 100.0, &filter).unwrap());
+
+    // Snapshots can be queried as they were when captured.
+    pipeline.update_with_state(&state);
+    let hit = pipeline.raycast(&ray, 100.0, &filter).unwrap();
+    assert!((hit.toi - 4.0).abs() < 1e-4);
+
+    // Colliders removed since the last update aren't hit anymore.
+    pipeline.update(state.bodies(), &state.colliders()[..1], &[]);
+    assert!(pipeline.raycast(&down, 100.0, &filter).is_none());
+    assert!(pipeline.raycast(&ray, 100.0, &filter).is_some());
+}
+
+#[test]
+fn incremental() {
+    let mut world = world();
+    let hull = ConvexHull::from_points(&Cuboid::new(Vector3::new(1.0, 1.0, 1.0)).vertices());
+    world.colliders_mut()[1].shape = Shape::convex_hull(hull.unwrap());
+    let mut pipeline = QueryPipeline::from_world(&world);
+    let down = Ray::new(Vector3::new(10.0, 10.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+    let filter = QueryFilter::new();
+
+    // The copies share the geometry of the colliders.
+    match (&world.colliders()[1].shape, &pipeline.colliders()[1].shape) {
+        (Shape::ConvexHull(one), Shape::ConvexHull(two)) => assert!(Arc::ptr_eq(one, two)),
+        _ => panic!("the collider should still be a hull"),
+    }
+
+    // Colliders that didn't move keep their placement, but take their new properties.
+    let placed = *pipeline.colliders()[1].transform();
+    world.colliders_mut()[1].with_groups(2, u32::MAX);
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+    pipeline.update_with_world(&world);
+    assert_eq!(placed, *pipeline.colliders()[1].transform());
+    assert!((pipeline.colliders()[0].position().x - 6.0).abs() < 1e-2);
+    let mut excluding = QueryFilter::new();
+    excluding.mask = 1;
+    assert_eq!(
+        Some(1),
+        pipeline.raycast(&down, 100.0, &filter).unwrap().collider
+    );
+    assert_eq!(
+        None,
+        pipeline.raycast(&down, 100.0, &excluding).unwrap().collider
+    );
+
+    // Colliders given other shapes are placed again.
+    world.colliders_mut()[1].shape = Shape::Sphere(Sphere::new(2.0));
+    pipeline.update_with_world(&world);
+    let hit = pipeline.raycast(&down, 100.0, &filter).unwrap();
+    assert!((hit.toi - 4.0).abs() < 1e-4);
+}
@@ -0,0 +1,122 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::hinge_joint::twist_angle;
+use crate::joint::{world_direction, RowBuilder};
+use crate::rigid_body::RigidBody;
+use crate::solver::tangent_basis;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Rack and pinion joint, coupling the rotation of a body around its axis, usually the axis
+/// of a hinge, with the translation of another one along its axis, usually the axis of a
+/// prismatic joint, like steering racks and jacks do.
+///
+/// # Remarks
+/// The second body (the rack) moves `ratio` units along its axis for every radian the first
+/// one (the pinion) turns around its own axis, so the ratio is the radius of the pinion.
+/// Negative ratios move the rack the other way. The joint doesn't hold the bodies in place,
+/// so it's combined with the hinge and prismatic joints that do.
+///
+/// The rotation and translation are measured relative to the world, from the first time the
+/// joint is solved, and accumulated over full turns.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RackAndPinionJoint<F: num_traits::Float = f64> {
+    /// Unit axis the pinion turns around, in its local space.
+    pub local_axis_one: Vector3<F>,
+
+    /// Unit axis the rack moves along, in its local space,
+    /// or in world space for joints with the scenery.
+    pub local_axis_two: Vector3<F>,
+
+    /// Distance moved by the rack for every radian turned by the pinion.
+    pub ratio: F,
+
+    local_reference_one: Vector3<F>,
+    references: Option<(Vector3<F>, Vector3<F>)>,
+    angle: F,
+    translation: F,
+}
+
+impl<F: num_traits::Float> RackAndPinionJoint<F> {
+    /// Creates a new rack and pinion joint around, and along, the given axes,
+    /// with the given ratio.
+    pub fn new(local_axis_one: Vector3<F>, local_axis_two: Vector3<F>, ratio: F) -> Self {
+        let local_axis_one = local_axis_one.normalize();
+        Self {
+            local_axis_one,
+            local_axis_two: local_axis_two.normalize(),
+            ratio,
+            local_reference_one: tangent_basis(&local_axis_one)[0],
+            references: None,
+            angle: num_traits::zero(),
+            translation: num_traits::zero(),
+        }
+    }
+
+    /// Creates a new rack and pinion joint between the given bodies, around, and along,
+    /// the given axes in world space.
+    pub fn from_world(
+        one: &RigidBody<F>,
+        two: Option<&RigidBody<F>>,
+        axis_one: &Vector3<F>,
+        axis_two: &Vector3<F>,
+        ratio: F,
+    ) -> Self {
+        let local_axis_two = match two {
+            Some(two) => two.direction_in_local_space(axis_two),
+            None => *axis_two,
+        };
+        Self::new(
+            one.direction_in_local_space(axis_one),
+            local_axis_two,
+            ratio,
+        )
+    }
+
+    /// Returns the angle, in radians, turned by the pinion since the joint was first solved.
+    pub fn angle(&self) -> F {
+        self.angle
+    }
+
+    /// Returns the distance moved by the rack since the joint was first solved.
+    pub fn translation(&self) -> F {
+        self.translation
+    }
+
+    /// Moves the last position of the rack the joint measures from by the opposite of the
+    /// given offset, as the origin of the world moves to it.
+    pub(crate) fn shift_origin(&mut self, offset: &Vector3<F>) {
+        if let Some((_, last)) = &mut self.references {
+            *last = last.vector_sub(offset);
+        }
+    }
+
+    /// Adds the constraint rows of the joint, accumulating the angle turned and the distance
+    /// moved since the last time it was solved.
+    pub(crate) fn rows(&mut self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let pair = builder.bodies();
+        let (one, two) = (&bodies[pair.0], pair.1.map(|body| &bodies[body]));
+        let axis_one = one.direction_in_world_space(&self.local_axis_one);
+        let axis_two = world_direction(two, &self.local_axis_two);
+        let reference = one.direction_in_world_space(&self.local_reference_one);
+        let position = two.map_or_else(Vector3::origin, |two| two.position);
+        if let Some((previous, last)) = self.references {
+            self.angle = self.angle + twist_angle(&axis_one, &reference, &previous);
+            self.translation = self.translation + position.vector_sub(&last).dot_product(&axis_two);
+        }
+        self.references = Some((reference, position));
+
+        let row = builder.add(
+            0,
+            Vector3::origin(),
+            axis_one.scalar_mul(self.ratio),
+            Vector3::origin(),
+            self.ratio * self.angle - self.translation,
+        );
+        row.linear_two = axis_two;
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Half-line starting at an origin and extending towards a direction.
+///
+/// # Remarks
+/// The direction doesn't need to be normalized. Times of impact along the ray
+/// are expressed in multiples of the direction, so with a unit direction they
+/// are distances.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Ray<F: num_traits::Float = f64> {
+    /// Starting point of the ray.
+    pub origin: Vector3<F>,
+
+    /// Direction of the ray.
+    pub direction: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Ray<F> {
+    /// Creates a new ray with the given origin and direction.
+    pub fn new(origin: Vector3<F>, direction: Vector3<F>) -> Self {
+        Self { origin, direction }
+    }
+
+    /// Returns the point of the ray at the given time of impact.
+    pub fn point_at(&self, toi: F) -> Vector3<F> {
+        self.origin.vector_add(&self.direction.scalar_mul(toi))
+    }
+}
@@ -0,0 +1,299 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::parallel::Parallel;
+use crate::rigid_body::RigidBody;
+use crate::shape::Shape;
+use crate::world::{World, WorldState};
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// Input given to a world during a frame, that a recording can give again when replaying it.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum RecordedInput<F: num_traits::Float = f64> {
+    /// Force applied at the center of mass of a body.
+    Force {
+        /// Index of the body.
+        body: usize,
+
+        /// Force in world coordinates.
+        force: Vector3<F>,
+    },
+
+    /// Force applied at a point of a body, both in world coordinates.
+    ForceAtPoint {
+        /// Index of the body.
+        body: usize,
+
+        /// Force in world coordinates.
+        force: Vector3<F>,
+
+        /// Point the force is applied at, in world coordinates.
+        point: Vector3<F>,
+    },
+
+    /// Torque applied to a body.
+    Torque {
+        /// Index of the body.
+        body: usize,
+
+        /// Torque in world coordinates.
+        torque: Vector3<F>,
+    },
+
+    /// Impulse applied at the center of mass of a body.
+    Impulse {
+        /// Index of the body.
+        body: usize,
+
+        /// Impulse in world coordinates.
+        impulse: Vector3<F>,
+    },
+
+    /// Impulse applied at a point of a body, both in world coordinates.
+    ImpulseAtPoint {
+        /// Index of the body.
+        body: usize,
+
+        /// Impulse in world coordinates.
+        impulse: Vector3<F>,
+
+        /// Point the impulse is applied at, in world coordinates.
+        point: Vector3<F>,
+    },
+
+    /// Torque impulse applied to a body.
+    TorqueImpulse {
+        /// Index of the body.
+        body: usize,
+
+        /// Torque impulse in world coordinates.
+        torque: Vector3<F>,
+    },
+
+    /// Body added to the world, along with its colliders, attached to it whatever the body
+    /// they name.
+    AddBody {
+        /// Body added.
+        body: RigidBody<F>,
+
+        /// Colliders attached to the body.
+        colliders: Vec<Collider<Shape<F>, F>>,
+    },
+
+    /// Body removed from the world, given by its index.
+    RemoveBody(usize),
+}
+
+impl<F: num_traits::Float> RecordedInput<F> {
+    /// Gives the input to the world, returning the index of the body added, if any.
+    pub fn apply(&self, world: &mut World<F>) -> Option<usize> {
+        match self {
+            RecordedInput::Force { body, force } => {
+                world.bodies[*body].add_force(force);
+            }
+            RecordedInput::ForceAtPoint { body, force, point } => {
+                world.bodies[*body].add_force_at_point(force, point);
+            }
+            RecordedInput::Torque { body, torque } => {
+                world.bodies[*body].add_torque(torque);
+            }
+            RecordedInput::Impulse { body, impulse } => {
+                world.bodies[*body].apply_impulse(impulse);
+            }
+            RecordedInput::ImpulseAtPoint {
+                body,
+                impulse,
+                point,
+            } => {
+                world.bodies[*body].apply_impulse_at_point(impulse, point);
+            }
+            RecordedInput::TorqueImpulse { body, torque } => {
+                world.bodies[*body].apply_torque_impulse(torque);
+            }
+            RecordedInput::AddBody { body, colliders } => {
+                let index = world.add_body(*body);
+                let index = world.body_index(index).unwrap();
+                for collider in colliders.iter() {
+                    let mut collider = collider.clone();
+                    collider.body = index;
+                    world.add_collider(collider);
+                }
+                return Some(index);
+            }
+            RecordedInput::RemoveBody(body) => {
+                world.remove_body_at(*body);
+            }
+        }
+        None
+    }
+}
+
+/// Inputs given to a world during a frame, along with the duration of the frame.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RecordedFrame<F: num_traits::Float = f64> {
+    /// Duration the world was run for.
+    pub duration: F,
+
+    /// Inputs given to the world before running it, in order.
+    pub inputs: Vec<RecordedInput<F>>,
+}
+
+/// Frames recorded from a world, that can be replayed against a world built the same way.
+///
+/// # Remarks
+/// Replays run the same steps with the same inputs, so they match the recording exactly as
+/// long as the world is deterministic. Keyframes keep the state of the world at the start of
+/// some frames, so replays can start from the last one before a frame instead of the first.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Recording<F: num_traits::Float = f64> {
+    /// Frames recorded, in order.
+    pub frames: Vec<RecordedFrame<F>>,
+
+    /// State of the world at the start of some frames, by frame index.
+    pub keyframes: BTreeMap<usize, WorldState<F>>,
+}
+
+impl<F: num_traits::Float> Recording<F> {
+    /// Returns the number of frames recorded.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns true if no frames were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Replays every frame against the given world, which should start as the recorded one did.
+    pub fn replay(&self, world: &mut World<F>)
+    where
+        F: Parallel,
+    {
+        self.replay_frames(world, 0..self.frames.len());
+    }
+
+    /// Replays a range of frames against the given world, which should be in the state the
+    /// recorded one was at the start of the range.
+    pub fn replay_frames(&self, world: &mut World<F>, frames: Range<usize>)
+    where
+        F: Parallel,
+    {
+        for frame in self.frames[frames].iter() {
+            world.start_frame();
+            for input in frame.inputs.iter() {
+                input.apply(world);
+            }
+            world.run_physics(frame.duration);
+        }
+    }
+
+    /// Returns the last keyframe at or before the given frame, along with its frame index.
+    pub fn keyframe_before(&self, frame: usize) -> Option<(usize, &WorldState<F>)> {
+        self.keyframes
+            .range(..=frame)
+            .next_back()
+            .map(|(frame, state)| (*frame, state))
+    }
+
+    /// Puts the world in the state of the keyframe of the given frame, returning false
+    /// if there is none.
+    ///
+    /// # Remarks
+    /// The force generators of the world are kept, so they should be the same ones the
+    /// recorded world had at that frame for the replay to match the recording.
+    pub fn restore_keyframe(&self, world: &mut World<F>, frame: usize) -> bool {
+        match self.keyframes.get(&frame) {
+            Some(state) => {
+                world.restore(state);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Records the inputs given to a world every frame, to replay them later.
+///
+/// # Remarks
+/// Frames are recorded by driving the world through the recorder: `start_frame`, then
+/// `apply` for every input, and finally `run_physics`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Recorder<F: num_traits::Float = f64> {
+    /// Frames recorded so far.
+    pub recording: Recording<F>,
+
+    /// Number of frames between keyframes, or zero to take none.
+    pub keyframe_interval: usize,
+
+    inputs: Vec<RecordedInput<F>>,
+}
+
+impl<F: num_traits::Float> Default for Recorder<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: num_traits::Float> Recorder<F> {
+    /// Creates a new recorder without frames, taking no keyframes.
+    pub fn new() -> Self {
+        Self {
+            recording: Recording {
+                frames: Vec::new(),
+                keyframes: BTreeMap::new(),
+            },
+            keyframe_interval: 0,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Takes a keyframe every given number of frames, starting with the first one.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the recorder.
+    pub fn with_keyframes(mut self, interval: usize) -> Self {
+        self.keyframe_interval = interval;
+        self
+    }
+
+    /// Starts a frame of the world, taking a keyframe if one is due.
+    pub fn start_frame(&mut self, world: &mut World<F>) {
+        world.start_frame();
+        let frame = self.recording.frames.len();
+        if self.keyframe_interval > 0 && frame.is_multiple_of(self.keyframe_interval) {
+            self.recording.keyframes.insert(frame, world.snapshot());
+        }
+    }
+
+    /// Gives an input to the world and records it, returning the index of the body added, if any.
+    pub fn apply(&mut self, world: &mut World<F>, input: RecordedInput<F>) -> Option<usize> {
+        let added = input.apply(world);
+        self.inputs.push(input);
+        added
+    }
+
+    /// Runs the physics of the world, recording the frame.
+    pub fn run_physics(&mut self, world: &mut World<F>, duration: F)
+    where
+        F: Parallel,
+    {
+        world.run_physics(duration);
+        self.recording.frames.push(RecordedFrame {
+            duration,
+            inputs: std::mem::take(&mut self.inputs),
+        });
+    }
+
+    /// Stops recording, returning the frames recorded.
+    pub fn finish(self) -> Recording<F> {
+        self.recording
+    }
+}
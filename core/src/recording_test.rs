@@ -0,0 +1,102 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::gravity::{GravityField, GravitySource};
+use crate::plane::Plane;
+use crate::recording::*;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Cuboid, Shape, Sphere};
+use crate::world::{World, WorldConfig};
+use math::Vector3;
+
+fn scene() -> World {
+    let mut world = World::new(WorldConfig {
+        deterministic: true,
+        ..WorldConfig::default()
+    });
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    for x in [-1.0, 1.0] {
+        let body = world.add_body(RigidBody::new(
+            Vector3::new(x, 0.5, 0.0),
+            1.0,
+            &cuboid.inertia_tensor(1.0),
+        ));
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+    }
+    world
+}
+
+#[test]
+fn record_and_replay() {
+    let mut world = scene();
+    let mut recorder = Recorder::new().with_keyframes(30);
+    let sphere = Sphere::new(0.25);
+    for frame in 0..90 {
+        recorder.start_frame(&mut world);
+        if frame < 60 {
+            let force = Vector3::new(5.0, 0.0, 0.0);
+            recorder.apply(&mut world, RecordedInput::Force { body: 0, force });
+        }
+        if frame == 20 {
+            let input = RecordedInput::ImpulseAtPoint {
+                body: 1,
+                impulse: Vector3::new(0.0, 4.0, 0.0),
+                point: Vector3::new(1.5, 0.5, 0.0),
+            };
+            recorder.apply(&mut world, input);
+        }
+        if frame == 30 {
+            let input = RecordedInput::AddBody {
+                body: RigidBody::new(
+                    Vector3::new(0.0, 3.0, 0.0),
+                    1.0,
+                    &sphere.inertia_tensor(1.0),
+                ),
+                colliders: vec![Collider::new(0, Shape::Sphere(sphere))],
+            };
+            assert_eq!(Some(2), recorder.apply(&mut world, input));
+        }
+        if frame == 60 {
+            recorder.apply(&mut world, RecordedInput::RemoveBody(0));
+        }
+        recorder.run_physics(&mut world, 1.0 / 60.0);
+    }
+    let recording = recorder.finish();
+    assert_eq!(90, recording.len());
+    assert_eq!(
+        vec![0, 30, 60],
+        recording.keyframes.keys().copied().collect::<Vec<_>>()
+    );
+
+    // Replaying against the same scene ends up in exactly the same state.
+    let mut replayed = scene();
+    recording.replay(&mut replayed);
+    assert_eq!(3, replayed.bodies.len());
+    assert_eq!(2, replayed.body_count());
+    assert_eq!(2, replayed.colliders.len());
+    assert_eq!(2, replayed.colliders[1].body);
+    assert_eq!(world.bodies, replayed.bodies);
+
+    // Keyframes match the state of a replay at their frame, and replays can resume from them.
+    let mut partial = scene();
+    recording.replay_frames(&mut partial, 0..60);
+    let (frame, state) = recording.keyframe_before(70).unwrap();
+    assert_eq!(60, frame);
+    partial.start_frame();
+    assert_eq!(state.bodies(), partial.bodies.as_slice());
+
+    let mut resumed = scene();
+    assert!(recording.restore_keyframe(&mut resumed, 30));
+    assert!(!recording.restore_keyframe(&mut resumed, 31));
+    recording.replay_frames(&mut resumed, 30..90);
+    assert_eq!(world.bodies, resumed.bodies);
+}
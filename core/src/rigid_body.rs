@@ -0,0 +1,689 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use math::{Matrix3, Matrix4, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Default motion threshold below which bodies are put to sleep.
+pub const DEFAULT_SLEEP_EPSILON: f64 = 0.3;
+
+/// Weight given to the motion of the previous second when updating the recency-weighted
+/// motion of a body. Lower values make the motion react faster to changes.
+pub const MOTION_BIAS: f64 = 0.5;
+
+/// Function raising a base to an exponent, either the one of the platform or one computed
+/// in software.
+pub(crate) type Power<F> = fn(F, F) -> F;
+
+/// How a rigid body is moved by the world it's simulated in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum BodyType {
+    /// Moved by forces, impulses, contacts and joints, unless its mass is infinite.
+    Dynamic,
+
+    /// Moved only by the velocities it's given, pushing dynamic bodies out of its way
+    /// without being affected by forces, impulses, contacts or joints.
+    Kinematic,
+
+    /// Never moves, and its colliders are placed in the broad phase only once.
+    Static,
+}
+
+/// Axes of world space along which a rigid body can't move, and around which it can't turn.
+///
+/// # Remarks
+/// Locked axes are enforced by the solver and the integrator rather than by resetting the
+/// velocities of the body afterwards, so contacts and joints see the body as infinitely
+/// heavy along them and don't fight the locks.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct AxisLocks {
+    /// Whether translation along the X, Y and Z axes is locked.
+    pub translation: [bool; 3],
+
+    /// Whether rotation around the X, Y and Z axes is locked.
+    pub rotation: [bool; 3],
+}
+
+impl AxisLocks {
+    /// Creates new locks leaving every axis free.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Creates new locks keeping the body upright, free to move but only to turn around
+    /// the given axis (0 for X, 1 for Y and 2 for Z), like characters.
+    pub fn upright(axis: usize) -> Self {
+        let mut rotation = [true; 3];
+        rotation[axis] = false;
+        Self {
+            translation: [false; 3],
+            rotation,
+        }
+    }
+
+    /// Creates new locks keeping the body on the plane perpendicular to the given axis
+    /// (0 for X, 1 for Y and 2 for Z), only turning around that axis, for top-down and
+    /// side-scrolling games.
+    pub fn planar(axis: usize) -> Self {
+        let mut translation = [false; 3];
+        translation[axis] = true;
+        Self {
+            translation,
+            ..Self::upright(axis)
+        }
+    }
+
+    /// Returns true if any axis is locked.
+    pub fn any(&self) -> bool {
+        self.translation
+            .iter()
+            .chain(self.rotation.iter())
+            .any(|locked| *locked)
+    }
+
+    /// Returns the given vector without its components along the locked translation axes.
+    pub fn lock_translation<F: num_traits::Float>(&self, vector: &Vector3<F>) -> Vector3<F> {
+        lock_components(&self.translation, vector)
+    }
+
+    /// Returns the given vector without its components around the locked rotation axes.
+    pub fn lock_rotation<F: num_traits::Float>(&self, vector: &Vector3<F>) -> Vector3<F> {
+        lock_components(&self.rotation, vector)
+    }
+
+    /// Returns the given inverse inertia tensor in world space without the rows and columns
+    /// of the locked rotation axes, so torques don't turn the body around them and turning
+    /// around them takes no torque.
+    fn lock_inverse_inertia<F: num_traits::Float>(&self, tensor: &Matrix3<F>) -> Matrix3<F> {
+        let mut locked = *tensor;
+        for (axis, _) in self
+            .rotation
+            .iter()
+            .enumerate()
+            .filter(|(_, locked)| **locked)
+        {
+            for other in 0..3 {
+                locked.data[axis * 3 + other] = num_traits::zero();
+                locked.data[other * 3 + axis] = num_traits::zero();
+            }
+        }
+        locked
+    }
+}
+
+/// Returns the given vector with the components flagged as locked set to zero.
+fn lock_components<F: num_traits::Float>(locks: &[bool; 3], vector: &Vector3<F>) -> Vector3<F> {
+    let component = |locked: bool, value: F| if locked { F::zero() } else { value };
+    Vector3::new(
+        component(locks[0], vector.x),
+        component(locks[1], vector.y),
+        component(locks[2], vector.z),
+    )
+}
+
+/// A rigid body is the basic simulation object in the physics engine.
+/// On top of the linear motion of a particle, it has an orientation and angular motion.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RigidBody<F: num_traits::Float = f64> {
+    /// Holds the inverse of the mass of the rigid body.
+    /// It's more useful to hold the inverse mass because integration is simpler,
+    /// and because in real-time simulation it's more useful to have bodies with
+    /// infinite mass (immovable) than zero mass (completely unstable in numerical simulation).
+    pub inverse_mass: F,
+
+    /// Holds the inverse of the body's inertia tensor, in body space.
+    /// The inertia tensor provided must not be degenerate (that would mean the body
+    /// had zero inertia for spinning along one axis). As long as the tensor is finite,
+    /// it will be invertible.
+    pub inverse_inertia_tensor: Matrix3<F>,
+
+    /// Linear position of the rigid body in world space.
+    pub position: Vector3<F>,
+
+    /// Angular orientation of the rigid body in world space.
+    pub orientation: Quaternion<F>,
+
+    /// Linear velocity of the rigid body in world space.
+    pub velocity: Vector3<F>,
+
+    /// Angular velocity, or rotation, of the rigid body in world space.
+    pub rotation: Vector3<F>,
+
+    /// Constant acceleration of the rigid body, typically used for gravity.
+    pub acceleration: Vector3<F>,
+
+    /// Linear acceleration of the rigid body during the previous frame.
+    pub last_frame_acceleration: Vector3<F>,
+
+    /// Accumulated force to be applied at the next integration step only.
+    pub force_accum: Vector3<F>,
+
+    /// Accumulated torque to be applied at the next integration step only.
+    pub torque_accum: Vector3<F>,
+
+    /// Inverse inertia tensor of the body in world space.
+    /// This is derived data, updated by `calculate_derived_data`.
+    pub inverse_inertia_tensor_world: Matrix3<F>,
+
+    /// Transform matrix for converting body space into world space and vice versa.
+    /// This is derived data, updated by `calculate_derived_data`.
+    pub transform_matrix: Matrix4<F>,
+
+    /// Amount of motion of the body, a recency-weighted mean of its linear
+    /// and angular kinetic energy used to decide when the body is at rest.
+    pub motion: F,
+
+    /// Sleeping bodies are not integrated until they are woken up,
+    /// either explicitly or by applying forces or impulses to them.
+    pub is_awake: bool,
+
+    /// Some bodies may never be allowed to fall asleep (e.g. user controlled bodies).
+    pub can_sleep: bool,
+
+    /// Motion threshold below which the body is considered at rest.
+    pub sleep_epsilon: F,
+
+    /// Fraction of linear velocity kept after one second, removing energy added
+    /// through numerical instability in the integrator. When `None`, the default
+    /// damping of the world the body is simulated in is used.
+    pub linear_damping: Option<F>,
+
+    /// Fraction of angular velocity kept after one second. When `None`, the default
+    /// damping of the world the body is simulated in is used.
+    pub angular_damping: Option<F>,
+
+    /// Fast bodies with continuous collision detection are stopped where they hit
+    /// something during the frame, instead of tunneling through thin objects.
+    pub continuous_collision: bool,
+
+    /// How the body is moved by the world, dynamic by default.
+    pub body_type: BodyType,
+
+    /// Multiplier of the gravity pulling the body, one by default. Zero makes the body float,
+    /// and negative values make it rise.
+    pub gravity_scale: F,
+
+    /// Bodies with a higher dominance push the ones with a lower dominance they touch,
+    /// without being pushed back, as if their mass was infinite in those contacts.
+    pub dominance: i8,
+
+    /// Axes of world space the body can't move along, or turn around, none by default.
+    pub locked_axes: AxisLocks,
+}
+
+impl<F: num_traits::Float> RigidBody<F> {
+    /// Creates a new rigid body at rest in the specified position with the given mass
+    /// and body space inertia tensor.
+    pub fn new(position: Vector3<F>, mass: F, inertia_tensor: &Matrix3<F>) -> Self {
+        let mut body = Self {
+            inverse_mass: num_traits::one(),
+            inverse_inertia_tensor: Matrix3::identity(),
+            position,
+            orientation: Quaternion::identity(),
+            velocity: Vector3::origin(),
+            rotation: Vector3::origin(),
+            acceleration: Vector3::origin(),
+            last_frame_acceleration: Vector3::origin(),
+            force_accum: Vector3::origin(),
+            torque_accum: Vector3::origin(),
+            inverse_inertia_tensor_world: Matrix3::identity(),
+            transform_matrix: Matrix4::identity(),
+            motion: num_traits::zero(),
+            is_awake: true,
+            can_sleep: true,
+            sleep_epsilon: math::real(DEFAULT_SLEEP_EPSILON),
+            linear_damping: None,
+            angular_damping: None,
+            continuous_collision: false,
+            body_type: BodyType::Dynamic,
+            gravity_scale: num_traits::one(),
+            dominance: 0,
+            locked_axes: AxisLocks::none(),
+        };
+        body.set_mass(mass);
+        body.set_inertia_tensor(inertia_tensor);
+        body.set_awake(true);
+        body.calculate_derived_data();
+        body
+    }
+
+    /// Returns the mass of the rigid body.
+    /// Bodies with infinite mass return the largest representable value.
+    pub fn mass(&self) -> F {
+        if self.inverse_mass == num_traits::zero() {
+            F::max_value()
+        } else {
+            F::one() / self.inverse_mass
+        }
+    }
+
+    /// Sets the mass of the rigid body.
+    ///
+    /// # Remarks
+    /// A zero mass would result in an infinite inverse mass, which is not supported;
+    /// in that case the body is treated as having infinite mass.
+    pub fn set_mass(&mut self, mass: F) -> &mut Self {
+        if mass == num_traits::zero() {
+            self.inverse_mass = num_traits::zero();
+        } else {
+            self.inverse_mass = F::one() / mass;
+        }
+        self
+    }
+
+    /// Marks the rigid body as immovable by setting its inverse mass
+    /// and inverse inertia tensor to `0`.
+    pub fn set_infinite_mass(&mut self) -> &mut Self {
+        self.inverse_mass = num_traits::zero();
+        self.inverse_inertia_tensor = Matrix3::zero();
+        self.inverse_inertia_tensor_world = Matrix3::zero();
+        self
+    }
+
+    /// Returns true if the rigid body is dynamic and its mass is not infinite, so forces,
+    /// impulses, contacts and joints can move it.
+    pub fn has_finite_mass(&self) -> bool {
+        self.body_type == BodyType::Dynamic && self.inverse_mass > num_traits::zero()
+    }
+
+    /// Returns the change in linear velocity an impulse at the center of mass of the body
+    /// would cause, along its unlocked axes only.
+    pub fn velocity_change(&self, impulse: &Vector3<F>) -> Vector3<F> {
+        self.locked_axes
+            .lock_translation(&impulse.scalar_mul(self.inverse_mass))
+    }
+
+    /// Changes how the body is moved by the world, keeping its mass for when it's dynamic.
+    ///
+    /// # Remarks
+    /// Static bodies lose all their velocity, while the others are woken up.
+    pub fn set_body_type(&mut self, body_type: BodyType) -> &mut Self {
+        self.body_type = body_type;
+        if body_type == BodyType::Static {
+            self.velocity = Vector3::origin();
+            self.rotation = Vector3::origin();
+        } else {
+            self.set_awake(true);
+        }
+        self
+    }
+
+    /// Sets the velocities of a kinematic body so it reaches the given position and
+    /// orientation after integrating it for the given duration (in seconds).
+    ///
+    /// # Remarks
+    /// This should be called before every step the body follows a target in, with the
+    /// duration of the step.
+    pub fn move_kinematic(
+        &mut self,
+        position: &Vector3<F>,
+        orientation: &Quaternion<F>,
+        duration: F,
+    ) -> &mut Self {
+        debug_assert!(duration > num_traits::zero());
+        self.velocity = position.vector_sub(&self.position).scalar_div(duration);
+
+        // Both signs of the quaternion are the same rotation, keep the shortest one.
+        let mut delta = orientation
+            .normalize()
+            .quaternion_mul(&self.orientation.conjugate());
+        if delta.r < F::zero() {
+            delta = Quaternion::new(-delta.r, -delta.i, -delta.j, -delta.k);
+        }
+        let axis = Vector3::new(delta.i, delta.j, delta.k);
+        let sine = axis.magnitude();
+        self.rotation = if sine > F::zero() {
+            let angle = math::real::<F>(2.0) * sine.atan2(delta.r);
+            axis.scalar_mul(angle / (sine * duration))
+        } else {
+            Vector3::origin()
+        };
+        self.wake_up();
+        self
+    }
+
+    /// Sets the inertia tensor of the rigid body, in body space.
+    pub fn set_inertia_tensor(&mut self, inertia_tensor: &Matrix3<F>) -> &mut Self {
+        self.inverse_inertia_tensor = inertia_tensor.inverse();
+        self
+    }
+
+    /// Returns the inertia tensor of the rigid body, in body space.
+    pub fn inertia_tensor(&self) -> Matrix3<F> {
+        self.inverse_inertia_tensor.inverse()
+    }
+
+    /// Wakes up or puts to sleep the rigid body.
+    ///
+    /// # Remarks
+    /// Bodies put to sleep lose all their velocity, while woken up bodies are given
+    /// enough motion to not immediately fall asleep again.
+    pub fn set_awake(&mut self, awake: bool) -> &mut Self {
+        if awake {
+            self.is_awake = true;
+
+            // Add a bit of motion to avoid it falling asleep immediately.
+            self.motion = self.sleep_epsilon * math::real(2.0);
+        } else {
+            self.is_awake = false;
+            self.velocity = Vector3::origin();
+            self.rotation = Vector3::origin();
+        }
+        self
+    }
+
+    /// Sets whether the body is ever allowed to go to sleep.
+    /// Bodies that can't sleep are woken up immediately.
+    pub fn set_can_sleep(&mut self, can_sleep: bool) -> &mut Self {
+        self.can_sleep = can_sleep;
+        if !can_sleep && !self.is_awake {
+            self.set_awake(true);
+        }
+        self
+    }
+
+    /// Returns true if the body is allowed to sleep and its motion has settled below
+    /// the sleep threshold.
+    pub fn is_sleep_candidate(&self) -> bool {
+        self.can_sleep && self.motion < self.sleep_epsilon
+    }
+
+    /// Updates the recency-weighted motion of the body after moving for the given duration.
+    fn update_motion(&mut self, duration: F, power: Power<F>) {
+        let current_motion =
+            self.velocity.dot_product(&self.velocity) + self.rotation.dot_product(&self.rotation);
+        let bias = power(math::real(MOTION_BIAS), duration);
+        self.motion = bias * self.motion + (F::one() - bias) * current_motion;
+
+        // Cap the motion so a body that stops abruptly can fall asleep quickly.
+        let cap = self.sleep_epsilon * math::real(10.0);
+        if self.motion > cap {
+            self.motion = cap;
+        }
+    }
+
+    /// Wakes up the body if it's sleeping.
+    fn wake_up(&mut self) {
+        if !self.is_awake {
+            self.set_awake(true);
+        }
+    }
+
+    /// Calculates internal data from state data.
+    /// This should be called after the body's state is altered directly
+    /// (it's called automatically during integration).
+    pub fn calculate_derived_data(&mut self) -> &mut Self {
+        self.orientation.inplace_normalize();
+        self.transform_matrix =
+            Matrix4::from_orientation_and_position(&self.orientation, &self.position);
+
+        // The inertia tensor is transformed into world space: R * I^-1 * R^T.
+        let rotation = self.transform_matrix.rotation();
+        self.inverse_inertia_tensor_world = rotation
+            .matrix_mul(&self.inverse_inertia_tensor)
+            .matrix_mul(&rotation.transpose());
+        if self.locked_axes.any() {
+            self.inverse_inertia_tensor_world = self
+                .locked_axes
+                .lock_inverse_inertia(&self.inverse_inertia_tensor_world);
+        }
+        self
+    }
+
+    /// Converts the given point from world space into the body's local space.
+    pub fn point_in_local_space(&self, point: &Vector3<F>) -> Vector3<F> {
+        self.transform_matrix.transform_inverse(point)
+    }
+
+    /// Converts the given point from the body's local space into world space.
+    pub fn point_in_world_space(&self, point: &Vector3<F>) -> Vector3<F> {
+        self.transform_matrix.transform(point)
+    }
+
+    /// Converts the given direction from world space into the body's local space.
+    pub fn direction_in_local_space(&self, direction: &Vector3<F>) -> Vector3<F> {
+        self.transform_matrix.transform_inverse_direction(direction)
+    }
+
+    /// Converts the given direction from the body's local space into world space.
+    pub fn direction_in_world_space(&self, direction: &Vector3<F>) -> Vector3<F> {
+        self.transform_matrix.transform_direction(direction)
+    }
+
+    /// Returns the velocity of the given point of the body, in world space.
+    pub fn velocity_at_point(&self, point: &Vector3<F>) -> Vector3<F> {
+        let relative = point.vector_sub(&self.position);
+        self.velocity
+            .vector_add(&self.rotation.cross_product(&relative))
+    }
+
+    /// Clears the forces and torques in the accumulators.
+    /// This is called automatically after each integration step.
+    pub fn clear_accumulators(&mut self) -> &mut Self {
+        self.force_accum = Vector3::origin();
+        self.torque_accum = Vector3::origin();
+        self
+    }
+
+    /// Adds the given force to the center of mass of the rigid body,
+    /// to be applied at the next integration only. The force is expressed in world coordinates.
+    pub fn add_force(&mut self, force: &Vector3<F>) -> &mut Self {
+        self.wake_up();
+        self.force_accum.inplace_vector_add(force);
+        self
+    }
+
+    /// Adds the given torque to the rigid body, to be applied at the next integration only.
+    /// The torque is expressed in world coordinates.
+    pub fn add_torque(&mut self, torque: &Vector3<F>) -> &mut Self {
+        self.wake_up();
+        self.torque_accum.inplace_vector_add(torque);
+        self
+    }
+
+    /// Adds the given force to the given point on the rigid body, to be applied
+    /// at the next integration only. Both the force and the application point
+    /// are given in world space. Because the force is not applied at the center of mass,
+    /// it may be split into both a force and a torque.
+    pub fn add_force_at_point(&mut self, force: &Vector3<F>, point: &Vector3<F>) -> &mut Self {
+        let relative = point.vector_sub(&self.position);
+        self.wake_up();
+        self.force_accum.inplace_vector_add(force);
+        self.torque_accum
+            .inplace_vector_add(&relative.cross_product(force));
+        self
+    }
+
+    /// Adds the given force to the given point on the rigid body, to be applied
+    /// at the next integration only. The force is given in world space, and
+    /// the application point in body space.
+    ///
+    /// # Remarks
+    /// This is useful for spring forces, or other forces fixed to the body.
+    pub fn add_force_at_body_point(&mut self, force: &Vector3<F>, point: &Vector3<F>) -> &mut Self {
+        let point = self.point_in_world_space(point);
+        self.add_force_at_point(force, &point)
+    }
+
+    /// Applies an instantaneous change in momentum at the center of mass of the rigid body.
+    /// The impulse is expressed in world coordinates.
+    pub fn apply_impulse(&mut self, impulse: &Vector3<F>) -> &mut Self {
+        if !self.has_finite_mass() {
+            return self;
+        }
+        self.wake_up();
+        self.velocity
+            .inplace_vector_add(&self.velocity_change(impulse));
+        self
+    }
+
+    /// Applies an instantaneous change in momentum at the given point on the rigid body.
+    /// Both the impulse and the application point are given in world space, so the
+    /// impulse changes both the linear and angular velocity of the body.
+    pub fn apply_impulse_at_point(
+        &mut self,
+        impulse: &Vector3<F>,
+        point: &Vector3<F>,
+    ) -> &mut Self {
+        let relative = point.vector_sub(&self.position);
+        self.apply_impulse(impulse);
+        self.apply_torque_impulse(&relative.cross_product(impulse))
+    }
+
+    /// Applies an instantaneous change in angular momentum to the rigid body.
+    /// The torque impulse is expressed in world coordinates.
+    pub fn apply_torque_impulse(&mut self, torque_impulse: &Vector3<F>) -> &mut Self {
+        if !self.has_finite_mass() {
+            return self;
+        }
+        self.wake_up();
+        self.rotation
+            .inplace_vector_add(&self.inverse_inertia_tensor_world.transform(torque_impulse));
+        self
+    }
+
+    /// Integrates the rigid body forward in time by the given amount (in seconds).
+    /// Uses a Newton-Euler integration method, which is a linear approximation
+    /// to the correct integral.
+    ///
+    /// # Remarks
+    /// Bodies without their own damping coefficients are not damped.
+    ///
+    /// Kinematic bodies only move with their velocities, while static bodies, rigid bodies
+    /// with infinite mass, and sleeping bodies are never integrated.
+    pub fn integrate(&mut self, duration: F) -> &mut Self {
+        self.integrate_with_damping(duration, num_traits::one(), num_traits::one())
+    }
+
+    /// Integrates the rigid body forward in time by the given amount (in seconds),
+    /// using the given damping coefficients unless the body has its own.
+    ///
+    /// # Remarks
+    /// Damping is applied as `damping^duration`, so the result is independent of the frame rate.
+    ///
+    /// Kinematic bodies only move with their velocities, while static bodies, rigid bodies
+    /// with infinite mass, and sleeping bodies are never integrated.
+    pub fn integrate_with_damping(
+        &mut self,
+        duration: F,
+        default_linear_damping: F,
+        default_angular_damping: F,
+    ) -> &mut Self {
+        self.integrate_velocity(duration, default_linear_damping, default_angular_damping)
+            .integrate_position(duration)
+    }
+
+    /// Integrates the velocities of the rigid body forward in time by the given amount
+    /// (in seconds), from the accumulated forces and the given damping coefficients
+    /// unless the body has its own.
+    ///
+    /// # Remarks
+    /// This is the first half of `integrate_with_damping`, which lets the contact solver
+    /// correct the velocities before they are used to move the body.
+    ///
+    /// Rigid bodies with infinite mass, and sleeping bodies, are never integrated.
+    pub fn integrate_velocity(
+        &mut self,
+        duration: F,
+        default_linear_damping: F,
+        default_angular_damping: F,
+    ) -> &mut Self {
+        self.integrate_velocity_with(
+            duration,
+            default_linear_damping,
+            default_angular_damping,
+            F::powf,
+        )
+    }
+
+    /// Integrates the velocities of the rigid body like `integrate_velocity`, raising the
+    /// damping coefficients to the duration with the given function.
+    pub(crate) fn integrate_velocity_with(
+        &mut self,
+        duration: F,
+        default_linear_damping: F,
+        default_angular_damping: F,
+        power: Power<F>,
+    ) -> &mut Self {
+        if !self.has_finite_mass() || !self.is_awake {
+            return self;
+        }
+
+        // Calculate linear acceleration from force inputs.
+        self.last_frame_acceleration = self.locked_axes.lock_translation(
+            &self
+                .acceleration
+                .vector_add(&self.force_accum.scalar_mul(self.inverse_mass)),
+        );
+
+        // Calculate angular acceleration from torque inputs.
+        let angular_acceleration = self
+            .inverse_inertia_tensor_world
+            .transform(&self.torque_accum);
+
+        // Adjust velocities.
+        self.velocity
+            .inplace_vector_add(&self.last_frame_acceleration.scalar_mul(duration));
+        self.rotation
+            .inplace_vector_add(&angular_acceleration.scalar_mul(duration));
+
+        // Impose drag.
+        let linear_damping = self.linear_damping.unwrap_or(default_linear_damping);
+        let angular_damping = self.angular_damping.unwrap_or(default_angular_damping);
+        self.velocity
+            .inplace_scalar_mul(power(linear_damping, duration));
+        self.rotation
+            .inplace_scalar_mul(power(angular_damping, duration));
+
+        // Keep the body from drifting along its locked axes, even if it was given
+        // velocities along them.
+        if self.locked_axes.any() {
+            self.velocity = self.locked_axes.lock_translation(&self.velocity);
+            self.rotation = self.locked_axes.lock_rotation(&self.rotation);
+        }
+        self
+    }
+
+    /// Integrates the position and orientation of the rigid body forward in time
+    /// by the given amount (in seconds), from its current velocities.
+    ///
+    /// # Remarks
+    /// This is the second half of `integrate_with_damping`. It also clears the accumulators
+    /// and updates the motion of the body, used to decide when it can sleep.
+    ///
+    /// Kinematic bodies are moved with their velocities, while static bodies, rigid bodies
+    /// with infinite mass, and sleeping bodies are never integrated.
+    pub fn integrate_position(&mut self, duration: F) -> &mut Self {
+        self.integrate_position_with(duration, F::powf)
+    }
+
+    /// Integrates the position and orientation of the rigid body like `integrate_position`,
+    /// weighting its motion with the given power function.
+    pub(crate) fn integrate_position_with(&mut self, duration: F, power: Power<F>) -> &mut Self {
+        let moves = match self.body_type {
+            BodyType::Dynamic => self.has_finite_mass(),
+            BodyType::Kinematic => true,
+            BodyType::Static => false,
+        };
+        if !moves || !self.is_awake {
+            return self;
+        }
+
+        // Adjust positions.
+        self.position
+            .inplace_vector_add(&self.velocity.scalar_mul(duration));
+        self.orientation
+            .inplace_add_scaled_vector(&self.rotation, duration);
+
+        // Normalize the orientation, and update the matrices with the new position and orientation.
+        self.calculate_derived_data();
+        self.clear_accumulators();
+
+        // Update the kinetic energy store, used to decide when the body can sleep.
+        self.update_motion(duration, power);
+        self
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::rigid_body::*;
+use math::{Matrix3, Quaternion, Vector3};
+
+fn assert_vector_eq(expected: Vector3<f64>, actual: Vector3<f64>) {
+    assert!(
+        (expected - actual).magnitude() < 1e-12,
+        "{:?} != {:?}",
+        expected,
+        actual
+    );
+}
+
+fn unit_cube() -> RigidBody {
+    // Unit mass cube with half-size 1.5 has an inertia of 1.5 along every axis.
+    RigidBody::new(
+        Vector3::new(1.0, 2.0, 3.0),
+        1.0,
+        &Matrix3::block_inertia_tensor(&Vector3::new(1.5, 1.5, 1.5), 1.0),
+    )
+}
+
+#[test]
+fn space_conversions() {
+    let mut body = unit_cube();
+    body.orientation =
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2);
+    body.calculate_derived_data();
+
+    let local = Vector3::new(1.0, 0.0, 0.0);
+    let world = body.point_in_world_space(&local);
+    assert_vector_eq(Vector3::new(1.0, 3.0, 3.0), world);
+    assert_vector_eq(local, body.point_in_local_space(&world));
+    assert_vector_eq(
+        Vector3::new(0.0, 1.0, 0.0),
+        body.direction_in_world_space(&local),
+    );
+    assert_vector_eq(
+        local,
+        body.direction_in_local_space(&Vector3::new(0.0, 1.0, 0.0)),
+    );
+}
+
+#[test]
+fn force_at_point() {
+    let mut body = unit_cube();
+
+    // Forces through the center of mass generate no torque.
+    body.add_force_at_point(&Vector3::new(0.0, 1.0, 0.0), &Vector3::new(1.0, 5.0, 3.0));
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), body.force_accum);
+    assert_eq!(Vector3::origin(), body.torque_accum);
+    body.clear_accumulators();
+
+    // Off-center forces spin the body.
+    body.add_force_at_body_point(&Vector3::new(0.0, 1.0, 0.0), &Vector3::new(1.0, 0.0, 0.0));
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), body.force_accum);
+    assert_eq!(Vector3::new(0.0, 0.0, 1.0), body.torque_accum);
+
+    body.integrate(1.0);
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), body.velocity);
+    assert_vector_eq(Vector3::new(0.0, 0.0, 1.0 / 1.5), body.rotation);
+    assert_eq!(Vector3::origin(), body.force_accum);
+    assert_eq!(Vector3::origin(), body.torque_accum);
+}
+
+#[test]
+fn impulses() {
+    let mut body = unit_cube();
+    body.apply_impulse_at_point(&Vector3::new(0.0, 3.0, 0.0), &Vector3::new(2.0, 2.0, 3.0));
+    assert_vector_eq(Vector3::new(0.0, 3.0, 0.0), body.velocity);
+    assert_vector_eq(Vector3::new(0.0, 0.0, 2.0), body.rotation);
+    assert_vector_eq(
+        Vector3::new(0.0, 5.0, 0.0),
+        body.velocity_at_point(&Vector3::new(2.0, 2.0, 3.0)),
+    );
+
+    body.apply_torque_impulse(&Vector3::new(0.0, 0.0, -3.0));
+    assert_vector_eq(Vector3::origin(), body.rotation);
+
+    // Immovable bodies are unaffected by impulses.
+    body.set_infinite_mass();
+    body.apply_impulse_at_point(&Vector3::new(0.0, 3.0, 0.0), &Vector3::new(2.0, 2.0, 3.0));
+    assert_vector_eq(Vector3::new(0.0, 3.0, 0.0), body.velocity);
+    assert_vector_eq(Vector3::origin(), body.rotation);
+}
+
+#[test]
+fn sleep() {
+    let mut body = unit_cube();
+    assert!(body.is_awake);
+    assert!(!body.is_sleep_candidate());
+
+    // Resting bodies settle their motion below the threshold.
+    for _ in 0..100 {
+        body.integrate(0.1);
+    }
+    assert!(body.is_sleep_candidate());
+
+    // Sleeping bodies are not integrated.
+    body.set_awake(false);
+    body.velocity = Vector3::new(1.0, 0.0, 0.0);
+    body.integrate(0.1);
+    assert_eq!(Vector3::new(1.0, 2.0, 3.0), body.position);
+
+    // Impulses wake bodies up.
+    body.apply_impulse(&Vector3::new(1.0, 0.0, 0.0));
+    assert!(body.is_awake);
+    assert!(!body.is_sleep_candidate());
+
+    // Bodies that can't sleep are never sleep candidates.
+    body.set_awake(false).set_can_sleep(false);
+    assert!(body.is_awake);
+    body.motion = 0.0;
+    assert!(!body.is_sleep_candidate());
+}
+
+#[test]
+fn damping() {
+    let mut body = unit_cube();
+    body.velocity = Vector3::new(1.0, 0.0, 0.0);
+    body.rotation = Vector3::new(0.0, 1.0, 0.0);
+
+    // Damping is frame rate independent.
+    let mut other = body;
+    body.integrate_with_damping(1.0, 0.5, 0.25);
+    for _ in 0..4 {
+        other.integrate_with_damping(0.25, 0.5, 0.25);
+    }
+    assert!((body.velocity - other.velocity).magnitude() < 1e-12);
+    assert!((body.rotation - other.rotation).magnitude() < 1e-12);
+    assert_vector_eq(Vector3::new(0.5, 0.0, 0.0), body.velocity);
+    assert_vector_eq(Vector3::new(0.0, 0.25, 0.0), body.rotation);
+
+    // Per-body coefficients take precedence over the defaults.
+    body.angular_damping = Some(1.0);
+    body.integrate_with_damping(1.0, 0.5, 0.25);
+    assert_vector_eq(Vector3::new(0.25, 0.0, 0.0), body.velocity);
+    assert_vector_eq(Vector3::new(0.0, 0.25, 0.0), body.rotation);
+
+    // Plain integration doesn't damp bodies without their own coefficients.
+    body.integrate(1.0);
+    assert_vector_eq(Vector3::new(0.25, 0.0, 0.0), body.velocity);
+}
+
+#[test]
+fn body_types() {
+    // Kinematic bodies move with their velocities only, ignoring forces and impulses.
+    let mut body = unit_cube();
+    body.set_body_type(BodyType::Kinematic);
+    assert!(!body.has_finite_mass());
+    body.velocity = Vector3::new(1.0, 0.0, 0.0);
+    body.acceleration = Vector3::new(0.0, -10.0, 0.0);
+    body.add_force(&Vector3::new(0.0, 5.0, 0.0));
+    body.apply_impulse(&Vector3::new(0.0, 5.0, 0.0));
+    body.apply_torque_impulse(&Vector3::new(0.0, 5.0, 0.0));
+    body.integrate(0.5);
+    assert_vector_eq(Vector3::new(1.5, 2.0, 3.0), body.position);
+    assert_vector_eq(Vector3::new(1.0, 0.0, 0.0), body.velocity);
+    assert_vector_eq(Vector3::origin(), body.rotation);
+
+    // Following a target sets the velocities that reach it in the given duration.
+    let orientation = Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), 0.5);
+    body.move_kinematic(&Vector3::new(2.5, 2.0, 2.0), &orientation, 0.25);
+    assert_vector_eq(Vector3::new(4.0, 0.0, -4.0), body.velocity);
+    assert_vector_eq(Vector3::new(0.0, 2.0, 0.0), body.rotation);
+    body.integrate(0.25);
+    assert_vector_eq(Vector3::new(2.5, 2.0, 2.0), body.position);
+
+    // Static bodies never move, and get their mass back once dynamic again.
+    body.set_body_type(BodyType::Static);
+    assert_eq!(Vector3::origin(), body.velocity);
+    body.velocity = Vector3::new(1.0, 0.0, 0.0);
+    body.integrate(0.5);
+    assert_vector_eq(Vector3::new(2.5, 2.0, 2.0), body.position);
+    body.set_body_type(BodyType::Dynamic);
+    assert!(body.has_finite_mass());
+    assert_eq!(1.0, body.mass());
+}
+
+#[test]
+fn axis_locks() {
+    let mut body = unit_cube();
+    body.locked_axes = AxisLocks::planar(1);
+    body.calculate_derived_data();
+
+    // Impulses don't move the body along, nor turn it around, its locked axes.
+    body.apply_impulse_at_point(&Vector3::new(0.0, 3.0, 0.0), &Vector3::new(2.0, 2.0, 3.0));
+    assert_vector_eq(Vector3::origin(), body.velocity);
+    assert_vector_eq(Vector3::origin(), body.rotation);
+    body.apply_impulse_at_point(&Vector3::new(3.0, 0.0, 0.0), &Vector3::new(1.0, 2.0, 4.0));
+    assert_vector_eq(Vector3::new(3.0, 0.0, 0.0), body.velocity);
+    assert_vector_eq(Vector3::new(0.0, 2.0, 0.0), body.rotation);
+
+    // Neither do accelerations, nor the velocities the body is given along them.
+    body.velocity.y = 5.0;
+    body.rotation.x = 1.0;
+    body.acceleration = Vector3::new(0.0, -10.0, 0.0);
+    body.integrate(0.5);
+    assert_vector_eq(Vector3::new(3.0, 0.0, 0.0), body.velocity);
+    assert_vector_eq(Vector3::new(0.0, 2.0, 0.0), body.rotation);
+    assert_vector_eq(Vector3::new(2.5, 2.0, 3.0), body.position);
+
+    let upright = AxisLocks::upright(2);
+    assert_eq!([false; 3], upright.translation);
+    assert_eq!([true, true, false], upright.rotation);
+    assert!(upright.any());
+    assert!(!AxisLocks::none().any());
+}
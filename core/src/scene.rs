@@ -0,0 +1,502 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::{Collider, DEFAULT_COLLISION_GROUP, DEFAULT_COLLISION_MASK};
+use crate::gravity::GravityField;
+use crate::joint::{Joint, JointKind};
+use crate::material::PhysicsMaterial;
+use crate::plane::Plane;
+use crate::rigid_body::{AxisLocks, BodyType, RigidBody};
+use crate::shape::Shape;
+use crate::world::{IndexRemap, World, WorldConfig};
+use math::{Matrix3, Matrix4, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Declarative description of a physics scene, meant to be written by hand and loaded
+/// into a world with serde, e.g. from RON or TOML files.
+///
+/// # Remarks
+/// Every field has a default, so descriptions only need to list what they change.
+/// Colliders use the materials of the scene by name, and joints refer to the bodies of
+/// the scene by their index in `bodies`.
+///
+/// Unlike the state saved by `World::snapshot`, descriptions leave out everything the world
+/// changes while stepping, like forces, contacts or sleeping bodies.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scene<F: num_traits::Float = f64> {
+    /// Configuration of the world.
+    pub config: WorldConfig<F>,
+
+    /// Gravity pulling the bodies of the world.
+    pub gravity: GravityField<F>,
+
+    /// Materials the colliders and the scenery planes are made of, by name.
+    pub materials: BTreeMap<String, PhysicsMaterial<F>>,
+
+    /// Half-spaces that are part of the scenery.
+    pub planes: Vec<Plane<F>>,
+
+    /// Name of the material the scenery planes are made of, the default one if `None`.
+    pub scenery_material: Option<String>,
+
+    /// Rigid bodies of the scene, with their colliders.
+    pub bodies: Vec<SceneBody<F>>,
+
+    /// Joints between the bodies of the scene.
+    pub joints: Vec<SceneJoint<F>>,
+}
+
+/// Rigid body of a scene description.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneBody<F: num_traits::Float = f64> {
+    /// Name of the body, for the tools editing the scene.
+    pub name: Option<String>,
+
+    /// How the body is simulated.
+    pub body_type: BodyType,
+
+    /// Position of the body in world space.
+    pub position: Vector3<F>,
+
+    /// Orientation of the body in world space.
+    pub orientation: Quaternion<F>,
+
+    /// Linear velocity of the body in world space.
+    pub velocity: Vector3<F>,
+
+    /// Angular velocity of the body in world space.
+    pub rotation: Vector3<F>,
+
+    /// Mass of the dynamic body, distributed like the mass its colliders get from their
+    /// densities. The body gets the mass of its colliders when `None`.
+    pub mass: Option<F>,
+
+    /// Linear damping of the body, the one of the world when `None`.
+    pub linear_damping: Option<F>,
+
+    /// Angular damping of the body, the one of the world when `None`.
+    pub angular_damping: Option<F>,
+
+    /// Multiplier of the gravity pulling the body.
+    pub gravity_scale: F,
+
+    /// Dominance of the body over the ones it touches.
+    pub dominance: i8,
+
+    /// Axes of world space the body can't move along, or turn around.
+    pub locked_axes: AxisLocks,
+
+    /// Colliders attached to the body.
+    pub colliders: Vec<SceneCollider<F>>,
+}
+
+/// Collider of a scene description, attached to the body listing it.
+/// Every field but the shape has a default.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SceneCollider<F: num_traits::Float = f64> {
+    /// Shape of the collider.
+    pub shape: Shape<F>,
+
+    /// Position of the shape in the local space of the body.
+    #[serde(default = "Vector3::origin")]
+    pub position: Vector3<F>,
+
+    /// Orientation of the shape in the local space of the body.
+    #[serde(default = "Quaternion::identity")]
+    pub orientation: Quaternion<F>,
+
+    /// Name of the material the collider is made of, the default one if `None`.
+    #[serde(default)]
+    pub material: Option<String>,
+
+    /// Bitfield of the collision groups the collider belongs to.
+    #[serde(default = "default_group")]
+    pub group: u32,
+
+    /// Bitfield of the collision groups the collider interacts with.
+    #[serde(default = "default_mask")]
+    pub mask: u32,
+
+    /// Only detects the colliders overlapping it when true, without generating contacts.
+    #[serde(default)]
+    pub sensor: bool,
+
+    /// Distance at which the collider starts generating speculative contacts.
+    #[serde(default = "num_traits::zero")]
+    pub contact_margin: F,
+
+    /// Linear velocity of the surface of the collider, in its local space.
+    #[serde(default = "Vector3::origin")]
+    pub surface_velocity: Vector3<F>,
+
+    /// Angular velocity of the surface of the collider around its origin, in its local space.
+    #[serde(default = "Vector3::origin")]
+    pub surface_rotation: Vector3<F>,
+
+    /// Direction, in the local space of the collider, towards which it pushes what touches it,
+    /// making it one-way, or `None` to block everything from every side.
+    #[serde(default = "Option::default")]
+    pub one_way: Option<Vector3<F>>,
+}
+
+/// Joint of a scene description.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SceneJoint<F: num_traits::Float = f64> {
+    /// Index of the first body in the scene.
+    pub body_one: usize,
+
+    /// Index of the second body in the scene, or `None` to attach the first one to the world.
+    pub body_two: Option<usize>,
+
+    /// Kind of the joint, with its anchors and axes in the local spaces of the bodies.
+    pub kind: JointKind<F>,
+
+    /// Force beyond which the joint breaks, if any.
+    pub break_force: Option<F>,
+
+    /// Torque beyond which the joint breaks, if any.
+    pub break_torque: Option<F>,
+}
+
+/// Error raised while loading or saving a scene description.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SceneError {
+    /// The description couldn't be parsed or written, described by the message.
+    Format(String),
+
+    /// A collider or the scenery uses a material the scene doesn't define, by name.
+    Material(String),
+
+    /// A joint refers to a body that isn't in the scene, by index.
+    Body(usize),
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Format(message) => write!(f, "invalid scene: {}", message),
+            SceneError::Material(name) => write!(f, "unknown material {:?}", name),
+            SceneError::Body(index) => write!(f, "unknown body {}", index),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl<F: num_traits::Float> Default for Scene<F> {
+    fn default() -> Self {
+        Self {
+            config: WorldConfig::default(),
+            gravity: GravityField::default(),
+            materials: BTreeMap::new(),
+            planes: Vec::new(),
+            scenery_material: None,
+            bodies: Vec::new(),
+            joints: Vec::new(),
+        }
+    }
+}
+
+impl<F: num_traits::Float> Default for SceneBody<F> {
+    fn default() -> Self {
+        Self {
+            name: None,
+            body_type: BodyType::Dynamic,
+            position: Vector3::origin(),
+            orientation: Quaternion::identity(),
+            velocity: Vector3::origin(),
+            rotation: Vector3::origin(),
+            mass: None,
+            linear_damping: None,
+            angular_damping: None,
+            gravity_scale: F::one(),
+            dominance: 0,
+            locked_axes: AxisLocks::none(),
+            colliders: Vec::new(),
+        }
+    }
+}
+
+impl<F: num_traits::Float> SceneBody<F> {
+    /// Adds the body to the world with its colliders, made of the given materials, and
+    /// returns the index of the body and the ones of its colliders.
+    pub(crate) fn add_to(
+        &self,
+        world: &mut World<F>,
+        materials: &[PhysicsMaterial<F>],
+    ) -> (usize, Vec<usize>) {
+        let mut body = RigidBody::new(self.position, F::one(), &Matrix3::identity());
+        body.orientation = self.orientation.normalize();
+        body.velocity = self.velocity;
+        body.rotation = self.rotation;
+        body.linear_damping = self.linear_damping;
+        body.angular_damping = self.angular_damping;
+        body.gravity_scale = self.gravity_scale;
+        body.dominance = self.dominance;
+        body.locked_axes = self.locked_axes;
+        body.set_body_type(self.body_type);
+        body.calculate_derived_data();
+        let index = world.add_body(body);
+        let index = world.body_index(index).unwrap();
+
+        let mut colliders = Vec::with_capacity(self.colliders.len());
+        for (collider, material) in self.colliders.iter().zip(materials.iter()) {
+            let offset = Matrix4::from_orientation_and_position(
+                &collider.orientation.normalize(),
+                &collider.position,
+            );
+            let mut added = Collider::with_offset(index, collider.shape.clone(), offset);
+            added.material = *material;
+            added.with_groups(collider.group, collider.mask);
+            added.sensor = collider.sensor;
+            added.contact_margin = collider.contact_margin;
+            added.surface_velocity = collider.surface_velocity;
+            added.surface_rotation = collider.surface_rotation;
+            added.one_way = collider.one_way;
+            let added = world.add_collider(added);
+            colliders.push(world.collider_index(added).unwrap());
+        }
+
+        // Dynamic bodies get their mass once all of their colliders are attached. The others
+        // keep their origin, since they're moved by hand.
+        if self.body_type != BodyType::Dynamic {
+            return (index, colliders);
+        }
+        let properties = world.mass_properties(index);
+        match self.mass {
+            Some(mass) if properties.mass > F::zero() => {
+                world.set_mass_properties(index, &properties.with_mass(mass))
+            }
+            Some(mass) => {
+                world.bodies[index].set_mass(mass);
+            }
+            None => {
+                world.update_mass_properties(index);
+            }
+        }
+        (index, colliders)
+    }
+}
+
+impl<F: num_traits::Float> SceneCollider<F> {
+    /// Creates a new collider description with the given shape, at the origin of its body.
+    pub fn new(shape: Shape<F>) -> Self {
+        Self {
+            shape,
+            position: Vector3::origin(),
+            orientation: Quaternion::identity(),
+            material: None,
+            group: DEFAULT_COLLISION_GROUP,
+            mask: DEFAULT_COLLISION_MASK,
+            sensor: false,
+            contact_margin: F::zero(),
+            surface_velocity: Vector3::origin(),
+            surface_rotation: Vector3::origin(),
+            one_way: None,
+        }
+    }
+}
+
+fn default_group() -> u32 {
+    DEFAULT_COLLISION_GROUP
+}
+
+fn default_mask() -> u32 {
+    DEFAULT_COLLISION_MASK
+}
+
+impl<F: num_traits::Float> Scene<F> {
+    /// Creates a new world with the configuration and gravity of the scene,
+    /// and everything in it.
+    pub fn build(&self) -> Result<World<F>, SceneError> {
+        let mut world = World::new(self.config);
+        world.gravity = self.gravity.clone();
+        self.instantiate(&mut world)?;
+        Ok(world)
+    }
+
+    /// Adds the bodies, colliders, joints and planes of the scene to the world, keeping its
+    /// configuration and gravity, and returns the indices they got in the world by their
+    /// index in the scene. Colliders are numbered in the order the bodies list them.
+    ///
+    /// # Remarks
+    /// Dynamic bodies are moved onto the center of mass of their colliders, as with
+    /// `World::set_mass_properties`, so joint anchors are best placed in scenes exported from
+    /// a world. The scene is checked before anything is added, so the world is left untouched
+    /// when it refers to missing materials or bodies. The scenery material of the world is only
+    /// replaced when the scene names one.
+    pub fn instantiate(&self, world: &mut World<F>) -> Result<IndexRemap, SceneError> {
+        let material = |name: &Option<String>| match name {
+            Some(name) => self
+                .materials
+                .get(name)
+                .copied()
+                .ok_or_else(|| SceneError::Material(name.clone())),
+            None => Ok(PhysicsMaterial::default()),
+        };
+        let mut materials = Vec::new();
+        for collider in self.bodies.iter().flat_map(|body| body.colliders.iter()) {
+            materials.push(material(&collider.material)?);
+        }
+        let scenery_material = match self.scenery_material {
+            Some(_) => Some(material(&self.scenery_material)?),
+            None => None,
+        };
+        for joint in self.joints.iter() {
+            for body in std::iter::once(joint.body_one).chain(joint.body_two) {
+                if body >= self.bodies.len() {
+                    return Err(SceneError::Body(body));
+                }
+            }
+        }
+
+        let mut remap = IndexRemap::default();
+        let mut materials = materials.as_slice();
+        for description in self.bodies.iter() {
+            let (used, rest) = materials.split_at(description.colliders.len());
+            materials = rest;
+            let (body, colliders) = description.add_to(world, used);
+            remap.bodies.push(body);
+            remap.colliders.extend(colliders);
+        }
+
+        for joint in self.joints.iter() {
+            let bodies = (
+                remap.bodies[joint.body_one],
+                joint.body_two.map(|body| remap.bodies[body]),
+            );
+            let mut added = Joint::new(bodies, joint.kind);
+            added.break_force = joint.break_force;
+            added.break_torque = joint.break_torque;
+            let added = world.add_joint(added);
+            remap.joints.push(world.joint_index(added).unwrap());
+        }
+        world.planes.extend(self.planes.iter().copied());
+        if let Some(material) = scenery_material {
+            world.scenery_material = material;
+        }
+        Ok(remap)
+    }
+
+    /// Describes the rigid bodies, colliders, joints and scenery of the world as they are now,
+    /// so they can be saved and edited.
+    ///
+    /// # Remarks
+    /// The materials of the world are named `material_0`, `material_1` and so on, in the order
+    /// the colliders first use them. Dynamic bodies keep their mass, and get the inertia
+    /// of their colliders back when loaded. Soft bodies, fluids and force generators aren't
+    /// part of scene descriptions, and are left out.
+    pub fn from_world(world: &World<F>) -> Self {
+        let mut materials: Vec<PhysicsMaterial<F>> = Vec::new();
+        let mut name = |material: &PhysicsMaterial<F>| {
+            let index = match materials.iter().position(|known| known == material) {
+                Some(index) => index,
+                None => {
+                    materials.push(*material);
+                    materials.len() - 1
+                }
+            };
+            format!("material_{}", index)
+        };
+
+        let mut bodies: Vec<SceneBody<F>> = world
+            .bodies
+            .iter()
+            .map(|body| SceneBody {
+                name: None,
+                body_type: body.body_type,
+                position: body.position,
+                orientation: body.orientation,
+                velocity: body.velocity,
+                rotation: body.rotation,
+                mass: match body.body_type {
+                    BodyType::Dynamic if body.inverse_mass > F::zero() => Some(body.mass()),
+                    _ => None,
+                },
+                linear_damping: body.linear_damping,
+                angular_damping: body.angular_damping,
+                gravity_scale: body.gravity_scale,
+                dominance: body.dominance,
+                locked_axes: body.locked_axes,
+                colliders: Vec::new(),
+            })
+            .collect();
+        for collider in world.colliders.iter() {
+            bodies[collider.body].colliders.push(SceneCollider {
+                shape: collider.shape.clone(),
+                position: collider.offset.translation(),
+                orientation: Quaternion::from_rotation_matrix(&collider.offset.rotation()),
+                material: Some(name(&collider.material)),
+                group: collider.group,
+                mask: collider.mask,
+                sensor: collider.sensor,
+                contact_margin: collider.contact_margin,
+                surface_velocity: collider.surface_velocity,
+                surface_rotation: collider.surface_rotation,
+                one_way: collider.one_way,
+            });
+        }
+        let scenery_material = if world.planes.is_empty() {
+            None
+        } else {
+            Some(name(&world.scenery_material))
+        };
+
+        Self {
+            config: world.config,
+            gravity: world.gravity.clone(),
+            materials: materials
+                .into_iter()
+                .enumerate()
+                .map(|(index, material)| (format!("material_{}", index), material))
+                .collect(),
+            planes: world.planes.clone(),
+            scenery_material,
+            bodies,
+            joints: world
+                .joints
+                .iter()
+                .map(|joint| SceneJoint {
+                    body_one: joint.bodies.0,
+                    body_two: joint.bodies.1,
+                    kind: joint.kind,
+                    break_force: joint.break_force,
+                    break_torque: joint.break_torque,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "ron")]
+impl<F: num_traits::Float + Serialize + serde::de::DeserializeOwned> Scene<F> {
+    /// Parses a scene description written in RON.
+    pub fn from_ron(text: &str) -> Result<Self, SceneError> {
+        ron::from_str(text).map_err(|error| SceneError::Format(error.to_string()))
+    }
+
+    /// Writes the scene description in RON, formatted to be read and edited.
+    pub fn to_ron(&self) -> Result<String, SceneError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|error| SceneError::Format(error.to_string()))
+    }
+}
+
+#[cfg(feature = "toml")]
+impl<F: num_traits::Float + Serialize + serde::de::DeserializeOwned> Scene<F> {
+    /// Parses a scene description written in TOML.
+    pub fn from_toml(text: &str) -> Result<Self, SceneError> {
+        toml::from_str(text).map_err(|error| SceneError::Format(error.to_string()))
+    }
+
+    /// Writes the scene description in TOML, formatted to be read and edited.
+    pub fn to_toml(&self) -> Result<String, SceneError> {
+        toml::to_string_pretty(self).map_err(|error| SceneError::Format(error.to_string()))
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::ball_joint::BallJoint;
+use crate::gravity::{GravityField, GravitySource};
+use crate::joint::JointKind;
+use crate::material::PhysicsMaterial;
+use crate::plane::Plane;
+use crate::rigid_body::BodyType;
+use crate::scene::*;
+use crate::shape::{Cuboid, Shape, Sphere};
+use math::{Quaternion, Vector3};
+
+/// Returns a scene with a static floor, a heavy ball hanging from the world,
+/// and a rotated box made of a named material.
+fn scene() -> Scene {
+    let mut scene = Scene {
+        gravity: GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0))),
+        planes: vec![Plane::new(Vector3::new(0.0, 1.0, 0.0), -5.0)],
+        scenery_material: Some(String::from("stone")),
+        ..Scene::default()
+    };
+    scene
+        .materials
+        .insert(String::from("rubber"), PhysicsMaterial::new(0.8, 0.9, 2.0));
+    scene
+        .materials
+        .insert(String::from("stone"), PhysicsMaterial::new(0.1, 0.7, 3.0));
+
+    scene.bodies.push(SceneBody {
+        name: Some(String::from("floor")),
+        body_type: BodyType::Static,
+        colliders: vec![SceneCollider::new(Shape::Cuboid(Cuboid::new(
+            Vector3::new(10.0, 0.5, 10.0),
+        )))],
+        ..SceneBody::default()
+    });
+    scene.bodies.push(SceneBody {
+        name: Some(String::from("ball")),
+        position: Vector3::new(0.0, 3.0, 0.0),
+        mass: Some(5.0),
+        colliders: vec![SceneCollider::new(Shape::Sphere(Sphere::new(0.5)))],
+        ..SceneBody::default()
+    });
+    let mut block = SceneCollider::new(Shape::Cuboid(Cuboid::new(Vector3::new(0.5, 0.5, 0.5))));
+    block.position = Vector3::new(0.0, 0.5, 0.0);
+    block.orientation =
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), std::f64::consts::FRAC_PI_4);
+    block.material = Some(String::from("rubber"));
+    scene.bodies.push(SceneBody {
+        position: Vector3::new(3.0, 1.0, 0.0),
+        velocity: Vector3::new(-1.0, 0.0, 0.0),
+        colliders: vec![block],
+        ..SceneBody::default()
+    });
+    scene.joints.push(SceneJoint {
+        body_one: 1,
+        body_two: None,
+        kind: JointKind::Ball(BallJoint::new(
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 4.0, 0.0),
+        )),
+        break_force: Some(100.0),
+        break_torque: None,
+    });
+    scene
+}
+
+#[test]
+fn build() {
+    let scene = scene();
+    let world = scene.build().unwrap();
+    assert_eq!(3, world.bodies.len());
+    assert_eq!(3, world.colliders.len());
+    assert_eq!(scene.gravity, world.gravity);
+    assert_eq!(scene.planes, world.planes);
+    assert_eq!(scene.materials["stone"], world.scenery_material);
+
+    // Bodies get the mass they're given, or the one of their colliders.
+    assert_eq!(BodyType::Static, world.bodies[0].body_type);
+    assert!((world.bodies[1].mass() - 5.0).abs() < 1e-9);
+    assert!((world.bodies[2].mass() - 2.0).abs() < 1e-9);
+    assert_eq!(Vector3::new(-1.0, 0.0, 0.0), world.bodies[2].velocity);
+    assert_eq!(scene.materials["rubber"], world.colliders[2].material);
+    assert_eq!(PhysicsMaterial::default(), world.colliders[1].material);
+
+    // Bodies are moved onto the center of mass of their colliders.
+    assert_eq!(2, world.colliders[2].body);
+    assert_eq!(Vector3::new(3.0, 1.5, 0.0), world.bodies[2].position);
+    assert_eq!(Vector3::origin(), world.colliders[2].offset.translation());
+
+    assert_eq!(1, world.joints.len());
+    assert_eq!((1, None), world.joints[0].bodies);
+    assert_eq!(Some(100.0), world.joints[0].break_force);
+
+    // Scenes added to a world are numbered after what it already has.
+    let mut world = world;
+    let remap = scene.instantiate(&mut world).unwrap();
+    assert_eq!(vec![3, 4, 5], remap.bodies);
+    assert_eq!(vec![3, 4, 5], remap.colliders);
+    assert_eq!(vec![1], remap.joints);
+    assert_eq!((4, None), world.joints[1].bodies);
+
+    // Scenes referring to missing materials or bodies aren't added at all.
+    let mut broken = scene.clone();
+    broken.bodies[0].colliders[0].material = Some(String::from("ice"));
+    assert_eq!(
+        Err(SceneError::Material(String::from("ice"))),
+        broken.instantiate(&mut world)
+    );
+    let mut broken = scene;
+    broken.joints[0].body_two = Some(7);
+    assert_eq!(Err(SceneError::Body(7)), broken.instantiate(&mut world));
+    assert_eq!(6, world.bodies.len());
+}
+
+#[test]
+fn export() {
+    let world = scene().build().unwrap();
+    let exported = Scene::from_world(&world);
+    assert_eq!(3, exported.materials.len());
+    assert_eq!(Some(5.0), exported.bodies[1].mass.map(|mass| mass.round()));
+    assert_eq!(None, exported.bodies[0].mass);
+    let block = &exported.bodies[2].colliders[0];
+    let expected =
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), std::f64::consts::FRAC_PI_4);
+    let point = Vector3::new(1.0, 0.0, 0.0);
+    assert!((block.orientation.rotate(&point) - expected.rotate(&point)).magnitude() < 1e-9);
+
+    // Exported scenes build the same world again.
+    let rebuilt = exported.build().unwrap();
+    for (one, two) in world.bodies.iter().zip(rebuilt.bodies.iter()) {
+        assert_eq!(one.position, two.position);
+        assert!((one.inverse_mass - two.inverse_mass).abs() < 1e-12);
+    }
+    for (one, two) in world.colliders.iter().zip(rebuilt.colliders.iter()) {
+        assert_eq!(one.material, two.material);
+        assert!((one.offset.transform(&point) - two.offset.transform(&point)).magnitude() < 1e-9);
+    }
+    assert_eq!(world.joints, rebuilt.joints);
+    assert_eq!(world.scenery_material, rebuilt.scenery_material);
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn ron() {
+    let scene: Scene = Scene::from_ron(
+        r#"(
+            gravity: (global: Uniform((x: 0.0, y: -9.8, z: 0.0))),
+            materials: { "ice": (
+                restitution: 0.0,
+                friction: 0.05,
+                static_friction: 0.1,
+                density: 0.9,
+                friction_combine: Min,
+                restitution_combine: Average,
+            ) },
+            bodies: [
+                (body_type: Static, colliders: [(
+                    shape: Cuboid((half_size: (x: 5.0, y: 0.5, z: 5.0))),
+                    material: Some("ice"),
+                )]),
+                (position: (x: 0.0, y: 2.0, z: 0.0), colliders: [(shape: Sphere((radius: 0.5)))]),
+            ],
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(2, scene.bodies.len());
+    assert_eq!(
+        Some(String::from("ice")),
+        scene.bodies[0].colliders[0].material
+    );
+    let world = scene.build().unwrap();
+    assert_eq!(0.05, world.colliders[0].material.friction);
+    assert_eq!(Vector3::new(0.0, 2.0, 0.0), world.bodies[1].position);
+
+    let text = self::scene().to_ron().unwrap();
+    assert_eq!(self::scene(), Scene::from_ron(&text).unwrap());
+    assert!(matches!(
+        Scene::<f64>::from_ron("(bodies: [(mass: heavy)])"),
+        Err(SceneError::Format(_))
+    ));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml() {
+    let scene: Scene = Scene::from_toml(
+        r#"
+            [gravity.global]
+            Uniform = { x = 0.0, y = -9.8, z = 0.0 }
+
+            [[bodies]]
+            body_type = "Static"
+            colliders = [{ shape = { Cuboid = { half_size = { x = 5.0, y = 0.5, z = 5.0 } } } }]
+
+            [[bodies]]
+            position = { x = 0.0, y = 2.0, z = 0.0 }
+            mass = 3.0
+            colliders = [{ shape = { Sphere = { radius = 0.5 } } }]
+        "#,
+    )
+    .unwrap();
+    let world = scene.build().unwrap();
+    assert_eq!(2, world.bodies.len());
+    assert!((world.bodies[1].mass() - 3.0).abs() < 1e-9);
+
+    let text = self::scene().to_toml().unwrap();
+    assert_eq!(self::scene(), Scene::from_toml(&text).unwrap());
+    assert!(matches!(
+        Scene::<f64>::from_toml("bodies = 1"),
+        Err(SceneError::Format(_))
+    ));
+}
@@ -0,0 +1,882 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::mass_properties::parallel_axis;
+use crate::trimesh::TriMesh;
+use math::{Matrix3, Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Polymorphic interface for convex shapes described by their support function.
+///
+/// # Remarks
+/// The support point of a shape in a direction is its furthest point along that direction.
+/// It fully describes a convex shape, so generic algorithms like GJK can work with any
+/// pair of shapes implementing this trait, instead of one routine per pair of shapes.
+pub trait SupportMap<F: num_traits::Float = f64> {
+    /// Returns the support point of the shape in local space, along the given local direction.
+    /// The direction doesn't need to be normalized.
+    fn local_support_point(&self, direction: &Vector3<F>) -> Vector3<F>;
+
+    /// Returns the support point of the shape placed with the given transform,
+    /// along the given world direction.
+    fn support_point(&self, transform: &Matrix4<F>, direction: &Vector3<F>) -> Vector3<F> {
+        let local_direction = transform.transform_inverse_direction(direction);
+        transform.transform(&self.local_support_point(&local_direction))
+    }
+}
+
+/// Shapes shared between colliders have the support points of the shapes they point to.
+impl<F: num_traits::Float, S: SupportMap<F> + ?Sized> SupportMap<F> for Arc<S> {
+    fn local_support_point(&self, direction: &Vector3<F>) -> Vector3<F> {
+        (**self).local_support_point(direction)
+    }
+
+    fn support_point(&self, transform: &Matrix4<F>, direction: &Vector3<F>) -> Vector3<F> {
+        (**self).support_point(transform, direction)
+    }
+}
+
+/// Sphere centered at the origin of its local space.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Sphere<F: num_traits::Float = f64> {
+    /// Radius of the sphere.
+    pub radius: F,
+}
+
+impl<F: num_traits::Float> Sphere<F> {
+    /// Creates a new sphere with the given radius.
+    pub fn new(radius: F) -> Self {
+        Self { radius }
+    }
+
+    /// Returns the bounding box of the sphere placed with the given transform.
+    pub fn aabb(&self, transform: &Matrix4<F>) -> Aabb<F> {
+        Aabb::from_center(
+            &transform.translation(),
+            &Vector3::new(self.radius, self.radius, self.radius),
+        )
+    }
+
+    /// Returns the volume of the sphere.
+    pub fn volume(&self) -> F {
+        math::real::<F>(4.0 / 3.0 * std::f64::consts::PI) * self.radius * self.radius * self.radius
+    }
+
+    /// Returns the inertia tensor of a solid sphere with the given mass.
+    pub fn inertia_tensor(&self, mass: F) -> Matrix3<F> {
+        let moment = math::real::<F>(0.4) * mass * self.radius * self.radius;
+        Matrix3::diagonal(moment, moment, moment)
+    }
+}
+
+impl<F: num_traits::Float> SupportMap<F> for Sphere<F> {
+    fn local_support_point(&self, direction: &Vector3<F>) -> Vector3<F> {
+        if direction.squared_magnitude() > num_traits::zero() {
+            direction.normalize().scalar_mul(self.radius)
+        } else {
+            Vector3::new(self.radius, num_traits::zero(), num_traits::zero())
+        }
+    }
+}
+
+/// Rectangular box centered at the origin of its local space, aligned with its axes.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Cuboid<F: num_traits::Float = f64> {
+    /// Half of the size of the box along each of its axes.
+    pub half_size: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Cuboid<F> {
+    /// Creates a new box with the given half-sizes.
+    pub fn new(half_size: Vector3<F>) -> Self {
+        Self { half_size }
+    }
+
+    /// Returns the vertices of the box in local space.
+    pub fn vertices(&self) -> [Vector3<F>; 8] {
+        let h = &self.half_size;
+        [
+            Vector3::new(-h.x, -h.y, -h.z),
+            Vector3::new(-h.x, -h.y, h.z),
+            Vector3::new(-h.x, h.y, -h.z),
+            Vector3::new(-h.x, h.y, h.z),
+            Vector3::new(h.x, -h.y, -h.z),
+            Vector3::new(h.x, -h.y, h.z),
+            Vector3::new(h.x, h.y, -h.z),
+            Vector3::new(h.x, h.y, h.z),
+        ]
+    }
+
+    /// Returns the bounding box of the box placed with the given transform.
+    pub fn aabb(&self, transform: &Matrix4<F>) -> Aabb<F> {
+        let rotation = transform.rotation();
+        let mut half_extents = [num_traits::zero(); 3];
+        for (row, extent) in half_extents.iter_mut().enumerate() {
+            let row = rotation.row(row);
+            *extent = row.x.abs() * self.half_size.x
+                + row.y.abs() * self.half_size.y
+                + row.z.abs() * self.half_size.z;
+        }
+        Aabb::from_center(
+            &transform.translation(),
+            &Vector3::new(half_extents[0], half_extents[1], half_extents[2]),
+        )
+    }
+
+    /// Returns the volume of the box.
+    pub fn volume(&self) -> F {
+        math::real::<F>(8.0) * self.half_size.x * self.half_size.y * self.half_size.z
+    }
+
+    /// Returns the inertia tensor of a solid box with the given mass.
+    pub fn inertia_tensor(&self, mass: F) -> Matrix3<F> {
+        Matrix3::block_inertia_tensor(&self.half_size, mass)
+    }
+}
+
+impl<F: num_traits::Float> SupportMap<F> for Cuboid<F> {
+    fn local_support_point(&self, direction: &Vector3<F>) -> Vector3<F> {
+        let signed = |value: F, half_size: F| {
+            if value < num_traits::zero() {
+                -half_size
+            } else {
+                half_size
+            }
+        };
+        Vector3::new(
+            signed(direction.x, self.half_size.x),
+            signed(direction.y, self.half_size.y),
+            signed(direction.z, self.half_size.z),
+        )
+    }
+}
+
+/// Capsule centered at the origin of its local space: the set of points within `radius`
+/// of a segment along the local y axis, from `-half_height` to `half_height`.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Capsule<F: num_traits::Float = f64> {
+    /// Half of the length of the inner segment.
+    pub half_height: F,
+
+    /// Radius of the capsule.
+    pub radius: F,
+}
+
+impl<F: num_traits::Float> Capsule<F> {
+    /// Creates a new capsule with the given segment half-length and radius.
+    pub fn new(half_height: F, radius: F) -> Self {
+        Self {
+            half_height,
+            radius,
+        }
+    }
+
+    /// Returns the end points of the inner segment of the capsule placed with the given transform.
+    pub fn segment(&self, transform: &Matrix4<F>) -> (Vector3<F>, Vector3<F>) {
+        let zero = num_traits::zero();
+        (
+            transform.transform(&Vector3::new(zero, -self.half_height, zero)),
+            transform.transform(&Vector3::new(zero, self.half_height, zero)),
+        )
+    }
+
+    /// Returns the bounding box of the capsule placed with the given transform.
+    pub fn aabb(&self, transform: &Matrix4<F>) -> Aabb<F> {
+        let (start, end) = self.segment(transform);
+        let mut aabb = Aabb::new(start, start);
+        aabb.inplace_merge_point(&end);
+        aabb.loosened(self.radius)
+    }
+
+    /// Returns the volume of the capsule.
+    pub fn volume(&self) -> F {
+        let squared_radius = self.radius * self.radius;
+        math::real::<F>(std::f64::consts::PI)
+            * squared_radius
+            * (self.half_height * math::real(2.0) + self.radius * math::real(4.0 / 3.0))
+    }
+
+    /// Returns the inertia tensor of a solid capsule with the given mass.
+    ///
+    /// # Remarks
+    /// The mass is split between the cylinder and the two hemispherical caps
+    /// according to their volumes, and the caps are shifted to the ends of the cylinder.
+    pub fn inertia_tensor(&self, mass: F) -> Matrix3<F> {
+        let radius = self.radius;
+        let height = self.half_height * math::real(2.0);
+        let squared_radius = radius * radius;
+
+        // The common factor of pi cancels out in the mass ratios.
+        let cylinder_volume = squared_radius * height;
+        let caps_volume = math::real::<F>(4.0 / 3.0) * squared_radius * radius;
+        let cylinder_mass = mass * cylinder_volume / (cylinder_volume + caps_volume);
+        let caps_mass = mass - cylinder_mass;
+
+        let axial = cylinder_mass * squared_radius * math::real(0.5)
+            + caps_mass * squared_radius * math::real(0.4);
+        let lateral = cylinder_mass
+            * (squared_radius * math::real(0.25) + height * height / math::real(12.0))
+            + caps_mass
+                * (squared_radius * math::real(0.4)
+                    + height * height * math::real(0.25)
+                    + height * radius * math::real(3.0 / 8.0));
+        Matrix3::diagonal(lateral, axial, lateral)
+    }
+}
+
+impl<F: num_traits::Float> SupportMap<F> for Capsule<F> {
+    fn local_support_point(&self, direction: &Vector3<F>) -> Vector3<F> {
+        let mut point = Sphere::new(self.radius).local_support_point(direction);
+        if direction.y < num_traits::zero() {
+            point.y = point.y - self.half_height;
+        } else {
+            point.y = point.y + self.half_height;
+        }
+        point
+    }
+}
+
+/// Tolerance used to decide whether points are coplanar while building convex hulls,
+/// relative to the size of the point cloud.
+const HULL_EPSILON: f64 = 1e-9;
+
+/// Polygonal face of a convex hull.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HullFace<F: num_traits::Float = f64> {
+    /// Indices of the vertices of the face, in counter-clockwise order seen from outside.
+    pub vertices: Vec<usize>,
+
+    /// Outward unit normal of the face.
+    pub normal: Vector3<F>,
+
+    /// Distance of the plane of the face from the origin along its normal.
+    pub offset: F,
+}
+
+/// Convex polyhedron, described by its vertices, faces and edges in local space.
+///
+/// # Remarks
+/// Hulls are usually built from a point cloud with `from_points`, which discards the
+/// points inside the hull and merges coplanar triangles into polygonal faces. Building
+/// a hull is expensive, so precomputed hulls can be stored and loaded with serde.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ConvexHull<F: num_traits::Float = f64> {
+    /// Vertices of the hull.
+    pub vertices: Vec<Vector3<F>>,
+
+    /// Faces of the hull.
+    pub faces: Vec<HullFace<F>>,
+
+    /// Edges of the hull, as pairs of vertex indices.
+    pub edges: Vec<[usize; 2]>,
+}
+
+impl<F: num_traits::Float> ConvexHull<F> {
+    /// Computes the convex hull of the given points.
+    /// Returns `None` if the points are all coplanar, since they don't enclose any volume.
+    pub fn from_points(points: &[Vector3<F>]) -> Option<Self> {
+        let triangles = hull_triangles(points)?;
+        Some(Self::from_triangles(points, &triangles))
+    }
+
+    /// Returns the bounding box of the hull placed with the given transform.
+    pub fn aabb(&self, transform: &Matrix4<F>) -> Aabb<F> {
+        let vertices: Vec<Vector3<F>> = self
+            .vertices
+            .iter()
+            .map(|vertex| transform.transform(vertex))
+            .collect();
+        Aabb::from_points(vertices.iter()).expect("hulls have vertices")
+    }
+
+    /// Returns the index of the face whose normal is most aligned with the given local direction.
+    pub fn face_along(&self, direction: &Vector3<F>) -> usize {
+        let mut best = 0;
+        let mut best_alignment = F::neg_infinity();
+        for (index, face) in self.faces.iter().enumerate() {
+            let alignment = face.normal.dot_product(direction);
+            if alignment > best_alignment {
+                best = index;
+                best_alignment = alignment;
+            }
+        }
+        best
+    }
+
+    /// Returns the volume of the hull.
+    pub fn volume(&self) -> F {
+        self.mass_properties().0
+    }
+
+    /// Returns the center of mass of the hull in local space, assuming a uniform density.
+    pub fn center_of_mass(&self) -> Vector3<F> {
+        self.mass_properties().1
+    }
+
+    /// Returns the inertia tensor of a solid hull with the given mass and uniform density.
+    ///
+    /// # Remarks
+    /// The inertia tensor is relative to the center of mass of the hull, which isn't
+    /// necessarily the origin of its local space. Place the hull with an offset of minus
+    /// its center of mass to line them up with the body.
+    pub fn inertia_tensor(&self, mass: F) -> Matrix3<F> {
+        let (volume, center, covariance) = self.mass_properties();
+        if volume <= num_traits::zero() {
+            return Matrix3::zero();
+        }
+
+        // Move the covariance to the center of mass, and scale it to the given mass.
+        let shift = Matrix3::from_components(
+            &center.scalar_mul(center.x),
+            &center.scalar_mul(center.y),
+            &center.scalar_mul(center.z),
+        );
+        let covariance = covariance
+            .matrix_add(&shift.scalar_mul(-volume))
+            .scalar_mul(mass / volume);
+        let trace = covariance.data[0] + covariance.data[4] + covariance.data[8];
+        Matrix3::diagonal(trace, trace, trace).matrix_add(&covariance.scalar_mul(-F::one()))
+    }
+
+    /// Computes the volume, center of mass and covariance matrix of the hull with unit density,
+    /// by splitting it in tetrahedra with the origin.
+    fn mass_properties(&self) -> (F, Vector3<F>, Matrix3<F>) {
+        let mut volume = num_traits::zero();
+        let mut weighted_center = Vector3::origin();
+        let mut covariance = Matrix3::zero();
+        let outer = |a: &Vector3<F>, b: &Vector3<F>| {
+            Matrix3::from_components(&b.scalar_mul(a.x), &b.scalar_mul(a.y), &b.scalar_mul(a.z))
+        };
+
+        for face in self.faces.iter() {
+            let a = self.vertices[face.vertices[0]];
+            for pair in face.vertices[1..].windows(2) {
+                let (b, c) = (self.vertices[pair[0]], self.vertices[pair[1]]);
+                let determinant = a.dot_product(&b.cross_product(&c));
+                let sum = a.vector_add(&b).vector_add(&c);
+
+                volume = volume + determinant / math::real(6.0);
+                weighted_center.inplace_vector_add(&sum.scalar_mul(determinant / math::real(24.0)));
+                covariance.inplace_matrix_add(
+                    &outer(&sum, &sum)
+                        .matrix_add(&outer(&a, &a))
+                        .matrix_add(&outer(&b, &b))
+                        .matrix_add(&outer(&c, &c))
+                        .scalar_mul(determinant / math::real(120.0)),
+                );
+            }
+        }
+
+        let center = if volume > num_traits::zero() {
+            weighted_center.scalar_div(volume)
+        } else {
+            Vector3::origin()
+        };
+        (volume, center, covariance)
+    }
+
+    /// Builds the hull from the triangles of its surface, merging coplanar triangles into faces.
+    fn from_triangles(points: &[Vector3<F>], triangles: &[[usize; 3]]) -> Self {
+        let epsilon = hull_epsilon(points);
+
+        // Group the triangles by plane.
+        let mut faces: Vec<HullFace<F>> = Vec::new();
+        for triangle in triangles.iter() {
+            let (a, b, c) = (
+                points[triangle[0]],
+                points[triangle[1]],
+                points[triangle[2]],
+            );
+            let normal = b
+                .vector_sub(&a)
+                .cross_product(&c.vector_sub(&a))
+                .normalize();
+            let offset = normal.dot_product(&a);
+            let existing = faces.iter_mut().find(|face| {
+                face.normal.dot_product(&normal) > F::one() - epsilon
+                    && (face.offset - offset).abs() <= epsilon
+            });
+            match existing {
+                Some(face) => {
+                    for index in triangle.iter() {
+                        if !face.vertices.contains(index) {
+                            face.vertices.push(*index);
+                        }
+                    }
+                }
+                None => faces.push(HullFace {
+                    vertices: triangle.to_vec(),
+                    normal,
+                    offset,
+                }),
+            }
+        }
+
+        // Coplanar points inside of the faces or along their edges aren't vertices.
+        for face in faces.iter_mut() {
+            face.vertices = face_outline(points, face, epsilon);
+        }
+
+        // Keep only the points used by the faces.
+        let mut remap = vec![None; points.len()];
+        let mut vertices = Vec::new();
+        for face in faces.iter_mut() {
+            for index in face.vertices.iter_mut() {
+                *index = *remap[*index].get_or_insert_with(|| {
+                    vertices.push(points[*index]);
+                    vertices.len() - 1
+                });
+            }
+        }
+
+        let mut edges = Vec::new();
+        for face in faces.iter() {
+            for (position, start) in face.vertices.iter().enumerate() {
+                let end = face.vertices[(position + 1) % face.vertices.len()];
+                let edge = [(*start).min(end), (*start).max(end)];
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        Self {
+            vertices,
+            faces,
+            edges,
+        }
+    }
+}
+
+impl<F: num_traits::Float> SupportMap<F> for ConvexHull<F> {
+    fn local_support_point(&self, direction: &Vector3<F>) -> Vector3<F> {
+        let mut best = self.vertices[0];
+        let mut best_distance = best.dot_product(direction);
+        for vertex in self.vertices[1..].iter() {
+            let distance = vertex.dot_product(direction);
+            if distance > best_distance {
+                best = *vertex;
+                best_distance = distance;
+            }
+        }
+        best
+    }
+}
+
+/// Returns the indices of the points of the face that are corners of its outline,
+/// in counter-clockwise order seen from outside.
+///
+/// # Remarks
+/// The points are projected onto the plane of the face, and wrapped with Andrew's monotone
+/// chain, dropping the ones within `epsilon` of the outline of the others.
+fn face_outline<F: num_traits::Float>(
+    points: &[Vector3<F>],
+    face: &HullFace<F>,
+    epsilon: F,
+) -> Vec<usize> {
+    let origin = points[face.vertices[0]];
+    let u = face
+        .vertices
+        .iter()
+        .map(|index| points[*index].vector_sub(&origin))
+        .fold(Vector3::origin(), |longest: Vector3<F>, offset| {
+            if offset.magnitude() > longest.magnitude() {
+                offset
+            } else {
+                longest
+            }
+        })
+        .normalize();
+    let v = face.normal.cross_product(&u);
+    let mut projected: Vec<(F, F, usize)> = face
+        .vertices
+        .iter()
+        .map(|index| {
+            let offset = points[*index].vector_sub(&origin);
+            (offset.dot_product(&u), offset.dot_product(&v), *index)
+        })
+        .collect();
+    projected.sort_by(|a, b| {
+        (a.0, a.1)
+            .partial_cmp(&(b.0, b.1))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // Whether `c` turns left of the line from `a` to `b` by more than the tolerance.
+    let turns_left = |a: &(F, F, usize), b: &(F, F, usize), c: &(F, F, usize)| {
+        let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+        let cross = abx * (c.1 - a.1) - aby * (c.0 - a.0);
+        cross > epsilon * (abx * abx + aby * aby).sqrt()
+    };
+    let mut outline: Vec<(F, F, usize)> = Vec::with_capacity(projected.len() + 1);
+    for pass in 0..2 {
+        let start = outline.len();
+        let mut chain = |point: &(F, F, usize)| {
+            while outline.len() >= start + 2
+                && !turns_left(
+                    &outline[outline.len() - 2],
+                    &outline[outline.len() - 1],
+                    point,
+                )
+            {
+                outline.pop();
+            }
+            outline.push(*point);
+        };
+        if pass == 0 {
+            projected.iter().for_each(&mut chain);
+        } else {
+            projected.iter().rev().for_each(&mut chain);
+        }
+        // The last point of each chain is the first one of the next.
+        outline.pop();
+    }
+    outline.iter().map(|point| point.2).collect()
+}
+
+/// Returns the tolerance used to build the hull of the given points.
+fn hull_epsilon<F: num_traits::Float>(points: &[Vector3<F>]) -> F {
+    let size = Aabb::from_points(points.iter())
+        .map(|aabb| aabb.max.vector_sub(&aabb.min).magnitude())
+        .unwrap_or_else(num_traits::zero);
+    math::real::<F>(HULL_EPSILON) * size.max(num_traits::one())
+}
+
+/// Computes the triangles of the surface of the convex hull of the given points,
+/// with an incremental algorithm. The triangles are wound counter-clockwise seen from outside.
+/// Returns `None` if the points are all coplanar.
+fn hull_triangles<F: num_traits::Float>(points: &[Vector3<F>]) -> Option<Vec<[usize; 3]>> {
+    let epsilon = hull_epsilon(points);
+    let distance_to_plane = |triangle: &[usize; 3], point: &Vector3<F>| {
+        let a = points[triangle[0]];
+        let normal = points[triangle[1]]
+            .vector_sub(&a)
+            .cross_product(&points[triangle[2]].vector_sub(&a));
+        let length = normal.magnitude();
+        if length <= num_traits::zero() {
+            num_traits::zero()
+        } else {
+            normal.dot_product(&point.vector_sub(&a)) / length
+        }
+    };
+
+    // Find an initial tetrahedron: two distant points, the furthest from
+    // their line, and the furthest from the plane of the three.
+    let first = 0;
+    let second = (0..points.len()).max_by(|a, b| {
+        let distance = |index: &usize| {
+            points[*index]
+                .vector_sub(&points[first])
+                .squared_magnitude()
+        };
+        distance(a)
+            .partial_cmp(&distance(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })?;
+    let direction = points[second].vector_sub(&points[first]);
+    let third = (0..points.len()).max_by(|a, b| {
+        let distance = |index: &usize| {
+            direction
+                .cross_product(&points[*index].vector_sub(&points[first]))
+                .squared_magnitude()
+        };
+        distance(a)
+            .partial_cmp(&distance(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })?;
+    let base = [first, second, third];
+    let fourth = (0..points.len()).max_by(|a, b| {
+        distance_to_plane(&base, &points[*a])
+            .abs()
+            .partial_cmp(&distance_to_plane(&base, &points[*b]).abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })?;
+    if distance_to_plane(&base, &points[fourth]).abs() <= epsilon {
+        return None;
+    }
+
+    // Wind the faces of the tetrahedron so they face away from their opposite vertex.
+    let mut triangles = Vec::with_capacity(4);
+    for (mut triangle, opposite) in [
+        ([first, second, third], fourth),
+        ([first, second, fourth], third),
+        ([second, third, fourth], first),
+        ([third, first, fourth], second),
+    ]
+    .iter()
+    .copied()
+    {
+        if distance_to_plane(&triangle, &points[opposite]) > num_traits::zero() {
+            triangle.swap(1, 2);
+        }
+        triangles.push(triangle);
+    }
+
+    // Add the points one at a time, replacing the triangles they can see
+    // with a fan of triangles from the point to the horizon.
+    for (index, point) in points.iter().enumerate() {
+        let (visible, hidden): (Vec<[usize; 3]>, Vec<[usize; 3]>) = triangles
+            .iter()
+            .partition(|triangle| distance_to_plane(triangle, point) > epsilon);
+        if visible.is_empty() {
+            continue;
+        }
+
+        let mut horizon = Vec::new();
+        for triangle in visible.iter() {
+            for edge in 0..3 {
+                let (start, end) = (triangle[edge], triangle[(edge + 1) % 3]);
+                let shared = visible.iter().any(|other| {
+                    (0..3).any(|other_edge| {
+                        other[other_edge] == end && other[(other_edge + 1) % 3] == start
+                    })
+                });
+                if !shared {
+                    horizon.push([start, end, index]);
+                }
+            }
+        }
+
+        triangles = hidden;
+        triangles.extend(horizon);
+    }
+
+    Some(triangles)
+}
+
+/// Any of the shapes supported by colliders.
+///
+/// # Remarks
+/// The vertices of hulls, the children of compounds and the triangles of meshes are shared
+/// by the copies of a shape, so cloning colliders or snapshotting worlds doesn't copy them.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Shape<F: num_traits::Float = f64> {
+    /// Sphere shape.
+    Sphere(Sphere<F>),
+
+    /// Box shape.
+    Cuboid(Cuboid<F>),
+
+    /// Capsule shape.
+    Capsule(Capsule<F>),
+
+    /// Convex hull shape.
+    ConvexHull(Arc<ConvexHull<F>>),
+
+    /// Compound shape made of other shapes.
+    Compound(Arc<Compound<F>>),
+
+    /// Hollow triangle mesh shape.
+    TriMesh(Arc<TriMesh<F>>),
+}
+
+impl<F: num_traits::Float> Shape<F> {
+    /// Creates a sphere with the given radius.
+    pub fn sphere(radius: F) -> Self {
+        Shape::Sphere(Sphere::new(radius))
+    }
+
+    /// Creates a box with the given half size along each axis.
+    pub fn cuboid(half_size: Vector3<F>) -> Self {
+        Shape::Cuboid(Cuboid::new(half_size))
+    }
+
+    /// Creates a capsule along the y axis with the given half height and radius.
+    pub fn capsule(half_height: F, radius: F) -> Self {
+        Shape::Capsule(Capsule::new(half_height, radius))
+    }
+
+    /// Creates a convex hull shape from the given hull.
+    pub fn convex_hull(hull: ConvexHull<F>) -> Self {
+        Shape::ConvexHull(Arc::new(hull))
+    }
+
+    /// Creates a compound shape from the given compound.
+    pub fn compound(compound: Compound<F>) -> Self {
+        Shape::Compound(Arc::new(compound))
+    }
+
+    /// Creates a triangle mesh shape from the given mesh.
+    pub fn trimesh(mesh: TriMesh<F>) -> Self {
+        Shape::TriMesh(Arc::new(mesh))
+    }
+
+    /// Returns true if the shapes are equal, comparing the geometry they share by address
+    /// rather than by value.
+    pub(crate) fn shares(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Shape::ConvexHull(one), Shape::ConvexHull(two)) => Arc::ptr_eq(one, two),
+            (Shape::Compound(one), Shape::Compound(two)) => Arc::ptr_eq(one, two),
+            (Shape::TriMesh(one), Shape::TriMesh(two)) => Arc::ptr_eq(one, two),
+            _ => self == other,
+        }
+    }
+
+    /// Returns the bounding box of the shape placed with the given transform.
+    pub fn aabb(&self, transform: &Matrix4<F>) -> Aabb<F> {
+        match self {
+            Shape::Sphere(shape) => shape.aabb(transform),
+            Shape::Cuboid(shape) => shape.aabb(transform),
+            Shape::Capsule(shape) => shape.aabb(transform),
+            Shape::ConvexHull(shape) => shape.aabb(transform),
+            Shape::Compound(shape) => shape.aabb(transform),
+            Shape::TriMesh(shape) => shape.aabb(transform),
+        }
+    }
+
+    /// Returns the volume of the shape. Triangle meshes are hollow, without volume.
+    pub fn volume(&self) -> F {
+        match self {
+            Shape::Sphere(shape) => shape.volume(),
+            Shape::Cuboid(shape) => shape.volume(),
+            Shape::Capsule(shape) => shape.volume(),
+            Shape::ConvexHull(shape) => shape.volume(),
+            Shape::Compound(shape) => shape.volume(),
+            Shape::TriMesh(_) => num_traits::zero(),
+        }
+    }
+
+    /// Returns the center of mass of the shape in local space, assuming a uniform density.
+    pub fn center_of_mass(&self) -> Vector3<F> {
+        match self {
+            Shape::ConvexHull(shape) => shape.center_of_mass(),
+            Shape::Compound(shape) => shape.center_of_mass(),
+            _ => Vector3::origin(),
+        }
+    }
+
+    /// Returns the inertia tensor of the shape with the given mass and uniform density,
+    /// relative to its center of mass.
+    pub fn inertia_tensor(&self, mass: F) -> Matrix3<F> {
+        match self {
+            Shape::Sphere(shape) => shape.inertia_tensor(mass),
+            Shape::Cuboid(shape) => shape.inertia_tensor(mass),
+            Shape::Capsule(shape) => shape.inertia_tensor(mass),
+            Shape::ConvexHull(shape) => shape.inertia_tensor(mass),
+            Shape::Compound(shape) => shape.inertia_tensor(mass),
+            Shape::TriMesh(_) => Matrix3::zero(),
+        }
+    }
+}
+
+/// Shape of a compound, placed in the local space of the compound.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CompoundChild<F: num_traits::Float = f64> {
+    /// Placement of the child in the local space of the compound.
+    pub offset: Matrix4<F>,
+
+    /// Shape of the child.
+    pub shape: Shape<F>,
+}
+
+/// Shape made of several child shapes, each one placed with its own offset.
+///
+/// # Remarks
+/// Compounds model objects that can't be described with a single primitive, like a
+/// table made of a top and four legs. The children are considered solid with the same
+/// density, so the mass of the compound is split between them according to their volume.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Compound<F: num_traits::Float = f64> {
+    /// Child shapes of the compound.
+    pub children: Vec<CompoundChild<F>>,
+}
+
+impl<F: num_traits::Float> Default for Compound<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: num_traits::Float> Compound<F> {
+    /// Creates a new compound without children.
+    pub fn new() -> Self {
+        Self {
+            children: Vec::new(),
+        }
+    }
+
+    /// Adds a child shape to the compound, placed with the given offset.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the compound.
+    pub fn add(&mut self, offset: Matrix4<F>, shape: Shape<F>) -> &mut Self {
+        self.children.push(CompoundChild { offset, shape });
+        self
+    }
+
+    /// Returns the bounding box of the compound placed with the given transform.
+    /// An empty compound has a degenerate bounding box at its position.
+    pub fn aabb(&self, transform: &Matrix4<F>) -> Aabb<F> {
+        let mut children = self
+            .children
+            .iter()
+            .map(|child| child.shape.aabb(&transform.matrix_mul(&child.offset)));
+        let position = transform.translation();
+        let first = children
+            .next()
+            .unwrap_or_else(|| Aabb::new(position, position));
+        children.fold(first, |aabb, child| aabb.merge(&child))
+    }
+
+    /// Returns the volume of the compound, the sum of the volumes of its children.
+    pub fn volume(&self) -> F {
+        self.children
+            .iter()
+            .fold(num_traits::zero(), |volume, child| {
+                volume + child.shape.volume()
+            })
+    }
+
+    /// Returns the center of mass of the compound in local space.
+    pub fn center_of_mass(&self) -> Vector3<F> {
+        let volume = self.volume();
+        if volume <= num_traits::zero() {
+            return Vector3::origin();
+        }
+
+        self.children
+            .iter()
+            .fold(Vector3::origin(), |center, child| {
+                let child_center = child.offset.transform(&child.shape.center_of_mass());
+                center.vector_add(&child_center.scalar_mul(child.shape.volume()))
+            })
+            .scalar_div(volume)
+    }
+
+    /// Returns the inertia tensor of the compound with the given mass, relative to its center of mass.
+    ///
+    /// # Remarks
+    /// The inertia tensors of the children are rotated into the space of the compound,
+    /// and moved to its center of mass with the parallel axis theorem.
+    pub fn inertia_tensor(&self, mass: F) -> Matrix3<F> {
+        let volume = self.volume();
+        if volume <= num_traits::zero() {
+            return Matrix3::zero();
+        }
+
+        let center = self.center_of_mass();
+        self.children
+            .iter()
+            .fold(Matrix3::zero(), |inertia, child| {
+                let child_mass = mass * child.shape.volume() / volume;
+                let rotation = child.offset.rotation();
+                let rotated = rotation
+                    .matrix_mul(&child.shape.inertia_tensor(child_mass))
+                    .matrix_mul(&rotation.transpose());
+
+                let d = child
+                    .offset
+                    .transform(&child.shape.center_of_mass())
+                    .vector_sub(&center);
+                inertia
+                    .matrix_add(&rotated)
+                    .matrix_add(&parallel_axis(&d, child_mass))
+            })
+    }
+}
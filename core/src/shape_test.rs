@@ -0,0 +1,237 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::gjk;
+use crate::shape::*;
+use math::{Matrix4, Quaternion, Vector3};
+
+fn assert_vector_eq(expected: Vector3<f64>, actual: Vector3<f64>) {
+    assert!(
+        (expected - actual).magnitude() < 1e-12,
+        "{:?} != {:?}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn capsule() {
+    let capsule = Capsule::new(1.0, 0.5);
+    let transform = Matrix4::from_orientation_and_position(
+        &Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2),
+        &Vector3::new(1.0, 2.0, 3.0),
+    );
+
+    // Lying along the x axis.
+    let (start, end) = capsule.segment(&transform);
+    assert_vector_eq(Vector3::new(2.0, 2.0, 3.0), start);
+    assert_vector_eq(Vector3::new(0.0, 2.0, 3.0), end);
+    let aabb = capsule.aabb(&transform);
+    assert_vector_eq(Vector3::new(-0.5, 1.5, 2.5), aabb.min);
+    assert_vector_eq(Vector3::new(2.5, 2.5, 3.5), aabb.max);
+
+    assert_vector_eq(
+        Vector3::new(0.0, 1.5, 0.0),
+        capsule.local_support_point(&Vector3::new(0.0, 2.0, 0.0)),
+    );
+    assert_vector_eq(
+        Vector3::new(0.5, -1.0, 0.0),
+        capsule.local_support_point(&Vector3::new(1.0, -1e-12, 0.0)),
+    );
+
+    // A capsule without a segment is a sphere.
+    let sphere = Capsule::<f64>::new(0.0, 2.0).inertia_tensor(3.0);
+    let expected = Sphere::new(2.0).inertia_tensor(3.0);
+    for (actual, expected) in sphere.data.iter().zip(expected.data.iter()) {
+        assert!((actual - expected).abs() < 1e-12);
+    }
+
+    // Long capsules are harder to turn around their lateral axes.
+    let inertia = capsule.inertia_tensor(1.0);
+    assert!(inertia.data[0] > inertia.data[4]);
+    assert_eq!(inertia.data[0], inertia.data[8]);
+
+    // Support mapping works with GJK.
+    let other = Matrix4::from_orientation_and_position(
+        &Quaternion::identity(),
+        &Vector3::new(1.0, 4.0, 3.0),
+    );
+    assert!((gjk::distance(&capsule, &transform, &Sphere::new(1.0), &other) - 0.5).abs() < 1e-9);
+}
+
+fn cube_points() -> Vec<Vector3<f64>> {
+    let mut points = Vec::new();
+    for x in [-1.0, 1.0].iter() {
+        for y in [-1.0, 1.0].iter() {
+            for z in [-1.0, 1.0].iter() {
+                points.push(Vector3::new(*x, *y, *z));
+            }
+        }
+    }
+    points
+}
+
+#[test]
+fn convex_hull_of_cube() {
+    // Interior points and points on faces are discarded.
+    let mut points = cube_points();
+    points.insert(3, Vector3::new(0.1, 0.2, -0.3));
+    points.push(Vector3::new(1.0, 0.0, 0.0));
+    points.push(Vector3::new(0.5, 0.5, 0.5));
+    let hull = ConvexHull::from_points(&points).unwrap();
+
+    assert_eq!(8, hull.vertices.len());
+    assert_eq!(6, hull.faces.len());
+    assert_eq!(12, hull.edges.len());
+    for face in hull.faces.iter() {
+        assert_eq!(4, face.vertices.len());
+        assert!((face.offset - 1.0).abs() < 1e-12);
+
+        // Vertices are counter-clockwise seen from outside.
+        let [a, b, c] = [
+            hull.vertices[face.vertices[0]],
+            hull.vertices[face.vertices[1]],
+            hull.vertices[face.vertices[2]],
+        ];
+        let winding = (b - a).cross_product(&(c - a)).dot_product(&face.normal);
+        assert!(winding > 0.0);
+    }
+    let top = hull.face_along(&Vector3::new(0.1, 1.0, 0.0));
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), hull.faces[top].normal);
+
+    assert!((hull.volume() - 8.0).abs() < 1e-12);
+    assert_vector_eq(Vector3::origin(), hull.center_of_mass());
+    let expected = Cuboid::new(Vector3::new(1.0, 1.0, 1.0)).inertia_tensor(2.0);
+    let inertia = hull.inertia_tensor(2.0);
+    for (actual, expected) in inertia.data.iter().zip(expected.data.iter()) {
+        assert!((actual - expected).abs() < 1e-12);
+    }
+
+    let transform = Matrix4::from_orientation_and_position(
+        &Quaternion::identity(),
+        &Vector3::new(1.0, 2.0, 3.0),
+    );
+    let aabb = hull.aabb(&transform);
+    assert_vector_eq(Vector3::new(0.0, 1.0, 2.0), aabb.min);
+    assert_vector_eq(Vector3::new(2.0, 3.0, 4.0), aabb.max);
+    let sphere = Matrix4::from_orientation_and_position(
+        &Quaternion::identity(),
+        &Vector3::new(4.0, 2.0, 3.0),
+    );
+    assert!((gjk::distance(&hull, &transform, &Sphere::new(1.0), &sphere) - 1.0).abs() < 1e-9);
+
+    // Lattices have many coplanar points inside of the faces and along the edges.
+    let mut lattice = Vec::new();
+    for x in 0..=4 {
+        for y in 0..=4 {
+            for z in 0..=4 {
+                lattice.push(Vector3::new(x as f64, y as f64, z as f64).scalar_mul(0.5));
+            }
+        }
+    }
+    let hull = ConvexHull::from_points(&lattice).unwrap();
+    assert_eq!(8, hull.vertices.len());
+    assert_eq!(6, hull.faces.len());
+    assert!((hull.volume() - 8.0).abs() < 1e-12);
+}
+
+#[test]
+fn convex_hull_of_point_cloud() {
+    // A tetrahedron has its center of mass at the average of its vertices.
+    let tetrahedron = ConvexHull::<f64>::from_points(&[
+        Vector3::new(1.0, 1.0, 1.0),
+        Vector3::new(2.0, 1.0, 1.0),
+        Vector3::new(1.0, 2.0, 1.0),
+        Vector3::new(1.0, 1.0, 2.0),
+    ])
+    .unwrap();
+    assert_eq!(4, tetrahedron.faces.len());
+    assert_eq!(6, tetrahedron.edges.len());
+    assert!((tetrahedron.volume() - 1.0 / 6.0).abs() < 1e-12);
+    assert_vector_eq(Vector3::new(1.25, 1.25, 1.25), tetrahedron.center_of_mass());
+
+    // Points scattered on a sphere all end up on the hull, which encloses them all.
+    let mut seed = 3u64;
+    let mut next = move || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+    };
+    let points: Vec<Vector3<f64>> = (0..200)
+        .map(|_| Vector3::new(next(), next(), next()).normalize())
+        .collect();
+    let hull = ConvexHull::from_points(&points).unwrap();
+    assert_eq!(200, hull.vertices.len());
+    assert_eq!(2 * 200 - 4, hull.faces.len());
+    for face in hull.faces.iter() {
+        for point in points.iter() {
+            assert!(face.normal.dot_product(point) - face.offset <= 1e-9);
+        }
+    }
+    assert!(hull.volume() < 4.0 / 3.0 * std::f64::consts::PI);
+    assert!(hull.volume() > 3.5);
+
+    // Flat point clouds have no hull.
+    assert_eq!(
+        None,
+        ConvexHull::from_points(&[
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(1.0, 1.0, 0.0),
+        ])
+    );
+    assert_eq!(None, ConvexHull::<f64>::from_points(&[]));
+}
+
+fn offset(x: f64, y: f64, z: f64) -> Matrix4<f64> {
+    Matrix4::from_orientation_and_position(&Quaternion::identity(), &Vector3::new(x, y, z))
+}
+
+#[test]
+fn compound() {
+    // Two unit cubes side by side make a 2x1x1 box.
+    let cube = Shape::Cuboid(Cuboid::new(Vector3::new(0.5, 0.5, 0.5)));
+    let mut compound = Compound::new();
+    compound
+        .add(offset(1.5, 0.0, 0.0), cube.clone())
+        .add(offset(2.5, 0.0, 0.0), cube);
+
+    assert!((compound.volume() - 2.0).abs() < 1e-12);
+    assert_vector_eq(Vector3::new(2.0, 0.0, 0.0), compound.center_of_mass());
+    let expected = Cuboid::new(Vector3::new(1.0, 0.5, 0.5)).inertia_tensor(4.0);
+    let inertia = compound.inertia_tensor(4.0);
+    for (actual, expected) in inertia.data.iter().zip(expected.data.iter()) {
+        assert!((actual - expected).abs() < 1e-12);
+    }
+
+    let aabb = compound.aabb(&offset(0.0, 1.0, 0.0));
+    assert_vector_eq(Vector3::new(1.0, 0.5, -0.5), aabb.min);
+    assert_vector_eq(Vector3::new(3.0, 1.5, 0.5), aabb.max);
+
+    // Rotated children contribute their rotated inertia.
+    let mut turned = Compound::new();
+    turned.add(
+        Matrix4::from_orientation_and_position(
+            &Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2),
+            &Vector3::origin(),
+        ),
+        Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 0.5, 0.5))),
+    );
+    let expected = Cuboid::new(Vector3::new(0.5, 1.0, 0.5)).inertia_tensor(1.0);
+    let inertia = Shape::compound(turned).inertia_tensor(1.0);
+    for (actual, expected) in inertia.data.iter().zip(expected.data.iter()) {
+        assert!((actual - expected).abs() < 1e-12);
+    }
+
+    let empty = Compound::<f64>::default();
+    assert_eq!(0.0, empty.volume());
+    assert_vector_eq(
+        Vector3::new(0.0, 1.0, 0.0),
+        empty.aabb(&offset(0.0, 1.0, 0.0)).min,
+    );
+}
@@ -0,0 +1,358 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::particle::Particle;
+use crate::pbd::{derive_velocities, predict, project_distance, project_volume, signed_volume};
+use crate::rigid_body::Power;
+use math::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Default number of times the constraints of a soft body are projected every frame.
+pub const DEFAULT_SOFT_BODY_ITERATIONS: usize = 8;
+
+/// Default stiffness of the edges of a soft body.
+pub const DEFAULT_EDGE_STIFFNESS: f64 = 0.5;
+
+/// Default stiffness of the volume of the tetrahedra of a soft body.
+pub const DEFAULT_VOLUME_STIFFNESS: f64 = 1.0;
+
+/// Default radius of the particles of a soft body, used to collide with rigid bodies.
+pub const DEFAULT_SOFT_BODY_RADIUS: f64 = 0.05;
+
+/// Distance constraint along an edge of the tetrahedra of a soft body.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SoftBodyEdge<F: num_traits::Float = f64> {
+    /// Indices of the particles at the ends of the edge.
+    pub particles: [usize; 2],
+
+    /// Length of the edge at rest.
+    pub rest_length: F,
+}
+
+/// Tetrahedron of a soft body, keeping its volume.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Tetrahedron<F: num_traits::Float = f64> {
+    /// Indices of the particles at the corners of the tetrahedron.
+    pub particles: [usize; 4],
+
+    /// Signed volume of the tetrahedron at rest.
+    pub rest_volume: F,
+}
+
+/// Vertex of a surface mesh following the deformation of a soft body.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SkinnedVertex<F: num_traits::Float = f64> {
+    /// Index of the tetrahedron the vertex is bound to.
+    pub tetrahedron: usize,
+
+    /// Barycentric coordinates of the vertex in the tetrahedron.
+    pub weights: [F; 4],
+}
+
+/// Deformable volume simulated as a mesh of tetrahedra with particles at their corners.
+///
+/// # Remarks
+/// Particles are integrated first, then the edges and volumes of the tetrahedra are projected
+/// a number of times, moving the particles in proportion to their inverse masses and the
+/// stiffness of each kind of constraint, and finally the velocities are derived from the
+/// corrected positions. Soft bodies added to a world also collide with its rigid bodies,
+/// treating the particles as small spheres, and push the rigid bodies back.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SoftBody<F: num_traits::Float = f64> {
+    /// Particles at the corners of the tetrahedra.
+    pub particles: Vec<Particle<F>>,
+
+    /// Edges of the tetrahedra, each listed once.
+    pub edges: Vec<SoftBodyEdge<F>>,
+
+    /// Tetrahedra of the mesh.
+    pub tetrahedra: Vec<Tetrahedron<F>>,
+
+    /// Vertices of the surface mesh bound to the tetrahedra.
+    pub surface: Vec<SkinnedVertex<F>>,
+
+    /// Acceleration due to gravity.
+    pub gravity: Vector3<F>,
+
+    /// Stiffness of the edges, from `0` to `1`.
+    pub edge_stiffness: F,
+
+    /// Stiffness of the volumes, from `0` to `1`.
+    pub volume_stiffness: F,
+
+    /// Radius of the particles when colliding with rigid bodies.
+    pub radius: F,
+
+    /// Number of times the constraints are projected every frame.
+    pub iterations: usize,
+
+    rest_positions: Vec<Vector3<F>>,
+    previous: Vec<Vector3<F>>,
+}
+
+impl<F: num_traits::Float> SoftBody<F> {
+    /// Creates a new soft body at rest from the positions of its particles, the tetrahedra
+    /// between them, and its total mass, shared evenly by the particles.
+    pub fn new(positions: Vec<Vector3<F>>, tetrahedra: &[[usize; 4]], mass: F) -> Self {
+        let particle_mass = mass / math::real(positions.len() as f64);
+        let particles = positions
+            .iter()
+            .map(|position| Particle::new(*position, particle_mass))
+            .collect();
+
+        let mut links = BTreeSet::new();
+        for corners in tetrahedra.iter() {
+            for (index, one) in corners.iter().enumerate() {
+                for two in corners[index + 1..].iter() {
+                    links.insert((*one.min(two), *one.max(two)));
+                }
+            }
+        }
+        let edges = links
+            .into_iter()
+            .map(|(one, two)| SoftBodyEdge {
+                particles: [one, two],
+                rest_length: positions[one].vector_sub(&positions[two]).magnitude(),
+            })
+            .collect();
+        let tetrahedra = tetrahedra
+            .iter()
+            .map(|corners| Tetrahedron {
+                particles: *corners,
+                rest_volume: signed_volume(&corners.map(|corner| positions[corner])),
+            })
+            .collect();
+
+        Self {
+            particles,
+            edges,
+            tetrahedra,
+            surface: Vec::new(),
+            gravity: Vector3::origin(),
+            edge_stiffness: math::real(DEFAULT_EDGE_STIFFNESS),
+            volume_stiffness: math::real(DEFAULT_VOLUME_STIFFNESS),
+            radius: math::real(DEFAULT_SOFT_BODY_RADIUS),
+            iterations: DEFAULT_SOFT_BODY_ITERATIONS,
+            previous: positions.clone(),
+            rest_positions: positions,
+        }
+    }
+
+    /// Creates a new soft box centered at the given position, split into `divisions` cells
+    /// along each axis, with six tetrahedra per cell.
+    pub fn cuboid(center: Vector3<F>, half_size: Vector3<F>, divisions: usize, mass: F) -> Self {
+        let divisions = divisions.max(1);
+        let side = divisions + 1;
+        let cell = half_size.scalar_mul(math::real::<F>(2.0) / math::real(divisions as f64));
+        let corner = center.vector_sub(&half_size);
+        let index = |x: usize, y: usize, z: usize| (z * side + y) * side + x;
+
+        let mut positions = Vec::with_capacity(side * side * side);
+        for z in 0..side {
+            for y in 0..side {
+                for x in 0..side {
+                    positions.push(Vector3::new(
+                        corner.x + cell.x * math::real(x as f64),
+                        corner.y + cell.y * math::real(y as f64),
+                        corner.z + cell.z * math::real(z as f64),
+                    ));
+                }
+            }
+        }
+
+        // Every cell is split along its diagonal, walking one axis at a time.
+        let orders = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        let mut tetrahedra = Vec::with_capacity(divisions * divisions * divisions * 6);
+        for z in 0..divisions {
+            for y in 0..divisions {
+                for x in 0..divisions {
+                    for order in orders.iter() {
+                        let mut step = [x, y, z];
+                        let mut corners = [index(x, y, z); 4];
+                        for (corner, axis) in corners[1..].iter_mut().zip(order.iter()) {
+                            step[*axis] += 1;
+                            *corner = index(step[0], step[1], step[2]);
+                        }
+                        tetrahedra.push(corners);
+                    }
+                }
+            }
+        }
+
+        Self::new(positions, &tetrahedra, mass)
+    }
+
+    /// Binds the vertices of a surface mesh, given at the rest pose of the soft body,
+    /// to the tetrahedra containing them, or to the closest ones for vertices outside.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the soft body.
+    pub fn set_surface(&mut self, vertices: &[Vector3<F>]) -> &mut Self {
+        let surface = vertices
+            .iter()
+            .map(|vertex| {
+                self.tetrahedra
+                    .iter()
+                    .enumerate()
+                    .map(|(index, tetrahedron)| SkinnedVertex {
+                        tetrahedron: index,
+                        weights: barycentric(&self.rest_positions, &tetrahedron.particles, vertex),
+                    })
+                    .max_by(|one, two| {
+                        let smallest = |vertex: &SkinnedVertex<F>| {
+                            vertex
+                                .weights
+                                .iter()
+                                .fold(F::infinity(), |min, weight| min.min(*weight))
+                        };
+                        smallest(one)
+                            .partial_cmp(&smallest(two))
+                            .expect("finite weights")
+                    })
+                    .expect("soft bodies have tetrahedra")
+            })
+            .collect();
+        self.surface = surface;
+        self
+    }
+
+    /// Returns the current positions of the vertices of the surface mesh.
+    pub fn surface_positions(&self) -> Vec<Vector3<F>> {
+        self.surface
+            .iter()
+            .map(|vertex| {
+                let corners = &self.tetrahedra[vertex.tetrahedron].particles;
+                corners.iter().zip(vertex.weights.iter()).fold(
+                    Vector3::origin(),
+                    |sum, (corner, weight)| {
+                        sum.vector_add(&self.particles[*corner].position.scalar_mul(*weight))
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the current volume of the soft body.
+    pub fn volume(&self) -> F {
+        let positions = self.positions();
+        self.tetrahedra
+            .iter()
+            .fold(num_traits::zero(), |sum, tetrahedron| {
+                sum + signed_volume(&tetrahedron.particles.map(|corner| positions[corner])).abs()
+            })
+    }
+
+    /// Returns the volume of the soft body at rest.
+    pub fn rest_volume(&self) -> F {
+        self.tetrahedra
+            .iter()
+            .fold(num_traits::zero(), |sum, tetrahedron| {
+                sum + tetrahedron.rest_volume.abs()
+            })
+    }
+
+    /// Returns the current center of mass of the soft body.
+    pub fn center_of_mass(&self) -> Vector3<F> {
+        let (weighted, mass) = self
+            .particles
+            .iter()
+            .filter(|particle| particle.has_finite_mass())
+            .fold(
+                (Vector3::origin(), F::zero()),
+                |(weighted, mass), particle| {
+                    (
+                        weighted.vector_add(&particle.position.scalar_mul(particle.mass())),
+                        mass + particle.mass(),
+                    )
+                },
+            );
+        weighted.scalar_div(mass)
+    }
+
+    /// Processes all the physics for the soft body on its own.
+    pub fn run_physics(&mut self, duration: F) {
+        self.integrate(duration, F::powf);
+        self.project_constraints();
+        self.update_velocities(duration);
+    }
+
+    /// Moves the particles with their velocities after applying gravity and their forces,
+    /// remembering where they started, damping them with the given power function.
+    pub(crate) fn integrate(&mut self, duration: F, power: Power<F>) {
+        predict(
+            &mut self.particles,
+            &mut self.previous,
+            &self.gravity,
+            duration,
+            power,
+        );
+    }
+
+    /// Moves the particles to restore the lengths of the edges and the volumes of the tetrahedra.
+    pub(crate) fn project_constraints(&mut self) {
+        for _ in 0..self.iterations {
+            for edge in self.edges.iter() {
+                project_distance(
+                    &mut self.particles,
+                    edge.particles,
+                    edge.rest_length,
+                    self.edge_stiffness,
+                );
+            }
+            for tetrahedron in self.tetrahedra.iter() {
+                project_volume(
+                    &mut self.particles,
+                    tetrahedron.particles,
+                    tetrahedron.rest_volume,
+                    self.volume_stiffness,
+                );
+            }
+        }
+    }
+
+    /// Derives the velocities of the particles from the distance they moved during the frame.
+    pub(crate) fn update_velocities(&mut self, duration: F) {
+        derive_velocities(&mut self.particles, &self.previous, duration);
+    }
+
+    /// Returns the current positions of the particles.
+    fn positions(&self) -> Vec<Vector3<F>> {
+        self.particles
+            .iter()
+            .map(|particle| particle.position)
+            .collect()
+    }
+}
+
+/// Returns the barycentric coordinates of a point in the tetrahedron with the given corners.
+fn barycentric<F: num_traits::Float>(
+    positions: &[Vector3<F>],
+    corners: &[usize; 4],
+    point: &Vector3<F>,
+) -> [F; 4] {
+    let origin = positions[corners[0]];
+    let edges = Matrix3::from_components(
+        &positions[corners[1]].vector_sub(&origin),
+        &positions[corners[2]].vector_sub(&origin),
+        &positions[corners[3]].vector_sub(&origin),
+    );
+    let weights = edges.inverse().transform(&point.vector_sub(&origin));
+    [
+        F::one() - weights.x - weights.y - weights.z,
+        weights.x,
+        weights.y,
+        weights.z,
+    ]
+}
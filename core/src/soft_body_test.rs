@@ -0,0 +1,43 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::soft_body::*;
+use math::Vector3;
+
+#[test]
+fn cuboid() {
+    let mut jelly = SoftBody::<f64>::cuboid(Vector3::origin(), Vector3::new(1.0, 0.5, 0.5), 2, 2.7);
+    assert_eq!(27, jelly.particles.len());
+    assert_eq!(48, jelly.tetrahedra.len());
+    assert!((jelly.rest_volume() - 2.0).abs() < 1e-12);
+    assert!((jelly.volume() - 2.0).abs() < 1e-12);
+    assert!((jelly.particles[0].mass() - 0.1).abs() < 1e-12);
+
+    // The surface follows the particles around.
+    jelly.set_surface(&[Vector3::new(1.0, 0.5, 0.5), Vector3::new(0.2, -0.1, 0.3)]);
+    for particle in jelly.particles.iter_mut() {
+        particle.position.y *= 2.0;
+    }
+    let surface = jelly.surface_positions();
+    assert!((surface[0] - Vector3::new(1.0, 1.0, 0.5)).magnitude() < 1e-12);
+    assert!((surface[1] - Vector3::new(0.2, -0.2, 0.3)).magnitude() < 1e-12);
+}
+
+#[test]
+fn volume_preservation() {
+    let mut jelly = SoftBody::<f64>::cuboid(Vector3::origin(), Vector3::new(0.5, 0.5, 0.5), 2, 1.0);
+    for particle in jelly.particles.iter_mut() {
+        particle.position.y *= 0.5;
+    }
+    assert!((jelly.volume() - 0.5).abs() < 1e-12);
+
+    // Squashed, the soft body bulges back to its volume without moving away.
+    for _ in 0..30 {
+        jelly.run_physics(1.0 / 60.0);
+    }
+    assert!((jelly.volume() - 1.0).abs() < 0.05);
+    assert!(jelly.center_of_mass().magnitude() < 1e-9);
+}
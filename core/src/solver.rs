@@ -0,0 +1,917 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+#[cfg(feature = "parallel")]
+use crate::island::Islands;
+use crate::joint::{Joint, JointRow};
+use crate::manifold::{world_points, ManifoldCache, ManifoldPoint};
+#[cfg(feature = "parallel")]
+use crate::parallel::map_chunks_mut;
+use crate::parallel::Parallel;
+use crate::rigid_body::{BodyType, RigidBody};
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "parallel")]
+use std::collections::BTreeMap;
+
+/// Default number of iterations used by the contact solver.
+pub const DEFAULT_VELOCITY_ITERATIONS: usize = 10;
+
+/// Default fraction of the penetration, or joint drift, removed every frame by the contact solver.
+pub const DEFAULT_BAUMGARTE: f64 = 0.2;
+
+/// Default penetration allowed before the contact solver starts pushing bodies apart.
+/// Keeping bodies slightly penetrated keeps their contacts alive between frames.
+pub const DEFAULT_ALLOWED_PENETRATION: f64 = 0.005;
+
+/// Largest distance a contact is pushed apart by every position iteration, so deep
+/// penetrations are resolved over a few frames instead of in a single jump.
+const MAX_POSITION_CORRECTION: f64 = 0.2;
+
+/// Closing velocity below which contacts don't bounce, so resting bodies don't jitter.
+pub(crate) const RESTITUTION_VELOCITY_LIMIT: f64 = 0.25;
+
+/// How the contact solver pushes apart penetrated bodies.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum PositionCorrection {
+    /// Biases the velocity of the contacts to push the bodies apart. Cheap, but the bias
+    /// adds energy, which makes deep contacts pop apart and stacks jitter.
+    Baumgarte,
+
+    /// Moves the bodies apart after their positions are integrated, with the given number
+    /// of non-linear Gauss-Seidel iterations, leaving their velocities untouched.
+    NonLinearGaussSeidel(usize),
+}
+
+/// Iterative solver resolving the contacts of the manifolds, and the joints between bodies,
+/// with sequential impulses.
+///
+/// # Remarks
+/// Each iteration visits every contact point, applying the impulse that removes the closing
+/// velocity along the normal and the sliding velocity along the tangents. Impulses are
+/// accumulated per point and clamped, so contacts only push. Friction follows Coulomb's
+/// law over a friction cone: contacts stick while the friction impulse stays below the
+/// normal impulse scaled by the static coefficient, and slide otherwise, with the friction
+/// impulse limited by the dynamic coefficient. More iterations converge closer to the exact
+/// solution, at a higher cost.
+///
+/// With warm starting, the impulses accumulated by the points of the manifolds, and by
+/// the joints, in the previous frame are applied before iterating. Persistent contacts, such as the ones
+/// in a resting stack, start close to their solution and converge in fewer iterations.
+///
+/// Penetration is removed with Baumgarte stabilization by default, biasing the contacts to
+/// separate by a fraction of their penetration beyond the allowed one every frame. Larger
+/// fractions sink less but jitter more. The non-linear Gauss-Seidel correction moves the
+/// bodies apart instead, in a separate pass run by the world after moving them. Joints
+/// always correct their drift with the Baumgarte fraction.
+///
+/// With shock propagation, a last pass visits the contacts from the ground up, by the
+/// level of their bodies in the contact graph, treating the lower body of every contact as
+/// if it had infinite mass. The weight of the bodies above is then fully carried by the ones
+/// below, so tall stacks stop sinking into themselves, at the cost of the bodies below not
+/// being pushed back by the ones above during the pass.
+///
+/// With the `parallel` feature, the bodies are split into islands that can't affect each
+/// other, which are iterated on separate threads. Every island is solved in the same order
+/// as on a single thread, so the results are exactly the same.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ContactSolver {
+    /// Number of times every contact is visited per frame.
+    pub iterations: usize,
+
+    /// Whether the impulses of the previous frame are used as the initial guess.
+    pub warm_starting: bool,
+
+    /// How penetrated bodies are pushed apart.
+    pub position_correction: PositionCorrection,
+
+    /// Fraction of the penetration, and joint drift, removed every frame.
+    pub baumgarte: f64,
+
+    /// Penetration allowed before bodies are pushed apart, also known as slop.
+    pub allowed_penetration: f64,
+
+    /// Whether a shock propagation pass is run after the iterations.
+    pub shock_propagation: bool,
+}
+
+impl Default for ContactSolver {
+    fn default() -> Self {
+        Self::new(DEFAULT_VELOCITY_ITERATIONS)
+    }
+}
+
+impl ContactSolver {
+    /// Creates a new contact solver with the given number of iterations.
+    pub fn new(iterations: usize) -> Self {
+        Self {
+            iterations,
+            warm_starting: true,
+            position_correction: PositionCorrection::Baumgarte,
+            baumgarte: DEFAULT_BAUMGARTE,
+            allowed_penetration: DEFAULT_ALLOWED_PENETRATION,
+            shock_propagation: false,
+        }
+    }
+
+    /// Resolves the contacts of all the manifolds in the cache, and the given joints,
+    /// changing the velocities of the bodies. The accumulated impulses are stored in the
+    /// manifold points and the joints.
+    ///
+    /// # Remarks
+    /// The duration of the frame is used to turn penetration and joint drift into velocity.
+    ///
+    /// Sleeping bodies in contact with, or joined to, awake bodies are woken up. Manifolds
+    /// and joints where no body is awake are left untouched.
+    pub fn solve<F: num_traits::Float + Parallel>(
+        &self,
+        manifolds: &mut ManifoldCache<F>,
+        joints: &mut [Joint<F>],
+        bodies: &mut [RigidBody<F>],
+        duration: F,
+    ) {
+        let mut rows = Vec::new();
+        let mut joined = Vec::with_capacity(joints.len());
+        for (index, joint) in joints.iter_mut().enumerate() {
+            let solve = joint.enabled && wake_up_pair(joint.bodies, bodies);
+            joined.push(solve);
+            if solve {
+                joint.rows(index, bodies, duration, self.baumgarte, &mut rows);
+            }
+        }
+        for row in rows.iter_mut() {
+            if self.warm_starting {
+                row.impulse = joints[row.joint].impulse(row.slot);
+            }
+            row.prepare(bodies);
+        }
+
+        let bias = match self.position_correction {
+            PositionCorrection::Baumgarte => Some((
+                math::real(self.baumgarte),
+                math::real(self.allowed_penetration),
+            )),
+            PositionCorrection::NonLinearGaussSeidel(_) => None,
+        };
+        let mut constraints = Vec::new();
+        let mut solved = Vec::with_capacity(manifolds.len());
+        for manifold in manifolds.iter_mut() {
+            let solve = wake_up_pair(manifold.bodies, bodies);
+            solved.push(solve);
+            if !solve {
+                continue;
+            }
+            for point in manifold.points.iter_mut() {
+                if !self.warm_starting {
+                    point.normal_impulse = num_traits::zero();
+                    point.tangent_impulses = [num_traits::zero(); 2];
+                }
+                constraints.push(ContactConstraint::new(point, bodies, bias, duration));
+            }
+        }
+
+        self.iterate_islands(&mut rows, &mut constraints, bodies);
+
+        let points = manifolds
+            .iter_mut()
+            .zip(solved.iter())
+            .filter(|(_, solved)| **solved)
+            .flat_map(|(manifold, _)| manifold.points.iter_mut());
+        for (point, constraint) in points.zip(constraints.iter()) {
+            point.normal_impulse = constraint.normal_impulse;
+            point.tangent_impulses = constraint.tangent_impulses;
+        }
+
+        for (joint, joined) in joints.iter_mut().zip(joined.iter()) {
+            if *joined {
+                joint.reset_impulses();
+            }
+        }
+        for row in rows.iter() {
+            joints[row.joint].store_row(row, duration);
+        }
+    }
+
+    /// Moves apart the bodies of the manifolds in the cache that are still penetrated,
+    /// when using the non-linear Gauss-Seidel correction. Does nothing otherwise.
+    ///
+    /// # Remarks
+    /// Every iteration recomputes the penetration of the contact points from the current
+    /// positions of the bodies, and moves them along the normal by the Baumgarte fraction
+    /// of the penetration beyond the allowed one, as if a position impulse was applied.
+    /// The positions are corrected after the bodies moved, so their velocities, and the
+    /// energy of the world, stay untouched.
+    pub fn correct_positions<F: num_traits::Float>(
+        &self,
+        manifolds: &ManifoldCache<F>,
+        bodies: &mut [RigidBody<F>],
+    ) {
+        let iterations = match self.position_correction {
+            PositionCorrection::Baumgarte => return,
+            PositionCorrection::NonLinearGaussSeidel(iterations) => iterations,
+        };
+
+        let baumgarte = math::real::<F>(self.baumgarte);
+        let allowed = math::real::<F>(self.allowed_penetration);
+        let max_correction = math::real::<F>(MAX_POSITION_CORRECTION);
+        for _ in 0..iterations {
+            for manifold in manifolds.iter() {
+                let (one, two) = manifold.bodies;
+                if !is_moving(&bodies[one]) && !two.is_some_and(|body| is_moving(&bodies[body])) {
+                    continue;
+                }
+
+                for point in manifold.points.iter() {
+                    let (point_one, point_two) = world_points(point, bodies);
+                    let normal = &point.contact.contact_normal;
+                    let penetration = point_two.vector_sub(&point_one).dot_product(normal);
+                    let correction = (baumgarte * (penetration - allowed)).min(max_correction);
+                    if correction <= F::zero() {
+                        continue;
+                    }
+
+                    let contact_point =
+                        point_one.vector_add(&point_two).scalar_mul(math::real(0.5));
+                    let movable = movable(manifold.bodies, bodies);
+                    let relative_one = contact_point.vector_sub(&bodies[one].position);
+                    let mut inverse = F::zero();
+                    if movable[0] {
+                        inverse = inverse_mass_along(&bodies[one], &relative_one, normal);
+                    }
+                    let relative_two = two.map(|body| {
+                        let relative = contact_point.vector_sub(&bodies[body].position);
+                        if movable[1] {
+                            inverse =
+                                inverse + inverse_mass_along(&bodies[body], &relative, normal);
+                        }
+                        relative
+                    });
+                    if inverse <= F::zero() {
+                        continue;
+                    }
+
+                    let impulse = normal.scalar_mul(correction / inverse);
+                    if movable[0] {
+                        move_at(&mut bodies[one], &relative_one, &impulse);
+                    }
+                    if let (Some(body), Some(relative), true) = (two, relative_two, movable[1]) {
+                        move_at(&mut bodies[body], &relative, &impulse.invert());
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl ContactSolver {
+    /// Warm starts the contacts and runs the iterations of the solver over the given rows
+    /// and contacts.
+    fn iterate<F: num_traits::Float>(
+        &self,
+        rows: &mut [JointRow<F>],
+        constraints: &mut [ContactConstraint<F>],
+        bodies: &mut [RigidBody<F>],
+    ) {
+        iterate_impulses(self.iterations, rows, constraints, bodies);
+
+        if self.shock_propagation {
+            propagate_shock(constraints, bodies);
+        }
+    }
+
+    /// Iterates over all the rows and contacts on the calling thread.
+    #[cfg(not(feature = "parallel"))]
+    fn iterate_islands<F: num_traits::Float>(
+        &self,
+        rows: &mut [JointRow<F>],
+        constraints: &mut [ContactConstraint<F>],
+        bodies: &mut [RigidBody<F>],
+    ) {
+        self.iterate(rows, constraints, bodies);
+    }
+
+    /// Splits the rows and contacts by island, iterating over the islands on multiple threads.
+    ///
+    /// # Remarks
+    /// Every island gets a copy of its bodies, along with the ones with infinite mass it
+    /// touches, and the rows and contacts refer to them by their index in the copy.
+    /// The bodies are copied back, and the rows and contacts put back in their order,
+    /// once every island is solved.
+    #[cfg(feature = "parallel")]
+    fn iterate_islands<F: num_traits::Float + Send + Sync>(
+        &self,
+        rows: &mut Vec<JointRow<F>>,
+        constraints: &mut Vec<ContactConstraint<F>>,
+        bodies: &mut [RigidBody<F>],
+    ) {
+        let pairs: Vec<(usize, usize)> = rows
+            .iter()
+            .map(|row| row.bodies)
+            .chain(constraints.iter().map(|constraint| constraint.bodies))
+            .filter_map(|(one, two)| Some((one, two?)))
+            .collect();
+        let islands = Islands::build(bodies, &pairs);
+        if islands.len() < 2 {
+            self.iterate(rows, constraints, bodies);
+            return;
+        }
+
+        let island_of = |(one, two): (usize, Option<usize>)| {
+            islands
+                .island_of(one)
+                .or_else(|| two.and_then(|body| islands.island_of(body)))
+                .unwrap_or(0)
+        };
+        let mut groups: Vec<IslandGroup<F>> =
+            (0..islands.len()).map(|_| IslandGroup::default()).collect();
+        let row_count = rows.len();
+        for (position, mut row) in rows.drain(..).enumerate() {
+            let group = &mut groups[island_of(row.bodies)];
+            row.bodies = group.localize(row.bodies, bodies);
+            group.row_positions.push(position);
+            group.rows.push(row);
+        }
+        let constraint_count = constraints.len();
+        for (position, mut constraint) in constraints.drain(..).enumerate() {
+            let group = &mut groups[island_of(constraint.bodies)];
+            constraint.bodies = group.localize(constraint.bodies, bodies);
+            group.constraint_positions.push(position);
+            group.constraints.push(constraint);
+        }
+
+        map_chunks_mut(&mut groups, 1, |_, chunk| {
+            for group in chunk.iter_mut() {
+                self.iterate(&mut group.rows, &mut group.constraints, &mut group.bodies);
+            }
+        });
+
+        let mut solved_rows: Vec<Option<JointRow<F>>> = (0..row_count).map(|_| None).collect();
+        let mut solved_constraints: Vec<Option<ContactConstraint<F>>> =
+            (0..constraint_count).map(|_| None).collect();
+        for mut group in groups.into_iter() {
+            for (global, body) in group.globals.iter().zip(group.bodies.drain(..)) {
+                if is_moving(&body) {
+                    bodies[*global] = body;
+                }
+            }
+            let rows = std::mem::take(&mut group.rows);
+            for (position, mut row) in group.row_positions.iter().zip(rows) {
+                row.bodies = group.globalize(row.bodies);
+                solved_rows[*position] = Some(row);
+            }
+            let constraints = std::mem::take(&mut group.constraints);
+            for (position, mut constraint) in group.constraint_positions.iter().zip(constraints) {
+                constraint.bodies = group.globalize(constraint.bodies);
+                solved_constraints[*position] = Some(constraint);
+            }
+        }
+        rows.extend(solved_rows.into_iter().flatten());
+        constraints.extend(solved_constraints.into_iter().flatten());
+    }
+}
+
+/// Rows and contacts of an island, along with copies of the bodies they act on.
+#[cfg(feature = "parallel")]
+struct IslandGroup<F: num_traits::Float> {
+    bodies: Vec<RigidBody<F>>,
+    globals: Vec<usize>,
+    locals: BTreeMap<usize, usize>,
+    rows: Vec<JointRow<F>>,
+    row_positions: Vec<usize>,
+    constraints: Vec<ContactConstraint<F>>,
+    constraint_positions: Vec<usize>,
+}
+
+#[cfg(feature = "parallel")]
+impl<F: num_traits::Float> Default for IslandGroup<F> {
+    fn default() -> Self {
+        Self {
+            bodies: Vec::new(),
+            globals: Vec::new(),
+            locals: BTreeMap::new(),
+            rows: Vec::new(),
+            row_positions: Vec::new(),
+            constraints: Vec::new(),
+            constraint_positions: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<F: num_traits::Float> IslandGroup<F> {
+    /// Returns the indices of a pair of bodies in the copies of the island, copying them
+    /// the first time they're seen.
+    fn localize(
+        &mut self,
+        (one, two): (usize, Option<usize>),
+        bodies: &[RigidBody<F>],
+    ) -> (usize, Option<usize>) {
+        let mut local = |global: usize| match self.locals.get(&global) {
+            Some(local) => *local,
+            None => {
+                self.locals.insert(global, self.globals.len());
+                self.bodies.push(bodies[global]);
+                self.globals.push(global);
+                self.globals.len() - 1
+            }
+        };
+        let one = local(one);
+        (one, two.map(local))
+    }
+
+    /// Returns the indices of a pair of bodies in the world, from their indices in the copies.
+    fn globalize(&self, (one, two): (usize, Option<usize>)) -> (usize, Option<usize>) {
+        (self.globals[one], two.map(|body| self.globals[body]))
+    }
+}
+
+/// Wakes up the sleeping body of a pair in contact with an awake or moving kinematic body.
+/// Returns true if the contacts between the pair need to be solved.
+fn wake_up_pair<F: num_traits::Float>(
+    pair: (usize, Option<usize>),
+    bodies: &mut [RigidBody<F>],
+) -> bool {
+    let one = is_active(&bodies[pair.0]);
+    let two = pair.1.is_some_and(|body| is_active(&bodies[body]));
+    if one == two {
+        return one;
+    }
+
+    let sleeping = if one { pair.1 } else { Some(pair.0) };
+    if let Some(body) = sleeping {
+        if bodies[body].has_finite_mass() {
+            bodies[body].set_awake(true);
+        }
+    }
+    true
+}
+
+/// Returns true if the body can be moved by the contact solver, or is a kinematic body
+/// moving on its own, which keeps the bodies it touches awake.
+fn is_active<F: num_traits::Float>(body: &RigidBody<F>) -> bool {
+    is_moving(body)
+        || (body.body_type == BodyType::Kinematic
+            && body.is_awake
+            && (body.velocity.squared_magnitude() > F::zero()
+                || body.rotation.squared_magnitude() > F::zero()))
+}
+
+/// Returns which bodies of a pair in contact can be pushed by the other one: both of them,
+/// unless one dominates the other. Bodies are never dominated by the scenery.
+fn movable<F: num_traits::Float>(
+    (one, two): (usize, Option<usize>),
+    bodies: &[RigidBody<F>],
+) -> [bool; 2] {
+    match two {
+        Some(two) => {
+            let (one, two) = (bodies[one].dominance, bodies[two].dominance);
+            [one <= two, two <= one]
+        }
+        None => [true, true],
+    }
+}
+
+/// Returns true if the body can be moved by the contact solver.
+pub(crate) fn is_moving<F: num_traits::Float>(body: &RigidBody<F>) -> bool {
+    body.has_finite_mass() && body.is_awake
+}
+
+/// Returns two unit vectors perpendicular to the given normal and to each other.
+pub(crate) fn tangent_basis<F: num_traits::Float>(normal: &Vector3<F>) -> [Vector3<F>; 2] {
+    // Cross the normal with the world axis it's most perpendicular to.
+    let axis = if normal.x.abs() < normal.y.abs() && normal.x.abs() < normal.z.abs() {
+        Vector3::new(F::one(), F::zero(), F::zero())
+    } else if normal.y.abs() < normal.z.abs() {
+        Vector3::new(F::zero(), F::one(), F::zero())
+    } else {
+        Vector3::new(F::zero(), F::zero(), F::one())
+    };
+    let mut first = normal.cross_product(&axis);
+    first.inplace_normalize();
+    let second = normal.cross_product(&first);
+    [first, second]
+}
+
+/// Constraint solved with sequential impulses, over the bodies of either the 3D or the
+/// 2D world.
+pub(crate) trait ImpulseConstraint<B> {
+    /// Applies the impulses the constraint starts with, unless they're applied when it's
+    /// prepared.
+    fn warm_start(&self, _bodies: &mut [B]) {}
+
+    /// Runs one iteration of the solver over the constraint.
+    fn solve(&mut self, bodies: &mut [B]);
+}
+
+/// Warm starts the given joint rows and contacts, and runs the given number of iterations
+/// over them, solving the rows first in every iteration.
+pub(crate) fn iterate_impulses<B, R: ImpulseConstraint<B>, C: ImpulseConstraint<B>>(
+    iterations: usize,
+    rows: &mut [R],
+    constraints: &mut [C],
+    bodies: &mut [B],
+) {
+    for row in rows.iter() {
+        row.warm_start(bodies);
+    }
+    for constraint in constraints.iter() {
+        constraint.warm_start(bodies);
+    }
+
+    for _ in 0..iterations {
+        for row in rows.iter_mut() {
+            row.solve(bodies);
+        }
+        for constraint in constraints.iter_mut() {
+            constraint.solve(bodies);
+        }
+    }
+}
+
+/// Returns the velocity a contact separates at, bouncing back if it closes fast enough, and
+/// pushing penetrated bodies apart with the given Baumgarte fraction beyond the allowed
+/// penetration, if any, whichever needs the largest velocity.
+pub(crate) fn separating_velocity<F: num_traits::Float>(
+    closing_velocity: F,
+    restitution: F,
+    penetration: F,
+    bias: Option<(F, F)>,
+    duration: F,
+) -> F {
+    let bounce = if closing_velocity < -math::real::<F>(RESTITUTION_VELOCITY_LIMIT) {
+        -restitution * closing_velocity
+    } else {
+        num_traits::zero()
+    };
+    let correction = match bias {
+        Some((baumgarte, allowed)) => baumgarte * (penetration - allowed).max(F::zero()) / duration,
+        None => num_traits::zero(),
+    };
+    bounce.max(correction)
+}
+
+/// Returns the impulse a joint row accumulates, from the one it accumulated so far, when
+/// driving its relative velocity towards its bias by the given velocity error. The impulse
+/// is softened in proportion to the accumulated one, and clamped within the given bounds.
+pub(crate) fn row_impulse<F: num_traits::Float>(
+    previous: F,
+    error: F,
+    softness: F,
+    mass: F,
+    (lower, upper): (F, F),
+) -> F {
+    (previous + (error - softness * previous) * mass)
+        .max(lower)
+        .min(upper)
+}
+
+/// Returns the factor scaling a friction impulse of the given magnitude, so it sticks inside
+/// the static friction cone of the normal impulse, and otherwise slides on the dynamic one.
+pub(crate) fn friction_scale<F: num_traits::Float>(
+    magnitude: F,
+    normal_impulse: F,
+    static_friction: F,
+    dynamic_friction: F,
+) -> F {
+    if magnitude > static_friction * normal_impulse {
+        dynamic_friction * normal_impulse / magnitude
+    } else {
+        F::one()
+    }
+}
+
+/// Contact point prepared for the solver, with the data that doesn't change between iterations.
+struct ContactConstraint<F: num_traits::Float> {
+    bodies: (usize, Option<usize>),
+    movable: [bool; 2],
+    relative_one: Vector3<F>,
+    relative_two: Vector3<F>,
+    normal: Vector3<F>,
+    tangents: [Vector3<F>; 2],
+    normal_mass: F,
+    tangent_masses: [F; 2],
+    target_velocity: F,
+    surface_velocities: [F; 2],
+    dynamic_friction: F,
+    static_friction: F,
+    normal_impulse: F,
+    tangent_impulses: [F; 2],
+}
+
+impl<F: num_traits::Float> ContactConstraint<F> {
+    /// Prepares the given manifold point to be solved, pushing it apart with the given
+    /// Baumgarte fraction beyond the allowed penetration, if any.
+    fn new(
+        point: &ManifoldPoint<F>,
+        bodies: &[RigidBody<F>],
+        bias: Option<(F, F)>,
+        duration: F,
+    ) -> Self {
+        let contact = &point.contact;
+        let relative_one = contact
+            .contact_point
+            .vector_sub(&bodies[contact.bodies.0].position);
+        let relative_two = match contact.bodies.1 {
+            Some(body) => contact.contact_point.vector_sub(&bodies[body].position),
+            None => Vector3::origin(),
+        };
+        let normal = contact.contact_normal;
+        let tangents = tangent_basis(&normal);
+
+        let mut constraint = Self {
+            bodies: contact.bodies,
+            movable: movable(contact.bodies, bodies),
+            relative_one,
+            relative_two,
+            normal,
+            tangents,
+            normal_mass: num_traits::zero(),
+            tangent_masses: [num_traits::zero(); 2],
+            target_velocity: num_traits::zero(),
+            surface_velocities: [
+                contact.surface_velocity.dot_product(&tangents[0]),
+                contact.surface_velocity.dot_product(&tangents[1]),
+            ],
+            dynamic_friction: contact.friction,
+            static_friction: contact.static_friction.max(contact.friction),
+            normal_impulse: point.normal_impulse,
+            tangent_impulses: point.tangent_impulses,
+        };
+        constraint.update_masses(bodies);
+
+        // Speculative contacts, generated within the contact margin before the bodies touch,
+        // let them close the gap between them, but no more.
+        if contact.margin > F::zero()
+            && contact.penetration < F::zero()
+            && -contact.penetration <= contact.margin
+        {
+            constraint.target_velocity = contact.penetration / duration;
+            return constraint;
+        }
+
+        let closing_velocity = constraint.relative_velocity(bodies).dot_product(&normal);
+        constraint.target_velocity = separating_velocity(
+            closing_velocity,
+            contact.restitution,
+            contact.penetration,
+            bias,
+            duration,
+        );
+        constraint
+    }
+
+    /// Updates the masses the contact opposes to impulses along its normal and tangents.
+    fn update_masses(&mut self, bodies: &[RigidBody<F>]) {
+        self.normal_mass = self.effective_mass(&self.normal, bodies);
+        self.tangent_masses = [
+            self.effective_mass(&self.tangents[0], bodies),
+            self.effective_mass(&self.tangents[1], bodies),
+        ];
+    }
+
+    /// Returns the mass the contact opposes to an impulse along the given direction.
+    /// Dominant bodies oppose an infinite mass.
+    fn effective_mass(&self, direction: &Vector3<F>, bodies: &[RigidBody<F>]) -> F {
+        let mut inverse = F::zero();
+        if self.movable[0] {
+            inverse = inverse_mass_along(&bodies[self.bodies.0], &self.relative_one, direction);
+        }
+        if let (Some(body), true) = (self.bodies.1, self.movable[1]) {
+            inverse = inverse + inverse_mass_along(&bodies[body], &self.relative_two, direction);
+        }
+
+        if inverse > F::zero() {
+            F::one() / inverse
+        } else {
+            num_traits::zero()
+        }
+    }
+
+    /// Returns the velocity of the first body relative to the second one, at the contact point.
+    fn relative_velocity(&self, bodies: &[RigidBody<F>]) -> Vector3<F> {
+        let one = &bodies[self.bodies.0];
+        let mut velocity = one
+            .velocity
+            .vector_add(&one.rotation.cross_product(&self.relative_one));
+        if let Some(body) = self.bodies.1 {
+            let two = &bodies[body];
+            velocity.inplace_vector_sub(
+                &two.velocity
+                    .vector_add(&two.rotation.cross_product(&self.relative_two)),
+            );
+        }
+        velocity
+    }
+
+    /// Applies the given impulse to the first body at the contact point,
+    /// and its opposite to the second body, unless they dominate each other.
+    fn apply_impulse(&self, impulse: &Vector3<F>, bodies: &mut [RigidBody<F>]) {
+        if self.movable[0] {
+            apply_impulse_at(&mut bodies[self.bodies.0], &self.relative_one, impulse);
+        }
+        if let (Some(body), true) = (self.bodies.1, self.movable[1]) {
+            apply_impulse_at(&mut bodies[body], &self.relative_two, &impulse.invert());
+        }
+    }
+}
+
+impl<F: num_traits::Float> ImpulseConstraint<RigidBody<F>> for ContactConstraint<F> {
+    /// Applies the impulses the contact starts with.
+    fn warm_start(&self, bodies: &mut [RigidBody<F>]) {
+        let impulse = self
+            .normal
+            .scalar_mul(self.normal_impulse)
+            .vector_add(&self.tangents[0].scalar_mul(self.tangent_impulses[0]))
+            .vector_add(&self.tangents[1].scalar_mul(self.tangent_impulses[1]));
+        self.apply_impulse(&impulse, bodies);
+    }
+
+    /// Runs one iteration of the solver over the contact.
+    fn solve(&mut self, bodies: &mut [RigidBody<F>]) {
+        // Friction is solved first, since the normal impulse is the more important one.
+        // It drives the sliding velocity towards the one of the surfaces.
+        let velocity = self.relative_velocity(bodies);
+        let previous = self.tangent_impulses;
+        let sliding = |axis: usize| {
+            velocity.dot_product(&self.tangents[axis]) - self.surface_velocities[axis]
+        };
+        let mut tangent_impulses = [
+            previous[0] - sliding(0) * self.tangent_masses[0],
+            previous[1] - sliding(1) * self.tangent_masses[1],
+        ];
+
+        let scale = friction_scale(
+            tangent_impulses[0].hypot(tangent_impulses[1]),
+            self.normal_impulse,
+            self.static_friction,
+            self.dynamic_friction,
+        );
+        tangent_impulses = [tangent_impulses[0] * scale, tangent_impulses[1] * scale];
+        self.tangent_impulses = tangent_impulses;
+        let impulse = self.tangents[0]
+            .scalar_mul(tangent_impulses[0] - previous[0])
+            .vector_add(&self.tangents[1].scalar_mul(tangent_impulses[1] - previous[1]));
+        self.apply_impulse(&impulse, bodies);
+
+        let velocity = self.relative_velocity(bodies).dot_product(&self.normal);
+        let previous = self.normal_impulse;
+        self.normal_impulse =
+            (previous + (self.target_velocity - velocity) * self.normal_mass).max(F::zero());
+        let impulse = self.normal.scalar_mul(self.normal_impulse - previous);
+        self.apply_impulse(&impulse, bodies);
+    }
+}
+
+/// Runs one more iteration over the contacts, from the ground up, with the lower body of
+/// every contact between bodies on different levels of a stack treated as immovable.
+fn propagate_shock<F: num_traits::Float>(
+    constraints: &mut [ContactConstraint<F>],
+    bodies: &mut [RigidBody<F>],
+) {
+    let levels = stack_levels(constraints, bodies);
+    let level = |body: usize| {
+        if is_moving(&bodies[body]) {
+            levels[body]
+        } else {
+            0
+        }
+    };
+    let mut order: Vec<(usize, usize)> = constraints
+        .iter()
+        .enumerate()
+        .map(|(index, constraint)| {
+            let (one, two) = constraint.bodies;
+            (level(one).min(two.map_or(0, level)), index)
+        })
+        .collect();
+    order.sort_unstable();
+
+    for (_, index) in order {
+        let constraint = &mut constraints[index];
+        let lower = match constraint.bodies {
+            (one, Some(two)) if is_moving(&bodies[one]) && is_moving(&bodies[two]) => {
+                match levels[one].cmp(&levels[two]) {
+                    std::cmp::Ordering::Less => Some(one),
+                    std::cmp::Ordering::Greater => Some(two),
+                    std::cmp::Ordering::Equal => None,
+                }
+            }
+            _ => None,
+        };
+        let lower = match lower {
+            Some(lower) => lower,
+            None => {
+                constraint.solve(bodies);
+                continue;
+            }
+        };
+
+        // Bodies without inverse mass can't be moved, but still move the bodies above.
+        let inverse_mass = bodies[lower].inverse_mass;
+        bodies[lower].inverse_mass = num_traits::zero();
+        constraint.update_masses(bodies);
+        constraint.solve(bodies);
+        bodies[lower].inverse_mass = inverse_mass;
+        constraint.update_masses(bodies);
+    }
+}
+
+/// Returns the level of every body in the stacks formed by the contacts, counting the
+/// contacts between them and the bodies that can't move, or `usize::MAX` for the bodies
+/// not resting on any of those.
+fn stack_levels<F: num_traits::Float>(
+    constraints: &[ContactConstraint<F>],
+    bodies: &[RigidBody<F>],
+) -> Vec<usize> {
+    let mut levels = vec![usize::MAX; bodies.len()];
+    let mut neighbors = vec![Vec::new(); bodies.len()];
+    let mut queue = std::collections::VecDeque::new();
+    for constraint in constraints.iter() {
+        let (one, two) = constraint.bodies;
+        let grounded = match two {
+            Some(two) if is_moving(&bodies[one]) && is_moving(&bodies[two]) => {
+                neighbors[one].push(two);
+                neighbors[two].push(one);
+                continue;
+            }
+            Some(two) if is_moving(&bodies[two]) => two,
+            _ => one,
+        };
+        if is_moving(&bodies[grounded]) && levels[grounded] != 0 {
+            levels[grounded] = 0;
+            queue.push_back(grounded);
+        }
+    }
+
+    while let Some(body) = queue.pop_front() {
+        for neighbor in neighbors[body].iter() {
+            if levels[*neighbor] == usize::MAX {
+                levels[*neighbor] = levels[body] + 1;
+                queue.push_back(*neighbor);
+            }
+        }
+    }
+    levels
+}
+
+/// Returns the inverse mass a body opposes to an impulse along the given direction,
+/// applied at the given point relative to its center of mass.
+fn inverse_mass_along<F: num_traits::Float>(
+    body: &RigidBody<F>,
+    relative: &Vector3<F>,
+    direction: &Vector3<F>,
+) -> F {
+    if !is_moving(body) {
+        return num_traits::zero();
+    }
+
+    let angular = body
+        .inverse_inertia_tensor_world
+        .transform(&relative.cross_product(direction))
+        .cross_product(relative);
+    body.velocity_change(direction).dot_product(direction) + angular.dot_product(direction)
+}
+
+/// Changes the velocities of a body by an impulse applied at the given point
+/// relative to its center of mass. Bodies that can't move are left untouched.
+fn apply_impulse_at<F: num_traits::Float>(
+    body: &mut RigidBody<F>,
+    relative: &Vector3<F>,
+    impulse: &Vector3<F>,
+) {
+    if !is_moving(body) {
+        return;
+    }
+
+    body.velocity
+        .inplace_vector_add(&body.velocity_change(impulse));
+    body.rotation.inplace_vector_add(
+        &body
+            .inverse_inertia_tensor_world
+            .transform(&relative.cross_product(impulse)),
+    );
+}
+
+/// Moves a body as if a position impulse was applied at the given point relative to its
+/// center of mass. Bodies that can't move are left untouched.
+fn move_at<F: num_traits::Float>(
+    body: &mut RigidBody<F>,
+    relative: &Vector3<F>,
+    impulse: &Vector3<F>,
+) {
+    if !is_moving(body) {
+        return;
+    }
+
+    body.position
+        .inplace_vector_add(&body.velocity_change(impulse));
+    let rotation = body
+        .inverse_inertia_tensor_world
+        .transform(&relative.cross_product(impulse));
+    body.orientation
+        .inplace_add_scaled_vector(&rotation, F::one());
+    body.calculate_derived_data();
+}
@@ -0,0 +1,255 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::manifold::ManifoldCache;
+use crate::narrow_phase::{
+    box_and_half_space, sphere_and_half_space, sphere_and_sphere, CollisionData,
+};
+use crate::plane::Plane;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Cuboid, Sphere};
+use crate::solver::*;
+use math::Vector3;
+
+fn ball(position: Vector3<f64>, velocity: Vector3<f64>) -> RigidBody {
+    let sphere = Sphere::new(1.0);
+    let mut body = RigidBody::new(position, 1.0, &sphere.inertia_tensor(1.0));
+    body.velocity = velocity;
+    body
+}
+
+fn colliding_balls(bodies: &[RigidBody], restitution: f64) -> ManifoldCache {
+    let mut colliders: Vec<Collider<Sphere>> = (0..bodies.len())
+        .map(|body| Collider::new(body, Sphere::new(1.0)))
+        .collect();
+    for collider in colliders.iter_mut() {
+        collider.calculate_internals(bodies);
+    }
+
+    let mut data = CollisionData::new(4);
+    data.restitution = restitution;
+    sphere_and_sphere(&colliders[0], &colliders[1], &mut data);
+    let mut cache = ManifoldCache::default();
+    cache.update(&data.contacts, bodies);
+    cache
+}
+
+#[test]
+fn restitution() {
+    let mut bodies = vec![
+        ball(Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0)),
+        ball(Vector3::new(1.9, 0.0, 0.0), Vector3::new(-2.0, 0.0, 0.0)),
+    ];
+
+    // Perfectly elastic bodies of the same mass exchange their velocities.
+    let mut cache = colliding_balls(&bodies, 1.0);
+    ContactSolver::default().solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert!((bodies[0].velocity.x + 2.0).abs() < 1e-9);
+    assert!((bodies[1].velocity.x - 2.0).abs() < 1e-9);
+    assert_eq!(0.0, bodies[0].rotation.squared_magnitude());
+    let point = &cache.get((0, Some(1))).unwrap().points[0];
+    assert!((point.normal_impulse - 4.0).abs() < 1e-9);
+
+    // Perfectly plastic bodies stop, only separating to fix the penetration.
+    bodies[0].velocity = Vector3::new(2.0, 0.0, 0.0);
+    bodies[1].velocity = Vector3::new(-2.0, 0.0, 0.0);
+    let mut cache = colliding_balls(&bodies, 0.0);
+    ContactSolver::default().solve(&mut cache, &mut [], &mut bodies, 1.0);
+    let separating = bodies[1].velocity.x - bodies[0].velocity.x;
+    assert!((separating - 0.2 * 0.095).abs() < 1e-9);
+    assert!((bodies[0].velocity.x + bodies[1].velocity.x).abs() < 1e-9);
+}
+
+#[test]
+fn dominance() {
+    let mut bodies = vec![
+        ball(Vector3::new(0.0, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0)),
+        ball(Vector3::new(1.9, 0.0, 0.0), Vector3::new(-2.0, 0.0, 0.0)),
+    ];
+    bodies[0].dominance = 1;
+
+    // The dominant body keeps going, pushing the other one along.
+    let mut cache = colliding_balls(&bodies, 0.0);
+    ContactSolver::default().solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), bodies[0].velocity);
+    assert!((bodies[1].velocity.x - 2.0 - 0.2 * 0.095).abs() < 1e-9);
+
+    // Bouncing off it, the other body takes all the energy of the impact.
+    bodies[1].velocity = Vector3::new(-2.0, 0.0, 0.0);
+    let mut cache = colliding_balls(&bodies, 1.0);
+    ContactSolver::default().solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), bodies[0].velocity);
+    assert!((bodies[1].velocity.x - 6.0).abs() < 1e-9);
+
+    // Bodies with the same dominance push each other as usual.
+    bodies[1].dominance = 1;
+    bodies[1].velocity = Vector3::new(-2.0, 0.0, 0.0);
+    let mut cache = colliding_balls(&bodies, 1.0);
+    ContactSolver::default().solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert!((bodies[0].velocity.x + 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn separating_bodies() {
+    let mut bodies = vec![
+        ball(Vector3::new(0.0, 0.0, 0.0), Vector3::new(-2.0, 0.0, 0.0)),
+        ball(Vector3::new(1.999, 0.0, 0.0), Vector3::new(2.0, 0.0, 0.0)),
+    ];
+    let mut cache = colliding_balls(&bodies, 1.0);
+    ContactSolver::default().solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert_eq!(Vector3::new(-2.0, 0.0, 0.0), bodies[0].velocity);
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), bodies[1].velocity);
+    assert_eq!(
+        0.0,
+        cache.get((0, Some(1))).unwrap().points[0].normal_impulse
+    );
+}
+
+#[test]
+fn position_correction() {
+    let resting = || {
+        vec![
+            ball(Vector3::new(0.0, 0.0, 0.0), Vector3::origin()),
+            ball(Vector3::new(1.9, 0.0, 0.0), Vector3::origin()),
+        ]
+    };
+
+    // Penetration within the allowed one isn't corrected.
+    let mut bodies = resting();
+    let mut cache = colliding_balls(&bodies, 0.0);
+    let solver = ContactSolver {
+        allowed_penetration: 0.15,
+        ..ContactSolver::default()
+    };
+    solver.solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert!((bodies[1].velocity.x - bodies[0].velocity.x).abs() < 1e-9);
+
+    // The non-linear Gauss-Seidel correction moves the bodies instead of pushing them.
+    let mut bodies = resting();
+    let mut cache = colliding_balls(&bodies, 0.0);
+    let mut solver = ContactSolver {
+        position_correction: PositionCorrection::NonLinearGaussSeidel(1),
+        baumgarte: 0.5,
+        ..ContactSolver::default()
+    };
+    solver.solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert!((bodies[1].velocity.x - bodies[0].velocity.x).abs() < 1e-9);
+    solver.correct_positions(&cache, &mut bodies);
+    let distance = bodies[1].position.x - bodies[0].position.x;
+    assert!((distance - (1.9 + 0.5 * 0.095)).abs() < 1e-9);
+    assert!((bodies[0].position.x + bodies[1].position.x - 1.9).abs() < 1e-9);
+    assert!(bodies[0].velocity.magnitude() < 1e-9);
+
+    // More iterations get closer to the allowed penetration.
+    solver.position_correction = PositionCorrection::NonLinearGaussSeidel(20);
+    solver.correct_positions(&cache, &mut bodies);
+    let distance = bodies[1].position.x - bodies[0].position.x;
+    assert!((distance - (2.0 - DEFAULT_ALLOWED_PENETRATION)).abs() < 1e-6);
+}
+
+fn sliding_block(friction: f64) -> RigidBody {
+    let cuboid = Cuboid::new(Vector3::new(1.0, 1.0, 1.0));
+    let mut bodies = vec![RigidBody::new(
+        Vector3::new(0.0, 0.999, 0.0),
+        1.0,
+        &cuboid.inertia_tensor(1.0),
+    )];
+    bodies[0].velocity = Vector3::new(1.0, -1.0, 0.0);
+
+    let mut collider = Collider::new(0, cuboid);
+    collider.calculate_internals(&bodies);
+    let mut data = CollisionData::new(8);
+    data.friction = friction;
+    box_and_half_space(
+        &collider,
+        &Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0),
+        &mut data,
+    );
+    let mut cache = ManifoldCache::default();
+    cache.update(&data.contacts, &bodies);
+    ContactSolver::new(20).solve(&mut cache, &mut [], &mut bodies, 0.1);
+    bodies[0]
+}
+
+#[test]
+fn friction() {
+    let frictionless = sliding_block(0.0);
+    assert!((frictionless.velocity.x - 1.0).abs() < 1e-9);
+    assert!(frictionless.velocity.y.abs() < 1e-6);
+
+    // The normal impulse stopping the fall is enough to stop the sliding too.
+    let rough = sliding_block(2.0);
+    assert!(rough.velocity.x.abs() < 1e-2);
+    assert!(rough.velocity.y.abs() < 1e-2);
+    assert!(rough.rotation.magnitude() < 1e-2);
+
+    // Weaker friction only slows the block down.
+    let smooth = sliding_block(0.25);
+    assert!(smooth.velocity.x > 0.5 && smooth.velocity.x < 1.0);
+}
+
+#[test]
+fn sleeping_bodies() {
+    let mut bodies = vec![
+        ball(Vector3::new(0.0, 0.0, 0.0), Vector3::origin()),
+        ball(Vector3::new(1.9, 0.0, 0.0), Vector3::origin()),
+    ];
+    bodies[0].set_awake(false);
+    bodies[1].set_awake(false);
+    let mut cache = colliding_balls(&bodies, 0.0);
+    ContactSolver::default().solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert!(!bodies[0].is_awake && !bodies[1].is_awake);
+    assert_eq!(Vector3::origin(), bodies[1].velocity);
+
+    // An awake body wakes up the sleeping body it touches.
+    bodies[0].set_awake(true);
+    ContactSolver::default().solve(&mut cache, &mut [], &mut bodies, 1.0);
+    assert!(bodies[1].is_awake);
+    assert!(bodies[1].velocity.x > 0.0);
+}
+
+fn resting_ball(cache: &mut ManifoldCache, solver: &ContactSolver) -> RigidBody {
+    let mut bodies = vec![ball(Vector3::new(0.0, 0.99, 0.0), Vector3::origin())];
+    let mut collider = Collider::new(0, Sphere::new(1.0));
+    collider.calculate_internals(&bodies);
+    let mut data = CollisionData::new(4);
+    sphere_and_half_space(
+        &collider,
+        &Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0),
+        &mut data,
+    );
+    cache.update(&data.contacts, &bodies);
+
+    // Gravity pulls the ball into the ground every frame.
+    bodies[0].velocity = Vector3::new(0.0, -0.1, 0.0);
+    solver.solve(cache, &mut [], &mut bodies, 0.01);
+    bodies[0]
+}
+
+#[test]
+fn warm_starting() {
+    let mut cache = ManifoldCache::default();
+    let solver = ContactSolver::default();
+    let first = resting_ball(&mut cache, &solver);
+    let impulse = cache.get((0, None)).unwrap().points[0].normal_impulse;
+    assert!(impulse > 0.1);
+
+    // Without iterations, the impulse of the previous frame alone holds the ball.
+    let lazy = ContactSolver::new(0);
+    let second = resting_ball(&mut cache, &lazy);
+    assert!((second.velocity.y - first.velocity.y).abs() < 1e-9);
+    assert_eq!(
+        impulse,
+        cache.get((0, None)).unwrap().points[0].normal_impulse
+    );
+
+    let mut cold = lazy;
+    cold.warm_starting = false;
+    let third = resting_ball(&mut cache, &cold);
+    assert_eq!(-0.1, third.velocity.y);
+    assert_eq!(0.0, cache.get((0, None)).unwrap().points[0].normal_impulse);
+}
@@ -0,0 +1,461 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::broad_phase::{BroadPhase, DEFAULT_BROAD_PHASE_MARGIN};
+use crate::plane::Plane;
+use crate::ray::Ray;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Default ratio between the loose bounds of an octree cell and the cell itself.
+pub const DEFAULT_OCTREE_LOOSENESS: f64 = 2.0;
+
+/// Default maximum depth of an octree.
+pub const DEFAULT_OCTREE_MAX_DEPTH: usize = 8;
+
+/// Convex volume bounded by six planes, like the view volume of a camera.
+///
+/// # Remarks
+/// The normals of the planes point towards the inside of the frustum.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Frustum<F: num_traits::Float = f64> {
+    /// Planes bounding the frustum, with their normals pointing inwards.
+    pub planes: [Plane<F>; 6],
+}
+
+impl<F: num_traits::Float> Frustum<F> {
+    /// Creates a new frustum bounded by the given planes.
+    pub fn new(planes: [Plane<F>; 6]) -> Self {
+        Self { planes }
+    }
+
+    /// Returns true if the given point is inside the frustum.
+    pub fn contains_point(&self, point: &Vector3<F>) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= num_traits::zero())
+    }
+
+    /// Returns true if the bounding box might overlap the frustum.
+    ///
+    /// # Remarks
+    /// The test is conservative: boxes close to the edges of the frustum may be
+    /// reported as overlapping even if they are slightly outside.
+    pub fn intersects_aabb(&self, aabb: &Aabb<F>) -> bool {
+        self.planes.iter().all(|plane| plane.intersects_aabb(aabb))
+    }
+}
+
+/// Coordinates of a cell of a spatial hash.
+type CellCoordinates = (i64, i64, i64);
+
+/// Uniform grid of cubic cells, hashing points to find the ones near a location.
+///
+/// # Remarks
+/// Every point is stored in the cell containing it, so finding the points within a radius
+/// only visits the cells overlapping the sphere. Queries are fastest when the cell size is
+/// close to the radius, as happens with the neighborhoods of the particles of a fluid.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SpatialHash<F: num_traits::Float = f64> {
+    /// Length of the edges of the cells.
+    pub cell_size: F,
+
+    cells: HashMap<CellCoordinates, Vec<(usize, Vector3<F>)>>,
+}
+
+impl<F: num_traits::Float> SpatialHash<F> {
+    /// Creates a new empty spatial hash with cells of the given size.
+    pub fn new(cell_size: F) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Removes every point from the spatial hash, keeping the memory of the cells in use.
+    pub fn clear(&mut self) {
+        self.cells.retain(|_, points| !points.is_empty());
+        for points in self.cells.values_mut() {
+            points.clear();
+        }
+    }
+
+    /// Adds a point with the given identifier to the spatial hash.
+    pub fn insert(&mut self, id: usize, point: &Vector3<F>) {
+        self.cells
+            .entry(self.cell(point))
+            .or_default()
+            .push((id, *point));
+    }
+
+    /// Calls `callback` with the identifier of every point within `radius` of the given one.
+    /// The traversal stops when the callback returns false.
+    ///
+    /// # Remarks
+    /// Points in the same cell are visited in the order they were inserted.
+    pub fn query_sphere<C: FnMut(usize) -> bool>(
+        &self,
+        center: &Vector3<F>,
+        radius: F,
+        mut callback: C,
+    ) {
+        let extent = Vector3::new(radius, radius, radius);
+        let min = self.cell(&center.vector_sub(&extent));
+        let max = self.cell(&center.vector_add(&extent));
+        let squared_radius = radius * radius;
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    for (id, point) in self.cells.get(&(x, y, z)).into_iter().flatten() {
+                        let near = point.vector_sub(center).squared_magnitude() <= squared_radius;
+                        if near && !callback(*id) {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the identifiers of every point within `radius` of the given one.
+    pub fn within(&self, center: &Vector3<F>, radius: F) -> Vec<usize> {
+        let mut ids = Vec::new();
+        self.query_sphere(center, radius, |id| {
+            ids.push(id);
+            true
+        });
+        ids
+    }
+
+    /// Coordinates of the cell containing the given point.
+    fn cell(&self, point: &Vector3<F>) -> CellCoordinates {
+        let coordinate = |value: F| (value / self.cell_size).floor().to_i64().unwrap_or(0);
+        (
+            coordinate(point.x),
+            coordinate(point.y),
+            coordinate(point.z),
+        )
+    }
+}
+
+/// Cell of the loose octree.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct Cell<F: num_traits::Float> {
+    center: Vector3<F>,
+    half_size: F,
+    depth: usize,
+    children: [Option<usize>; 8],
+    proxies: Vec<usize>,
+}
+
+/// Loose octree, storing bounding boxes in a hierarchy of cubic cells.
+///
+/// # Remarks
+/// Every cell has loose bounds, larger than the cell by the looseness factor. A proxy
+/// is stored in the deepest cell that contains its center and whose loose bounds fully
+/// enclose it, so each proxy lives in exactly one cell and moving it is a cheap removal
+/// and insertion. Proxies outside the bounds of the tree are kept in the root cell.
+///
+/// The octree can be used as a broad phase, and for spatial lookups such as finding
+/// every proxy within a radius, inside a view frustum, or along a ray.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LooseOctree<F: num_traits::Float = f64> {
+    /// Margin by which the bounding boxes of proxies are fattened.
+    pub margin: F,
+
+    looseness: F,
+    max_depth: usize,
+    cells: Vec<Cell<F>>,
+    proxies: Vec<Option<(Aabb<F>, usize)>>,
+}
+
+impl<F: num_traits::Float> LooseOctree<F> {
+    /// Creates a new empty octree covering the cube with the given center and half-size.
+    pub fn new(center: Vector3<F>, half_size: F) -> Self {
+        Self::with_parameters(
+            center,
+            half_size,
+            math::real(DEFAULT_BROAD_PHASE_MARGIN),
+            math::real(DEFAULT_OCTREE_LOOSENESS),
+            DEFAULT_OCTREE_MAX_DEPTH,
+        )
+    }
+
+    /// Creates a new empty octree covering the cube with the given center and half-size,
+    /// with the given fattening margin, looseness factor and maximum depth.
+    /// Looseness factors below `1` are clamped, since cells can't be tighter than the cell itself.
+    pub fn with_parameters(
+        center: Vector3<F>,
+        half_size: F,
+        margin: F,
+        looseness: F,
+        max_depth: usize,
+    ) -> Self {
+        Self {
+            margin,
+            looseness: looseness.max(num_traits::one()),
+            max_depth,
+            cells: vec![Cell {
+                center,
+                half_size,
+                depth: 0,
+                children: [None; 8],
+                proxies: Vec::new(),
+            }],
+            proxies: Vec::new(),
+        }
+    }
+
+    /// Returns the number of proxies in the octree.
+    pub fn len(&self) -> usize {
+        self.proxies.iter().filter(|proxy| proxy.is_some()).count()
+    }
+
+    /// Returns true if there are no proxies in the octree.
+    pub fn is_empty(&self) -> bool {
+        self.proxies.iter().all(|proxy| proxy.is_none())
+    }
+
+    /// Returns the depth of the cell holding the given proxy, if any. The root cell has depth `0`.
+    pub fn depth(&self, id: usize) -> Option<usize> {
+        self.proxies
+            .get(id)
+            .copied()
+            .flatten()
+            .map(|(_, cell)| self.cells[cell].depth)
+    }
+
+    /// Calls `callback` with the identifier of every proxy whose fat bounding box overlaps
+    /// the given region. The traversal stops when the callback returns false.
+    pub fn query_aabb<C: FnMut(usize) -> bool>(&self, aabb: &Aabb<F>, callback: C) {
+        self.query(|bounds| bounds.intersects(aabb), callback);
+    }
+
+    /// Returns the identifiers of every proxy whose fat bounding box overlaps the given region.
+    pub fn intersecting(&self, aabb: &Aabb<F>) -> Vec<usize> {
+        Self::collect(|callback| self.query_aabb(aabb, callback))
+    }
+
+    /// Calls `callback` with the identifier of every proxy whose fat bounding box is
+    /// within `radius` of the given point. The traversal stops when the callback returns false.
+    pub fn query_sphere<C: FnMut(usize) -> bool>(
+        &self,
+        center: &Vector3<F>,
+        radius: F,
+        callback: C,
+    ) {
+        let squared_radius = radius * radius;
+        self.query(
+            |bounds| bounds.squared_distance_to_point(center) <= squared_radius,
+            callback,
+        );
+    }
+
+    /// Returns the identifiers of every proxy whose fat bounding box is within `radius`
+    /// of the given point.
+    pub fn within_radius(&self, center: &Vector3<F>, radius: F) -> Vec<usize> {
+        Self::collect(|callback| self.query_sphere(center, radius, callback))
+    }
+
+    /// Calls `callback` with the identifier of every proxy whose fat bounding box might
+    /// overlap the given frustum. The traversal stops when the callback returns false.
+    pub fn query_frustum<C: FnMut(usize) -> bool>(&self, frustum: &Frustum<F>, callback: C) {
+        self.query(|bounds| frustum.intersects_aabb(bounds), callback);
+    }
+
+    /// Returns the identifiers of every proxy whose fat bounding box might overlap the given frustum.
+    pub fn in_frustum(&self, frustum: &Frustum<F>) -> Vec<usize> {
+        Self::collect(|callback| self.query_frustum(frustum, callback))
+    }
+
+    /// Casts a ray through the octree, calling `callback` with the identifier of every
+    /// proxy whose fat bounding box is hit before the current maximum time of impact.
+    ///
+    /// # Remarks
+    /// The callback receives the proxy and the current maximum time of impact, and returns
+    /// the new maximum. Returning the time of impact of an actual hit clips the ray, so only
+    /// closer proxies are visited afterwards (closest hit search); returning the same maximum
+    /// visits every proxy along the ray, and returning a negative value stops the traversal.
+    pub fn cast_ray<C: FnMut(usize, F) -> F>(&self, ray: &Ray<F>, max_toi: F, mut callback: C) {
+        let mut max_toi = max_toi;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let cell = &self.cells[index];
+            if index != 0 && self.loose_bounds(cell).cast_ray(ray, max_toi).is_none() {
+                continue;
+            }
+
+            for proxy in cell.proxies.iter() {
+                let (aabb, _) = self.proxies[*proxy].expect("cells only reference live proxies");
+                if aabb.cast_ray(ray, max_toi).is_some() {
+                    let toi = callback(*proxy, max_toi);
+                    if toi < num_traits::zero() {
+                        return;
+                    }
+                    max_toi = max_toi.min(toi);
+                }
+            }
+            stack.extend(cell.children.iter().flatten());
+        }
+    }
+
+    fn collect<Q: FnOnce(&mut dyn FnMut(usize) -> bool)>(query: Q) -> Vec<usize> {
+        let mut proxies = Vec::new();
+        query(&mut |proxy| {
+            proxies.push(proxy);
+            true
+        });
+        proxies
+    }
+
+    /// Visits the proxies of every cell whose loose bounds pass the `test`,
+    /// calling `callback` for the proxies that pass it as well.
+    fn query<T: Fn(&Aabb<F>) -> bool, C: FnMut(usize) -> bool>(&self, test: T, mut callback: C) {
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            // The root also holds proxies outside of its bounds, so it's always visited.
+            let cell = &self.cells[index];
+            if index != 0 && !test(&self.loose_bounds(cell)) {
+                continue;
+            }
+
+            for proxy in cell.proxies.iter() {
+                let (aabb, _) = self.proxies[*proxy].expect("cells only reference live proxies");
+                if test(&aabb) && !callback(*proxy) {
+                    return;
+                }
+            }
+            stack.extend(cell.children.iter().flatten());
+        }
+    }
+
+    fn loose_bounds(&self, cell: &Cell<F>) -> Aabb<F> {
+        let half_size = cell.half_size * self.looseness;
+        Aabb::from_center(&cell.center, &Vector3::new(half_size, half_size, half_size))
+    }
+
+    /// Finds, creating it if needed, the deepest cell that can hold the given bounding box.
+    fn find_cell(&mut self, aabb: &Aabb<F>) -> usize {
+        let center = aabb.center();
+        let half_extents = aabb.half_extents();
+        let extent = half_extents.x.max(half_extents.y).max(half_extents.z);
+        let two: F = math::real(2.0);
+
+        let root = &self.cells[0];
+        let root_bounds = Aabb::from_center(
+            &root.center,
+            &Vector3::new(root.half_size, root.half_size, root.half_size),
+        );
+        if !root_bounds.contains_point(&center) {
+            return 0;
+        }
+
+        let mut index = 0;
+        loop {
+            let cell = &self.cells[index];
+            let child_half_size = cell.half_size / two;
+            if cell.depth >= self.max_depth
+                || extent > child_half_size * (self.looseness - num_traits::one())
+            {
+                return index;
+            }
+
+            // Pick the octant containing the center of the bounding box.
+            let mut octant = 0;
+            let mut child_center = cell.center;
+            if center.x >= cell.center.x {
+                octant |= 1;
+                child_center.x = child_center.x + child_half_size;
+            } else {
+                child_center.x = child_center.x - child_half_size;
+            }
+            if center.y >= cell.center.y {
+                octant |= 2;
+                child_center.y = child_center.y + child_half_size;
+            } else {
+                child_center.y = child_center.y - child_half_size;
+            }
+            if center.z >= cell.center.z {
+                octant |= 4;
+                child_center.z = child_center.z + child_half_size;
+            } else {
+                child_center.z = child_center.z - child_half_size;
+            }
+
+            index = match cell.children[octant] {
+                Some(child) => child,
+                None => {
+                    let depth = cell.depth + 1;
+                    self.cells.push(Cell {
+                        center: child_center,
+                        half_size: child_half_size,
+                        depth,
+                        children: [None; 8],
+                        proxies: Vec::new(),
+                    });
+                    let child = self.cells.len() - 1;
+                    self.cells[index].children[octant] = Some(child);
+                    child
+                }
+            };
+        }
+    }
+}
+
+impl<F: num_traits::Float> BroadPhase<F> for LooseOctree<F> {
+    fn insert(&mut self, id: usize, aabb: &Aabb<F>) {
+        if self.proxies.len() <= id {
+            self.proxies.resize(id + 1, None);
+        }
+
+        self.remove(id);
+        let fat = aabb.loosened(self.margin);
+        let cell = self.find_cell(&fat);
+        self.cells[cell].proxies.push(id);
+        self.proxies[id] = Some((fat, cell));
+    }
+
+    fn update(&mut self, id: usize, aabb: &Aabb<F>) -> bool {
+        match self.proxies.get(id).copied().flatten() {
+            Some((fat, _)) if fat.contains(aabb) => false,
+            _ => {
+                self.insert(id, aabb);
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some((_, cell)) = self.proxies.get_mut(id).and_then(|proxy| proxy.take()) {
+            self.cells[cell].proxies.retain(|proxy| *proxy != id);
+        }
+    }
+
+    fn fat_aabb(&self, id: usize) -> Option<Aabb<F>> {
+        self.proxies
+            .get(id)
+            .copied()
+            .flatten()
+            .map(|(aabb, _)| aabb)
+    }
+
+    fn potential_pairs(&mut self, pairs: &mut Vec<(usize, usize)>) {
+        pairs.clear();
+        for (id, proxy) in self.proxies.iter().enumerate() {
+            if let Some((aabb, _)) = proxy {
+                self.query_aabb(aabb, |other| {
+                    if other > id {
+                        pairs.push((id, other));
+                    }
+                    true
+                });
+            }
+        }
+        pairs.sort_unstable();
+    }
+}
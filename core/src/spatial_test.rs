@@ -0,0 +1,202 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::broad_phase::BroadPhase;
+use crate::plane::Plane;
+use crate::ray::Ray;
+use crate::spatial::*;
+use math::Vector3;
+
+fn cube(x: f64, y: f64, z: f64, half_size: f64) -> Aabb<f64> {
+    Aabb::from_center(
+        &Vector3::new(x, y, z),
+        &Vector3::new(half_size, half_size, half_size),
+    )
+}
+
+fn random() -> impl FnMut() -> f64 {
+    let mut seed = 7u64;
+    move || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn sorted(mut proxies: Vec<usize>) -> Vec<usize> {
+    proxies.sort_unstable();
+    proxies
+}
+
+#[test]
+fn frustum() {
+    // Axis aligned box from -1 to 1 on every axis.
+    let frustum = Frustum::new([
+        Plane::new(Vector3::new(1.0, 0.0, 0.0), -1.0),
+        Plane::new(Vector3::new(-1.0, 0.0, 0.0), -1.0),
+        Plane::new(Vector3::new(0.0, 1.0, 0.0), -1.0),
+        Plane::new(Vector3::new(0.0, -1.0, 0.0), -1.0),
+        Plane::new(Vector3::new(0.0, 0.0, 1.0), -1.0),
+        Plane::new(Vector3::new(0.0, 0.0, -1.0), -1.0),
+    ]);
+    assert!(frustum.contains_point(&Vector3::new(0.5, -0.5, 1.0)));
+    assert!(!frustum.contains_point(&Vector3::new(0.5, -1.5, 0.0)));
+    assert!(frustum.intersects_aabb(&cube(1.4, 0.0, 0.0, 0.5)));
+    assert!(!frustum.intersects_aabb(&cube(1.6, 0.0, 0.0, 0.5)));
+}
+
+#[test]
+fn general_usage() {
+    let mut octree = LooseOctree::with_parameters(Vector3::origin(), 64.0, 0.0, 2.0, 8);
+    assert!(octree.is_empty());
+
+    octree.insert(0, &cube(10.0, 10.0, 10.0, 0.5));
+    octree.insert(1, &cube(10.5, 10.0, 10.0, 0.5));
+    octree.insert(2, &cube(-20.0, 0.0, 0.0, 30.0));
+    octree.insert(3, &cube(500.0, 0.0, 0.0, 1.0));
+    assert_eq!(4, octree.len());
+
+    // Small proxies go deep, big proxies and the ones outside the tree stay shallow.
+    assert!(octree.depth(0).unwrap() >= 5);
+    assert_eq!(Some(1), octree.depth(2));
+    assert_eq!(Some(0), octree.depth(3));
+
+    let mut pairs = Vec::new();
+    octree.potential_pairs(&mut pairs);
+    assert_eq!(vec![(0, 1), (0, 2), (1, 2)], pairs);
+
+    assert_eq!(vec![3], octree.intersecting(&cube(499.0, 0.0, 0.0, 0.5)));
+    assert_eq!(
+        vec![0, 1],
+        sorted(octree.within_radius(&Vector3::new(12.0, 11.0, 10.0), 1.9))
+    );
+
+    // Moving a proxy out of its fat bounding box relocates it.
+    assert!(octree.update(3, &cube(10.0, 12.0, 10.0, 0.5)));
+    assert!(octree.depth(3).unwrap() > 0);
+    assert!(!octree.update(3, &cube(10.0, 12.0, 10.0, 0.5)));
+    assert_eq!(
+        vec![0, 1, 3],
+        sorted(octree.within_radius(&Vector3::new(12.0, 11.0, 10.0), 1.9))
+    );
+
+    octree.remove(0);
+    octree.remove(0);
+    assert_eq!(None, octree.fat_aabb(0));
+    assert_eq!(3, octree.len());
+}
+
+#[test]
+fn ray_queries() {
+    let mut octree = LooseOctree::with_parameters(Vector3::origin(), 32.0, 0.0, 2.0, 8);
+    for id in 0..10 {
+        octree.insert(id, &cube(id as f64 * 2.0, 0.0, 0.0, 0.5));
+    }
+    octree.insert(10, &cube(0.0, 10.0, 0.0, 0.5));
+
+    let ray = Ray::new(Vector3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    let mut hits = Vec::new();
+    octree.cast_ray(&ray, 100.0, |proxy, max_toi| {
+        hits.push(proxy);
+        max_toi
+    });
+    assert_eq!((0..10).collect::<Vec<_>>(), sorted(hits));
+
+    let mut closest = None;
+    octree.cast_ray(&ray, 100.0, |proxy, max_toi| {
+        let toi = octree
+            .fat_aabb(proxy)
+            .unwrap()
+            .cast_ray(&ray, max_toi)
+            .unwrap();
+        closest = Some((proxy, toi));
+        toi
+    });
+    assert_eq!(Some((0, 4.5)), closest);
+
+    // A short ray doesn't reach the proxies.
+    octree.cast_ray(&ray, 4.0, |_, _| panic!("unexpected hit"));
+}
+
+#[test]
+fn matches_brute_force() {
+    let mut next = random();
+    let mut octree = LooseOctree::new(Vector3::new(15.0, 15.0, 15.0), 16.0);
+    let mut aabbs: Vec<Aabb<f64>> = (0..300)
+        .map(|_| cube(next() * 30.0, next() * 30.0, next() * 30.0, next() * 2.0))
+        .collect();
+    for (id, aabb) in aabbs.iter().enumerate() {
+        octree.insert(id, aabb);
+    }
+
+    let mut pairs = Vec::new();
+    for _ in 0..3 {
+        for (id, aabb) in aabbs.iter_mut().enumerate() {
+            let center = aabb.center();
+            let half_size = aabb.half_extents().x;
+            *aabb = cube(
+                center.x + next() * 4.0 - 2.0,
+                center.y + next() * 4.0 - 2.0,
+                center.z + next() * 4.0 - 2.0,
+                half_size,
+            );
+            octree.update(id, aabb);
+        }
+
+        let fat: Vec<Aabb<f64>> = (0..aabbs.len())
+            .map(|id| octree.fat_aabb(id).unwrap())
+            .collect();
+        let mut expected = Vec::new();
+        for first in 0..fat.len() {
+            for second in first + 1..fat.len() {
+                if fat[first].intersects(&fat[second]) {
+                    expected.push((first, second));
+                }
+            }
+        }
+        octree.potential_pairs(&mut pairs);
+        assert_eq!(expected, pairs);
+
+        let center = Vector3::new(15.0, 15.0, 15.0);
+        let expected: Vec<usize> = (0..fat.len())
+            .filter(|id| fat[*id].squared_distance_to_point(&center) <= 25.0)
+            .collect();
+        assert_eq!(expected, sorted(octree.within_radius(&center, 5.0)));
+    }
+}
+
+#[test]
+fn spatial_hash() {
+    let mut next = random();
+    let points: Vec<Vector3<f64>> = (0..500)
+        .map(|_| Vector3::new(next() * 10.0 - 5.0, next() * 10.0 - 5.0, next() * 10.0))
+        .collect();
+    let mut hash = SpatialHash::new(0.75);
+    for (id, point) in points.iter().enumerate() {
+        hash.insert(id, point);
+    }
+
+    for center in points.iter().step_by(25) {
+        let expected: Vec<usize> = (0..points.len())
+            .filter(|id| (points[*id] - *center).magnitude() <= 1.0)
+            .collect();
+        assert_eq!(expected, sorted(hash.within(center, 1.0)));
+    }
+
+    // Clearing the hash forgets the points, and the traversal stops when asked to.
+    hash.clear();
+    assert!(hash.within(&points[0], 100.0).is_empty());
+    hash.insert(3, &Vector3::origin());
+    hash.insert(4, &Vector3::origin());
+    let mut visited = 0;
+    hash.query_sphere(&Vector3::origin(), 1.0, |_| {
+        visited += 1;
+        false
+    });
+    assert_eq!(1, visited);
+}
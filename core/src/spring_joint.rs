@@ -0,0 +1,170 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::hinge_joint::twist_angle;
+use crate::joint::{world_direction, Anchors, JointSpring, RowBuilder};
+use crate::rigid_body::RigidBody;
+use crate::solver::tangent_basis;
+use math::Vector3;
+use serde::{Deserialize, Serialize};
+
+/// Linear spring joint, pulling the anchor point of the first body towards a rest position
+/// along an axis of the first body, measured from the anchor point of the second body.
+///
+/// # Remarks
+/// Only the motion along the axis is constrained, so suspensions combine the spring with
+/// another joint keeping the wheel on the axis. The spring is solved implicitly, so it
+/// stays stable no matter how stiff it is.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LinearSpringJoint<F: num_traits::Float = f64> {
+    /// Anchor point in the local space of the first body.
+    pub local_anchor_one: Vector3<F>,
+
+    /// Anchor point in the local space of the second body,
+    /// or in world space for joints with the scenery.
+    pub local_anchor_two: Vector3<F>,
+
+    /// Unit axis of the spring in the local space of the first body.
+    pub local_axis_one: Vector3<F>,
+
+    /// Position along the axis the spring rests at.
+    pub rest_position: F,
+
+    /// Stiffness and damping of the spring.
+    pub spring: JointSpring<F>,
+}
+
+impl<F: num_traits::Float> LinearSpringJoint<F> {
+    /// Creates a new linear spring between the given anchors, along the given axis.
+    pub fn new(
+        local_anchor_one: Vector3<F>,
+        local_anchor_two: Vector3<F>,
+        local_axis_one: Vector3<F>,
+        rest_position: F,
+        spring: JointSpring<F>,
+    ) -> Self {
+        Self {
+            local_anchor_one,
+            local_anchor_two,
+            local_axis_one: local_axis_one.normalize(),
+            rest_position,
+            spring,
+        }
+    }
+
+    /// Returns the current position of the anchor of the first body along the axis,
+    /// relative to the anchor of the second body.
+    pub fn position(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> F {
+        let point_two = match two {
+            Some(two) => two.point_in_world_space(&self.local_anchor_two),
+            None => self.local_anchor_two,
+        };
+        one.point_in_world_space(&self.local_anchor_one)
+            .vector_sub(&point_two)
+            .dot_product(&one.direction_in_world_space(&self.local_axis_one))
+    }
+
+    /// Adds the constraint rows of the joint.
+    pub(crate) fn rows(&self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let pair = builder.bodies();
+        let anchors = Anchors::new(pair, &self.local_anchor_one, &self.local_anchor_two, bodies);
+        let one = &bodies[pair.0];
+        let axis = one.direction_in_world_space(&self.local_axis_one);
+        let offset = anchors.point_one.vector_sub(&anchors.point_two);
+
+        // The axis turns with the first body, so its rotation moves the position
+        // as if applied at the anchor of the second body.
+        let lever = anchors.point_two.vector_sub(&one.position);
+        builder.set_spring(Some(self.spring));
+        builder.add(
+            0,
+            axis,
+            lever.cross_product(&axis),
+            anchors.relative_two.cross_product(&axis),
+            offset.dot_product(&axis) - self.rest_position,
+        );
+    }
+}
+
+/// Angular spring joint, twisting the first body towards a rest angle around an axis,
+/// relative to the second body.
+///
+/// # Remarks
+/// Only the twist around the axis is constrained, so torsion springs combine the spring
+/// with a hinge joint around the same axis.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct AngularSpringJoint<F: num_traits::Float = f64> {
+    /// Unit axis of the spring in the local space of the first body.
+    pub local_axis_one: Vector3<F>,
+
+    /// Unit direction perpendicular to the axis of the first body, in its local space,
+    /// from which the angle is measured.
+    pub local_reference_one: Vector3<F>,
+
+    /// Unit direction perpendicular to the axis in the local space of the second body,
+    /// or in world space for joints with the scenery, from which the angle is measured.
+    pub local_reference_two: Vector3<F>,
+
+    /// Angle the spring rests at, in radians.
+    pub rest_angle: F,
+
+    /// Stiffness and damping of the spring.
+    pub spring: JointSpring<F>,
+}
+
+impl<F: num_traits::Float> AngularSpringJoint<F> {
+    /// Creates a new angular spring between the given bodies around the given axis,
+    /// in world space. The current angle of the spring is zero.
+    pub fn from_world(
+        one: &RigidBody<F>,
+        two: Option<&RigidBody<F>>,
+        axis: &Vector3<F>,
+        rest_angle: F,
+        spring: JointSpring<F>,
+    ) -> Self {
+        let axis = axis.normalize();
+        let reference = tangent_basis(&axis)[0];
+        Self {
+            local_axis_one: one.direction_in_local_space(&axis),
+            local_reference_one: one.direction_in_local_space(&reference),
+            local_reference_two: match two {
+                Some(two) => two.direction_in_local_space(&reference),
+                None => reference,
+            },
+            rest_angle,
+            spring,
+        }
+    }
+
+    /// Returns the current angle of the spring, in radians within `[-PI, PI]`.
+    pub fn angle(&self, one: &RigidBody<F>, two: Option<&RigidBody<F>>) -> F {
+        twist_angle(
+            &one.direction_in_world_space(&self.local_axis_one),
+            &one.direction_in_world_space(&self.local_reference_one),
+            &world_direction(two, &self.local_reference_two),
+        )
+    }
+
+    /// Adds the constraint rows of the joint.
+    pub(crate) fn rows(&self, bodies: &[RigidBody<F>], builder: &mut RowBuilder<F>) {
+        let pair = builder.bodies();
+        let one = &bodies[pair.0];
+        let axis = one.direction_in_world_space(&self.local_axis_one);
+        let angle = self.angle(one, pair.1.map(|body| &bodies[body]));
+
+        // Wrap the error, so the spring takes the shortest way to the rest angle.
+        let pi = math::real::<F>(std::f64::consts::PI);
+        let mut error = angle - self.rest_angle;
+        if error > pi {
+            error = error - pi - pi;
+        } else if error < -pi {
+            error = error + pi + pi;
+        }
+
+        builder.set_spring(Some(self.spring));
+        builder.add(0, Vector3::origin(), axis, axis, error);
+    }
+}
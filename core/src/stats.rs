@@ -0,0 +1,78 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) use std::time::Instant;
+
+/// Stands in for the clock of the platform where there's none to read, such as
+/// `wasm32-unknown-unknown`, timing everything as taking no time.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Instant;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl Instant {
+    /// Returns the current instant, which is always the same one.
+    pub(crate) fn now() -> Self {
+        Instant
+    }
+
+    /// Returns the time elapsed since the instant, which is always zero.
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+impl std::ops::Sub for Instant {
+    type Output = Duration;
+
+    fn sub(self, _: Instant) -> Duration {
+        Duration::from_secs(0)
+    }
+}
+
+/// Timings and counters of the last step of a world, to find out where the time of a frame goes.
+///
+/// # Remarks
+/// The timings are measured with the clock of the platform, so they differ from run to run,
+/// but they never change the outcome of a step. Platforms without a clock, such as
+/// `wasm32-unknown-unknown`, report every timing as zero.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub struct WorldStats {
+    /// Time spent integrating the velocities and positions of the bodies.
+    pub integration: Duration,
+
+    /// Time spent placing the colliders and finding the pairs whose bounding boxes overlap.
+    pub broad_phase: Duration,
+
+    /// Time spent generating the contacts of the pairs, and updating the manifolds.
+    pub narrow_phase: Duration,
+
+    /// Time spent solving the contacts and joints.
+    pub solver: Duration,
+
+    /// Time spent on the whole step.
+    pub total: Duration,
+
+    /// Pairs of colliders found by the broad phase.
+    pub pairs: usize,
+
+    /// Contacts generated by the narrow phase.
+    pub contacts: usize,
+
+    /// Contact manifolds kept after the narrow phase.
+    pub manifolds: usize,
+
+    /// Islands of bodies touching or joined to each other.
+    pub islands: usize,
+
+    /// Iterations the solver ran over the contacts and joints, over all the substeps.
+    pub iterations: usize,
+}
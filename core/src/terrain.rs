@@ -0,0 +1,235 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::collider::Collider;
+use crate::handle::{BodyHandle, ColliderHandle};
+use crate::heightfield::HeightField;
+use crate::material::PhysicsMaterial;
+use crate::shape::Shape;
+use crate::trimesh::TriMesh;
+use crate::world::World;
+use math::{Matrix4, Quaternion, Vector3};
+use std::collections::BTreeMap;
+
+/// Piece of terrain, collided on its own, that can be added to and removed from a world.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TerrainChunk<F: num_traits::Float = f64> {
+    /// Shape of the chunk.
+    pub shape: Shape<F>,
+
+    /// Offset of the shape from the origin of the terrain.
+    pub offset: Vector3<F>,
+
+    /// Bounding box of the chunk in the local space of the terrain.
+    pub aabb: Aabb<F>,
+}
+
+impl<F: num_traits::Float> TerrainChunk<F> {
+    /// Creates a new chunk with the given shape, offset from the origin of the terrain.
+    pub fn new(shape: Shape<F>, offset: Vector3<F>) -> Self {
+        let aabb = shape.aabb(&translation(&offset));
+        Self {
+            shape,
+            offset,
+            aabb,
+        }
+    }
+}
+
+/// Terrain split into chunks, streamed in and out of a world as they come in and out of reach.
+///
+/// # Remarks
+/// Chunks are attached to a body of the world, usually a static one, as colliders of their own.
+/// Loading a chunk adds its collider, and unloading it removes it again, only updating that
+/// collider in the broad phase, so the colliders of the rest of the world keep their handles
+/// and the cost of streaming doesn't grow with the size of the world.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChunkedTerrain<F: num_traits::Float = f64> {
+    /// Body the chunks are attached to.
+    pub body: BodyHandle,
+
+    /// Material of the chunks loaded from now on.
+    pub material: PhysicsMaterial<F>,
+
+    chunks: Vec<TerrainChunk<F>>,
+    colliders: Vec<Option<ColliderHandle>>,
+}
+
+impl<F: num_traits::Float> ChunkedTerrain<F> {
+    /// Creates a new terrain made of the given chunks, attached to the given body, with every
+    /// chunk unloaded.
+    pub fn new(body: BodyHandle, chunks: Vec<TerrainChunk<F>>) -> Self {
+        let colliders = vec![None; chunks.len()];
+        Self {
+            body,
+            material: PhysicsMaterial::default(),
+            chunks,
+            colliders,
+        }
+    }
+
+    /// Creates a new terrain from a heightfield, split into square chunks of up to the given
+    /// number of cells per side. Chunks made only of holes are left out.
+    ///
+    /// # Panics
+    /// Panics if the number of cells per chunk is zero.
+    pub fn from_heightfield(body: BodyHandle, field: &HeightField<F>, cells: usize) -> Self {
+        assert!(cells > 0, "chunks need at least one cell");
+        let (rows, columns) = (field.rows(), field.columns());
+        let centered = |index: usize, count: usize| index as f64 - (count - 1) as f64 * 0.5;
+        let mut chunks = Vec::new();
+        for first_row in (0..rows - 1).step_by(cells) {
+            for first_column in (0..columns - 1).step_by(cells) {
+                let last_row = (first_row + cells).min(rows - 1);
+                let last_column = (first_column + cells).min(columns - 1);
+                let mut heights = Vec::new();
+                for row in first_row..=last_row {
+                    for column in first_column..=last_column {
+                        heights.push(field.height(row, column));
+                    }
+                }
+                let (chunk_rows, chunk_columns) =
+                    (last_row - first_row + 1, last_column - first_column + 1);
+                let mut chunk = HeightField::new(chunk_rows, chunk_columns, heights, field.scale)
+                    .expect("chunks have at least two rows and columns");
+                for row in 0..chunk_rows - 1 {
+                    for column in 0..chunk_columns - 1 {
+                        chunk.set_hole(
+                            row,
+                            column,
+                            field.is_hole(first_row + row, first_column + column),
+                        );
+                    }
+                }
+
+                let offset = Vector3::new(
+                    math::real::<F>(
+                        centered(first_column, columns) + (chunk_columns - 1) as f64 * 0.5,
+                    ) * field.scale.x,
+                    F::zero(),
+                    math::real::<F>(centered(first_row, rows) + (chunk_rows - 1) as f64 * 0.5)
+                        * field.scale.z,
+                );
+                if let Some(shape) = chunk.to_shape() {
+                    chunks.push(TerrainChunk::new(shape, offset));
+                }
+            }
+        }
+        Self::new(body, chunks)
+    }
+
+    /// Creates a new terrain from a triangle mesh, split along the x and z axes into square
+    /// chunks of the given size, holding the triangles whose centers fall into them.
+    pub fn from_trimesh(body: BodyHandle, mesh: &TriMesh<F>, size: F) -> Self {
+        let cell = |value: F| (value / size).floor().to_i64().unwrap_or(0);
+        let mut pieces: BTreeMap<(i64, i64), Vec<usize>> = BTreeMap::new();
+        for index in 0..mesh.triangles().len() {
+            let center = mesh.triangle(index).aabb().center();
+            pieces
+                .entry((cell(center.x), cell(center.z)))
+                .or_default()
+                .push(index);
+        }
+
+        let mut chunks = Vec::with_capacity(pieces.len());
+        for triangles in pieces.values() {
+            let mut vertices = Vec::new();
+            let mut remap = BTreeMap::new();
+            let mut corners = |vertex: usize| {
+                *remap.entry(vertex).or_insert_with(|| {
+                    vertices.push(mesh.vertices()[vertex]);
+                    vertices.len() - 1
+                })
+            };
+            let triangles: Vec<[usize; 3]> = triangles
+                .iter()
+                .map(|index| {
+                    let [a, b, c] = mesh.triangles()[*index];
+                    [corners(a), corners(b), corners(c)]
+                })
+                .collect();
+            if let Some(mesh) = TriMesh::new(vertices, triangles) {
+                chunks.push(TerrainChunk::new(Shape::trimesh(mesh), Vector3::origin()));
+            }
+        }
+        Self::new(body, chunks)
+    }
+
+    /// Returns the chunks of the terrain.
+    pub fn chunks(&self) -> &[TerrainChunk<F>] {
+        &self.chunks
+    }
+
+    /// Returns the handle of the collider of a chunk, if it's loaded.
+    pub fn collider(&self, chunk: usize) -> Option<ColliderHandle> {
+        self.colliders[chunk]
+    }
+
+    /// Returns true if the given chunk is loaded.
+    pub fn is_loaded(&self, chunk: usize) -> bool {
+        self.colliders[chunk].is_some()
+    }
+
+    /// Adds the collider of a chunk to the world, if it isn't loaded yet, returning its handle.
+    /// Returns `None` if the body of the terrain was removed from the world.
+    pub fn load(&mut self, world: &mut World<F>, chunk: usize) -> Option<ColliderHandle> {
+        if let Some(handle) = self.colliders[chunk] {
+            return Some(handle);
+        }
+
+        let body = world.body_index(self.body)?;
+        let piece = &self.chunks[chunk];
+        let mut collider =
+            Collider::with_offset(body, piece.shape.clone(), translation(&piece.offset));
+        collider.material = self.material;
+        let handle = world.add_collider(collider);
+        self.colliders[chunk] = Some(handle);
+        Some(handle)
+    }
+
+    /// Removes the collider of a chunk from the world, returning true if it was loaded.
+    pub fn unload(&mut self, world: &mut World<F>, chunk: usize) -> bool {
+        match self.colliders[chunk].take() {
+            Some(handle) => world.remove_collider(handle).is_some(),
+            None => false,
+        }
+    }
+
+    /// Loads the chunks within `radius` of the given point in world space, and unloads the
+    /// ones beyond it.
+    ///
+    /// # Remarks
+    /// Streaming around every point of interest, like the player or the camera, every frame
+    /// keeps the colliders near them in the world, and the rest of the terrain out of it.
+    pub fn stream(&mut self, world: &mut World<F>, center: &Vector3<F>, radius: F) {
+        let local = match world.body(self.body) {
+            Some(body) => body.transform_matrix.transform_inverse(center),
+            None => return,
+        };
+        for chunk in 0..self.chunks.len() {
+            let aabb = &self.chunks[chunk].aabb;
+            let distance = aabb.closest_point(&local).vector_sub(&local).magnitude();
+            if distance <= radius {
+                self.load(world, chunk);
+            } else {
+                self.unload(world, chunk);
+            }
+        }
+    }
+
+    /// Removes the colliders of every loaded chunk from the world.
+    pub fn unload_all(&mut self, world: &mut World<F>) {
+        for chunk in 0..self.chunks.len() {
+            self.unload(world, chunk);
+        }
+    }
+}
+
+/// Returns the transform translating by the given offset.
+fn translation<F: num_traits::Float>(offset: &Vector3<F>) -> Matrix4<F> {
+    Matrix4::from_orientation_and_position(&Quaternion::identity(), offset)
+}
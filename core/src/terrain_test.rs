@@ -0,0 +1,139 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::gravity::{GravityField, GravitySource};
+use crate::heightfield::HeightField;
+use crate::query::QueryFilter;
+use crate::ray::Ray;
+use crate::rigid_body::{BodyType, RigidBody};
+use crate::shape::Shape;
+use crate::terrain::*;
+use crate::world::World;
+use math::{Matrix3, Vector3};
+
+/// Returns a world with a static terrain body at the given position, and its handle.
+fn terrain_world(position: Vector3<f64>) -> (World, crate::handle::BodyHandle) {
+    let mut world = World::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    let mut ground = RigidBody::new(position, 1.0, &Matrix3::identity());
+    ground.set_infinite_mass();
+    ground.body_type = BodyType::Static;
+    let ground = world.add_body(ground);
+    let ground = world.body_index(ground).unwrap();
+    let handle = world.body_handle(ground);
+    (world, handle)
+}
+
+/// Returns the height of the terrain of the world under the given point, if any.
+fn ground_height(world: &World, x: f64, z: f64) -> Option<f64> {
+    let ray = Ray::new(Vector3::new(x, 100.0, z), Vector3::new(0.0, -1.0, 0.0));
+    let hit = world.raycast(&ray, 200.0, &QueryFilter::new())?;
+    Some(hit.point.y)
+}
+
+#[test]
+fn heightfield_chunks() {
+    let field = HeightField::<f64>::from_noise(17, 13, Vector3::new(1.0, 4.0, 1.0), 0.3, 0.0);
+    let (mut whole, body) = terrain_world(Vector3::new(3.0, 0.0, -2.0));
+    whole.add_collider(Collider::new(0, field.to_shape().unwrap()));
+    whole.update_colliders();
+
+    // Chunks share their borders, so the terrain has the same surface.
+    let (mut chunked, _) = terrain_world(Vector3::new(3.0, 0.0, -2.0));
+    let mut terrain = ChunkedTerrain::from_heightfield(body, &field, 5);
+    assert_eq!(4 * 3, terrain.chunks().len());
+    for chunk in 0..terrain.chunks().len() {
+        assert!(terrain.load(&mut chunked, chunk).is_some());
+    }
+    chunked.update_colliders();
+    for (x, z) in [
+        (0.37, 0.21),
+        (2.3, -7.9),
+        (8.3, 5.6),
+        (-2.9, 3.1),
+        (0.5, -2.3),
+    ]
+    .iter()
+    {
+        let expected = ground_height(&whole, *x, *z).unwrap();
+        let height = ground_height(&chunked, *x, *z).unwrap();
+        assert!((expected - height).abs() < 1e-6);
+    }
+
+    // Chunks made only of holes are left out.
+    let mut holed = field;
+    holed.set_hole_mask(&[true; 16 * 12]);
+    holed.set_hole(0, 0, false);
+    assert_eq!(
+        1,
+        ChunkedTerrain::from_heightfield(body, &holed, 5)
+            .chunks()
+            .len()
+    );
+}
+
+#[test]
+fn trimesh_chunks() {
+    let field = HeightField::<f64>::from_noise(21, 21, Vector3::new(1.0, 4.0, 1.0), 0.3, 1.0);
+    let mesh = field.to_trimesh().unwrap();
+    let (_, body) = terrain_world(Vector3::origin());
+    let terrain = ChunkedTerrain::from_trimesh(body, &mesh, 5.0);
+    assert_eq!(16, terrain.chunks().len());
+    let triangles: usize = terrain
+        .chunks()
+        .iter()
+        .map(|chunk| match &chunk.shape {
+            Shape::TriMesh(mesh) => mesh.triangles().len(),
+            _ => panic!("chunks of meshes are meshes"),
+        })
+        .sum();
+    assert_eq!(mesh.triangles().len(), triangles);
+}
+
+#[test]
+fn streaming() {
+    let field =
+        HeightField::<f64>::new(41, 41, vec![0.0; 41 * 41], Vector3::new(1.0, 1.0, 1.0)).unwrap();
+    let (mut world, body) = terrain_world(Vector3::origin());
+    let ball = world.add_body(RigidBody::new(
+        Vector3::new(0.0, 5.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let ball = world.body_index(ball).unwrap();
+    world.add_collider(Collider::new(ball, Shape::sphere(0.5)));
+    let ball_collider = world.collider_handle(0);
+
+    let mut terrain = ChunkedTerrain::from_heightfield(body, &field, 10);
+    assert_eq!(16, terrain.chunks().len());
+    terrain.stream(&mut world, &Vector3::new(0.5, 0.0, 0.5), 1.0);
+    let loaded = (0..16).filter(|chunk| terrain.is_loaded(*chunk)).count();
+    assert_eq!(4, loaded);
+    assert_eq!(5, world.colliders.len());
+
+    // The ball lands on the chunks streamed in around it.
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    assert!((world.bodies[ball].position.y - 0.5).abs() < 0.05);
+
+    // Moving away unloads the chunks out of reach, and the ball keeps its handle.
+    terrain.stream(&mut world, &Vector3::new(15.0, 0.0, 15.0), 1.0);
+    assert_eq!(1, (0..16).filter(|chunk| terrain.is_loaded(*chunk)).count());
+    assert_eq!(2, world.colliders.len());
+    assert_eq!(ball, world.collider(ball_collider).unwrap().body);
+    for _ in 0..60 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    assert!(world.bodies[ball].position.y < 0.0);
+
+    terrain.unload_all(&mut world);
+    assert_eq!(1, world.colliders.len());
+    assert!(terrain.collider(15).is_none());
+}
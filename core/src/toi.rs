@@ -0,0 +1,178 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::gjk::{closest_points, CastHit, GjkResult, GJK_CAST_TOLERANCE, GJK_MAX_ITERATIONS};
+use crate::rigid_body::RigidBody;
+use crate::shape::SupportMap;
+use math::{Matrix4, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Rigid motion of a shape with constant linear and angular velocities.
+///
+/// # Remarks
+/// The shape rotates around the origin of its transform, so shapes attached to bodies
+/// should be centered on the body to match its motion.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Motion<F: num_traits::Float = f64> {
+    /// Placement of the shape at time `0`.
+    pub transform: Matrix4<F>,
+
+    /// Linear velocity of the shape.
+    pub velocity: Vector3<F>,
+
+    /// Angular velocity of the shape, as a scaled axis in world space.
+    pub rotation: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Motion<F> {
+    /// Creates a new motion from the given placement and velocities.
+    pub fn new(transform: Matrix4<F>, velocity: Vector3<F>, rotation: Vector3<F>) -> Self {
+        Self {
+            transform,
+            velocity,
+            rotation,
+        }
+    }
+
+    /// Creates a new motion of a shape that doesn't move from the given placement.
+    pub fn stationary(transform: Matrix4<F>) -> Self {
+        Self::new(transform, Vector3::origin(), Vector3::origin())
+    }
+
+    /// Creates a new motion following the current velocities of the given body.
+    pub fn from_body(body: &RigidBody<F>) -> Self {
+        Self::new(body.transform_matrix, body.velocity, body.rotation)
+    }
+
+    /// Returns the placement of the shape at the given time.
+    pub fn transform_at(&self, time: F) -> Matrix4<F> {
+        let position = self
+            .transform
+            .translation()
+            .vector_add(&self.velocity.scalar_mul(time));
+        let angle = self.rotation.magnitude() * time;
+        let turn = if angle > num_traits::zero() {
+            Quaternion::from_axis_angle(&self.rotation.normalize(), angle)
+        } else {
+            Quaternion::identity()
+        };
+
+        let mut orientation = self.transform;
+        orientation.data[3] = num_traits::zero();
+        orientation.data[7] = num_traits::zero();
+        orientation.data[11] = num_traits::zero();
+        Matrix4::from_orientation_and_position(&turn, &position).matrix_mul(&orientation)
+    }
+}
+
+/// Computes the first time both convex shapes touch while following their motions,
+/// before the given maximum time, if any.
+///
+/// # Remarks
+/// Uses conservative advancement: both shapes are moved forward by the distance between them,
+/// divided by an upper bound of the speed at which that distance can close, which never goes
+/// past the contact. The bound accounts for rotations with the radius of each shape around
+/// the origin of its transform, so spinning shapes take more iterations to converge.
+///
+/// The normal of the hit points from the second shape towards the first one, and the point
+/// is on the surface of the second shape. Shapes overlapping from the start hit at time `0`,
+/// with a normal opposing their relative velocity.
+pub fn time_of_impact<F: num_traits::Float, A: SupportMap<F>, B: SupportMap<F>>(
+    one: &A,
+    motion_one: &Motion<F>,
+    two: &B,
+    motion_two: &Motion<F>,
+    max_time: F,
+) -> Option<CastHit<F>> {
+    let tolerance: F = math::real(GJK_CAST_TOLERANCE);
+    let relative_velocity = motion_one.velocity.vector_sub(&motion_two.velocity);
+    let angular_speed = motion_one.rotation.magnitude() * bounding_radius(one, motion_one)
+        + motion_two.rotation.magnitude() * bounding_radius(two, motion_two);
+
+    let mut time: F = num_traits::zero();
+    // Contact of the last step, in the local space of the second shape.
+    let mut last: Option<(Vector3<F>, Vector3<F>)> = None;
+    for _ in 0..GJK_MAX_ITERATIONS {
+        let transform_one = motion_one.transform_at(time);
+        let transform_two = motion_two.transform_at(time);
+        let (distance, point_one, point_two) =
+            match closest_points(one, &transform_one, two, &transform_two) {
+                GjkResult::Separated {
+                    distance,
+                    point_one,
+                    point_two,
+                } => (distance, point_one, point_two),
+
+                // Touching after advancing, keep the contact found on the way.
+                GjkResult::Intersecting => {
+                    let hit = last.map_or(
+                        CastHit {
+                            toi: time,
+                            point: transform_one.translation(),
+                            normal: relative_velocity.invert().normalize(),
+                        },
+                        |(point, normal)| CastHit {
+                            toi: time,
+                            point: transform_two.transform(&point),
+                            normal: transform_two.transform_direction(&normal),
+                        },
+                    );
+                    return Some(hit);
+                }
+            };
+
+        let normal = point_one.vector_sub(&point_two).scalar_div(distance);
+        if distance <= tolerance {
+            return Some(CastHit {
+                toi: time,
+                point: point_two,
+                normal,
+            });
+        }
+
+        let speed = angular_speed - relative_velocity.dot_product(&normal);
+        if speed <= num_traits::zero() {
+            return None;
+        }
+        time = time + distance / speed;
+        if time > max_time {
+            return None;
+        }
+        last = Some((
+            transform_two.transform_inverse(&point_two),
+            transform_two.transform_inverse_direction(&normal),
+        ));
+    }
+
+    None
+}
+
+/// Returns the largest distance from the origin of the transform of the motion to the shape,
+/// or `0` when the shape doesn't rotate.
+fn bounding_radius<F: num_traits::Float, S: SupportMap<F>>(shape: &S, motion: &Motion<F>) -> F {
+    if motion.rotation.squared_magnitude() <= num_traits::zero() {
+        return num_traits::zero();
+    }
+
+    let axes = [
+        Vector3::new(F::one(), F::zero(), F::zero()),
+        Vector3::new(F::zero(), F::one(), F::zero()),
+        Vector3::new(F::zero(), F::zero(), F::one()),
+    ];
+    let mut extents = Vector3::origin();
+    for (index, axis) in axes.iter().enumerate() {
+        let furthest = shape
+            .local_support_point(axis)
+            .dot_product(axis)
+            .max(-shape.local_support_point(&axis.invert()).dot_product(axis));
+        match index {
+            0 => extents.x = furthest,
+            1 => extents.y = furthest,
+            _ => extents.z = furthest,
+        }
+    }
+    extents.magnitude()
+}
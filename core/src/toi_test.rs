@@ -0,0 +1,68 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::shape::{Cuboid, Sphere};
+use crate::toi::*;
+use math::{Matrix4, Quaternion, Vector3};
+
+fn placed(x: f64, y: f64, z: f64) -> Matrix4<f64> {
+    Matrix4::from_orientation_and_position(&Quaternion::identity(), &Vector3::new(x, y, z))
+}
+
+#[test]
+fn moving_shapes() {
+    // Two spheres running into each other meet halfway.
+    let sphere = Sphere::new(1.0);
+    let one = Motion::new(
+        placed(-5.0, 0.0, 0.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::origin(),
+    );
+    let two = Motion::new(
+        placed(5.0, 0.0, 0.0),
+        Vector3::new(-2.0, 0.0, 0.0),
+        Vector3::origin(),
+    );
+    let hit = time_of_impact(&sphere, &one, &sphere, &two, 10.0).unwrap();
+    assert!((hit.toi - 2.0).abs() < 1e-5);
+    assert!(hit.point.magnitude() < 1e-5);
+    assert!((hit.normal - Vector3::new(-1.0, 0.0, 0.0)).magnitude() < 1e-5);
+    assert!(time_of_impact(&sphere, &one, &sphere, &two, 1.5).is_none());
+
+    // Moving together, they never meet.
+    let chasing = Motion::new(
+        placed(5.0, 0.0, 0.0),
+        Vector3::new(2.0, 0.0, 0.0),
+        Vector3::origin(),
+    );
+    assert!(time_of_impact(&sphere, &one, &sphere, &chasing, 100.0).is_none());
+}
+
+#[test]
+fn rotating_shapes() {
+    // A spinning paddle swats a ball that is out of reach of any translation.
+    let paddle = Cuboid::new(Vector3::new(2.0, 0.1, 0.1));
+    let ball = Sphere::new(0.25);
+    let spinning = Motion::new(
+        Matrix4::identity(),
+        Vector3::origin(),
+        Vector3::new(0.0, 0.0, std::f64::consts::FRAC_PI_2),
+    );
+    let resting = Motion::stationary(placed(0.0, 1.5, 0.0));
+    let hit = time_of_impact(&paddle, &spinning, &ball, &resting, 2.0).unwrap();
+
+    // The face of the paddle reaches the ball once it is 0.35 away from its center.
+    let angle = (0.35f64 / 1.5).acos();
+    assert!((hit.toi - angle / std::f64::consts::FRAC_PI_2).abs() < 1e-4);
+    let face = spinning.transform_at(hit.toi);
+    let normal = face.transform_direction(&Vector3::new(0.0, -1.0, 0.0));
+    assert!((hit.normal - normal).magnitude() < 1e-3);
+    assert!(((hit.point - Vector3::new(0.0, 1.5, 0.0)).magnitude() - 0.25).abs() < 1e-5);
+
+    // Without spinning, the paddle stays clear of the ball.
+    let still = Motion::stationary(Matrix4::identity());
+    assert!(time_of_impact(&paddle, &still, &ball, &resting, 2.0).is_none());
+}
@@ -0,0 +1,279 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::broad_phase::BroadPhase;
+use crate::bvh::DynamicBvh;
+use crate::ray::Ray;
+use crate::shape::SupportMap;
+use math::{Matrix4, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Triangle given by its three vertices, wound counter-clockwise seen from its front.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Triangle<F: num_traits::Float = f64> {
+    /// First vertex of the triangle.
+    pub a: Vector3<F>,
+
+    /// Second vertex of the triangle.
+    pub b: Vector3<F>,
+
+    /// Third vertex of the triangle.
+    pub c: Vector3<F>,
+}
+
+impl<F: num_traits::Float> Triangle<F> {
+    /// Creates a new triangle with the given vertices.
+    pub fn new(a: Vector3<F>, b: Vector3<F>, c: Vector3<F>) -> Self {
+        Self { a, b, c }
+    }
+
+    /// Returns the triangle with its vertices placed with the given transform.
+    pub fn transformed(&self, transform: &Matrix4<F>) -> Self {
+        Self::new(
+            transform.transform(&self.a),
+            transform.transform(&self.b),
+            transform.transform(&self.c),
+        )
+    }
+
+    /// Returns the unit normal of the front of the triangle,
+    /// or a zero vector if the triangle is degenerate.
+    pub fn normal(&self) -> Vector3<F> {
+        self.b
+            .vector_sub(&self.a)
+            .cross_product(&self.c.vector_sub(&self.a))
+            .normalize()
+    }
+
+    /// Returns the area of the triangle.
+    pub fn area(&self) -> F {
+        self.b
+            .vector_sub(&self.a)
+            .cross_product(&self.c.vector_sub(&self.a))
+            .magnitude()
+            * math::real(0.5)
+    }
+
+    /// Returns the bounding box of the triangle.
+    pub fn aabb(&self) -> Aabb<F> {
+        let mut aabb = Aabb::new(self.a, self.a);
+        aabb.inplace_merge_point(&self.b)
+            .inplace_merge_point(&self.c);
+        aabb
+    }
+
+    /// Computes the time of impact of the ray against either side of the triangle.
+    /// Returns `None` if the ray doesn't hit the triangle before `max_toi`.
+    pub fn cast_ray(&self, ray: &Ray<F>, max_toi: F) -> Option<F> {
+        let ab = self.b.vector_sub(&self.a);
+        let ac = self.c.vector_sub(&self.a);
+        let p = ray.direction.cross_product(&ac);
+        let determinant = ab.dot_product(&p);
+        if determinant.abs() <= F::epsilon() * ab.magnitude() * p.magnitude() {
+            // The ray is parallel to the triangle.
+            return None;
+        }
+
+        let inverse = F::one() / determinant;
+        let offset = ray.origin.vector_sub(&self.a);
+        let u = offset.dot_product(&p) * inverse;
+        if u < F::zero() || u > F::one() {
+            return None;
+        }
+        let q = offset.cross_product(&ab);
+        let v = ray.direction.dot_product(&q) * inverse;
+        if v < F::zero() || u + v > F::one() {
+            return None;
+        }
+        let toi = ac.dot_product(&q) * inverse;
+        if toi < F::zero() || toi > max_toi {
+            return None;
+        }
+        Some(toi)
+    }
+
+    /// Returns the point of the triangle closest to the given point.
+    ///
+    /// # Remarks
+    /// The point is tested against the regions of the vertices, then the ones of the edges,
+    /// and is projected onto the face when it isn't in any of them.
+    pub fn closest_point(&self, point: &Vector3<F>) -> Vector3<F> {
+        let zero = F::zero();
+        let ab = self.b.vector_sub(&self.a);
+        let ac = self.c.vector_sub(&self.a);
+        let ap = point.vector_sub(&self.a);
+        let d1 = ab.dot_product(&ap);
+        let d2 = ac.dot_product(&ap);
+        if d1 <= zero && d2 <= zero {
+            return self.a;
+        }
+
+        let bp = point.vector_sub(&self.b);
+        let d3 = ab.dot_product(&bp);
+        let d4 = ac.dot_product(&bp);
+        if d3 >= zero && d4 <= d3 {
+            return self.b;
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= zero && d1 >= zero && d3 <= zero {
+            return self.a.vector_add(&ab.scalar_mul(d1 / (d1 - d3)));
+        }
+
+        let cp = point.vector_sub(&self.c);
+        let d5 = ab.dot_product(&cp);
+        let d6 = ac.dot_product(&cp);
+        if d6 >= zero && d5 <= d6 {
+            return self.c;
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= zero && d2 >= zero && d6 <= zero {
+            return self.a.vector_add(&ac.scalar_mul(d2 / (d2 - d6)));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= zero && d4 - d3 >= zero && d5 - d6 >= zero {
+            let t = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+            return self.b.vector_add(&self.c.vector_sub(&self.b).scalar_mul(t));
+        }
+
+        let denominator = F::one() / (va + vb + vc);
+        let v = vb * denominator;
+        let w = vc * denominator;
+        self.a
+            .vector_add(&ab.scalar_mul(v))
+            .vector_add(&ac.scalar_mul(w))
+    }
+}
+
+impl<F: num_traits::Float> SupportMap<F> for Triangle<F> {
+    fn local_support_point(&self, direction: &Vector3<F>) -> Vector3<F> {
+        let (a, b, c) = (
+            self.a.dot_product(direction),
+            self.b.dot_product(direction),
+            self.c.dot_product(direction),
+        );
+        if a >= b && a >= c {
+            self.a
+        } else if b >= c {
+            self.b
+        } else {
+            self.c
+        }
+    }
+}
+
+/// Surface made of triangles sharing their vertices, for static level geometry.
+///
+/// # Remarks
+/// The triangles are kept in a bounding volume hierarchy (the mid-phase), built once when the
+/// mesh is created, so only the few triangles near another shape are ever tested against it.
+/// Meshes are hollow, without volume or mass, and are meant for static or kinematic bodies.
+///
+/// Building the hierarchy of a large mesh takes time, so meshes can be cooked ahead of time:
+/// serializing a mesh stores its hierarchy along with it, and deserializing it doesn't
+/// rebuild anything.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TriMesh<F: num_traits::Float = f64> {
+    vertices: Vec<Vector3<F>>,
+    triangles: Vec<[usize; 3]>,
+    bvh: DynamicBvh<F>,
+}
+
+impl<F: num_traits::Float> TriMesh<F> {
+    /// Creates a new mesh with the given vertices and triangles, given as indices of
+    /// their vertices wound counter-clockwise seen from the front, and builds its hierarchy.
+    /// Returns `None` if there are no triangles, or any index is out of bounds.
+    pub fn new(vertices: Vec<Vector3<F>>, triangles: Vec<[usize; 3]>) -> Option<Self> {
+        if triangles.is_empty() || triangles.iter().flatten().any(|i| *i >= vertices.len()) {
+            return None;
+        }
+
+        let mut mesh = Self {
+            vertices,
+            triangles,
+            bvh: DynamicBvh::new(F::zero()),
+        };
+        for index in 0..mesh.triangles.len() {
+            let aabb = mesh.triangle(index).aabb();
+            mesh.bvh.insert(index, &aabb);
+        }
+        Some(mesh)
+    }
+
+    /// Returns the vertices of the mesh, in local space.
+    pub fn vertices(&self) -> &[Vector3<F>] {
+        &self.vertices
+    }
+
+    /// Returns the triangles of the mesh, as indices of their vertices.
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// Returns the bounding volume hierarchy of the triangles of the mesh,
+    /// whose proxies are the indices of the triangles.
+    pub fn bvh(&self) -> &DynamicBvh<F> {
+        &self.bvh
+    }
+
+    /// Returns the triangle with the given index, in local space.
+    pub fn triangle(&self, index: usize) -> Triangle<F> {
+        let [a, b, c] = self.triangles[index];
+        Triangle::new(self.vertices[a], self.vertices[b], self.vertices[c])
+    }
+
+    /// Returns the bounding box of the mesh in local space.
+    pub fn local_aabb(&self) -> Aabb<F> {
+        self.bvh.root_aabb().expect("meshes have triangles")
+    }
+
+    /// Returns the bounding box of the mesh placed with the given transform.
+    ///
+    /// # Remarks
+    /// The box encloses the local bounding box of the mesh, rotated, so it can be larger
+    /// than the mesh itself, but doesn't need to go through every vertex.
+    pub fn aabb(&self, transform: &Matrix4<F>) -> Aabb<F> {
+        let local = self.local_aabb();
+        let half_size = local.half_extents();
+        let rotation = transform.rotation();
+        let mut half_extents = [num_traits::zero(); 3];
+        for (row, extent) in half_extents.iter_mut().enumerate() {
+            let row = rotation.row(row);
+            *extent =
+                row.x.abs() * half_size.x + row.y.abs() * half_size.y + row.z.abs() * half_size.z;
+        }
+        Aabb::from_center(
+            &transform.transform(&local.center()),
+            &Vector3::new(half_extents[0], half_extents[1], half_extents[2]),
+        )
+    }
+
+    /// Returns the area of the surface of the mesh.
+    pub fn surface_area(&self) -> F {
+        (0..self.triangles.len()).fold(F::zero(), |area, index| area + self.triangle(index).area())
+    }
+
+    /// Returns the indices of the triangles of the mesh placed with the given transform
+    /// whose bounding boxes overlap the given world space region.
+    pub fn triangles_near(&self, aabb: &Aabb<F>, transform: &Matrix4<F>) -> Vec<usize> {
+        let (min, max) = (aabb.min, aabb.max);
+        let corners = (0..8).map(|corner| {
+            transform.transform_inverse(&Vector3::new(
+                if corner & 4 == 0 { min.x } else { max.x },
+                if corner & 2 == 0 { min.y } else { max.y },
+                if corner & 1 == 0 { min.z } else { max.z },
+            ))
+        });
+        let corners: Vec<Vector3<F>> = corners.collect();
+        match Aabb::from_points(corners.iter()) {
+            Some(local) => self.bvh.intersecting(&local),
+            None => Vec::new(),
+        }
+    }
+}
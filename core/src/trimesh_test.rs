@@ -0,0 +1,174 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::collider::Collider;
+use crate::gravity::{GravityField, GravitySource};
+use crate::narrow_phase::{collide, CollisionData};
+use crate::query::QueryFilter;
+use crate::ray::Ray;
+use crate::rigid_body::{BodyType, RigidBody};
+use crate::shape::{Capsule, Shape, Sphere};
+use crate::trimesh::*;
+use crate::world::World;
+use math::{Matrix3, Matrix4, Quaternion, Vector3};
+use std::sync::Arc;
+
+/// Returns a flat floor of 4x4 unit squares centered at the origin, facing up.
+fn floor() -> TriMesh {
+    let mut vertices = Vec::new();
+    for x in 0..5 {
+        for z in 0..5 {
+            vertices.push(Vector3::new(x as f64 - 2.0, 0.0, z as f64 - 2.0));
+        }
+    }
+    let mut triangles = Vec::new();
+    for x in 0..4 {
+        for z in 0..4 {
+            let corner = x * 5 + z;
+            triangles.push([corner, corner + 1, corner + 5]);
+            triangles.push([corner + 5, corner + 1, corner + 6]);
+        }
+    }
+    TriMesh::new(vertices, triangles).unwrap()
+}
+
+#[test]
+fn triangle() {
+    let triangle = Triangle::new(
+        Vector3::new(0.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 2.0),
+        Vector3::new(2.0, 0.0, 0.0),
+    );
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), triangle.normal());
+    assert_eq!(2.0, triangle.area());
+
+    // Points get projected onto the face, or clamped to the edges and vertices.
+    let closest = |x, y, z| triangle.closest_point(&Vector3::new(x, y, z));
+    assert_eq!(Vector3::new(0.5, 0.0, 0.5), closest(0.5, 3.0, 0.5));
+    assert_eq!(Vector3::new(0.0, 0.0, 0.0), closest(-1.0, 1.0, -1.0));
+    assert_eq!(Vector3::new(1.0, 0.0, 0.0), closest(1.0, -1.0, -2.0));
+    assert_eq!(Vector3::new(1.0, 0.0, 1.0), closest(2.0, 0.0, 2.0));
+    assert_eq!(Vector3::new(0.0, 0.0, 2.0), closest(-1.0, 0.0, 3.0));
+}
+
+#[test]
+fn mesh() {
+    let mesh = floor();
+    assert_eq!(32, mesh.triangles().len());
+    assert_eq!(32, mesh.bvh().len());
+    assert_eq!(16.0, mesh.surface_area());
+    assert_eq!(Shape::trimesh(mesh.clone()).volume(), 0.0);
+    assert!(TriMesh::new(vec![Vector3::<f64>::origin()], vec![[0, 0, 1]]).is_none());
+    assert!(TriMesh::new(vec![Vector3::<f64>::origin()], Vec::new()).is_none());
+
+    // The hierarchy finds the triangles around a region, in the space of the mesh.
+    let transform = Matrix4::from_orientation_and_position(
+        &Quaternion::identity(),
+        &Vector3::new(10.0, 0.0, 0.0),
+    );
+    let region = Aabb::new(Vector3::new(8.2, -1.0, -1.8), Vector3::new(8.8, 1.0, -1.2));
+    let mut near = mesh.triangles_near(&region, &transform);
+    near.sort_unstable();
+    assert_eq!(vec![0, 1], near);
+    let aabb = mesh.aabb(&transform);
+    assert_eq!(Vector3::new(8.0, 0.0, -2.0), aabb.min);
+    assert_eq!(Vector3::new(12.0, 0.0, 2.0), aabb.max);
+
+    // Cooked meshes keep their hierarchy.
+    let cooked = bincode::serialize(&mesh).unwrap();
+    let loaded: TriMesh = bincode::deserialize(&cooked).unwrap();
+    assert_eq!(mesh, loaded);
+}
+
+#[test]
+fn collisions() {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    let mut ground = RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity());
+    ground.set_body_type(BodyType::Static);
+    let ground = world.add_body(ground);
+    let ground = world.body_index(ground).unwrap();
+    let mesh = world.add_collider(Collider::new(ground, Shape::trimesh(floor())));
+    let mesh = world.collider_index(mesh).unwrap();
+
+    // Spheres touching the mesh get contacts pushing them out of the triangles.
+    let sphere = Collider::placed(
+        1,
+        Shape::Sphere(Sphere::new(0.5)),
+        Matrix4::from_orientation_and_position(
+            &Quaternion::identity(),
+            &Vector3::new(0.3, 0.4, 0.3),
+        ),
+    );
+    let mut data = CollisionData::new(16);
+    assert!(collide(&sphere, &world.colliders[mesh], &mut data) > 0);
+    for contact in data.contacts.iter() {
+        assert_eq!((1, Some(ground)), contact.bodies);
+        assert!((contact.contact_normal.y - 1.0).abs() < 1e-9);
+        assert!((contact.penetration - 0.1).abs() < 1e-9);
+    }
+
+    // Spheres and capsules come to rest on the mesh.
+    let ball = world.add_body(RigidBody::new(
+        Vector3::new(-1.0, 1.0, 0.5),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let ball = world.body_index(ball).unwrap();
+    world.add_collider(Collider::new(ball, Shape::Sphere(Sphere::new(0.5))));
+    let mut pill = RigidBody::new(Vector3::new(1.0, 1.0, 0.5), 1.0, &Matrix3::identity());
+    pill.orientation =
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), std::f64::consts::FRAC_PI_2);
+    pill.calculate_derived_data();
+    let pill = world.add_body(pill);
+    let pill = world.body_index(pill).unwrap();
+    world.add_collider(Collider::new(pill, Shape::Capsule(Capsule::new(0.5, 0.25))));
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    assert!((world.bodies[ball].position.y - 0.5).abs() < 0.02);
+    assert!((world.bodies[pill].position.y - 0.25).abs() < 0.02);
+    assert!(
+        world.bodies[pill]
+            .orientation
+            .rotate(&Vector3::new(0.0, 1.0, 0.0))
+            .y
+            .abs()
+            < 0.05
+    );
+
+    // Rays and shapes find the triangles below them.
+    let ray = Ray::new(Vector3::new(1.5, 2.0, -1.5), Vector3::new(0.0, -1.0, 0.0));
+    let hit = world.raycast(&ray, 10.0, &QueryFilter::new()).unwrap();
+    assert_eq!(Some(mesh), hit.collider);
+    assert!((hit.distance - 2.0).abs() < 1e-6);
+    assert!((hit.normal.y.abs() - 1.0).abs() < 1e-6);
+    assert_eq!(
+        vec![mesh],
+        world.intersections_with_sphere(&Vector3::new(1.5, 0.2, -1.5), 0.3, &QueryFilter::new())
+    );
+    assert!(world
+        .intersections_with_sphere(&Vector3::new(1.5, 0.5, -1.5), 0.3, &QueryFilter::new())
+        .is_empty());
+}
+
+#[test]
+fn sharing() {
+    let mut world = World::<f64>::default();
+    world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    world.add_collider(Collider::new(0, Shape::trimesh(floor())));
+
+    // Snapshots and the worlds restored from them share the geometry of meshes.
+    let state = world.snapshot();
+    let mut restored = World::from(state.clone());
+    restored.restore(&state);
+    match (&world.colliders[0].shape, &restored.colliders[0].shape) {
+        (Shape::TriMesh(one), Shape::TriMesh(two)) => assert!(Arc::ptr_eq(one, two)),
+        _ => panic!("the collider should still be a mesh"),
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::collider::Collider;
+use crate::gravity::{GravityField, GravitySource};
+use crate::handle::BodyHandle;
+use crate::plane::Plane;
+use crate::rigid_body::{BodyType, RigidBody};
+use crate::shape::Shape;
+use crate::world::World;
+use math::{Matrix3, Vector3};
+#[cfg(feature = "wasm-threads")]
+use std::sync::{Condvar, Mutex};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Threads of the global rayon pool waiting for a web worker to run them.
+#[cfg(feature = "wasm-threads")]
+static PENDING_THREADS: Mutex<Vec<rayon::ThreadBuilder>> = Mutex::new(Vec::new());
+
+/// Signaled every time a thread of the global rayon pool starts waiting for a web worker.
+#[cfg(feature = "wasm-threads")]
+static THREAD_PENDING: Condvar = Condvar::new();
+
+/// Builds the global rayon pool with the given number of threads, returning false if it was
+/// already built, with the `wasm-threads` feature.
+///
+/// # Remarks
+/// Browsers have no threads to spawn, so the threads of the pool are run by web workers
+/// calling `run_pool_thread`, each one instantiating the same module over the same memory.
+/// This blocks until all of them are running, so it has to be called from a worker as well,
+/// as do the steps of worlds, since the main thread of a browser can't block. The module has
+/// to be built with the `atomics` and `bulk-memory` target features.
+#[cfg(feature = "wasm-threads")]
+#[wasm_bindgen]
+pub fn init_thread_pool(threads: usize) -> bool {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .spawn_handler(|thread| {
+            PENDING_THREADS
+                .lock()
+                .expect("pending threads poisoned")
+                .push(thread);
+            THREAD_PENDING.notify_one();
+            Ok(())
+        })
+        .build_global()
+        .is_ok()
+}
+
+/// Waits for a thread of the pool built by `init_thread_pool` and runs it on the calling web
+/// worker, with the `wasm-threads` feature.
+///
+/// # Remarks
+/// The global pool is never torn down, so this never returns.
+#[cfg(feature = "wasm-threads")]
+#[wasm_bindgen]
+pub fn run_pool_thread() {
+    let mut pending = PENDING_THREADS.lock().expect("pending threads poisoned");
+    let thread = loop {
+        match pending.pop() {
+            Some(thread) => break thread,
+            None => {
+                pending = THREAD_PENDING
+                    .wait(pending)
+                    .expect("pending threads poisoned")
+            }
+        }
+    };
+    drop(pending);
+    thread.run();
+}
+
+/// World exported to JavaScript through wasm-bindgen, with the `wasm` feature.
+///
+/// # Remarks
+/// Bodies and colliders are referred to by the bits of their handles, which JavaScript sees
+/// as `BigInt`s. Dynamic bodies take their mass from the colliders attached to them.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmWorld {
+    world: World,
+}
+
+#[wasm_bindgen]
+impl WasmWorld {
+    /// Creates a new world with the default configuration and no gravity.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmWorld {
+        Self::default()
+    }
+
+    /// Sets the gravity pulling every body of the world, replacing its whole gravity field.
+    pub fn set_gravity(&mut self, x: f64, y: f64, z: f64) {
+        self.world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(x, y, z)));
+    }
+
+    /// Adds a scenery plane to the world, given by its unit normal and its offset from the
+    /// origin along it.
+    pub fn add_plane(&mut self, x: f64, y: f64, z: f64, offset: f64) {
+        self.world
+            .planes
+            .push(Plane::new(Vector3::new(x, y, z), offset));
+    }
+
+    /// Adds a dynamic or static rigid body at the given position, returning its handle.
+    pub fn add_body(&mut self, x: f64, y: f64, z: f64, dynamic: bool) -> u64 {
+        let mut body = RigidBody::new(Vector3::new(x, y, z), 1.0, &Matrix3::identity());
+        if !dynamic {
+            body.set_body_type(BodyType::Static);
+        }
+        body.calculate_derived_data();
+        self.world.add_body(body).to_bits()
+    }
+
+    /// Attaches a sphere collider centered on a body, returning the handle of the collider,
+    /// or `undefined` if the body handle was no longer valid.
+    pub fn add_sphere(&mut self, body: u64, radius: f64) -> Option<u64> {
+        self.add_collider(body, Shape::sphere(radius))
+    }
+
+    /// Attaches a box collider centered on a body, returning the handle of the collider,
+    /// or `undefined` if the body handle was no longer valid.
+    pub fn add_box(&mut self, body: u64, x: f64, y: f64, z: f64) -> Option<u64> {
+        self.add_collider(body, Shape::cuboid(Vector3::new(x, y, z)))
+    }
+
+    /// Starts a frame of the world and runs its physics for the given duration (in seconds).
+    pub fn step(&mut self, duration: f64) {
+        self.world.start_frame();
+        self.world.run_physics(duration);
+    }
+
+    /// Returns the position of a body as `[x, y, z]`, or `undefined` if the handle no longer
+    /// referred to a body.
+    pub fn body_position(&self, body: u64) -> Option<Vec<f64>> {
+        self.world
+            .body(BodyHandle::from_bits(body))
+            .map(|body| vec![body.position.x, body.position.y, body.position.z])
+    }
+
+    /// Returns the time spent on the last step, in milliseconds.
+    ///
+    /// # Remarks
+    /// There's no clock to read on `wasm32-unknown-unknown`, so it's always zero there.
+    pub fn step_time(&self) -> f64 {
+        self.world.stats().total.as_secs_f64() * 1000.0
+    }
+
+    /// Returns the number of contacts generated by the last step.
+    pub fn contacts(&self) -> usize {
+        self.world.stats().contacts
+    }
+}
+
+impl WasmWorld {
+    /// Attaches a collider with the given shape to a body, giving dynamic bodies the mass of
+    /// their colliders.
+    fn add_collider(&mut self, body: u64, shape: Shape) -> Option<u64> {
+        let body = self.world.body_index(BodyHandle::from_bits(body))?;
+        let collider = self.world.add_collider(Collider::new(body, shape));
+        if self.world.bodies()[body].body_type == BodyType::Dynamic {
+            self.world.update_mass_properties(body);
+        }
+        Some(collider.to_bits())
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::wasm::*;
+
+#[test]
+fn falling() {
+    let mut world = WasmWorld::new();
+    world.set_gravity(0.0, -9.8, 0.0);
+    world.add_plane(0.0, 1.0, 0.0, 0.0);
+    let ball = world.add_body(0.0, 2.0, 0.0, true);
+    assert!(world.add_sphere(ball, 0.5).is_some());
+    let ground = world.add_body(4.0, 0.0, 0.0, false);
+    assert!(world.add_box(ground, 1.0, 0.5, 1.0).is_some());
+
+    for _ in 0..120 {
+        world.step(1.0 / 60.0);
+    }
+
+    // The ball lands on the plane, while the static box stays where it was.
+    let position = world.body_position(ball).unwrap();
+    assert!((position[1] - 0.5).abs() < 0.05);
+    assert_eq!(vec![4.0, 0.0, 0.0], world.body_position(ground).unwrap());
+    assert!(world.contacts() > 0);
+    assert!(world.step_time() >= 0.0);
+
+    // Stale handles are reported as missing.
+    assert_eq!(None, world.add_sphere(u64::MAX, 1.0));
+    assert_eq!(None, world.body_position(u64::MAX));
+}
+
+#[cfg(feature = "wasm-threads")]
+#[test]
+fn thread_pool() {
+    // Native threads stand in for the web workers running the threads of the pool. They're
+    // left waiting if another test got the global pool built first.
+    for _ in 0..2 {
+        std::thread::spawn(run_pool_thread);
+    }
+    if init_thread_pool(2) {
+        assert_eq!(2, rayon::current_num_threads());
+    }
+    assert!(!init_thread_pool(2));
+
+    let mut world = WasmWorld::new();
+    world.set_gravity(0.0, -9.8, 0.0);
+    let balls: Vec<_> = (0..64)
+        .map(|x| world.add_body(x as f64, 0.0, 0.0, true))
+        .collect();
+    for ball in balls.iter() {
+        assert!(world.add_sphere(*ball, 0.4).is_some());
+    }
+    world.step(1.0 / 60.0);
+    for ball in balls.iter() {
+        assert!(world.body_position(*ball).unwrap()[1] < 0.0);
+    }
+}
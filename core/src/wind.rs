@@ -0,0 +1,296 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::atmosphere::Atmosphere;
+use crate::cloth::Cloth;
+use crate::force::ForceGenerator;
+use crate::particle::Particle;
+use crate::particle_force::ParticleForceGenerator;
+use crate::rigid_body::RigidBody;
+use crate::shape::Shape;
+use math::{Matrix4, Vector3};
+use std::collections::BTreeMap;
+
+/// Density of air at sea level, in kilograms per cubic meter.
+pub const AIR_DENSITY: f64 = 1.225;
+
+/// Default area particles and rigid bodies without a shape present to the wind.
+pub const DEFAULT_WIND_AREA: f64 = 0.01;
+
+/// Function returning the velocity of the wind at a position and time.
+pub type WindFunction<F> = Box<dyn Fn(&Vector3<F>, F) -> Vector3<F>>;
+
+/// Returns smooth noise between `-1` and `1` at the given point, varying over distances of
+/// about one unit.
+///
+/// # Remarks
+/// Random values at the corners of a unit lattice are blended with a smooth step, so the
+/// noise is continuous and always the same at the same point.
+pub fn noise<F: num_traits::Float>(point: &Vector3<F>) -> F {
+    let floor = |value: F| value.floor();
+    let corner = |value: F| value.floor().to_i64().unwrap_or(0);
+    let (x, y, z) = (corner(point.x), corner(point.y), corner(point.z));
+    let smooth = |value: F| {
+        let t = value - floor(value);
+        t * t * (math::real::<F>(3.0) - math::real::<F>(2.0) * t)
+    };
+    let (u, v, w) = (smooth(point.x), smooth(point.y), smooth(point.z));
+
+    let value = |dx: i64, dy: i64, dz: i64| {
+        let mut hash = ((x + dx) as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ ((y + dy) as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+            ^ ((z + dz) as u64).wrapping_mul(0x1656_67b1_9e37_79f9);
+        hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        hash ^= hash >> 31;
+        let unit = (hash >> 11) as f64 / (1u64 << 53) as f64;
+        math::real::<F>(unit * 2.0 - 1.0)
+    };
+    let lerp = |a: F, b: F, t: F| a + (b - a) * t;
+    let plane = |dz: i64| {
+        lerp(
+            lerp(value(0, 0, dz), value(1, 0, dz), u),
+            lerp(value(0, 1, dz), value(1, 1, dz), u),
+            v,
+        )
+    };
+    lerp(plane(0), plane(1), w)
+}
+
+/// Returns the area of a shape placed with the given transform, projected along a direction
+/// of unit length.
+///
+/// # Remarks
+/// The areas of spheres, boxes, capsules and convex hulls are exact, and so are the ones of
+/// closed convex triangle meshes. The children of compounds are added up, so parts hiding
+/// each other count more than once, and so do the folds of concave meshes.
+pub fn projected_area<F: num_traits::Float>(
+    shape: &Shape<F>,
+    transform: &Matrix4<F>,
+    direction: &Vector3<F>,
+) -> F {
+    let local = transform.transform_inverse_direction(direction);
+    let pi = math::real::<F>(std::f64::consts::PI);
+    match shape {
+        Shape::Sphere(sphere) => pi * sphere.radius * sphere.radius,
+        Shape::Cuboid(cuboid) => {
+            let h = &cuboid.half_size;
+            math::real::<F>(4.0)
+                * (h.y * h.z * local.x.abs()
+                    + h.x * h.z * local.y.abs()
+                    + h.x * h.y * local.z.abs())
+        }
+        Shape::Capsule(capsule) => {
+            let across = (F::one() - local.y * local.y).max(F::zero()).sqrt();
+            pi * capsule.radius * capsule.radius
+                + math::real::<F>(4.0) * capsule.radius * capsule.half_height * across
+        }
+        Shape::ConvexHull(hull) => {
+            // A convex hull seen from any direction shows as much area in front as behind.
+            let total = hull.faces.iter().fold(F::zero(), |total, face| {
+                let first = hull.vertices[face.vertices[0]];
+                let twice_area =
+                    face.vertices
+                        .windows(2)
+                        .skip(1)
+                        .fold(Vector3::origin(), |sum, pair| {
+                            let one = hull.vertices[pair[0]].vector_sub(&first);
+                            let two = hull.vertices[pair[1]].vector_sub(&first);
+                            sum.vector_add(&one.cross_product(&two))
+                        });
+                total + twice_area.dot_product(&local).abs()
+            });
+            total * math::real(0.25)
+        }
+        Shape::TriMesh(mesh) => {
+            // Meshes are assumed closed, showing as much area in front as behind.
+            let total = (0..mesh.triangles().len()).fold(F::zero(), |total, index| {
+                let triangle = mesh.triangle(index);
+                total + triangle.area() * triangle.normal().dot_product(&local).abs()
+            });
+            total * math::real(0.5)
+        }
+        Shape::Compound(compound) => compound.children.iter().fold(F::zero(), |area, child| {
+            let placement = transform.matrix_mul(&child.offset);
+            area + projected_area(&child.shape, &placement, direction)
+        }),
+    }
+}
+
+/// Force generator blowing wind that changes from place to place, and over time.
+/// One instance can be used for multiple particles and rigid bodies.
+///
+/// # Remarks
+/// The velocity of the wind is sampled at each particle or body, and the air drags them along
+/// with a force growing with the square of their speed relative to the wind, and the area they
+/// present to it. Rigid bodies with a shape set present its projected area, the rest use the
+/// area of the field. Cloth is pushed triangle by triangle. With an atmosphere set, the
+/// density of the air depends on the altitude of each particle or body.
+///
+/// Register the field through a shared handle, and advance its time once per frame.
+pub struct WindField<F: num_traits::Float = f64> {
+    /// Velocity of the wind at a position and time.
+    pub function: WindFunction<F>,
+
+    /// Time the wind is sampled at.
+    pub time: F,
+
+    /// Density of the air.
+    pub air_density: F,
+
+    /// Atmosphere the density of the air is sampled from at each position, instead of using
+    /// `air_density` everywhere.
+    pub atmosphere: Option<Atmosphere<F>>,
+
+    /// Drag coefficient of the objects blown by the wind.
+    pub drag_coefficient: F,
+
+    /// Area presented to the wind by particles and rigid bodies without a shape.
+    pub area: F,
+
+    shapes: BTreeMap<usize, (Shape<F>, Matrix4<F>)>,
+}
+
+impl<F: num_traits::Float> WindField<F> {
+    /// Creates a new wind field sampling the given function.
+    pub fn new(function: WindFunction<F>) -> Self {
+        Self {
+            function,
+            time: F::zero(),
+            air_density: math::real(AIR_DENSITY),
+            atmosphere: None,
+            drag_coefficient: F::one(),
+            area: math::real(DEFAULT_WIND_AREA),
+            shapes: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the shape the given rigid body presents to the wind, placed with an offset in body space.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the wind field.
+    pub fn set_shape(&mut self, body: usize, shape: Shape<F>, offset: Matrix4<F>) -> &mut Self {
+        self.shapes.insert(body, (shape, offset));
+        self
+    }
+
+    /// Moves the time of the wind forward.
+    pub fn advance(&mut self, duration: F) {
+        self.time = self.time + duration;
+    }
+
+    /// Returns the velocity of the wind at the given position.
+    pub fn sample(&self, position: &Vector3<F>) -> Vector3<F> {
+        (self.function)(position, self.time)
+    }
+
+    /// Returns the density of the air at the given position.
+    pub fn density(&self, position: &Vector3<F>) -> F {
+        match &self.atmosphere {
+            Some(atmosphere) => atmosphere.density(position),
+            None => self.air_density,
+        }
+    }
+
+    /// Adds the push of the wind to the particles of a cloth, which drags through still air
+    /// on its own.
+    pub fn apply_to_cloth(&self, cloth: &mut Cloth<F>) {
+        cloth.apply_air(|position| self.sample(position), F::zero());
+    }
+
+    /// Returns the drag force of the wind on an object moving with the given velocity, for the
+    /// area the object presents along the direction of the relative wind.
+    fn drag<A: Fn(&Vector3<F>) -> F>(
+        &self,
+        position: &Vector3<F>,
+        velocity: &Vector3<F>,
+        area: A,
+    ) -> Vector3<F> {
+        let relative = self.sample(position).vector_sub(velocity);
+        let speed = relative.magnitude();
+        if speed <= F::epsilon() {
+            return Vector3::origin();
+        }
+        let direction = relative.scalar_div(speed);
+        let scale = math::real::<F>(0.5)
+            * self.density(position)
+            * self.drag_coefficient
+            * area(&direction);
+        relative.scalar_mul(scale * speed)
+    }
+}
+
+impl<F: num_traits::Float + 'static> WindField<F> {
+    /// Creates a new wind field blowing with a constant velocity.
+    pub fn uniform(velocity: Vector3<F>) -> Self {
+        Self::new(Box::new(move |_, _| velocity))
+    }
+
+    /// Creates a new field of still air with the given atmosphere, dragging the objects moving
+    /// through it more the lower they fly.
+    pub fn atmospheric(atmosphere: Atmosphere<F>) -> Self {
+        let mut field = Self::uniform(Vector3::origin());
+        field.atmosphere = Some(atmosphere);
+        field
+    }
+
+    /// Creates a new wind field blowing with a mean velocity plus gusts, drifting along with it.
+    ///
+    /// # Remarks
+    /// Each component of the gusts is noise with the given amplitude, varying over distances
+    /// of about `scale`.
+    pub fn gusty(mean: Vector3<F>, gust: F, scale: F) -> Self {
+        Self::new(Box::new(move |position, time| {
+            let drifted = position
+                .vector_sub(&mean.scalar_mul(time))
+                .scalar_div(scale);
+            let offset = |shift: f64| {
+                drifted.vector_add(&Vector3::new(
+                    math::real(shift),
+                    math::real(shift * 0.5),
+                    math::real(shift * 0.25),
+                ))
+            };
+            mean.vector_add(
+                &Vector3::new(noise(&drifted), noise(&offset(17.0)), noise(&offset(53.0)))
+                    .scalar_mul(gust),
+            )
+        }))
+    }
+}
+
+impl<F: num_traits::Float> ParticleForceGenerator<F> for WindField<F> {
+    fn update_force(&mut self, particles: &mut [Particle<F>], particle: usize, _duration: F) {
+        let particle = &mut particles[particle];
+        if !particle.has_finite_mass() {
+            return;
+        }
+
+        let force = self.drag(&particle.position, &particle.velocity, |_| self.area);
+        particle.add_force(&force);
+    }
+}
+
+impl<F: num_traits::Float> ForceGenerator<F> for WindField<F> {
+    fn update_force(&mut self, bodies: &mut [RigidBody<F>], index: usize, _duration: F) {
+        let body = &mut bodies[index];
+        if !body.has_finite_mass() {
+            return;
+        }
+
+        let force = match self.shapes.get(&index) {
+            Some((shape, offset)) => {
+                let transform = body.transform_matrix.matrix_mul(offset);
+                self.drag(&body.position, &body.velocity, |direction| {
+                    projected_area(shape, &transform, direction)
+                })
+            }
+            None => self.drag(&body.position, &body.velocity, |_| self.area),
+        };
+        body.add_force(&force);
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::cloth::Cloth;
+use crate::force::ForceGenerator;
+use crate::particle::Particle;
+use crate::particle_force::ParticleForceGenerator;
+use crate::rigid_body::RigidBody;
+use crate::shape::{Capsule, ConvexHull, Cuboid, Shape, Sphere};
+use crate::wind::*;
+use math::{Matrix3, Matrix4, Quaternion, Vector3};
+
+#[test]
+fn noise_field() {
+    let mut previous = noise(&Vector3::new(0.0, 0.3, 0.7));
+    let (mut low, mut high) = (previous, previous);
+    for step in 1..2000 {
+        let point = Vector3::new(step as f64 * 0.01, 0.3, 0.7);
+        let value = noise(&point);
+        assert!((-1.0..=1.0).contains(&value));
+        assert!((value - previous).abs() < 0.05);
+        assert_eq!(value, noise(&point));
+        low = low.min(value);
+        high = high.max(value);
+        previous = value;
+    }
+    assert!(high - low > 0.5);
+}
+
+#[test]
+fn projected_areas() {
+    let direction = Vector3::new(1.0, 0.0, 0.0);
+    let upright = Matrix4::identity();
+    let sphere = Shape::Sphere(Sphere::new(2.0));
+    assert!(
+        (projected_area(&sphere, &upright, &direction) - 4.0 * std::f64::consts::PI).abs() < 1e-12
+    );
+    let capsule = Shape::Capsule(Capsule::new(1.0, 0.5));
+    let side = 0.25 * std::f64::consts::PI + 2.0;
+    assert!((projected_area(&capsule, &upright, &direction) - side).abs() < 1e-12);
+
+    // Boxes and hulls with the same vertices present the same area from any direction.
+    let half_size = Vector3::new(1.0, 0.5, 2.0);
+    let cuboid = Shape::Cuboid(Cuboid::new(half_size));
+    let hull =
+        Shape::convex_hull(ConvexHull::from_points(&Cuboid::new(half_size).vertices()).unwrap());
+    assert!((projected_area(&cuboid, &upright, &direction) - 4.0).abs() < 1e-12);
+    let tilted = Matrix4::from_orientation_and_position(
+        &Quaternion::from_axis_angle(&Vector3::new(1.0, 1.0, 0.0).normalize(), 0.6),
+        &Vector3::new(3.0, 0.0, 0.0),
+    );
+    let direction = Vector3::new(0.3, -0.2, 0.9).normalize();
+    let area = projected_area(&cuboid, &tilted, &direction);
+    assert!(area > 2.0 && area < 8.0);
+    assert!((projected_area(&hull, &tilted, &direction) - area).abs() < 1e-9);
+}
+
+#[test]
+fn blowing() {
+    let mut field = WindField::<f64>::uniform(Vector3::new(10.0, 0.0, 0.0));
+
+    // Particles at rest are pushed along with the wind.
+    let mut particles = vec![Particle::new(Vector3::origin(), 1.0)];
+    ParticleForceGenerator::update_force(&mut field, &mut particles, 0, 0.1);
+    let expected = 0.5 * AIR_DENSITY * DEFAULT_WIND_AREA * 100.0;
+    assert!((particles[0].force_accum - Vector3::new(expected, 0.0, 0.0)).magnitude() < 1e-12);
+
+    // Rigid bodies present the area of their shape, and aren't pushed moving with the wind.
+    let mut bodies = vec![
+        RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()),
+        RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()),
+    ];
+    bodies[1].velocity = Vector3::new(10.0, 0.0, 0.0);
+    field.set_shape(
+        0,
+        Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 1.0, 2.0))),
+        Matrix4::identity(),
+    );
+    ForceGenerator::update_force(&mut field, &mut bodies, 0, 0.1);
+    ForceGenerator::update_force(&mut field, &mut bodies, 1, 0.1);
+    let expected = 0.5 * AIR_DENSITY * 8.0 * 100.0;
+    assert!((bodies[0].force_accum - Vector3::new(expected, 0.0, 0.0)).magnitude() < 1e-9);
+    assert_eq!(Vector3::origin(), bodies[1].force_accum);
+}
+
+#[test]
+fn gusts() {
+    let mean = Vector3::new(5.0, 0.0, 0.0);
+    let mut field = WindField::<f64>::gusty(mean, 2.0, 3.0);
+
+    // The gusts change from place to place around the mean, and drift along with it.
+    let samples: Vec<Vector3<f64>> = (0..400)
+        .map(|index| field.sample(&Vector3::new(index as f64 * 0.37, (index % 7) as f64, 0.0)))
+        .collect();
+    for sample in samples.iter() {
+        assert!((*sample - mean).magnitude() <= 2.0 * 3f64.sqrt());
+    }
+    assert!(samples
+        .iter()
+        .any(|sample| (*sample - samples[0]).magnitude() > 0.5));
+    let average = samples
+        .iter()
+        .fold(Vector3::origin(), |sum, sample| sum + *sample)
+        .scalar_div(samples.len() as f64);
+    assert!((average - mean).magnitude() < 0.5);
+
+    let point = Vector3::new(1.0, 2.0, 3.0);
+    let now = field.sample(&point);
+    field.advance(2.0);
+    assert!((field.sample(&(point + mean * 2.0)) - now).magnitude() < 1e-9);
+}
+
+#[test]
+fn cloth() {
+    let flag = || {
+        let mut cloth = Cloth::grid(
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            9,
+            5,
+            1.0,
+        );
+        cloth.pin(0, 0).pin(8, 0);
+        cloth.gravity = Vector3::new(0.0, -10.0, 0.0);
+        cloth
+    };
+
+    // A uniform field blows the cloth just like its own wind.
+    let wind = Vector3::new(0.0, 0.0, 5.0);
+    let field = WindField::uniform(wind);
+    let mut blown = flag();
+    let mut windy = flag();
+    windy.wind = wind;
+    for _ in 0..60 {
+        field.apply_to_cloth(&mut blown);
+        blown.run_physics(1.0 / 60.0);
+        windy.run_physics(1.0 / 60.0);
+    }
+    for (one, two) in blown.particles.iter().zip(windy.particles.iter()) {
+        assert!((one.position - two.position).magnitude() < 1e-9);
+    }
+    assert!(blown.particles[blown.index(4, 4)].position.z > 0.1);
+}
@@ -0,0 +1,1872 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::broad_phase::BroadPhase;
+use crate::bvh::DynamicBvh;
+use crate::collider::Collider;
+use crate::constraint_graph::{joint_name, ConstraintGraph, GraphBody, GraphContact, GraphJoint};
+use crate::contact::Contact;
+use crate::diagnostics::{ConservationMonitor, Diagnostics};
+use crate::event::{ContactEvent, ContactEventKind, SensorEvent, SensorEventKind};
+use crate::fluid::{Fluid, FluidCoupling};
+use crate::force::ForceRegistry;
+use crate::gjk::GjkResult;
+use crate::gravity::{GravityField, GravitySource};
+use crate::handle::{BodyHandle, ColliderHandle, Handles, JointHandle};
+use crate::island::Islands;
+use crate::joint::{Joint, JointBreak};
+use crate::manifold::{ManifoldCache, MAX_MANIFOLD_POINTS};
+use crate::mass_properties::MassProperties;
+use crate::material::PhysicsMaterial;
+use crate::narrow_phase::{collide, collide_with_half_space, CollisionData};
+use crate::parallel::{map_chunks, map_chunks_mut, Parallel};
+use crate::particle::Particle;
+use crate::plane::Plane;
+use crate::query::{closest_point, PointProjection, QueryFilter, RayHit, ShapeCastHit};
+use crate::query_pipeline::QueryView;
+use crate::ray::Ray;
+use crate::rigid_body::{BodyType, Power, RigidBody};
+use crate::shape::{Shape, Sphere, SupportMap};
+use crate::soft_body::SoftBody;
+use crate::solver::ContactSolver;
+use crate::stats::{Instant, WorldStats};
+use math::{Matrix3, Matrix4, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Default fraction of linear velocity kept by bodies after one second.
+pub const DEFAULT_LINEAR_DAMPING: f64 = 0.95;
+
+/// Default fraction of angular velocity kept by bodies after one second.
+pub const DEFAULT_ANGULAR_DAMPING: f64 = 0.8;
+
+/// Default maximum number of contacts generated by a world every frame.
+pub const DEFAULT_MAX_CONTACTS: usize = 4096;
+
+/// Depth bodies with continuous collision detection are allowed to sink into what they hit
+/// in a single frame, so the contact is picked up and solved in the next one.
+pub const CONTINUOUS_COLLISION_DEPTH: f64 = 0.01;
+
+/// Minimum number of colliders placed, or collided with the scenery, by every thread.
+const MIN_COLLIDERS_PER_THREAD: usize = 256;
+
+/// Minimum number of pairs of colliders collided by every thread.
+const MIN_PAIRS_PER_THREAD: usize = 64;
+
+/// Gap under which speculative contacts are reported as touching, since bodies resting
+/// on each other with a contact margin are kept right at the surface.
+const TOUCHING_DISTANCE: f64 = 0.005;
+
+/// Configuration of a rigid body world.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct WorldConfig<F: num_traits::Float = f64> {
+    /// Linear damping applied to bodies that don't have their own.
+    pub linear_damping: F,
+
+    /// Angular damping applied to bodies that don't have their own.
+    pub angular_damping: F,
+
+    /// Solver resolving the contacts of the manifolds and the joints, with its number of
+    /// velocity iterations and how it pushes penetrated bodies apart.
+    ///
+    /// # Remarks
+    /// More velocity iterations make tall stacks and long chains of joints stiffer, at a
+    /// cost that grows linearly with them. Scenes of loose bodies, like debris or particles,
+    /// settle fine with fewer.
+    pub solver: ContactSolver,
+
+    /// Number of substeps every call to `run_physics` is split into.
+    ///
+    /// # Remarks
+    /// Collisions are detected once per step, and every substep integrates the velocities,
+    /// solves the contacts and joints, and moves the bodies over its share of the step,
+    /// refreshing the contact points from the new positions. Substeps are more effective
+    /// than iterations at keeping heavy bodies on light ones, or fast joints, from drifting
+    /// apart, since every one of them sees the effect of the previous one, but they also
+    /// multiply the cost of the solver and the integration. Zero is treated as one.
+    pub substeps: usize,
+
+    /// Whether the energy and momentum of the bodies are measured before and after every
+    /// step, to be checked by the conservation monitors of the world.
+    ///
+    /// # Remarks
+    /// Measuring the bodies visits all of them twice per step, so it's off by default.
+    pub diagnostics: bool,
+
+    /// Whether the world runs the same bits on every run and platform.
+    ///
+    /// # Remarks
+    /// Bodies, colliders, pairs of colliders and contact manifolds are always visited in a
+    /// stable order, sorted by index, so a world replays identically on the same machine.
+    /// Deterministic worlds also compute the damping and sleep weights of rigid and soft
+    /// bodies with the software functions of `math::soft`, instead of the math library of the
+    /// platform. Joint angles always use them.
+    ///
+    /// The guarantee holds as long as the engine is built without fast-math flags and the
+    /// compiler doesn't fuse multiplications and additions, which Rust never does on its own,
+    /// and as long as the force generators added to the world are deterministic themselves.
+    pub deterministic: bool,
+}
+
+impl<F: num_traits::Float> Default for WorldConfig<F> {
+    fn default() -> Self {
+        Self {
+            linear_damping: math::real(DEFAULT_LINEAR_DAMPING),
+            angular_damping: math::real(DEFAULT_ANGULAR_DAMPING),
+            solver: ContactSolver::default(),
+            substeps: 1,
+            diagnostics: false,
+            deterministic: false,
+        }
+    }
+}
+
+/// How the strength of an explosion fades with the distance from its center.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Falloff {
+    /// Full strength everywhere within the radius.
+    Constant,
+
+    /// Strength fading linearly to zero at the radius.
+    Linear,
+
+    /// Strength fading with the square of the remaining distance to the radius.
+    Quadratic,
+}
+
+impl Falloff {
+    /// Returns the fraction of the strength left at the given distance from the center,
+    /// for the given radius.
+    pub fn scale<F: num_traits::Float>(&self, distance: F, radius: F) -> F {
+        if distance >= radius {
+            return F::zero();
+        }
+
+        let remaining = F::one() - distance / radius;
+        match self {
+            Falloff::Constant => F::one(),
+            Falloff::Linear => remaining,
+            Falloff::Quadratic => remaining * remaining,
+        }
+    }
+}
+
+/// Predicate deciding whether a pair of colliders, given along with their indices,
+/// can collide.
+pub type CollisionPredicate<F> =
+    Box<dyn Fn(usize, &Collider<Shape<F>, F>, usize, &Collider<Shape<F>, F>) -> bool>;
+
+/// Hook given the contacts generated between a collider, given along with its index, and
+/// another collider or the scenery planes (`None`), before they're solved.
+///
+/// # Remarks
+/// The hook can change the friction, restitution and normal of the contacts, or remove
+/// them, clearing the list to disable the whole pair for the frame.
+pub type ContactModifier<F> = Box<
+    dyn Fn(
+        usize,
+        &Collider<Shape<F>, F>,
+        Option<(usize, &Collider<Shape<F>, F>)>,
+        &mut Vec<Contact<F>>,
+    ),
+>;
+
+/// State of a world at some point, captured by `World::snapshot` to be restored later.
+///
+/// # Remarks
+/// The state holds everything the world changes while stepping, so restoring it and stepping
+/// again gives the same results. The force generators, the collision predicate, the contact
+/// modifier and the conservation monitors are left out, since they can't be copied, and so
+/// are the contacts of the last frame, which are generated again by the next step.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct WorldState<F: num_traits::Float = f64> {
+    bodies: Vec<RigidBody<F>>,
+    gravity: GravityField<F>,
+    gravity_overrides: BTreeMap<usize, GravitySource<F>>,
+    config: WorldConfig<F>,
+    colliders: Vec<Collider<Shape<F>, F>>,
+    planes: Vec<Plane<F>>,
+    scenery_material: PhysicsMaterial<F>,
+    soft_bodies: Vec<SoftBody<F>>,
+    fluids: Vec<Fluid<F>>,
+    joints: Vec<Joint<F>>,
+    joint_breaks: Vec<JointBreak<F>>,
+    manifolds: ManifoldCache<F>,
+    broad_phase: DynamicBvh<F>,
+    touching: BTreeSet<(usize, Option<usize>)>,
+    contact_events: Vec<ContactEvent<F>>,
+    overlapping: BTreeSet<(usize, usize)>,
+    sensor_events: Vec<SensorEvent>,
+    passing: BTreeSet<(usize, usize)>,
+    body_handles: Handles,
+    collider_handles: Handles,
+    joint_handles: Handles,
+}
+
+impl<F: num_traits::Float> WorldState<F> {
+    /// Returns the rigid bodies of the world, as they were when captured.
+    pub fn bodies(&self) -> &[RigidBody<F>] {
+        &self.bodies
+    }
+
+    /// Returns the colliders of the world, as they were when captured.
+    pub fn colliders(&self) -> &[Collider<Shape<F>, F>] {
+        &self.colliders
+    }
+
+    /// Returns the scenery planes of the world, as they were when captured.
+    pub fn planes(&self) -> &[Plane<F>] {
+        &self.planes
+    }
+
+    /// Drops the caches the world keeps across steps, like the contact manifolds and the
+    /// pairs touching, to make saved states smaller.
+    ///
+    /// # Remarks
+    /// Worlds restored without caches rebuild them in the next step, starting their
+    /// contacts cold and reporting them as started again.
+    ///
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the world state.
+    pub fn without_caches(mut self) -> Self {
+        self.manifolds = ManifoldCache::new(self.manifolds.breaking_threshold);
+        self.broad_phase = DynamicBvh::new(self.broad_phase.margin);
+        self.touching.clear();
+        self.contact_events.clear();
+        self.overlapping.clear();
+        self.sensor_events.clear();
+        self.passing.clear();
+        self.joint_breaks.clear();
+        self
+    }
+}
+
+/// New indices of the bodies, colliders, joints, soft bodies and fluids of a state appended
+/// to a world, by their index in the state.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct IndexRemap {
+    /// New indices of the rigid bodies.
+    pub bodies: Vec<usize>,
+
+    /// New indices of the colliders.
+    pub colliders: Vec<usize>,
+
+    /// New indices of the joints.
+    pub joints: Vec<usize>,
+
+    /// New indices of the soft bodies.
+    pub soft_bodies: Vec<usize>,
+
+    /// New indices of the fluids.
+    pub fluids: Vec<usize>,
+}
+
+/// Keeps track of a set of rigid bodies, and provides the means to update them all.
+pub struct World<F: num_traits::Float = f64> {
+    /// Rigid bodies simulated by the world, which only the world adds and removes so their
+    /// handles keep resolving to them.
+    pub(crate) bodies: Vec<RigidBody<F>>,
+
+    /// Force generators applied to the bodies of the world.
+    pub registry: ForceRegistry<F>,
+
+    /// Gravity pulling the bodies of the world, none by default.
+    pub gravity: GravityField<F>,
+
+    /// Gravity replacing the one of the field for some of the bodies, by body index.
+    pub gravity_overrides: BTreeMap<usize, GravitySource<F>>,
+
+    /// Configuration of the world.
+    pub config: WorldConfig<F>,
+
+    /// Colliders attached to the bodies of the world, which only the world adds and removes
+    /// so their handles keep resolving to them.
+    pub(crate) colliders: Vec<Collider<Shape<F>, F>>,
+
+    /// Half-spaces that are part of the scenery, colliding with every collider.
+    pub planes: Vec<Plane<F>>,
+
+    /// Material the scenery planes are made of.
+    pub scenery_material: PhysicsMaterial<F>,
+
+    /// Soft bodies simulated by the world, colliding with its rigid bodies.
+    pub soft_bodies: Vec<SoftBody<F>>,
+
+    /// Fluids simulated by the world, colliding with its rigid bodies.
+    pub fluids: Vec<Fluid<F>>,
+
+    /// Joints between the bodies of the world, which only the world adds and removes so their
+    /// handles keep resolving to them.
+    pub(crate) joints: Vec<Joint<F>>,
+
+    /// Joints broken during the last frame.
+    pub joint_breaks: Vec<JointBreak<F>>,
+
+    /// Contacts generated during the last frame.
+    pub contacts: CollisionData<F>,
+
+    /// Contact manifolds persisted across frames.
+    pub manifolds: ManifoldCache<F>,
+
+    /// Predicate consulted for the pairs of colliders whose collision groups interact,
+    /// ignoring the pairs it returns false for.
+    pub collision_predicate: Option<CollisionPredicate<F>>,
+
+    /// Hook given the contacts of every pair of colliders, and of every collider with the
+    /// scenery planes, before the solver sees them.
+    pub contact_modifier: Option<ContactModifier<F>>,
+
+    /// Monitors called back when the quantities they watch aren't conserved during a step,
+    /// while diagnostics are enabled.
+    pub conservation_monitors: Vec<ConservationMonitor<F>>,
+
+    broad_phase: DynamicBvh<F>,
+    touching: BTreeSet<(usize, Option<usize>)>,
+    contact_events: Vec<ContactEvent<F>>,
+    overlapping: BTreeSet<(usize, usize)>,
+    sensor_events: Vec<SensorEvent>,
+    passing: BTreeSet<(usize, usize)>,
+    stats: WorldStats,
+    diagnostics: Option<Diagnostics<F>>,
+    previous_poses: Vec<(Vector3<F>, Quaternion<F>)>,
+    body_handles: Handles,
+    collider_handles: Handles,
+    joint_handles: Handles,
+}
+
+impl<F: num_traits::Float> Default for World<F> {
+    fn default() -> Self {
+        Self::new(WorldConfig::default())
+    }
+}
+
+impl<F: num_traits::Float> From<WorldState<F>> for World<F> {
+    fn from(state: WorldState<F>) -> Self {
+        let mut world = Self::new(state.config);
+        world.restore(&state);
+        world
+    }
+}
+
+/// Worlds are saved as the state captured by `World::snapshot`, so the force generators and
+/// the collision predicate have to be added again after loading them.
+impl<F: num_traits::Float + Serialize> Serialize for World<F> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+impl<'de, F: num_traits::Float + Deserialize<'de>> Deserialize<'de> for World<F> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        WorldState::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl<F: num_traits::Float> World<F> {
+    /// Creates a new empty world with the given configuration.
+    pub fn new(config: WorldConfig<F>) -> Self {
+        Self {
+            bodies: Vec::new(),
+            registry: ForceRegistry::new(),
+            gravity: GravityField::default(),
+            gravity_overrides: BTreeMap::new(),
+            config,
+            colliders: Vec::new(),
+            planes: Vec::new(),
+            scenery_material: PhysicsMaterial::default(),
+            soft_bodies: Vec::new(),
+            fluids: Vec::new(),
+            joints: Vec::new(),
+            joint_breaks: Vec::new(),
+            contacts: CollisionData::new(DEFAULT_MAX_CONTACTS),
+            manifolds: ManifoldCache::default(),
+            collision_predicate: None,
+            contact_modifier: None,
+            conservation_monitors: Vec::new(),
+            broad_phase: DynamicBvh::default(),
+            touching: BTreeSet::new(),
+            contact_events: Vec::new(),
+            overlapping: BTreeSet::new(),
+            sensor_events: Vec::new(),
+            passing: BTreeSet::new(),
+            stats: WorldStats::default(),
+            diagnostics: None,
+            previous_poses: Vec::new(),
+            body_handles: Handles::default(),
+            collider_handles: Handles::default(),
+            joint_handles: Handles::default(),
+        }
+    }
+
+    /// Returns the timings and counters of the last step of the world.
+    pub fn stats(&self) -> &WorldStats {
+        &self.stats
+    }
+
+    /// Returns the energy and momentum of the bodies after the last step, if diagnostics
+    /// were enabled for it.
+    pub fn diagnostics(&self) -> Option<&Diagnostics<F>> {
+        self.diagnostics.as_ref()
+    }
+
+    /// Measures the energy and momentum of the bodies as they are now.
+    pub fn measure_diagnostics(&self) -> Diagnostics<F> {
+        Diagnostics::measure(&self.bodies, &self.gravity, &self.gravity_overrides)
+    }
+
+    /// Adds a rigid body to the world, returning its handle.
+    ///
+    /// # Remarks
+    /// The body takes the next index, given by `body_index`, which colliders, joints and
+    /// force generators refer to it with. Indices of removed bodies aren't reused.
+    pub fn add_body(&mut self, body: RigidBody<F>) -> BodyHandle {
+        self.bodies.push(body);
+        self.body_handles.handle(self.bodies.len() - 1)
+    }
+
+    /// Adds a collider to the world, returning its handle.
+    pub fn add_collider(&mut self, collider: Collider<Shape<F>, F>) -> ColliderHandle {
+        self.colliders.push(collider);
+        self.collider_handles.handle(self.colliders.len() - 1)
+    }
+
+    /// Returns the rigid bodies of the world, by index.
+    ///
+    /// # Remarks
+    /// Removed bodies leave inert static bodies behind, so indices stay valid.
+    pub fn bodies(&self) -> &[RigidBody<F>] {
+        &self.bodies
+    }
+
+    /// Returns the colliders of the world, by index.
+    pub fn colliders(&self) -> &[Collider<Shape<F>, F>] {
+        &self.colliders
+    }
+
+    /// Returns the joints of the world, by index.
+    pub fn joints(&self) -> &[Joint<F>] {
+        &self.joints
+    }
+
+    /// Returns the rigid bodies of the world, by index, to change them in place.
+    pub fn bodies_mut(&mut self) -> &mut [RigidBody<F>] {
+        &mut self.bodies
+    }
+
+    /// Returns the colliders of the world, by index, to change them in place.
+    pub fn colliders_mut(&mut self) -> &mut [Collider<Shape<F>, F>] {
+        &mut self.colliders
+    }
+
+    /// Returns the joints of the world, by index, to change them in place.
+    pub fn joints_mut(&mut self) -> &mut [Joint<F>] {
+        &mut self.joints
+    }
+
+    /// Returns the mass properties of a rigid body made of its colliders, in its local space,
+    /// from their shapes and the densities of their materials. Sensors add no mass.
+    pub fn mass_properties(&self, body: usize) -> MassProperties<F> {
+        self.colliders
+            .iter()
+            .filter(|collider| collider.body == body && !collider.sensor)
+            .fold(MassProperties::zero(), |properties, collider| {
+                properties.merge(&collider.mass_properties())
+            })
+    }
+
+    /// Gives a rigid body the given mass properties, in its local space.
+    ///
+    /// # Remarks
+    /// The body is moved onto the center of mass, and its colliders are offset the other way
+    /// so they stay where they were, along with the velocity of the body at its new center.
+    /// Joints and force generators attached to points of the body aren't moved, so they
+    /// should be attached afterwards.
+    pub fn set_mass_properties(&mut self, body: usize, properties: &MassProperties<F>) {
+        let center = properties.center_of_mass;
+        let rigid_body = &mut self.bodies[body];
+        rigid_body.calculate_derived_data();
+        let position = rigid_body.point_in_world_space(&center);
+        rigid_body.velocity = rigid_body.velocity_at_point(&position);
+        rigid_body.position = position;
+        rigid_body.set_mass(properties.mass);
+        rigid_body.set_inertia_tensor(&properties.inertia_tensor);
+        rigid_body.calculate_derived_data();
+
+        for collider in self.colliders.iter_mut().filter(|c| c.body == body) {
+            collider.offset.data[3] = collider.offset.data[3] - center.x;
+            collider.offset.data[7] = collider.offset.data[7] - center.y;
+            collider.offset.data[11] = collider.offset.data[11] - center.z;
+        }
+    }
+
+    /// Gives a rigid body the mass properties of its colliders, returning them.
+    ///
+    /// # Remarks
+    /// Bodies without colliders with mass are left untouched. To override some of the
+    /// properties, change the ones returned by `mass_properties` and give them to the body
+    /// with `set_mass_properties` instead.
+    pub fn update_mass_properties(&mut self, body: usize) -> MassProperties<F> {
+        let properties = self.mass_properties(body);
+        if properties.mass > F::zero() {
+            self.set_mass_properties(body, &properties);
+        }
+        properties
+    }
+
+    /// Initializes the world for a simulation frame.
+    /// This clears the force and torque accumulators for the bodies in the world,
+    /// and updates their derived data.
+    pub fn start_frame(&mut self) {
+        for body in self.bodies.iter_mut() {
+            body.clear_accumulators();
+            body.calculate_derived_data();
+        }
+    }
+
+    /// Integrates all the bodies in the world forward in time by the given duration.
+    pub fn integrate(&mut self, duration: F) {
+        let power = self.power();
+        for body in self.bodies.iter_mut() {
+            body.integrate_velocity_with(
+                duration,
+                self.config.linear_damping,
+                self.config.angular_damping,
+                power,
+            )
+            .integrate_position_with(duration, power);
+        }
+    }
+
+    /// Adds a soft body to the world, returning its index.
+    pub fn add_soft_body(&mut self, soft_body: SoftBody<F>) -> usize {
+        self.soft_bodies.push(soft_body);
+        self.soft_bodies.len() - 1
+    }
+
+    /// Adds a fluid to the world, returning its index.
+    pub fn add_fluid(&mut self, fluid: Fluid<F>) -> usize {
+        self.fluids.push(fluid);
+        self.fluids.len() - 1
+    }
+
+    /// Adds a joint to the world, returning its handle.
+    pub fn add_joint(&mut self, joint: Joint<F>) -> JointHandle {
+        self.joints.push(joint);
+        self.joint_handles.handle(self.joints.len() - 1)
+    }
+
+    /// Captures the state of the world, to restore it later.
+    ///
+    /// # Remarks
+    /// The cost of the copy grows linearly with the number of bodies and colliders, so
+    /// rollback schemes can afford to snapshot every frame. The geometry of meshes is
+    /// shared with the snapshot rather than copied.
+    pub fn snapshot(&self) -> WorldState<F> {
+        WorldState {
+            bodies: self.bodies.clone(),
+            gravity: self.gravity.clone(),
+            gravity_overrides: self.gravity_overrides.clone(),
+            config: self.config,
+            colliders: self.colliders.clone(),
+            planes: self.planes.clone(),
+            scenery_material: self.scenery_material,
+            soft_bodies: self.soft_bodies.clone(),
+            fluids: self.fluids.clone(),
+            joints: self.joints.clone(),
+            joint_breaks: self.joint_breaks.clone(),
+            manifolds: self.manifolds.clone(),
+            broad_phase: self.broad_phase.clone(),
+            touching: self.touching.clone(),
+            contact_events: self.contact_events.clone(),
+            overlapping: self.overlapping.clone(),
+            sensor_events: self.sensor_events.clone(),
+            passing: self.passing.clone(),
+            body_handles: self.body_handles.clone(),
+            collider_handles: self.collider_handles.clone(),
+            joint_handles: self.joint_handles.clone(),
+        }
+    }
+
+    /// Puts the world back in a state captured by `snapshot`.
+    ///
+    /// # Remarks
+    /// Bodies, colliders and joints keep the indices and handles they had when captured, so
+    /// handles given out before the snapshot refer to them again. The memory of the world is
+    /// reused where possible, and its force generators and collision predicate are kept as
+    /// they are. Interpolated poses start over from the restored bodies.
+    pub fn restore(&mut self, state: &WorldState<F>) {
+        self.previous_poses.clear();
+        self.bodies.clone_from(&state.bodies);
+        self.gravity.clone_from(&state.gravity);
+        self.gravity_overrides.clone_from(&state.gravity_overrides);
+        self.config = state.config;
+        self.colliders.clone_from(&state.colliders);
+        self.planes.clone_from(&state.planes);
+        self.scenery_material = state.scenery_material;
+        self.soft_bodies.clone_from(&state.soft_bodies);
+        self.fluids.clone_from(&state.fluids);
+        self.joints.clone_from(&state.joints);
+        self.joint_breaks.clone_from(&state.joint_breaks);
+        self.manifolds.clone_from(&state.manifolds);
+        self.broad_phase.clone_from(&state.broad_phase);
+        self.touching.clone_from(&state.touching);
+        self.contact_events.clone_from(&state.contact_events);
+        self.overlapping.clone_from(&state.overlapping);
+        self.sensor_events.clone_from(&state.sensor_events);
+        self.passing.clone_from(&state.passing);
+        self.body_handles.clone_from(&state.body_handles);
+        self.collider_handles.clone_from(&state.collider_handles);
+        self.joint_handles.clone_from(&state.joint_handles);
+        self.contacts.reset();
+    }
+
+    /// Adds the bodies, colliders, joints, scenery planes, soft bodies and fluids of a state
+    /// to the world, after the ones it already has, returning their new indices.
+    ///
+    /// # Remarks
+    /// The colliders, joints and gravity overrides of the state are attached to the new
+    /// indices of their bodies. The configuration, gravity and caches of the world
+    /// are kept, and the ones of the state ignored.
+    pub fn append(&mut self, state: &WorldState<F>) -> IndexRemap {
+        let indices = |offset: usize, count: usize| (offset..offset + count).collect();
+        let remap = IndexRemap {
+            bodies: indices(self.bodies.len(), state.bodies.len()),
+            colliders: indices(self.colliders.len(), state.colliders.len()),
+            joints: indices(self.joints.len(), state.joints.len()),
+            soft_bodies: indices(self.soft_bodies.len(), state.soft_bodies.len()),
+            fluids: indices(self.fluids.len(), state.fluids.len()),
+        };
+
+        self.bodies.extend_from_slice(&state.bodies);
+        for collider in state.colliders.iter() {
+            let mut collider = collider.clone();
+            collider.body = remap.bodies[collider.body];
+            self.colliders.push(collider);
+        }
+        for joint in state.joints.iter() {
+            let mut joint = joint.clone();
+            joint.bodies = (
+                remap.bodies[joint.bodies.0],
+                joint.bodies.1.map(|body| remap.bodies[body]),
+            );
+            self.joints.push(joint);
+        }
+        for (body, source) in state.gravity_overrides.iter() {
+            self.gravity_overrides.insert(remap.bodies[*body], *source);
+        }
+        self.planes.extend_from_slice(&state.planes);
+        self.soft_bodies.extend_from_slice(&state.soft_bodies);
+        self.fluids.extend_from_slice(&state.fluids);
+        remap
+    }
+
+    /// Moves the origin of the world to the given point, translating everything in it by the
+    /// opposite offset, so the simulation carries on as before but closer to the origin.
+    ///
+    /// # Remarks
+    /// Bodies, colliders, joints attached to the scenery, scenery planes, soft bodies,
+    /// fluids, gravity sources and zones are all moved at once, along with the cached contact
+    /// manifolds, contact events and the broad phase, so contacts stay warm and no pair is
+    /// lost or reported again. Velocities and orientations are left untouched. Force
+    /// generators are opaque to the world, so the ones holding points in world space, like
+    /// anchored springs, have to be moved by the caller.
+    ///
+    /// Single precision loses about a millimeter of resolution at ten kilometers from the
+    /// origin, enough for contacts to jitter and resting bodies to creep. Worlds using `f32`
+    /// that span more than that should keep the origin near the area of interest, such as the
+    /// player or the camera, shifting it whenever they get too far away from it.
+    pub fn shift_origin(&mut self, offset: &Vector3<F>) {
+        for body in self.bodies.iter_mut() {
+            body.position = body.position.vector_sub(offset);
+            body.calculate_derived_data();
+        }
+        for (position, _) in self.previous_poses.iter_mut() {
+            *position = position.vector_sub(offset);
+        }
+        for collider in self.colliders.iter_mut() {
+            collider.shift_origin(offset);
+        }
+        self.broad_phase.shift_origin(offset);
+        for plane in self.planes.iter_mut() {
+            plane.offset = plane.offset - plane.normal.dot_product(offset);
+        }
+        for joint in self.joints.iter_mut() {
+            joint.shift_origin(offset);
+        }
+
+        self.gravity.shift_origin(offset);
+        for source in self.gravity_overrides.values_mut() {
+            *source = source.shifted(offset);
+        }
+
+        for soft_body in self.soft_bodies.iter_mut() {
+            for particle in soft_body.particles.iter_mut() {
+                particle.position = particle.position.vector_sub(offset);
+            }
+        }
+        for fluid in self.fluids.iter_mut() {
+            for particle in fluid.particles.iter_mut() {
+                particle.position = particle.position.vector_sub(offset);
+            }
+            for plane in fluid.boundaries.iter_mut() {
+                plane.offset = plane.offset - plane.normal.dot_product(offset);
+            }
+        }
+
+        for manifold in self.manifolds.iter_mut() {
+            let scenery = manifold.bodies.1.is_none();
+            for point in manifold.points.iter_mut() {
+                point.contact.contact_point = point.contact.contact_point.vector_sub(offset);
+                if scenery {
+                    point.local_point_two = point.local_point_two.vector_sub(offset);
+                }
+            }
+        }
+        for contact in self.contacts.contacts.iter_mut() {
+            contact.contact_point = contact.contact_point.vector_sub(offset);
+        }
+        for event in self.contact_events.iter_mut() {
+            event.point = event.point.vector_sub(offset);
+        }
+    }
+
+    /// Removes the rigid body of a handle from the world, along with its colliders, joints,
+    /// force registrations and gravity override, returning it if the handle still referred
+    /// to a body.
+    ///
+    /// # Remarks
+    /// The body leaves an inert static body behind, asleep where it was, so the other bodies
+    /// keep their indices and the indices kept elsewhere, like in force generators or the
+    /// collision predicate, stay valid. The index is never given to another body. The
+    /// colliders and joints after the ones removed move down to fill the gaps, while their
+    /// handles keep resolving. Contact manifolds are dropped, so contacts start cold again.
+    pub fn remove_body(&mut self, handle: BodyHandle) -> Option<RigidBody<F>> {
+        let index = self.body_index(handle)?;
+        Some(self.remove_body_at(index))
+    }
+
+    /// Removes the rigid body with the given index from the world, like `remove_body`.
+    pub(crate) fn remove_body_at(&mut self, index: usize) -> RigidBody<F> {
+        let mut vacant = RigidBody::new(
+            self.bodies[index].position,
+            num_traits::one(),
+            &Matrix3::identity(),
+        );
+        vacant.set_body_type(BodyType::Static).set_awake(false);
+        let body = std::mem::replace(&mut self.bodies[index], vacant);
+        let _: BodyHandle = self.body_handles.handle(index);
+        self.body_handles.vacate(index);
+
+        // Map the colliders kept to their new indices, to keep reporting their events.
+        let mut kept = Vec::with_capacity(self.colliders.len());
+        let mut next = 0;
+        for collider in self.colliders.iter() {
+            if collider.body == index {
+                kept.push(None);
+            } else {
+                kept.push(Some(next));
+                next += 1;
+            }
+        }
+        self.collider_handles
+            .retain(|collider| kept[collider].is_some());
+        self.colliders.retain(|collider| collider.body != index);
+        self.touching = self
+            .touching
+            .iter()
+            .filter_map(|(one, two)| match two {
+                Some(two) => Some((kept[*one]?, Some(kept[*two]?))),
+                None => Some((kept[*one]?, None)),
+            })
+            .collect();
+        self.overlapping = self
+            .overlapping
+            .iter()
+            .filter_map(|(one, two)| Some((kept[*one]?, kept[*two]?)))
+            .collect();
+        self.passing = self
+            .passing
+            .iter()
+            .filter_map(|(one, two)| Some((kept[*one]?, kept[*two]?)))
+            .collect();
+        self.broad_phase = DynamicBvh::new(self.broad_phase.margin);
+
+        let joined = |joint: &Joint<F>| joint.bodies.0 == index || joint.bodies.1 == Some(index);
+        let removed: Vec<bool> = self.joints.iter().map(joined).collect();
+        self.joint_handles.retain(|joint| !removed[joint]);
+        self.joints.retain(|joint| !joined(joint));
+
+        self.registry.remove(index);
+        self.gravity_overrides.remove(&index);
+        self.manifolds = ManifoldCache::new(self.manifolds.breaking_threshold);
+        self.contacts.reset();
+        body
+    }
+
+    /// Removes the collider of a handle from the world, returning it if the handle still
+    /// referred to a collider. The last collider of the world takes its place, so only that
+    /// one changes its index.
+    ///
+    /// # Remarks
+    /// Only the removed collider and the one moved into its place are updated in the broad
+    /// phase, so colliders can be streamed in and out of large worlds every frame. The mass of
+    /// the body the collider was attached to is left as it is.
+    pub fn remove_collider(&mut self, handle: ColliderHandle) -> Option<Collider<Shape<F>, F>> {
+        let index = self.collider_index(handle)?;
+        Some(self.remove_collider_at(index))
+    }
+
+    /// Removes the collider with the given index from the world, like `remove_collider`.
+    fn remove_collider_at(&mut self, index: usize) -> Collider<Shape<F>, F> {
+        let last = self.colliders.len() - 1;
+        let _: ColliderHandle = self.collider_handles.handle(last);
+        self.collider_handles.swap_remove(index);
+        let collider = self.colliders.swap_remove(index);
+
+        // Forget the pairs of the removed collider, and give the moved one its new index.
+        let moved = |collider: usize| {
+            if collider == index {
+                None
+            } else if collider == last {
+                Some(index)
+            } else {
+                Some(collider)
+            }
+        };
+        let ordered = |one: usize, two: usize| (one.min(two), one.max(two));
+        self.touching = self
+            .touching
+            .iter()
+            .filter_map(|(one, two)| match two {
+                Some(two) => {
+                    let (one, two) = ordered(moved(*one)?, moved(*two)?);
+                    Some((one, Some(two)))
+                }
+                None => Some((moved(*one)?, None)),
+            })
+            .collect();
+        self.overlapping = self
+            .overlapping
+            .iter()
+            .filter_map(|(one, two)| Some(ordered(moved(*one)?, moved(*two)?)))
+            .collect();
+        self.passing = self
+            .passing
+            .iter()
+            .filter_map(|(one, two)| Some(ordered(moved(*one)?, moved(*two)?)))
+            .collect();
+
+        // Colliders not placed yet are left for the next update to insert.
+        let placed = self.broad_phase.fat_aabb(last).is_some();
+        self.broad_phase.remove(last);
+        if index < last {
+            self.broad_phase.remove(index);
+        }
+        if index < last && placed {
+            let collider = &self.colliders[index];
+            let aabb = collider.shape.aabb(collider.transform());
+            self.broad_phase
+                .insert(index, &aabb.loosened(collider.contact_margin));
+        }
+        collider
+    }
+
+    /// Removes the joint of a handle from the world, returning it if the handle still
+    /// referred to a joint. The joints after it move down to fill the gap.
+    pub fn remove_joint(&mut self, handle: JointHandle) -> Option<Joint<F>> {
+        let index = self.joint_index(handle)?;
+        self.joint_handles.retain(|joint| joint != index);
+        Some(self.joints.remove(index))
+    }
+
+    /// Returns the handle of the rigid body with the given index.
+    ///
+    /// # Remarks
+    /// Handles keep referring to the same body as others are removed, and stop resolving
+    /// once it's removed itself, even after its slot is reused, so they can be held across
+    /// frames.
+    pub fn body_handle(&mut self, index: usize) -> BodyHandle {
+        assert!(index < self.bodies.len(), "body index out of bounds");
+        assert!(!self.body_handles.is_vacant(index), "body was removed");
+        self.body_handles.handle(index)
+    }
+
+    /// Returns the number of rigid bodies in the world, leaving out the removed ones.
+    pub fn body_count(&self) -> usize {
+        self.bodies.len() - self.body_handles.vacant()
+    }
+
+    /// Returns the current index of the rigid body of a handle, if it wasn't removed.
+    pub fn body_index(&self, handle: BodyHandle) -> Option<usize> {
+        self.body_handles.index(handle, self.bodies.len())
+    }
+
+    /// Returns the rigid body of a handle, if it wasn't removed.
+    pub fn body(&self, handle: BodyHandle) -> Option<&RigidBody<F>> {
+        self.body_index(handle).map(|index| &self.bodies[index])
+    }
+
+    /// Returns the rigid body of a handle, if it wasn't removed.
+    pub fn body_mut(&mut self, handle: BodyHandle) -> Option<&mut RigidBody<F>> {
+        let index = self.body_index(handle)?;
+        Some(&mut self.bodies[index])
+    }
+
+    /// Returns the position and orientation of the rigid body of a handle, the given
+    /// fraction of the way from where it was before the last step to where it is now.
+    ///
+    /// # Remarks
+    /// Renderers drawing at a different rate than the fixed step of the world can draw the
+    /// bodies with the fraction of a step left in their time accumulator, one step behind the
+    /// simulation but without stuttering. Bodies added after the last step are drawn where
+    /// they are now, and handles no longer referring to a body return `None`.
+    pub fn interpolated_pose(
+        &self,
+        handle: BodyHandle,
+        alpha: F,
+    ) -> Option<(Vector3<F>, Quaternion<F>)> {
+        let index = self.body_index(handle)?;
+        let body = &self.bodies[index];
+        Some(match self.previous_poses.get(index) {
+            Some((position, orientation)) => (
+                position.vector_add(&body.position.vector_sub(position).scalar_mul(alpha)),
+                orientation.slerp(&body.orientation, alpha),
+            ),
+            None => (body.position, body.orientation),
+        })
+    }
+
+    /// Returns the handle of the collider with the given index.
+    ///
+    /// # Remarks
+    /// Handles keep referring to the same collider as others are removed, and stop resolving
+    /// once it's removed itself, or along with its body, even after its slot is reused.
+    pub fn collider_handle(&mut self, index: usize) -> ColliderHandle {
+        assert!(index < self.colliders.len(), "collider index out of bounds");
+        self.collider_handles.handle(index)
+    }
+
+    /// Returns the current index of the collider of a handle, if it wasn't removed.
+    pub fn collider_index(&self, handle: ColliderHandle) -> Option<usize> {
+        self.collider_handles.index(handle, self.colliders.len())
+    }
+
+    /// Returns the collider of a handle, if it wasn't removed.
+    pub fn collider(&self, handle: ColliderHandle) -> Option<&Collider<Shape<F>, F>> {
+        self.collider_index(handle)
+            .map(|index| &self.colliders[index])
+    }
+
+    /// Returns the collider of a handle, if it wasn't removed.
+    pub fn collider_mut(&mut self, handle: ColliderHandle) -> Option<&mut Collider<Shape<F>, F>> {
+        let index = self.collider_index(handle)?;
+        Some(&mut self.colliders[index])
+    }
+
+    /// Returns the handle of the joint with the given index.
+    ///
+    /// # Remarks
+    /// Handles keep referring to the same joint as others are removed, and stop resolving
+    /// once it's removed itself, or along with its body, even after its slot is reused.
+    pub fn joint_handle(&mut self, index: usize) -> JointHandle {
+        assert!(index < self.joints.len(), "joint index out of bounds");
+        self.joint_handles.handle(index)
+    }
+
+    /// Returns the current index of the joint of a handle, if it wasn't removed.
+    pub fn joint_index(&self, handle: JointHandle) -> Option<usize> {
+        self.joint_handles.index(handle, self.joints.len())
+    }
+
+    /// Returns the joint of a handle, if it wasn't removed.
+    pub fn joint(&self, handle: JointHandle) -> Option<&Joint<F>> {
+        self.joint_index(handle).map(|index| &self.joints[index])
+    }
+
+    /// Returns the joint of a handle, if it wasn't removed.
+    pub fn joint_mut(&mut self, handle: JointHandle) -> Option<&mut Joint<F>> {
+        let index = self.joint_index(handle)?;
+        Some(&mut self.joints[index])
+    }
+
+    /// Returns true if the colliders with the given indices are allowed to collide,
+    /// according to their collision groups and the collision predicate of the world.
+    pub fn can_collide(&self, one: usize, two: usize) -> bool {
+        let (first, second) = (&self.colliders[one], &self.colliders[two]);
+        first.interacts_with(second.group, second.mask)
+            && self
+                .collision_predicate
+                .as_ref()
+                .is_none_or(|predicate| predicate(one, first, two, second))
+    }
+
+    /// Casts a ray against the colliders and scenery planes passing the filter,
+    /// returning the closest hit before the given maximum time of impact, if any.
+    pub fn raycast(&self, ray: &Ray<F>, max_toi: F, filter: &QueryFilter<F>) -> Option<RayHit<F>> {
+        self.query_view().raycast(ray, max_toi, filter)
+    }
+
+    /// Casts a ray against the colliders and scenery planes passing the filter,
+    /// returning every hit before the given maximum time of impact, closest first.
+    ///
+    /// # Remarks
+    /// Each collider is hit at most once, where the ray enters it.
+    pub fn raycast_all(&self, ray: &Ray<F>, max_toi: F, filter: &QueryFilter<F>) -> Vec<RayHit<F>> {
+        self.query_view().raycast_all(ray, max_toi, filter)
+    }
+
+    /// Sweeps a convex shape, placed with the given transform, along the given direction
+    /// against the colliders and scenery planes passing the filter, returning the first hit
+    /// before the given maximum distance, if any.
+    ///
+    /// # Remarks
+    /// Shapes overlapping something from the start hit it at distance `0`.
+    pub fn shape_cast<S: SupportMap<F>>(
+        &self,
+        shape: &S,
+        transform: &Matrix4<F>,
+        direction: &Vector3<F>,
+        max_distance: F,
+        filter: &QueryFilter<F>,
+    ) -> Option<ShapeCastHit<F>> {
+        self.query_view()
+            .shape_cast(shape, transform, direction, max_distance, filter)
+    }
+
+    /// Returns the fraction of the motion of a body with continuous collision detection
+    /// it can travel in the given duration without sinking into anything deeper than
+    /// `CONTINUOUS_COLLISION_DEPTH`. Other bodies always travel their whole motion.
+    fn motion_fraction(&self, index: usize, duration: F) -> F {
+        let body = &self.bodies[index];
+        let travel = body.velocity.magnitude() * duration;
+        if !body.continuous_collision
+            || !body.has_finite_mass()
+            || !body.is_awake
+            || travel <= num_traits::zero()
+        {
+            return num_traits::one();
+        }
+
+        let direction = body.velocity.normalize();
+        let depth = math::real::<F>(CONTINUOUS_COLLISION_DEPTH);
+        let allowed = self
+            .colliders
+            .iter()
+            .enumerate()
+            .filter(|(_, collider)| collider.body == index && !collider.sensor)
+            .flat_map(|(moving, collider)| {
+                let can_collide = |other: usize, collider: &Collider<Shape<F>, F>| {
+                    !collider.sensor && self.can_collide(moving, other)
+                };
+                let mut filter = QueryFilter::new();
+                filter.exclude_body = Some(index);
+                filter.predicate = Some(&can_collide);
+                let mut hits = Vec::new();
+                self.query_view().sweep(
+                    &collider.shape,
+                    collider.transform(),
+                    &direction,
+                    travel,
+                    &filter,
+                    &mut hits,
+                );
+                hits
+            })
+            // Shapes overlapping from the start are left to the contact solver, and hits the
+            // body only grazes, or moves away from, never sink it any deeper.
+            .filter_map(|hit| {
+                let approach = -hit.normal.dot_product(&direction);
+                if hit.distance > num_traits::zero() && approach > F::epsilon().sqrt() {
+                    Some(hit.distance + depth / approach)
+                } else {
+                    None
+                }
+            })
+            .fold(travel, |allowed, distance| allowed.min(distance));
+        (allowed / travel).max(F::zero()).min(F::one())
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the given
+    /// bounding box, in ascending order.
+    pub fn intersections_with_aabb(&self, aabb: &Aabb<F>, filter: &QueryFilter<F>) -> Vec<usize> {
+        self.query_view().intersections_with_aabb(aabb, filter)
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the sphere
+    /// with the given center and radius, in ascending order.
+    pub fn intersections_with_sphere(
+        &self,
+        center: &Vector3<F>,
+        radius: F,
+        filter: &QueryFilter<F>,
+    ) -> Vec<usize> {
+        self.query_view()
+            .intersections_with_sphere(center, radius, filter)
+    }
+
+    /// Returns the indices of the colliders passing the filter that overlap the convex shape
+    /// placed with the given transform, in ascending order.
+    pub fn intersections_with_shape<S: SupportMap<F>>(
+        &self,
+        shape: &S,
+        transform: &Matrix4<F>,
+        filter: &QueryFilter<F>,
+    ) -> Vec<usize> {
+        self.query_view()
+            .intersections_with_shape(shape, transform, filter)
+    }
+
+    /// Projects a point onto the closest collider or scenery plane passing the filter, if any.
+    ///
+    /// # Remarks
+    /// Points inside something are at distance zero from it, and are projected onto its
+    /// closest surface.
+    pub fn project_point(
+        &self,
+        point: &Vector3<F>,
+        filter: &QueryFilter<F>,
+    ) -> Option<PointProjection<F>> {
+        self.query_view().project_point(point, filter)
+    }
+
+    /// Computes whether the colliders with the given indices overlap and, if they don't,
+    /// their distance and closest points, as placed the last time the colliders were updated.
+    pub fn distance(&self, one: usize, two: usize) -> GjkResult<F> {
+        self.query_view().distance(one, two)
+    }
+
+    /// Pushes the bodies within `radius` of the center away from it, returning the indices of
+    /// the bodies pushed, in ascending order.
+    ///
+    /// # Remarks
+    /// Every body in reach gets a single impulse, at the point of its colliders closest to the
+    /// center, so bodies hit off center start spinning. The impulse fades with the distance to
+    /// that point, and bodies with a collider engulfing the center are pushed at their center
+    /// at full strength. Sleeping bodies are woken up by the impulse, or left alone if `wake`
+    /// is false. Sensors and bodies with infinite mass are never pushed.
+    pub fn apply_explosion(
+        &mut self,
+        center: &Vector3<F>,
+        radius: F,
+        impulse: F,
+        falloff: Falloff,
+        wake: bool,
+    ) -> Vec<usize> {
+        let extent = Vector3::new(radius, radius, radius);
+        let bounds = Aabb::new(center.vector_sub(&extent), center.vector_add(&extent));
+        let mut candidates = self.broad_phase.intersecting(&bounds);
+        candidates.sort_unstable();
+
+        // Find the point of every body closest to the center, over all its colliders.
+        let mut closest: BTreeMap<usize, (Vector3<F>, F, Vector3<F>)> = BTreeMap::new();
+        for index in candidates {
+            let collider = &self.colliders[index];
+            let body = &self.bodies[collider.body];
+            if collider.sensor || !body.has_finite_mass() || (!wake && !body.is_awake) {
+                continue;
+            }
+
+            let hit = match closest_point(center, &collider.shape, collider.transform()) {
+                Some(point) => {
+                    let offset = point.vector_sub(center);
+                    (point, offset.magnitude(), offset.normalize())
+                }
+                None => (
+                    body.position,
+                    F::zero(),
+                    body.position.vector_sub(center).normalize(),
+                ),
+            };
+            match closest.get(&collider.body) {
+                Some((_, distance, _)) if *distance <= hit.1 => {}
+                _ => {
+                    closest.insert(collider.body, hit);
+                }
+            }
+        }
+
+        let mut pushed = Vec::new();
+        for (index, (point, distance, direction)) in closest {
+            let strength = impulse * falloff.scale(distance, radius);
+            if strength <= F::zero() || direction.squared_magnitude() <= F::zero() {
+                continue;
+            }
+
+            self.bodies[index].apply_impulse_at_point(&direction.scalar_mul(strength), &point);
+            pushed.push(index);
+        }
+        pushed
+    }
+
+    /// Returns a view of the colliders and scenery planes of the world to query them,
+    /// as placed the last time the colliders were updated.
+    fn query_view(&self) -> QueryView<'_, F> {
+        QueryView {
+            colliders: &self.colliders,
+            broad_phase: &self.broad_phase,
+            planes: &self.planes,
+        }
+    }
+}
+
+impl<F: num_traits::Float + Parallel> World<F> {
+    /// Places the colliders of the world with the current transforms of their bodies,
+    /// and updates the acceleration structure used by the broad phase and the queries.
+    ///
+    /// # Remarks
+    /// This is done when detecting collisions, so it only needs to be called before
+    /// querying a world whose bodies or colliders were changed since then.
+    ///
+    /// Colliders of static bodies are only placed the first time, so a static body moved by
+    /// hand should be made kinematic for the step it moves in.
+    pub fn update_colliders(&mut self) {
+        let (bodies, broad_phase) = (&self.bodies, &self.broad_phase);
+        let aabbs = map_chunks_mut(
+            &mut self.colliders,
+            MIN_COLLIDERS_PER_THREAD,
+            |first, colliders| {
+                colliders
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(offset, collider)| {
+                        if bodies[collider.body].body_type == BodyType::Static
+                            && broad_phase.fat_aabb(first + offset).is_some()
+                        {
+                            return None;
+                        }
+                        collider.calculate_internals(bodies);
+                        let aabb = collider.shape.aabb(collider.transform());
+                        Some(aabb.loosened(collider.contact_margin))
+                    })
+                    .collect::<Vec<_>>()
+            },
+        );
+        for (index, aabb) in aabbs.into_iter().flatten().enumerate() {
+            if let Some(aabb) = aabb {
+                self.broad_phase.update(index, &aabb);
+            }
+        }
+    }
+
+    /// Generates the contacts between the colliders of the world, and between them and
+    /// the scenery planes, and updates the contact manifolds with them.
+    ///
+    /// # Remarks
+    /// Pairs of colliders attached to the same body, or to bodies that can't move,
+    /// are never tested, and neither are the ones filtered out by `can_collide`.
+    /// Pairs with a sensor are only tested for overlap, without generating contacts.
+    pub fn detect_collisions(&mut self) {
+        let start = Instant::now();
+        self.update_colliders();
+
+        let mut pairs = Vec::new();
+        self.broad_phase.potential_pairs_parallel(&mut pairs);
+        self.stats.pairs = pairs.len();
+        let broad_phase = Instant::now();
+        self.stats.broad_phase = broad_phase - start;
+
+        self.contacts.reset();
+        self.touching.clear();
+        self.overlapping.clear();
+
+        // Pairs are filtered on this thread, as the collision predicate may not be shareable.
+        pairs.retain(|(first, second)| {
+            let (one, two) = (&self.colliders[*first], &self.colliders[*second]);
+            one.body != two.body
+                && (self.bodies[one.body].has_finite_mass()
+                    || self.bodies[two.body].has_finite_mass())
+                && self.can_collide(*first, *second)
+        });
+
+        let (colliders, max_contacts) = (&self.colliders, self.contacts.max_contacts);
+        let generated = map_chunks(&pairs, MIN_PAIRS_PER_THREAD, |_, pairs| {
+            let mut data = CollisionData::new(max_contacts);
+            let mut overlap = CollisionData::new(1);
+            let counts: Vec<usize> = pairs
+                .iter()
+                .map(|(first, second)| {
+                    let (one, two) = (&colliders[*first], &colliders[*second]);
+                    if one.sensor || two.sensor {
+                        overlap.reset();
+                        collide(one, two, &mut overlap);
+                        return overlap.contacts.len();
+                    }
+                    data.set_materials(&one.material, &two.material);
+                    data.margin = one.contact_margin + two.contact_margin;
+                    let count = data.contacts.len();
+                    collide(one, two, &mut data);
+                    for contact in data.contacts[count..].iter_mut() {
+                        contact.colliders = if contact.bodies.0 == one.body {
+                            (*first, *second)
+                        } else {
+                            (*second, *first)
+                        };
+                    }
+                    data.contacts.len() - count
+                })
+                .collect();
+            (data.contacts, counts)
+        });
+        let (contacts, counts) = merge(generated);
+        let mut contacts = contacts.into_iter();
+        let passing = std::mem::take(&mut self.passing);
+        for ((first, second), count) in pairs.into_iter().zip(counts) {
+            let (one, two) = (&self.colliders[first], &self.colliders[second]);
+            if one.sensor || two.sensor {
+                if count > 0 {
+                    self.overlapping.insert((first, second));
+                }
+                continue;
+            }
+
+            // Pairs going through one-way colliders are let through until they separate.
+            let going_through = passing.contains(&(first, second));
+            let mut blocked = 0;
+            let pair = contacts.by_ref().take(count).filter(|contact| {
+                let blocks = !going_through && blocks_one_way(one, two, contact);
+                blocked += blocks as usize;
+                blocks
+            });
+            if keep_modified(
+                &mut self.contacts,
+                pair,
+                self.contact_modifier.as_ref(),
+                (first, one),
+                Some((second, two)),
+            ) {
+                self.touching.insert((first, Some(second)));
+            }
+            if count > 0 && blocked == 0 {
+                self.passing.insert((first, second));
+            }
+        }
+
+        let (bodies, planes) = (&self.bodies, &self.planes);
+        let scenery_material = &self.scenery_material;
+        let generated = map_chunks(colliders, MIN_COLLIDERS_PER_THREAD, |first, colliders| {
+            let mut data = CollisionData::new(max_contacts);
+            let counts: Vec<usize> = colliders
+                .iter()
+                .enumerate()
+                .map(|(index, collider)| {
+                    if collider.sensor || !bodies[collider.body].has_finite_mass() {
+                        return 0;
+                    }
+                    data.set_materials(&collider.material, scenery_material);
+                    data.margin = collider.contact_margin;
+                    let count = data.contacts.len();
+                    for (plane_index, plane) in planes.iter().enumerate() {
+                        let start = data.contacts.len();
+                        collide_with_half_space(collider, plane, &mut data);
+                        for contact in data.contacts[start..].iter_mut() {
+                            contact.colliders = (first + index, plane_index);
+                        }
+                    }
+                    data.contacts.len() - count
+                })
+                .collect();
+            (data.contacts, counts)
+        });
+        let (contacts, counts) = merge(generated);
+        let mut contacts = contacts.into_iter();
+        for (index, count) in counts.into_iter().enumerate() {
+            if keep_modified(
+                &mut self.contacts,
+                contacts.by_ref().take(count),
+                self.contact_modifier.as_ref(),
+                (index, &self.colliders[index]),
+                None,
+            ) {
+                self.touching.insert((index, None));
+            }
+        }
+
+        self.manifolds.update(&self.contacts.contacts, &self.bodies);
+        self.stats.contacts = self.contacts.contacts.len();
+        self.stats.manifolds = self.manifolds.len();
+        self.stats.narrow_phase = broad_phase.elapsed();
+    }
+
+    /// Processes all the physics for the world.
+    ///
+    /// # Remarks
+    /// The velocities of the bodies are integrated first, then corrected by the contact
+    /// solver, and finally used to move the bodies. With substeps, collisions are detected
+    /// once, and the rest is repeated for every substep. With diagnostics enabled, the
+    /// bodies are measured before and after the step for the conservation monitors.
+    pub fn run_physics(&mut self, duration: F) {
+        let start = Instant::now();
+        let before = if self.config.diagnostics {
+            Some(self.measure_diagnostics())
+        } else {
+            None
+        };
+        self.previous_poses.clear();
+        self.previous_poses.extend(
+            self.bodies
+                .iter()
+                .map(|body| (body.position, body.orientation)),
+        );
+        let substeps = self.config.substeps.max(1);
+        let step = duration / math::real(substeps as f64);
+        let power = self.power();
+        let touched = std::mem::take(&mut self.touching);
+        let overlapped = std::mem::take(&mut self.overlapping);
+        self.stats.integration = Default::default();
+        self.stats.solver = Default::default();
+        for substep in 0..substeps {
+            // First apply gravity and the force generators.
+            self.apply_gravity();
+            self.registry.update_forces(&mut self.bodies, step);
+
+            // Then integrate the velocities of the objects.
+            let integration = Instant::now();
+            for body in self.bodies.iter_mut() {
+                body.integrate_velocity_with(
+                    step,
+                    self.config.linear_damping,
+                    self.config.angular_damping,
+                    power,
+                );
+            }
+            self.stats.integration += integration.elapsed();
+
+            // Resolve the contacts and joints, detected in the first substep and refreshed in the rest.
+            if substep == 0 {
+                self.detect_collisions();
+            } else {
+                for manifold in self.manifolds.iter_mut() {
+                    manifold.refresh(&self.bodies);
+                }
+            }
+            let solver = Instant::now();
+            self.config.solver.solve(
+                &mut self.manifolds,
+                &mut self.joints,
+                &mut self.bodies,
+                step,
+            );
+            self.stats.solver += solver.elapsed();
+
+            // Move the objects with the corrected velocities, where fast bodies with
+            // continuous collision detection stop at the first thing they hit.
+            let integration = Instant::now();
+            let fractions: Vec<F> = (0..self.bodies.len())
+                .map(|body| self.motion_fraction(body, step))
+                .collect();
+            for (body, fraction) in self.bodies.iter_mut().zip(fractions) {
+                body.integrate_position_with(step * fraction, power);
+            }
+            self.config
+                .solver
+                .correct_positions(&self.manifolds, &mut self.bodies);
+            self.stats.integration += integration.elapsed();
+        }
+        self.stats.iterations = self.config.solver.iterations * substeps;
+        self.report_contacts(&touched);
+        self.report_sensors(&overlapped);
+
+        // Then move the soft bodies and fluids, colliding with the rigid bodies in their new places.
+        if !self.soft_bodies.is_empty() || !self.fluids.is_empty() {
+            self.update_colliders();
+            self.run_soft_bodies(duration);
+            self.run_fluids(duration);
+        }
+
+        // Break the joints that couldn't withstand the load.
+        self.joint_breaks.clear();
+        for (index, joint) in self.joints.iter_mut().enumerate() {
+            if joint.enabled && joint.is_overloaded() {
+                joint.enabled = false;
+                self.joint_breaks.push(JointBreak {
+                    joint: index,
+                    force: joint.force(),
+                    torque: joint.torque(),
+                });
+            }
+        }
+
+        // Finally put to sleep the bodies that came to rest, along with the ones touching or joined to them.
+        let islands = self.islands();
+        islands.update_sleep(&mut self.bodies);
+        self.stats.islands = islands.len();
+
+        // Check the conservation of energy and momentum, when diagnostics are enabled.
+        self.diagnostics = before.map(|before| {
+            let after = self.measure_diagnostics();
+            for monitor in self.conservation_monitors.iter_mut() {
+                monitor.check(&before, &after);
+            }
+            after
+        });
+        self.stats.total = start.elapsed();
+    }
+}
+
+impl<F: num_traits::Float> World<F> {
+    /// Returns the islands of bodies touching or joined together by an enabled joint.
+    fn islands(&self) -> Islands {
+        let contacts = self.manifolds.iter().map(|manifold| manifold.bodies);
+        let joints = self
+            .joints
+            .iter()
+            .filter(|joint| joint.enabled)
+            .map(|joint| joint.bodies);
+        let pairs: Vec<(usize, usize)> = contacts
+            .chain(joints)
+            .filter_map(|(one, two)| Some((one, two?)))
+            .collect();
+        Islands::build(&self.bodies, &pairs)
+    }
+
+    /// Returns the bodies of the world, along with the joints and contacts of the last step
+    /// linking them and the islands they form, to inspect why bodies don't fall asleep or
+    /// which constraints are fighting each other.
+    pub fn export_constraint_graph(&self) -> ConstraintGraph<F> {
+        let islands = self.islands();
+        let bodies = self
+            .bodies
+            .iter()
+            .enumerate()
+            .map(|(index, body)| GraphBody {
+                body: index,
+                body_type: body.body_type,
+                is_awake: body.is_awake,
+                can_sleep: body.can_sleep,
+                motion: body.motion,
+                sleep_epsilon: body.sleep_epsilon,
+                island: islands.island_of(index),
+            })
+            .collect();
+        let joints = self
+            .joints
+            .iter()
+            .enumerate()
+            .map(|(index, joint)| GraphJoint {
+                joint: index,
+                bodies: joint.bodies,
+                kind: String::from(joint_name(&joint.kind)),
+                enabled: joint.enabled,
+                force: joint.force().magnitude(),
+                torque: joint.torque().magnitude(),
+            })
+            .collect();
+        let contacts = self
+            .manifolds
+            .iter()
+            .map(|manifold| GraphContact {
+                bodies: manifold.bodies,
+                points: manifold.len(),
+                penetration: manifold
+                    .points
+                    .iter()
+                    .map(|point| point.contact.penetration)
+                    .fold(F::neg_infinity(), F::max),
+                normal_impulse: manifold
+                    .points
+                    .iter()
+                    .fold(F::zero(), |total, point| total + point.normal_impulse),
+            })
+            .collect();
+        ConstraintGraph {
+            bodies,
+            joints,
+            contacts,
+            islands: islands.iter().map(|island| island.to_vec()).collect(),
+        }
+    }
+
+    /// Returns the function raising the damping coefficients to the duration of a frame,
+    /// computed in software for deterministic worlds.
+    fn power(&self) -> Power<F> {
+        if self.config.deterministic {
+            math::soft::powf
+        } else {
+            F::powf
+        }
+    }
+
+    /// Pulls the awake bodies with finite mass with the gravity at their position, or their own.
+    fn apply_gravity(&mut self) {
+        for (index, body) in self.bodies.iter_mut().enumerate() {
+            if !body.has_finite_mass() || !body.is_awake {
+                continue;
+            }
+            let acceleration = match self.gravity_overrides.get(&index) {
+                Some(source) => source.acceleration_at(&body.position),
+                None => self.gravity.acceleration_at(&body.position),
+            };
+            let scale = body.mass() * body.gravity_scale;
+            if scale != F::zero() && acceleration.squared_magnitude() > F::zero() {
+                body.add_force(&acceleration.scalar_mul(scale));
+            }
+        }
+    }
+
+    /// Processes the physics of the soft bodies, pushing their particles out of the colliders
+    /// and scenery planes, and the rigid bodies back with the momentum given to the particles.
+    fn run_soft_bodies(&mut self, duration: F) {
+        let mut data = CollisionData::new(MAX_MANIFOLD_POINTS);
+        let power = self.power();
+        for soft_body in self.soft_bodies.iter_mut() {
+            soft_body.integrate(duration, power);
+            soft_body.project_constraints();
+
+            for particle in soft_body.particles.iter_mut() {
+                if !particle.has_finite_mass() {
+                    continue;
+                }
+                collide_particle(
+                    &self.colliders,
+                    &self.broad_phase,
+                    &self.planes,
+                    particle,
+                    soft_body.radius,
+                    &mut data,
+                );
+
+                for contact in data.contacts.iter() {
+                    let push = contact.contact_normal.scalar_mul(contact.penetration);
+                    particle.position.inplace_vector_add(&push);
+                    if let Some(body) = contact.bodies.1 {
+                        let body = &mut self.bodies[body];
+                        if body.has_finite_mass() {
+                            let impulse = push.scalar_mul(-particle.mass() / duration);
+                            body.apply_impulse_at_point(&impulse, &contact.contact_point);
+                        }
+                    }
+                }
+            }
+
+            soft_body.update_velocities(duration);
+        }
+    }
+
+    /// Processes the physics of the fluids, pushing their particles out of the colliders and
+    /// scenery planes, and the rigid bodies back if the fluid is coupled both ways.
+    ///
+    /// # Remarks
+    /// Particles hitting a body lose the velocity they had towards it, and the body takes the
+    /// momentum the particles lost.
+    fn run_fluids(&mut self, duration: F) {
+        let mut data = CollisionData::new(MAX_MANIFOLD_POINTS);
+        let zero = F::zero();
+        let bodies = &mut self.bodies;
+        for fluid in self.fluids.iter_mut() {
+            let substeps = fluid.substeps.max(1);
+            let step = duration / math::real(substeps as f64);
+            for _ in 0..substeps {
+                fluid.step(step);
+
+                for particle in fluid.particles.iter_mut() {
+                    if !particle.has_finite_mass() {
+                        continue;
+                    }
+                    collide_particle(
+                        &self.colliders,
+                        &self.broad_phase,
+                        &self.planes,
+                        particle,
+                        fluid.radius,
+                        &mut data,
+                    );
+
+                    for contact in data.contacts.iter() {
+                        let normal = contact.contact_normal;
+                        particle
+                            .position
+                            .inplace_vector_add(&normal.scalar_mul(contact.penetration));
+
+                        let body = contact.bodies.1.map(|body| &mut bodies[body]);
+                        let surface = body
+                            .as_ref()
+                            .map(|body| body.velocity_at_point(&contact.contact_point))
+                            .unwrap_or_else(Vector3::origin);
+                        let speed = particle.velocity.vector_sub(&surface).dot_product(&normal);
+                        if speed >= zero {
+                            continue;
+                        }
+                        let change = normal.scalar_mul(-speed);
+                        particle.velocity.inplace_vector_add(&change);
+
+                        if let Some(body) = body {
+                            if fluid.coupling == FluidCoupling::TwoWay && body.has_finite_mass() {
+                                let impulse = change.scalar_mul(-particle.mass());
+                                body.apply_impulse_at_point(&impulse, &contact.contact_point);
+                            }
+                        }
+                    }
+                }
+            }
+            fluid.clear_accumulators();
+        }
+    }
+
+    /// Returns the contact events reported since the last call, removing them from the world.
+    ///
+    /// # Remarks
+    /// Every step reports the pairs of colliders that started, kept or stopped touching,
+    /// sorted by pair of colliders. Events accumulate across steps until drained.
+    pub fn drain_contact_events(&mut self) -> std::vec::Drain<'_, ContactEvent<F>> {
+        self.contact_events.drain(..)
+    }
+
+    /// Returns the sensor events reported since the last call, removing them from the world.
+    ///
+    /// # Remarks
+    /// Every step reports the pairs of colliders that entered or exited a sensor,
+    /// sorted by pair of colliders. Events accumulate across steps until drained.
+    pub fn drain_sensor_events(&mut self) -> std::vec::Drain<'_, SensorEvent> {
+        self.sensor_events.drain(..)
+    }
+
+    /// Reports the sensor events of the pairs of colliders overlapping before or after the step.
+    fn report_sensors(&mut self, overlapped: &BTreeSet<(usize, usize)>) {
+        for pair in overlapped.symmetric_difference(&self.overlapping) {
+            let kind = if overlapped.contains(pair) {
+                SensorEventKind::Exited
+            } else {
+                SensorEventKind::Entered
+            };
+            self.sensor_events.push(SensorEvent {
+                kind,
+                colliders: *pair,
+                bodies: (self.colliders[pair.0].body, self.colliders[pair.1].body),
+            });
+        }
+    }
+
+    /// Reports the contact events of the pairs of colliders touching before and after the step.
+    fn report_contacts(&mut self, touched: &BTreeSet<(usize, Option<usize>)>) {
+        for pair in touched.union(&self.touching) {
+            let kind = match (touched.contains(pair), self.touching.contains(pair)) {
+                (false, _) => ContactEventKind::Started,
+                (true, true) => ContactEventKind::Persisted,
+                (true, false) => ContactEventKind::Stopped,
+            };
+            let bodies = (
+                self.colliders[pair.0].body,
+                pair.1.map(|collider| self.colliders[collider].body),
+            );
+            let mut event = ContactEvent::new(kind, *pair, bodies);
+            if kind != ContactEventKind::Stopped {
+                if let Some(manifold) = self.manifolds.get(bodies) {
+                    event.with_manifold(manifold);
+                }
+            }
+            self.contact_events.push(event);
+        }
+    }
+}
+
+/// Contacts generated on a thread, along with the number of contacts every pair, or
+/// collider, generated.
+type Generated<F> = (Vec<Contact<F>>, Vec<usize>);
+
+/// Joins the contacts generated on every thread, keeping their order.
+fn merge<F: num_traits::Float>(generated: Vec<Generated<F>>) -> Generated<F> {
+    let mut merged: Generated<F> = (Vec::new(), Vec::new());
+    for (contacts, counts) in generated {
+        merged.0.extend(contacts);
+        merged.1.extend(counts);
+    }
+    merged
+}
+
+/// Keeps the contacts generated between a collider and another one, or the scenery planes,
+/// once the contact modifier has gone through them, returning true if any of the ones kept
+/// is touching, rather than speculative, or about to.
+fn keep_modified<F: num_traits::Float>(
+    data: &mut CollisionData<F>,
+    contacts: impl Iterator<Item = Contact<F>>,
+    modifier: Option<&ContactModifier<F>>,
+    (index, collider): (usize, &Collider<Shape<F>, F>),
+    other: Option<(usize, &Collider<Shape<F>, F>)>,
+) -> bool {
+    let start = data.contacts.len();
+    let bodies = (collider.body, other.map(|(_, other)| other.body));
+    let contacts = contacts.map(|mut contact| {
+        // Friction drives the first body towards the surface of the second one.
+        let (one, two) = if contact.bodies == bodies {
+            (Some(collider), other.map(|(_, other)| other))
+        } else {
+            (other.map(|(_, other)| other), Some(collider))
+        };
+        let point = contact.contact_point;
+        let mut velocity = Vector3::origin();
+        if let Some(one) = one {
+            velocity.inplace_vector_sub(&one.surface_velocity_at(&point));
+        }
+        if let Some(two) = two {
+            velocity.inplace_vector_add(&two.surface_velocity_at(&point));
+        }
+        let normal = contact.contact_normal;
+        contact.surface_velocity =
+            velocity.vector_sub(&normal.scalar_mul(velocity.dot_product(&normal)));
+        contact
+    });
+    match modifier {
+        Some(modifier) => {
+            let mut contacts = contacts.collect();
+            modifier(index, collider, other, &mut contacts);
+            data.keep(contacts.into_iter());
+        }
+        None => {
+            data.keep(contacts);
+        }
+    }
+    data.contacts[start..]
+        .iter()
+        .any(|contact| contact.penetration > -math::real::<F>(TOUCHING_DISTANCE))
+}
+
+/// Returns true if the colliders of a pair block each other at the given contact,
+/// always the case unless one of them is one-way and the contact opposes its solid side.
+fn blocks_one_way<F: num_traits::Float>(
+    one: &Collider<Shape<F>, F>,
+    two: &Collider<Shape<F>, F>,
+    contact: &Contact<F>,
+) -> bool {
+    // The normal pushes the first body of the contact away from the second one.
+    let (pushed, pushing) = if contact.bodies.0 == one.body {
+        (one, two)
+    } else {
+        (two, one)
+    };
+    let normal = contact.contact_normal;
+    pushing.blocks(&normal) && pushed.blocks(&normal.scalar_mul(-F::one()))
+}
+
+/// Collides a particle, as a sphere with the given radius, with the colliders and scenery planes
+/// of a world, replacing the contacts in the collision data.
+fn collide_particle<F: num_traits::Float>(
+    colliders: &[Collider<Shape<F>, F>],
+    broad_phase: &DynamicBvh<F>,
+    planes: &[Plane<F>],
+    particle: &Particle<F>,
+    radius: F,
+    data: &mut CollisionData<F>,
+) {
+    let transform =
+        Matrix4::from_orientation_and_position(&Quaternion::identity(), &particle.position);
+    let sphere = Collider::placed(usize::MAX, Shape::Sphere(Sphere::new(radius)), transform);
+    let extent = Vector3::new(radius, radius, radius);
+    let bounds = Aabb::new(
+        particle.position.vector_sub(&extent),
+        particle.position.vector_add(&extent),
+    );
+
+    data.reset();
+    for index in broad_phase.intersecting(&bounds) {
+        if !colliders[index].sensor {
+            collide(&sphere, &colliders[index], data);
+        }
+    }
+    for plane in planes.iter() {
+        collide_with_half_space(&sphere, plane, data);
+    }
+}
@@ -0,0 +1,1938 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::aabb::Aabb;
+use crate::ball_joint::BallJoint;
+use crate::collider::Collider;
+use crate::event::{ContactEventKind, SensorEventKind};
+use crate::fluid::{Fluid, FluidCoupling};
+use crate::force::Gravity;
+use crate::gjk::GjkResult;
+use crate::gravity::{GravityField, GravityRegion, GravitySource};
+use crate::handle::BodyHandle;
+use crate::joint::{Joint, JointKind};
+use crate::material::{CombineRule, PhysicsMaterial};
+use crate::narrow_phase::{collide, collide_with_half_space, CollisionData};
+use crate::plane::Plane;
+use crate::query::{self, shape_distance, QueryFilter};
+use crate::ray::Ray;
+use crate::rigid_body::{AxisLocks, BodyType, RigidBody};
+use crate::shape::{Compound, Cuboid, Shape, Sphere};
+use crate::soft_body::SoftBody;
+use crate::stats::WorldStats;
+use crate::trimesh::TriMesh;
+use crate::world::*;
+use math::{Matrix3, Matrix4, Quaternion, Vector3};
+
+#[test]
+fn damping() {
+    let mut world = World::<f64>::new(WorldConfig {
+        linear_damping: 0.5,
+        angular_damping: 0.5,
+        ..WorldConfig::default()
+    });
+    let mut body = RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity());
+    body.velocity = Vector3::new(4.0, 0.0, 0.0);
+    body.rotation = Vector3::new(4.0, 0.0, 0.0);
+    let damped = world.add_body(body);
+    let damped = world.body_index(damped).unwrap();
+    body.angular_damping = Some(1.0);
+    let prop = world.add_body(body);
+    let prop = world.body_index(prop).unwrap();
+
+    world.start_frame();
+    world.run_physics(1.0);
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), world.bodies[damped].velocity);
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), world.bodies[damped].rotation);
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), world.bodies[prop].velocity);
+    assert_eq!(Vector3::new(4.0, 0.0, 0.0), world.bodies[prop].rotation);
+}
+
+#[test]
+fn run_physics() {
+    let mut world = World::<f64>::default();
+    let falling = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    let falling = world.body_index(falling).unwrap();
+    let resting = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    let resting = world.body_index(resting).unwrap();
+    world.registry.add(
+        falling,
+        Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))),
+    );
+
+    for _ in 0..100 {
+        world.start_frame();
+        world.run_physics(0.1);
+    }
+
+    assert!(world.bodies[falling].position.y < 0.0);
+    assert!(world.bodies[falling].is_awake);
+    assert!(!world.bodies[resting].is_awake);
+}
+
+#[test]
+fn resting_contact() {
+    let mut world = World::<f64>::default();
+    let sphere = Sphere::new(0.5);
+    let ball = world.add_body(RigidBody::new(
+        Vector3::new(0.0, 2.0, 0.0),
+        1.0,
+        &sphere.inertia_tensor(1.0),
+    ));
+    let ball = world.body_index(ball).unwrap();
+    world.add_collider(Collider::new(ball, Shape::Sphere(sphere)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    world
+        .registry
+        .add(ball, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+
+    for _ in 0..300 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+
+    // The ball lands on the plane and comes to rest on it.
+    let position = world.bodies[ball].position;
+    assert!((position.y - 0.5).abs() < 0.01);
+    assert!(world.bodies[ball].velocity.magnitude() < 0.1);
+    assert_eq!(1, world.manifolds.len());
+    assert!(!world.bodies[ball].is_awake);
+}
+
+#[test]
+fn contact_events() {
+    let mut world = World::<f64>::default();
+    let sphere = Sphere::new(0.5);
+    let ball = world.add_body(RigidBody::new(
+        Vector3::new(0.0, 0.6, 0.0),
+        1.0,
+        &sphere.inertia_tensor(1.0),
+    ));
+    let ball = world.body_index(ball).unwrap();
+    world.add_collider(Collider::new(ball, Shape::Sphere(sphere)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    world
+        .registry
+        .add(ball, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+
+    let step = |world: &mut World| -> Vec<_> {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+        world.drain_contact_events().collect()
+    };
+
+    // Nothing is reported before the ball lands.
+    assert!(step(&mut world).is_empty());
+    let events = (0..60)
+        .map(|_| step(&mut world))
+        .find(|events| !events.is_empty())
+        .unwrap();
+    assert_eq!(1, events.len());
+    assert_eq!(ContactEventKind::Started, events[0].kind);
+    assert_eq!((0, None), events[0].colliders);
+    assert_eq!((ball, None), events[0].bodies);
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), events[0].normal);
+    assert!(events[0].normal_impulse > 0.0);
+
+    // Resting on the plane, the contact persists and holds the ball up against gravity.
+    for _ in 0..30 {
+        step(&mut world);
+    }
+    let events = step(&mut world);
+    assert_eq!(1, events.len());
+    assert_eq!(ContactEventKind::Persisted, events[0].kind);
+    assert!((events[0].normal_impulse - 10.0 / 60.0).abs() < 0.05);
+
+    // Launched up, the ball leaves the plane.
+    world.bodies[ball].velocity = Vector3::new(0.0, 10.0, 0.0);
+    let stopped = (0..3)
+        .flat_map(|_| step(&mut world))
+        .find(|event| event.kind == ContactEventKind::Stopped)
+        .unwrap();
+    assert_eq!(0.0, stopped.normal_impulse);
+    assert!(step(&mut world).is_empty());
+}
+
+#[test]
+fn sensors() {
+    let mut world = World::<f64>::default();
+    let mut checkpoint = RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity());
+    checkpoint.set_infinite_mass();
+    let checkpoint = world.add_body(checkpoint);
+    let checkpoint = world.body_index(checkpoint).unwrap();
+    let mut volume = Collider::new(
+        checkpoint,
+        Shape::Cuboid(Cuboid::new(Vector3::new(2.0, 1.0, 2.0))),
+    );
+    volume.sensor = true;
+    let volume = world.add_collider(volume);
+    let volume = world.collider_index(volume).unwrap();
+
+    let sphere = Sphere::new(0.5);
+    let ball = world.add_body(RigidBody::new(
+        Vector3::new(0.0, 3.0, 0.0),
+        1.0,
+        &sphere.inertia_tensor(1.0),
+    ));
+    let ball = world.body_index(ball).unwrap();
+    let collider = world.add_collider(Collider::new(ball, Shape::Sphere(sphere)));
+    let collider = world.collider_index(collider).unwrap();
+    world.bodies[ball].velocity = Vector3::new(0.0, -6.0, 0.0);
+
+    // The ball falls through the sensor, reporting when it enters and exits it.
+    let mut events = Vec::new();
+    for _ in 0..60 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+        events.extend(world.drain_sensor_events());
+    }
+    assert_eq!(2, events.len());
+    assert_eq!(SensorEventKind::Entered, events[0].kind);
+    assert_eq!(SensorEventKind::Exited, events[1].kind);
+    assert_eq!((volume, collider), events[0].colliders);
+    assert_eq!((checkpoint, ball), events[1].bodies);
+
+    // Without ever colliding with it.
+    assert!(world.bodies[ball].position.y < -2.0);
+    assert!(world.drain_contact_events().next().is_none());
+}
+
+#[test]
+fn contact_modifier() {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let mut shelf = RigidBody::new(Vector3::new(0.0, 1.0, 0.0), 1.0, &Matrix3::identity());
+    shelf.set_body_type(BodyType::Static);
+    let shelf = world.add_body(shelf);
+    let shelf = world.body_index(shelf).unwrap();
+    let ghost = world.add_collider(Collider::new(
+        shelf,
+        Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 0.25, 1.0))),
+    ));
+    let ghost = world.collider_index(ghost).unwrap();
+    let sphere = Sphere::new(0.5);
+    let balls: Vec<_> = [0.0, 5.0]
+        .iter()
+        .map(|x| {
+            let ball = world.add_body(RigidBody::new(
+                Vector3::new(*x, 3.0, 0.0),
+                1.0,
+                &sphere.inertia_tensor(1.0),
+            ));
+            let ball = world.body_index(ball).unwrap();
+            world.add_collider(Collider::new(ball, Shape::Sphere(sphere)));
+            ball
+        })
+        .collect();
+
+    // Contacts with the shelf are dropped, while the ones with the planes bounce fully.
+    world.contact_modifier = Some(Box::new(move |one, _, other, contacts| match other {
+        Some((two, _)) if one == ghost || two == ghost => contacts.clear(),
+        Some(_) => {}
+        None => {
+            for contact in contacts.iter_mut() {
+                contact.restitution = 1.0;
+            }
+        }
+    }));
+    let (mut lowest, mut highest) = (3.0f64, 0.0f64);
+    for frame in 0..90 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+        lowest = lowest.min(world.bodies[balls[0]].position.y);
+        if frame > 60 {
+            highest = highest.max(world.bodies[balls[1]].position.y);
+        }
+    }
+
+    // The first ball falls through the shelf, and the second one bounces back up.
+    assert!(lowest < 0.75);
+    assert!(highest > 2.0);
+}
+
+fn block_on_slope(static_friction: f64, friction: f64) -> f64 {
+    let angle = 20f64.to_radians();
+    let normal = Vector3::new(-angle.sin(), angle.cos(), 0.0);
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+
+    let mut world = World::<f64>::default();
+    let start = normal.scalar_mul(0.5);
+    let mut body = RigidBody::new(start, 1.0, &cuboid.inertia_tensor(1.0));
+    body.orientation = Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 1.0), angle);
+    body.can_sleep = false;
+    let block = world.add_body(body);
+    let block = world.body_index(block).unwrap();
+    let material = PhysicsMaterial {
+        friction,
+        static_friction,
+        ..PhysicsMaterial::default()
+    };
+    let mut collider = Collider::new(block, Shape::Cuboid(cuboid));
+    collider.material = material;
+    world.add_collider(collider);
+    world.planes.push(Plane::new(normal, 0.0));
+    world.scenery_material = material;
+    world
+        .registry
+        .add(block, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    world.bodies[block].position.x - start.x
+}
+
+#[test]
+fn friction_on_slope() {
+    // Static friction keeps the block in place as long as it exceeds the tangent of the slope.
+    assert!(block_on_slope(0.5, 0.3).abs() < 0.01);
+
+    // Once sliding, dynamic friction only slows the block down, less so when smoother.
+    let sliding = block_on_slope(0.3, 0.3);
+    let smoother = block_on_slope(0.3, 0.2);
+    let frictionless = block_on_slope(0.0, 0.0);
+    assert!(sliding < -0.1);
+    assert!(smoother < sliding);
+    assert!(frictionless < smoother);
+}
+
+#[test]
+fn locked_axes() {
+    // A box dropped on a floor tilted around the X axis would slide along Z and tumble,
+    // unless it's locked to the XY plane.
+    let angle = 20f64.to_radians();
+    let normal = Vector3::new(0.0, angle.cos(), angle.sin());
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+
+    let mut world = World::<f64>::default();
+    let mut body = RigidBody::new(
+        Vector3::new(0.0, 1.0, 0.0),
+        1.0,
+        &cuboid.inertia_tensor(1.0),
+    );
+    body.locked_axes = AxisLocks::planar(2);
+    body.can_sleep = false;
+    let block = world.add_body(body);
+    let block = world.body_index(block).unwrap();
+    world.add_collider(Collider::new(block, Shape::Cuboid(cuboid)));
+    world.planes.push(Plane::new(normal, 0.0));
+    world
+        .registry
+        .add(block, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    let body = &world.bodies[block];
+    assert_eq!(0.0, body.position.z);
+    assert_eq!(0.0, body.orientation.i);
+    assert_eq!(0.0, body.orientation.j);
+    assert!(body.position.y < 1.0);
+    assert!(body.velocity.magnitude() < 0.01);
+}
+
+fn bounce_height(restitution: f64, scenery_restitution: f64) -> f64 {
+    let mut world = World::<f64>::default();
+    let sphere = Sphere::new(0.5);
+    let mut body = RigidBody::new(
+        Vector3::new(0.0, 2.5, 0.0),
+        1.0,
+        &sphere.inertia_tensor(1.0),
+    );
+    body.linear_damping = Some(1.0);
+    let ball = world.add_body(body);
+    let ball = world.body_index(ball).unwrap();
+    let mut collider = Collider::new(ball, Shape::Sphere(sphere));
+    collider.material.restitution = restitution;
+    collider.material.restitution_combine = CombineRule::Max;
+    world.add_collider(collider);
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    world.scenery_material.restitution = scenery_restitution;
+    world
+        .registry
+        .add(ball, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+
+    // Let the ball fall and bounce, keeping the highest point after the bounce.
+    let mut height: f64 = 0.0;
+    let mut bounced = false;
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 120.0);
+        let body = &world.bodies[ball];
+        bounced |= body.velocity.y > 0.0;
+        if bounced {
+            height = height.max(body.position.y);
+        }
+    }
+    height
+}
+
+#[test]
+fn materials() {
+    // The restitution of a rubber ball wins over the one of the floor with the max rule.
+    let rubber = bounce_height(0.8, 0.0);
+    assert!(rubber > 1.5 && rubber < 2.0);
+    assert_eq!(rubber, bounce_height(0.0, 0.8));
+
+    let clay = bounce_height(0.0, 0.0);
+    assert!(clay < 0.55);
+}
+
+/// Drops a small box on a static platform with the given surface velocities, 2 units from
+/// its center, and returns the box after a second.
+fn box_on_moving_surface(velocity: Vector3<f64>, rotation: Vector3<f64>) -> RigidBody {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    let mut platform = RigidBody::new(Vector3::new(0.0, -0.5, 0.0), 1.0, &Matrix3::identity());
+    platform.set_body_type(BodyType::Static);
+    let platform = world.add_body(platform);
+    let platform = world.body_index(platform).unwrap();
+    let mut surface = Collider::new(
+        platform,
+        Shape::Cuboid(Cuboid::new(Vector3::new(5.0, 0.5, 5.0))),
+    );
+    surface.surface_velocity = velocity;
+    surface.surface_rotation = rotation;
+    world.add_collider(surface);
+
+    let cuboid = Cuboid::new(Vector3::new(0.25, 0.25, 0.25));
+    let body = world.add_body(RigidBody::new(
+        Vector3::new(2.0, 0.25, 0.0),
+        1.0,
+        &cuboid.inertia_tensor(1.0),
+    ));
+    let body = world.body_index(body).unwrap();
+    world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+    for _ in 0..60 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    world.bodies[body]
+}
+
+#[test]
+fn surface_velocity() {
+    // Conveyor belts carry the bodies on them at the speed of the belt.
+    let carried = box_on_moving_surface(Vector3::new(0.0, 0.0, 2.0), Vector3::origin());
+    assert!((carried.velocity.z - 2.0).abs() < 0.05);
+    assert!(carried.velocity.x.abs() < 0.05);
+    assert!(carried.position.z > 1.0);
+    assert!((carried.position.y - 0.25).abs() < 0.02);
+
+    // Spinning surfaces drag them around their center.
+    let spun = box_on_moving_surface(Vector3::origin(), Vector3::new(0.0, 1.0, 0.0));
+    assert!(spun.position.z < -1.0);
+    assert!(spun.velocity.magnitude() > 1.5);
+    assert!((spun.position.y - 0.25).abs() < 0.02);
+
+    // Still surfaces leave them alone.
+    let still = box_on_moving_surface(Vector3::origin(), Vector3::origin());
+    assert!(still.velocity.magnitude() < 0.01);
+}
+
+/// Returns the path, as heights every step, of a box thrown up from the given height
+/// around a thin platform, which is one-way when given the direction of its solid side.
+fn box_thrown_at_platform(one_way: Option<Vector3<f64>>, height: f64, speed: f64) -> Vec<f64> {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    let mut platform = RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity());
+    platform.set_body_type(BodyType::Static);
+    let platform = world.add_body(platform);
+    let platform = world.body_index(platform).unwrap();
+    let mut surface = Collider::new(
+        platform,
+        Shape::Cuboid(Cuboid::new(Vector3::new(2.0, 0.1, 2.0))),
+    );
+    surface.one_way = one_way;
+    world.add_collider(surface);
+
+    let cuboid = Cuboid::new(Vector3::new(0.25, 0.25, 0.25));
+    let mut body = RigidBody::new(
+        Vector3::new(0.0, height, 0.0),
+        1.0,
+        &cuboid.inertia_tensor(1.0),
+    );
+    body.velocity = Vector3::new(0.0, speed, 0.0);
+    let body = world.add_body(body);
+    let body = world.body_index(body).unwrap();
+    world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+    (0..180)
+        .map(|_| {
+            world.start_frame();
+            world.run_physics(1.0 / 60.0);
+            world.bodies[body].position.y
+        })
+        .collect()
+}
+
+#[test]
+fn one_way() {
+    // Boxes jump through one-way platforms from below, and land on them.
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    let path = box_thrown_at_platform(Some(up), -1.0, 8.0);
+    assert!(path.iter().cloned().fold(0.0, f64::max) > 2.0);
+    assert!((path[path.len() - 1] - 0.35).abs() < 0.02);
+
+    // Solid platforms stop them from below.
+    let path = box_thrown_at_platform(None, -1.0, 8.0);
+    assert!(path.iter().all(|height| *height < 0.0));
+
+    // Platforms solid towards the ground let them fall through instead.
+    let path = box_thrown_at_platform(Some(up.scalar_mul(-1.0)), 2.0, 0.0);
+    assert!(path[path.len() - 1] < -2.0);
+}
+
+fn query_world() -> World {
+    let mut world = World::default();
+    let ball = world.add_body(RigidBody::new(
+        Vector3::new(5.0, 0.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let ball = world.body_index(ball).unwrap();
+    world.add_collider(Collider::new(ball, Shape::Sphere(Sphere::new(1.0))));
+    let crate_ = world.add_body(RigidBody::new(
+        Vector3::new(10.0, 0.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let crate_ = world.body_index(crate_).unwrap();
+    world.add_collider(Collider::new(
+        crate_,
+        Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 1.0, 1.0))),
+    ));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), -2.0));
+    world.start_frame();
+    world.update_colliders();
+    world
+}
+
+#[test]
+fn raycast() {
+    let world = query_world();
+    let filter = QueryFilter::new();
+    let ray = Ray::new(Vector3::origin(), Vector3::new(1.0, 0.0, 0.0));
+    let hit = world.raycast(&ray, 100.0, &filter).unwrap();
+    assert_eq!((Some(0), Some(0)), (hit.collider, hit.body));
+    assert!(
+        hit.point
+            .vector_sub(&Vector3::new(4.0, 0.0, 0.0))
+            .magnitude()
+            < 1e-4
+    );
+    assert!(
+        hit.normal
+            .vector_sub(&Vector3::new(-1.0, 0.0, 0.0))
+            .magnitude()
+            < 1e-4
+    );
+    assert!((hit.distance - 4.0).abs() < 1e-4);
+    assert!(world.raycast(&ray, 3.0, &filter).is_none());
+
+    // Filtered colliders are ignored.
+    let mut shooter = QueryFilter::new();
+    shooter.exclude_body = Some(0);
+    let hit = world.raycast(&ray, 100.0, &shooter).unwrap();
+    assert_eq!(Some(1), hit.collider);
+    assert!((hit.distance - 9.0).abs() < 1e-4);
+    let no_boxes =
+        |_: usize, collider: &Collider<Shape>| !matches!(collider.shape, Shape::Cuboid(_));
+    let mut spheres = QueryFilter::new();
+    spheres.predicate = Some(&no_boxes);
+    assert_eq!(
+        Some(0),
+        world.raycast(&ray, 100.0, &spheres).unwrap().collider
+    );
+    spheres.exclude_body = Some(0);
+    assert!(world.raycast(&ray, 100.0, &spheres).is_none());
+
+    // Every collider along the ray is reported, closest first.
+    let hits = world.raycast_all(&ray, 100.0, &filter);
+    assert_eq!(2, hits.len());
+    assert_eq!(Some(0), hits[0].collider);
+    assert_eq!(Some(1), hits[1].collider);
+
+    // Scenery planes are hit too, with times of impact in multiples of the direction.
+    let ray = Ray::new(Vector3::origin(), Vector3::new(0.0, -2.0, 0.0));
+    let hit = world.raycast(&ray, 100.0, &filter).unwrap();
+    assert_eq!((None, None), (hit.collider, hit.body));
+    assert_eq!(Vector3::new(0.0, -2.0, 0.0), hit.point);
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), hit.normal);
+    assert_eq!((1.0, 2.0), (hit.toi, hit.distance));
+    let mut no_scenery = QueryFilter::new();
+    no_scenery.exclude_scenery = true;
+    assert!(world.raycast(&ray, 100.0, &no_scenery).is_none());
+}
+
+#[test]
+fn shape_cast() {
+    let world = query_world();
+    let filter = QueryFilter::new();
+    let sphere = Sphere::new(0.5);
+    let start = Matrix4::identity();
+
+    // A sphere swept towards the ball stops when touching it.
+    let direction = Vector3::new(2.0, 0.0, 0.0);
+    let hit = world
+        .shape_cast(&sphere, &start, &direction, 100.0, &filter)
+        .unwrap();
+    assert_eq!((Some(0), Some(0)), (hit.collider, hit.body));
+    assert!((hit.distance - 3.5).abs() < 1e-4);
+    assert!(
+        hit.point
+            .vector_sub(&Vector3::new(4.0, 0.0, 0.0))
+            .magnitude()
+            < 1e-4
+    );
+    assert!(
+        hit.normal
+            .vector_sub(&Vector3::new(-1.0, 0.0, 0.0))
+            .magnitude()
+            < 1e-4
+    );
+    assert!(world
+        .shape_cast(&sphere, &start, &direction, 3.0, &filter)
+        .is_none());
+
+    // Ignoring the ball, a box catches the edge of the crate.
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    let above = Matrix4::new([1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.2, 0.0, 0.0, 1.0, 0.0]);
+    let mut shooter = QueryFilter::new();
+    shooter.exclude_body = Some(0);
+    let hit = world
+        .shape_cast(&cuboid, &above, &direction, 100.0, &shooter)
+        .unwrap();
+    assert_eq!(Some(1), hit.collider);
+    assert!((hit.distance - 8.5).abs() < 1e-4);
+
+    // Falling boxes land on the scenery.
+    let down = Vector3::new(0.0, -1.0, 0.0);
+    let hit = world
+        .shape_cast(&cuboid, &start, &down, 100.0, &filter)
+        .unwrap();
+    assert_eq!(None, hit.collider);
+    assert_eq!(1.5, hit.distance);
+    assert_eq!(Vector3::new(0.0, 1.0, 0.0), hit.normal);
+}
+
+#[test]
+fn intersections() {
+    let world = query_world();
+    let filter = QueryFilter::new();
+
+    // The corner of the region misses the ball, but not the crate.
+    let aabb = Aabb::new(Vector3::new(5.8, 0.8, -0.1), Vector3::new(9.1, 2.0, 0.1));
+    assert_eq!(vec![1], world.intersections_with_aabb(&aabb, &filter));
+    let aabb = Aabb::new(Vector3::new(4.0, -0.1, -0.1), Vector3::new(9.1, 0.1, 0.1));
+    assert_eq!(vec![0, 1], world.intersections_with_aabb(&aabb, &filter));
+
+    let center = Vector3::new(7.5, 0.0, 0.0);
+    assert!(world
+        .intersections_with_sphere(&center, 1.0, &filter)
+        .is_empty());
+    assert_eq!(
+        vec![0, 1],
+        world.intersections_with_sphere(&center, 1.6, &filter)
+    );
+
+    let mut filtered = QueryFilter::new();
+    filtered.exclude_body = Some(1);
+    assert_eq!(
+        vec![0],
+        world.intersections_with_sphere(&center, 1.6, &filtered)
+    );
+
+    let transform = Matrix4::from_orientation_and_position(
+        &Quaternion::from_axis_angle(&Vector3::new(1.0, 0.0, 0.0), std::f64::consts::FRAC_PI_4),
+        &Vector3::new(7.5, 0.0, 0.0),
+    );
+    let cuboid = Cuboid::new(Vector3::new(1.1, 0.1, 0.1));
+    assert!(world
+        .intersections_with_shape(&cuboid, &transform, &filter)
+        .is_empty());
+    let cuboid = Cuboid::new(Vector3::new(2.5, 0.1, 0.1));
+    assert_eq!(
+        vec![0, 1],
+        world.intersections_with_shape(&cuboid, &transform, &filter)
+    );
+}
+
+#[test]
+fn project_point() {
+    let world = query_world();
+    let filter = QueryFilter::new();
+    let close = |one: Vector3<f64>, two: Vector3<f64>| one.vector_sub(&two).magnitude() < 1e-4;
+
+    let projection = world
+        .project_point(&Vector3::new(5.0, 3.0, 0.0), &filter)
+        .unwrap();
+    assert_eq!((Some(0), Some(0)), (projection.collider, projection.body));
+    assert!(close(Vector3::new(5.0, 1.0, 0.0), projection.point));
+    assert!(!projection.is_inside);
+
+    // Points inside are projected onto the closest surface.
+    let projection = world
+        .project_point(&Vector3::new(10.0, 0.0, 0.5), &filter)
+        .unwrap();
+    assert_eq!(Some(1), projection.collider);
+    assert!(close(Vector3::new(10.0, 0.0, 1.0), projection.point));
+    assert!(projection.is_inside);
+    let projection = world
+        .project_point(&Vector3::new(0.0, -2.5, 0.0), &filter)
+        .unwrap();
+    assert_eq!(None, projection.collider);
+    assert!(close(Vector3::new(0.0, -2.0, 0.0), projection.point));
+    assert!(projection.is_inside);
+
+    let mut filtered = QueryFilter::new();
+    filtered.exclude_body = Some(0);
+    filtered.exclude_scenery = true;
+    let projection = world
+        .project_point(&Vector3::new(5.0, 3.0, 0.0), &filtered)
+        .unwrap();
+    assert_eq!(Some(1), projection.collider);
+    assert!(close(Vector3::new(9.0, 1.0, 0.0), projection.point));
+
+    let empty = World::<f64>::default();
+    assert!(empty.project_point(&Vector3::origin(), &filter).is_none());
+}
+
+#[test]
+fn distance() {
+    let mut world = query_world();
+    let close = |one: Vector3<f64>, two: Vector3<f64>| one.vector_sub(&two).magnitude() < 1e-4;
+    match world.distance(0, 1) {
+        GjkResult::Separated {
+            distance,
+            point_one,
+            point_two,
+        } => {
+            assert!((distance - 3.0).abs() < 1e-4);
+            assert!(close(Vector3::new(6.0, 0.0, 0.0), point_one));
+            assert!(close(Vector3::new(9.0, 0.0, 0.0), point_two));
+        }
+        GjkResult::Intersecting => panic!("the ball and the crate are apart"),
+    }
+
+    world.bodies[0].position = Vector3::new(8.5, 0.0, 0.0);
+    world.bodies[0].calculate_derived_data();
+    world.update_colliders();
+    assert_eq!(GjkResult::Intersecting, world.distance(0, 1));
+
+    // Compound shapes and triangle meshes are split into their parts.
+    let mut compound = Compound::<f64>::new();
+    compound.add(
+        Matrix4::from_orientation_and_position(
+            &Quaternion::identity(),
+            &Vector3::new(0.0, 3.0, 0.0),
+        ),
+        Shape::sphere(1.0),
+    );
+    compound.add(Matrix4::identity(), Shape::sphere(0.5));
+    let floor = TriMesh::new(
+        vec![
+            Vector3::new(-10.0, 0.0, -10.0),
+            Vector3::new(10.0, 0.0, -10.0),
+            Vector3::new(10.0, 0.0, 10.0),
+            Vector3::new(-10.0, 0.0, 10.0),
+        ],
+        vec![[0, 2, 1], [0, 3, 2]],
+    )
+    .unwrap();
+    let placed = Matrix4::from_orientation_and_position(
+        &Quaternion::identity(),
+        &Vector3::new(2.0, 1.0, 2.0),
+    );
+    match shape_distance(
+        &Shape::compound(compound),
+        &placed,
+        &Shape::trimesh(floor),
+        &Matrix4::identity(),
+    ) {
+        GjkResult::Separated {
+            distance,
+            point_one,
+            point_two,
+        } => {
+            assert!((distance - 0.5).abs() < 1e-4);
+            assert!(close(Vector3::new(2.0, 0.5, 2.0), point_one));
+            assert!(close(Vector3::new(2.0, 0.0, 2.0), point_two));
+        }
+        GjkResult::Intersecting => panic!("the compound is above the floor"),
+    }
+}
+
+#[test]
+fn contact() {
+    let at = |x: f64, y: f64| {
+        Matrix4::from_orientation_and_position(&Quaternion::identity(), &Vector3::new(x, y, 0.0))
+    };
+    let ball = Shape::sphere(1.0);
+    let crate_ = Shape::cuboid(Vector3::new(1.0, 1.0, 1.0));
+
+    // A ball sunk into the top of a crate is pushed up, out of it.
+    let sunk = query::contact(&ball, &at(0.0, 1.75), &crate_, &at(0.0, 0.0), 0.0).unwrap();
+    assert!(
+        sunk.normal
+            .vector_sub(&Vector3::new(0.0, 1.0, 0.0))
+            .magnitude()
+            < 1e-9
+    );
+    assert!((sunk.penetration - 0.25).abs() < 1e-9);
+    assert_eq!(1, sunk.points.len());
+
+    // Contacts seen from the crate push it down instead.
+    let flipped = query::contact(&crate_, &at(0.0, 0.0), &ball, &at(0.0, 1.75), 0.0).unwrap();
+    assert!(flipped.normal.vector_add(&sunk.normal).magnitude() < 1e-9);
+
+    // Shapes apart only touch within the prediction distance, with negative penetration.
+    assert!(query::contact(&ball, &at(3.5, 0.0), &ball, &at(0.0, 0.0), 0.0).is_none());
+    let predicted = query::contact(&ball, &at(3.5, 0.0), &ball, &at(0.0, 0.0), 2.0).unwrap();
+    assert!((predicted.penetration + 1.5).abs() < 1e-9);
+}
+
+fn bullet(continuous_collision: bool) -> World {
+    let mut world = World::default();
+    let mut wall = RigidBody::new(Vector3::new(2.0, 0.0, 0.0), 1.0, &Matrix3::identity());
+    wall.set_infinite_mass();
+    let wall = world.add_body(wall);
+    let wall = world.body_index(wall).unwrap();
+    world.add_collider(Collider::new(
+        wall,
+        Shape::Cuboid(Cuboid::new(Vector3::new(0.05, 1.0, 1.0))),
+    ));
+
+    let sphere = Sphere::new(0.05);
+    let mut bullet = RigidBody::new(Vector3::origin(), 0.01, &sphere.inertia_tensor(0.01));
+    bullet.velocity = Vector3::new(300.0, 0.0, 0.0);
+    bullet.continuous_collision = continuous_collision;
+    let bullet = world.add_body(bullet);
+    let bullet = world.body_index(bullet).unwrap();
+    world.add_collider(Collider::new(bullet, Shape::Sphere(sphere)));
+    world
+}
+
+#[test]
+fn continuous_collision() {
+    // Without continuous collision detection, the bullet goes through the wall.
+    let mut world = bullet(false);
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+    assert!(world.bodies[1].position.x > 2.0);
+
+    // With it, the bullet stops right where it hits the wall, and the contact stops it for good.
+    let mut world = bullet(true);
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+    let x = world.bodies[1].position.x;
+    assert!((x - (1.9 + CONTINUOUS_COLLISION_DEPTH)).abs() < 1e-3);
+    for _ in 0..10 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+        assert!(world.bodies[1].position.x < 1.95);
+    }
+    assert!(world.bodies[1].velocity.x <= 0.0);
+}
+
+#[test]
+fn speculative_contacts() {
+    // With a contact margin covering the travel of a frame, the bullet only closes the gap
+    // to the wall, without reporting the contact before touching it.
+    let mut world = bullet(false);
+    world.colliders[1].contact_margin = 6.0;
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+    assert!((world.bodies[1].position.x - 1.9).abs() < 1e-9);
+    assert_eq!(1, world.manifolds.len());
+    assert!(world.drain_contact_events().next().is_none());
+    let mut events = Vec::new();
+    for _ in 0..10 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+        assert!(world.bodies[1].position.x < 1.95);
+        events.extend(world.drain_contact_events());
+    }
+    assert!(world.bodies[1].velocity.x <= 0.0);
+    assert_eq!(ContactEventKind::Started, events[0].kind);
+}
+
+#[test]
+fn collision_groups() {
+    const DEBRIS: u32 = 2;
+    let mut world = World::<f64>::default();
+    for x in [0.0, 1.5] {
+        let body = world.add_body(RigidBody::new(
+            Vector3::new(x, 0.0, 0.0),
+            1.0,
+            &Matrix3::identity(),
+        ));
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Sphere(Sphere::new(1.0))));
+    }
+    world.start_frame();
+    world.detect_collisions();
+    assert!(!world.contacts.contacts.is_empty());
+
+    // Debris doesn't collide with debris.
+    for collider in world.colliders.iter_mut() {
+        collider.with_groups(DEBRIS, !DEBRIS);
+    }
+    world.detect_collisions();
+    assert!(world.contacts.contacts.is_empty());
+
+    // Neither do the pairs rejected by the predicate.
+    for collider in world.colliders.iter_mut() {
+        collider.with_groups(DEBRIS, u32::MAX);
+    }
+    assert!(world.can_collide(0, 1));
+    world.collision_predicate = Some(Box::new(|one, _, two, _| one + two != 1));
+    world.detect_collisions();
+    assert!(world.contacts.contacts.is_empty());
+    assert!(!world.can_collide(0, 1));
+
+    // Rays of the player ignore the player.
+    const PLAYER: u32 = 4;
+    let mut world = query_world();
+    world.colliders[0].with_groups(PLAYER, u32::MAX);
+    let ray = Ray::new(Vector3::origin(), Vector3::new(1.0, 0.0, 0.0));
+    let mut filter = QueryFilter::new();
+    filter.mask = !PLAYER;
+    assert_eq!(
+        Some(1),
+        world.raycast(&ray, 100.0, &filter).unwrap().collider
+    );
+
+    // Colliders can also ignore the queries of some groups.
+    world.colliders[1].with_groups(1, !PLAYER);
+    filter.group = PLAYER;
+    assert!(world.raycast(&ray, 100.0, &filter).is_none());
+}
+
+#[test]
+fn soft_bodies() {
+    let mut world = World::<f64>::default();
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let mut jelly = SoftBody::cuboid(
+        Vector3::new(0.0, 1.0, 0.0),
+        Vector3::new(0.5, 0.5, 0.5),
+        2,
+        1.0,
+    );
+    jelly.gravity = Vector3::new(0.0, -10.0, 0.0);
+    let jelly = world.add_soft_body(jelly);
+
+    // A floating plank in the way is pushed down by the falling soft body.
+    let plank = world.add_body(RigidBody::new(
+        Vector3::new(3.0, 0.5, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let plank = world.body_index(plank).unwrap();
+    world.add_collider(Collider::new(
+        plank,
+        Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 0.1, 1.0))),
+    ));
+    let mut falling = SoftBody::cuboid(
+        Vector3::new(3.0, 1.2, 0.0),
+        Vector3::new(0.5, 0.5, 0.5),
+        1,
+        1.0,
+    );
+    falling.gravity = Vector3::new(0.0, -10.0, 0.0);
+    world.add_soft_body(falling);
+
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+
+    // The soft body rests on the ground, keeping its volume.
+    let resting = &world.soft_bodies[jelly];
+    for particle in resting.particles.iter() {
+        assert!(particle.position.y > -resting.radius);
+    }
+    assert!((resting.volume() - 1.0).abs() < 0.1);
+    assert!(resting.center_of_mass().y < 0.6);
+    assert!(world.bodies[plank].position.y < 0.4);
+}
+
+#[test]
+fn fluids() {
+    let mut world = World::<f64>::default();
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+
+    // The same box is dropped on a pool of fluid coupled both ways, and on one that doesn't push back.
+    let mut pools = Vec::new();
+    let mut boxes = Vec::new();
+    for (offset, coupling) in [(0.0, FluidCoupling::TwoWay), (5.0, FluidCoupling::OneWay)] {
+        let mut fluid = Fluid::block(
+            &Vector3::new(offset, 0.0, 0.0),
+            &Vector3::new(offset + 0.6, 0.4, 0.6),
+            0.1,
+            1000.0,
+        );
+        fluid.gravity = Vector3::new(0.0, -10.0, 0.0);
+        fluid.coupling = coupling;
+        fluid.boundaries = vec![
+            Plane::new(Vector3::new(1.0, 0.0, 0.0), offset),
+            Plane::new(Vector3::new(-1.0, 0.0, 0.0), -offset - 0.6),
+            Plane::new(Vector3::new(0.0, 0.0, 1.0), 0.0),
+            Plane::new(Vector3::new(0.0, 0.0, -1.0), -0.6),
+        ];
+        pools.push(world.add_fluid(fluid));
+
+        let body = world.add_body(RigidBody::new(
+            Vector3::new(offset + 0.3, 0.6, 0.3),
+            5.0,
+            &Matrix3::identity(),
+        ));
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(
+            body,
+            Shape::Cuboid(Cuboid::new(Vector3::new(0.15, 0.1, 0.15))),
+        ));
+        world
+            .registry
+            .add(body, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+        boxes.push(body);
+    }
+
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+
+    // The fluid coupled both ways keeps the box afloat, the other one lets it sink to the floor.
+    assert!(world.bodies[boxes[0]].position.y > 0.15);
+    assert!(world.bodies[boxes[1]].position.y < 0.15);
+    for pool in pools {
+        for particle in world.fluids[pool].particles.iter() {
+            assert!(particle.position.y > 0.0);
+        }
+    }
+}
+
+#[test]
+fn explosions() {
+    let mut world = World::<f64>::default();
+    let add_box = |world: &mut World<f64>, position: Vector3<f64>| {
+        let body = world.add_body(RigidBody::new(position, 1.0, &Matrix3::identity()));
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(
+            body,
+            Shape::Cuboid(Cuboid::new(Vector3::new(0.5, 0.5, 0.5))),
+        ));
+        body
+    };
+    let near = add_box(&mut world, Vector3::new(2.0, 0.0, 0.0));
+    let far = add_box(&mut world, Vector3::new(0.0, 1.0, 4.0));
+    let asleep = add_box(&mut world, Vector3::new(-2.0, 0.0, 0.0));
+    let outside = add_box(&mut world, Vector3::new(0.0, 0.0, -9.0));
+    world.bodies[asleep].set_awake(false);
+    world.update_colliders();
+
+    let center = Vector3::new(0.0, 0.0, 0.0);
+    let pushed = world.apply_explosion(&center, 5.0, 10.0, Falloff::Linear, false);
+    assert_eq!(vec![near, far], pushed);
+
+    // The near box is pushed straight away, the far one more weakly and set spinning.
+    let near = &world.bodies[near];
+    assert!((near.velocity - Vector3::new(7.0, 0.0, 0.0)).magnitude() < 1e-6);
+    assert!(near.rotation.magnitude() < 1e-6);
+    let far = &world.bodies[far];
+    assert!(far.velocity.z > 0.0 && far.velocity.magnitude() < 7.0);
+    assert!(far.rotation.magnitude() > 0.1);
+    assert_eq!(Vector3::origin(), world.bodies[asleep].velocity);
+    assert_eq!(Vector3::origin(), world.bodies[outside].velocity);
+
+    // Waking explosions push sleeping bodies too.
+    let pushed = world.apply_explosion(&center, 5.0, 10.0, Falloff::Constant, true);
+    assert!(pushed.contains(&asleep));
+    assert!(world.bodies[asleep].is_awake);
+    assert!(world.bodies[asleep].velocity.x < -9.0);
+    assert_eq!(0.0, Falloff::Quadratic.scale(5.0, 5.0));
+    assert_eq!(0.25, Falloff::Quadratic.scale(2.5, 5.0));
+
+    // Bodies with several colliders in reach are pushed once, from their closest one.
+    let mut world = World::<f64>::default();
+    world.add_body(RigidBody::new(
+        Vector3::new(0.0, -3.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    for y in [1.0, -1.0] {
+        let offset = Matrix4::from_orientation_and_position(
+            &Quaternion::identity(),
+            &Vector3::new(0.0, y, 0.0),
+        );
+        let cube = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+        world.add_collider(Collider::with_offset(0, Shape::Cuboid(cube), offset));
+    }
+    world.update_colliders();
+    let pushed = world.apply_explosion(&center, 5.0, 10.0, Falloff::Linear, false);
+    assert_eq!(vec![0], pushed);
+    assert!((world.bodies[0].velocity - Vector3::new(0.0, -7.0, 0.0)).magnitude() < 1e-6);
+    assert!(world.bodies[0].rotation.magnitude() < 1e-6);
+}
+
+#[test]
+fn resting_on_several_colliders() {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let sphere = Sphere::new(0.5);
+    let handle = world.add_body(RigidBody::new(
+        Vector3::new(0.0, 0.5, 0.0),
+        2.0,
+        &sphere.inertia_tensor(2.0),
+    ));
+    let body = world.body_index(handle).unwrap();
+    for x in [-1.0, 1.0] {
+        let offset = Matrix4::from_orientation_and_position(
+            &Quaternion::identity(),
+            &Vector3::new(x, 0.0, 0.0),
+        );
+        world.add_collider(Collider::with_offset(body, Shape::Sphere(sphere), offset));
+    }
+    world.update_mass_properties(body);
+
+    for _ in 0..300 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+
+    // Both spheres keep their own point in the manifold with the ground, so the body rests level.
+    let manifold = world.manifolds.get((body, None)).unwrap();
+    assert_eq!(2, manifold.len());
+    assert_ne!(
+        manifold.points[0].contact.colliders,
+        manifold.points[1].contact.colliders
+    );
+    let body = &world.bodies[body];
+    assert!((body.position.y - 0.5).abs() < 0.02);
+    assert!(body.orientation.i.abs() + body.orientation.k.abs() < 1e-3);
+}
+
+fn tumbling_boxes(deterministic: bool) -> World {
+    let mut world = World::<f64>::new(WorldConfig {
+        deterministic,
+        ..WorldConfig::default()
+    });
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    for index in 0..4 {
+        let offset = index as f64;
+        let mut body = RigidBody::new(
+            Vector3::new(0.3 * offset, 1.0 + 1.2 * offset, 0.1 * offset),
+            1.0,
+            &cuboid.inertia_tensor(1.0),
+        );
+        body.rotation = Vector3::new(1.0, 2.0 - offset, 0.5);
+        let body = world.add_body(body);
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+    }
+    world
+}
+
+#[test]
+fn deterministic() {
+    let mut one = tumbling_boxes(true);
+    let mut two = tumbling_boxes(true);
+    let mut platform = tumbling_boxes(false);
+    for _ in 0..240 {
+        for world in [&mut one, &mut two, &mut platform] {
+            world.start_frame();
+            world.run_physics(1.0 / 60.0);
+        }
+    }
+
+    // Deterministic worlds replay bit by bit, and stay close to the ones using the platform.
+    for index in 0..one.bodies.len() {
+        let (body, other) = (&one.bodies[index], &two.bodies[index]);
+        assert_eq!(body.position.x.to_bits(), other.position.x.to_bits());
+        assert_eq!(body.position.y.to_bits(), other.position.y.to_bits());
+        assert_eq!(body.orientation, other.orientation);
+        assert_eq!(body.velocity, other.velocity);
+        assert!(body.position.y > 0.4);
+    }
+    let drift = (one.bodies[0].position - platform.bodies[0].position).magnitude();
+    assert!(drift < 0.1, "{}", drift);
+}
+
+fn stacks(stacks: std::ops::Range<usize>) -> World {
+    let mut world = World::<f64>::new(WorldConfig::default());
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    for stack in stacks {
+        for level in 0..3 {
+            let mut body = RigidBody::new(
+                Vector3::new(
+                    5.0 * stack as f64,
+                    0.6 + 1.1 * level as f64,
+                    0.1 * level as f64,
+                ),
+                1.0,
+                &cuboid.inertia_tensor(1.0),
+            );
+            body.rotation = Vector3::new(0.5, 1.0 - level as f64, 0.2 * stack as f64);
+            let body = world.add_body(body);
+            let body = world.body_index(body).unwrap();
+            world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+        }
+    }
+    world
+}
+
+#[test]
+fn islands() {
+    let mut world = stacks(0..3);
+    let mut alone: Vec<World> = (0..3).map(|stack| stacks(stack..stack + 1)).collect();
+    for _ in 0..120 {
+        for world in std::iter::once(&mut world).chain(alone.iter_mut()) {
+            world.start_frame();
+            world.run_physics(1.0 / 60.0);
+        }
+    }
+
+    // Stacks far apart are solved the same whether they share a world or not, whatever the
+    // threads the islands are solved on.
+    for (stack, alone) in alone.iter().enumerate() {
+        for level in 0..3 {
+            let body = &world.bodies[3 * stack + level];
+            assert_eq!(body.position, alone.bodies[level].position);
+            assert_eq!(body.orientation, alone.bodies[level].orientation);
+            assert_eq!(body.velocity, alone.bodies[level].velocity);
+        }
+    }
+}
+
+#[test]
+fn substeps() {
+    let falling = |substeps: usize| {
+        let mut world = World::<f64>::new(WorldConfig {
+            linear_damping: 1.0,
+            substeps,
+            ..WorldConfig::default()
+        });
+        world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+        let body = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+        let body = world.body_index(body).unwrap();
+        world.start_frame();
+        world.run_physics(1.0);
+        (world.bodies[body].position.y, world.bodies[body].velocity.y)
+    };
+
+    // Substeps integrate the same velocity, but get closer to the exact fall of 5 meters.
+    assert_eq!((-10.0, -10.0), falling(1));
+    assert_eq!((-6.25, -10.0), falling(4));
+    assert_eq!(falling(1), falling(0));
+
+    // Stacks stay upright with few iterations split over several substeps.
+    let mut world = stacks(0..1);
+    world.config.solver.iterations = 2;
+    world.config.substeps = 4;
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    assert!((world.bodies[2].position.y - 2.5).abs() < 0.05);
+    assert!(world.bodies[2].velocity.magnitude() < 0.1);
+    assert_eq!(8, world.stats().iterations);
+}
+
+fn tower_sag(shock_propagation: bool) -> f64 {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world.config.solver.iterations = 4;
+    world.config.solver.shock_propagation = shock_propagation;
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    for level in 0..20 {
+        let body = world.add_body(RigidBody::new(
+            Vector3::new(0.0, 0.5 + level as f64, 0.0),
+            1.0,
+            &cuboid.inertia_tensor(1.0),
+        ));
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+    }
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    19.5 - world.bodies[19].position.y
+}
+
+#[test]
+fn shock_propagation() {
+    // With few iterations, the weight of a tall tower squeezes the boxes into each other,
+    // unless it's carried from the ground up.
+    assert!(tower_sag(false) > 0.05);
+    assert!(tower_sag(true).abs() < 0.01);
+}
+
+#[test]
+fn stats() {
+    let mut world = stacks(0..3);
+    assert_eq!(WorldStats::default(), *world.stats());
+    for _ in 0..60 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+
+    let stats = world.stats();
+    assert_eq!(6, stats.pairs);
+    assert_eq!(9, stats.manifolds);
+    assert!(stats.contacts >= stats.manifolds);
+    assert_eq!(3, stats.islands);
+    assert_eq!(world.config.solver.iterations, stats.iterations);
+    assert!(stats.total >= stats.integration + stats.solver);
+    assert!(stats.total >= stats.broad_phase + stats.narrow_phase);
+}
+
+#[test]
+fn many_colliders() {
+    let mut world = World::<f64>::new(WorldConfig::default());
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    for index in 0..400 {
+        let (row, column) = ((index / 20) as f64, (index % 20) as f64);
+        let mut body = RigidBody::new(
+            Vector3::new(0.99 * column, 0.49 + 0.01 * row, 0.99 * row),
+            1.0,
+            &cuboid.inertia_tensor(1.0),
+        );
+        body.orientation = Quaternion::from_axis_angle(&Vector3::new(0.0, 1.0, 0.0), 0.01 * row);
+        let body = world.add_body(body);
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+    }
+    world.detect_collisions();
+
+    // Contacts come in the order of the pairs, whatever the threads generating them.
+    let mut expected = CollisionData::new(DEFAULT_MAX_CONTACTS);
+    let colliders = &world.colliders;
+    for first in 0..colliders.len() {
+        for second in first + 1..colliders.len() {
+            let (one, two) = (&colliders[first], &colliders[second]);
+            expected.set_materials(&one.material, &two.material);
+            let start = expected.contacts.len();
+            collide(one, two, &mut expected);
+            for contact in expected.contacts[start..].iter_mut() {
+                contact.colliders = if contact.bodies.0 == one.body {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+            }
+        }
+    }
+    for (index, collider) in colliders.iter().enumerate() {
+        expected.set_materials(&collider.material, &world.scenery_material);
+        let start = expected.contacts.len();
+        collide_with_half_space(collider, &world.planes[0], &mut expected);
+        for contact in expected.contacts[start..].iter_mut() {
+            contact.colliders = (index, 0);
+        }
+    }
+    assert!(expected.contacts.len() > 1000);
+    assert_eq!(expected.contacts, world.contacts.contacts);
+}
+
+#[test]
+fn remove_body() {
+    let mut world = World::<f64>::default();
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    for x in 0..3 {
+        let body = world.add_body(RigidBody::new(
+            Vector3::new(x as f64, 0.0, 0.0),
+            1.0,
+            &Matrix3::identity(),
+        ));
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+    }
+    let ball = BallJoint::new(Vector3::new(1.0, 0.0, 0.0), Vector3::origin());
+    world.add_joint(Joint::new((0, Some(1)), JointKind::Ball(ball)));
+    world.add_joint(Joint::new((0, Some(2)), JointKind::Ball(ball)));
+    world
+        .registry
+        .add(2, Box::new(Gravity::new(Vector3::new(0.0, -10.0, 0.0))));
+    world
+        .gravity_overrides
+        .insert(2, GravitySource::Uniform(Vector3::origin()));
+    world.start_frame();
+    world.run_physics(0.01);
+
+    // The other bodies keep their indices, and the removed one leaves an inert body behind.
+    let removed = world.remove_body_at(1);
+    assert!((removed.position.x - 1.0).abs() < 0.1);
+    assert_eq!(3, world.bodies.len());
+    assert_eq!(2, world.body_count());
+    assert_eq!(BodyType::Static, world.bodies[1].body_type);
+    assert!(!world.bodies[1].is_awake);
+    assert_eq!(
+        vec![0, 2],
+        world.colliders.iter().map(|c| c.body).collect::<Vec<_>>()
+    );
+    assert_eq!(1, world.joints.len());
+    assert_eq!((0, Some(2)), world.joints[0].bodies);
+    assert_eq!(1, world.registry.len());
+    assert!(world.gravity_overrides.contains_key(&2));
+    world.start_frame();
+    world.run_physics(0.01);
+    assert!(world.bodies[2].velocity.y < 0.0);
+    assert!((world.bodies[1].position.x - 1.0).abs() < 0.1);
+    assert_eq!(Vector3::origin(), world.bodies[1].velocity);
+}
+
+#[test]
+fn body_types() {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    let mut floor = RigidBody::new(Vector3::new(0.0, -0.5, 0.0), 1.0, &Matrix3::identity());
+    floor.set_body_type(BodyType::Static);
+    let floor = world.add_body(floor);
+    let floor = world.body_index(floor).unwrap();
+    world.add_collider(Collider::new(
+        floor,
+        Shape::Cuboid(Cuboid::new(Vector3::new(10.0, 0.5, 10.0))),
+    ));
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    let mut pusher = RigidBody::new(Vector3::new(-6.0, 0.5, 0.0), 1.0, &Matrix3::identity());
+    pusher.set_body_type(BodyType::Kinematic);
+    pusher.velocity = Vector3::new(2.0, 0.0, 0.0);
+    let pusher = world.add_body(pusher);
+    let pusher = world.body_index(pusher).unwrap();
+    world.add_collider(Collider::new(pusher, Shape::Cuboid(cuboid)));
+    let crate_ = world.add_body(RigidBody::new(
+        Vector3::new(0.0, 0.5, 0.0),
+        1.0,
+        &cuboid.inertia_tensor(1.0),
+    ));
+    let crate_ = world.body_index(crate_).unwrap();
+    world.add_collider(Collider::new(crate_, Shape::Cuboid(cuboid)));
+
+    // The crate falls asleep on the floor before the pusher reaches it.
+    for _ in 0..90 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    assert!(!world.bodies[crate_].is_awake);
+    for _ in 0..120 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+
+    // The pusher moves at its own pace regardless of gravity and contacts, shoving the crate
+    // across the floor, which stays where it was.
+    let (pusher, crate_, floor) = (
+        &world.bodies[pusher],
+        &world.bodies[crate_],
+        &world.bodies[floor],
+    );
+    assert!((pusher.position.x - 1.0).abs() < 1e-9);
+    assert_eq!(0.5, pusher.position.y);
+    assert_eq!(Vector3::new(2.0, 0.0, 0.0), pusher.velocity);
+    assert!(crate_.position.x > pusher.position.x + 0.9);
+    assert!((crate_.position.y - 0.5).abs() < 0.05);
+    assert_eq!(Vector3::new(0.0, -0.5, 0.0), floor.position);
+}
+
+#[test]
+fn mass_properties() {
+    let mut world = World::<f64>::default();
+    let body = world.add_body(RigidBody::new(
+        Vector3::new(1.0, 0.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let body = world.body_index(body).unwrap();
+    let cube = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    let heavy = PhysicsMaterial {
+        density: 3.0,
+        ..PhysicsMaterial::default()
+    };
+    for (x, density) in [(0.0, 1.0), (2.0, 3.0)] {
+        let offset = Matrix4::from_orientation_and_position(
+            &Quaternion::identity(),
+            &Vector3::new(x, 0.0, 0.0),
+        );
+        let mut collider = Collider::with_offset(body, Shape::Cuboid(cube), offset);
+        collider.material = PhysicsMaterial { density, ..heavy };
+        world.add_collider(collider);
+    }
+    let mut sensor = Collider::new(body, Shape::Cuboid(cube));
+    sensor.sensor = true;
+    world.add_collider(sensor);
+
+    // The body is weighed from its colliders, and moved onto their center of mass.
+    let properties = world.update_mass_properties(body);
+    assert_eq!(4.0, properties.mass);
+    assert_eq!(Vector3::new(1.5, 0.0, 0.0), properties.center_of_mass);
+    let rigid_body = &world.bodies[body];
+    assert_eq!(0.25, rigid_body.inverse_mass);
+    assert_eq!(Vector3::new(2.5, 0.0, 0.0), rigid_body.position);
+    assert_eq!(
+        properties.inertia_tensor.inverse(),
+        rigid_body.inverse_inertia_tensor
+    );
+
+    // While the colliders stay where they were.
+    world.update_colliders();
+    assert_eq!(Vector3::new(1.0, 0.0, 0.0), world.colliders[0].position());
+    assert_eq!(Vector3::new(3.0, 0.0, 0.0), world.colliders[1].position());
+    assert_eq!(
+        Vector3::origin(),
+        world.mass_properties(body).center_of_mass
+    );
+
+    // Overridden properties are given as they are.
+    let properties = world.mass_properties(body).with_mass(8.0);
+    world.set_mass_properties(body, &properties);
+    assert_eq!(0.125, world.bodies[body].inverse_mass);
+    assert_eq!(Vector3::new(2.5, 0.0, 0.0), world.bodies[body].position);
+}
+
+#[test]
+fn handles() {
+    let mut world = World::<f64>::default();
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    let mut bodies = Vec::new();
+    let mut colliders = Vec::new();
+    for x in 0..3 {
+        bodies.push(world.add_body(RigidBody::new(
+            Vector3::new(x as f64, 0.0, 0.0),
+            1.0,
+            &Matrix3::identity(),
+        )));
+        colliders.push(world.add_collider(Collider::new(x, Shape::Cuboid(cuboid))));
+    }
+    let ball = BallJoint::new(Vector3::new(1.0, 0.0, 0.0), Vector3::origin());
+    let joints = [
+        world.add_joint(Joint::new((0, Some(1)), JointKind::Ball(ball))),
+        world.add_joint(Joint::new((0, Some(2)), JointKind::Ball(ball))),
+    ];
+    assert_eq!(bodies[2], world.body_handle(2));
+    let state = world.snapshot();
+
+    // Bodies keep their indices, and the handles of the colliders and joints left follow
+    // them to their new indices.
+    let removed = world.remove_body(bodies[1]).unwrap();
+    assert!((removed.position.x - 1.0).abs() < 1e-9);
+    assert_eq!(Some(0), world.body_index(bodies[0]));
+    assert_eq!(Some(2), world.body_index(bodies[2]));
+    assert!((world.body(bodies[2]).unwrap().position.x - 2.0).abs() < 1e-9);
+    assert_eq!(Some(1), world.collider_index(colliders[2]));
+    assert_eq!(2, world.collider(colliders[2]).unwrap().body);
+    assert_eq!(Some(0), world.joint_index(joints[1]));
+    assert_eq!((0, Some(2)), world.joint(joints[1]).unwrap().bodies);
+
+    // Handles of removed objects stop resolving, even once their slots are reused.
+    assert_eq!(None, world.body(bodies[1]));
+    assert!(world.remove_body(bodies[1]).is_none());
+    assert!(world.collider(colliders[1]).is_none());
+    assert!(world.joint(joints[0]).is_none());
+    let handle = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    assert_ne!(bodies[1], handle);
+    assert_eq!(None, world.body_mut(bodies[1]));
+    assert_eq!(Some(3), world.body_index(handle));
+
+    // Restoring a snapshot brings back the handles it had.
+    world.restore(&state);
+    assert_eq!(Some(1), world.body_index(bodies[1]));
+    assert_eq!(Some(2), world.body_index(bodies[2]));
+    assert_eq!(Some(1), world.collider_index(colliders[1]));
+    assert_eq!(Some(0), world.joint_index(joints[0]));
+    assert_eq!(None, world.body_index(handle));
+}
+
+#[test]
+fn stale_handles() {
+    let mut world = World::<f64>::default();
+    let one = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    let two = world.add_body(RigidBody::new(
+        Vector3::new(1.0, 0.0, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let ball = BallJoint::new(Vector3::new(1.0, 0.0, 0.0), Vector3::origin());
+    let first = world.add_joint(Joint::new((0, Some(1)), JointKind::Ball(ball)));
+    let second = world.add_joint(Joint::new((1, None), JointKind::Ball(ball)));
+    let sphere = world.add_collider(Collider::new(0, Shape::sphere(0.5)));
+
+    // Removed joints stop resolving, and the ones after them follow them down.
+    let removed = world.remove_joint(first).unwrap();
+    assert_eq!((0, Some(1)), removed.bodies);
+    assert!(world.remove_joint(first).is_none());
+    assert_eq!(Some(0), world.joint_index(second));
+    assert_eq!((1, None), world.joint(second).unwrap().bodies);
+
+    // Objects added in the slots of removed ones get new handles, and the old ones never
+    // resolve to them.
+    let reused = world.add_joint(Joint::new((0, None), JointKind::Ball(ball)));
+    assert_ne!(first, reused);
+    assert!(world.joint(first).is_none());
+    assert_eq!(Some(1), world.joint_index(reused));
+    world.remove_collider(sphere).unwrap();
+    let cuboid = world.add_collider(Collider::new(1, Shape::cuboid(Vector3::new(0.5, 0.5, 0.5))));
+    assert!(world.collider(sphere).is_none());
+    assert!(world.collider_mut(sphere).is_none());
+    assert!(world.remove_collider(sphere).is_none());
+    assert_eq!(Some(0), world.collider_index(cuboid));
+    world.remove_body(one).unwrap();
+    let three = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    assert!(world.body(one).is_none());
+    assert!(world.interpolated_pose(one, 0.5).is_none());
+    assert_eq!(Some(1), world.body_index(two));
+    assert_eq!(Some(2), world.body_index(three));
+    assert_eq!(Some(0), world.joint_index(second));
+    assert!(world.joint(reused).is_none());
+
+    // Handles from other worlds, or made up, only resolve to objects they were given for.
+    let other = World::<f64>::default();
+    assert!(other.body(two).is_none());
+    assert!(world
+        .body(BodyHandle::from_bits(three.to_bits() + 1))
+        .is_none());
+}
+
+#[test]
+fn remove_collider() {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    let floor = world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    let floor = world.body_index(floor).unwrap();
+    world.bodies[floor].set_infinite_mass();
+    world.bodies[floor].body_type = BodyType::Static;
+    for x in 0..3 {
+        world.add_collider(Collider::with_offset(
+            floor,
+            Shape::cuboid(Vector3::new(1.0, 0.5, 1.0)),
+            Matrix4::from_orientation_and_position(
+                &Quaternion::identity(),
+                &Vector3::new(x as f64 * 2.0, 0.0, 0.0),
+            ),
+        ));
+    }
+    let ball = world.add_body(RigidBody::new(
+        Vector3::new(4.0, 1.4, 0.0),
+        1.0,
+        &Matrix3::identity(),
+    ));
+    let ball = world.body_index(ball).unwrap();
+    world.add_collider(Collider::new(ball, Shape::sphere(0.5)));
+    let colliders: Vec<_> = (0..4).map(|index| world.collider_handle(index)).collect();
+    for _ in 0..30 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    assert!(world.drain_contact_events().count() > 0);
+
+    // The last collider fills the gap, keeping its handle and its contacts going.
+    let removed = world.remove_collider(colliders[0]).unwrap();
+    assert_eq!(Vector3::origin(), removed.offset.translation());
+    assert_eq!(3, world.colliders.len());
+    assert_eq!(None, world.collider_index(colliders[0]));
+    assert_eq!(Some(0), world.collider_index(colliders[3]));
+    assert_eq!(Some(2), world.collider_index(colliders[2]));
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+    let events: Vec<_> = world.drain_contact_events().collect();
+    assert_eq!(1, events.len());
+    assert_eq!(ContactEventKind::Persisted, events[0].kind);
+    assert!((world.bodies[ball].position.y - 1.0).abs() < 0.05);
+
+    // Removing the collider under the ball lets it fall.
+    world.remove_collider(colliders[2]).unwrap();
+    for _ in 0..30 {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    }
+    assert!(world.bodies[ball].position.y < 0.5);
+    assert_eq!(Some(0), world.collider_index(colliders[3]));
+    assert_eq!(Some(1), world.collider_index(colliders[1]));
+}
+
+#[test]
+fn interpolation() {
+    let mut world = World::<f64>::new(WorldConfig {
+        linear_damping: 1.0,
+        angular_damping: 1.0,
+        ..WorldConfig::default()
+    });
+    let mut body = RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity());
+    body.velocity = Vector3::new(2.0, 0.0, 0.0);
+    body.rotation = Vector3::new(0.0, 0.0, 0.1);
+    let handle = world.add_body(body);
+    let other = world.add_body(body);
+    let index = world.body_index(handle).unwrap();
+
+    // Bodies that haven't stepped yet stay where they are.
+    let (position, orientation) = world.interpolated_pose(handle, 0.5).unwrap();
+    assert_eq!(Vector3::origin(), position);
+    assert_eq!(Quaternion::identity(), orientation);
+
+    world.start_frame();
+    world.run_physics(1.0);
+    let body = &world.bodies[index];
+    let (position, orientation) = world.interpolated_pose(handle, 0.5).unwrap();
+    assert_eq!(Vector3::new(1.0, 0.0, 0.0), position);
+    let expected = Quaternion::identity().slerp(&body.orientation, 0.5);
+    assert!((orientation.r - expected.r).abs() < 1e-12);
+    assert!((orientation.k - expected.k).abs() < 1e-12);
+    let (position, orientation) = world.interpolated_pose(handle, 1.0).unwrap();
+    assert_eq!(body.position, position);
+    assert!((orientation.k - body.orientation.k).abs() < 1e-12);
+
+    // Removing bodies keeps the previous poses of the rest.
+    world.remove_body(handle);
+    assert!(world.interpolated_pose(handle, 0.5).is_none());
+    let (position, _) = world.interpolated_pose(other, 0.25).unwrap();
+    assert_eq!(Vector3::new(0.5, 0.0, 0.0), position);
+}
+
+#[test]
+fn shift_origin() {
+    let build = || {
+        let mut world = World::<f64>::default();
+        world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+        world.gravity.add_zone(
+            GravityRegion::Sphere(Vector3::new(20.0, 0.0, 0.0), 5.0),
+            GravitySource::Point {
+                center: Vector3::new(20.0, 0.0, 0.0),
+                strength: 10.0,
+                inverse_square: false,
+            },
+            0,
+        );
+        world
+            .planes
+            .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+        let block = world.add_body(RigidBody::new(
+            Vector3::new(0.0, 0.5, 0.0),
+            1.0,
+            &Matrix3::identity(),
+        ));
+        let block = world.body_index(block).unwrap();
+        world.add_collider(Collider::new(
+            block,
+            Shape::cuboid(Vector3::new(0.5, 0.5, 0.5)),
+        ));
+        let bob = world.add_body(RigidBody::new(
+            Vector3::new(6.0, 3.0, 0.0),
+            1.0,
+            &Matrix3::identity(),
+        ));
+        let bob = world.body_index(bob).unwrap();
+        world.add_collider(Collider::new(bob, Shape::sphere(0.25)));
+        world.add_joint(Joint::new(
+            (bob, None),
+            JointKind::Ball(BallJoint::new(
+                Vector3::new(-2.0, 0.0, 0.0),
+                Vector3::new(4.0, 3.0, 0.0),
+            )),
+        ));
+        let orbiter = world.add_body(RigidBody::new(
+            Vector3::new(22.0, 0.0, 0.0),
+            1.0,
+            &Matrix3::identity(),
+        ));
+        let orbiter = world.body_index(orbiter).unwrap();
+        world.bodies[orbiter].velocity = Vector3::new(0.0, 3.0, 0.0);
+        world
+    };
+    let step = |world: &mut World| {
+        for _ in 0..30 {
+            world.start_frame();
+            world.run_physics(1.0 / 60.0);
+        }
+    };
+    let mut world = build();
+    let mut shifted = build();
+    step(&mut world);
+    step(&mut shifted);
+    shifted.drain_contact_events();
+
+    // Everything moves at once, so the simulation carries on as if nothing happened.
+    let offset = Vector3::new(1000.0, -50.0, 20000.0);
+    shifted.shift_origin(&offset);
+    let handle = shifted.body_handle(0);
+    let (position, _) = shifted.interpolated_pose(handle, 0.5).unwrap();
+    let original = world.body_handle(0);
+    let (expected, _) = world.interpolated_pose(original, 0.5).unwrap();
+    assert!((position.vector_add(&offset) - expected).magnitude() < 1e-9);
+    step(&mut world);
+    step(&mut shifted);
+    for (body, moved) in world.bodies.iter().zip(shifted.bodies.iter()) {
+        assert!((moved.position.vector_add(&offset) - body.position).magnitude() < 1e-6);
+        assert!((moved.velocity - body.velocity).magnitude() < 1e-6);
+    }
+    assert!(world.bodies[0].position.y > 0.4);
+    assert!(shifted
+        .drain_contact_events()
+        .all(|event| event.kind == ContactEventKind::Persisted));
+}
+
+#[test]
+fn snapshots() {
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let cuboid = Cuboid::new(Vector3::new(0.5, 0.5, 0.5));
+    for height in [0.5, 1.6, 2.7] {
+        let body = world.add_body(RigidBody::new(
+            Vector3::new(0.1 * height, height, 0.0),
+            1.0,
+            &cuboid.inertia_tensor(1.0),
+        ));
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Cuboid(cuboid)));
+    }
+    let step = |world: &mut World| {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    };
+    for _ in 0..30 {
+        step(&mut world);
+    }
+
+    // Rolling back and stepping again gives the same results as the first time.
+    let state = world.snapshot();
+    for _ in 0..30 {
+        step(&mut world);
+    }
+    let bodies = world.bodies.clone();
+    let events: Vec<_> = world.drain_contact_events().collect();
+    world.bodies[2].apply_impulse(&Vector3::new(50.0, 0.0, 0.0));
+    world.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+
+    world.restore(&state);
+    assert_eq!(3, world.bodies.len());
+    assert_eq!(state, world.snapshot());
+    for _ in 0..30 {
+        step(&mut world);
+    }
+    assert_eq!(bodies, world.bodies);
+    assert_eq!(events, world.drain_contact_events().collect::<Vec<_>>());
+}
+
+fn serializable<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+
+#[test]
+fn saving() {
+    serializable::<World<f64>>();
+    let mut world = World::<f64>::default();
+    world.gravity = GravityField::new(GravitySource::Uniform(Vector3::new(0.0, -10.0, 0.0)));
+    world
+        .planes
+        .push(Plane::new(Vector3::new(0.0, 1.0, 0.0), 0.0));
+    let sphere = Sphere::new(0.5);
+    for x in [0.0, 1.0] {
+        let body = world.add_body(RigidBody::new(
+            Vector3::new(x, 0.5 + x, 0.0),
+            1.0,
+            &sphere.inertia_tensor(1.0),
+        ));
+        let body = world.body_index(body).unwrap();
+        world.add_collider(Collider::new(body, Shape::Sphere(sphere)));
+    }
+    let ball = BallJoint::new(Vector3::new(1.0, 0.5, 0.0), Vector3::origin());
+    world.add_joint(Joint::new((0, Some(1)), JointKind::Ball(ball)));
+    world
+        .gravity_overrides
+        .insert(1, GravitySource::Uniform(Vector3::origin()));
+    let step = |world: &mut World| {
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+    };
+    for _ in 0..10 {
+        step(&mut world);
+    }
+
+    // Worlds loaded from a state carry on exactly like the original one.
+    let mut loaded = World::from(world.snapshot());
+    let mut cold = World::from(world.snapshot().without_caches());
+    assert_eq!(0, cold.manifolds.len());
+    for _ in 0..20 {
+        step(&mut world);
+        step(&mut loaded);
+        step(&mut cold);
+    }
+    assert_eq!(world.bodies, loaded.bodies);
+    let drift = (world.bodies[1].position - cold.bodies[1].position).magnitude();
+    assert!(drift < 0.01, "{}", drift);
+
+    // Appending a state to a world moves its handles after the ones already there.
+    let mut level = World::<f64>::default();
+    level.add_body(RigidBody::new(Vector3::origin(), 1.0, &Matrix3::identity()));
+    let remap = level.append(&world.snapshot());
+    assert_eq!(vec![1, 2], remap.bodies);
+    assert_eq!(vec![0, 1], remap.colliders);
+    assert_eq!(vec![0], remap.joints);
+    assert_eq!(3, level.bodies.len());
+    assert_eq!(2, level.colliders[1].body);
+    assert_eq!((1, Some(2)), level.joints[0].bodies);
+    assert!(level.gravity_overrides.contains_key(&2));
+    assert_eq!(world.bodies[1], level.bodies[remap.bodies[1]]);
+}
+
+#[test]
+fn tangential_sweep() {
+    // Bullets grazing the top of a floor are never moved backwards, nor past their motion.
+    for height in [0.55, 0.55 - 1e-12, 0.55 + 1e-12, 0.56] {
+        let mut world = bullet(true);
+        world.bodies[0].position = Vector3::new(2.5, 0.0, 0.0);
+        world.colliders[0].shape = Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 0.5, 1.0)));
+        world.bodies[1].position = Vector3::new(0.0, height, 0.0);
+        world.start_frame();
+        world.run_physics(1.0 / 60.0);
+        let position = world.bodies[1].position;
+        assert!(position.x > 1.5 && position.x <= 5.0);
+        assert!((position.y - height).abs() < 1e-9);
+    }
+
+    // Sliding right along the floor, they travel their whole motion.
+    let mut world = bullet(true);
+    world.bodies[0].position = Vector3::new(2.5, 0.0, 0.0);
+    world.colliders[0].shape = Shape::Cuboid(Cuboid::new(Vector3::new(1.0, 0.5, 1.0)));
+    world.bodies[1].position = Vector3::new(0.0, 0.55, 0.0);
+    world.start_frame();
+    world.run_physics(1.0 / 60.0);
+    assert!((world.bodies[1].position.x - 5.0).abs() < 0.01);
+}
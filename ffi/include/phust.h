@@ -0,0 +1,342 @@
+#ifndef PHUST_H
+#define PHUST_H
+
+/* C API of the `ffi` crate, regenerated with cbindgen from its `cbindgen.toml`. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Value returned instead of a handle when no object could be referred to.
+ */
+#define PHUST_INVALID_HANDLE UINT64_MAX
+
+/**
+ * World of rigid bodies, only ever handed to C through a pointer.
+ */
+typedef struct PhustWorld PhustWorld;
+
+/**
+ * Vector with three components, laid out the same way in C.
+ */
+typedef struct PhustVector3 {
+  /**
+   * First component of the vector.
+   */
+  double x;
+  /**
+   * Second component of the vector.
+   */
+  double y;
+  /**
+   * Third component of the vector.
+   */
+  double z;
+} PhustVector3;
+
+/**
+ * Quaternion representing an orientation, laid out the same way in C.
+ */
+typedef struct PhustQuaternion {
+  /**
+   * Real component of the quaternion.
+   */
+  double r;
+  /**
+   * First complex component of the quaternion.
+   */
+  double i;
+  /**
+   * Second complex component of the quaternion.
+   */
+  double j;
+  /**
+   * Third complex component of the quaternion.
+   */
+  double k;
+} PhustQuaternion;
+
+/**
+ * Hit of a ray cast against the colliders and scenery planes of a world.
+ */
+typedef struct PhustRayHit {
+  /**
+   * Handle of the collider hit, or `PHUST_INVALID_HANDLE` for scenery planes.
+   */
+  uint64_t collider;
+  /**
+   * Handle of the body the collider hit is attached to, or `PHUST_INVALID_HANDLE` for
+   * scenery planes.
+   */
+  uint64_t body;
+  /**
+   * Point hit in world space.
+   */
+  struct PhustVector3 point;
+  /**
+   * Unit normal of the surface hit, in world space.
+   */
+  struct PhustVector3 normal;
+  /**
+   * Time of impact along the ray, in multiples of its direction.
+   */
+  double toi;
+} PhustRayHit;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Adds a rigid body to the world, returning its handle.
+ *
+ * # Remarks
+ * Bodies with zero mass are static, ignoring their inertia. Otherwise, the inertia is given
+ * by the moments of its principal axes, which are the axes of the body. Returns
+ * `PHUST_INVALID_HANDLE` for null worlds.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+uint64_t phust_body_add(struct PhustWorld *world,
+                        struct PhustVector3 position,
+                        double mass,
+                        struct PhustVector3 inertia);
+
+/**
+ * Removes a rigid body from the world along with its colliders and joints, returning false
+ * if the handle no longer referred to a body.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+bool phust_body_remove(struct PhustWorld *world,
+                       uint64_t body);
+
+/**
+ * Writes the position of a rigid body, returning false if the handle no longer referred
+ * to a body.
+ *
+ * # Safety
+ * The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+ * the position pointer must be null or valid for writes.
+ */
+bool phust_body_get_position(struct PhustWorld *world,
+                             uint64_t body,
+                             struct PhustVector3 *position);
+
+/**
+ * Writes the orientation of a rigid body, returning false if the handle no longer referred
+ * to a body.
+ *
+ * # Safety
+ * The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+ * the orientation pointer must be null or valid for writes.
+ */
+bool phust_body_get_orientation(struct PhustWorld *world,
+                                uint64_t body,
+                                struct PhustQuaternion *orientation);
+
+/**
+ * Writes the linear velocity of a rigid body, returning false if the handle no longer
+ * referred to a body.
+ *
+ * # Safety
+ * The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+ * the velocity pointer must be null or valid for writes.
+ */
+bool phust_body_get_velocity(struct PhustWorld *world,
+                             uint64_t body,
+                             struct PhustVector3 *velocity);
+
+/**
+ * Writes the angular velocity of a rigid body, returning false if the handle no longer
+ * referred to a body.
+ *
+ * # Safety
+ * The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+ * the rotation pointer must be null or valid for writes.
+ */
+bool phust_body_get_rotation(struct PhustWorld *world,
+                             uint64_t body,
+                             struct PhustVector3 *rotation);
+
+/**
+ * Moves a rigid body to the given position and orientation, returning false if the handle
+ * no longer referred to a body.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+bool phust_body_set_transform(struct PhustWorld *world,
+                              uint64_t body,
+                              struct PhustVector3 position,
+                              struct PhustQuaternion orientation);
+
+/**
+ * Sets the linear and angular velocity of a rigid body, returning false if the handle no
+ * longer referred to a body.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+bool phust_body_set_velocity(struct PhustWorld *world,
+                             uint64_t body,
+                             struct PhustVector3 velocity,
+                             struct PhustVector3 rotation);
+
+/**
+ * Adds a force at the center of mass of a rigid body, to be applied during the next step
+ * only, returning false if the handle no longer referred to a body.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+bool phust_body_add_force(struct PhustWorld *world,
+                          uint64_t body,
+                          struct PhustVector3 force);
+
+/**
+ * Applies an impulse at the center of mass of a rigid body, returning false if the handle
+ * no longer referred to a body.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+bool phust_body_apply_impulse(struct PhustWorld *world,
+                              uint64_t body,
+                              struct PhustVector3 impulse);
+
+/**
+ * Attaches a sphere collider centered on the body of a handle, returning the handle of the
+ * collider, or `PHUST_INVALID_HANDLE` if the body handle was no longer valid.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+uint64_t phust_collider_add_sphere(struct PhustWorld *world,
+                                   uint64_t body,
+                                   double radius);
+
+/**
+ * Attaches a box collider centered on the body of a handle, returning the handle of the
+ * collider, or `PHUST_INVALID_HANDLE` if the body handle was no longer valid.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+uint64_t phust_collider_add_box(struct PhustWorld *world,
+                                uint64_t body,
+                                struct PhustVector3 half_size);
+
+/**
+ * Attaches a capsule collider along the Y axis of the body of a handle, returning the handle
+ * of the collider, or `PHUST_INVALID_HANDLE` if the body handle was no longer valid.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+uint64_t phust_collider_add_capsule(struct PhustWorld *world,
+                                    uint64_t body,
+                                    double half_height,
+                                    double radius);
+
+/**
+ * Sets the restitution and friction of a collider, returning false if the handle no longer
+ * referred to a collider.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+bool phust_collider_set_material(struct PhustWorld *world,
+                                 uint64_t collider,
+                                 double restitution,
+                                 double friction);
+
+/**
+ * Returns the handle of the body a collider is attached to, or `PHUST_INVALID_HANDLE` if the
+ * handle no longer referred to a collider.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+uint64_t phust_collider_body(struct PhustWorld *world,
+                             uint64_t collider);
+
+/**
+ * Casts a ray against the world, writing the closest hit within the given time of impact
+ * and returning false if nothing was hit.
+ *
+ * # Remarks
+ * The colliders of the body of `exclude_body` are ignored, unless it's `PHUST_INVALID_HANDLE`.
+ *
+ * # Safety
+ * The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+ * the hit pointer must be valid for writes.
+ */
+bool phust_world_raycast(struct PhustWorld *world,
+                         struct PhustVector3 origin,
+                         struct PhustVector3 direction,
+                         double max_toi,
+                         uint64_t exclude_body,
+                         struct PhustRayHit *hit);
+
+/**
+ * Creates a new world with the default configuration and no gravity.
+ *
+ * # Remarks
+ * The world must be freed with `phust_world_free`.
+ */
+struct PhustWorld *phust_world_new(void);
+
+/**
+ * Frees a world created with `phust_world_new`, doing nothing for null pointers.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+void phust_world_free(struct PhustWorld *world);
+
+/**
+ * Sets the gravity pulling every body of the world, replacing its whole gravity field.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+void phust_world_set_gravity(struct PhustWorld *world,
+                             struct PhustVector3 gravity);
+
+/**
+ * Adds a scenery plane to the world, which bodies collide with but never move, given by
+ * its unit normal and its offset from the origin along it.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+void phust_world_add_plane(struct PhustWorld *world,
+                           struct PhustVector3 normal,
+                           double offset);
+
+/**
+ * Starts a frame of the world and runs its physics for the given duration (in seconds).
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+void phust_world_step(struct PhustWorld *world,
+                      double duration);
+
+/**
+ * Returns the number of bodies in the world, or zero for null pointers.
+ *
+ * # Safety
+ * The pointer must be null or come from `phust_world_new` and not be freed yet.
+ */
+size_t phust_world_body_count(const struct PhustWorld *world);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PHUST_H */
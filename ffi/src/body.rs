@@ -0,0 +1,228 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::types::{PhustQuaternion, PhustVector3, PHUST_INVALID_HANDLE};
+use crate::world::{world_mut, PhustWorld};
+use core::handle::BodyHandle;
+use core::rigid_body::{BodyType, RigidBody};
+use math::{Matrix3, Vector3};
+
+/// Returns the body of a handle in the world behind a pointer, if both are still valid.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+unsafe fn body_mut<'a>(world: *mut PhustWorld, body: u64) -> Option<&'a mut RigidBody> {
+    world_mut(world)?.body_mut(BodyHandle::from_bits(body))
+}
+
+/// Writes the given value through a pointer, returning false if there was none to write.
+///
+/// # Safety
+/// The pointer must be null or valid for writes.
+unsafe fn write<T>(pointer: *mut T, value: Option<T>) -> bool {
+    match (pointer.is_null(), value) {
+        (false, Some(value)) => {
+            pointer.write(value);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Adds a rigid body to the world, returning its handle.
+///
+/// # Remarks
+/// Bodies with zero mass are static, ignoring their inertia. Otherwise, the inertia is given
+/// by the moments of its principal axes, which are the axes of the body. Returns
+/// `PHUST_INVALID_HANDLE` for null worlds.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_add(
+    world: *mut PhustWorld,
+    position: PhustVector3,
+    mass: f64,
+    inertia: PhustVector3,
+) -> u64 {
+    let world = match world_mut(world) {
+        Some(world) => world,
+        None => return PHUST_INVALID_HANDLE,
+    };
+    let mut body = if mass > 0.0 {
+        let inertia = Matrix3::diagonal(inertia.x, inertia.y, inertia.z);
+        RigidBody::new(position.into(), mass, &inertia)
+    } else {
+        let mut body = RigidBody::new(position.into(), 1.0, &Matrix3::identity());
+        body.set_body_type(BodyType::Static);
+        body
+    };
+    body.calculate_derived_data();
+    world.add_body(body).to_bits()
+}
+
+/// Removes a rigid body from the world along with its colliders and joints, returning false
+/// if the handle no longer referred to a body.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_remove(world: *mut PhustWorld, body: u64) -> bool {
+    world_mut(world)
+        .and_then(|world| world.remove_body(BodyHandle::from_bits(body)))
+        .is_some()
+}
+
+/// Writes the position of a rigid body, returning false if the handle no longer referred
+/// to a body.
+///
+/// # Safety
+/// The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+/// the position pointer must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_get_position(
+    world: *mut PhustWorld,
+    body: u64,
+    position: *mut PhustVector3,
+) -> bool {
+    write(
+        position,
+        body_mut(world, body).map(|body| body.position.into()),
+    )
+}
+
+/// Writes the orientation of a rigid body, returning false if the handle no longer referred
+/// to a body.
+///
+/// # Safety
+/// The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+/// the orientation pointer must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_get_orientation(
+    world: *mut PhustWorld,
+    body: u64,
+    orientation: *mut PhustQuaternion,
+) -> bool {
+    write(
+        orientation,
+        body_mut(world, body).map(|body| body.orientation.into()),
+    )
+}
+
+/// Writes the linear velocity of a rigid body, returning false if the handle no longer
+/// referred to a body.
+///
+/// # Safety
+/// The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+/// the velocity pointer must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_get_velocity(
+    world: *mut PhustWorld,
+    body: u64,
+    velocity: *mut PhustVector3,
+) -> bool {
+    write(
+        velocity,
+        body_mut(world, body).map(|body| body.velocity.into()),
+    )
+}
+
+/// Writes the angular velocity of a rigid body, returning false if the handle no longer
+/// referred to a body.
+///
+/// # Safety
+/// The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+/// the rotation pointer must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_get_rotation(
+    world: *mut PhustWorld,
+    body: u64,
+    rotation: *mut PhustVector3,
+) -> bool {
+    write(
+        rotation,
+        body_mut(world, body).map(|body| body.rotation.into()),
+    )
+}
+
+/// Moves a rigid body to the given position and orientation, returning false if the handle
+/// no longer referred to a body.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_set_transform(
+    world: *mut PhustWorld,
+    body: u64,
+    position: PhustVector3,
+    orientation: PhustQuaternion,
+) -> bool {
+    match body_mut(world, body) {
+        Some(body) => {
+            body.position = position.into();
+            body.orientation = orientation.into();
+            body.calculate_derived_data();
+            body.set_awake(true);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Sets the linear and angular velocity of a rigid body, returning false if the handle no
+/// longer referred to a body.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_set_velocity(
+    world: *mut PhustWorld,
+    body: u64,
+    velocity: PhustVector3,
+    rotation: PhustVector3,
+) -> bool {
+    match body_mut(world, body) {
+        Some(body) => {
+            body.set_awake(true);
+            body.velocity = velocity.into();
+            body.rotation = rotation.into();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Adds a force at the center of mass of a rigid body, to be applied during the next step
+/// only, returning false if the handle no longer referred to a body.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_add_force(
+    world: *mut PhustWorld,
+    body: u64,
+    force: PhustVector3,
+) -> bool {
+    body_mut(world, body)
+        .map(|body| body.add_force(&Vector3::from(force)))
+        .is_some()
+}
+
+/// Applies an impulse at the center of mass of a rigid body, returning false if the handle
+/// no longer referred to a body.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_body_apply_impulse(
+    world: *mut PhustWorld,
+    body: u64,
+    impulse: PhustVector3,
+) -> bool {
+    body_mut(world, body)
+        .map(|body| body.apply_impulse(&Vector3::from(impulse)))
+        .is_some()
+}
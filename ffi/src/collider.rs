@@ -0,0 +1,117 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::types::{PhustVector3, PHUST_INVALID_HANDLE};
+use crate::world::{world_mut, PhustWorld};
+use core::collider::Collider;
+use core::handle::{BodyHandle, ColliderHandle};
+use core::shape::{Capsule, Cuboid, Shape, Sphere};
+
+/// Attaches a collider with the given shape to the body of a handle, returning the handle
+/// of the collider, or `PHUST_INVALID_HANDLE` if the body handle was no longer valid.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+unsafe fn add(world: *mut PhustWorld, body: u64, shape: Shape) -> u64 {
+    let world = match world_mut(world) {
+        Some(world) => world,
+        None => return PHUST_INVALID_HANDLE,
+    };
+    match world.body_index(BodyHandle::from_bits(body)) {
+        Some(body) => world.add_collider(Collider::new(body, shape)).to_bits(),
+        None => PHUST_INVALID_HANDLE,
+    }
+}
+
+/// Attaches a sphere collider centered on the body of a handle, returning the handle of the
+/// collider, or `PHUST_INVALID_HANDLE` if the body handle was no longer valid.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_collider_add_sphere(
+    world: *mut PhustWorld,
+    body: u64,
+    radius: f64,
+) -> u64 {
+    add(world, body, Shape::Sphere(Sphere::new(radius)))
+}
+
+/// Attaches a box collider centered on the body of a handle, returning the handle of the
+/// collider, or `PHUST_INVALID_HANDLE` if the body handle was no longer valid.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_collider_add_box(
+    world: *mut PhustWorld,
+    body: u64,
+    half_size: PhustVector3,
+) -> u64 {
+    add(world, body, Shape::Cuboid(Cuboid::new(half_size.into())))
+}
+
+/// Attaches a capsule collider along the Y axis of the body of a handle, returning the handle
+/// of the collider, or `PHUST_INVALID_HANDLE` if the body handle was no longer valid.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_collider_add_capsule(
+    world: *mut PhustWorld,
+    body: u64,
+    half_height: f64,
+    radius: f64,
+) -> u64 {
+    add(
+        world,
+        body,
+        Shape::Capsule(Capsule::new(half_height, radius)),
+    )
+}
+
+/// Sets the restitution and friction of a collider, returning false if the handle no longer
+/// referred to a collider.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_collider_set_material(
+    world: *mut PhustWorld,
+    collider: u64,
+    restitution: f64,
+    friction: f64,
+) -> bool {
+    match world_mut(world).and_then(|world| world.collider_mut(ColliderHandle::from_bits(collider)))
+    {
+        Some(collider) => {
+            collider.material.restitution = restitution;
+            collider.material.friction = friction;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Returns the handle of the body a collider is attached to, or `PHUST_INVALID_HANDLE` if the
+/// handle no longer referred to a collider.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_collider_body(world: *mut PhustWorld, collider: u64) -> u64 {
+    let world = match world_mut(world) {
+        Some(world) => world,
+        None => return PHUST_INVALID_HANDLE,
+    };
+    match world.collider(ColliderHandle::from_bits(collider)) {
+        Some(collider) => {
+            let body = collider.body;
+            world.body_handle(body).to_bits()
+        }
+        None => PHUST_INVALID_HANDLE,
+    }
+}
@@ -0,0 +1,23 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use std::path::Path;
+
+#[test]
+fn header() {
+    // The header matches the one cbindgen generates from the exports of the crate.
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(root.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(root.join("src").join("lib.rs"))
+        .generate()
+        .unwrap()
+        .write(&mut generated);
+    let header = std::fs::read_to_string(root.join("include").join("phust.h")).unwrap();
+    assert_eq!(String::from_utf8(generated).unwrap(), header);
+}
@@ -0,0 +1,19 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+extern crate core;
+extern crate math;
+
+pub mod body;
+pub mod collider;
+pub mod query;
+pub mod types;
+pub mod world;
+
+#[cfg(test)]
+mod header_test;
+#[cfg(test)]
+mod world_test;
@@ -0,0 +1,80 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::types::{PhustVector3, PHUST_INVALID_HANDLE};
+use crate::world::{world_mut, PhustWorld};
+use core::handle::BodyHandle;
+use core::query::QueryFilter;
+use core::ray::Ray;
+
+/// Hit of a ray cast against the colliders and scenery planes of a world.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PhustRayHit {
+    /// Handle of the collider hit, or `PHUST_INVALID_HANDLE` for scenery planes.
+    pub collider: u64,
+
+    /// Handle of the body the collider hit is attached to, or `PHUST_INVALID_HANDLE` for
+    /// scenery planes.
+    pub body: u64,
+
+    /// Point hit in world space.
+    pub point: PhustVector3,
+
+    /// Unit normal of the surface hit, in world space.
+    pub normal: PhustVector3,
+
+    /// Time of impact along the ray, in multiples of its direction.
+    pub toi: f64,
+}
+
+/// Casts a ray against the world, writing the closest hit within the given time of impact
+/// and returning false if nothing was hit.
+///
+/// # Remarks
+/// The colliders of the body of `exclude_body` are ignored, unless it's `PHUST_INVALID_HANDLE`.
+///
+/// # Safety
+/// The world pointer must be null or come from `phust_world_new` and not be freed yet, and
+/// the hit pointer must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn phust_world_raycast(
+    world: *mut PhustWorld,
+    origin: PhustVector3,
+    direction: PhustVector3,
+    max_toi: f64,
+    exclude_body: u64,
+    hit: *mut PhustRayHit,
+) -> bool {
+    let world = match world_mut(world) {
+        Some(world) => world,
+        None => return false,
+    };
+    let mut filter = QueryFilter::new();
+    if exclude_body != PHUST_INVALID_HANDLE {
+        filter.exclude_body = world.body_index(BodyHandle::from_bits(exclude_body));
+    }
+    let found = world.raycast(&Ray::new(origin.into(), direction.into()), max_toi, &filter);
+    match found {
+        Some(found) if !hit.is_null() => {
+            let collider = found.collider.map_or(PHUST_INVALID_HANDLE, |collider| {
+                world.collider_handle(collider).to_bits()
+            });
+            let body = found.body.map_or(PHUST_INVALID_HANDLE, |body| {
+                world.body_handle(body).to_bits()
+            });
+            hit.write(PhustRayHit {
+                collider,
+                body,
+                point: found.point.into(),
+                normal: found.normal.into(),
+                toi: found.toi,
+            });
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use math::{Quaternion, Vector3};
+
+/// Value returned instead of a handle when no object could be referred to.
+pub const PHUST_INVALID_HANDLE: u64 = u64::MAX;
+
+/// Vector with three components, laid out the same way in C.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Default, Debug)]
+pub struct PhustVector3 {
+    /// First component of the vector.
+    pub x: f64,
+
+    /// Second component of the vector.
+    pub y: f64,
+
+    /// Third component of the vector.
+    pub z: f64,
+}
+
+impl From<Vector3> for PhustVector3 {
+    fn from(vector: Vector3) -> Self {
+        Self {
+            x: vector.x,
+            y: vector.y,
+            z: vector.z,
+        }
+    }
+}
+
+impl From<PhustVector3> for Vector3 {
+    fn from(vector: PhustVector3) -> Self {
+        Vector3::new(vector.x, vector.y, vector.z)
+    }
+}
+
+/// Quaternion representing an orientation, laid out the same way in C.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Default, Debug)]
+pub struct PhustQuaternion {
+    /// Real component of the quaternion.
+    pub r: f64,
+
+    /// First complex component of the quaternion.
+    pub i: f64,
+
+    /// Second complex component of the quaternion.
+    pub j: f64,
+
+    /// Third complex component of the quaternion.
+    pub k: f64,
+}
+
+impl From<Quaternion> for PhustQuaternion {
+    fn from(quaternion: Quaternion) -> Self {
+        Self {
+            r: quaternion.r,
+            i: quaternion.i,
+            j: quaternion.j,
+            k: quaternion.k,
+        }
+    }
+}
+
+impl From<PhustQuaternion> for Quaternion {
+    fn from(quaternion: PhustQuaternion) -> Self {
+        Quaternion::new(quaternion.r, quaternion.i, quaternion.j, quaternion.k)
+    }
+}
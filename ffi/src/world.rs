@@ -0,0 +1,89 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::types::PhustVector3;
+use core::gravity::{GravityField, GravitySource};
+use core::plane::Plane;
+use core::world::World;
+
+/// World of rigid bodies, only ever handed to C through a pointer.
+pub struct PhustWorld(pub World);
+
+/// Returns the world behind a pointer, if it isn't null.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+pub(crate) unsafe fn world_mut<'a>(world: *mut PhustWorld) -> Option<&'a mut World> {
+    world.as_mut().map(|world| &mut world.0)
+}
+
+/// Creates a new world with the default configuration and no gravity.
+///
+/// # Remarks
+/// The world must be freed with `phust_world_free`.
+#[no_mangle]
+pub extern "C" fn phust_world_new() -> *mut PhustWorld {
+    Box::into_raw(Box::new(PhustWorld(World::default())))
+}
+
+/// Frees a world created with `phust_world_new`, doing nothing for null pointers.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_world_free(world: *mut PhustWorld) {
+    if !world.is_null() {
+        drop(Box::from_raw(world));
+    }
+}
+
+/// Sets the gravity pulling every body of the world, replacing its whole gravity field.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_world_set_gravity(world: *mut PhustWorld, gravity: PhustVector3) {
+    if let Some(world) = world_mut(world) {
+        world.gravity = GravityField::new(GravitySource::Uniform(gravity.into()));
+    }
+}
+
+/// Adds a scenery plane to the world, which bodies collide with but never move, given by
+/// its unit normal and its offset from the origin along it.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_world_add_plane(
+    world: *mut PhustWorld,
+    normal: PhustVector3,
+    offset: f64,
+) {
+    if let Some(world) = world_mut(world) {
+        world.planes.push(Plane::new(normal.into(), offset));
+    }
+}
+
+/// Starts a frame of the world and runs its physics for the given duration (in seconds).
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_world_step(world: *mut PhustWorld, duration: f64) {
+    if let Some(world) = world_mut(world) {
+        world.start_frame();
+        world.run_physics(duration);
+    }
+}
+
+/// Returns the number of bodies in the world, or zero for null pointers.
+///
+/// # Safety
+/// The pointer must be null or come from `phust_world_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn phust_world_body_count(world: *const PhustWorld) -> usize {
+    world.as_ref().map_or(0, |world| world.0.body_count())
+}
@@ -0,0 +1,110 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::body::*;
+use crate::collider::*;
+use crate::query::*;
+use crate::types::*;
+use crate::world::*;
+
+fn vector(x: f64, y: f64, z: f64) -> PhustVector3 {
+    PhustVector3 { x, y, z }
+}
+
+#[test]
+fn world() {
+    unsafe {
+        let world = phust_world_new();
+        phust_world_set_gravity(world, vector(0.0, -10.0, 0.0));
+        phust_world_add_plane(world, vector(0.0, 1.0, 0.0), 0.0);
+        let ground = phust_body_add(world, vector(5.0, 0.5, 0.0), 0.0, vector(0.0, 0.0, 0.0));
+        let ground_box = phust_collider_add_box(world, ground, vector(0.5, 0.5, 0.5));
+        let ball = phust_body_add(world, vector(0.0, 2.0, 0.0), 1.0, vector(0.4, 0.4, 0.4));
+        let sphere = phust_collider_add_sphere(world, ball, 0.5);
+        assert_eq!(ball, phust_collider_body(world, sphere));
+        assert!(phust_collider_set_material(world, sphere, 0.0, 0.5));
+        for _ in 0..120 {
+            phust_world_step(world, 1.0 / 60.0);
+        }
+
+        // The ball falls onto the plane and rests there, while the fixed body never moves.
+        let mut position = PhustVector3::default();
+        assert!(phust_body_get_position(world, ball, &mut position));
+        assert!((position.y - 0.5).abs() < 0.05);
+        let mut orientation = PhustQuaternion::default();
+        assert!(phust_body_get_orientation(world, ground, &mut orientation));
+        assert_eq!(1.0, orientation.r);
+        assert!(phust_body_set_velocity(
+            world,
+            ball,
+            vector(1.0, 0.0, 0.0),
+            vector(0.0, 0.0, 0.0),
+        ));
+        let mut velocity = PhustVector3::default();
+        assert!(phust_body_get_velocity(world, ball, &mut velocity));
+        assert_eq!(1.0, velocity.x);
+
+        // Rays hit the colliders and planes, reporting them by handle.
+        let mut hit = PhustRayHit {
+            collider: 0,
+            body: 0,
+            point: PhustVector3::default(),
+            normal: PhustVector3::default(),
+            toi: 0.0,
+        };
+        let ray = (vector(2.0, 0.5, 0.0), vector(1.0, 0.0, 0.0));
+        assert!(phust_world_raycast(
+            world,
+            ray.0,
+            ray.1,
+            10.0,
+            PHUST_INVALID_HANDLE,
+            &mut hit
+        ));
+        assert_eq!((ground_box, ground), (hit.collider, hit.body));
+        assert!((hit.toi - 2.5).abs() < 1e-9);
+        assert!(!phust_world_raycast(
+            world, ray.0, ray.1, 10.0, ground, &mut hit
+        ));
+        let down = vector(0.0, -1.0, 0.0);
+        assert!(phust_world_raycast(
+            world,
+            vector(-2.0, 1.0, 0.0),
+            down,
+            10.0,
+            PHUST_INVALID_HANDLE,
+            &mut hit
+        ));
+        assert_eq!(PHUST_INVALID_HANDLE, hit.body);
+
+        // Removed bodies take their colliders with them, and their handles stop resolving.
+        assert!(phust_body_remove(world, ball));
+        assert!(!phust_body_remove(world, ball));
+        assert!(!phust_body_get_position(world, ball, &mut position));
+        assert_eq!(PHUST_INVALID_HANDLE, phust_collider_body(world, sphere));
+        assert_eq!(
+            PHUST_INVALID_HANDLE,
+            phust_collider_add_sphere(world, ball, 0.5)
+        );
+        assert_eq!(1, phust_world_body_count(world));
+        assert!(phust_body_get_position(world, ground, &mut position));
+        assert_eq!(5.0, position.x);
+        phust_world_free(world);
+
+        // Null worlds are ignored.
+        phust_world_step(std::ptr::null_mut(), 1.0);
+        assert_eq!(0, phust_world_body_count(std::ptr::null()));
+        assert_eq!(
+            PHUST_INVALID_HANDLE,
+            phust_body_add(
+                std::ptr::null_mut(),
+                vector(0.0, 0.0, 0.0),
+                1.0,
+                vector(1.0, 1.0, 1.0)
+            )
+        );
+    }
+}
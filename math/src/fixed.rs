@@ -0,0 +1,457 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::soft;
+use num_traits::{Float, Num, NumCast, One, ParseFloatError, ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
+use std::num::FpCategory;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// Number of bits of a fixed-point number after the binary point.
+pub const FRACTIONAL_BITS: u32 = 16;
+
+/// Bits of the fixed-point number one.
+const ONE_BITS: i64 = 1 << FRACTIONAL_BITS;
+
+/// Bits of the largest fixed-point number, also standing for infinity.
+const MAX_BITS: i64 = i64::MAX;
+
+/// Fixed-point number with 48 integer bits and 16 fractional bits.
+///
+/// # Remarks
+/// Every operation works on integers, so the results are the same on every compiler and
+/// platform, which makes the type suited to lockstep simulations. It implements
+/// `num_traits::Float`, so the engine can be instantiated over it.
+///
+/// Arithmetic saturates instead of overflowing, with the largest numbers standing for the
+/// infinities. There is no NaN: operations without a result, like the square root of a
+/// negative number or a division of zero by zero, return zero. Multiplications and
+/// divisions round to the nearest number, and the square root and transcendental functions
+/// are computed in software from them.
+#[derive(
+    Copy, Clone, Default, Eq, PartialEq, Hash, Debug, Ord, PartialOrd, Serialize, Deserialize,
+)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    /// Creates a new fixed-point number from its raw bits.
+    pub fn from_bits(bits: i64) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw bits of the fixed-point number.
+    pub fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    /// Creates a new fixed-point number from an integer, saturating if it doesn't fit.
+    pub fn from_int(value: i64) -> Self {
+        Self::saturate((value as i128) << FRACTIONAL_BITS)
+    }
+
+    /// Clamps a wide result into the range of fixed-point numbers.
+    fn saturate(bits: i128) -> Self {
+        Self(bits.clamp(-(MAX_BITS as i128), MAX_BITS as i128) as i64)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::saturate((self.0 as i128) + (other.0 as i128))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::saturate((self.0 as i128) - (other.0 as i128))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let product = (self.0 as i128) * (other.0 as i128);
+        Self::saturate((product + (1 << (FRACTIONAL_BITS - 1))) >> FRACTIONAL_BITS)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        if other.0 == 0 {
+            return match self.0.signum() {
+                1 => Self::infinity(),
+                -1 => Self::neg_infinity(),
+                _ => Self::zero(),
+            };
+        }
+        let numerator = (self.0 as i128) << FRACTIONAL_BITS;
+        let denominator = other.0 as i128;
+        let mut quotient = numerator / denominator;
+        if 2 * (numerator % denominator).abs() >= denominator.abs() {
+            quotient += numerator.signum() * denominator.signum();
+        }
+        Self::saturate(quotient)
+    }
+}
+
+impl Rem for Fixed {
+    type Output = Self;
+
+    fn rem(self, other: Self) -> Self {
+        if other.0 == 0 {
+            return Self::zero();
+        }
+        Self(self.0 % other.0)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::saturate(-(self.0 as i128))
+    }
+}
+
+impl Zero for Fixed {
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl One for Fixed {
+    fn one() -> Self {
+        Self(ONE_BITS)
+    }
+}
+
+impl Num for Fixed {
+    type FromStrRadixErr = ParseFloatError;
+
+    fn from_str_radix(text: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let value = f64::from_str_radix(text, radix)?;
+        Ok(<Self as NumCast>::from(value).unwrap_or_else(Self::zero))
+    }
+}
+
+impl ToPrimitive for Fixed {
+    fn to_i64(&self) -> Option<i64> {
+        Some(self.trunc().0 >> FRACTIONAL_BITS)
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.to_i64().and_then(|value| value.to_u64())
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.0 as f64 / ONE_BITS as f64)
+    }
+}
+
+impl NumCast for Fixed {
+    fn from<T: ToPrimitive>(value: T) -> Option<Self> {
+        // Scaling by a power of two is exact, so the conversion is the same everywhere.
+        let scaled = value.to_f64()? * ONE_BITS as f64;
+        if scaled.is_nan() || scaled.abs() >= MAX_BITS as f64 {
+            return None;
+        }
+        Some(Self(scaled.round() as i64))
+    }
+}
+
+impl Float for Fixed {
+    fn nan() -> Self {
+        Self::zero()
+    }
+
+    fn infinity() -> Self {
+        Self(MAX_BITS)
+    }
+
+    fn neg_infinity() -> Self {
+        Self(-MAX_BITS)
+    }
+
+    fn neg_zero() -> Self {
+        Self::zero()
+    }
+
+    fn min_value() -> Self {
+        Self(1 - MAX_BITS)
+    }
+
+    fn min_positive_value() -> Self {
+        Self(1)
+    }
+
+    fn epsilon() -> Self {
+        Self(1)
+    }
+
+    fn max_value() -> Self {
+        Self(MAX_BITS - 1)
+    }
+
+    fn is_nan(self) -> bool {
+        false
+    }
+
+    fn is_infinite(self) -> bool {
+        self.0 == MAX_BITS || self.0 == -MAX_BITS
+    }
+
+    fn is_finite(self) -> bool {
+        !self.is_infinite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.0 != 0 && self.is_finite()
+    }
+
+    fn classify(self) -> FpCategory {
+        if self.0 == 0 {
+            FpCategory::Zero
+        } else if self.is_infinite() {
+            FpCategory::Infinite
+        } else {
+            FpCategory::Normal
+        }
+    }
+
+    fn floor(self) -> Self {
+        Self(self.0 & !(ONE_BITS - 1))
+    }
+
+    fn ceil(self) -> Self {
+        -(-self).floor()
+    }
+
+    fn round(self) -> Self {
+        let half = Self(ONE_BITS / 2);
+        if self.0 >= 0 {
+            (self + half).floor()
+        } else {
+            -(half - self).floor()
+        }
+    }
+
+    fn trunc(self) -> Self {
+        if self.0 >= 0 {
+            self.floor()
+        } else {
+            self.ceil()
+        }
+    }
+
+    fn fract(self) -> Self {
+        self - self.trunc()
+    }
+
+    fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    fn signum(self) -> Self {
+        if self.0 < 0 {
+            -Self::one()
+        } else {
+            Self::one()
+        }
+    }
+
+    fn is_sign_positive(self) -> bool {
+        self.0 >= 0
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        Self::one() / self
+    }
+
+    fn powi(self, exponent: i32) -> Self {
+        let mut base = self;
+        let mut remaining = exponent.unsigned_abs();
+        let mut result = Self::one();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            remaining >>= 1;
+        }
+        if exponent < 0 {
+            result.recip()
+        } else {
+            result
+        }
+    }
+
+    fn powf(self, exponent: Self) -> Self {
+        soft::powf(self, exponent)
+    }
+
+    fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::zero();
+        }
+
+        // Integer square root of the bits scaled once more, found digit by digit.
+        let mut remainder = (self.0 as u128) << FRACTIONAL_BITS;
+        let mut bit = 1u128 << ((127 - remainder.leading_zeros()) & !1);
+        let mut root = 0u128;
+        while bit != 0 {
+            if remainder >= root + bit {
+                remainder -= root + bit;
+                root = (root >> 1) + bit;
+            } else {
+                root >>= 1;
+            }
+            bit >>= 2;
+        }
+        Self(root as i64)
+    }
+
+    fn exp(self) -> Self {
+        soft::exp(self)
+    }
+
+    fn exp2(self) -> Self {
+        soft::exp(self * crate::real(std::f64::consts::LN_2))
+    }
+
+    fn ln(self) -> Self {
+        soft::ln(self)
+    }
+
+    fn log(self, base: Self) -> Self {
+        soft::ln(self) / soft::ln(base)
+    }
+
+    fn log2(self) -> Self {
+        soft::ln(self) / crate::real(std::f64::consts::LN_2)
+    }
+
+    fn log10(self) -> Self {
+        soft::ln(self) / crate::real(std::f64::consts::LN_10)
+    }
+
+    fn max(self, other: Self) -> Self {
+        std::cmp::max(self, other)
+    }
+
+    fn min(self, other: Self) -> Self {
+        std::cmp::min(self, other)
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        Float::max(self - other, Self::zero())
+    }
+
+    fn cbrt(self) -> Self {
+        if self.0 == 0 {
+            return self;
+        }
+        let root = soft::exp(soft::ln(self.abs()) / crate::real(3.0));
+        if self.0 < 0 {
+            -root
+        } else {
+            root
+        }
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        (self * self + other * other).sqrt()
+    }
+
+    fn sin(self) -> Self {
+        soft::sin(self)
+    }
+
+    fn cos(self) -> Self {
+        soft::cos(self)
+    }
+
+    fn tan(self) -> Self {
+        let (sine, cosine) = soft::sin_cos(self);
+        sine / cosine
+    }
+
+    fn asin(self) -> Self {
+        soft::atan2(self, (Self::one() - self * self).sqrt())
+    }
+
+    fn acos(self) -> Self {
+        soft::atan2((Self::one() - self * self).sqrt(), self)
+    }
+
+    fn atan(self) -> Self {
+        soft::atan(self)
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        soft::atan2(self, other)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        soft::sin_cos(self)
+    }
+
+    fn exp_m1(self) -> Self {
+        soft::exp(self) - Self::one()
+    }
+
+    fn ln_1p(self) -> Self {
+        soft::ln(self + Self::one())
+    }
+
+    fn sinh(self) -> Self {
+        (soft::exp(self) - soft::exp(-self)) / crate::real(2.0)
+    }
+
+    fn cosh(self) -> Self {
+        (soft::exp(self) + soft::exp(-self)) / crate::real(2.0)
+    }
+
+    fn tanh(self) -> Self {
+        let (up, down) = (soft::exp(self), soft::exp(-self));
+        (up - down) / (up + down)
+    }
+
+    fn asinh(self) -> Self {
+        soft::ln(self + (self * self + Self::one()).sqrt())
+    }
+
+    fn acosh(self) -> Self {
+        soft::ln(self + (self * self - Self::one()).sqrt())
+    }
+
+    fn atanh(self) -> Self {
+        soft::ln((Self::one() + self) / (Self::one() - self)) / crate::real(2.0)
+    }
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        let sign = if self.0 < 0 { -1 } else { 1 };
+        (self.0.unsigned_abs(), -(FRACTIONAL_BITS as i16), sign)
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use super::*;
+use num_traits::{Float, One, ToPrimitive, Zero};
+
+fn fixed(value: f64) -> Fixed {
+    real(value)
+}
+
+fn assert_close(expected: f64, actual: Fixed, tolerance: f64) {
+    let actual = actual.to_f64().unwrap();
+    assert!(
+        (expected - actual).abs() <= tolerance,
+        "{} != {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(1 << FRACTIONAL_BITS, Fixed::one().to_bits());
+    assert_eq!(Fixed::from_int(3), fixed(1.5) + fixed(1.5));
+    assert_eq!(fixed(-0.25), fixed(1.25) - fixed(1.5));
+    assert_eq!(fixed(3.75), fixed(1.5) * fixed(2.5));
+    assert_eq!(fixed(-0.6), fixed(-1.5) / fixed(2.5));
+    assert_eq!(fixed(0.5), fixed(2.5) % fixed(1.0));
+    assert_eq!(Fixed::infinity(), fixed(1.0) / Fixed::zero());
+    assert_eq!(Fixed::infinity(), Fixed::max_value() + fixed(2.0));
+    assert_eq!(Fixed::neg_infinity(), -Fixed::max_value() * fixed(2.0));
+    assert!(Fixed::infinity().is_infinite());
+    assert!(!Fixed::max_value().is_infinite());
+    assert_eq!(Fixed::from_bits(1), Fixed::epsilon());
+
+    assert_eq!(fixed(-2.0), fixed(-1.5).floor());
+    assert_eq!(fixed(-1.0), fixed(-1.5).ceil());
+    assert_eq!(fixed(-2.0), fixed(-1.5).round());
+    assert_eq!(fixed(2.0), fixed(1.5).round());
+    assert_eq!(fixed(-1.0), fixed(-1.75).trunc());
+    assert_eq!(fixed(-0.75), fixed(-1.75).fract());
+    assert_eq!(Some(-1), fixed(-1.75).to_i64());
+    assert_eq!(fixed(0.125), fixed(0.5).powi(3));
+    assert_eq!(fixed(4.0), fixed(0.5).powi(-2));
+}
+
+#[test]
+fn functions() {
+    assert_eq!(fixed(1.5), fixed(2.25).sqrt());
+    assert_eq!(Fixed::zero(), fixed(-4.0).sqrt());
+    assert_close(2.0.sqrt(), fixed(2.0).sqrt(), 2e-5);
+    assert_close(1000.0.sqrt(), fixed(1000.0).sqrt(), 2e-5);
+
+    for x in [-3.0f64, -1.0, 0.0, 0.5, 1.5, 3.0, 10.0] {
+        assert_close(x.sin(), fixed(x).sin(), 1e-4);
+        assert_close(x.cos(), fixed(x).cos(), 1e-4);
+        assert_close(x.atan(), fixed(x).atan(), 1e-4);
+    }
+    assert_close(f64::atan2(-1.0, -2.0), fixed(-1.0).atan2(fixed(-2.0)), 1e-4);
+    assert_close(0.5.acos(), fixed(0.5).acos(), 1e-4);
+    assert_close(2.0.exp(), fixed(2.0).exp(), 1e-3);
+    assert_close(10.0.ln(), fixed(10.0).ln(), 1e-4);
+    assert_close(0.95.powf(0.1), fixed(0.95).powf(fixed(0.1)), 1e-4);
+    assert_close(-3.0, fixed(-27.0).cbrt(), 1e-3);
+
+    // Vectors work over fixed-point numbers just like over floating point ones.
+    let vector = Vector3::new(fixed(3.0), fixed(0.0), fixed(4.0));
+    assert_eq!(fixed(5.0), vector.magnitude());
+    assert_eq!(
+        Vector3::new(fixed(0.6), fixed(0.0), fixed(0.8)),
+        vector.normalize()
+    );
+}
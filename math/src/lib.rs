@@ -7,11 +7,55 @@
 extern crate num_traits;
 extern crate serde;
 
+#[cfg(feature = "fixed")]
+mod fixed;
+mod matrix3;
+mod matrix4;
+mod quaternion;
+mod vector2;
+
+/// Transcendental functions computed in software.
+///
+/// # Remarks
+/// The functions of the standard library call into the math library of the platform, whose
+/// results may differ in the last bits from one platform to another. These functions only use
+/// additions, subtractions, multiplications, divisions and square roots, which IEEE 754
+/// requires to be correctly rounded, so they return the same bits everywhere.
+pub mod soft;
+
+#[cfg(all(test, feature = "fixed"))]
+mod fixed_test;
+#[cfg(test)]
+mod matrix3_test;
+#[cfg(test)]
+mod matrix4_test;
+#[cfg(test)]
+mod quaternion_test;
+#[cfg(test)]
+mod soft_test;
+#[cfg(test)]
+mod vector2_test;
 #[cfg(test)]
 mod vector3_test;
 
+#[cfg(feature = "fixed")]
+pub use fixed::{Fixed, FRACTIONAL_BITS};
+pub use matrix3::Matrix3;
+pub use matrix4::Matrix4;
+pub use quaternion::Quaternion;
+pub use vector2::Vector2;
+
 use serde::{Deserialize, Serialize};
 
+/// Converts an `f64` literal into the floating point type `F`.
+///
+/// # Remarks
+/// Every `num_traits::Float` is able to represent the constants used across the engine,
+/// so the conversion is expected to never fail.
+pub fn real<F: num_traits::Float>(value: f64) -> F {
+    F::from(value).expect("constant representable by floating point type")
+}
+
 /// Vector in 3 dimensions.
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Ord, PartialOrd, Serialize, Deserialize)]
 pub struct Vector3<F: num_traits::Float = f64> {
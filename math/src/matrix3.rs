@@ -0,0 +1,278 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::{Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Holds a 3x3 row-major matrix.
+/// Most commonly used to represent inertia tensors and rotations.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Matrix3<F: num_traits::Float = f64> {
+    /// Holds the tensor matrix data in array form.
+    pub data: [F; 9],
+}
+
+impl<F: num_traits::Float> Matrix3<F> {
+    /// Creates a matrix with all its elements set to `0`.
+    pub fn zero() -> Self {
+        Self {
+            data: [num_traits::zero(); 9],
+        }
+    }
+
+    /// Creates an identity matrix.
+    pub fn identity() -> Self {
+        Self::diagonal(num_traits::one(), num_traits::one(), num_traits::one())
+    }
+
+    /// Creates a new matrix with the given elements, in row-major order.
+    pub fn new(data: [F; 9]) -> Self {
+        Self { data }
+    }
+
+    /// Creates a diagonal matrix with the given coefficients.
+    pub fn diagonal(a: F, b: F, c: F) -> Self {
+        let mut matrix = Self::zero();
+        matrix.data[0] = a;
+        matrix.data[4] = b;
+        matrix.data[8] = c;
+        matrix
+    }
+
+    /// Creates a matrix whose columns are the given vectors.
+    pub fn from_components(one: &Vector3<F>, two: &Vector3<F>, three: &Vector3<F>) -> Self {
+        Self {
+            data: [
+                one.x, two.x, three.x, one.y, two.y, three.y, one.z, two.z, three.z,
+            ],
+        }
+    }
+
+    /// Creates a skew symmetric matrix from the given vector.
+    /// The skew symmetric matrix is the equivalent of the vector product:
+    /// if `a` and `b` are vectors, `a x b = A_s b`, where `A_s` is the skew symmetric form of `a`.
+    pub fn skew_symmetric(vector: &Vector3<F>) -> Self {
+        let zero = num_traits::zero();
+        Self {
+            data: [
+                zero, -vector.z, vector.y, vector.z, zero, -vector.x, -vector.y, vector.x, zero,
+            ],
+        }
+    }
+
+    /// Creates a rotation matrix from the given orientation quaternion.
+    pub fn from_orientation(q: &Quaternion<F>) -> Self {
+        let one: F = num_traits::one();
+        let two: F = crate::real(2.0);
+        Self {
+            data: [
+                one - (two * q.j * q.j + two * q.k * q.k),
+                two * q.i * q.j - two * q.k * q.r,
+                two * q.i * q.k + two * q.j * q.r,
+                two * q.i * q.j + two * q.k * q.r,
+                one - (two * q.i * q.i + two * q.k * q.k),
+                two * q.j * q.k - two * q.i * q.r,
+                two * q.i * q.k - two * q.j * q.r,
+                two * q.j * q.k + two * q.i * q.r,
+                one - (two * q.i * q.i + two * q.j * q.j),
+            ],
+        }
+    }
+
+    /// Creates an inertia tensor from the given coefficients.
+    /// `ix`, `iy` and `iz` are the moments of inertia, and `ixy`, `ixz`, `iyz`
+    /// the products of inertia.
+    pub fn inertia_tensor(ix: F, iy: F, iz: F, ixy: F, ixz: F, iyz: F) -> Self {
+        Self {
+            data: [ix, -ixy, -ixz, -ixy, iy, -iyz, -ixz, -iyz, iz],
+        }
+    }
+
+    /// Creates the inertia tensor of a rectangular block aligned with the body's
+    /// coordinate system, with the given axis half-sizes and mass.
+    pub fn block_inertia_tensor(half_sizes: &Vector3<F>, mass: F) -> Self {
+        let squares = half_sizes.vector_mul(half_sizes);
+        let third: F = crate::real(1.0 / 3.0);
+        Self::diagonal(
+            third * mass * (squares.y + squares.z),
+            third * mass * (squares.x + squares.z),
+            third * mass * (squares.x + squares.y),
+        )
+    }
+
+    /// Returns the given row of the matrix as a vector.
+    pub fn row(&self, index: usize) -> Vector3<F> {
+        Vector3::new(
+            self.data[index * 3],
+            self.data[index * 3 + 1],
+            self.data[index * 3 + 2],
+        )
+    }
+
+    /// Returns the given column of the matrix as a vector.
+    pub fn column(&self, index: usize) -> Vector3<F> {
+        Vector3::new(self.data[index], self.data[index + 3], self.data[index + 6])
+    }
+
+    /// Transforms the given vector by this matrix.
+    pub fn transform(&self, vector: &Vector3<F>) -> Vector3<F> {
+        let d = &self.data;
+        Vector3::new(
+            vector.x * d[0] + vector.y * d[1] + vector.z * d[2],
+            vector.x * d[3] + vector.y * d[4] + vector.z * d[5],
+            vector.x * d[6] + vector.y * d[7] + vector.z * d[8],
+        )
+    }
+
+    /// Transforms the given vector by the transpose of this matrix.
+    pub fn transform_transpose(&self, vector: &Vector3<F>) -> Vector3<F> {
+        let d = &self.data;
+        Vector3::new(
+            vector.x * d[0] + vector.y * d[3] + vector.z * d[6],
+            vector.x * d[1] + vector.y * d[4] + vector.z * d[7],
+            vector.x * d[2] + vector.y * d[5] + vector.z * d[8],
+        )
+    }
+
+    /// Returns the determinant of the matrix.
+    pub fn determinant(&self) -> F {
+        let d = &self.data;
+        d[0] * (d[4] * d[8] - d[5] * d[7]) - d[1] * (d[3] * d[8] - d[5] * d[6])
+            + d[2] * (d[3] * d[7] - d[4] * d[6])
+    }
+
+    /// Returns the transpose of the matrix.
+    pub fn transpose(&self) -> Self {
+        let d = &self.data;
+        Self {
+            data: [d[0], d[3], d[6], d[1], d[4], d[7], d[2], d[5], d[8]],
+        }
+    }
+
+    /// Returns the inverse of the matrix.
+    ///
+    /// # Remarks
+    /// Singular matrices have no inverse, in which case a copy of the matrix is returned.
+    pub fn inverse(&self) -> Self {
+        let mut copy = *self;
+        copy.inplace_inverse();
+        copy
+    }
+
+    /// Inverts the matrix.
+    ///
+    /// # Remarks
+    /// Singular matrices have no inverse, in which case the matrix is left untouched.
+    ///
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the matrix.
+    pub fn inplace_inverse(&mut self) -> &mut Self {
+        let det = self.determinant();
+        if det == num_traits::zero() {
+            return self;
+        }
+
+        let inv = F::one() / det;
+        let d = self.data;
+        self.data = [
+            (d[4] * d[8] - d[5] * d[7]) * inv,
+            (d[2] * d[7] - d[1] * d[8]) * inv,
+            (d[1] * d[5] - d[2] * d[4]) * inv,
+            (d[5] * d[6] - d[3] * d[8]) * inv,
+            (d[0] * d[8] - d[2] * d[6]) * inv,
+            (d[2] * d[3] - d[0] * d[5]) * inv,
+            (d[3] * d[7] - d[4] * d[6]) * inv,
+            (d[1] * d[6] - d[0] * d[7]) * inv,
+            (d[0] * d[4] - d[1] * d[3]) * inv,
+        ];
+        self
+    }
+
+    /// Multiplies the matrix by another one.
+    pub fn matrix_mul(&self, other: &Matrix3<F>) -> Self {
+        let mut copy = *self;
+        copy.inplace_matrix_mul(other);
+        copy
+    }
+
+    /// Multiplies the matrix by another one.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the matrix.
+    pub fn inplace_matrix_mul(&mut self, other: &Matrix3<F>) -> &mut Self {
+        let a = self.data;
+        let b = &other.data;
+        for row in 0..3 {
+            for column in 0..3 {
+                self.data[row * 3 + column] = a[row * 3] * b[column]
+                    + a[row * 3 + 1] * b[column + 3]
+                    + a[row * 3 + 2] * b[column + 6];
+            }
+        }
+        self
+    }
+
+    /// Adds the matrix to another one, component by component.
+    pub fn matrix_add(&self, other: &Matrix3<F>) -> Self {
+        let mut copy = *self;
+        copy.inplace_matrix_add(other);
+        copy
+    }
+
+    /// Adds the matrix to another one, component by component.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the matrix.
+    pub fn inplace_matrix_add(&mut self, other: &Matrix3<F>) -> &mut Self {
+        for (value, other) in self.data.iter_mut().zip(other.data.iter()) {
+            *value = *value + *other;
+        }
+        self
+    }
+
+    /// Scalar multiplication of the matrix.
+    pub fn scalar_mul(&self, scalar: F) -> Self {
+        let mut copy = *self;
+        copy.inplace_scalar_mul(scalar);
+        copy
+    }
+
+    /// Scalar multiplication of the matrix.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the matrix.
+    pub fn inplace_scalar_mul(&mut self, scalar: F) -> &mut Self {
+        for value in self.data.iter_mut() {
+            *value = *value * scalar;
+        }
+        self
+    }
+}
+
+use std::ops::{Mul, MulAssign};
+
+impl<F: num_traits::Float> Mul<Vector3<F>> for Matrix3<F> {
+    type Output = Vector3<F>;
+    fn mul(self, vector: Vector3<F>) -> Vector3<F> {
+        self.transform(&vector)
+    }
+}
+
+impl<F: num_traits::Float> Mul<Matrix3<F>> for Matrix3<F> {
+    type Output = Matrix3<F>;
+    fn mul(self, other: Matrix3<F>) -> Matrix3<F> {
+        self.matrix_mul(&other)
+    }
+}
+
+impl<F: num_traits::Float> MulAssign<Matrix3<F>> for Matrix3<F> {
+    fn mul_assign(&mut self, other: Matrix3<F>) {
+        self.inplace_matrix_mul(&other);
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use super::*;
+
+#[test]
+fn general_usage() {
+    let matrix = Matrix3::<f64>::new([1.0, 2.0, 3.0, 0.0, 1.0, 4.0, 5.0, 6.0, 0.0]);
+    assert_eq!(1.0, matrix.determinant());
+    assert_eq!(
+        Matrix3::new([1.0, 0.0, 5.0, 2.0, 1.0, 6.0, 3.0, 4.0, 0.0]),
+        matrix.transpose()
+    );
+    assert_eq!(
+        Matrix3::new([-24.0, 18.0, 5.0, 20.0, -15.0, -4.0, -5.0, 4.0, 1.0]),
+        matrix.inverse()
+    );
+    assert_eq!(Matrix3::identity(), matrix * matrix.inverse());
+    assert_eq!(
+        Vector3::new(14.0, 14.0, 17.0),
+        matrix * Vector3::new(1.0, 2.0, 3.0)
+    );
+    assert_eq!(
+        Vector3::new(16.0, 22.0, 11.0),
+        matrix.transform_transpose(&Vector3::new(1.0, 2.0, 3.0))
+    );
+    assert_eq!(Vector3::new(2.0, 1.0, 6.0), matrix.column(1));
+    assert_eq!(Vector3::new(0.0, 1.0, 4.0), matrix.row(1));
+
+    // Singular matrices are left untouched.
+    assert_eq!(Matrix3::zero(), Matrix3::<f64>::zero().inverse());
+}
+
+#[test]
+fn skew_symmetric() {
+    let a = Vector3::<f64>::new(1.0, 2.0, 3.0);
+    let b = Vector3::<f64>::new(-2.0, 0.5, 4.0);
+    assert_eq!(a.cross_product(&b), Matrix3::skew_symmetric(&a) * b);
+}
+
+#[test]
+fn inertia_tensor() {
+    assert_eq!(
+        Matrix3::<f64>::diagonal(8.0, 5.0, 5.0),
+        Matrix3::block_inertia_tensor(&Vector3::new(1.0, 2.0, 2.0), 3.0)
+    );
+    assert_eq!(
+        Matrix3::<f64>::new([1.0, -4.0, -5.0, -4.0, 2.0, -6.0, -5.0, -6.0, 3.0]),
+        Matrix3::inertia_tensor(1.0, 2.0, 3.0, 4.0, 5.0, 6.0)
+    );
+}
@@ -0,0 +1,186 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::{Matrix3, Quaternion, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Holds a transform matrix, consisting of a rotation matrix and a position.
+/// The matrix has 12 elements, it is assumed that the remaining four are (0, 0, 0, 1),
+/// producing a homogeneous matrix.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Matrix4<F: num_traits::Float = f64> {
+    /// Holds the transform matrix data in array form, row-major.
+    pub data: [F; 12],
+}
+
+impl<F: num_traits::Float> Matrix4<F> {
+    /// Creates an identity transform.
+    pub fn identity() -> Self {
+        Self::from_orientation_and_position(&Quaternion::identity(), &Vector3::origin())
+    }
+
+    /// Creates a new transform with the given elements, in row-major order.
+    pub fn new(data: [F; 12]) -> Self {
+        Self { data }
+    }
+
+    /// Creates a transform from the given orientation and position.
+    pub fn from_orientation_and_position(
+        orientation: &Quaternion<F>,
+        position: &Vector3<F>,
+    ) -> Self {
+        let r = Matrix3::from_orientation(orientation).data;
+        Self {
+            data: [
+                r[0], r[1], r[2], position.x, r[3], r[4], r[5], position.y, r[6], r[7], r[8],
+                position.z,
+            ],
+        }
+    }
+
+    /// Returns the rotation part of the transform.
+    pub fn rotation(&self) -> Matrix3<F> {
+        let d = &self.data;
+        Matrix3::new([d[0], d[1], d[2], d[4], d[5], d[6], d[8], d[9], d[10]])
+    }
+
+    /// Returns the translation part of the transform.
+    pub fn translation(&self) -> Vector3<F> {
+        self.axis_vector(3)
+    }
+
+    /// Returns a vector representing one axis (one column) of the matrix.
+    /// Column `3` holds the position of the transform.
+    pub fn axis_vector(&self, index: usize) -> Vector3<F> {
+        Vector3::new(self.data[index], self.data[index + 4], self.data[index + 8])
+    }
+
+    /// Transforms the given vector by this matrix.
+    pub fn transform(&self, vector: &Vector3<F>) -> Vector3<F> {
+        let d = &self.data;
+        Vector3::new(
+            vector.x * d[0] + vector.y * d[1] + vector.z * d[2] + d[3],
+            vector.x * d[4] + vector.y * d[5] + vector.z * d[6] + d[7],
+            vector.x * d[8] + vector.y * d[9] + vector.z * d[10] + d[11],
+        )
+    }
+
+    /// Transforms the given vector by the inverse of this matrix.
+    ///
+    /// # Remarks
+    /// This function relies on the fact that the inverse of a pure rotation
+    /// matrix is its transpose, separating the translational and rotational parts.
+    /// It will not work on transforms with scaling or shearing.
+    pub fn transform_inverse(&self, vector: &Vector3<F>) -> Vector3<F> {
+        let d = &self.data;
+        let tmp = Vector3::new(vector.x - d[3], vector.y - d[7], vector.z - d[11]);
+        Vector3::new(
+            tmp.x * d[0] + tmp.y * d[4] + tmp.z * d[8],
+            tmp.x * d[1] + tmp.y * d[5] + tmp.z * d[9],
+            tmp.x * d[2] + tmp.y * d[6] + tmp.z * d[10],
+        )
+    }
+
+    /// Transforms the given direction vector by this matrix.
+    /// Directions are only affected by the rotational part of the transform.
+    pub fn transform_direction(&self, vector: &Vector3<F>) -> Vector3<F> {
+        let d = &self.data;
+        Vector3::new(
+            vector.x * d[0] + vector.y * d[1] + vector.z * d[2],
+            vector.x * d[4] + vector.y * d[5] + vector.z * d[6],
+            vector.x * d[8] + vector.y * d[9] + vector.z * d[10],
+        )
+    }
+
+    /// Transforms the given direction vector by the inverse of this matrix.
+    ///
+    /// # Remarks
+    /// This function relies on the fact that the inverse of a pure rotation
+    /// matrix is its transpose. It will not work on transforms with scaling or shearing.
+    pub fn transform_inverse_direction(&self, vector: &Vector3<F>) -> Vector3<F> {
+        let d = &self.data;
+        Vector3::new(
+            vector.x * d[0] + vector.y * d[4] + vector.z * d[8],
+            vector.x * d[1] + vector.y * d[5] + vector.z * d[9],
+            vector.x * d[2] + vector.y * d[6] + vector.z * d[10],
+        )
+    }
+
+    /// Returns the determinant of the matrix.
+    pub fn determinant(&self) -> F {
+        self.rotation().determinant()
+    }
+
+    /// Returns the inverse of the matrix.
+    ///
+    /// # Remarks
+    /// Singular matrices have no inverse, in which case a copy of the matrix is returned.
+    pub fn inverse(&self) -> Self {
+        if self.determinant() == num_traits::zero() {
+            return *self;
+        }
+
+        let rotation = self.rotation().inverse();
+        let translation = rotation.transform(&self.translation()).invert();
+        let r = rotation.data;
+        Self {
+            data: [
+                r[0],
+                r[1],
+                r[2],
+                translation.x,
+                r[3],
+                r[4],
+                r[5],
+                translation.y,
+                r[6],
+                r[7],
+                r[8],
+                translation.z,
+            ],
+        }
+    }
+
+    /// Multiplies the transform by another one.
+    /// The resulting transform applies `other` first, and then `self`.
+    pub fn matrix_mul(&self, other: &Matrix4<F>) -> Self {
+        let rotation = self.rotation().matrix_mul(&other.rotation());
+        let translation = self.transform(&other.translation());
+        let r = rotation.data;
+        Self {
+            data: [
+                r[0],
+                r[1],
+                r[2],
+                translation.x,
+                r[3],
+                r[4],
+                r[5],
+                translation.y,
+                r[6],
+                r[7],
+                r[8],
+                translation.z,
+            ],
+        }
+    }
+}
+
+use std::ops::Mul;
+
+impl<F: num_traits::Float> Mul<Vector3<F>> for Matrix4<F> {
+    type Output = Vector3<F>;
+    fn mul(self, vector: Vector3<F>) -> Vector3<F> {
+        self.transform(&vector)
+    }
+}
+
+impl<F: num_traits::Float> Mul<Matrix4<F>> for Matrix4<F> {
+    type Output = Matrix4<F>;
+    fn mul(self, other: Matrix4<F>) -> Matrix4<F> {
+        self.matrix_mul(&other)
+    }
+}
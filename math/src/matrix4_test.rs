@@ -0,0 +1,47 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use super::*;
+
+fn assert_vector_eq(expected: Vector3<f64>, actual: Vector3<f64>) {
+    assert!(
+        (expected - actual).magnitude() < 1e-12,
+        "{:?} != {:?}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn general_usage() {
+    let orientation = Quaternion::<f64>::from_axis_angle(
+        &Vector3::new(0.0, 0.0, 1.0),
+        std::f64::consts::FRAC_PI_2,
+    );
+    let transform =
+        Matrix4::from_orientation_and_position(&orientation, &Vector3::new(1.0, 2.0, 3.0));
+    let point = Vector3::new(1.0, 0.0, 0.0);
+
+    assert_vector_eq(Vector3::new(1.0, 3.0, 3.0), transform * point);
+    assert_vector_eq(
+        Vector3::new(0.0, 1.0, 0.0),
+        transform.transform_direction(&point),
+    );
+    assert_vector_eq(
+        point,
+        transform.transform_inverse(&transform.transform(&point)),
+    );
+    assert_vector_eq(
+        point,
+        transform.transform_inverse_direction(&transform.transform_direction(&point)),
+    );
+    assert_vector_eq(point, transform.inverse() * (transform * point));
+    assert_vector_eq(point, (transform.inverse() * transform) * point);
+    assert_vector_eq(Vector3::new(1.0, 2.0, 3.0), transform.translation());
+    assert_vector_eq(Vector3::new(0.0, 1.0, 0.0), transform.axis_vector(0));
+    assert!((1.0 - transform.determinant()).abs() < 1e-12);
+    assert_eq!(Matrix4::identity() * point, point);
+}
@@ -0,0 +1,266 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Holds a three degree of freedom orientation.
+///
+/// # Remarks
+/// Quaternions have several mathematical properties that make them useful
+/// for representing orientations, but require four items of data to hold
+/// the three degrees of freedom. These four items of data can be viewed as
+/// the coefficients of a complex number with three imaginary parts.
+/// The quaternion is only a valid rotation if it is normalized.
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Quaternion<F: num_traits::Float = f64> {
+    /// Real component of the quaternion.
+    pub r: F,
+    /// First complex component of the quaternion.
+    pub i: F,
+    /// Second complex component of the quaternion.
+    pub j: F,
+    /// Third complex component of the quaternion.
+    pub k: F,
+}
+
+impl<F: num_traits::Float> Quaternion<F> {
+    /// Creates a quaternion that represents no rotation.
+    pub fn identity() -> Self {
+        Self {
+            r: num_traits::one(),
+            i: num_traits::zero(),
+            j: num_traits::zero(),
+            k: num_traits::zero(),
+        }
+    }
+
+    /// Creates a new quaternion with the specified components.
+    pub fn new(r: F, i: F, j: F, k: F) -> Self {
+        Self { r, i, j, k }
+    }
+
+    /// Creates a quaternion representing a rotation of `angle` radians around `axis`.
+    pub fn from_axis_angle(axis: &Vector3<F>, angle: F) -> Self {
+        let axis = axis.normalize();
+        let half = angle * crate::real(0.5);
+        let sin = half.sin();
+        Self {
+            r: half.cos(),
+            i: axis.x * sin,
+            j: axis.y * sin,
+            k: axis.z * sin,
+        }
+    }
+
+    /// Creates the quaternion of the orientation described by the given rotation matrix.
+    ///
+    /// # Remarks
+    /// The largest of the four components is found from the diagonal first, and the others
+    /// are derived from it, so the result stays accurate for every rotation.
+    pub fn from_rotation_matrix(matrix: &Matrix3<F>) -> Self {
+        let one: F = num_traits::one();
+        let quarter: F = crate::real(0.25);
+        let m = &matrix.data;
+        let trace = m[0] + m[4] + m[8];
+        let quaternion = if trace > F::zero() {
+            let s = (trace + one).sqrt() * crate::real(2.0);
+            Self::new(
+                quarter * s,
+                (m[7] - m[5]) / s,
+                (m[2] - m[6]) / s,
+                (m[3] - m[1]) / s,
+            )
+        } else if m[0] > m[4] && m[0] > m[8] {
+            let s = (one + m[0] - m[4] - m[8]).sqrt() * crate::real(2.0);
+            Self::new(
+                (m[7] - m[5]) / s,
+                quarter * s,
+                (m[1] + m[3]) / s,
+                (m[2] + m[6]) / s,
+            )
+        } else if m[4] > m[8] {
+            let s = (one + m[4] - m[0] - m[8]).sqrt() * crate::real(2.0);
+            Self::new(
+                (m[2] - m[6]) / s,
+                (m[1] + m[3]) / s,
+                quarter * s,
+                (m[5] + m[7]) / s,
+            )
+        } else {
+            let s = (one + m[8] - m[0] - m[4]).sqrt() * crate::real(2.0);
+            Self::new(
+                (m[3] - m[1]) / s,
+                (m[2] + m[6]) / s,
+                (m[5] + m[7]) / s,
+                quarter * s,
+            )
+        };
+        quaternion.normalize()
+    }
+
+    /// Returns the squared magnitude of the quaternion.
+    pub fn squared_magnitude(&self) -> F {
+        self.r * self.r + self.i * self.i + self.j * self.j + self.k * self.k
+    }
+
+    /// Normalizes the quaternion to unit length, making it a valid orientation quaternion.
+    pub fn normalize(&self) -> Self {
+        let mut copy = *self;
+        copy.inplace_normalize();
+        copy
+    }
+
+    /// Normalizes the quaternion to unit length, making it a valid orientation quaternion.
+    ///
+    /// # Remarks
+    /// A zero length quaternion is turned into the identity quaternion.
+    ///
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the quaternion.
+    pub fn inplace_normalize(&mut self) -> &mut Self {
+        let d = self.squared_magnitude();
+
+        // Check for zero length quaternion, and use the no-rotation quaternion in that case.
+        if d < F::epsilon() {
+            *self = Self::identity();
+            return self;
+        }
+
+        let d = F::one() / d.sqrt();
+        self.r = self.r * d;
+        self.i = self.i * d;
+        self.j = self.j * d;
+        self.k = self.k * d;
+        self
+    }
+
+    /// Returns the conjugate of the quaternion.
+    /// For unit quaternions, the conjugate represents the inverse rotation.
+    pub fn conjugate(&self) -> Self {
+        Self {
+            r: self.r,
+            i: -self.i,
+            j: -self.j,
+            k: -self.k,
+        }
+    }
+
+    /// Multiplies the quaternion by the given quaternion.
+    pub fn quaternion_mul(&self, multiplier: &Quaternion<F>) -> Self {
+        let mut copy = *self;
+        copy.inplace_quaternion_mul(multiplier);
+        copy
+    }
+
+    /// Multiplies the quaternion by the given quaternion.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the quaternion.
+    pub fn inplace_quaternion_mul(&mut self, multiplier: &Quaternion<F>) -> &mut Self {
+        let q = *self;
+        let m = multiplier;
+        self.r = q.r * m.r - q.i * m.i - q.j * m.j - q.k * m.k;
+        self.i = q.r * m.i + q.i * m.r + q.j * m.k - q.k * m.j;
+        self.j = q.r * m.j + q.j * m.r + q.k * m.i - q.i * m.k;
+        self.k = q.r * m.k + q.k * m.r + q.i * m.j - q.j * m.i;
+        self
+    }
+
+    /// Rotates the quaternion by a vector, scaled by the given amount.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the quaternion.
+    pub fn inplace_rotate_by_vector(&mut self, vector: &Vector3<F>, scale: F) -> &mut Self {
+        let q = Self::new(
+            num_traits::zero(),
+            vector.x * scale,
+            vector.y * scale,
+            vector.z * scale,
+        );
+        self.inplace_quaternion_mul(&q)
+    }
+
+    /// Adds the given vector to the quaternion, scaled by the given amount.
+    /// This is used to update the orientation quaternion by a rotation and time.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the quaternion.
+    pub fn inplace_add_scaled_vector(&mut self, vector: &Vector3<F>, scale: F) -> &mut Self {
+        let mut q = Self::new(
+            num_traits::zero(),
+            vector.x * scale,
+            vector.y * scale,
+            vector.z * scale,
+        );
+        q.inplace_quaternion_mul(self);
+        let half: F = crate::real(0.5);
+        self.r = self.r + q.r * half;
+        self.i = self.i + q.i * half;
+        self.j = self.j + q.j * half;
+        self.k = self.k + q.k * half;
+        self
+    }
+
+    /// Returns the rotation the given fraction of the way from this quaternion to the given
+    /// one, turning along the shortest arc at a constant angular speed.
+    ///
+    /// # Remarks
+    /// Both quaternions are expected to be unit quaternions. Nearly equal ones are
+    /// interpolated linearly and normalized instead, to avoid dividing by a vanishing sine.
+    pub fn slerp(&self, other: &Quaternion<F>, t: F) -> Self {
+        // Both signs of a quaternion are the same rotation, turn towards the closest one.
+        let mut cosine = self.r * other.r + self.i * other.i + self.j * other.j + self.k * other.k;
+        let mut other = *other;
+        if cosine < F::zero() {
+            cosine = -cosine;
+            other = Self::new(-other.r, -other.i, -other.j, -other.k);
+        }
+
+        let (from, to) = if cosine > crate::real(0.9995) {
+            (F::one() - t, t)
+        } else {
+            let angle = cosine.acos();
+            let sine = angle.sin();
+            (
+                ((F::one() - t) * angle).sin() / sine,
+                (t * angle).sin() / sine,
+            )
+        };
+        Self::new(
+            self.r * from + other.r * to,
+            self.i * from + other.i * to,
+            self.j * from + other.j * to,
+            self.k * from + other.k * to,
+        )
+        .normalize()
+    }
+
+    /// Rotates the given vector by the orientation represented by the quaternion.
+    pub fn rotate(&self, vector: &Vector3<F>) -> Vector3<F> {
+        let v = Self::new(num_traits::zero(), vector.x, vector.y, vector.z);
+        let rotated = self.quaternion_mul(&v).quaternion_mul(&self.conjugate());
+        Vector3::new(rotated.i, rotated.j, rotated.k)
+    }
+}
+
+use std::ops::{Mul, MulAssign};
+
+impl<F: num_traits::Float> Mul<Quaternion<F>> for Quaternion<F> {
+    type Output = Quaternion<F>;
+    fn mul(self, other: Quaternion<F>) -> Quaternion<F> {
+        self.quaternion_mul(&other)
+    }
+}
+
+impl<F: num_traits::Float> MulAssign<Quaternion<F>> for Quaternion<F> {
+    fn mul_assign(&mut self, other: Quaternion<F>) {
+        self.inplace_quaternion_mul(&other);
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use super::*;
+
+fn assert_vector_eq(expected: Vector3<f64>, actual: Vector3<f64>) {
+    assert!(
+        (expected - actual).magnitude() < 1e-12,
+        "{:?} != {:?}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn general_usage() {
+    let identity = Quaternion::<f64>::identity();
+    assert_eq!(Quaternion::new(1.0, 0.0, 0.0, 0.0), identity);
+    assert_eq!(identity, Quaternion::new(0.0, 0.0, 0.0, 0.0).normalize());
+    assert_eq!(
+        Quaternion::new(0.5, 0.5, 0.5, 0.5),
+        Quaternion::new(2.0, 2.0, 2.0, 2.0).normalize()
+    );
+    assert_eq!(
+        Quaternion::new(1.0, -2.0, -3.0, -4.0),
+        Quaternion::new(1.0, 2.0, 3.0, 4.0).conjugate()
+    );
+    assert_eq!(
+        Quaternion::new(1.0, 2.0, 3.0, 4.0),
+        identity * Quaternion::new(1.0, 2.0, 3.0, 4.0)
+    );
+}
+
+#[test]
+fn rotation() {
+    let quarter =
+        Quaternion::from_axis_angle(&Vector3::new(0.0, 0.0, 2.0), std::f64::consts::FRAC_PI_2);
+    assert_vector_eq(
+        Vector3::new(0.0, 1.0, 0.0),
+        quarter.rotate(&Vector3::new(1.0, 0.0, 0.0)),
+    );
+    assert_vector_eq(
+        Vector3::new(-1.0, 0.0, 0.0),
+        (quarter * quarter).rotate(&Vector3::new(1.0, 0.0, 0.0)),
+    );
+    assert_vector_eq(
+        Matrix3::from_orientation(&quarter).transform(&Vector3::new(1.0, 2.0, 3.0)),
+        quarter.rotate(&Vector3::new(1.0, 2.0, 3.0)),
+    );
+
+    // Rotation matrices convert back to the same rotation, whichever component is largest.
+    for (axis, angle) in [
+        (Vector3::new(0.0, 0.0, 2.0), std::f64::consts::FRAC_PI_2),
+        (Vector3::new(1.0, 0.0, 0.0), 3.0),
+        (Vector3::new(0.0, 1.0, 0.0), 3.0),
+        (Vector3::new(0.0, 0.0, 1.0), 3.0),
+        (Vector3::new(1.0, -2.0, 0.5), 1.0),
+    ]
+    .iter()
+    {
+        let expected = Quaternion::from_axis_angle(axis, *angle);
+        let actual = Quaternion::from_rotation_matrix(&Matrix3::from_orientation(&expected));
+        let point = Vector3::new(1.0, 2.0, 3.0);
+        assert_vector_eq(expected.rotate(&point), actual.rotate(&point));
+    }
+
+    // Integrating an angular velocity of PI/2 rad/s for a second in small steps.
+    let mut orientation = Quaternion::identity();
+    for _ in 0..1000 {
+        orientation
+            .inplace_add_scaled_vector(&Vector3::new(0.0, 0.0, std::f64::consts::FRAC_PI_2), 0.001)
+            .inplace_normalize();
+    }
+    assert!(
+        (orientation.rotate(&Vector3::new(1.0, 0.0, 0.0)) - Vector3::new(0.0, 1.0, 0.0))
+            .magnitude()
+            < 1e-3
+    );
+}
+
+#[test]
+fn slerp() {
+    let axis = Vector3::new(0.0, 0.0, 1.0);
+    let identity = Quaternion::<f64>::identity();
+    let half_turn = Quaternion::from_axis_angle(&axis, std::f64::consts::PI);
+    let quarter = identity.slerp(&half_turn, 0.5);
+    assert_vector_eq(
+        Vector3::new(0.0, 1.0, 0.0),
+        quarter.rotate(&Vector3::new(1.0, 0.0, 0.0)),
+    );
+    assert_eq!(identity, identity.slerp(&half_turn, 0.0));
+
+    // The opposite sign of the target turns the same way.
+    let target = Quaternion::from_axis_angle(&axis, std::f64::consts::FRAC_PI_2);
+    let negated = Quaternion::new(-target.r, -target.i, -target.j, -target.k);
+    let eighth = Quaternion::from_axis_angle(&axis, std::f64::consts::FRAC_PI_4);
+    for to in [target, negated].iter() {
+        assert_vector_eq(
+            eighth.rotate(&Vector3::new(1.0, 0.0, 0.0)),
+            identity.slerp(to, 0.5).rotate(&Vector3::new(1.0, 0.0, 0.0)),
+        );
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use crate::real;
+
+/// High part of the natural logarithm of 2, with its last bits zeroed so multiplying it by
+/// small integers is exact.
+const LN_2_HI: f64 = 0.693_147_180_369_123_8;
+
+/// Remaining part of the natural logarithm of 2.
+const LN_2_LO: f64 = 1.908_214_929_270_587_7e-10;
+
+/// High part of a half of pi, with its last bits zeroed so multiplying it by small integers
+/// is exact.
+const FRAC_PI_2_HI: f64 = 1.570_796_326_734_125_6;
+
+/// Remaining part of a half of pi.
+const FRAC_PI_2_LO: f64 = 6.077_100_506_506_192e-11;
+
+/// Number of terms of the series used by the functions.
+const SERIES_TERMS: usize = 24;
+
+/// Returns `2^exponent`, computed by halving or doubling one.
+fn exp2i<F: num_traits::Float>(exponent: i64) -> F {
+    let two = real::<F>(2.0);
+    let mut result = F::one();
+    if exponent >= 0 {
+        for _ in 0..exponent {
+            result = result * two;
+        }
+    } else {
+        for _ in exponent..0 {
+            result = result / two;
+        }
+    }
+    result
+}
+
+/// Returns `e^x`.
+pub fn exp<F: num_traits::Float>(x: F) -> F {
+    if x.is_nan() {
+        return x;
+    }
+    if x > real(710.0) {
+        return F::infinity();
+    }
+    if x < real(-746.0) {
+        return F::zero();
+    }
+
+    // Split x into k ln(2) + r, with r within half a ln(2) of zero.
+    let k = (x / real(std::f64::consts::LN_2)).round();
+    let r = x - k * real(LN_2_HI) - k * real(LN_2_LO);
+
+    let mut term = F::one();
+    let mut sum = F::one();
+    for n in 1..SERIES_TERMS {
+        term = term * r / real(n as f64);
+        sum = sum + term;
+    }
+
+    // Scale in two halves, so the intermediate results don't overflow before the end.
+    let k = k.to_i64().unwrap_or(0);
+    sum * exp2i::<F>(k / 2) * exp2i::<F>(k - k / 2)
+}
+
+/// Returns the natural logarithm of `x`.
+pub fn ln<F: num_traits::Float>(x: F) -> F {
+    if x.is_nan() || x < F::zero() {
+        return F::nan();
+    }
+    if x == F::zero() {
+        return F::neg_infinity();
+    }
+    if x.is_infinite() {
+        return x;
+    }
+
+    // Split x into 2^e m, with m between a half and twice the square root of 2.
+    let two = real::<F>(2.0);
+    let mut m = x;
+    let mut e = 0i64;
+    while m >= two {
+        m = m / two;
+        e += 1;
+    }
+    while m < F::one() {
+        m = m * two;
+        e -= 1;
+    }
+    if m > real(std::f64::consts::SQRT_2) {
+        m = m / two;
+        e += 1;
+    }
+
+    // ln(m) = 2 atanh(s), for s = (m - 1) / (m + 1).
+    let s = (m - F::one()) / (m + F::one());
+    let squared = s * s;
+    let mut power = s;
+    let mut sum = F::zero();
+    for n in 0..SERIES_TERMS {
+        sum = sum + power / real((2 * n + 1) as f64);
+        power = power * squared;
+    }
+
+    let e = real::<F>(e as f64);
+    e * real(LN_2_HI) + (two * sum + e * real(LN_2_LO))
+}
+
+/// Returns `base^exponent`.
+pub fn powf<F: num_traits::Float>(base: F, exponent: F) -> F {
+    if exponent == F::zero() || base == F::one() {
+        return F::one();
+    }
+    if base.is_nan() || exponent.is_nan() {
+        return F::nan();
+    }
+    if base == F::zero() {
+        return if exponent > F::zero() {
+            F::zero()
+        } else {
+            F::infinity()
+        };
+    }
+    if base < F::zero() {
+        if exponent.fract() != F::zero() {
+            return F::nan();
+        }
+        let magnitude = powf(-base, exponent);
+        let odd = (exponent / real(2.0)).fract() != F::zero();
+        return if odd { -magnitude } else { magnitude };
+    }
+    exp(exponent * ln(base))
+}
+
+/// Returns the sine and cosine of `x`, in radians.
+pub fn sin_cos<F: num_traits::Float>(x: F) -> (F, F) {
+    if !x.is_finite() {
+        return (F::nan(), F::nan());
+    }
+
+    // Split x into k pi / 2 + r, with r within a quarter of pi of zero.
+    let k = (x / real(std::f64::consts::FRAC_PI_2)).round();
+    let r = x - k * real(FRAC_PI_2_HI) - k * real(FRAC_PI_2_LO);
+
+    let squared = r * r;
+    let mut sine_term = r;
+    let mut cosine_term = F::one();
+    let mut sine = r;
+    let mut cosine = F::one();
+    for n in 1..SERIES_TERMS / 2 {
+        let n = real::<F>(n as f64);
+        let two = real::<F>(2.0);
+        sine_term = -sine_term * squared / (two * n * (two * n + F::one()));
+        cosine_term = -cosine_term * squared / (two * n * (two * n - F::one()));
+        sine = sine + sine_term;
+        cosine = cosine + cosine_term;
+    }
+
+    let quadrant = (k % real(4.0) + real(4.0)) % real(4.0);
+    match quadrant.to_u8().unwrap_or(0) {
+        0 => (sine, cosine),
+        1 => (cosine, -sine),
+        2 => (-sine, -cosine),
+        _ => (-cosine, sine),
+    }
+}
+
+/// Returns the sine of `x`, in radians.
+pub fn sin<F: num_traits::Float>(x: F) -> F {
+    sin_cos(x).0
+}
+
+/// Returns the cosine of `x`, in radians.
+pub fn cos<F: num_traits::Float>(x: F) -> F {
+    sin_cos(x).1
+}
+
+/// Returns the arc tangent of `x`, in radians within `[-PI / 2, PI / 2]`.
+pub fn atan<F: num_traits::Float>(x: F) -> F {
+    if x.is_nan() {
+        return x;
+    }
+    if x < F::zero() {
+        return -atan(-x);
+    }
+    if x > F::one() {
+        return real::<F>(std::f64::consts::FRAC_PI_2) - atan(F::one() / x);
+    }
+
+    // Bring x below the tangent of a twelfth of pi, using atan(x) = pi / 6 + atan(t).
+    let mut offset = F::zero();
+    let mut t = x;
+    if t > real(0.267_949_192_431_122_7) {
+        let root = real::<F>(3.0).sqrt();
+        t = (t * root - F::one()) / (t + root);
+        offset = real(std::f64::consts::FRAC_PI_6);
+    }
+
+    let squared = t * t;
+    let mut power = t;
+    let mut sum = F::zero();
+    for n in 0..SERIES_TERMS {
+        let term = power / real((2 * n + 1) as f64);
+        sum = if n % 2 == 0 { sum + term } else { sum - term };
+        power = power * squared;
+    }
+    offset + sum
+}
+
+/// Returns the angle, in radians within `[-PI, PI]`, of the point `(x, y)` around the origin.
+pub fn atan2<F: num_traits::Float>(y: F, x: F) -> F {
+    if x.is_nan() || y.is_nan() {
+        return F::nan();
+    }
+
+    let pi = real::<F>(std::f64::consts::PI);
+    if x == F::zero() {
+        return if y > F::zero() {
+            pi / real(2.0)
+        } else if y < F::zero() {
+            -pi / real(2.0)
+        } else if x.is_sign_negative() {
+            if y.is_sign_negative() {
+                -pi
+            } else {
+                pi
+            }
+        } else {
+            y
+        };
+    }
+
+    let angle = atan(y / x);
+    if x > F::zero() {
+        angle
+    } else if y.is_sign_negative() {
+        angle - pi
+    } else {
+        angle + pi
+    }
+}
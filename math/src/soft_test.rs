@@ -0,0 +1,71 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use super::soft;
+
+fn assert_close(expected: f64, actual: f64) {
+    let tolerance = 1e-14 * expected.abs().max(1.0);
+    assert!(
+        (expected - actual).abs() <= tolerance,
+        "{} != {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn exponentials() {
+    for x in [
+        -700.0f64, -20.5, -1.0, -1e-9, 0.0, 0.3, 1.0, 2.5, 50.0, 700.0,
+    ] {
+        assert_close(x.exp(), soft::exp(x));
+    }
+    assert_eq!(1.0, soft::exp(0.0));
+    assert_eq!(f64::INFINITY, soft::exp(1000.0));
+    assert_eq!(0.0, soft::exp(-1000.0));
+
+    for x in [
+        1e-300f64, 1e-5, 0.5, 0.99, 1.0, 1.5, 2.0, 10.0, 12345.678, 1e300,
+    ] {
+        assert_close(x.ln(), soft::ln(x));
+    }
+    assert_eq!(0.0, soft::ln(1.0));
+    assert_eq!(f64::NEG_INFINITY, soft::ln(0.0));
+    assert!(soft::ln(-1.0f64).is_nan());
+
+    for (base, exponent) in [
+        (0.99f64, 0.016),
+        (0.5, 3.0),
+        (2.0, -10.0),
+        (10.0, 0.5),
+        (7.0, 1.0),
+    ] {
+        assert_close(base.powf(exponent), soft::powf(base, exponent));
+    }
+    assert_eq!(1.0, soft::powf(1.0, 0.016));
+    assert_close(-8.0, soft::powf(-2.0, 3.0));
+    assert!(soft::powf(-2.0f64, 0.5).is_nan());
+    assert_eq!(0.0, soft::powf(0.0, 2.0));
+}
+
+#[test]
+fn trigonometry() {
+    for x in [
+        -100.0f64, -3.0, -1.0, 0.0, 0.5, 0.785, 1.5, 3.1, 4.0, 6.0, 1000.0,
+    ] {
+        assert_close(x.sin(), soft::sin(x));
+        assert_close(x.cos(), soft::cos(x));
+    }
+    assert!(soft::sin(f64::INFINITY).is_nan());
+
+    for y in [-3.0, -1.0, -0.2, 0.0, 0.2, 1.0, 3.0] {
+        for x in [-3.0, -1.0, -0.2, 0.0, 0.2, 1.0, 3.0] {
+            assert_close(f64::atan2(y, x), soft::atan2(y, x));
+        }
+    }
+    assert_close(f64::atan2(0.0, -1.0), soft::atan2(0.0, -1.0));
+    assert_close(f64::atan2(-0.0, -1.0), soft::atan2(-0.0, -1.0));
+}
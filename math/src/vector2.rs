@@ -0,0 +1,166 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+/// Vector in 2 dimensions.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct Vector2<F: num_traits::Float = f64> {
+    pub x: F,
+    pub y: F,
+}
+
+impl<F: num_traits::Float> Vector2<F> {
+    /// Creates a vector with all its coordinates at origin (0, 0).
+    pub fn origin() -> Self {
+        Self {
+            x: num_traits::zero(),
+            y: num_traits::zero(),
+        }
+    }
+
+    /// Creates a new vector with the specified coordinates.
+    pub fn new(x: F, y: F) -> Self {
+        Self { x, y }
+    }
+
+    /// Creates a unit vector pointing at the given angle (in radians) from the x axis.
+    pub fn from_angle(angle: F) -> Self {
+        Self::new(angle.cos(), angle.sin())
+    }
+
+    /// Returns the magnitude of the vector.
+    /// Magnitude represents the length of the vector.
+    pub fn magnitude(&self) -> F {
+        self.squared_magnitude().sqrt()
+    }
+
+    /// Returns the squared magnitude of the vector.
+    pub fn squared_magnitude(&self) -> F {
+        self.x * self.x + self.y * self.y
+    }
+
+    /// Flips the sign of all the coordinates of the vector.
+    pub fn invert(&self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+
+    /// Transforms a non-zero vector into a vector of unit length.
+    pub fn normalize(&self) -> Self {
+        let magnitude = self.magnitude();
+        if magnitude > F::zero() {
+            self.scalar_div(magnitude)
+        } else {
+            *self
+        }
+    }
+
+    /// Multiplies every coordinate of the vector by a scalar.
+    pub fn scalar_mul(&self, scalar: F) -> Self {
+        Self::new(self.x * scalar, self.y * scalar)
+    }
+
+    /// Divides every coordinate of the vector by a scalar.
+    pub fn scalar_div(&self, scalar: F) -> Self {
+        Self::new(self.x / scalar, self.y / scalar)
+    }
+
+    /// Adds another vector to this one.
+    pub fn vector_add(&self, other: &Vector2<F>) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+
+    /// Adds another vector to this one.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the vector.
+    pub fn inplace_vector_add(&mut self, other: &Vector2<F>) -> &mut Self {
+        *self = self.vector_add(other);
+        self
+    }
+
+    /// Subtracts another vector from this one.
+    pub fn vector_sub(&self, other: &Vector2<F>) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+
+    /// Subtracts another vector from this one.
+    ///
+    /// # Remarks
+    /// This function follows the Builder pattern, so it can be chained to other
+    /// methods that modify the vector.
+    pub fn inplace_vector_sub(&mut self, other: &Vector2<F>) -> &mut Self {
+        *self = self.vector_sub(other);
+        self
+    }
+
+    /// Returns the dot product of this vector and another one.
+    pub fn dot_product(&self, other: &Vector2<F>) -> F {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// Returns the cross product of this vector and another one, the `z` coordinate
+    /// of the cross product of both vectors lying on the `xy` plane.
+    pub fn cross_product(&self, other: &Vector2<F>) -> F {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Returns the vector rotated a quarter turn counter-clockwise, the cross product
+    /// of a unit vector along `z` and this one.
+    pub fn perpendicular(&self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    /// Returns the cross product of a vector along `z` with the given length and this one,
+    /// like the velocity of a point turning around the origin at the given angular velocity.
+    pub fn scalar_cross(&self, scalar: F) -> Self {
+        self.perpendicular().scalar_mul(scalar)
+    }
+
+    /// Returns the vector rotated counter-clockwise by the given angle (in radians).
+    pub fn rotate(&self, angle: F) -> Self {
+        let (sine, cosine) = angle.sin_cos();
+        Self::new(
+            self.x * cosine - self.y * sine,
+            self.x * sine + self.y * cosine,
+        )
+    }
+}
+
+impl<F: num_traits::Float> Add for Vector2<F> {
+    type Output = Vector2<F>;
+    fn add(self, other: Vector2<F>) -> Vector2<F> {
+        self.vector_add(&other)
+    }
+}
+
+impl<F: num_traits::Float> AddAssign for Vector2<F> {
+    fn add_assign(&mut self, other: Vector2<F>) {
+        self.inplace_vector_add(&other);
+    }
+}
+
+impl<F: num_traits::Float> Sub for Vector2<F> {
+    type Output = Vector2<F>;
+    fn sub(self, other: Vector2<F>) -> Vector2<F> {
+        self.vector_sub(&other)
+    }
+}
+
+impl<F: num_traits::Float> SubAssign for Vector2<F> {
+    fn sub_assign(&mut self, other: Vector2<F>) {
+        self.inplace_vector_sub(&other);
+    }
+}
+
+impl<F: num_traits::Float> Neg for Vector2<F> {
+    type Output = Vector2<F>;
+    fn neg(self) -> Vector2<F> {
+        self.invert()
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright (c) 2020-2021 Rafael Alcaraz Mercado. All rights reserved.
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>.
+// All files in the project carrying such notice may not be copied, modified, or distributed
+// except according to those terms.
+// THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
+
+use super::*;
+
+#[test]
+fn general_usage() {
+    assert_eq!(Vector2 { x: 0.0, y: 0.0 }, Vector2::origin());
+    let one = Vector2::<f64>::new(3.0, 4.0);
+    let two = Vector2::new(-1.0, 2.0);
+    assert_eq!(5.0, one.magnitude());
+    assert_eq!(25.0, one.squared_magnitude());
+    assert_eq!(Vector2::new(0.6, 0.8), one.normalize());
+    assert_eq!(Vector2::origin(), Vector2::<f64>::origin().normalize());
+    assert_eq!(Vector2::new(-3.0, -4.0), -one);
+    assert_eq!(Vector2::new(2.0, 6.0), one + two);
+    assert_eq!(Vector2::new(4.0, 2.0), one - two);
+    assert_eq!(Vector2::new(6.0, 8.0), one.scalar_mul(2.0));
+    assert_eq!(5.0, one.dot_product(&two));
+    assert_eq!(10.0, one.cross_product(&two));
+    assert_eq!(Vector2::new(-4.0, 3.0), one.perpendicular());
+    assert_eq!(Vector2::new(-8.0, 6.0), one.scalar_cross(2.0));
+
+    // Rotations turn counter-clockwise.
+    let rotated = Vector2::new(1.0, 0.0).rotate(std::f64::consts::FRAC_PI_2);
+    assert!((rotated - Vector2::new(0.0, 1.0)).magnitude() < 1e-12);
+    let direction = Vector2::from_angle(std::f64::consts::PI);
+    assert!((direction - Vector2::new(-1.0, 0.0)).magnitude() < 1e-12);
+}
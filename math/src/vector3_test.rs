@@ -4,6 +4,8 @@
 // except according to those terms.
 // THE SOURCE CODE IS AVAILABLE UNDER THE ABOVE CHOSEN LICENSE "AS IS", WITH NO WARRANTIES.
 
+#![allow(clippy::op_ref)]
+
 use super::*;
 
 #[test]